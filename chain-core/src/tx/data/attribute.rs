@@ -9,6 +9,17 @@ use std::prelude::v1::Vec;
 
 use crate::tx::data::access::TxAccessPolicy;
 
+/// Maximum length (in bytes) of memo in transaction attributes
+pub const MAX_TX_MEMO_LENGTH: usize = 128;
+
+/// Minimum app version of transaction attributes carrying a memo
+///
+/// Memo changes encoding (and so id) of transactions carrying it, so it is gated by app version:
+/// nodes refuse transactions with app version newer than theirs (`crate::APP_VERSION`), so memo
+/// transactions are only accepted after the network is upgraded to this app version. Encoding of
+/// attributes without memo is unchanged.
+pub const MEMO_APP_VERSION: u64 = 1;
+
 /// Tx extra metadata, e.g. network ID
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
 #[cfg_attr(not(feature = "mesalock_sgx"), derive(Serialize, Deserialize))]
@@ -27,20 +38,35 @@ pub struct TxAttributes {
     pub allowed_view: Vec<TxAccessPolicy>,
     /// the global chain-core app version that the transaction was generated with
     pub app_version: u64,
+    /// optional memo (e.g. a deposit tag required by the receiver), only encoded with app version
+    /// at least `MEMO_APP_VERSION`
+    #[cfg_attr(
+        not(feature = "mesalock_sgx"),
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub memo: Option<Vec<u8>>,
 }
 
 impl Encode for TxAttributes {
     fn encode_to<EncOut: Output>(&self, dest: &mut EncOut) {
-        dest.push_byte(0);
+        // attributes without memo keep the original encoding
+        match self.memo {
+            None => dest.push_byte(0),
+            Some(_) => dest.push_byte(1),
+        }
         dest.push_byte(self.chain_hex_id);
         dest.push(&self.allowed_view);
         dest.push(&self.app_version);
+        if let Some(ref memo) = self.memo {
+            dest.push(memo);
+        }
     }
 
     fn size_hint(&self) -> usize {
         self.chain_hex_id.size_hint()
             + self.allowed_view.size_hint()
             + self.app_version.size_hint()
+            + self.memo.as_ref().map_or(0, |memo| memo.size_hint())
             + 1
     }
 }
@@ -48,16 +74,31 @@ impl Encode for TxAttributes {
 impl Decode for TxAttributes {
     fn decode<DecIn: Input>(input: &mut DecIn) -> Result<Self, Error> {
         let tag = input.read_byte()?;
-        if tag != 0 {
+        if tag > 1 {
             return Err(Error::from("Unsupported TxAttributes variant"));
         }
         let chain_hex_id = input.read_byte()?;
         let allowed_view: Vec<TxAccessPolicy> = Vec::decode(input)?;
         let app_version = u64::decode(input)?;
+        let memo = if tag == 1 {
+            if app_version < MEMO_APP_VERSION {
+                return Err(Error::from(
+                    "TxAttributes memo is not supported by their app version",
+                ));
+            }
+            let memo: Vec<u8> = Vec::decode(input)?;
+            if memo.len() > MAX_TX_MEMO_LENGTH {
+                return Err(Error::from("TxAttributes memo is too long"));
+            }
+            Some(memo)
+        } else {
+            None
+        };
         Ok(TxAttributes {
             chain_hex_id,
             allowed_view,
             app_version,
+            memo,
         })
     }
 }
@@ -116,6 +157,7 @@ impl TxAttributes {
             chain_hex_id,
             allowed_view: Vec::new(),
             app_version: crate::APP_VERSION,
+            memo: None,
         }
     }
 
@@ -125,6 +167,62 @@ impl TxAttributes {
             chain_hex_id,
             allowed_view,
             app_version: crate::APP_VERSION,
+            memo: None,
         }
     }
+
+    /// sets memo of tx attributes (raising their app version to `MEMO_APP_VERSION` if it is
+    /// older, so that the transaction is refused by nodes not supporting memo)
+    pub fn with_memo(mut self, memo: Vec<u8>) -> Self {
+        self.memo = Some(memo);
+        self.app_version = self.app_version.max(MEMO_APP_VERSION);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_decode_without_memo() {
+        // encoding of attributes before memo was added: tag, chain hex id, allowed view and app
+        // version
+        let mut encoded = vec![0, 0xab];
+        encoded.extend(Vec::<TxAccessPolicy>::new().encode());
+        encoded.extend(0u64.encode());
+
+        let attributes = TxAttributes::decode(&mut encoded.as_slice()).unwrap();
+        assert_eq!(TxAttributes::new(0xab), attributes);
+        assert_eq!(None, attributes.memo);
+        // so ids of transactions without memo are unchanged
+        assert_eq!(encoded, attributes.encode());
+    }
+
+    #[test]
+    fn check_memo_encode_decode() {
+        let attributes = TxAttributes::new(0xab).with_memo(b"deposit tag 42".to_vec());
+        assert_eq!(MEMO_APP_VERSION, attributes.app_version);
+        let encoded = attributes.encode();
+
+        let mut expected = vec![1, 0xab];
+        expected.extend(Vec::<TxAccessPolicy>::new().encode());
+        expected.extend(MEMO_APP_VERSION.encode());
+        expected.extend(b"deposit tag 42".to_vec().encode());
+        assert_eq!(expected, encoded);
+        assert_eq!(
+            attributes,
+            TxAttributes::decode(&mut encoded.as_slice()).unwrap()
+        );
+
+        // memo is refused in attributes of app version not supporting it
+        let attributes = TxAttributes {
+            app_version: MEMO_APP_VERSION - 1,
+            ..attributes
+        };
+        assert!(TxAttributes::decode(&mut attributes.encode().as_slice()).is_err());
+
+        let attributes = TxAttributes::new(0xab).with_memo(vec![b'a'; MAX_TX_MEMO_LENGTH + 1]);
+        assert!(TxAttributes::decode(&mut attributes.encode().as_slice()).is_err());
+    }
 }
@@ -28,6 +28,7 @@ use client_core::wallet::syncer::{ObfuscationSyncerConfig, ProgressReport, Walle
 use client_core::wallet::{DefaultWalletClient, WalletClient};
 use client_core::TransactionObfuscation;
 use client_network::network_ops::{DefaultNetworkOpsClient, NetworkOpsClient};
+use client_network::WatchList;
 
 use self::address_command::AddressCommand;
 use self::multisig_command::MultiSigCommand;
@@ -227,24 +228,32 @@ impl Command {
     pub fn execute(&self) -> Result<()> {
         match self {
             Command::Wallet { wallet_command } => {
-                let storage = SledStorage::new(storage_path())?;
-                let wallet_client = DefaultWalletClient::new_read_only(storage);
+                let storage = if wallet_command.is_read_only() {
+                    SledStorage::read_only(storage_path())?
+                } else {
+                    SledStorage::new(storage_path())?
+                };
+                let wallet_client = DefaultWalletClient::open_read_only(storage)?;
                 wallet_command.execute(wallet_client)
             }
             Command::Address { address_command } => {
-                let storage = SledStorage::new(storage_path())?;
-                let wallet_client = DefaultWalletClient::new_read_only(storage);
+                let storage = if address_command.is_read_only() {
+                    SledStorage::read_only(storage_path())?
+                } else {
+                    SledStorage::new(storage_path())?
+                };
+                let wallet_client = DefaultWalletClient::open_read_only(storage)?;
                 address_command.execute(wallet_client)
             }
             Command::ViewKey { name, private } => {
-                let storage = SledStorage::new(storage_path())?;
-                let wallet_client = DefaultWalletClient::new_read_only(storage);
+                let storage = SledStorage::read_only(storage_path())?;
+                let wallet_client = DefaultWalletClient::open_read_only(storage)?;
 
                 Self::get_view_key(wallet_client, name, *private)
             }
             Command::Balance { name } => {
-                let storage = SledStorage::new(storage_path())?;
-                let wallet_client = DefaultWalletClient::new_read_only(storage);
+                let storage = SledStorage::read_only(storage_path())?;
+                let wallet_client = DefaultWalletClient::open_read_only(storage)?;
                 Self::get_balance(wallet_client, name)
             }
             Command::History {
@@ -253,8 +262,8 @@ impl Command {
                 limit,
                 reversed,
             } => {
-                let storage = SledStorage::new(storage_path())?;
-                let wallet_client = DefaultWalletClient::new_read_only(storage);
+                let storage = SledStorage::read_only(storage_path())?;
+                let wallet_client = DefaultWalletClient::open_read_only(storage)?;
                 Self::get_history(wallet_client, name, *offset, *limit, *reversed)
             }
             Command::Transaction {
@@ -280,20 +289,22 @@ impl Command {
                     transaction_obfuscation.clone(),
                 );
 
-                let wallet_client = DefaultWalletClient::new(
+                let watch_list = WatchList::new(storage.clone());
+                let wallet_client = DefaultWalletClient::open(
                     storage,
                     tendermint_client.clone(),
                     transaction_builder,
                     None,
                     hw_key_service,
-                );
+                )?;
                 let network_ops_client = DefaultNetworkOpsClient::new(
                     wallet_client,
                     signer_manager,
                     tendermint_client,
                     fee_algorithm,
                     transaction_obfuscation,
-                );
+                )
+                .with_watch_list(watch_list);
                 transaction_command
                     .execute(network_ops_client.get_wallet_client(), &network_ops_client)
             }
@@ -316,13 +327,13 @@ impl Command {
                     fee_algorithm,
                     transaction_obfuscation.clone(),
                 );
-                let wallet_client = DefaultWalletClient::new(
+                let wallet_client = DefaultWalletClient::open(
                     storage,
                     tendermint_client.clone(),
                     transaction_builder,
                     None,
                     hw_key_service,
-                );
+                )?;
 
                 let network_ops_client = DefaultNetworkOpsClient::new(
                     wallet_client,
@@ -355,7 +366,7 @@ impl Command {
             }
             Command::MultiSig { multisig_command } => {
                 let storage = SledStorage::new(storage_path())?;
-                let wallet_client = DefaultWalletClient::new_read_only(storage);
+                let wallet_client = DefaultWalletClient::open_read_only(storage)?;
                 multisig_command.execute(wallet_client)
             }
         }
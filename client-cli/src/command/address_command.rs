@@ -100,6 +100,14 @@ pub enum AddressCommand {
 }
 
 impl AddressCommand {
+    /// Returns `true` if command never writes to storage
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            AddressCommand::New { .. } => false,
+            AddressCommand::List { .. } | AddressCommand::ListPubKey { .. } => true,
+        }
+    }
+
     pub fn execute<T: WalletClient>(&self, wallet_client: T) -> Result<()> {
         match self {
             AddressCommand::New { name, address_type } => {
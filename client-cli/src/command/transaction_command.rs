@@ -8,9 +8,7 @@ use std::str::FromStr;
 use chain_core::common::{Timespec, HASH_SIZE_256};
 use chain_core::init::coin::Coin;
 use chain_core::init::network::get_network_id;
-use chain_core::state::account::{
-    ConfidentialInit, CouncilNode, StakedStateAddress, StakedStateOpAttributes,
-};
+use chain_core::state::account::{ConfidentialInit, CouncilNode, StakedStateAddress};
 use chain_core::state::tendermint::TendermintValidatorPubKey;
 use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
@@ -20,9 +18,10 @@ use chain_core::tx::data::output::TxOut;
 use chain_core::tx::TxAux;
 use client_common::{Error, ErrorKind, PublicKey, Result, ResultExt, SecKey, Transaction};
 use client_core::transaction_builder::SignedTransferTransaction;
-use client_core::types::{BalanceChange, TransactionPending};
+use client_core::types::{BalanceChange, TransactionPending, DEFAULT_PENDING_TX_VALID_BLOCKS};
 use client_core::WalletClient;
-use client_network::NetworkOpsClient;
+use client_network::types::NonceSource;
+use client_network::{validate_council_node, NetworkOpsClient};
 
 use chrono::{DateTime, Local, NaiveDateTime, Utc};
 use cli_table::format::{CellFormat, Color, Justify};
@@ -549,8 +548,9 @@ fn new_transaction<T: WalletClient, N: NetworkOpsClient>(
             }
         }
         TransactionType::Unbond => {
-            let tx_aux = new_unbond_transaction(network_ops_client, name, &enckey)?;
+            let (tx_aux, tx_pending) = new_unbond_transaction(network_ops_client, name, &enckey)?;
             wallet_client.broadcast_transaction(&tx_aux)?;
+            wallet_client.update_tx_pending_state(&name, &enckey, tx_aux.tx_id(), tx_pending)?;
         }
         TransactionType::Withdraw => {
             let (tx_aux, tx_pending) =
@@ -559,12 +559,15 @@ fn new_transaction<T: WalletClient, N: NetworkOpsClient>(
             wallet_client.update_tx_pending_state(&name, &enckey, tx_aux.tx_id(), tx_pending)?;
         }
         TransactionType::Unjail => {
-            let tx_aux = new_unjail_transaction(network_ops_client, name, &enckey)?;
+            let (tx_aux, tx_pending) = new_unjail_transaction(network_ops_client, name, &enckey)?;
             wallet_client.broadcast_transaction(&tx_aux)?;
+            wallet_client.update_tx_pending_state(&name, &enckey, tx_aux.tx_id(), tx_pending)?;
         }
         TransactionType::NodeJoin => {
-            let tx_aux = new_node_join_transaction(network_ops_client, name, &enckey)?;
+            let (tx_aux, tx_pending) =
+                new_node_join_transaction(network_ops_client, name, &enckey)?;
             wallet_client.broadcast_transaction(&tx_aux)?;
+            wallet_client.update_tx_pending_state(&name, &enckey, tx_aux.tx_id(), tx_pending)?;
         }
     };
 
@@ -602,6 +605,8 @@ fn new_withdraw_transaction<T: WalletClient, N: NetworkOpsClient>(
         &from_address,
         to_address,
         attributes,
+        None,
+        None,
     )
 }
 
@@ -609,11 +614,17 @@ fn new_unbond_transaction<N: NetworkOpsClient>(
     network_ops_client: &N,
     name: &str,
     enckey: &SecKey,
-) -> Result<TxAux> {
-    let attributes = StakedStateOpAttributes::new(get_network_id());
+) -> Result<(TxAux, TransactionPending)> {
     let address = ask_staking_address()?;
     let value = ask_cro()?;
-    network_ops_client.create_unbond_stake_transaction(name, enckey, address, value, attributes)
+    network_ops_client.create_unbond_stake_transaction(
+        name,
+        enckey,
+        address,
+        value,
+        None,
+        NonceSource::Network,
+    )
 }
 
 fn new_deposit_transaction<T: WalletClient, N: NetworkOpsClient>(
@@ -622,15 +633,24 @@ fn new_deposit_transaction<T: WalletClient, N: NetworkOpsClient>(
     name: &str,
     enckey: &SecKey,
 ) -> Result<(TxAux, TransactionPending)> {
-    let attributes = StakedStateOpAttributes::new(get_network_id());
     let inputs = ask_inputs()?;
-    let to_address = ask_staking_address()?;
+    let (to_address, allow_external) =
+        ask_deposit_destination(wallet_client, network_ops_client, name, enckey)?;
     if !wallet_client.has_unspent_transactions(name, enckey, &inputs)? {
         return Err(Error::new(
             ErrorKind::InvalidInput,
             "Given transaction inputs are not present in unspent transactions (synchronizing your wallet may help)",
         ));
     }
+    let frozen = wallet_client.list_frozen_utxos(name, enckey)?;
+    let allow_frozen = if inputs.iter().any(|input| frozen.contains(input)) {
+        ask("Some of given inputs are frozen, spend them anyway? [yN] ");
+        yesno(false)
+            .chain(|| (ErrorKind::IoError, "Unable to read yes/no"))?
+            .unwrap_or(false)
+    } else {
+        false
+    };
     let transactions = inputs
         .into_iter()
         .map(|txo_pointer| {
@@ -643,7 +663,9 @@ fn new_deposit_transaction<T: WalletClient, N: NetworkOpsClient>(
         enckey,
         transactions,
         to_address,
-        attributes,
+        None,
+        allow_external,
+        allow_frozen,
     )
 }
 
@@ -653,10 +675,10 @@ fn new_deposit_amount_transaction<T: WalletClient, N: NetworkOpsClient>(
     name: &str,
     enckey: &SecKey,
 ) -> Result<()> {
-    let to_staking_address = ask_staking_address()?;
-    let attr = StakedStateOpAttributes::new(get_network_id());
+    let (to_staking_address, allow_external) =
+        ask_deposit_destination(wallet_client, network_ops_client, name, enckey)?;
     let amount = ask_cro()?;
-    let fee = network_ops_client.calculate_deposit_fee()?;
+    let fee = network_ops_client.calculate_single_input_deposit_fee()?;
     let total_amount = (amount + fee).chain(|| (ErrorKind::InvalidInput, "invalid amount"))?;
     success(&format!(
         "create a transfer transaction to make a UTXO with {} amount(fee is {})",
@@ -700,7 +722,9 @@ fn new_deposit_amount_transaction<T: WalletClient, N: NetworkOpsClient>(
         enckey,
         transactions,
         to_staking_address,
-        attr,
+        None,
+        allow_external,
+        false,
     )?;
     let tx_id = transaction.tx_id();
     success(&format!(
@@ -742,10 +766,13 @@ fn new_transfer_transaction<T: WalletClient>(
         None,
         return_address,
     )?;
+    let block_height = wallet_client.get_current_block_height()?;
     let tx_pending = TransactionPending {
-        block_height: wallet_client.get_current_block_height()?,
+        block_height,
         used_inputs,
         return_amount,
+        tx_id: transaction.tx_id(),
+        valid_until_height: block_height + DEFAULT_PENDING_TX_VALID_BLOCKS,
     };
     Ok((transaction, tx_pending))
 }
@@ -754,19 +781,17 @@ fn new_unjail_transaction<N: NetworkOpsClient>(
     network_ops_client: &N,
     name: &str,
     enckey: &SecKey,
-) -> Result<TxAux> {
-    let attributes = StakedStateOpAttributes::new(get_network_id());
+) -> Result<(TxAux, TransactionPending)> {
     let address = ask_staking_address()?;
 
-    network_ops_client.create_unjail_transaction(name, enckey, address, attributes)
+    network_ops_client.create_unjail_transaction(name, enckey, address, None, NonceSource::Network)
 }
 
 fn new_node_join_transaction<N: NetworkOpsClient>(
     network_ops_client: &N,
     name: &str,
     enckey: &SecKey,
-) -> Result<TxAux> {
-    let attributes = StakedStateOpAttributes::new(get_network_id());
+) -> Result<(TxAux, TransactionPending)> {
     let staking_account_address = ask_staking_address()?;
     let node_metadata = ask_node_metadata()?;
 
@@ -774,8 +799,9 @@ fn new_node_join_transaction<N: NetworkOpsClient>(
         name,
         enckey,
         staking_account_address,
-        attributes,
+        None,
         node_metadata,
+        NonceSource::Network,
     )
 }
 
@@ -932,6 +958,32 @@ fn ask_staking_address() -> Result<StakedStateAddress> {
     Ok(address)
 }
 
+fn ask_deposit_destination<T: WalletClient, N: NetworkOpsClient>(
+    wallet_client: &T,
+    network_ops_client: &N,
+    name: &str,
+    enckey: &SecKey,
+) -> Result<(StakedStateAddress, bool)> {
+    let to_address = ask_staking_address()?;
+    if wallet_client
+        .staking_addresses(name, enckey)?
+        .contains(&to_address)
+    {
+        return Ok((to_address, false));
+    }
+
+    ask(&format!(
+        "Staking address {} does not belong to this wallet, deposit to it anyway? [yN] ",
+        to_address
+    ));
+    let allow_external = yesno(false)
+        .chain(|| (ErrorKind::IoError, "Unable to read yes/no"))?
+        .unwrap_or(false);
+    network_ops_client.verify_deposit_destination(name, enckey, &to_address, allow_external)?;
+
+    Ok((to_address, allow_external))
+}
+
 fn ask_transfer_address() -> Result<ExtendedAddr> {
     ask("Enter transfer address: ");
     let address = text()
@@ -972,12 +1024,15 @@ fn ask_node_metadata() -> Result<CouncilNode> {
     let mut pubkey_bytes = [0; 32];
     pubkey_bytes.copy_from_slice(&decoded_pubkey);
 
-    Ok(CouncilNode {
+    let node_metadata = CouncilNode {
         name,
         security_contact: None,
         consensus_pubkey: TendermintValidatorPubKey::Ed25519(pubkey_bytes),
         confidential_init: ConfidentialInit {
             cert: b"FIXME".to_vec(),
         },
-    })
+    };
+    validate_council_node(&node_metadata)?;
+
+    Ok(node_metadata)
 }
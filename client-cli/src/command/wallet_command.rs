@@ -35,6 +35,13 @@ pub enum WalletCommand {
             case_insensitive = true
         )]
         wallet_type: WalletKind,
+        #[structopt(
+            name = "account",
+            short = "a",
+            long = "account",
+            help = "Account of HD wallet (defaults to 0)"
+        )]
+        account: Option<u32>,
     },
     #[structopt(name = "export", about = "Backup wallet to a file")]
     Export {
@@ -115,19 +122,61 @@ pub enum WalletCommand {
         )]
         name: String,
     },
+    #[structopt(name = "change-passphrase", about = "Change passphrase of wallet")]
+    ChangePassphrase {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+    },
+    #[structopt(name = "rename", about = "Rename wallet")]
+    Rename {
+        #[structopt(
+            name = "wallet name",
+            short = "n",
+            long = "name",
+            help = "Name of wallet"
+        )]
+        name: String,
+        #[structopt(
+            name = "new wallet name",
+            short = "t",
+            long = "to",
+            help = "New name of wallet"
+        )]
+        new_name: String,
+    },
 }
 
 impl WalletCommand {
+    /// Returns `true` if command never writes to storage
+    pub fn is_read_only(&self) -> bool {
+        match self {
+            WalletCommand::List | WalletCommand::Export { .. } => true,
+            // authentication completes interrupted passphrase change
+            _ => false,
+        }
+    }
+
     pub fn execute<T: WalletClient>(&self, wallet_client: T) -> Result<()> {
         match self {
-            WalletCommand::New { name, wallet_type } => {
-                Self::new_wallet(wallet_client, name, *wallet_type)
-            }
+            WalletCommand::New {
+                name,
+                wallet_type,
+                account,
+            } => Self::new_wallet(wallet_client, name, *wallet_type, *account),
             WalletCommand::List => Self::list_wallets(wallet_client),
             WalletCommand::Restore { name } => Self::restore_wallet(wallet_client, name),
             WalletCommand::RestoreBasic { name } => Self::restore_basic_wallet(wallet_client, name),
             WalletCommand::AuthToken { name } => Self::auth_token(wallet_client, name),
             WalletCommand::Delete { name } => Self::delete(wallet_client, name),
+            WalletCommand::ChangePassphrase { name } => {
+                Self::change_passphrase(wallet_client, name)
+            }
+            WalletCommand::Rename { name, new_name } => Self::rename(wallet_client, name, new_name),
             WalletCommand::Export {
                 name,
                 from_file,
@@ -141,6 +190,7 @@ impl WalletCommand {
         wallet_client: T,
         name: &str,
         wallet_kind: WalletKind,
+        account: Option<u32>,
     ) -> Result<()> {
         let passphrase = ask_passphrase(None)?;
         let confirmed_passphrase = ask_passphrase(Some("Confirm passphrase: "))?;
@@ -151,7 +201,8 @@ impl WalletCommand {
                 "Passphrases do not match",
             ));
         }
-        let (enckey, mnemonic) = wallet_client.new_wallet(name, &passphrase, wallet_kind)?;
+        let (enckey, mnemonic) =
+            wallet_client.new_wallet(name, &passphrase, wallet_kind, account, None)?;
 
         if let WalletKind::HD = wallet_kind {
             ask("Please store following mnemonic safely to restore your wallet later: ");
@@ -298,7 +349,7 @@ impl WalletCommand {
             ));
         }
 
-        let enckey = wallet_client.restore_wallet(name, &passphrase, &mnemonic)?;
+        let enckey = wallet_client.restore_wallet(name, &passphrase, &mnemonic, None)?;
 
         mnemonic.zeroize();
 
@@ -358,7 +409,53 @@ impl WalletCommand {
 
     fn delete<T: WalletClient>(wallet_client: T, name: &str) -> Result<()> {
         let passphrase = ask_passphrase(None)?;
-        wallet_client.delete_wallet(name, &passphrase)?;
+        let token = wallet_client.delete_wallet(name, &passphrase, None)?;
+
+        ask(&format!(
+            "Enter wallet name ({}) to confirm deletion: ",
+            name
+        ));
+        let confirmation = text().chain(|| (ErrorKind::IoError, "Unable to read wallet name"))?;
+        if confirmation != name {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Wallet name does not match, deletion cancelled",
+            ));
+        }
+
+        wallet_client.delete_wallet(name, &passphrase, token.as_deref())?;
+        success(&format!("Wallet {} deleted", name));
+        Ok(())
+    }
+
+    fn change_passphrase<T: WalletClient>(wallet_client: T, name: &str) -> Result<()> {
+        let old_passphrase = ask_passphrase(Some("Enter current passphrase: "))?;
+        let new_passphrase = ask_passphrase(Some("Enter new passphrase: "))?;
+        let confirmed_passphrase = ask_passphrase(Some("Confirm new passphrase: "))?;
+
+        if new_passphrase != confirmed_passphrase {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Passphrases do not match",
+            ));
+        }
+
+        let enckey = wallet_client.change_passphrase(name, &old_passphrase, &new_passphrase)?;
+        success(&format!(
+            "Passphrase changed, new authentication token: {}",
+            &hex::encode(enckey.unsecure())
+        ));
+        Ok(())
+    }
+
+    fn rename<T: WalletClient>(wallet_client: T, name: &str, new_name: &str) -> Result<()> {
+        let passphrase = ask_passphrase(None)?;
+        let enckey = wallet_client.rename_wallet(name, &passphrase, new_name)?;
+        success(&format!(
+            "Wallet renamed to {}, new authentication token: {}",
+            new_name,
+            &hex::encode(enckey.unsecure())
+        ));
         Ok(())
     }
 }
@@ -121,6 +121,14 @@ pub enum ErrorKind {
     ValidationError,
     /// Block data verify failed
     VerifyError,
+    /// Insufficient balance
+    InsufficientBalance,
+    /// Transaction is not included in a block in time
+    TransactionTimeout,
+    /// Request is rejected by user (e.g. on hardware wallet)
+    RejectedByUser,
+    /// Wallet session is expired, locked or unknown
+    InvalidSession,
 }
 
 impl fmt::Display for ErrorKind {
@@ -143,6 +151,10 @@ impl fmt::Display for ErrorKind {
             ErrorKind::InternalError => write!(f, "Internal error"),
             ErrorKind::ValidationError => write!(f, "Validation error"),
             ErrorKind::VerifyError => write!(f, "Verify error"),
+            ErrorKind::InsufficientBalance => write!(f, "Insufficient balance"),
+            ErrorKind::TransactionTimeout => write!(f, "Transaction timeout"),
+            ErrorKind::RejectedByUser => write!(f, "Rejected by user"),
+            ErrorKind::InvalidSession => write!(f, "Invalid session"),
         }
     }
 }
@@ -8,6 +8,7 @@ pub mod multi_sig_address;
 pub mod seckey;
 pub mod storage;
 pub mod tendermint;
+pub mod tx_aux_encoding;
 
 #[doc(inline)]
 pub use error::{Error, ErrorKind, Result, ResultExt};
@@ -18,9 +19,11 @@ pub use multi_sig_address::MultiSigAddress;
 #[doc(inline)]
 pub use seckey::SecKey;
 #[doc(inline)]
-pub use storage::{SecureStorage, Storage};
+pub use storage::{SecureStorage, Storage, StorageOp};
 #[doc(inline)]
 pub use transaction::{SignedTransaction, Transaction, TransactionInfo};
+#[doc(inline)]
+pub use tx_aux_encoding::{decode_tx_aux, encode_tx_aux, encode_tx_aux_base64};
 
 use secp256k1::{All, Secp256k1};
 
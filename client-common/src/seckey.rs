@@ -3,6 +3,7 @@ use std::str::FromStr;
 
 use aes::{block_cipher_trait::BlockCipher, Aes256};
 use aes_gcm_siv::aead::generic_array::{typenum::Unsigned, GenericArray};
+use parity_scale_codec::{Decode, Encode};
 use secstr::{SecBox, SecUtf8};
 use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
 use zeroize::Zeroize;
@@ -49,6 +50,29 @@ pub fn parse_hex_enckey(s: &str) -> Result<SecKey> {
 const GLOBAL_DATA_CONTEXT: &str =
     "Crypto.com Chain Wallet 2020-03-30 16:59:10 global wallet data encryption";
 const SALT_CONTEXT: &str = "Crypto.com Chain Wallet 2020-03-30 16:59:10 salt from wallet name";
+const BACKUP_CONTEXT: &str = "Crypto.com Chain Wallet 2020-06-15 11:20:42 wallet backup encryption";
+
+/// Parameters of argon2 key derivation (stored along with data encrypted with derived key)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Encode, Decode)]
+pub struct KdfParams {
+    /// memory cost (in KiB)
+    pub mem_cost: u32,
+    /// number of passes
+    pub time_cost: u32,
+    /// degree of parallelism
+    pub lanes: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        let config = argon2::Config::default();
+        KdfParams {
+            mem_cost: config.mem_cost,
+            time_cost: config.time_cost,
+            lanes: config.lanes,
+        }
+    }
+}
 
 /// derive encryption key from passphrase
 /// FIXME: derivation should derive multiple keys, e.g. for view/sync and spending operations
@@ -63,6 +87,26 @@ pub fn derive_enckey(passphrase: &SecUtf8, name: &str) -> argon2::Result<SecKey>
     Ok(SecKey(SecBox::new(Box::new(arr))))
 }
 
+/// derive encryption key of wallet backup from backup passphrase, random salt and given
+/// key derivation parameters
+pub fn derive_backup_key(
+    passphrase: &SecUtf8,
+    salt: &[u8],
+    params: &KdfParams,
+) -> argon2::Result<SecKey> {
+    let config = argon2::Config {
+        mem_cost: params.mem_cost,
+        time_cost: params.time_cost,
+        lanes: params.lanes,
+        ..Default::default()
+    };
+    let mut extended = argon2::hash_raw(passphrase.unsecure().as_bytes(), salt, &config)?;
+    let mut arr = GenericArray::clone_from_slice(&[0; 32]);
+    blake3::derive_key(BACKUP_CONTEXT, &extended, &mut arr);
+    extended.zeroize();
+    Ok(SecKey(SecBox::new(Box::new(arr))))
+}
+
 impl<'de> Deserialize<'de> for SecKey {
     fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
     where
@@ -98,6 +142,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn check_backup_key_depends_on_salt_and_params() {
+        let passphrase = SecUtf8::from("passphrase");
+        let params = KdfParams::default();
+        let key = derive_backup_key(&passphrase, &[0; 16], &params).unwrap();
+
+        assert_eq!(
+            key,
+            derive_backup_key(&passphrase, &[0; 16], &params).unwrap()
+        );
+        assert_ne!(
+            key,
+            derive_backup_key(&passphrase, &[1; 16], &params).unwrap()
+        );
+        assert_ne!(
+            key,
+            derive_backup_key(
+                &passphrase,
+                &[0; 16],
+                &KdfParams {
+                    time_cost: params.time_cost + 1,
+                    ..params
+                }
+            )
+            .unwrap()
+        );
+    }
+
     quickcheck! {
         fn check_serialization(passphrase: String, name: String) -> bool {
             let key = derive_enckey(&SecUtf8::from(passphrase), &name).unwrap();
@@ -1,13 +1,21 @@
 //! Data storage layer
+mod batch_storage;
 mod memory_storage;
 #[cfg(feature = "sled")]
 mod sled_storage;
+#[cfg(feature = "sled")]
+mod storage_lock;
+#[cfg(any(test, feature = "test-utils"))]
+mod test_storage;
 mod unauthorized_storage;
 use parity_scale_codec::{Decode, Encode};
 
+pub use batch_storage::BatchStorage;
 pub use memory_storage::MemoryStorage;
 #[cfg(feature = "sled")]
 pub use sled_storage::SledStorage;
+#[cfg(any(test, feature = "test-utils"))]
+pub use test_storage::TestStorage;
 pub use unauthorized_storage::UnauthorizedStorage;
 
 use crate::SecKey;
@@ -22,6 +30,58 @@ use crate::{Error, ErrorKind, Result, ResultExt};
 /// Nonce size in bytes
 const NONCE_SIZE: usize = 12;
 
+/// Iterator over `(key, value)` records of a keyspace (see `Storage::iter_prefix`)
+pub type RecordIter<'a> = Box<dyn Iterator<Item = Result<(Vec<u8>, Vec<u8>)>> + 'a>;
+
+/// Write operation in a batch of writes (see `Storage::write_batch`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageOp {
+    /// Sets value of a key in keyspace
+    Set {
+        /// Keyspace of record
+        keyspace: Vec<u8>,
+        /// Key of record
+        key: Vec<u8>,
+        /// New value of record
+        value: Vec<u8>,
+    },
+    /// Deletes a key from keyspace
+    Delete {
+        /// Keyspace of record
+        keyspace: Vec<u8>,
+        /// Key of record
+        key: Vec<u8>,
+    },
+}
+
+impl StorageOp {
+    /// Creates an operation setting value of a key in keyspace
+    pub fn set<S: AsRef<[u8]>, K: AsRef<[u8]>>(keyspace: S, key: K, value: Vec<u8>) -> Self {
+        StorageOp::Set {
+            keyspace: keyspace.as_ref().to_vec(),
+            key: key.as_ref().to_vec(),
+            value,
+        }
+    }
+
+    /// Creates an operation deleting a key from keyspace
+    pub fn delete<S: AsRef<[u8]>, K: AsRef<[u8]>>(keyspace: S, key: K) -> Self {
+        StorageOp::Delete {
+            keyspace: keyspace.as_ref().to_vec(),
+            key: key.as_ref().to_vec(),
+        }
+    }
+
+    /// Returns keyspace and key of record written by this operation
+    pub fn record(&self) -> (&[u8], &[u8]) {
+        match self {
+            StorageOp::Set { keyspace, key, .. } | StorageOp::Delete { keyspace, key } => {
+                (keyspace, key)
+            }
+        }
+    }
+}
+
 /// Interface for a generic key-value storage
 pub trait Storage: Send + Sync + Clone {
     /// Clears all data in a keyspace.
@@ -61,6 +121,79 @@ pub trait Storage: Send + Sync + Clone {
     /// Returns all the keyspaces currently available.
     fn keyspaces(&self) -> Result<Vec<Vec<u8>>>;
 
+    /// Returns an iterator over `(key, value)` records of keyspace whose keys start with given
+    /// prefix (in order of keys). Storage can be written while iterating.
+    ///
+    /// Default implementation loads keys of keyspace and reads values one by one. Storages which
+    /// support it should read records lazily.
+    fn iter_prefix<S: AsRef<[u8]>, P: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        prefix: P,
+    ) -> Result<RecordIter<'_>> {
+        let mut keys = self
+            .keys(&keyspace)?
+            .into_iter()
+            .filter(|key| key.starts_with(prefix.as_ref()))
+            .collect::<Vec<_>>();
+        keys.sort();
+
+        let keyspace = keyspace.as_ref().to_vec();
+        Ok(Box::new(keys.into_iter().filter_map(move |key| {
+            // records deleted while iterating are skipped
+            self.get(&keyspace, &key)
+                .transpose()
+                .map(|value| value.map(|value| (key, value)))
+        })))
+    }
+
+    /// Overwrites values of given `(keyspace, key)` records with zeros and deletes them.
+    ///
+    /// Storages which support it should delete all the records in a single batch.
+    fn wipe<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, records: &[(S, K)]) -> Result<()> {
+        for (keyspace, key) in records {
+            if let Some(value) = self.get(keyspace, key)? {
+                self.set(keyspace, key, vec![0; value.len()])?;
+            }
+        }
+        for (keyspace, key) in records {
+            self.delete(keyspace, key)?;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if storage refuses all writes.
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Writes all buffered changes durably to disk (where storage buffers them).
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Applies given write operations in order (either all of them or none).
+    ///
+    /// Default implementation applies them one by one and restores previous values if any of them
+    /// fails. Storages which support it should apply all the operations in a single transaction.
+    fn write_batch(&self, ops: Vec<StorageOp>) -> Result<()> {
+        write_ops(self, ops)
+    }
+
+    /// Sets values of given `(keyspace, key, value)` records (either all of them or none, see
+    /// `write_batch`).
+    fn set_batch<S: AsRef<[u8]>, K: AsRef<[u8]>>(
+        &self,
+        records: Vec<(S, K, Vec<u8>)>,
+    ) -> Result<()> {
+        self.write_batch(
+            records
+                .into_iter()
+                .map(|(keyspace, key, value)| StorageOp::set(keyspace, key, value))
+                .collect(),
+        )
+    }
+
     /// load and deserialize object
     fn load<T: Decode>(&self, keyspace: &str, key: &str) -> Result<Option<T>> {
         if let Some(bytes) = self.get(keyspace, key)? {
@@ -81,6 +214,47 @@ pub trait Storage: Send + Sync + Clone {
     }
 }
 
+/// Returns identifiers of storage backends compiled in this build
+pub fn storage_backends() -> Vec<&'static str> {
+    let mut backends = vec!["memory"];
+    if cfg!(feature = "sled") {
+        backends.push("sled");
+    }
+    backends
+}
+
+/// Applies given write operations to storage one by one, restoring previous values if any of them
+/// fails (default implementation of `Storage::write_batch`)
+fn write_ops<T: Storage>(storage: &T, ops: Vec<StorageOp>) -> Result<()> {
+    let mut previous = Vec::with_capacity(ops.len());
+    for op in ops.iter() {
+        let result = match op {
+            StorageOp::Set {
+                keyspace,
+                key,
+                value,
+            } => storage.set(keyspace, key, value.clone()),
+            StorageOp::Delete { keyspace, key } => storage.delete(keyspace, key),
+        };
+        match result {
+            Ok(old_value) => {
+                let (keyspace, key) = op.record();
+                previous.push((keyspace, key, old_value));
+            }
+            Err(err) => {
+                for (keyspace, key, old_value) in previous.into_iter().rev() {
+                    match old_value {
+                        Some(old_value) => storage.set(keyspace, key, old_value)?,
+                        None => storage.delete(keyspace, key)?,
+                    };
+                }
+                return Err(err);
+            }
+        }
+    }
+    Ok(())
+}
+
 /// Interface for a generic key-value storage (with encryption)
 pub trait SecureStorage: Storage {
     /// Returns value (after decryption) of key if it exists in given keyspace.
@@ -234,6 +408,13 @@ pub fn encrypt_bytes<K: AsRef<[u8]>>(key: K, enckey: &SecKey, bytes: &[u8]) -> R
 
 /// Decrypts bytes with given enckey
 pub fn decrypt_bytes<K: AsRef<[u8]>>(key: K, enckey: &SecKey, bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.len() < NONCE_SIZE {
+        return Err(Error::new(
+            ErrorKind::DecryptionError,
+            "Encrypted bytes are shorter than nonce",
+        ));
+    }
+
     let algo = get_algo(enckey);
 
     let payload = Payload {
@@ -253,3 +434,123 @@ pub fn decrypt_bytes<K: AsRef<[u8]>>(key: K, enckey: &SecKey, bytes: &[u8]) -> R
 fn get_algo(enckey: &SecKey) -> Aes256GcmSiv {
     Aes256GcmSiv::new(*enckey.unsecure())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents<T: Storage>(storage: &T) -> Vec<(Vec<u8>, Vec<u8>, Vec<u8>)> {
+        let mut contents = Vec::new();
+        for keyspace in storage.keyspaces().unwrap() {
+            for key in storage.keys(&keyspace).unwrap() {
+                let value = storage.get(&keyspace, &key).unwrap().unwrap();
+                contents.push((keyspace.clone(), key, value));
+            }
+        }
+        contents.sort();
+        contents
+    }
+
+    #[test]
+    fn check_write_batch_rolls_back() {
+        let storage = TestStorage::default().with_non_atomic_batches();
+        storage.set("a", "key", vec![1]).unwrap();
+        storage.set("b", "key", vec![2]).unwrap();
+        let before = contents(&storage);
+
+        let ops = vec![
+            StorageOp::set("a", "key", vec![3]),
+            StorageOp::delete("b", "key"),
+            StorageOp::set("a", "new", vec![4]),
+            StorageOp::delete("b", "missing"),
+        ];
+        for writes in 0..ops.len() {
+            storage.fail_once_after(writes);
+            assert!(storage.write_batch(ops.clone()).is_err());
+            assert_eq!(before, contents(&storage));
+        }
+
+        storage.write_batch(ops).unwrap();
+        assert_eq!(Some(vec![3]), storage.get("a", "key").unwrap());
+        assert_eq!(Some(vec![4]), storage.get("a", "new").unwrap());
+        assert!(!storage.contains_key("b", "key").unwrap());
+    }
+
+    fn prefix_keys<T: Storage>(storage: &T, keyspace: &str, prefix: &str) -> Vec<Vec<u8>> {
+        storage
+            .iter_prefix(keyspace, prefix)
+            .unwrap()
+            .map(|record| record.unwrap().0)
+            .collect()
+    }
+
+    fn check_iter_prefix<T: Storage>(storage: &T) {
+        for key in &["b", "abd", "ab", "", "abc", "ba"] {
+            storage.set("a", key, key.as_bytes().to_vec()).unwrap();
+        }
+        storage.set("x", "ab", vec![]).unwrap();
+        let keys = |keys: &[&str]| {
+            keys.iter()
+                .map(|key| key.as_bytes().to_vec())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            keys(&["", "ab", "abc", "abd", "b", "ba"]),
+            prefix_keys(storage, "a", "")
+        );
+        assert_eq!(keys(&["ab", "abc", "abd"]), prefix_keys(storage, "a", "ab"));
+        assert_eq!(keys(&["abc"]), prefix_keys(storage, "a", "abc"));
+        assert!(prefix_keys(storage, "a", "c").is_empty());
+        assert!(prefix_keys(storage, "missing", "").is_empty());
+        assert!(!storage.keyspaces().unwrap().contains(&b"missing".to_vec()));
+        let (key, value) = storage
+            .iter_prefix("a", "abc")
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        assert_eq!((b"abc".to_vec(), b"abc".to_vec()), (key, value));
+
+        // writing while iterating
+        let mut seen = Vec::new();
+        for record in storage.iter_prefix("a", "ab").unwrap() {
+            let (key, _) = record.unwrap();
+            if seen.is_empty() {
+                storage.set("a", "aa", vec![]).unwrap();
+                storage.delete("a", "abd").unwrap();
+            }
+            seen.push(key);
+        }
+        assert!(seen.windows(2).all(|keys| keys[0] < keys[1]));
+        assert!(seen.iter().all(|key| key.starts_with(b"ab")));
+        assert!(seen.contains(&b"ab".to_vec()) && seen.contains(&b"abc".to_vec()));
+        assert_eq!(keys(&["ab", "abc"]), prefix_keys(storage, "a", "ab"));
+        assert_eq!(keys(&["aa", "ab", "abc"]), prefix_keys(storage, "a", "a"));
+    }
+
+    #[test]
+    fn check_storage_backends() {
+        let backends = storage_backends();
+        assert!(backends.contains(&"memory"));
+        assert_eq!(cfg!(feature = "sled"), backends.contains(&"sled"));
+    }
+
+    #[test]
+    fn check_iter_prefix_default() {
+        check_iter_prefix(&TestStorage::default());
+    }
+
+    #[test]
+    fn check_iter_prefix_memory() {
+        check_iter_prefix(&MemoryStorage::default());
+    }
+
+    #[cfg(feature = "sled")]
+    #[test]
+    fn check_iter_prefix_sled() {
+        let storage = SledStorage::new("./storage-iter-test").unwrap();
+        check_iter_prefix(&storage);
+        std::fs::remove_dir_all("./storage-iter-test").unwrap();
+    }
+}
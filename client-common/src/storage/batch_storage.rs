@@ -0,0 +1,196 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{Error, ErrorKind, Result, Storage};
+
+/// Pending writes (`keyspace -> key -> value`)
+type Writes = HashMap<Vec<u8>, BTreeMap<Vec<u8>, Vec<u8>>>;
+
+/// Storage buffering all writes in memory until `commit` writes them to underlying storage in a
+/// single batch (reads see buffered writes)
+///
+/// Deleting records is not supported (only values set in a batch can be committed atomically).
+#[derive(Debug, Clone)]
+pub struct BatchStorage<T: Storage> {
+    storage: T,
+    writes: Arc<RwLock<Writes>>,
+}
+
+impl<T> BatchStorage<T>
+where
+    T: Storage,
+{
+    /// Creates a new batch of writes to given storage
+    pub fn new(storage: T) -> Self {
+        Self {
+            storage,
+            writes: Default::default(),
+        }
+    }
+
+    /// Writes all buffered records to underlying storage (either all of them or none)
+    pub fn commit(&self) -> Result<()> {
+        let writes = std::mem::take(&mut *self.write_lock()?);
+        let records = writes
+            .into_iter()
+            .flat_map(|(keyspace, space)| {
+                space
+                    .into_iter()
+                    .map(move |(key, value)| (keyspace.clone(), key, value))
+            })
+            .collect::<Vec<_>>();
+
+        if records.is_empty() {
+            Ok(())
+        } else {
+            self.storage.set_batch(records)
+        }
+    }
+
+    fn read_lock(&self) -> Result<RwLockReadGuard<'_, Writes>> {
+        self.writes.read().map_err(|_| {
+            Error::new(
+                ErrorKind::StorageError,
+                "Unable to acquire read lock on batch storage",
+            )
+        })
+    }
+
+    fn write_lock(&self) -> Result<RwLockWriteGuard<'_, Writes>> {
+        self.writes.write().map_err(|_| {
+            Error::new(
+                ErrorKind::StorageError,
+                "Unable to acquire write lock on batch storage",
+            )
+        })
+    }
+}
+
+fn delete_error() -> Error {
+    Error::new(
+        ErrorKind::InternalError,
+        "Records can not be deleted in a batch of writes",
+    )
+}
+
+impl<T> Storage for BatchStorage<T>
+where
+    T: Storage,
+{
+    fn clear<S: AsRef<[u8]>>(&self, _keyspace: S) -> Result<()> {
+        Err(delete_error())
+    }
+
+    fn get<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<Option<Vec<u8>>> {
+        let buffered = self
+            .read_lock()?
+            .get(keyspace.as_ref())
+            .and_then(|space| space.get(key.as_ref()))
+            .cloned();
+
+        match buffered {
+            Some(value) => Ok(Some(value)),
+            None => self.storage.get(keyspace, key),
+        }
+    }
+
+    fn set<S: AsRef<[u8]>, K: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        key: K,
+        value: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>> {
+        let current = self.get(&keyspace, &key)?;
+        self.write_lock()?
+            .entry(keyspace.as_ref().to_vec())
+            .or_default()
+            .insert(key.as_ref().to_vec(), value);
+        Ok(current)
+    }
+
+    fn delete<S: AsRef<[u8]>, K: AsRef<[u8]>>(
+        &self,
+        _keyspace: S,
+        _key: K,
+    ) -> Result<Option<Vec<u8>>> {
+        Err(delete_error())
+    }
+
+    fn fetch_and_update<S, K, F>(&self, keyspace: S, key: K, f: F) -> Result<Option<Vec<u8>>>
+    where
+        S: AsRef<[u8]>,
+        K: AsRef<[u8]>,
+        F: Fn(Option<&[u8]>) -> Result<Option<Vec<u8>>>,
+    {
+        let current = self.get(&keyspace, &key)?;
+        match f(current.as_deref())? {
+            Some(next) => self.set(keyspace, key, next),
+            None => Err(delete_error()),
+        }
+    }
+
+    fn keys<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<Vec<Vec<u8>>> {
+        let mut keys = self.storage.keys(&keyspace)?;
+        if let Some(space) = self.read_lock()?.get(keyspace.as_ref()) {
+            for key in space.keys() {
+                if !keys.contains(key) {
+                    keys.push(key.clone());
+                }
+            }
+        }
+        Ok(keys)
+    }
+
+    fn contains_key<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<bool> {
+        let buffered = self
+            .read_lock()?
+            .get(keyspace.as_ref())
+            .map_or(false, |space| space.contains_key(key.as_ref()));
+
+        Ok(buffered || self.storage.contains_key(keyspace, key)?)
+    }
+
+    fn keyspaces(&self) -> Result<Vec<Vec<u8>>> {
+        let mut keyspaces = self.storage.keyspaces()?;
+        for keyspace in self.read_lock()?.keys() {
+            if !keyspaces.contains(keyspace) {
+                keyspaces.push(keyspace.clone());
+            }
+        }
+        Ok(keyspaces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::storage::MemoryStorage;
+
+    #[test]
+    fn check_commit() {
+        let storage = MemoryStorage::default();
+        storage.set("a", "key", vec![1]).unwrap();
+
+        let batch = BatchStorage::new(storage.clone());
+        assert_eq!(Some(vec![1]), batch.set("a", "key", vec![2]).unwrap());
+        batch.set("b", "key", vec![3]).unwrap();
+        batch
+            .fetch_and_update("b", "key", |value| Ok(Some(vec![value.unwrap()[0] + 1])))
+            .unwrap();
+
+        // writes are visible in batch only
+        assert_eq!(Some(vec![2]), batch.get("a", "key").unwrap());
+        assert_eq!(Some(vec![4]), batch.get("b", "key").unwrap());
+        assert!(batch.contains_key("b", "key").unwrap());
+        assert_eq!(vec![b"key".to_vec()], batch.keys("a").unwrap());
+        assert_eq!(Some(vec![1]), storage.get("a", "key").unwrap());
+        assert!(storage.keys("b").unwrap().is_empty());
+        assert!(batch.delete("a", "key").is_err());
+
+        batch.commit().unwrap();
+
+        assert_eq!(Some(vec![2]), storage.get("a", "key").unwrap());
+        assert_eq!(Some(vec![4]), storage.get("b", "key").unwrap());
+    }
+}
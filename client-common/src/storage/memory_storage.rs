@@ -1,12 +1,68 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
 use std::sync::{Arc, RwLock};
 
-use crate::{Error, ErrorKind, Result, Storage};
+use zeroize::Zeroize;
 
-/// Storage backed by `HashMap`
+use crate::storage::RecordIter;
+use crate::{Error, ErrorKind, Result, Storage, StorageOp};
+
+/// Storage backed by `HashMap` (keys of a keyspace are kept in order)
 #[allow(clippy::type_complexity)]
 #[derive(Debug, Default, Clone)]
-pub struct MemoryStorage(Arc<RwLock<HashMap<Vec<u8>, HashMap<Vec<u8>, Vec<u8>>>>>);
+pub struct MemoryStorage(Arc<RwLock<HashMap<Vec<u8>, BTreeMap<Vec<u8>, Vec<u8>>>>>);
+
+/// Iterator over records of memory storage with given prefix (lock is only held while reading
+/// next record, so that storage can be written while iterating)
+struct PrefixIter<'a> {
+    storage: &'a MemoryStorage,
+    keyspace: Vec<u8>,
+    prefix: Vec<u8>,
+    last_key: Option<Vec<u8>>,
+    done: bool,
+}
+
+impl Iterator for PrefixIter<'_> {
+    type Item = Result<(Vec<u8>, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let memory = match self.storage.0.read() {
+            Ok(memory) => memory,
+            Err(_) => {
+                self.done = true;
+                return Some(Err(Error::new(
+                    ErrorKind::StorageError,
+                    "Unable to acquire read lock on memory storage",
+                )));
+            }
+        };
+        let lower = match self.last_key {
+            Some(ref last_key) => Bound::Excluded(last_key.as_slice()),
+            None => Bound::Included(self.prefix.as_slice()),
+        };
+        let next = memory
+            .get(&self.keyspace)
+            .and_then(|space| space.range::<[u8], _>((lower, Bound::Unbounded)).next())
+            .filter(|(key, _)| key.starts_with(&self.prefix))
+            .map(|(key, value)| (key.clone(), value.clone()));
+        drop(memory);
+
+        match next {
+            Some((key, value)) => {
+                self.last_key = Some(key.clone());
+                Some(Ok((key, value)))
+            }
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
 
 impl Storage for MemoryStorage {
     fn clear<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<()> {
@@ -18,7 +74,7 @@ impl Storage for MemoryStorage {
         })?;
 
         if let Some(ref mut space) = memory.get_mut(keyspace.as_ref()) {
-            space.drain();
+            space.clear();
         }
 
         Ok(())
@@ -150,4 +206,106 @@ impl Storage for MemoryStorage {
 
         Ok(keyspaces)
     }
+
+    fn iter_prefix<S: AsRef<[u8]>, P: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        prefix: P,
+    ) -> Result<RecordIter<'_>> {
+        Ok(Box::new(PrefixIter {
+            storage: self,
+            keyspace: keyspace.as_ref().to_vec(),
+            prefix: prefix.as_ref().to_vec(),
+            last_key: None,
+            done: false,
+        }))
+    }
+
+    fn write_batch(&self, ops: Vec<StorageOp>) -> Result<()> {
+        let mut memory = self.0.write().map_err(|_| {
+            Error::new(
+                ErrorKind::StorageError,
+                "Unable to acquire write lock on memory storage",
+            )
+        })?;
+
+        for op in ops {
+            match op {
+                StorageOp::Set {
+                    keyspace,
+                    key,
+                    value,
+                } => {
+                    memory.entry(keyspace).or_default().insert(key, value);
+                }
+                StorageOp::Delete { keyspace, key } => {
+                    if let Some(space) = memory.get_mut(&keyspace) {
+                        space.remove(&key);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn wipe<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, records: &[(S, K)]) -> Result<()> {
+        let mut memory = self.0.write().map_err(|_| {
+            Error::new(
+                ErrorKind::StorageError,
+                "Unable to acquire write lock on memory storage",
+            )
+        })?;
+
+        for (keyspace, key) in records {
+            if let Some(mut value) = memory
+                .get_mut(keyspace.as_ref())
+                .and_then(|space| space.remove(key.as_ref()))
+            {
+                value.zeroize();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_wipe() {
+        let storage = MemoryStorage::default();
+        storage.set("a", "key", vec![1]).unwrap();
+        storage.set("b", "key", vec![2]).unwrap();
+        storage.set("b", "other", vec![3]).unwrap();
+
+        storage
+            .wipe(&[("a", "key"), ("b", "key"), ("b", "missing")])
+            .unwrap();
+
+        assert!(storage.keys("a").unwrap().is_empty());
+        assert_eq!(vec![b"other".to_vec()], storage.keys("b").unwrap());
+    }
+
+    #[test]
+    fn check_write_batch() {
+        let storage = MemoryStorage::default();
+        storage.set("a", "key", vec![1]).unwrap();
+        storage.set("b", "key", vec![2]).unwrap();
+
+        storage
+            .write_batch(vec![
+                StorageOp::set("a", "key", vec![3]),
+                StorageOp::set("c", "key", vec![4]),
+                StorageOp::delete("b", "key"),
+                StorageOp::delete("b", "missing"),
+            ])
+            .unwrap();
+
+        assert_eq!(Some(vec![3]), storage.get("a", "key").unwrap());
+        assert_eq!(Some(vec![4]), storage.get("c", "key").unwrap());
+        assert!(storage.keys("b").unwrap().is_empty());
+    }
 }
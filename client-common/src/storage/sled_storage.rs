@@ -1,37 +1,169 @@
 #![cfg(feature = "sled")]
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
 
-use sled::{Config, Db};
+use sled::transaction::ConflictableTransactionError;
+use sled::{Config, Db, Transactional, Tree};
 
-use crate::storage::Storage;
-use crate::{ErrorKind, Result, ResultExt};
+use super::storage_lock::{StorageLock, LOCK_FILE_NAME};
+use crate::storage::{RecordIter, Storage, StorageOp};
+use crate::{Error, ErrorKind, Result, ResultExt};
+
+/// Number of attempts to copy storage directory while it is not being written
+const SNAPSHOT_ATTEMPTS: usize = 10;
+/// Delay between attempts to copy storage directory
+const SNAPSHOT_RETRY_DELAY: Duration = Duration::from_millis(100);
 
 /// Storage backed by Sled
+///
+/// Storage directory is locked while it is opened for writing, so that it is never written by two
+/// clients at once.
 #[derive(Clone)]
-pub struct SledStorage(Arc<Db>);
+pub struct SledStorage {
+    db: Arc<Db>,
+    /// Lock of storage directory (shared if storage is opened read-only, `None` if a snapshot of
+    /// storage is opened)
+    lock: Option<Arc<StorageLock>>,
+}
 
 impl SledStorage {
-    /// Creates a new instance with specified path for data storage
+    /// Creates a new instance with specified path for data storage (fails if storage is locked by
+    /// another client)
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
-        if cfg!(test) {
-            Ok(Self(Arc::new(
-                Config::default()
-                    .path(&path)
-                    .temporary(true)
-                    .open()
-                    .chain(|| {
-                        (
-                            ErrorKind::InitializationError,
-                            format!(
-                                "Unable to initialize sled storage at path: {}",
-                                path.as_ref().display()
-                            ),
-                        )
-                    })?,
-            )))
+        let lock = StorageLock::acquire(path.as_ref())?;
+        Ok(Self {
+            db: Arc::new(open_db(path)?),
+            lock: Some(Arc::new(lock)),
+        })
+    }
+
+    /// Opens storage at specified path for reading (all writes are refused)
+    ///
+    /// Storage is locked for reading, so that no client can open it for writing meanwhile. Sled
+    /// database cannot be opened by two clients at once, so storage opened by another client is
+    /// read from a snapshot of its last flushed state (taken when it is opened).
+    pub fn read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let (db, lock) = match StorageLock::acquire_shared(path)? {
+            Some(lock) => match open_db(path) {
+                Ok(db) => (db, Some(Arc::new(lock))),
+                // storage is opened by another read-only client, so it is not written while
+                // snapshot is taken
+                Err(_) => (open_snapshot(path)?, None),
+            },
+            None => (open_snapshot(path)?, None),
+        };
+
+        Ok(Self {
+            db: Arc::new(db),
+            lock,
+        })
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.is_read_only() {
+            Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Storage is opened read-only",
+            ))
         } else {
-            Ok(Self(Arc::new(sled::open(&path).chain(|| {
+            Ok(())
+        }
+    }
+
+    /// Opens tree of given keyspace if it exists
+    fn existing_tree(&self, keyspace: &[u8]) -> Result<Option<Tree>> {
+        if !self
+            .db
+            .tree_names()
+            .iter()
+            .any(|name| name.as_ref() == keyspace)
+        {
+            return Ok(None);
+        }
+
+        self.db.open_tree(keyspace).map(Some).chain(|| {
+            (
+                ErrorKind::StorageError,
+                format!(
+                    "Unable to open sled storage tree for keyspace: {}",
+                    String::from_utf8_lossy(keyspace)
+                ),
+            )
+        })
+    }
+
+    /// Applies given write operations in a single transaction (which is aborted before operation
+    /// with index `abort_at`, so that rollback can be tested)
+    fn write_transaction(&self, ops: &[StorageOp], abort_at: Option<usize>) -> Result<()> {
+        self.check_writable()?;
+
+        // index of tree (in `trees`) of each operation
+        let mut keyspaces = Vec::<&[u8]>::new();
+        let mut tree_indices = Vec::with_capacity(ops.len());
+        for op in ops.iter() {
+            let (keyspace, _) = op.record();
+            let index = match keyspaces.iter().position(|opened| *opened == keyspace) {
+                Some(index) => index,
+                None => {
+                    keyspaces.push(keyspace);
+                    keyspaces.len() - 1
+                }
+            };
+            tree_indices.push(index);
+        }
+
+        let trees = keyspaces
+            .iter()
+            .map(|keyspace| {
+                self.db.open_tree(keyspace).chain(|| {
+                    (
+                        ErrorKind::StorageError,
+                        format!(
+                            "Unable to open sled storage tree for keyspace: {}",
+                            String::from_utf8_lossy(keyspace)
+                        ),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        trees
+            .as_slice()
+            .transaction(|trees| {
+                for (i, (op, index)) in ops.iter().zip(tree_indices.iter()).enumerate() {
+                    if abort_at == Some(i) {
+                        return Err(ConflictableTransactionError::Abort(
+                            sled::Error::Unsupported("Batch of records is aborted".to_owned()),
+                        ));
+                    }
+                    match op {
+                        StorageOp::Set { key, value, .. } => {
+                            trees[*index].insert(key.as_slice(), value.as_slice())?;
+                        }
+                        StorageOp::Delete { key, .. } => {
+                            trees[*index].remove(key.as_slice())?;
+                        }
+                    }
+                }
+                Ok::<_, ConflictableTransactionError<sled::Error>>(())
+            })
+            .chain(|| (ErrorKind::StorageError, "Unable to write batch of records"))
+    }
+}
+
+fn open_db<P: AsRef<Path>>(path: P) -> Result<Db> {
+    if cfg!(test) {
+        Config::default()
+            .path(&path)
+            .temporary(true)
+            .open()
+            .chain(|| {
                 (
                     ErrorKind::InitializationError,
                     format!(
@@ -39,14 +171,135 @@ impl SledStorage {
                         path.as_ref().display()
                     ),
                 )
-            })?)))
+            })
+    } else {
+        sled::open(&path).chain(|| {
+            (
+                ErrorKind::InitializationError,
+                format!(
+                    "Unable to initialize sled storage at path: {}",
+                    path.as_ref().display()
+                ),
+            )
+        })
+    }
+}
+
+/// Opens a temporary copy of storage at given path (which is removed when it is dropped)
+///
+/// Directory is copied again until none of its files changes while it is copied, so that the
+/// copy is the state of storage at a single point in time (which sled recovers as after a crash).
+fn open_snapshot(path: &Path) -> Result<Db> {
+    static SNAPSHOTS: AtomicUsize = AtomicUsize::new(0);
+
+    let snapshot_path = std::env::temp_dir().join(format!(
+        "sled-snapshot-{}-{}",
+        process::id(),
+        SNAPSHOTS.fetch_add(1, Ordering::SeqCst)
+    ));
+
+    let mut consistent = false;
+    for attempt in 0..SNAPSHOT_ATTEMPTS {
+        if attempt > 0 {
+            thread::sleep(SNAPSHOT_RETRY_DELAY);
+        }
+        let _ = fs::remove_dir_all(&snapshot_path);
+
+        let copied = dir_state(path).and_then(|before| {
+            copy_dir(path, &snapshot_path)?;
+            Ok(before == dir_state(path)?)
+        });
+        match copied {
+            Ok(true) => {
+                consistent = true;
+                break;
+            }
+            Ok(false) => {}
+            Err(err) => {
+                let _ = fs::remove_dir_all(&snapshot_path);
+                return Err(err).chain(|| {
+                    (
+                        ErrorKind::InitializationError,
+                        format!(
+                            "Unable to take snapshot of sled storage at path: {}",
+                            path.display()
+                        ),
+                    )
+                });
+            }
+        }
+    }
+
+    if !consistent {
+        let _ = fs::remove_dir_all(&snapshot_path);
+        return Err(Error::new(
+            ErrorKind::StorageError,
+            format!(
+                "Unable to take snapshot of sled storage at path: {} (it is being written)",
+                path.display()
+            ),
+        ));
+    }
+
+    Config::default()
+        .path(&snapshot_path)
+        .temporary(true)
+        .open()
+        .chain(|| {
+            (
+                ErrorKind::InitializationError,
+                format!(
+                    "Unable to initialize snapshot of sled storage at path: {}",
+                    path.display()
+                ),
+            )
+        })
+}
+
+/// Returns path, length and modification time of all files in given directory (except lock file)
+fn dir_state(dir: &Path) -> std::io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+    let mut state = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name() == LOCK_FILE_NAME {
+            continue;
+        }
+
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            state.extend(dir_state(&entry.path())?);
+        } else {
+            state.push((entry.path(), metadata.len(), metadata.modified()?));
+        }
+    }
+    state.sort();
+    Ok(state)
+}
+
+/// Copies contents of directory `from` (except lock file) to directory `to`
+fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == LOCK_FILE_NAME {
+            continue;
+        }
+
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
         }
     }
+    Ok(())
 }
 
 impl Storage for SledStorage {
     fn clear<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<()> {
-        let tree = self.0.open_tree(keyspace.as_ref().to_vec()).chain(|| {
+        self.check_writable()?;
+
+        let tree = self.db.open_tree(keyspace.as_ref().to_vec()).chain(|| {
             (
                 ErrorKind::StorageError,
                 format!(
@@ -69,7 +322,7 @@ impl Storage for SledStorage {
     }
 
     fn get<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<Option<Vec<u8>>> {
-        let tree = self.0.open_tree(keyspace.as_ref().to_vec()).chain(|| {
+        let tree = self.db.open_tree(keyspace.as_ref().to_vec()).chain(|| {
             (
                 ErrorKind::StorageError,
                 format!(
@@ -100,7 +353,9 @@ impl Storage for SledStorage {
         key: K,
         value: Vec<u8>,
     ) -> Result<Option<Vec<u8>>> {
-        let tree = self.0.open_tree(keyspace.as_ref().to_vec()).chain(|| {
+        self.check_writable()?;
+
+        let tree = self.db.open_tree(keyspace.as_ref().to_vec()).chain(|| {
             (
                 ErrorKind::StorageError,
                 format!(
@@ -130,7 +385,9 @@ impl Storage for SledStorage {
         keyspace: S,
         key: K,
     ) -> Result<Option<Vec<u8>>> {
-        let tree = self.0.open_tree(keyspace.as_ref().to_vec()).chain(|| {
+        self.check_writable()?;
+
+        let tree = self.db.open_tree(keyspace.as_ref().to_vec()).chain(|| {
             (
                 ErrorKind::StorageError,
                 format!(
@@ -160,12 +417,14 @@ impl Storage for SledStorage {
         K: AsRef<[u8]>,
         F: Fn(Option<&[u8]>) -> Result<Option<Vec<u8>>>,
     {
+        self.check_writable()?;
+
         let mut current = self.get(&keyspace, &key)?;
 
         loop {
             let tmp = current.as_ref().map(AsRef::as_ref);
             let next = f(tmp)?;
-            let tree = self.0.open_tree(keyspace.as_ref().to_vec()).chain(|| {
+            let tree = self.db.open_tree(keyspace.as_ref().to_vec()).chain(|| {
                 (
                     ErrorKind::StorageError,
                     format!(
@@ -192,7 +451,7 @@ impl Storage for SledStorage {
     }
 
     fn keys<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<Vec<Vec<u8>>> {
-        let tree = self.0.open_tree(keyspace.as_ref().to_vec()).chain(|| {
+        let tree = self.db.open_tree(keyspace.as_ref().to_vec()).chain(|| {
             (
                 ErrorKind::StorageError,
                 format!(
@@ -220,7 +479,7 @@ impl Storage for SledStorage {
     }
 
     fn contains_key<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<bool> {
-        let tree = self.0.open_tree(keyspace.as_ref().to_vec()).chain(|| {
+        let tree = self.db.open_tree(keyspace.as_ref().to_vec()).chain(|| {
             (
                 ErrorKind::StorageError,
                 format!(
@@ -242,20 +501,72 @@ impl Storage for SledStorage {
         })?)
     }
 
+    fn iter_prefix<S: AsRef<[u8]>, P: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        prefix: P,
+    ) -> Result<RecordIter<'_>> {
+        // opening a missing tree would create it
+        let tree = match self.existing_tree(keyspace.as_ref())? {
+            Some(tree) => tree,
+            None => return Ok(Box::new(std::iter::empty())),
+        };
+
+        let keyspace = String::from_utf8_lossy(keyspace.as_ref()).into_owned();
+        Ok(Box::new(tree.scan_prefix(prefix).map(move |record| {
+            let (key, value) = record.chain(|| {
+                (
+                    ErrorKind::StorageError,
+                    format!("Unable to retrieve records of keyspace: {}", keyspace),
+                )
+            })?;
+            Ok((key.to_vec(), value.to_vec()))
+        })))
+    }
+
     fn keyspaces(&self) -> Result<Vec<Vec<u8>>> {
-        let mut result = Vec::with_capacity(self.0.tree_names().len());
-        for name in self.0.tree_names().iter() {
+        let mut result = Vec::with_capacity(self.db.tree_names().len());
+        for name in self.db.tree_names().iter() {
             let v: Vec<u8> = name.iter().copied().collect();
             result.push(v);
         }
         Ok(result)
     }
+
+    #[inline]
+    fn is_read_only(&self) -> bool {
+        !self.lock.as_ref().map_or(false, |lock| lock.is_exclusive())
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.db
+            .flush()
+            .chain(|| (ErrorKind::StorageError, "Unable to flush sled storage"))?;
+        Ok(())
+    }
+
+    fn write_batch(&self, ops: Vec<StorageOp>) -> Result<()> {
+        self.write_transaction(&ops, None)
+    }
+
+    /// Deletes all given records in a single transaction, so that they are either all deleted or
+    /// none of them is
+    ///
+    /// Sled never overwrites records in place (new values are appended to its log), so values are
+    /// not overwritten with zeros before they are deleted.
+    fn wipe<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, records: &[(S, K)]) -> Result<()> {
+        let ops = records
+            .iter()
+            .map(|(keyspace, key)| StorageOp::delete(keyspace, key))
+            .collect::<Vec<_>>();
+        self.write_transaction(&ops, None)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::SledStorage;
-    use crate::Storage;
+    use crate::{ErrorKind, Storage, StorageOp};
 
     #[test]
     fn check_flow() {
@@ -322,4 +633,104 @@ mod tests {
             "More than two keyspaces present"
         );
     }
+
+    #[test]
+    fn check_write_batch() {
+        let storage =
+            SledStorage::new("./storage-batch-test").expect("Unable to start sled storage");
+        storage.set("a", "key", vec![1]).unwrap();
+        storage.set("b", "key", vec![2]).unwrap();
+
+        let ops = vec![
+            StorageOp::set("a", "key", vec![3]),
+            StorageOp::set("c", "key", vec![4]),
+            StorageOp::delete("b", "key"),
+        ];
+
+        // batch aborted after some of its operations leaves nothing behind
+        for abort_at in 1..ops.len() {
+            assert_eq!(
+                ErrorKind::StorageError,
+                storage
+                    .write_transaction(&ops, Some(abort_at))
+                    .unwrap_err()
+                    .kind()
+            );
+            assert_eq!(Some(vec![1]), storage.get("a", "key").unwrap());
+            assert_eq!(Some(vec![2]), storage.get("b", "key").unwrap());
+            assert!(!storage.contains_key("c", "key").unwrap());
+        }
+
+        storage.write_batch(ops).unwrap();
+
+        assert_eq!(Some(vec![3]), storage.get("a", "key").unwrap());
+        assert_eq!(Some(vec![4]), storage.get("c", "key").unwrap());
+        assert!(!storage.contains_key("b", "key").unwrap());
+
+        std::fs::remove_dir_all("./storage-batch-test").unwrap();
+    }
+
+    #[test]
+    fn check_wipe() {
+        let storage =
+            SledStorage::new("./storage-wipe-test").expect("Unable to start sled storage");
+        storage.set("a", "key", vec![1]).unwrap();
+        storage.set("a", "other", vec![2]).unwrap();
+        storage.set("b", "key", vec![3]).unwrap();
+
+        storage
+            .wipe(&[("a", "key"), ("b", "key"), ("b", "missing")])
+            .unwrap();
+
+        assert_eq!(vec![b"other".to_vec()], storage.keys("a").unwrap());
+        assert!(storage.keys("b").unwrap().is_empty());
+
+        let _ = std::fs::remove_dir_all("./storage-wipe-test");
+    }
+
+    #[test]
+    fn check_lock() {
+        let path = "./storage-lock-sled-test";
+        let storage = SledStorage::new(path).unwrap();
+        storage.set("keyspace", "key", vec![1]).unwrap();
+        storage.db.flush().unwrap();
+        let error = SledStorage::new(path).err().unwrap();
+        assert_eq!(ErrorKind::StorageError, error.kind());
+
+        // storage is readable while it is opened for writing
+        let read_only = SledStorage::read_only(path).unwrap();
+        assert!(read_only.is_read_only());
+        assert_eq!(Some(vec![1]), read_only.get("keyspace", "key").unwrap());
+        storage.set("keyspace", "key", vec![2]).unwrap();
+        assert_eq!(Some(vec![2]), storage.get("keyspace", "key").unwrap());
+        assert_eq!(Some(vec![1]), read_only.get("keyspace", "key").unwrap());
+
+        let permission_denied = |result: crate::Result<_>| {
+            result.map(|_| ()).unwrap_err().kind() == ErrorKind::PermissionDenied
+        };
+        assert!(permission_denied(read_only.set("keyspace", "key", vec![1])));
+        assert!(permission_denied(read_only.delete("keyspace", "key")));
+        assert!(permission_denied(read_only.clear("keyspace")));
+        assert!(permission_denied(
+            read_only.write_batch(vec![StorageOp::set("keyspace", "key", vec![1])])
+        ));
+
+        drop(read_only);
+        drop(storage);
+
+        // storage which is not opened for writing is locked for reading
+        let read_only = SledStorage::read_only(path).unwrap();
+        assert_eq!(
+            ErrorKind::StorageError,
+            SledStorage::new(path).err().unwrap().kind()
+        );
+        // sled database is opened already, so a snapshot is read
+        let another_read_only = SledStorage::read_only(path).unwrap();
+        assert!(another_read_only.is_read_only());
+        assert!(another_read_only.get("keyspace", "key").is_ok());
+
+        drop(another_read_only);
+        drop(read_only);
+        let _ = std::fs::remove_dir_all(path);
+    }
 }
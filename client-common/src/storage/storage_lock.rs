@@ -0,0 +1,177 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::process;
+
+use fs2::FileExt;
+
+use crate::{Error, ErrorKind, Result, ResultExt};
+
+/// Name of lock file in storage directory
+pub(crate) const LOCK_FILE_NAME: &str = "client.lock";
+
+/// Lock of on-disk storage directory, so that it is never written by two processes at once
+///
+/// Storage is locked exclusively for writing and shared for reading. Lock is held on an open
+/// handle of lock file, so it is released by the OS when locking process exits (even if it
+/// crashes). Lock file only records id of process locking it for writing (for error messages).
+#[derive(Debug)]
+pub(crate) struct StorageLock {
+    file: File,
+    exclusive: bool,
+}
+
+impl StorageLock {
+    /// Locks storage in given directory for writing
+    pub fn acquire(dir: &Path) -> Result<Self> {
+        let mut file = open_lock_file(dir)?;
+
+        if let Err(err) = file.try_lock_exclusive() {
+            if !is_contended(&err) {
+                return Err(err).chain(|| {
+                    (
+                        ErrorKind::StorageError,
+                        format!("Unable to lock storage: {}", dir.display()),
+                    )
+                });
+            }
+
+            let holder = lock_holder(&mut file)
+                .map(|pid| format!("process {}", pid))
+                .unwrap_or_else(|| "another process".to_owned());
+            return Err(Error::new(
+                ErrorKind::StorageError,
+                format!(
+                    "Storage ({}) is locked by {}: close other clients using it or open it read-only",
+                    dir.display(),
+                    holder
+                ),
+            ));
+        }
+
+        file.set_len(0)
+            .and_then(|_| write!(file, "{}", process::id()))
+            .and_then(|_| file.flush())
+            .chain(|| {
+                (
+                    ErrorKind::StorageError,
+                    format!("Unable to write lock file of storage: {}", dir.display()),
+                )
+            })?;
+        Ok(StorageLock {
+            file,
+            exclusive: true,
+        })
+    }
+
+    /// Locks storage in given directory for reading, so that it cannot be locked for writing until
+    /// lock is dropped (`None` if it is locked for writing by another lock)
+    pub fn acquire_shared(dir: &Path) -> Result<Option<Self>> {
+        let file = open_lock_file(dir)?;
+
+        match file.try_lock_shared() {
+            Ok(()) => Ok(Some(StorageLock {
+                file,
+                exclusive: false,
+            })),
+            Err(err) if is_contended(&err) => Ok(None),
+            Err(err) => Err(err).chain(|| {
+                (
+                    ErrorKind::StorageError,
+                    format!("Unable to lock storage: {}", dir.display()),
+                )
+            }),
+        }
+    }
+
+    /// Returns `true` if storage is locked for writing
+    #[inline]
+    pub fn is_exclusive(&self) -> bool {
+        self.exclusive
+    }
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        // lock file is kept, removing it would let another process lock a new file while the
+        // removed one is still locked
+        if self.exclusive {
+            let _ = self.file.set_len(0);
+        }
+        let _ = self.file.unlock();
+    }
+}
+
+fn open_lock_file(dir: &Path) -> Result<File> {
+    fs::create_dir_all(dir).chain(|| {
+        (
+            ErrorKind::StorageError,
+            format!("Unable to create storage directory: {}", dir.display()),
+        )
+    })?;
+
+    let path = dir.join(LOCK_FILE_NAME);
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .open(&path)
+        .chain(|| {
+            (
+                ErrorKind::StorageError,
+                format!("Unable to open lock file: {}", path.display()),
+            )
+        })
+}
+
+fn is_contended(err: &std::io::Error) -> bool {
+    err.raw_os_error() == fs2::lock_contended_error().raw_os_error()
+}
+
+/// Returns id of process holding lock of given lock file
+fn lock_holder(file: &mut File) -> Option<u32> {
+    let mut pid = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut pid).ok()?;
+    pid.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_lock() {
+        let dir = Path::new("./storage-lock-test");
+        let lock = StorageLock::acquire(dir).unwrap();
+        assert!(StorageLock::acquire_shared(dir).unwrap().is_none());
+
+        let error = StorageLock::acquire(dir).unwrap_err();
+        assert_eq!(ErrorKind::StorageError, error.kind());
+        assert!(error
+            .message()
+            .contains(&format!("process {}", process::id())));
+
+        drop(lock);
+        let lock = StorageLock::acquire(dir).unwrap();
+        drop(lock);
+
+        // storage locked for reading can be locked for reading again, but not for writing
+        let shared = StorageLock::acquire_shared(dir).unwrap().unwrap();
+        let another_shared = StorageLock::acquire_shared(dir).unwrap().unwrap();
+        assert_eq!(
+            ErrorKind::StorageError,
+            StorageLock::acquire(dir).unwrap_err().kind()
+        );
+        drop(shared);
+        drop(another_shared);
+
+        // lock file left by a process which exited without unlocking does not lock storage
+        fs::write(dir.join(LOCK_FILE_NAME), u32::max_value().to_string()).unwrap();
+        let mut lock = StorageLock::acquire(dir).unwrap();
+        assert_eq!(Some(process::id()), lock_holder(&mut lock.file));
+        drop(lock);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
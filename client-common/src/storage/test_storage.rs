@@ -0,0 +1,175 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::{write_ops, MemoryStorage, Storage, StorageOp};
+use crate::{Error, ErrorKind, Result};
+
+/// Memory storage for tests, counting reads and writes and failing writes on demand
+///
+/// A batch is written atomically (counted as a single write) unless storage is created with
+/// `with_non_atomic_batches`.
+#[derive(Debug, Clone, Default)]
+pub struct TestStorage {
+    storage: MemoryStorage,
+    reads: Arc<AtomicUsize>,
+    writes: Arc<AtomicUsize>,
+    failure: Arc<Mutex<Option<WriteFailure>>>,
+    non_atomic_batches: bool,
+    read_only: bool,
+}
+
+/// Injected failure of writes
+#[derive(Debug, Clone, Copy)]
+struct WriteFailure {
+    remaining_writes: usize,
+    once: bool,
+}
+
+impl TestStorage {
+    /// Writes batches operation by operation (as default implementation of
+    /// `Storage::write_batch` does)
+    pub fn with_non_atomic_batches(mut self) -> Self {
+        self.non_atomic_batches = true;
+        self
+    }
+
+    /// Refuses all writes (as storage opened read-only does)
+    pub fn with_read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Fails all writes after given number of writes, as if the process died (`None` to stop
+    /// failing)
+    pub fn fail_after(&self, writes: Option<usize>) {
+        *self.failure.lock().unwrap() = writes.map(|remaining_writes| WriteFailure {
+            remaining_writes,
+            once: false,
+        });
+    }
+
+    /// Fails a single write after given number of writes
+    pub fn fail_once_after(&self, writes: usize) {
+        *self.failure.lock().unwrap() = Some(WriteFailure {
+            remaining_writes: writes,
+            once: true,
+        });
+    }
+
+    /// Returns number of reads since last call
+    pub fn take_reads(&self) -> usize {
+        self.reads.swap(0, Ordering::SeqCst)
+    }
+
+    /// Returns number of writes since last call
+    pub fn take_writes(&self) -> usize {
+        self.writes.swap(0, Ordering::SeqCst)
+    }
+
+    fn read(&self) {
+        self.reads.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn write(&self) -> Result<()> {
+        if self.read_only {
+            return Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Storage is opened read-only",
+            ));
+        }
+        self.writes.fetch_add(1, Ordering::SeqCst);
+
+        let mut failure = self.failure.lock().unwrap();
+        match failure.as_mut() {
+            Some(WriteFailure {
+                remaining_writes: 0,
+                once,
+            }) => {
+                if *once {
+                    *failure = None;
+                }
+                Err(Error::new(
+                    ErrorKind::StorageError,
+                    "Injected storage failure",
+                ))
+            }
+            Some(WriteFailure {
+                remaining_writes, ..
+            }) => {
+                *remaining_writes -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Storage for TestStorage {
+    fn clear<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<()> {
+        self.write()?;
+        self.storage.clear(keyspace)
+    }
+
+    fn get<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<Option<Vec<u8>>> {
+        self.read();
+        self.storage.get(keyspace, key)
+    }
+
+    fn set<S: AsRef<[u8]>, K: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        key: K,
+        value: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>> {
+        self.write()?;
+        self.storage.set(keyspace, key, value)
+    }
+
+    fn delete<S: AsRef<[u8]>, K: AsRef<[u8]>>(
+        &self,
+        keyspace: S,
+        key: K,
+    ) -> Result<Option<Vec<u8>>> {
+        self.write()?;
+        self.storage.delete(keyspace, key)
+    }
+
+    fn fetch_and_update<S, K, F>(&self, keyspace: S, key: K, f: F) -> Result<Option<Vec<u8>>>
+    where
+        S: AsRef<[u8]>,
+        K: AsRef<[u8]>,
+        F: Fn(Option<&[u8]>) -> Result<Option<Vec<u8>>>,
+    {
+        self.read();
+        self.write()?;
+        self.storage.fetch_and_update(keyspace, key, f)
+    }
+
+    fn keys<S: AsRef<[u8]>>(&self, keyspace: S) -> Result<Vec<Vec<u8>>> {
+        self.read();
+        self.storage.keys(keyspace)
+    }
+
+    fn contains_key<S: AsRef<[u8]>, K: AsRef<[u8]>>(&self, keyspace: S, key: K) -> Result<bool> {
+        self.read();
+        self.storage.contains_key(keyspace, key)
+    }
+
+    fn keyspaces(&self) -> Result<Vec<Vec<u8>>> {
+        self.read();
+        self.storage.keyspaces()
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn write_batch(&self, ops: Vec<StorageOp>) -> Result<()> {
+        if self.non_atomic_batches {
+            write_ops(self, ops)
+        } else {
+            self.write()?;
+            self.storage.write_batch(ops)
+        }
+    }
+}
@@ -33,12 +33,22 @@ pub trait Client: Send + Sync + Clone {
         heights: T,
     ) -> Result<(Vec<Block>, lite::TrustedState)>;
 
+    /// Makes `validators` call to tendermint
+    fn validators(&self, height: u64) -> Result<ValidatorsResponse>;
+
     /// Makes `broadcast_tx_sync` call to tendermint
     fn broadcast_transaction(&self, transaction: &[u8]) -> Result<BroadcastTxResponse>;
 
     /// Makes `abci_query` call to tendermint
     fn query(&self, path: &str, data: &[u8]) -> Result<AbciQuery>;
 
+    /// Makes batched `abci_query` call to tendermint (responses are returned without checking
+    /// their codes, so that a failed query does not fail the whole batch)
+    fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+        &self,
+        queries: T,
+    ) -> Result<Vec<AbciQuery>>;
+
     /// Match batch state `abci_query` call to tendermint
     fn query_state_batch<T: Iterator<Item = u64>>(&self, heights: T) -> Result<Vec<ChainState>>;
 }
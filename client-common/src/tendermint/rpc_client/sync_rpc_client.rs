@@ -228,12 +228,14 @@ impl Client for SyncRpcClient {
     fn broadcast_transaction(&self, transaction: &[u8]) -> Result<BroadcastTxResponse> {
         let params = vec![json!(transaction)];
         let rsp = self.call::<BroadcastTxResponse>("broadcast_tx_sync", params)?;
+        check_broadcast_response(&rsp)?;
+        Ok(rsp)
+    }
 
-        if rsp.code.is_err() {
-            Err(Error::new(ErrorKind::TendermintRpcError, rsp.log.as_ref()))
-        } else {
-            Ok(rsp)
-        }
+    /// Makes `validators` call to tendermint
+    fn validators(&self, height: u64) -> Result<ValidatorsResponse> {
+        let params = vec![json!(height.to_string()), json!("0"), json!("100")];
+        self.call("validators", params)
     }
 
     /// Makes `abci_query` call to tendermint
@@ -258,6 +260,28 @@ impl Client for SyncRpcClient {
         Ok(result)
     }
 
+    /// Makes batched `abci_query` call to tendermint
+    fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+        &self,
+        queries: T,
+    ) -> Result<Vec<AbciQuery>> {
+        let params = queries
+            .map(|(path, data)| {
+                (
+                    "abci_query",
+                    vec![
+                        json!(path),
+                        json!(hex::encode(data)),
+                        json!(null),
+                        json!(null),
+                    ],
+                )
+            })
+            .collect::<Vec<(&'static str, Vec<Value>)>>();
+        let rsps = self.call_batch::<AbciQueryResponse>(params)?;
+        Ok(rsps.into_iter().map(|rsp| rsp.response).collect())
+    }
+
     /// Match batch state `abci_query` call to tendermint
     fn query_state_batch<T: Iterator<Item = u64>>(&self, heights: T) -> Result<Vec<ChainState>> {
         let params: Vec<(&str, Vec<Value>)> = heights
@@ -1,5 +1,6 @@
 //! Structures used in Tendermint RPC
 mod block_results;
+mod broadcast_error;
 
 use parity_scale_codec::Decode;
 use serde::{Deserialize, Serialize};
@@ -10,7 +11,10 @@ use chain_core::tx::data::TxId;
 use chain_core::tx::fee::LinearFee;
 use chain_core::tx::{TxAux, TxEnclaveAux, TxPublicAux};
 
-pub use self::block_results::BlockResults;
+pub use self::block_results::{BlockResults, PunishmentEvent, PunishmentKind, RewardRecord};
+pub use self::broadcast_error::{
+    broadcast_error, check_broadcast_response, interpret_broadcast_response, BroadcastError,
+};
 pub use tendermint::rpc::endpoint::{
     abci_query::AbciQuery, abci_query::Response as AbciQueryResponse,
     block::Response as BlockResponse, block_results::Response as BlockResultsResponse,
@@ -98,6 +102,9 @@ impl BlockExt for Block {
 pub trait GenesisExt {
     /// get fee policy
     fn fee_policy(&self) -> LinearFee;
+
+    /// get chain hex id (last two hex digits of chain id)
+    fn chain_hex_id(&self) -> Result<u8>;
 }
 
 impl GenesisExt for Genesis {
@@ -108,6 +115,22 @@ impl GenesisExt for Genesis {
             .network_params
             .initial_fee_policy
     }
+
+    fn chain_hex_id(&self) -> Result<u8> {
+        let chain_id = self.chain_id.to_string();
+        chain_id
+            .len()
+            .checked_sub(2)
+            .and_then(|start| chain_id.get(start..))
+            .and_then(|hex_id| hex::decode(hex_id).ok())
+            .map(|hex_id| hex_id[0])
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!("Chain id does not end with two hex digits: {}", chain_id),
+                )
+            })
+    }
 }
 
 /// crypto-chain specific methods.
@@ -1,9 +1,10 @@
 #![allow(missing_docs)]
 use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::str::{from_utf8, FromStr};
 
-use chain_core::common::{TendermintEventKey, TendermintEventType};
+use chain_core::common::{TendermintEventKey, TendermintEventType, Timespec};
 use chain_core::init::{coin::Coin, MAX_COIN_DECIMALS};
 use chain_core::state::account::StakedStateAddress;
 use chain_core::tx::data::TxId;
@@ -14,6 +15,46 @@ use crate::tendermint::types::BlockResultsResponse;
 use crate::{Error, ErrorKind, Result, ResultExt};
 use tendermint::abci::tag::Tag as Attribute;
 
+/// Kind of punishment of a staking account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PunishmentKind {
+    /// Staking account is jailed
+    Jail,
+    /// Bonded and/or unbonded amount of staking account is slashed
+    Slash,
+}
+
+/// Punishment (jail or slash) of a staking account found in block results
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PunishmentEvent {
+    /// Height of block in which punishment is applied
+    pub height: u64,
+    /// Kind of punishment
+    pub kind: PunishmentKind,
+    /// Total slashed amount (bonded and unbonded; `None` for jail)
+    pub amount: Option<Coin>,
+    /// Time until which staking account is jailed (`None` for slash)
+    pub jailed_until: Option<Timespec>,
+    /// Reason of punishment reported by chain (e.g. `NonLive`, `ByzantineFault`)
+    pub reason: Option<String>,
+}
+
+/// Rewards distributed to a staking account in a block
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RewardRecord {
+    /// Height of block in which rewards are distributed
+    pub height: u64,
+    /// Total rewards added to bonded amount of staking account
+    pub amount: Coin,
+}
+
+/// Entry of `staking_diff` attribute of staking change events
+#[derive(Debug, Deserialize)]
+struct StakingDiffEntry {
+    key: String,
+    value: serde_json::Value,
+}
+
 pub trait BlockResults {
     /// Returns transaction ids and the corresponding fees in block results
     fn fees(&self) -> Result<IndexMap<TxId, Fee>>;
@@ -24,78 +65,171 @@ pub trait BlockResults {
 
     /// Returns block filter in block results
     fn block_filter(&self) -> Result<BlockFilter>;
-}
-
-impl BlockResults for BlockResultsResponse {
-    fn fees(&self) -> Result<IndexMap<TxId, Fee>> {
-        match &self.txs_results {
-            None => Ok(IndexMap::default()),
-            Some(deliver_txs) => {
-                let mut fees: IndexMap<TxId, Fee> = IndexMap::new();
-                for deliver_txs in deliver_txs.iter() {
-                    for event in deliver_txs.events.iter() {
-                        if event.type_str == TendermintEventType::ValidTransactions.to_string() {
-                            let tx_id = find_tx_id_from_event_attributes(&event.attributes)?;
-                            let fee = find_fee_from_event_attributes(&event.attributes)?;
-                            if let (Some(tx_id), Some(fee)) = (tx_id, fee) {
-                                fees.insert(tx_id, fee);
-                            }
-                        }
-                    }
-                }
 
-                Ok(fees)
+    /// Returns jail and slash events of given staking address in begin/end block events
+    fn punishment_events(
+        &self,
+        target_account: &StakedStateAddress,
+    ) -> Result<Vec<PunishmentEvent>> {
+        let height = self.height.value();
+        let mut punishment_events = Vec::new();
+
+        for attributes in staking_change_attributes(self, target_account)? {
+            if let Some(punishment_event) = parse_punishment_event(height, attributes)? {
+                punishment_events.push(punishment_event);
             }
         }
+
+        Ok(punishment_events)
     }
 
-    fn contains_account(&self, target_account: &StakedStateAddress) -> Result<bool> {
-        match &self.txs_results {
-            None => Ok(false),
-            Some(deliver_tx) => {
-                for deliver_tx in deliver_tx.iter() {
-                    for event in deliver_tx.events.iter() {
-                        if event.type_str != TendermintEventType::StakingChange.to_string() {
-                            continue;
-                        }
-                        match find_staking_address_from_event_attributes(&event.attributes)? {
-                            None => continue,
-                            Some(address) => {
-                                if address == *target_account {
-                                    return Ok(true);
-                                }
-                            }
-                        }
-                    }
-                }
-                Ok(false)
+    fn reward_record(&self, target_account: &StakedStateAddress) -> Result<Option<RewardRecord>> {
+        let mut reward_record: Option<RewardRecord> = None;
+
+        for attributes in staking_change_attributes(self, target_account)? {
+            let op_type =
+                find_text_from_event_attributes(attributes, TendermintEventKey::StakingOpType)?;
+            if op_type.as_ref().map(String::as_str) != Some("reward") {
+                continue;
             }
+
+            let amount = parse_staking_diff(attributes)?.amount;
+            reward_record = Some(match reward_record {
+                None => RewardRecord {
+                    height: self.height.value(),
+                    amount,
+                },
+                Some(record) => RewardRecord {
+                    amount: (record.amount + amount).chain(|| {
+                        (
+                            ErrorKind::DeserializationError,
+                            "Rewards in block results exceed maximum coin value",
+                        )
+                    })?,
+                    ..record
+                },
+            });
         }
+
+        Ok(reward_record)
     }
+}
 
-    fn block_filter(&self) -> Result<BlockFilter> {
-        match &self.end_block_events {
-            None => Ok(BlockFilter::default()),
-            Some(events) => {
-                for event in events.iter() {
-                    if event.type_str == TendermintEventType::BlockFilter.to_string() {
-                        let attribute = &event.attributes[0];
-                        let decoded = base64::decode(attribute.value.as_ref()).chain(|| {
-                            (
-                                ErrorKind::DeserializationError,
-                                "Unable to decode base64 bytes of block filter in block results",
-                            )
-                        })?;
-
-                        return Ok(BlockFilter::try_from(decoded.as_slice()).map_err(
-                            |message| Error::new(ErrorKind::DeserializationError, message),
-                        )?);
-                    }
-                }
-                Ok(BlockFilter::default())
+/// Returns attributes of staking change events (in begin/end block events) of given staking
+/// address
+fn staking_change_attributes<'a>(
+    block_results: &'a BlockResultsResponse,
+    target_account: &StakedStateAddress,
+) -> Result<Vec<&'a [Attribute]>> {
+    let events = block_results
+        .begin_block_events
+        .iter()
+        .chain(block_results.end_block_events.iter())
+        .flatten();
+
+    let mut staking_change_attributes = Vec::new();
+    for event in events {
+        if event.type_str != TendermintEventType::StakingChange.to_string() {
+            continue;
+        }
+        if find_staking_address_from_event_attributes(&event.attributes)?.as_ref()
+            == Some(target_account)
+        {
+            staking_change_attributes.push(event.attributes.as_slice());
+        }
+    }
+
+    Ok(staking_change_attributes)
+}
+
+/// Amount and jail time changes decoded from `staking_diff` attribute
+struct StakingDiffSummary {
+    /// Sum of absolute bonded and unbonded amount changes
+    amount: Coin,
+    /// Time until which staking account is jailed
+    jailed_until: Option<Timespec>,
+}
+
+fn parse_staking_diff(attributes: &[Attribute]) -> Result<StakingDiffSummary> {
+    let diff = find_text_from_event_attributes(attributes, TendermintEventKey::StakingDiff)?
+        .chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Staking diff of staking change event not found in block results",
+            )
+        })?;
+    let diff: Vec<StakingDiffEntry> = serde_json::from_str(&diff).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Unable to deserialize staking diff in block results",
+        )
+    })?;
+
+    let mut summary = StakingDiffSummary {
+        amount: Coin::zero(),
+        jailed_until: None,
+    };
+    for entry in diff {
+        match entry.key.as_str() {
+            "Bonded" | "Unbonded" => {
+                let change = entry
+                    .value
+                    .as_str()
+                    .and_then(|value| Coin::from_str(value.trim_start_matches('-')).ok())
+                    .chain(|| {
+                        (
+                            ErrorKind::DeserializationError,
+                            "Invalid amount in staking diff of block results",
+                        )
+                    })?;
+                summary.amount = (summary.amount + change).chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        "Amount in staking diff of block results exceeds maximum coin value",
+                    )
+                })?;
             }
+            "JailedUntil" => {
+                summary.jailed_until = Some(entry.value.as_u64().chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        "Invalid jailed until time in staking diff of block results",
+                    )
+                })?);
+            }
+            _ => {}
         }
     }
+
+    Ok(summary)
+}
+
+fn parse_punishment_event(
+    height: u64,
+    attributes: &[Attribute],
+) -> Result<Option<PunishmentEvent>> {
+    let kind = match find_text_from_event_attributes(attributes, TendermintEventKey::StakingOpType)?
+        .as_ref()
+        .map(String::as_str)
+    {
+        Some("jail") => PunishmentKind::Jail,
+        Some("slash") => PunishmentKind::Slash,
+        _ => return Ok(None),
+    };
+
+    let reason = find_text_from_event_attributes(attributes, TendermintEventKey::StakingOpReason)?;
+    let diff = parse_staking_diff(attributes)?;
+
+    Ok(Some(PunishmentEvent {
+        height,
+        kind,
+        amount: match kind {
+            PunishmentKind::Jail => None,
+            PunishmentKind::Slash => Some(diff.amount),
+        },
+        jailed_until: diff.jailed_until,
+        reason,
+    }))
 }
 
 fn find_event_attribute_by_key(
@@ -117,6 +251,34 @@ fn find_event_attribute_by_key(
     Ok(None)
 }
 
+fn find_text_from_event_attributes(
+    attributes: &[Attribute],
+    target_key: TendermintEventKey,
+) -> Result<Option<String>> {
+    match find_event_attribute_by_key(attributes, target_key)? {
+        None => Ok(None),
+        Some(attribute) => {
+            let raw_text = base64::decode(attribute.value.as_ref()).chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!(
+                        "Unable to decode base64 bytes of {} in block results",
+                        target_key
+                    ),
+                )
+            })?;
+            let text = String::from_utf8(raw_text).chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!("Invalid {} text encoding", target_key),
+                )
+            })?;
+
+            Ok(Some(text))
+        }
+    }
+}
+
 fn find_fee_from_event_attributes(attributes: &[Attribute]) -> Result<Option<Fee>> {
     let maybe_attribute = find_event_attribute_by_key(attributes, TendermintEventKey::Fee)?;
     match maybe_attribute {
@@ -272,6 +434,117 @@ mod tests {
         }
     }
 
+    mod block_results_punishment_events {
+        use super::*;
+
+        #[test]
+        fn should_return_jail_and_slash_events_of_the_target_account() {
+            let response_str = r#"{"height": "42", "txs_results": null, "begin_block_events": [{"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgzMzUwMmVkMzlkMGM0ZTIwNDRmYjM3ZmRjZDUxNjE0OTNmNTkwMGMz"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "c2xhc2g="}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiQm9uZGVkIiwidmFsdWUiOiItNTAwMDAwMDAwIn0seyJrZXkiOiJVbmJvbmRlZCIsInZhbHVlIjoiLTEwMDAwMDAwMCJ9XQ=="}, {"key": "c3Rha2luZ19vcHJlYXNvbg==", "value": "Tm9uTGl2ZQ=="}]}, {"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgzMzUwMmVkMzlkMGM0ZTIwNDRmYjM3ZmRjZDUxNjE0OTNmNTkwMGMz"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "amFpbA=="}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiSmFpbGVkVW50aWwiLCJ2YWx1ZSI6MTU4NzA3MTAxNH1d"}, {"key": "c3Rha2luZ19vcHJlYXNvbg==", "value": "Tm9uTGl2ZQ=="}]}, {"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgwZTdjMDQ1MTEwYjhkYmYyOTc2NTA0NzM4MDg5ODkxOWM1Y2I1NmY0"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "amFpbA=="}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiSmFpbGVkVW50aWwiLCJ2YWx1ZSI6MTU4NzA3MTAxNH1d"}, {"key": "c3Rha2luZ19vcHJlYXNvbg==", "value": "Qnl6YW50aW5lRmF1bHQ="}]}], "end_block_events": null, "validator_updates": null, "consensus_param_updates": null}"#;
+            let block_results: BlockResultsResponse =
+                serde_json::from_str(response_str).expect("invalid response str");
+            let target_account = StakedStateAddress::from(
+                RedeemAddress::from_str("0x33502ed39d0c4e2044fb37fdcd5161493f5900c3").unwrap(),
+            );
+
+            assert_eq!(
+                vec![
+                    PunishmentEvent {
+                        height: 42,
+                        kind: PunishmentKind::Slash,
+                        amount: Some(Coin::new(600_000_000).unwrap()),
+                        jailed_until: None,
+                        reason: Some("NonLive".to_owned()),
+                    },
+                    PunishmentEvent {
+                        height: 42,
+                        kind: PunishmentKind::Jail,
+                        amount: None,
+                        jailed_until: Some(1587071014),
+                        reason: Some("NonLive".to_owned()),
+                    },
+                ],
+                block_results.punishment_events(&target_account).unwrap()
+            );
+        }
+
+        #[test]
+        fn should_return_empty_when_block_results_has_no_punishment_of_the_target_account() {
+            let response_str = r#"{"height": "42", "txs_results": null, "begin_block_events": [{"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgzMzUwMmVkMzlkMGM0ZTIwNDRmYjM3ZmRjZDUxNjE0OTNmNTkwMGMz"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "c2xhc2g="}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiQm9uZGVkIiwidmFsdWUiOiItNTAwMDAwMDAwIn0seyJrZXkiOiJVbmJvbmRlZCIsInZhbHVlIjoiLTEwMDAwMDAwMCJ9XQ=="}, {"key": "c3Rha2luZ19vcHJlYXNvbg==", "value": "Tm9uTGl2ZQ=="}]}, {"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgzMzUwMmVkMzlkMGM0ZTIwNDRmYjM3ZmRjZDUxNjE0OTNmNTkwMGMz"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "amFpbA=="}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiSmFpbGVkVW50aWwiLCJ2YWx1ZSI6MTU4NzA3MTAxNH1d"}, {"key": "c3Rha2luZ19vcHJlYXNvbg==", "value": "Tm9uTGl2ZQ=="}]}, {"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgwZTdjMDQ1MTEwYjhkYmYyOTc2NTA0NzM4MDg5ODkxOWM1Y2I1NmY0"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "amFpbA=="}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiSmFpbGVkVW50aWwiLCJ2YWx1ZSI6MTU4NzA3MTAxNH1d"}, {"key": "c3Rha2luZ19vcHJlYXNvbg==", "value": "Qnl6YW50aW5lRmF1bHQ="}]}], "end_block_events": null, "validator_updates": null, "consensus_param_updates": null}"#;
+            let block_results: BlockResultsResponse =
+                serde_json::from_str(response_str).expect("invalid response str");
+            let target_account = StakedStateAddress::from(
+                RedeemAddress::from_str("0xe4a2a719ca933d3f79a8506aa96cefde3405b0a7").unwrap(),
+            );
+
+            assert!(block_results
+                .punishment_events(&target_account)
+                .unwrap()
+                .is_empty());
+            assert!(BlockResultsResponse::default()
+                .punishment_events(&target_account)
+                .unwrap()
+                .is_empty());
+        }
+
+        #[test]
+        fn should_return_err_when_staking_diff_is_invalid() {
+            let response_str = r#"{"height": "42", "txs_results": null, "begin_block_events": [{"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgzMzUwMmVkMzlkMGM0ZTIwNDRmYjM3ZmRjZDUxNjE0OTNmNTkwMGMz"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "c2xhc2g="}, {"key": "c3Rha2luZ19kaWZm", "value": "aW52YWxpZA=="}]}], "end_block_events": null, "validator_updates": null, "consensus_param_updates": null}"#;
+            let block_results: BlockResultsResponse =
+                serde_json::from_str(response_str).expect("invalid response str");
+            let target_account = StakedStateAddress::from(
+                RedeemAddress::from_str("0x33502ed39d0c4e2044fb37fdcd5161493f5900c3").unwrap(),
+            );
+
+            assert_eq!(
+                ErrorKind::DeserializationError,
+                block_results
+                    .punishment_events(&target_account)
+                    .unwrap_err()
+                    .kind()
+            );
+        }
+    }
+
+    mod block_results_reward_record {
+        use super::*;
+
+        #[test]
+        fn should_return_rewards_of_the_target_account() {
+            let response_str = r#"{"height": "120", "txs_results": null, "begin_block_events": [{"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgzMzUwMmVkMzlkMGM0ZTIwNDRmYjM3ZmRjZDUxNjE0OTNmNTkwMGMz"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "cmV3YXJk"}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiQm9uZGVkIiwidmFsdWUiOiIxMjM0NTY3OCJ9XQ=="}]}, {"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgwZTdjMDQ1MTEwYjhkYmYyOTc2NTA0NzM4MDg5ODkxOWM1Y2I1NmY0"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "cmV3YXJk"}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiQm9uZGVkIiwidmFsdWUiOiIxMDAifV0="}]}, {"type": "reward", "attributes": [{"key": "bWludGVk", "value": "MTIzNDU3Nzg="}]}], "end_block_events": null, "validator_updates": null, "consensus_param_updates": null}"#;
+            let block_results: BlockResultsResponse =
+                serde_json::from_str(response_str).expect("invalid response str");
+            let target_account = StakedStateAddress::from(
+                RedeemAddress::from_str("0x33502ed39d0c4e2044fb37fdcd5161493f5900c3").unwrap(),
+            );
+
+            assert_eq!(
+                Some(RewardRecord {
+                    height: 120,
+                    amount: Coin::new(12_345_678).unwrap(),
+                }),
+                block_results.reward_record(&target_account).unwrap()
+            );
+        }
+
+        #[test]
+        fn should_return_none_when_block_results_has_no_reward_of_the_target_account() {
+            let response_str = r#"{"height": "120", "txs_results": null, "begin_block_events": [{"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgzMzUwMmVkMzlkMGM0ZTIwNDRmYjM3ZmRjZDUxNjE0OTNmNTkwMGMz"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "cmV3YXJk"}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiQm9uZGVkIiwidmFsdWUiOiIxMjM0NTY3OCJ9XQ=="}]}, {"type": "staking_change", "attributes": [{"key": "c3Rha2luZ19hZGRyZXNz", "value": "MHgwZTdjMDQ1MTEwYjhkYmYyOTc2NTA0NzM4MDg5ODkxOWM1Y2I1NmY0"}, {"key": "c3Rha2luZ19vcHR5cGU=", "value": "cmV3YXJk"}, {"key": "c3Rha2luZ19kaWZm", "value": "W3sia2V5IjoiQm9uZGVkIiwidmFsdWUiOiIxMDAifV0="}]}, {"type": "reward", "attributes": [{"key": "bWludGVk", "value": "MTIzNDU3Nzg="}]}], "end_block_events": null, "validator_updates": null, "consensus_param_updates": null}"#;
+            let block_results: BlockResultsResponse =
+                serde_json::from_str(response_str).expect("invalid response str");
+            let target_account = StakedStateAddress::from(
+                RedeemAddress::from_str("0xe4a2a719ca933d3f79a8506aa96cefde3405b0a7").unwrap(),
+            );
+
+            assert_eq!(None, block_results.reward_record(&target_account).unwrap());
+            assert_eq!(
+                None,
+                BlockResultsResponse::default()
+                    .reward_record(&target_account)
+                    .unwrap()
+            );
+        }
+    }
+
     #[test]
     fn check_ids() {
         let response_str = r#"{"height": "38", "txs_results": [{"code": 0, "data": null, "log": "", "info": "", "gasWanted": "0", "gasUsed": "0", "events": [{"type": "valid_txs", "attributes": [{"key": "ZmVl", "value": "MC4wMDAwMDYzMg=="}, {"key": "dHhpZA==", "value": "MGNkMDc4MDI3NzBiOGMwYzBkNjgwYTFiYTU5ODg1OGZlZDFhZDQ4MDY1MTgzMDUyMjgxOWQ0MzBiNzVlYTBlMQ=="}]}], "codespace": ""}], "begin_block_events": null, "end_block_events": [{"type": "block_filter", "attributes": [{"key": "ZXRoYmxvb20=", "value": "AAAAAAAAAAAAAAAAAgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAEAAAAAAAAAAAAAAAAAAAAAAAAAAAAACAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAgAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABAAAAAA=="}]}], "validator_updates": null, "consensus_param_updates": null}"#;
@@ -0,0 +1,221 @@
+//! Interpretation of rejections in responses of `broadcast_tx_sync`
+use std::fmt;
+
+use chain_core::tx::data::TxId;
+
+use crate::tendermint::types::BroadcastTxResponse;
+use crate::{Error, ErrorKind, Result};
+
+/// Logs of chain-abci (and tendermint) identifying each kind of rejection
+const NONCE_MISMATCH_LOGS: [&str; 3] = [
+    "tx nonce don't match staking state",
+    "nonce value don't match",
+    "incorrect transaction count for account operation",
+];
+const INSUFFICIENT_FEE_LOGS: [&str; 1] =
+    ["transaction input output coin (plus fee) sums don't match"];
+const ALREADY_IN_MEMPOOL_LOGS: [&str; 1] = ["already exists in cache"];
+const INVALID_WITNESS_LOGS: [&str; 3] = [
+    "verify staking witness failed",
+    "staking witness and address don't match",
+    "signature verification or public key recovery failed",
+];
+const WRONG_CHAIN_HEX_ID_LOGS: [&str; 2] = ["wrong chain_hex_id", "chain hex ID does not match"];
+const ACCOUNT_JAILED_LOGS: [&str; 2] = ["account is jailed", "the staking address is jailed"];
+
+/// Reason of rejection of a broadcasted transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BroadcastError {
+    /// Nonce of staking transaction does not match nonce of staking account
+    NonceMismatch,
+    /// Inputs do not cover outputs plus fee
+    InsufficientFee,
+    /// Transaction is already in mempool of the node
+    AlreadyInMempool,
+    /// Witness does not verify or is not signed by expected address
+    InvalidWitness,
+    /// Chain hex id of transaction does not match the network
+    WrongChainHexId,
+    /// Staking account is jailed
+    AccountJailed,
+    /// Rejection which is not recognized (raw code and log of response)
+    AppError {
+        /// Response code
+        code: u32,
+        /// Response log
+        log: String,
+    },
+}
+
+impl fmt::Display for BroadcastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BroadcastError::NonceMismatch => write!(f, "Transaction nonce is outdated"),
+            BroadcastError::InsufficientFee => write!(f, "Transaction fee is insufficient"),
+            BroadcastError::AlreadyInMempool => write!(f, "Transaction is already in mempool"),
+            BroadcastError::InvalidWitness => write!(f, "Transaction witness is invalid"),
+            BroadcastError::WrongChainHexId => write!(f, "Transaction is built for another chain"),
+            BroadcastError::AccountJailed => write!(f, "Staking account is jailed"),
+            BroadcastError::AppError { code, log } => {
+                write!(f, "Transaction rejected with code {}: {}", code, log)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BroadcastError {}
+
+/// Returns hash of broadcasted transaction reported by tendermint, or the reason of its rejection
+pub fn interpret_broadcast_response(
+    response: &BroadcastTxResponse,
+) -> std::result::Result<TxId, BroadcastError> {
+    if !response.code.is_err() {
+        let mut hash = TxId::default();
+        hash.copy_from_slice(response.hash.as_bytes());
+        return Ok(hash);
+    }
+
+    let log = response.log.as_ref();
+    let matches = |logs: &[&str]| logs.iter().any(|pattern| log.contains(pattern));
+
+    let error = if matches(&NONCE_MISMATCH_LOGS) {
+        BroadcastError::NonceMismatch
+    } else if matches(&INSUFFICIENT_FEE_LOGS) {
+        BroadcastError::InsufficientFee
+    } else if matches(&ALREADY_IN_MEMPOOL_LOGS) {
+        BroadcastError::AlreadyInMempool
+    } else if matches(&INVALID_WITNESS_LOGS) {
+        BroadcastError::InvalidWitness
+    } else if matches(&WRONG_CHAIN_HEX_ID_LOGS) {
+        BroadcastError::WrongChainHexId
+    } else if matches(&ACCOUNT_JAILED_LOGS) {
+        BroadcastError::AccountJailed
+    } else {
+        BroadcastError::AppError {
+            code: response.code.value(),
+            log: log.to_owned(),
+        }
+    };
+
+    Err(error)
+}
+
+/// Returns an error (with `BroadcastError` as its source) if broadcasted transaction is rejected
+pub fn check_broadcast_response(response: &BroadcastTxResponse) -> Result<TxId> {
+    interpret_broadcast_response(response).map_err(|error| {
+        Error::new_with_source(
+            ErrorKind::TendermintRpcError,
+            format!(
+                "Transaction rejected with code {}: {}",
+                response.code.value(),
+                response.log.as_ref()
+            ),
+            Box::new(error),
+        )
+    })
+}
+
+/// Returns reason of rejection if error is returned by `check_broadcast_response`
+pub fn broadcast_error(error: &Error) -> Option<&BroadcastError> {
+    std::error::Error::source(error).and_then(|source| source.downcast_ref::<BroadcastError>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(code: u32, log: &str) -> BroadcastTxResponse {
+        serde_json::from_str(&format!(
+            r#"{{"code":{},"data":"","log":"{}","hash":"{}"}}"#,
+            code,
+            log,
+            "AB".repeat(32)
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn check_interpret_broadcast_response() {
+        assert_eq!(
+            Ok([0xab; 32]),
+            interpret_broadcast_response(&response(0, ""))
+        );
+
+        let fixtures = vec![
+            (
+                "public tx process failed: tx nonce don't match staking state",
+                BroadcastError::NonceMismatch,
+            ),
+            (
+                "public tx process failed: unbond tx process failed: nonce value don't match",
+                BroadcastError::NonceMismatch,
+            ),
+            (
+                "enclave tx validation failed: incorrect transaction count for account operation",
+                BroadcastError::NonceMismatch,
+            ),
+            (
+                "enclave tx validation failed: transaction input output coin (plus fee) sums don't match",
+                BroadcastError::InsufficientFee,
+            ),
+            ("tx already exists in cache", BroadcastError::AlreadyInMempool),
+            (
+                "public tx process failed: staking witness and address don't match",
+                BroadcastError::InvalidWitness,
+            ),
+            (
+                "public tx process failed: verify staking witness failed: malformed signature",
+                BroadcastError::InvalidWitness,
+            ),
+            (
+                "public tx process failed: public tx wrong chain_hex_id",
+                BroadcastError::WrongChainHexId,
+            ),
+            (
+                "public tx process failed: unbond tx process failed: the staking address is jailed",
+                BroadcastError::AccountJailed,
+            ),
+        ];
+        for (log, expected) in fixtures {
+            assert_eq!(
+                Err(expected),
+                interpret_broadcast_response(&response(1, log))
+            );
+        }
+
+        assert_eq!(
+            Err(BroadcastError::AppError {
+                code: 2,
+                log: "public tx process failed: the value of tx is zero".to_owned(),
+            }),
+            interpret_broadcast_response(&response(
+                2,
+                "public tx process failed: the value of tx is zero"
+            ))
+        );
+    }
+
+    #[test]
+    fn check_broadcast_response_error() {
+        assert!(check_broadcast_response(&response(0, "")).is_ok());
+
+        let error = check_broadcast_response(&response(
+            1,
+            "public tx process failed: tx nonce don't match staking state",
+        ))
+        .unwrap_err();
+        assert_eq!(ErrorKind::TendermintRpcError, error.kind());
+        assert_eq!(
+            "Transaction rejected with code 1: public tx process failed: tx nonce don't match staking state",
+            error.message()
+        );
+        assert_eq!(
+            Some(&BroadcastError::NonceMismatch),
+            broadcast_error(&error)
+        );
+        assert_eq!(
+            None,
+            broadcast_error(&Error::new(ErrorKind::TendermintRpcError, "other error"))
+        );
+    }
+}
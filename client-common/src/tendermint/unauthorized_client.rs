@@ -52,6 +52,17 @@ impl Client for UnauthorizedClient {
         Err(ErrorKind::PermissionDenied.into())
     }
 
+    fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
+    fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+        &self,
+        _queries: T,
+    ) -> Result<Vec<AbciQuery>> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
     fn query_state_batch<T: Iterator<Item = u64>>(&self, _heights: T) -> Result<Vec<ChainState>> {
         Err(ErrorKind::PermissionDenied.into())
     }
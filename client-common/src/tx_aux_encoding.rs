@@ -0,0 +1,194 @@
+//! Text encoding of built transactions, used to move them between machines (e.g. build on one
+//! machine and broadcast from another)
+use parity_scale_codec::{Decode, Encode};
+
+use chain_core::tx::TxAux;
+
+use crate::{Error, ErrorKind, Result, ResultExt};
+
+const HEX_PREFIX: &str = "0x";
+
+/// Encodes transaction as hex string with `0x` prefix
+pub fn encode_tx_aux(tx_aux: &TxAux) -> String {
+    format!("{}{}", HEX_PREFIX, hex::encode(tx_aux.encode()))
+}
+
+/// Encodes transaction as base64 string
+pub fn encode_tx_aux_base64(tx_aux: &TxAux) -> String {
+    base64::encode(&tx_aux.encode())
+}
+
+/// Decodes transaction encoded with `encode_tx_aux` (hex with `0x` prefix) or
+/// `encode_tx_aux_base64` (base64)
+pub fn decode_tx_aux(encoded: &str) -> Result<TxAux> {
+    let encoded = encoded.trim();
+    let bytes = if encoded.starts_with(HEX_PREFIX) {
+        hex::decode(&encoded[HEX_PREFIX.len()..]).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to decode hex encoded transaction",
+            )
+        })?
+    } else {
+        base64::decode(encoded).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to decode base64 encoded transaction",
+            )
+        })?
+    };
+
+    let mut input = bytes.as_slice();
+    let tx_aux = TxAux::decode(&mut input).map_err(|e| {
+        Error::new(
+            ErrorKind::DeserializationError,
+            format!(
+                "Unable to decode transaction at byte offset {}: {}",
+                bytes.len() - input.len(),
+                e.what()
+            ),
+        )
+    })?;
+
+    if !input.is_empty() {
+        return Err(Error::new(
+            ErrorKind::DeserializationError,
+            format!(
+                "Unexpected {} trailing bytes after transaction at byte offset {}",
+                input.len(),
+                bytes.len() - input.len()
+            ),
+        ));
+    }
+
+    Ok(tx_aux)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use secp256k1::recovery::{RecoverableSignature, RecoveryId};
+
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::init::coin::Coin;
+    use chain_core::state::account::{
+        ConfidentialInit, CouncilNode, DepositBondTx, StakedStateAddress, StakedStateOpAttributes,
+        StakedStateOpWitness, UnbondTx, UnjailTx,
+    };
+    use chain_core::state::tendermint::{BlockHeight, TendermintValidatorPubKey};
+    use chain_core::state::validator::NodeJoinRequestTx;
+    use chain_core::tx::data::input::TxoPointer;
+    use chain_core::tx::{TxEnclaveAux, TxObfuscated, TxPublicAux};
+
+    fn address() -> StakedStateAddress {
+        StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]))
+    }
+
+    fn witness() -> StakedStateOpWitness {
+        StakedStateOpWitness::new(
+            RecoverableSignature::from_compact(&[1; 64], RecoveryId::from_i32(1).unwrap()).unwrap(),
+        )
+    }
+
+    fn payload() -> TxObfuscated {
+        TxObfuscated {
+            key_from: BlockHeight::genesis(),
+            init_vector: [2; 12],
+            txpayload: vec![3; 48],
+            txid: [4; 32],
+        }
+    }
+
+    fn transactions() -> Vec<TxAux> {
+        let attributes = StakedStateOpAttributes::new(171);
+
+        vec![
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                inputs: vec![TxoPointer::new([5; 32], 0)],
+                no_of_outputs: 2,
+                payload: payload(),
+            }),
+            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx {
+                tx: DepositBondTx::new(
+                    vec![TxoPointer::new([5; 32], 1)],
+                    address(),
+                    attributes.clone(),
+                ),
+                payload: payload(),
+            }),
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                no_of_outputs: 1,
+                witness: witness(),
+                payload: payload(),
+            }),
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(
+                UnbondTx::new(address(), 1, Coin::unit(), attributes.clone()),
+                witness(),
+            )),
+            TxAux::PublicTx(TxPublicAux::UnjailTx(
+                UnjailTx {
+                    nonce: 2,
+                    address: address(),
+                    attributes: attributes.clone(),
+                },
+                witness(),
+            )),
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(
+                NodeJoinRequestTx {
+                    nonce: 3,
+                    address: address(),
+                    attributes,
+                    node_meta: CouncilNode::new(
+                        TendermintValidatorPubKey::Ed25519([6; 32]),
+                        ConfidentialInit {
+                            cert: b"FIXME".to_vec(),
+                        },
+                    ),
+                },
+                witness(),
+            )),
+        ]
+    }
+
+    #[test]
+    fn check_tx_aux_encoding_round_trip() {
+        for tx_aux in transactions() {
+            let hex_encoded = encode_tx_aux(&tx_aux);
+            assert!(hex_encoded.starts_with("0x"));
+            assert_eq!(tx_aux, decode_tx_aux(&hex_encoded).unwrap());
+
+            let base64_encoded = encode_tx_aux_base64(&tx_aux);
+            assert_eq!(tx_aux, decode_tx_aux(&base64_encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn check_tx_aux_decoding_errors() {
+        let tx_aux = transactions().pop().unwrap();
+        let bytes = tx_aux.encode();
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        let error = decode_tx_aux(&format!("0x{}", hex::encode(&trailing))).unwrap_err();
+        assert_eq!(ErrorKind::DeserializationError, error.kind());
+        assert_eq!(
+            format!(
+                "Unexpected 1 trailing bytes after transaction at byte offset {}",
+                bytes.len()
+            ),
+            error.message()
+        );
+
+        // unknown variant of `TxAux` is detected after reading the first byte
+        let error = decode_tx_aux(&format!("0x05{}", hex::encode(&bytes[1..]))).unwrap_err();
+        assert_eq!(ErrorKind::DeserializationError, error.kind());
+        assert!(error
+            .message()
+            .starts_with("Unable to decode transaction at byte offset 1"));
+
+        let error = decode_tx_aux("0xzz").unwrap_err();
+        assert_eq!(ErrorKind::DeserializationError, error.kind());
+        assert!(decode_tx_aux("not base64!").is_err());
+    }
+}
@@ -0,0 +1,436 @@
+//! Strategies for selecting unspent transactions covering an amount and fee of a transaction
+use chain_core::init::coin::{sum_coins, Coin};
+use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::data::output::TxOut;
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+use crate::UnspentTransactions;
+
+/// Maximum number of steps of branch and bound search before falling back to `LargestFirst`
+const BRANCH_AND_BOUND_MAX_TRIES: usize = 100_000;
+
+/// Unspent transactions selected to cover an amount
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    /// Selected unspent transactions
+    pub inputs: Vec<(TxoPointer, TxOut)>,
+    /// Fee of transaction (including any excess which is not worth a change output)
+    pub fee: Coin,
+    /// Amount of change output (zero if transaction has no change output)
+    pub change: Coin,
+}
+
+/// Strategy for selecting unspent transactions covering a target amount plus fee
+///
+/// `fee_estimator` returns fee of a transaction with given number of inputs and given number of
+/// change outputs (`0` or `1`).
+pub trait CoinSelectionStrategy: Send + Sync {
+    /// Selects unspent transactions covering `target` plus fee of resulting transaction
+    fn select(
+        &self,
+        utxos: &UnspentTransactions,
+        target: Coin,
+        fee_estimator: &dyn Fn(usize, usize) -> Coin,
+    ) -> Result<Selection>;
+}
+
+/// Selects unspent transactions with highest value first
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LargestFirst;
+
+impl CoinSelectionStrategy for LargestFirst {
+    fn select(
+        &self,
+        utxos: &UnspentTransactions,
+        target: Coin,
+        fee_estimator: &dyn Fn(usize, usize) -> Coin,
+    ) -> Result<Selection> {
+        let mut sorted = utxos.iter().collect::<Vec<_>>();
+        sorted.sort_by(|(_, a), (_, b)| a.value.cmp(&b.value).reverse());
+        select_in_order(sorted, target, fee_estimator)
+    }
+}
+
+/// Selects unspent transactions with lowest value first (consolidates small outputs)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SmallestFirst;
+
+impl CoinSelectionStrategy for SmallestFirst {
+    fn select(
+        &self,
+        utxos: &UnspentTransactions,
+        target: Coin,
+        fee_estimator: &dyn Fn(usize, usize) -> Coin,
+    ) -> Result<Selection> {
+        let mut sorted = utxos.iter().collect::<Vec<_>>();
+        sorted.sort_by(|(_, a), (_, b)| a.value.cmp(&b.value));
+        select_in_order(sorted, target, fee_estimator)
+    }
+}
+
+/// Searches for unspent transactions covering target and fee without a change output (excess
+/// lower than fee of a change output is paid as fee), falling back to `LargestFirst`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BranchAndBound;
+
+impl CoinSelectionStrategy for BranchAndBound {
+    fn select(
+        &self,
+        utxos: &UnspentTransactions,
+        target: Coin,
+        fee_estimator: &dyn Fn(usize, usize) -> Coin,
+    ) -> Result<Selection> {
+        let mut sorted = utxos.iter().collect::<Vec<_>>();
+        sorted.sort_by(|(_, a), (_, b)| a.value.cmp(&b.value).reverse());
+
+        let values = sorted
+            .iter()
+            .map(|(_, output)| u64::from(output.value))
+            .collect::<Vec<_>>();
+
+        match search_without_change(&values, u64::from(target), fee_estimator) {
+            Some(indices) => {
+                let inputs = indices
+                    .into_iter()
+                    .map(|index| sorted[index].clone())
+                    .collect::<Vec<_>>();
+                let total = inputs
+                    .iter()
+                    .map(|(_, output)| u64::from(output.value))
+                    .sum::<u64>();
+                let fee = Coin::new(total - u64::from(target)).chain(|| {
+                    (
+                        ErrorKind::IllegalInput,
+                        "Total amount of selected UTXOs exceeds maximum allowed value",
+                    )
+                })?;
+
+                Ok(Selection {
+                    inputs,
+                    fee,
+                    change: Coin::zero(),
+                })
+            }
+            None => LargestFirst.select(utxos, target, fee_estimator),
+        }
+    }
+}
+
+/// Selects all unspent transactions, returning everything above target and fee in change output
+/// (e.g. to consolidate them into one output)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SweepAll;
+
+impl CoinSelectionStrategy for SweepAll {
+    fn select(
+        &self,
+        utxos: &UnspentTransactions,
+        target: Coin,
+        fee_estimator: &dyn Fn(usize, usize) -> Coin,
+    ) -> Result<Selection> {
+        let total = sum_coins(utxos.iter().map(|(_, output)| output.value)).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Total amount of selected UTXOs exceeds maximum allowed value",
+            )
+        })?;
+        let fee = fee_estimator(utxos.len(), 1);
+
+        let change = (target + fee)
+            .and_then(|required| total - required)
+            .ok()
+            .filter(|change| *change > Coin::zero())
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Fee {} of spending {} unspent transactions exceeds their value {} (minus {} sent)",
+                        fee,
+                        utxos.len(),
+                        total,
+                        target
+                    ),
+                )
+            })?;
+
+        Ok(Selection {
+            inputs: utxos.to_vec(),
+            fee,
+            change,
+        })
+    }
+}
+
+/// Selects unspent transactions in given order until they cover target and fee
+pub(crate) fn select_in_order(
+    utxos: Vec<&(TxoPointer, TxOut)>,
+    target: Coin,
+    fee_estimator: &dyn Fn(usize, usize) -> Coin,
+) -> Result<Selection> {
+    let mut inputs = Vec::new();
+    let mut total = Coin::zero();
+
+    for utxo in utxos {
+        inputs.push(utxo.clone());
+        total = (total + utxo.1.value).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Total amount of selected UTXOs exceeds maximum allowed value",
+            )
+        })?;
+
+        if let Some((fee, change)) = settle(total, target, inputs.len(), fee_estimator) {
+            return Ok(Selection {
+                inputs,
+                fee,
+                change,
+            });
+        }
+    }
+
+    Err(Error::new(
+        ErrorKind::InsufficientBalance,
+        format!("Insufficient balance to cover {} and fee", target),
+    ))
+}
+
+/// Returns fee and change of a transaction spending `total` in given number of inputs, if it
+/// covers target and fee
+fn settle(
+    total: Coin,
+    target: Coin,
+    no_of_inputs: usize,
+    fee_estimator: &dyn Fn(usize, usize) -> Coin,
+) -> Option<(Coin, Coin)> {
+    let fee_with_change = fee_estimator(no_of_inputs, 1);
+    if let Ok(required) = target + fee_with_change {
+        if total > required {
+            return Some((fee_with_change, (total - required).ok()?));
+        }
+    }
+
+    // excess which does not pay for a change output is paid as fee
+    let required = (target + fee_estimator(no_of_inputs, 0)).ok()?;
+    if total >= required {
+        Some(((total - target).ok()?, Coin::zero()))
+    } else {
+        None
+    }
+}
+
+/// Returns indices of values (sorted in decreasing order) whose sum covers target and fee
+/// without a change output, with excess not higher than the fee of a change output
+fn search_without_change(
+    values: &[u64],
+    target: u64,
+    fee_estimator: &dyn Fn(usize, usize) -> Coin,
+) -> Option<Vec<usize>> {
+    // sums of values after each index (to stop branches which cannot reach the target)
+    let mut remaining = vec![0u64; values.len() + 1];
+    for index in (0..values.len()).rev() {
+        remaining[index] = remaining[index + 1].saturating_add(values[index]);
+    }
+
+    let mut selected: Vec<usize> = Vec::new();
+    let mut sum = 0u64;
+    let mut index = 0;
+
+    for _ in 0..BRANCH_AND_BOUND_MAX_TRIES {
+        let fee = u64::from(fee_estimator(selected.len(), 0));
+        let cost_of_change = u64::from(fee_estimator(selected.len(), 1)).saturating_sub(fee);
+
+        let backtrack = match target.checked_add(fee) {
+            None => true,
+            Some(lower) => {
+                if !selected.is_empty() && sum >= lower && sum - lower <= cost_of_change {
+                    return Some(selected);
+                }
+
+                sum > lower.saturating_add(cost_of_change)
+                    || index == values.len()
+                    || sum.saturating_add(remaining[index]) < lower
+            }
+        };
+
+        if backtrack {
+            // continue with branch excluding the last selected value
+            let last = selected.pop()?;
+            sum -= values[last];
+            index = last + 1;
+        } else {
+            selected.push(index);
+            sum += values[index];
+            index += 1;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashSet;
+
+    use rand::{thread_rng, Rng};
+
+    use chain_core::tx::data::address::ExtendedAddr;
+
+    fn fee_estimator(no_of_inputs: usize, no_of_change_outputs: usize) -> Coin {
+        Coin::new(10 * no_of_inputs as u64 + 5 * no_of_change_outputs as u64 + 3).unwrap()
+    }
+
+    fn utxos(values: &[u64]) -> UnspentTransactions {
+        UnspentTransactions::new(
+            values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    (
+                        TxoPointer::new([index as u8; 32], index),
+                        TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(*value).unwrap()),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn strategies() -> Vec<Box<dyn CoinSelectionStrategy>> {
+        vec![
+            Box::new(LargestFirst),
+            Box::new(SmallestFirst),
+            Box::new(BranchAndBound),
+        ]
+    }
+
+    #[test]
+    fn check_selection_order() {
+        let utxos = utxos(&[100, 300, 200]);
+        let target = Coin::new(150).unwrap();
+
+        let selection = LargestFirst.select(&utxos, target, &fee_estimator).unwrap();
+        assert_eq!(vec![utxos[1].clone()], selection.inputs);
+        assert_eq!(Coin::new(18).unwrap(), selection.fee);
+        assert_eq!(Coin::new(132).unwrap(), selection.change);
+
+        let selection = SmallestFirst
+            .select(&utxos, target, &fee_estimator)
+            .unwrap();
+        assert_eq!(vec![utxos[0].clone(), utxos[2].clone()], selection.inputs);
+        assert_eq!(Coin::new(28).unwrap(), selection.fee);
+        assert_eq!(Coin::new(122).unwrap(), selection.change);
+    }
+
+    #[test]
+    fn check_branch_and_bound_avoids_change() {
+        // 100 + 87 covers target and fee of two inputs (23) exactly
+        let utxos = utxos(&[500, 100, 87, 40]);
+        let target = Coin::new(164).unwrap();
+
+        let selection = BranchAndBound
+            .select(&utxos, target, &fee_estimator)
+            .unwrap();
+        assert_eq!(vec![utxos[1].clone(), utxos[2].clone()], selection.inputs);
+        assert_eq!(Coin::new(23).unwrap(), selection.fee);
+        assert_eq!(Coin::zero(), selection.change);
+
+        // there is no such set, so largest value is selected first
+        let selection = BranchAndBound
+            .select(&utxos, Coin::new(400).unwrap(), &fee_estimator)
+            .unwrap();
+        assert_eq!(vec![utxos[0].clone()], selection.inputs);
+        assert_eq!(Coin::new(82).unwrap(), selection.change);
+    }
+
+    #[test]
+    fn check_sweep_all() {
+        let utxos = utxos(&[5, 40, 1, 300]);
+
+        let selection = SweepAll
+            .select(&utxos, Coin::zero(), &fee_estimator)
+            .unwrap();
+        assert_eq!(utxos.to_vec(), selection.inputs);
+        assert_eq!(Coin::new(48).unwrap(), selection.fee);
+        assert_eq!(Coin::new(298).unwrap(), selection.change);
+
+        // fee of spending the small outputs exceeds their value
+        let small = utxos(&[5, 4, 1]);
+        let error = SweepAll
+            .select(&small, Coin::zero(), &fee_estimator)
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error
+            .message()
+            .starts_with("Fee 0.00000038 of spending 3 unspent transactions exceeds their value"));
+    }
+
+    #[test]
+    fn check_insufficient_balance() {
+        let utxos = utxos(&[100, 200]);
+
+        for strategy in strategies() {
+            let error = strategy
+                .select(&utxos, Coin::new(280).unwrap(), &fee_estimator)
+                .unwrap_err();
+            assert_eq!(ErrorKind::InsufficientBalance, error.kind());
+        }
+    }
+
+    #[test]
+    fn check_selection_properties() {
+        let mut rng = thread_rng();
+
+        for _ in 0..200 {
+            let values = (0..rng.gen_range(0, 20))
+                .map(|_| rng.gen_range(1, 1000))
+                .collect::<Vec<u64>>();
+            let available = values.iter().sum::<u64>();
+            let target = Coin::new(rng.gen_range(0, available + 100)).unwrap();
+            let utxos = utxos(&values);
+
+            for strategy in strategies() {
+                match strategy.select(&utxos, target, &fee_estimator) {
+                    Ok(selection) => {
+                        let pointers = selection
+                            .inputs
+                            .iter()
+                            .map(|(pointer, _)| pointer.clone())
+                            .collect::<HashSet<_>>();
+                        assert_eq!(selection.inputs.len(), pointers.len());
+                        assert!(selection.inputs.iter().all(|input| utxos.contains(input)));
+
+                        let total = selection
+                            .inputs
+                            .iter()
+                            .map(|(_, output)| u64::from(output.value))
+                            .sum::<u64>();
+                        assert_eq!(
+                            total,
+                            u64::from(target)
+                                + u64::from(selection.fee)
+                                + u64::from(selection.change)
+                        );
+
+                        let no_of_change_outputs = if selection.change == Coin::zero() {
+                            0
+                        } else {
+                            1
+                        };
+                        assert!(
+                            selection.fee
+                                >= fee_estimator(selection.inputs.len(), no_of_change_outputs)
+                        );
+                    }
+                    Err(error) => {
+                        assert_eq!(ErrorKind::InsufficientBalance, error.kind());
+                        assert!(
+                            available
+                                < u64::from(target) + u64::from(fee_estimator(values.len(), 0))
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
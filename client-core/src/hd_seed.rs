@@ -1,10 +1,12 @@
 //! Hierarchical Deterministic seed implementing BIP39
 use parity_scale_codec::{Decode, Encode};
+use secstr::SecUtf8;
 
 use chain_core::init::network::{get_bip44_coin_type_from_network, Network};
 use client_common::{ErrorKind, PrivateKey, PublicKey, Result, ResultExt};
 
 use crate::hd_wallet::{ChainPath, DefaultKeyChain, ExtendedPrivKey, KeyChain};
+use crate::service::HdPath;
 use crate::Mnemonic;
 
 /// Hierarchical Deterministic seed
@@ -23,6 +25,16 @@ impl From<&Mnemonic> for HDSeed {
 }
 
 impl HDSeed {
+    /// Create HD seed from mnemonic and optional BIP39 passphrase ("25th word")
+    pub fn from_mnemonic(mnemonic: &Mnemonic, passphrase: Option<&SecUtf8>) -> Self {
+        match passphrase {
+            None => HDSeed::from(mnemonic),
+            Some(passphrase) => HDSeed {
+                bytes: mnemonic.seed_with_passphrase(passphrase),
+            },
+        }
+    }
+
     /// Create new HD seed from seed bytes
     #[inline]
     pub fn new(bytes: Vec<u8>) -> Self {
@@ -41,11 +53,19 @@ impl HDSeed {
         network: Network,
         account_index: u32,
         index: u32,
+    ) -> Result<(PublicKey, PrivateKey)> {
+        self.derive_key_pair_at(network, &HdPath::new(account_index, 0, index)?)
+    }
+
+    /// Derive HD wallet at given bip44 key path, and returns the key pair
+    pub fn derive_key_pair_at(
+        &self,
+        network: Network,
+        path: &HdPath,
     ) -> Result<(PublicKey, PrivateKey)> {
         let coin_type = get_bip44_coin_type_from_network(network);
 
-        let chain_path_string = format!("m/44'/{}'/{}'/0/{}", coin_type, account_index, index);
-        let chain_path = ChainPath::from(chain_path_string);
+        let chain_path = ChainPath::from(path.chain_path(coin_type));
         let key_chain = DefaultKeyChain::new(
             ExtendedPrivKey::with_seed(&self.bytes)
                 .chain(|| (ErrorKind::InternalError, "Invalid seed bytes"))?,
@@ -69,7 +89,6 @@ impl HDSeed {
 mod hd_seed_tests {
     use super::*;
     use crate::service::HDAccountType;
-    use secstr::SecUtf8;
 
     #[test]
     fn same_mnemonic_words_should_restore_the_same_hd_seed() {
@@ -87,6 +106,28 @@ mod hd_seed_tests {
         assert_wallet_is_same(&restored_hd_seed_1, &restored_hd_seed_2);
     }
 
+    #[test]
+    fn passphrase_should_be_mixed_into_hd_seed() {
+        // reference vector of BIP39 (https://github.com/trezor/python-mnemonic/blob/master/vectors.json)
+        let mnemonic = Mnemonic::from_secstr(&SecUtf8::from(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon art",
+        ))
+        .expect("should restore from mnemonic words");
+
+        assert_eq!(
+            "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+            hex::encode(HDSeed::from_mnemonic(&mnemonic, Some(&SecUtf8::from("TREZOR"))).as_bytes())
+        );
+        assert_eq!(
+            HDSeed::from(&mnemonic),
+            HDSeed::from_mnemonic(&mnemonic, None)
+        );
+        assert_eq!(
+            HDSeed::from(&mnemonic),
+            HDSeed::from_mnemonic(&mnemonic, Some(&SecUtf8::from("")))
+        );
+    }
+
     mod derive_key_pair {
         use super::*;
 
@@ -123,6 +164,36 @@ mod hd_seed_tests {
             assert_eq!(expected_public_key, public_key.serialize_compressed());
             assert_eq!(expected_private_key, private_key.serialize());
         }
+
+        #[test]
+        fn should_derive_at_explicit_path() {
+            let mnemonic_words = SecUtf8::from("point shiver hurt flight fun online hub antenna engine pave chef fantasy front interest poem accident catch load frequent praise elite pet remove used");
+            let mnemonic = Mnemonic::from_secstr(&mnemonic_words)
+                .expect("should create mnemonic from mnemonic words");
+            let hd_seed = HDSeed::from(&mnemonic);
+
+            assert_eq!(
+                hd_seed
+                    .derive_key_pair(Network::Mainnet, HDAccountType::Transfer.index(), 1)
+                    .expect("should derive key pair"),
+                hd_seed
+                    .derive_key_pair_at(Network::Mainnet, &"0'/0/1".parse().unwrap())
+                    .expect("should derive key pair")
+            );
+
+            let expected_public_key =
+                hex::decode("0231144f872b653baae8c319eb1e779415086702f1b96d0f4d9dcf56e6f04cb8ab")
+                    .expect("should decode from public key hex");
+            let expected_private_key =
+                hex::decode("77b3216caa84d2c2a337c07276b8a26f4065a2b96dd14a10b0e2b8403d817158")
+                    .expect("should decode from private key hex");
+
+            let (public_key, private_key) = hd_seed
+                .derive_key_pair_at(Network::Devnet, &HdPath::new(5, 1, 3).unwrap())
+                .expect("should derive key pair");
+            assert_eq!(expected_public_key, public_key.serialize_compressed());
+            assert_eq!(expected_private_key, private_key.serialize());
+        }
     }
 
     fn assert_wallet_is_same(wallet: &HDSeed, other: &HDSeed) {
@@ -1,5 +1,10 @@
 //! Input selection operations
+use chain_core::init::coin::Coin;
+use client_common::Result;
+
+use crate::coin_selection::{select_in_order, LargestFirst, Selection, SmallestFirst};
 use crate::unspent_transactions::{Operation, Sorter};
+use crate::{CoinSelectionStrategy, UnspentTransactions};
 
 /// Different strategies for input selection
 #[derive(Debug)]
@@ -32,3 +37,24 @@ impl AsRef<[Operation]> for InputSelectionStrategy {
         }
     }
 }
+
+impl CoinSelectionStrategy for InputSelectionStrategy {
+    fn select(
+        &self,
+        utxos: &UnspentTransactions,
+        target: Coin,
+        fee_estimator: &dyn Fn(usize, usize) -> Coin,
+    ) -> Result<Selection> {
+        match self {
+            InputSelectionStrategy::HighestValueFirst => {
+                LargestFirst.select(utxos, target, fee_estimator)
+            }
+            InputSelectionStrategy::LowestValueFirst => {
+                SmallestFirst.select(utxos, target, fee_estimator)
+            }
+            InputSelectionStrategy::Random => {
+                select_in_order(utxos.iter().collect(), target, fee_estimator)
+            }
+        }
+    }
+}
@@ -10,6 +10,7 @@
 //! - Transaction history
 //! - Transaction creation and signing (with automatic unspent transaction selection)
 pub mod cipher;
+pub mod coin_selection;
 pub mod hd_seed;
 pub mod hd_wallet;
 pub mod input_selection;
@@ -26,6 +27,10 @@ pub mod wallet;
 #[doc(inline)]
 pub use crate::cipher::TransactionObfuscation;
 #[doc(inline)]
+pub use crate::coin_selection::{
+    BranchAndBound, CoinSelectionStrategy, LargestFirst, Selection, SmallestFirst, SweepAll,
+};
+#[doc(inline)]
 pub use crate::hd_seed::HDSeed;
 #[doc(inline)]
 pub use crate::input_selection::InputSelectionStrategy;
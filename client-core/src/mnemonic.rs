@@ -74,11 +74,17 @@ impl Mnemonic {
     /// Returns the seed from the mnemonic words as byte slice
     #[inline]
     pub fn seed(&self) -> Vec<u8> {
-        // TODO: advanced/optional recovery" seeding option
-        // give salt as another argument, make default as ""
         Seed::new(&self.0, "").as_bytes().to_vec()
     }
 
+    /// Returns the seed from the mnemonic words and BIP39 passphrase ("25th word") as byte slice
+    #[inline]
+    pub fn seed_with_passphrase(&self, passphrase: &SecUtf8) -> Vec<u8> {
+        Seed::new(&self.0, passphrase.unsecure())
+            .as_bytes()
+            .to_vec()
+    }
+
     // TODO: Implement zeroize for bip39::Mnemonic phrase and entropy
     // Right now only the phrase can be zero out
     /// Take ownership and zeroize
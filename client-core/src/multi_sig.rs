@@ -1,8 +1,12 @@
 //! MultiSig operations support
 mod builder;
+mod psct;
 mod session;
 mod signer;
 
 pub use builder::MultiSigBuilder;
+pub use psct::{
+    merge_psct, CosignerContribution, PartiallySignedInput, PartiallySignedTransfer, PSCT_VERSION,
+};
 pub use session::MultiSigSession;
 use signer::Signer;
@@ -4,7 +4,7 @@ use secp256k1::schnorrsig::SchnorrSignature;
 use chain_core::common::H256;
 use client_common::{ErrorKind, PrivateKey, PublicKey, Result, ResultExt};
 
-use super::MultiSigSession;
+use super::{CosignerContribution, MultiSigSession};
 
 /// MultiSig session builder tailored for Crypto.com chain flow
 ///
@@ -137,6 +137,15 @@ impl MultiSigBuilder {
         self.session.public_keys()
     }
 
+    /// Returns contributions of all signers (sorted by public key) for a partially signed transfer
+    pub fn cosigner_contributions(&self) -> Vec<CosignerContribution> {
+        self.session
+            .signers
+            .iter()
+            .map(CosignerContribution::from)
+            .collect()
+    }
+
     /// Returns true if nonce commitment for given public key is already set,
     /// false otherwise.
     pub fn has_nonce_commitment(&self, public_key: &PublicKey) -> Result<bool> {
@@ -0,0 +1,581 @@
+//! Portable partially signed transfer (PSCT) exchanged between co-signers
+use parity_scale_codec::{Decode, Encode, Input, Output};
+
+use chain_core::common::{Proof, H256};
+use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::output::TxOut;
+use chain_core::tx::data::Tx;
+use chain_core::tx::witness::tree::RawXOnlyPubkey;
+use chain_core::tx::witness::TxInWitness;
+use chain_core::tx::TransactionId;
+use chain_tx_validation::witness::verify_tx_address;
+use client_common::{Error, ErrorKind, PublicKey, Result, ResultExt};
+
+use super::Signer;
+
+/// Version of binary encoding of partially signed transfers
+pub const PSCT_VERSION: u8 = 1;
+
+/// First line of base64-armored partially signed transfer
+const ARMOR_HEADER: &str = "-----BEGIN PARTIALLY SIGNED TRANSFER-----";
+/// Last line of base64-armored partially signed transfer
+const ARMOR_FOOTER: &str = "-----END PARTIALLY SIGNED TRANSFER-----";
+/// Maximum length of a line of base64 in armored text
+const ARMOR_LINE_LENGTH: usize = 64;
+
+/// Data contributed by a co-signer to a multi-sig signing session
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct CosignerContribution {
+    /// Public key of co-signer
+    pub public_key: PublicKey,
+    /// Nonce commitment of co-signer (when available)
+    pub nonce_commitment: Option<H256>,
+    /// Nonce of co-signer (when available)
+    pub nonce: Option<H256>,
+    /// Partial signature of co-signer (when available)
+    pub partial_signature: Option<H256>,
+}
+
+impl From<&Signer> for CosignerContribution {
+    fn from(signer: &Signer) -> Self {
+        CosignerContribution {
+            public_key: signer.public_key.clone(),
+            nonce_commitment: signer.nonce_commitment,
+            nonce: signer.nonce,
+            partial_signature: signer.partial_signature,
+        }
+    }
+}
+
+/// Multi-sig output spent by a partially signed transfer
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub struct PartiallySignedInput {
+    /// Output spent by the input
+    pub output: TxOut,
+    /// Inclusion proof of combined public key of co-signers in multi-sig address of output
+    pub proof: Proof<RawXOnlyPubkey>,
+    /// Witness of the input (once partial signatures of all co-signers are combined)
+    pub witness: Option<TxInWitness>,
+}
+
+/// Unsigned transfer transaction with everything co-signers need to sign it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartiallySignedTransfer {
+    /// Unsigned transfer transaction
+    pub transaction: Tx,
+    /// Spent outputs (in the order of transaction inputs)
+    pub inputs: Vec<PartiallySignedInput>,
+    /// Number of co-signers required to spend the outputs
+    pub required_signers: u16,
+    /// Total number of co-signers of the multi-sig address
+    pub total_signers: u16,
+    /// Contributions of co-signers (sorted by public key)
+    pub cosigners: Vec<CosignerContribution>,
+}
+
+impl Encode for PartiallySignedTransfer {
+    fn encode_to<W: Output>(&self, dest: &mut W) {
+        dest.push_byte(PSCT_VERSION);
+        self.transaction.encode_to(dest);
+        self.inputs.encode_to(dest);
+        self.required_signers.encode_to(dest);
+        self.total_signers.encode_to(dest);
+        self.cosigners.encode_to(dest);
+    }
+}
+
+impl Decode for PartiallySignedTransfer {
+    fn decode<I: Input>(input: &mut I) -> std::result::Result<Self, parity_scale_codec::Error> {
+        if input.read_byte()? != PSCT_VERSION {
+            return Err("Unsupported version of partially signed transfer".into());
+        }
+
+        Ok(PartiallySignedTransfer {
+            transaction: Tx::decode(input)?,
+            inputs: Vec::decode(input)?,
+            required_signers: u16::decode(input)?,
+            total_signers: u16::decode(input)?,
+            cosigners: Vec::decode(input)?,
+        })
+    }
+}
+
+impl PartiallySignedTransfer {
+    /// Decodes and validates a partially signed transfer (trailing bytes are rejected)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut input = bytes;
+        let psct = Self::decode(&mut input).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to decode partially signed transfer",
+            )
+        })?;
+        if !input.is_empty() {
+            return Err(Error::new(
+                ErrorKind::DeserializationError,
+                "Unexpected trailing bytes after partially signed transfer",
+            ));
+        }
+
+        psct.validate()?;
+        Ok(psct)
+    }
+
+    /// Returns base64-armored text encoding
+    pub fn to_armored(&self) -> String {
+        let encoded = base64::encode(&self.encode());
+        let mut lines = vec![ARMOR_HEADER];
+        lines.extend(
+            encoded
+                .as_bytes()
+                .chunks(ARMOR_LINE_LENGTH)
+                .map(|line| std::str::from_utf8(line).expect("base64 is ascii")),
+        );
+        lines.push(ARMOR_FOOTER);
+        lines.join("\n")
+    }
+
+    /// Decodes and validates base64-armored text encoding
+    pub fn from_armored(text: &str) -> Result<Self> {
+        let lines = text
+            .trim()
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>();
+        if lines.len() < 3 || lines[0] != ARMOR_HEADER || lines[lines.len() - 1] != ARMOR_FOOTER {
+            return Err(Error::new(
+                ErrorKind::DeserializationError,
+                "Partially signed transfer is not enclosed in armor header and footer",
+            ));
+        }
+
+        let bytes = base64::decode(&lines[1..lines.len() - 1].concat()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Partially signed transfer is not valid base64",
+            )
+        })?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns contribution of co-signer with given public key
+    pub fn cosigner(&self, public_key: &PublicKey) -> Option<&CosignerContribution> {
+        self.cosigners
+            .iter()
+            .find(|cosigner| &cosigner.public_key == public_key)
+    }
+
+    /// Checks consistency of partially signed transfer (proofs of co-signers, order of signing
+    /// rounds and witnesses against id of embedded transaction)
+    pub fn validate(&self) -> Result<()> {
+        if self.inputs.is_empty() || self.inputs.len() != self.transaction.inputs.len() {
+            return Err(invalid(format!(
+                "Partially signed transfer has {} spent outputs for {} transaction inputs",
+                self.inputs.len(),
+                self.transaction.inputs.len()
+            )));
+        }
+
+        if self.required_signers < 2 || self.required_signers > self.total_signers {
+            return Err(invalid(format!(
+                "Invalid threshold policy: {}-of-{}",
+                self.required_signers, self.total_signers
+            )));
+        }
+
+        if self.cosigners.len() != usize::from(self.required_signers) {
+            return Err(invalid(format!(
+                "{} co-signers are required, but {} are present",
+                self.required_signers,
+                self.cosigners.len()
+            )));
+        }
+
+        if self
+            .cosigners
+            .windows(2)
+            .any(|pair| pair[0].public_key >= pair[1].public_key)
+        {
+            return Err(invalid(
+                "Co-signers are not sorted by public key or contain duplicates",
+            ));
+        }
+
+        let all_committed = self
+            .cosigners
+            .iter()
+            .all(|cosigner| cosigner.nonce_commitment.is_some());
+        let all_revealed = self
+            .cosigners
+            .iter()
+            .all(|cosigner| cosigner.nonce.is_some());
+        for cosigner in self.cosigners.iter() {
+            if cosigner.nonce.is_some() && !all_committed {
+                return Err(invalid(format!(
+                    "Nonce of co-signer {} is present before nonce commitments of all co-signers",
+                    cosigner.public_key
+                )));
+            }
+            if cosigner.partial_signature.is_some() && !all_revealed {
+                return Err(invalid(format!(
+                    "Partial signature of co-signer {} is present before nonces of all co-signers",
+                    cosigner.public_key
+                )));
+            }
+        }
+
+        let public_keys = self
+            .cosigners
+            .iter()
+            .map(|cosigner| cosigner.public_key.clone())
+            .collect::<Vec<_>>();
+        let combined_public_key = PublicKey::combine_to_raw_pubkey(&public_keys)?;
+        let transaction_id = self.transaction.id();
+
+        for (index, input) in self.inputs.iter().enumerate() {
+            let ExtendedAddr::OrTree(ref root_hash) = input.output.address;
+            if input.proof.value() != &combined_public_key || !input.proof.verify(root_hash) {
+                return Err(invalid(format!(
+                    "Co-signers are not able to spend output of input {}",
+                    index
+                )));
+            }
+
+            if let Some(ref witness) = input.witness {
+                let TxInWitness::TreeSig(_, proof) = witness;
+                if proof != &input.proof
+                    || verify_tx_address(witness, &transaction_id, &input.output.address).is_err()
+                {
+                    return Err(Error::new(
+                        ErrorKind::VerifyError,
+                        format!(
+                            "Witness of input {} does not verify against transaction id {}",
+                            index,
+                            hex::encode(&transaction_id)
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Combines two partially signed transfers of the same transaction (e.g., signed independently
+/// by different co-signers)
+pub fn merge_psct(
+    first: &PartiallySignedTransfer,
+    second: &PartiallySignedTransfer,
+) -> Result<PartiallySignedTransfer> {
+    if first.transaction.id() != second.transaction.id() {
+        return Err(invalid(format!(
+            "Partially signed transfers are for different transactions: {} and {}",
+            hex::encode(&first.transaction.id()),
+            hex::encode(&second.transaction.id())
+        )));
+    }
+
+    if first.required_signers != second.required_signers
+        || first.total_signers != second.total_signers
+    {
+        return Err(invalid(
+            "Partially signed transfers have different threshold policies",
+        ));
+    }
+
+    if first.inputs.len() != second.inputs.len()
+        || first
+            .inputs
+            .iter()
+            .zip(second.inputs.iter())
+            .any(|(a, b)| a.output != b.output || a.proof != b.proof)
+    {
+        return Err(invalid(
+            "Partially signed transfers spend outputs with different co-signers",
+        ));
+    }
+
+    if first.cosigners.len() != second.cosigners.len()
+        || first
+            .cosigners
+            .iter()
+            .zip(second.cosigners.iter())
+            .any(|(a, b)| a.public_key != b.public_key)
+    {
+        return Err(invalid(
+            "Partially signed transfers are signed by different co-signers",
+        ));
+    }
+
+    let cosigners = first
+        .cosigners
+        .iter()
+        .zip(second.cosigners.iter())
+        .map(|(a, b)| {
+            let context = |field: &str| format!("{} of co-signer {}", field, a.public_key);
+            Ok(CosignerContribution {
+                public_key: a.public_key.clone(),
+                nonce_commitment: merge_field(a.nonce_commitment, b.nonce_commitment, || {
+                    context("nonce commitment")
+                })?,
+                nonce: merge_field(a.nonce, b.nonce, || context("nonce"))?,
+                partial_signature: merge_field(a.partial_signature, b.partial_signature, || {
+                    context("partial signature")
+                })?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let inputs = first
+        .inputs
+        .iter()
+        .zip(second.inputs.iter())
+        .enumerate()
+        .map(|(index, (a, b))| {
+            Ok(PartiallySignedInput {
+                output: a.output.clone(),
+                proof: a.proof.clone(),
+                witness: merge_field(a.witness.clone(), b.witness.clone(), || {
+                    format!("witness of input {}", index)
+                })?,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let merged = PartiallySignedTransfer {
+        transaction: first.transaction.clone(),
+        inputs,
+        required_signers: first.required_signers,
+        total_signers: first.total_signers,
+        cosigners,
+    };
+    merged.validate()?;
+    Ok(merged)
+}
+
+/// Merges two optional values, failing if both are present but different
+fn merge_field<T, F>(first: Option<T>, second: Option<T>, context: F) -> Result<Option<T>>
+where
+    T: PartialEq,
+    F: FnOnce() -> String,
+{
+    match (first, second) {
+        (Some(a), Some(b)) if a != b => Err(invalid(format!(
+            "Conflicting {} in partially signed transfers",
+            context()
+        ))),
+        (Some(a), _) => Ok(Some(a)),
+        (None, b) => Ok(b),
+    }
+}
+
+fn invalid<T: Into<String>>(message: T) -> Error {
+    Error::new(ErrorKind::InvalidInput, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chain_core::init::coin::Coin;
+    use chain_core::tx::data::attribute::TxAttributes;
+    use chain_core::tx::data::input::TxoPointer;
+    use client_common::{MultiSigAddress, PrivateKey};
+
+    use crate::multi_sig::MultiSigBuilder;
+
+    struct Fixture {
+        private_keys: Vec<PrivateKey>,
+        public_keys: Vec<PublicKey>,
+        address: MultiSigAddress,
+        transaction: Tx,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let private_keys = (0..3)
+                .map(|_| PrivateKey::new().unwrap())
+                .collect::<Vec<_>>();
+            let public_keys = private_keys.iter().map(PublicKey::from).collect::<Vec<_>>();
+            let address =
+                MultiSigAddress::new(public_keys.clone(), public_keys[0].clone(), 2).unwrap();
+            let transaction = transaction(1);
+
+            Fixture {
+                private_keys,
+                public_keys,
+                address,
+                transaction,
+            }
+        }
+
+        /// Returns signing sessions of first two co-signers, after running given number of rounds
+        fn sessions(&self, rounds: usize) -> Vec<MultiSigBuilder> {
+            let signers = vec![self.public_keys[0].clone(), self.public_keys[1].clone()];
+            let mut sessions = (0..2)
+                .map(|i| {
+                    MultiSigBuilder::new(
+                        self.transaction.id(),
+                        signers.clone(),
+                        self.public_keys[i].clone(),
+                        self.private_keys[i].clone(),
+                    )
+                    .unwrap()
+                })
+                .collect::<Vec<_>>();
+
+            if rounds >= 1 {
+                let commitments = sessions
+                    .iter_mut()
+                    .map(|session| session.nonce_commitment().unwrap())
+                    .collect::<Vec<_>>();
+                sessions[0]
+                    .add_nonce_commitment(&signers[1], commitments[1])
+                    .unwrap();
+                sessions[1]
+                    .add_nonce_commitment(&signers[0], commitments[0])
+                    .unwrap();
+            }
+            if rounds >= 2 {
+                let nonces = sessions
+                    .iter_mut()
+                    .map(|session| session.nonce().unwrap())
+                    .collect::<Vec<_>>();
+                sessions[0].add_nonce(&signers[1], &nonces[1]).unwrap();
+                sessions[1].add_nonce(&signers[0], &nonces[0]).unwrap();
+            }
+            if rounds >= 3 {
+                let partial_signatures = sessions
+                    .iter_mut()
+                    .map(|session| session.partial_signature().unwrap())
+                    .collect::<Vec<_>>();
+                sessions[0]
+                    .add_partial_signature(&signers[1], partial_signatures[1])
+                    .unwrap();
+                sessions[1]
+                    .add_partial_signature(&signers[0], partial_signatures[0])
+                    .unwrap();
+            }
+            sessions
+        }
+
+        fn psct(&self, session: &MultiSigBuilder) -> PartiallySignedTransfer {
+            let proof = self
+                .address
+                .generate_proof(vec![
+                    self.public_keys[0].clone(),
+                    self.public_keys[1].clone(),
+                ])
+                .unwrap()
+                .unwrap();
+            let witness = session
+                .signature()
+                .ok()
+                .map(|signature| TxInWitness::TreeSig(signature, proof.clone()));
+
+            PartiallySignedTransfer {
+                transaction: self.transaction.clone(),
+                inputs: vec![PartiallySignedInput {
+                    output: TxOut::new(self.address.to_extended_addr(), Coin::new(100).unwrap()),
+                    proof,
+                    witness,
+                }],
+                required_signers: 2,
+                total_signers: 3,
+                cosigners: session.cosigner_contributions(),
+            }
+        }
+    }
+
+    fn transaction(seed: u8) -> Tx {
+        Tx::new_with(
+            vec![TxoPointer::new([seed; 32], 0)],
+            vec![TxOut::new(
+                ExtendedAddr::OrTree([2; 32]),
+                Coin::new(100).unwrap(),
+            )],
+            TxAttributes::new(171),
+        )
+    }
+
+    #[test]
+    fn check_psct_round_trip() {
+        let fixture = Fixture::new();
+        let sessions = fixture.sessions(3);
+        let psct = fixture.psct(&sessions[0]);
+        assert!(psct.inputs[0].witness.is_some());
+
+        let bytes = psct.encode();
+        assert_eq!(psct, PartiallySignedTransfer::from_bytes(&bytes).unwrap());
+        let armored = psct.to_armored();
+        assert!(armored.starts_with(ARMOR_HEADER));
+        assert!(armored.lines().all(|line| line.len() <= ARMOR_LINE_LENGTH));
+        assert_eq!(
+            psct,
+            PartiallySignedTransfer::from_armored(&armored).unwrap()
+        );
+
+        // unknown version and trailing data are rejected
+        let mut unknown_version = bytes.clone();
+        unknown_version[0] = PSCT_VERSION + 1;
+        assert!(PartiallySignedTransfer::from_bytes(&unknown_version).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(PartiallySignedTransfer::from_bytes(&trailing).is_err());
+        assert!(PartiallySignedTransfer::from_armored(&armored.replace(ARMOR_HEADER, "")).is_err());
+
+        // witness has to verify against the embedded transaction id
+        let mut tampered = psct.clone();
+        tampered.transaction = transaction(3);
+        let error = PartiallySignedTransfer::from_bytes(&tampered.encode()).unwrap_err();
+        assert_eq!(ErrorKind::VerifyError, error.kind());
+
+        // partial signature before all nonces are revealed is rejected
+        let mut early = fixture.psct(&fixture.sessions(1)[0]);
+        early.cosigners[0].partial_signature = Some([1; 32]);
+        assert!(PartiallySignedTransfer::from_bytes(&early.encode()).is_err());
+
+        // co-signers have to match the proof
+        let mut other_cosigner = psct;
+        other_cosigner.cosigners[1].public_key = fixture.public_keys[2].clone();
+        other_cosigner
+            .cosigners
+            .sort_by(|a, b| a.public_key.cmp(&b.public_key));
+        assert!(PartiallySignedTransfer::from_bytes(&other_cosigner.encode()).is_err());
+    }
+
+    #[test]
+    fn check_merge_psct() {
+        let fixture = Fixture::new();
+
+        // halves after nonce round only contain partial signature of their own co-signer
+        let mut sessions = fixture.sessions(2);
+        for session in sessions.iter_mut() {
+            session.partial_signature().unwrap();
+        }
+        let first = fixture.psct(&sessions[0]);
+        let second = fixture.psct(&sessions[1]);
+        let merged = merge_psct(&first, &second).unwrap();
+        assert!(merged
+            .cosigners
+            .iter()
+            .all(|cosigner| cosigner.partial_signature.is_some()));
+        assert_eq!(merged, merge_psct(&merged, &first).unwrap());
+
+        // same co-signer signing twice (in another session) conflicts
+        let again = fixture.sessions(2);
+        let error = merge_psct(&first, &fixture.psct(&again[0])).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().starts_with("Conflicting nonce commitment"));
+
+        // transfers of different transactions can not be merged
+        let mut other = second;
+        other.transaction = transaction(3);
+        let error = merge_psct(&first, &other).unwrap_err();
+        assert!(error
+            .message()
+            .starts_with("Partially signed transfers are for different transactions"));
+    }
+}
@@ -1,29 +1,54 @@
 //! Management services
+mod address_label_service;
+mod broadcast_log_service;
+mod deletion_service;
 mod hd_key_service;
 mod hw_key_service;
 mod key_service;
+mod migration_service;
 #[cfg(feature = "mock-hardware-wallet")]
 mod mock_hw_key_service;
 mod multi_sig_session_service;
+mod passphrase_service;
+mod rename_service;
 mod root_hash_service;
 mod sync_state_service;
 mod wallet_service;
+mod wallet_session_service;
 mod wallet_state_service;
 
 #[doc(hidden)]
 pub use self::wallet_state_service::WalletStateMemento;
 
-pub use self::hd_key_service::{HDAccountType, HdKey, HdKeyService};
-pub use self::hw_key_service::{HwKeyService, UnauthorizedHwKeyService};
+pub use self::address_label_service::AddressLabelService;
+pub use self::broadcast_log_service::{
+    BroadcastLogEntry, BroadcastLogService, BroadcastOutcome, RebroadcastOutcome,
+};
+pub use self::deletion_service::DeletionService;
+pub use self::hd_key_service::{HDAccountType, HdKey, HdKeyService, HdPath};
+#[cfg(test)]
+pub(crate) use self::hw_key_service::tests::RecordingHwKeyService;
+pub use self::hw_key_service::{HwKeyService, StakingTxConfirmation, UnauthorizedHwKeyService};
 pub use self::key_service::KeyService;
+pub use self::migration_service::{
+    migrate_storage, migrations, run_migrations, schema_version, BroadcastLogTimeIndexMigration,
+    Migration, StakingKeyIndexMigration, SCHEMA_VERSION,
+};
 #[cfg(feature = "mock-hardware-wallet")]
 pub use self::mock_hw_key_service::{MockHardwareKey, MockHardwareService, MockHardwareWallet};
 pub use self::multi_sig_session_service::MultiSigSessionService;
+pub use self::passphrase_service::PassphraseService;
+pub use self::rename_service::RenameService;
 pub use self::root_hash_service::RootHashService;
 pub use self::sync_state_service::{
     delete_sync_state, load_sync_state, save_sync_state, SyncState, SyncStateService,
 };
-pub use self::wallet_service::{load_wallet, Wallet, WalletInfo, WalletService};
+pub use self::wallet_service::{
+    load_wallet, Wallet, WalletInfo, WalletService, WatchOnlyWalletInfo,
+};
+pub use self::wallet_session_service::{
+    SessionToken, WalletSessionService, DEFAULT_MAX_SESSIONS_PER_WALLET,
+};
 pub use self::wallet_state_service::{
     delete_wallet_state, load_wallet_state, modify_wallet_state, save_wallet_state, WalletState,
     WalletStateService,
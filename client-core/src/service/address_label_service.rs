@@ -0,0 +1,203 @@
+use std::collections::BTreeMap;
+
+use parity_scale_codec::{Decode, Encode};
+
+use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, SecureStorage, Storage};
+
+use crate::types::{LabeledAddress, MAX_ADDRESS_LABEL_LENGTH};
+
+/// key space of address labels
+const KEYSPACE: &str = "core_address_label";
+
+/// Maintains mapping `wallet-name -> (address -> label)` (encrypted with wallet's enckey)
+#[derive(Debug, Default, Clone)]
+pub struct AddressLabelService<S>
+where
+    S: Storage,
+{
+    storage: S,
+}
+
+impl<S> AddressLabelService<S>
+where
+    S: Storage,
+{
+    /// Creates new instance of address label service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Sets label of given address (an empty label removes it)
+    pub fn set_label(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: LabeledAddress,
+        label: &str,
+    ) -> Result<()> {
+        verify_label(label)?;
+
+        self.storage
+            .fetch_and_update_secure(KEYSPACE, name, enckey, |bytes_optional| {
+                let mut labels = parse_labels(name, bytes_optional)?;
+                if label.is_empty() {
+                    labels.remove(&address);
+                } else {
+                    labels.insert(address.clone(), label.to_owned());
+                }
+                Ok(Some(labels.encode()))
+            })
+            .map(|_| ())
+    }
+
+    /// Sets labels of given addresses (e.g. when importing a wallet)
+    pub fn set_labels(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        labels: Vec<(LabeledAddress, String)>,
+    ) -> Result<()> {
+        for (_, label) in labels.iter() {
+            verify_label(label)?;
+        }
+
+        self.storage
+            .fetch_and_update_secure(KEYSPACE, name, enckey, |bytes_optional| {
+                let mut stored_labels = parse_labels(name, bytes_optional)?;
+                for (address, label) in labels.iter() {
+                    if label.is_empty() {
+                        stored_labels.remove(address);
+                    } else {
+                        stored_labels.insert(address.clone(), label.clone());
+                    }
+                }
+                Ok(Some(stored_labels.encode()))
+            })
+            .map(|_| ())
+    }
+
+    /// Returns label of given address
+    pub fn get_label(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &LabeledAddress,
+    ) -> Result<Option<String>> {
+        Ok(self.get_labels(name, enckey)?.remove(address))
+    }
+
+    /// Returns all labelled addresses of given wallet
+    pub fn get_labels(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<BTreeMap<LabeledAddress, String>> {
+        Ok(self
+            .storage
+            .load_secure(KEYSPACE, name, enckey)?
+            .unwrap_or_default())
+    }
+
+    /// Deletes all labels of given wallet
+    #[inline]
+    pub fn delete_labels(&self, name: &str) -> Result<()> {
+        self.storage.delete(KEYSPACE, name).map(|_| ())
+    }
+
+    /// Clears all storage
+    #[inline]
+    pub fn clear(&self) -> Result<()> {
+        self.storage.clear(KEYSPACE)
+    }
+}
+
+fn verify_label(label: &str) -> Result<()> {
+    let length = label.chars().count();
+    if length > MAX_ADDRESS_LABEL_LENGTH {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Address label of {} characters exceeds maximum length of {} characters",
+                length, MAX_ADDRESS_LABEL_LENGTH
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn parse_labels(
+    name: &str,
+    bytes_optional: Option<&[u8]>,
+) -> Result<BTreeMap<LabeledAddress, String>> {
+    bytes_optional
+        .map(|mut bytes| {
+            BTreeMap::decode(&mut bytes).chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!(
+                        "Unable to deserialize address labels for wallet with name {}",
+                        name
+                    ),
+                )
+            })
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secstr::SecUtf8;
+    use std::str::FromStr;
+
+    use chain_core::state::account::StakedStateAddress;
+    use chain_core::tx::data::address::ExtendedAddr;
+    use client_common::{seckey::derive_enckey, storage::MemoryStorage};
+
+    #[test]
+    fn check_flow() {
+        let service = AddressLabelService::new(MemoryStorage::default());
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let transfer = LabeledAddress::from(ExtendedAddr::OrTree([1; 32]));
+        let staking = LabeledAddress::from(
+            StakedStateAddress::from_str("0x0e7c045110b8dbf29765047380898919c5cb56f4").unwrap(),
+        );
+
+        assert!(service.get_labels(name, enckey).unwrap().is_empty());
+
+        service
+            .set_label(name, enckey, transfer.clone(), "cold storage")
+            .unwrap();
+        service
+            .set_label(name, enckey, staking.clone(), "validator #2")
+            .unwrap();
+        assert_eq!(
+            Some("cold storage".to_owned()),
+            service.get_label(name, enckey, &transfer).unwrap()
+        );
+        assert_eq!(2, service.get_labels(name, enckey).unwrap().len());
+
+        // labels are encrypted with enckey of wallet
+        let wrong_enckey = &derive_enckey(&SecUtf8::from("wrong passphrase"), name).unwrap();
+        assert!(service.get_labels(name, wrong_enckey).is_err());
+
+        let error = service
+            .set_label(name, enckey, transfer.clone(), &"x".repeat(65))
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(service
+            .set_label(name, enckey, transfer.clone(), &"é".repeat(64))
+            .is_ok());
+
+        service
+            .set_label(name, enckey, transfer.clone(), "")
+            .unwrap();
+        assert_eq!(None, service.get_label(name, enckey, &transfer).unwrap());
+
+        service.delete_labels(name).unwrap();
+        assert!(service.get_labels(name, enckey).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,462 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use parity_scale_codec::{Decode, Encode, Error as CodecError, Input};
+
+use chain_core::tx::data::TxId;
+use chain_core::tx::{TxAux, TxEnclaveAux, TxPublicAux};
+use client_common::{ErrorKind, Result, ResultExt, Storage, StorageOp};
+
+const KEYSPACE: &str = "core_broadcast_log";
+/// Keyspace indexing broadcast log entries by timestamp (see `time_key`), so that expired entries
+/// are found without reading the whole log
+const TIME_KEYSPACE: &str = "core_broadcast_log_time";
+
+/// Entries older than this (in seconds) are removed from broadcast log
+const RETENTION_SECONDS: u64 = 30 * 24 * 60 * 60;
+
+/// Length of zero-padded timestamp prefix of time index keys
+const TIMESTAMP_LEN: usize = 20;
+
+/// Outcome of broadcasting a transaction
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub enum BroadcastOutcome {
+    /// Transaction is accepted by node (hex encoded hash returned by node)
+    Accepted(String),
+    /// Transaction is rejected in `CheckTx` with given code and log
+    Rejected {
+        /// Response code
+        code: u32,
+        /// Log returned by node
+        log: String,
+    },
+    /// Broadcast failed before a response is received
+    TransportError(String),
+}
+
+/// Successful outcome of rebroadcasting a pending transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebroadcastOutcome {
+    /// Transaction is accepted by node again
+    Accepted,
+    /// Transaction is still in mempool of node
+    AlreadyInMempool,
+    /// Transaction is already included in a block
+    AlreadyCommitted,
+}
+
+/// Broadcast log entry of a transaction
+#[derive(Debug, Clone, PartialEq, Encode)]
+pub struct BroadcastLogEntry {
+    /// Id of broadcasted transaction
+    pub transaction_id: TxId,
+    /// Kind of operation (e.g. `transfer`, `unbond`)
+    pub operation: String,
+    /// Unix timestamp (in seconds) of broadcast intent
+    pub timestamp: u64,
+    /// Outcome of broadcast (`None` if broadcast did not return, e.g. due to a crash)
+    pub outcome: Option<BroadcastOutcome>,
+    /// SCALE-encoded `TxAux` of broadcasted transaction (`None` for entries recorded before it
+    /// was stored)
+    pub raw_transaction: Option<Vec<u8>>,
+}
+
+impl Decode for BroadcastLogEntry {
+    fn decode<I: Input>(input: &mut I) -> std::result::Result<Self, CodecError> {
+        let transaction_id = TxId::decode(input)?;
+        let operation = String::decode(input)?;
+        let timestamp = u64::decode(input)?;
+        let outcome = <Option<BroadcastOutcome>>::decode(input)?;
+        // entries recorded before raw transactions were stored end here
+        let raw_transaction = if input.remaining_len()? == Some(0) {
+            None
+        } else {
+            <Option<Vec<u8>>>::decode(input)?
+        };
+        Ok(BroadcastLogEntry {
+            transaction_id,
+            operation,
+            timestamp,
+            outcome,
+            raw_transaction,
+        })
+    }
+}
+
+/// Maintains write-ahead log of broadcasted transactions
+#[derive(Debug, Default, Clone)]
+pub struct BroadcastLogService<S: Storage> {
+    storage: S,
+}
+
+impl<S> BroadcastLogService<S>
+where
+    S: Storage,
+{
+    /// Creates a new instance of broadcast log service
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Records intent of broadcasting given transaction (before it is broadcasted)
+    ///
+    /// Intent is flushed to disk before returning, so that it survives a crash during broadcast.
+    pub fn record_intent(&self, tx_aux: &TxAux) -> Result<()> {
+        self.record_intent_at(tx_aux, now()?)?;
+        self.storage.flush()
+    }
+
+    fn record_intent_at(&self, tx_aux: &TxAux, timestamp: u64) -> Result<()> {
+        self.remove_expired(timestamp)?;
+
+        let entry = BroadcastLogEntry {
+            transaction_id: tx_aux.tx_id(),
+            operation: operation(tx_aux).to_owned(),
+            timestamp,
+            outcome: None,
+            raw_transaction: Some(tx_aux.encode()),
+        };
+
+        let mut ops = Vec::with_capacity(3);
+        // transaction broadcasted again
+        if let Some(previous) = self.query(&entry.transaction_id)? {
+            ops.push(StorageOp::delete(
+                TIME_KEYSPACE,
+                time_key(previous.timestamp, &previous.transaction_id),
+            ));
+        }
+        ops.push(StorageOp::set(
+            KEYSPACE,
+            hex::encode(entry.transaction_id),
+            entry.encode(),
+        ));
+        ops.push(StorageOp::set(
+            TIME_KEYSPACE,
+            time_key(timestamp, &entry.transaction_id),
+            Vec::new(),
+        ));
+        self.storage.write_batch(ops)
+    }
+
+    /// Records outcome of broadcasting a transaction
+    pub fn record_outcome(&self, transaction_id: &TxId, outcome: BroadcastOutcome) -> Result<()> {
+        let mut entry = self.query(transaction_id)?.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!(
+                    "Broadcast intent of transaction {} is not found",
+                    hex::encode(transaction_id)
+                ),
+            )
+        })?;
+        entry.outcome = Some(outcome);
+        self.storage
+            .save(KEYSPACE, &hex::encode(transaction_id), &entry)
+    }
+
+    /// Returns broadcast log entry of given transaction
+    pub fn query(&self, transaction_id: &TxId) -> Result<Option<BroadcastLogEntry>> {
+        self.storage.load(KEYSPACE, &hex::encode(transaction_id))
+    }
+
+    /// Returns broadcasted transaction with given id (if it is still in broadcast log)
+    pub fn transaction(&self, transaction_id: &TxId) -> Result<Option<TxAux>> {
+        let raw_transaction = match self.query(transaction_id)? {
+            Some(BroadcastLogEntry {
+                raw_transaction: Some(raw_transaction),
+                ..
+            }) => raw_transaction,
+            _ => return Ok(None),
+        };
+        TxAux::decode(&mut raw_transaction.as_slice())
+            .map(Some)
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!(
+                        "Unable to decode broadcasted transaction {}",
+                        hex::encode(transaction_id)
+                    ),
+                )
+            })
+    }
+
+    /// Returns all broadcast log entries
+    pub fn entries(&self) -> Result<Vec<BroadcastLogEntry>> {
+        let mut entries = Vec::new();
+        for key in self.storage.keys(KEYSPACE)? {
+            let key = String::from_utf8_lossy(&key);
+            if let Some(entry) = self.storage.load(KEYSPACE, &key)? {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Returns entries with a broadcast intent but no outcome
+    pub fn unresolved(&self) -> Result<Vec<BroadcastLogEntry>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| entry.outcome.is_none())
+            .collect())
+    }
+
+    /// Indexes entries by timestamp (for entries recorded before the index existed)
+    pub(crate) fn backfill_time_index(&self) -> Result<()> {
+        let mut ops = Vec::new();
+        for entry in self.entries()? {
+            ops.push(StorageOp::set(
+                TIME_KEYSPACE,
+                time_key(entry.timestamp, &entry.transaction_id),
+                Vec::new(),
+            ));
+        }
+        self.storage.write_batch(ops)
+    }
+
+    /// Removes entries older than retention window, scanning time index only up to the first
+    /// entry which is not expired
+    fn remove_expired(&self, now: u64) -> Result<()> {
+        let oldest_kept = match now.checked_sub(RETENTION_SECONDS) {
+            Some(oldest_kept) => timestamp_prefix(oldest_kept),
+            None => return Ok(()),
+        };
+
+        let mut ops = Vec::new();
+        for record in self.storage.iter_prefix(TIME_KEYSPACE, "")? {
+            let (key, _) = record?;
+            if key.as_slice() >= oldest_kept.as_bytes() {
+                break;
+            }
+            ops.push(StorageOp::delete(KEYSPACE, &key[TIMESTAMP_LEN..]));
+            ops.push(StorageOp::delete(TIME_KEYSPACE, key));
+        }
+
+        if ops.is_empty() {
+            Ok(())
+        } else {
+            self.storage.write_batch(ops)
+        }
+    }
+}
+
+/// Returns zero-padded timestamp, so that time index keys are ordered by timestamp
+fn timestamp_prefix(timestamp: u64) -> String {
+    format!("{:01$}", timestamp, TIMESTAMP_LEN)
+}
+
+/// Returns key of time index: zero-padded timestamp followed by hex encoded transaction id (which
+/// is the key of entry)
+fn time_key(timestamp: u64, transaction_id: &TxId) -> String {
+    format!(
+        "{}{}",
+        timestamp_prefix(timestamp),
+        hex::encode(transaction_id)
+    )
+}
+
+fn now() -> Result<u64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .chain(|| (ErrorKind::InternalError, "System time is before unix epoch"))
+}
+
+fn operation(tx_aux: &TxAux) -> &'static str {
+    match tx_aux {
+        TxAux::EnclaveTx(TxEnclaveAux::TransferTx { .. }) => "transfer",
+        TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { .. }) => "deposit",
+        TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { .. }) => "withdraw",
+        TxAux::PublicTx(TxPublicAux::UnbondStakeTx(..)) => "unbond",
+        TxAux::PublicTx(TxPublicAux::UnjailTx(..)) => "unjail",
+        TxAux::PublicTx(TxPublicAux::NodeJoinTx(..)) => "node_join",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::init::coin::Coin;
+    use chain_core::state::account::{
+        StakedStateAddress, StakedStateOpAttributes, StakedStateOpWitness, UnbondTx,
+    };
+    use client_common::storage::{MemoryStorage, TestStorage};
+    use client_common::{PrivateKey, PrivateKeyAction, Transaction};
+
+    fn unbond_tx_aux() -> TxAux {
+        unbond_tx_aux_with_nonce(0)
+    }
+
+    fn unbond_tx_aux_with_nonce(nonce: u64) -> TxAux {
+        let transaction = UnbondTx::new(
+            StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+            nonce,
+            Coin::unit(),
+            StakedStateOpAttributes::new(0),
+        );
+        let witness = PrivateKey::new()
+            .unwrap()
+            .sign(&Transaction::UnbondStakeTransaction(transaction.clone()))
+            .map(StakedStateOpWitness::new)
+            .unwrap();
+        TxAux::PublicTx(TxPublicAux::UnbondStakeTx(transaction, witness))
+    }
+
+    #[test]
+    fn check_broadcast_log_flow() {
+        let storage = MemoryStorage::default();
+        let tx_aux = unbond_tx_aux();
+        let transaction_id = tx_aux.tx_id();
+
+        BroadcastLogService::new(storage.clone())
+            .record_intent(&tx_aux)
+            .unwrap();
+
+        // a new instance (e.g. after restart) finds the broadcast without outcome
+        let broadcast_log_service = BroadcastLogService::new(storage);
+        let unresolved = broadcast_log_service.unresolved().unwrap();
+        assert_eq!(1, unresolved.len());
+        assert_eq!(transaction_id, unresolved[0].transaction_id);
+        assert_eq!("unbond", unresolved[0].operation);
+
+        broadcast_log_service
+            .record_outcome(
+                &transaction_id,
+                BroadcastOutcome::Rejected {
+                    code: 1,
+                    log: "invalid nonce".to_owned(),
+                },
+            )
+            .unwrap();
+        assert!(broadcast_log_service.unresolved().unwrap().is_empty());
+        assert_eq!(
+            Some(BroadcastOutcome::Rejected {
+                code: 1,
+                log: "invalid nonce".to_owned(),
+            }),
+            broadcast_log_service
+                .query(&transaction_id)
+                .unwrap()
+                .unwrap()
+                .outcome
+        );
+    }
+
+    #[test]
+    fn check_broadcasted_transaction() {
+        let storage = MemoryStorage::default();
+        let broadcast_log_service = BroadcastLogService::new(storage.clone());
+        let tx_aux = unbond_tx_aux();
+        let transaction_id = tx_aux.tx_id();
+
+        assert_eq!(
+            None,
+            broadcast_log_service.transaction(&transaction_id).unwrap()
+        );
+        broadcast_log_service.record_intent(&tx_aux).unwrap();
+        assert_eq!(
+            Some(tx_aux),
+            broadcast_log_service.transaction(&transaction_id).unwrap()
+        );
+
+        // entries recorded before raw transactions were stored are still readable
+        let legacy = (
+            transaction_id,
+            "unbond".to_owned(),
+            0u64,
+            Some(BroadcastOutcome::Accepted("00".to_owned())),
+        )
+            .encode();
+        storage
+            .set(KEYSPACE, hex::encode(transaction_id), legacy)
+            .unwrap();
+        let entry = broadcast_log_service
+            .query(&transaction_id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(None, entry.raw_transaction);
+        assert_eq!(
+            None,
+            broadcast_log_service.transaction(&transaction_id).unwrap()
+        );
+    }
+
+    #[test]
+    fn check_remove_expired() {
+        let storage = TestStorage::default();
+        let broadcast_log_service = BroadcastLogService::new(storage.clone());
+        let now = 100 * RETENTION_SECONDS;
+
+        let expired = unbond_tx_aux_with_nonce(0);
+        let kept = unbond_tx_aux_with_nonce(1);
+        broadcast_log_service
+            .record_intent_at(&expired, now - RETENTION_SECONDS - 1)
+            .unwrap();
+        broadcast_log_service
+            .record_intent_at(&kept, now - RETENTION_SECONDS)
+            .unwrap();
+
+        let recent = unbond_tx_aux_with_nonce(2);
+        broadcast_log_service
+            .record_intent_at(&recent, now)
+            .unwrap();
+        assert_eq!(None, broadcast_log_service.query(&expired.tx_id()).unwrap());
+        assert!(broadcast_log_service
+            .query(&kept.tx_id())
+            .unwrap()
+            .is_some());
+        assert_eq!(2, storage.keys(TIME_KEYSPACE).unwrap().len());
+
+        // reads do not depend on number of entries which are not expired
+        for nonce in 3..20 {
+            broadcast_log_service
+                .record_intent_at(&unbond_tx_aux_with_nonce(nonce), now)
+                .unwrap();
+        }
+        storage.take_reads();
+        broadcast_log_service
+            .record_intent_at(&unbond_tx_aux_with_nonce(20), now)
+            .unwrap();
+        let reads = storage.take_reads();
+        for nonce in 21..40 {
+            broadcast_log_service
+                .record_intent_at(&unbond_tx_aux_with_nonce(nonce), now)
+                .unwrap();
+        }
+        storage.take_reads();
+        broadcast_log_service
+            .record_intent_at(&unbond_tx_aux_with_nonce(40), now)
+            .unwrap();
+        assert_eq!(reads, storage.take_reads());
+    }
+
+    #[test]
+    fn check_broadcast_again() {
+        let storage = MemoryStorage::default();
+        let broadcast_log_service = BroadcastLogService::new(storage.clone());
+        let tx_aux = unbond_tx_aux();
+
+        broadcast_log_service.record_intent_at(&tx_aux, 1).unwrap();
+        broadcast_log_service.record_intent_at(&tx_aux, 2).unwrap();
+        assert_eq!(
+            vec![time_key(2, &tx_aux.tx_id()).into_bytes()],
+            storage.keys(TIME_KEYSPACE).unwrap()
+        );
+    }
+
+    #[test]
+    fn check_record_outcome_without_intent() {
+        let broadcast_log_service = BroadcastLogService::new(MemoryStorage::default());
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            broadcast_log_service
+                .record_outcome(&[0; 32], BroadcastOutcome::Accepted("00".to_owned()))
+                .unwrap_err()
+                .kind()
+        );
+    }
+}
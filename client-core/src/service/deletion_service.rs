@@ -0,0 +1,109 @@
+use rand::rngs::OsRng;
+use rand::RngCore;
+use ring::constant_time::verify_slices_are_equal;
+use zeroize::Zeroize;
+
+use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, SecureStorage, Storage};
+
+use super::wallet_service::wallet_records;
+
+/// Keyspace of deletion tokens (`wallet-name -> token`)
+const KEYSPACE: &str = "core_wallet_deletion";
+
+/// Deletes all records of a wallet
+///
+/// Deletion is done in two steps: `request_deletion` issues a token which then has to be passed
+/// to `delete`, so that a wallet is never deleted by a single accidental call.
+#[derive(Debug, Default, Clone)]
+pub struct DeletionService<T: Storage> {
+    storage: T,
+}
+
+impl<T> DeletionService<T>
+where
+    T: Storage,
+{
+    /// Creates a new instance of deletion service
+    #[inline]
+    pub fn new(storage: T) -> Self {
+        DeletionService { storage }
+    }
+
+    /// Issues a token confirming deletion of wallet (replacing previously issued one)
+    pub fn request_deletion(&self, name: &str, enckey: &SecKey) -> Result<String> {
+        let mut token = [0; 32];
+        OsRng.fill_bytes(&mut token);
+        let encoded = hex::encode(&token);
+        token.zeroize();
+
+        self.storage
+            .set_secure(KEYSPACE, name, encoded.as_bytes().to_vec(), enckey)?;
+        Ok(encoded)
+    }
+
+    /// Overwrites and deletes all records of wallet if given token matches the issued one
+    pub fn delete(&self, name: &str, enckey: &SecKey, token: &str) -> Result<()> {
+        let mut issued = self.storage.get_secure(KEYSPACE, name, enckey)?.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("Deletion of wallet ({}) was not requested", name),
+            )
+        })?;
+        let matches = verify_slices_are_equal(&issued, token.as_bytes()).is_ok();
+        issued.zeroize();
+        if !matches {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Invalid deletion token",
+            ));
+        }
+
+        let records = wallet_records(&self.storage, name, enckey)?;
+        let keys = records
+            .iter()
+            .map(|record| (&record.keyspace, &record.key))
+            .collect::<Vec<_>>();
+        self.storage.wipe(&keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secstr::SecUtf8;
+
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+
+    #[test]
+    fn check_delete() {
+        let storage = MemoryStorage::default();
+        let service = DeletionService::new(storage.clone());
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), "name").unwrap();
+        let other_enckey = derive_enckey(&SecUtf8::from("passphrase"), "other").unwrap();
+
+        storage
+            .set_secure("a", "name", b"record".to_vec(), &enckey)
+            .unwrap();
+        storage.set("b", "name", vec![1]).unwrap();
+        storage
+            .set_secure("c", "session", b"session".to_vec(), &enckey)
+            .unwrap();
+        storage
+            .set_secure("a", "other", b"other wallet".to_vec(), &other_enckey)
+            .unwrap();
+
+        assert!(service.delete("name", &enckey, "token").is_err());
+        let token = service.request_deletion("name", &enckey).unwrap();
+        assert!(service.delete("name", &enckey, "token").is_err());
+        assert!(service.delete("name", &other_enckey, &token).is_err());
+        assert!(storage.get("a", "name").unwrap().is_some());
+
+        service.delete("name", &enckey, &token).unwrap();
+
+        assert_eq!(vec![b"other".to_vec()], storage.keys("a").unwrap());
+        assert!(storage.keys("b").unwrap().is_empty());
+        assert!(storage.keys("c").unwrap().is_empty());
+        assert!(storage.keys(KEYSPACE).unwrap().is_empty());
+    }
+}
@@ -1,4 +1,8 @@
+use std::fmt;
+use std::str::FromStr;
+
 use parity_scale_codec::{Decode, Encode};
+use secstr::SecUtf8;
 
 use chain_core::init::network::get_network;
 use client_common::storage::decrypt_bytes;
@@ -10,6 +14,12 @@ use crate::types::AddressType;
 use crate::{HDSeed, Mnemonic};
 
 const KEYSPACE: &str = "core_hd_key";
+const ACCOUNT_KEYSPACE: &str = "core_hd_key_account";
+
+/// Number of BIP44 accounts reserved for each account of HD wallet (one per `HDAccountType`)
+const ACCOUNT_TYPES: u32 = 3;
+/// Smallest hardened key index
+const HARDENED_OFFSET: u32 = 0x8000_0000;
 
 /// HD key
 #[derive(Debug, Clone, PartialEq, Encode, Decode)]
@@ -44,6 +54,106 @@ impl HDAccountType {
     }
 }
 
+/// BIP44 key path below `m / purpose' / coin_type'` (i.e., `account' / change / address_index`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HdPath {
+    /// BIP44 account (hardened)
+    pub account: u32,
+    /// BIP44 change
+    pub change: u32,
+    /// BIP44 address index
+    pub index: u32,
+}
+
+impl HdPath {
+    /// Creates a new key path (each component has to be less than `2^31`)
+    pub fn new(account: u32, change: u32, index: u32) -> Result<Self> {
+        if account >= HARDENED_OFFSET || change >= HARDENED_OFFSET || index >= HARDENED_OFFSET {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "HD path {}'/{}/{} is out of range (components have to be less than 2^31)",
+                    account, change, index
+                ),
+            ));
+        }
+
+        Ok(HdPath {
+            account,
+            change,
+            index,
+        })
+    }
+
+    /// Returns key path of given type and index in given account of HD wallet
+    ///
+    /// # Note
+    ///
+    /// BIP44 account is `wallet_account * 3 + account_type`, so that keys of account `0` are
+    /// derived at the same paths as keys of wallets created before support of multiple accounts.
+    pub fn wallet_key(
+        wallet_account: u32,
+        account_type: HDAccountType,
+        index: u32,
+    ) -> Result<Self> {
+        let account = wallet_account
+            .checked_mul(ACCOUNT_TYPES)
+            .and_then(|account| account.checked_add(account_type.index()))
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!("HD wallet account {} is out of range", wallet_account),
+                )
+            })?;
+
+        Self::new(account, 0, index)
+    }
+
+    /// Returns full BIP32 chain path for given BIP44 coin type
+    pub fn chain_path(&self, coin_type: u32) -> String {
+        format!(
+            "m/44'/{}'/{}'/{}/{}",
+            coin_type, self.account, self.change, self.index
+        )
+    }
+}
+
+impl fmt::Display for HdPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}'/{}/{}", self.account, self.change, self.index)
+    }
+}
+
+impl FromStr for HdPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let error = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Invalid HD path: {} (expected format: `account'/change/index`)",
+                    s
+                ),
+            )
+        };
+
+        let components = s.trim().split('/').collect::<Vec<_>>();
+        if components.len() != 3 || !components[0].ends_with('\'') {
+            return Err(error());
+        }
+
+        let account = components[0]
+            .trim_end_matches('\'')
+            .parse()
+            .map_err(|_| error())?;
+        let change = components[1].parse().map_err(|_| error())?;
+        let index = components[2].parse().map_err(|_| error())?;
+
+        Self::new(account, change, index)
+    }
+}
+
 // AddressType is subset of HDAccountType
 impl From<AddressType> for HDAccountType {
     fn from(addr_type: AddressType) -> HDAccountType {
@@ -83,19 +193,22 @@ where
                 format!("Wallet with name {} not found in hd key service", name)
             })?;
         self.storage.delete(KEYSPACE, name)?;
+        self.storage.delete(ACCOUNT_KEYSPACE, name)?;
         Ok(())
     }
 
-    /// Adds a new mnemonic in storage and sets its index to zero
-    pub fn add_mnemonic(&self, name: &str, mnemonic: &Mnemonic, enckey: &SecKey) -> Result<()> {
-        if self.storage.get(KEYSPACE, name)?.is_some() {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "HD Key with given name already exists",
-            ));
-        }
-
-        let hd_seed = HDSeed::from(mnemonic);
+    /// Adds a new mnemonic (for given account of HD wallet) in storage and sets its index to zero
+    ///
+    /// Only the seed derived from mnemonic and BIP39 passphrase is stored (never the passphrase).
+    pub fn add_mnemonic(
+        &self,
+        name: &str,
+        mnemonic: &Mnemonic,
+        mnemonic_passphrase: Option<&SecUtf8>,
+        account: u32,
+        enckey: &SecKey,
+    ) -> Result<()> {
+        let hd_seed = HDSeed::from_mnemonic(mnemonic, mnemonic_passphrase);
 
         let hd_key = HdKey {
             staking_index: 0,
@@ -104,11 +217,49 @@ where
             seed: hd_seed,
         };
 
+        self.add_hd_key(name, &hd_key, account, enckey)
+    }
+
+    /// Adds an existing HD key (for given account of HD wallet) in storage (e.g., from a backup)
+    pub fn add_hd_key(
+        &self,
+        name: &str,
+        hd_key: &HdKey,
+        account: u32,
+        enckey: &SecKey,
+    ) -> Result<()> {
+        if self.storage.get(KEYSPACE, name)?.is_some() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "HD Key with given name already exists",
+            ));
+        }
+
+        // validates that keys of the account can be derived
+        HdPath::wallet_key(account, HDAccountType::Viewkey, 0)?;
+        if account != 0 {
+            self.storage
+                .set_secure(ACCOUNT_KEYSPACE, name, account.encode(), enckey)?;
+        }
+
         self.storage
             .set_secure(KEYSPACE, name, hd_key.encode(), enckey)
             .map(|_| ())
     }
 
+    /// Returns account of HD wallet (`0` for wallets created before support of multiple accounts)
+    pub fn account(&self, name: &str, enckey: &SecKey) -> Result<u32> {
+        match self.storage.get_secure(ACCOUNT_KEYSPACE, name, enckey)? {
+            None => Ok(0),
+            Some(account_bytes) => u32::decode(&mut account_bytes.as_slice()).chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    "Unable to decode HD wallet account",
+                )
+            }),
+        }
+    }
+
     /// Generates keypair for given wallet and address type
     ///
     /// # Note
@@ -117,7 +268,8 @@ where
     ///
     /// - `purpose`: `44`
     /// - `coin_type`: `394` for mainnet and `1` for others
-    /// - `account`: `0` for `AddressType::Transfer` and `1` for `AddressType::Staking`
+    /// - `account`: `0` for `AddressType::Transfer` and `1` for `AddressType::Staking` (offset
+    ///   by `3 * wallet_account`, see `HdPath::wallet_key`)
     /// - `change`: `0`
     /// - `address_index`: Index of address as retrieved from storage
     pub fn generate_keypair(
@@ -171,27 +323,137 @@ where
             HDAccountType::Staking => hd_key.staking_index,
             HDAccountType::Viewkey => hd_key.viewkey_index,
         };
+        let path = HdPath::wallet_key(self.account(name, enckey)?, account_type, index)?;
+
+        hd_key.seed.derive_key_pair_at(get_network(), &path)
+    }
+
+    /// Returns index of next key of given type (i.e., number of keys derived so far)
+    pub fn next_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        account_type: HDAccountType,
+    ) -> Result<u32> {
+        let hd_key = self.get_hd_key(name, enckey)?;
+
+        Ok(match account_type {
+            HDAccountType::Transfer => hd_key.transfer_index,
+            HDAccountType::Staking => hd_key.staking_index,
+            HDAccountType::Viewkey => hd_key.viewkey_index,
+        })
+    }
+
+    /// Advances index of next key of given type to `next_index` (if it is currently lower), so that
+    /// keys below it (e.g., discovered by scanning) are not derived again
+    pub fn advance_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        account_type: HDAccountType,
+        next_index: u32,
+    ) -> Result<()> {
+        self.storage
+            .fetch_and_update_secure(KEYSPACE, name, enckey, |bytes| {
+                let mut hd_key_bytes = bytes.chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        format!("HD Key with name ({}) not found", name),
+                    )
+                })?;
 
-        hd_key
+                let mut hd_key = HdKey::decode(&mut hd_key_bytes).chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        "Unable to deserialize HD Key from bytes",
+                    )
+                })?;
+
+                let index = match account_type {
+                    HDAccountType::Staking => &mut hd_key.staking_index,
+                    HDAccountType::Transfer => &mut hd_key.transfer_index,
+                    HDAccountType::Viewkey => &mut hd_key.viewkey_index,
+                };
+                *index = (*index).max(next_index);
+
+                Ok(Some(hd_key.encode()))
+            })
+            .map(|_| ())
+    }
+
+    /// Derives keypair of given wallet at given key path (without changing indices of wallet)
+    pub fn derive_keypair_at(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        path: &HdPath,
+    ) -> Result<(PublicKey, PrivateKey)> {
+        self.get_hd_key(name, enckey)?
             .seed
-            .derive_key_pair(get_network(), account_type.index(), index)
+            .derive_key_pair_at(get_network(), path)
     }
 
     /// Clears all storage
     #[inline]
     pub fn clear(&self) -> Result<()> {
-        self.storage.clear(KEYSPACE)
+        self.storage.clear(KEYSPACE)?;
+        self.storage.clear(ACCOUNT_KEYSPACE)
+    }
+
+    /// Returns HD key (seed and indices) of wallet
+    pub fn get_hd_key(&self, name: &str, enckey: &SecKey) -> Result<HdKey> {
+        let hd_key_bytes = self.storage.get_secure(KEYSPACE, name, enckey)?.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("HD Key with name ({}) not found", name),
+            )
+        })?;
+        HdKey::decode(&mut hd_key_bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to decode HD key bytes",
+            )
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::WalletKind;
     use crate::wallet::{DefaultWalletClient, WalletClient};
-    use secstr::SecUtf8;
 
+    use chain_core::init::address::RedeemAddress;
+    use client_common::seckey::derive_enckey;
     use client_common::storage::MemoryStorage;
 
+    #[test]
+    fn check_hd_path() {
+        let path = "7'/1/5".parse::<HdPath>().unwrap();
+        assert_eq!(HdPath::new(7, 1, 5).unwrap(), path);
+        assert_eq!("7'/1/5", path.to_string());
+        assert_eq!("m/44'/394'/7'/1/5", path.chain_path(394));
+
+        for invalid in &["7/1/5", "7'/1", "7'/1/5/0", "x'/1/5", "2147483648'/0/0"] {
+            assert!(invalid.parse::<HdPath>().is_err());
+        }
+
+        // account `0` of wallet keeps the original paths
+        assert_eq!(
+            HdPath::new(0, 0, 3).unwrap(),
+            HdPath::wallet_key(0, HDAccountType::Transfer, 3).unwrap()
+        );
+        assert_eq!(
+            HdPath::new(1, 0, 3).unwrap(),
+            HdPath::wallet_key(0, HDAccountType::Staking, 3).unwrap()
+        );
+        assert_eq!(
+            HdPath::new(4, 0, 3).unwrap(),
+            HdPath::wallet_key(1, HDAccountType::Staking, 3).unwrap()
+        );
+        assert!(HdPath::wallet_key(u32::max_value() / 3, HDAccountType::Viewkey, 0).is_err());
+    }
+
     #[test]
     fn check_hd_key_encode_decode() {
         let hd_key = HdKey {
@@ -232,7 +494,7 @@ mod tests {
 
         let wallet = DefaultWalletClient::new_read_only(storage.clone());
         let enckey = wallet
-            .restore_wallet(&name, &passphrase, &mnemonic)
+            .restore_wallet(&name, &passphrase, &mnemonic, None)
             .expect("restore wallet");
 
         assert!(
@@ -278,7 +540,7 @@ mod tests {
 
         let wallet = DefaultWalletClient::new_read_only(storage.clone());
         let enckey = wallet
-            .restore_wallet(&name, &passphrase, &mnemonic)
+            .restore_wallet(&name, &passphrase, &mnemonic, None)
             .expect("restore wallet");
 
         // NOTE: addresses changed here in 0.4 due to migration to x-only pubkeys used in BIP-340
@@ -299,4 +561,131 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn check_deterministic_hdkey_mnemonic_passphrase() {
+        let storage = MemoryStorage::default();
+        let passphrase = SecUtf8::from("passphrase");
+        let mnemonic =
+            Mnemonic::from_secstr(&SecUtf8::from("speed tortoise kiwi forward extend baby acoustic foil coach castle ship purchase unlock base hip erode tag keen present vibrant oyster cotton write fetch")).unwrap();
+
+        let wallet = DefaultWalletClient::new_read_only(storage);
+        let fixtures = [
+            ("plain", None, "0x83fe11feb0887183eb62c30994bdd9e303497e3d"),
+            (
+                "trezor",
+                Some(SecUtf8::from("TREZOR")),
+                "0x7ee16ee155e82bfef4b64a4cdfad0f8043d58f2b",
+            ),
+        ];
+        for (name, mnemonic_passphrase, addr) in fixtures.iter() {
+            let enckey = wallet
+                .restore_wallet(name, &passphrase, &mnemonic, mnemonic_passphrase.as_ref())
+                .expect("restore wallet");
+            assert_eq!(
+                wallet
+                    .new_staking_address(name, &enckey)
+                    .expect("get new staking address")
+                    .to_string(),
+                *addr
+            );
+        }
+
+        assert!(wallet
+            .new_wallet(
+                "basic",
+                &passphrase,
+                WalletKind::Basic,
+                None,
+                Some(&SecUtf8::from("TREZOR"))
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn check_deterministic_hdkey_account() {
+        let storage = MemoryStorage::default();
+        let hd_key_service = HdKeyService::new(storage);
+        let name = "testhdwallet";
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let mnemonic =
+            Mnemonic::from_secstr(&SecUtf8::from("speed tortoise kiwi forward extend baby acoustic foil coach castle ship purchase unlock base hip erode tag keen present vibrant oyster cotton write fetch")).unwrap();
+
+        hd_key_service
+            .add_mnemonic(name, &mnemonic, None, 1, &enckey)
+            .unwrap();
+        assert_eq!(1, hd_key_service.account(name, &enckey).unwrap());
+
+        for addr in &[
+            "0xef39978a69f6ee48eb1e992770cb9f1228701850",
+            "0xf2426cc1a7c719a6cd2b380e311f8c04ee4391b7",
+        ] {
+            let (public_key, _) = hd_key_service
+                .generate_keypair(name, &enckey, HDAccountType::Staking)
+                .unwrap();
+            assert_eq!(RedeemAddress::from(&public_key).to_string(), *addr);
+        }
+
+        hd_key_service.delete_wallet(name, &enckey).unwrap();
+        hd_key_service
+            .add_mnemonic(name, &mnemonic, None, 0, &enckey)
+            .unwrap();
+        assert_eq!(0, hd_key_service.account(name, &enckey).unwrap());
+    }
+
+    #[test]
+    fn check_deterministic_hdkey_explicit_path() {
+        let storage = MemoryStorage::default();
+        let name = "testhdwallet";
+        let passphrase = SecUtf8::from("passphrase");
+        let mnemonic =
+            Mnemonic::from_secstr(&SecUtf8::from("speed tortoise kiwi forward extend baby acoustic foil coach castle ship purchase unlock base hip erode tag keen present vibrant oyster cotton write fetch")).unwrap();
+
+        let wallet = DefaultWalletClient::new_read_only(storage);
+        let enckey = wallet
+            .restore_wallet(&name, &passphrase, &mnemonic, None)
+            .expect("restore wallet");
+
+        for (path, addr) in &[
+            ("1'/0/0", "0x83fe11feb0887183eb62c30994bdd9e303497e3d"),
+            ("1'/1/0", "0x07ac773ea45751b7b42ef15d1fd010d04a1054a1"),
+            ("7'/0/5", "0x916a776b282f9e0631a84cdff7ebb6324b3da713"),
+        ] {
+            assert_eq!(
+                wallet
+                    .new_staking_address_at(&name, &enckey, &path.parse().unwrap())
+                    .expect("get staking address at path")
+                    .to_string(),
+                *addr
+            );
+        }
+
+        assert_eq!(
+            wallet
+                .new_transfer_address_at(&name, &enckey, &"0'/0/2".parse().unwrap())
+                .expect("get transfer address at path")
+                .to_string(),
+            "dcro1plg2e79emeypwaq2w2aryvteq9qefxhujc9xplslawz70dk6s8hstaw9kp"
+        );
+
+        // explicit paths do not advance indices of wallet
+        assert_eq!(
+            wallet
+                .new_transfer_address(&name, &enckey)
+                .expect("get new transfer address")
+                .to_string(),
+            "dcro1lgray2pkuqnkvd3hvhcvfta2ku5q0t3x8s03ehslu5xsauv4clfqv4yl40"
+        );
+
+        wallet
+            .new_wallet("basic", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let basic_enckey = derive_enckey(&passphrase, "basic").unwrap();
+        assert!(wallet
+            .new_staking_address_at("basic", &basic_enckey, &HdPath::new(1, 0, 0).unwrap())
+            .is_err());
+        assert!(wallet
+            .new_wallet("account", &passphrase, WalletKind::Basic, Some(1), None)
+            .is_err());
+    }
 }
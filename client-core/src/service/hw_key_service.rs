@@ -1,6 +1,42 @@
 #[cfg(feature = "mock-hardware-wallet")]
 use crate::service::MockHardwareService;
-use client_common::{ErrorKind, PrivateKeyAction, PublicKey, Result};
+use chain_core::init::coin::{sum_coins, Coin};
+use chain_core::tx::data::TxId;
+use chain_core::tx::TransactionId;
+use client_common::{ErrorKind, PrivateKeyAction, PublicKey, Result, ResultExt, Transaction};
+use secp256k1::recovery::RecoverableSignature;
+
+/// Details of a staking transaction presented on hardware wallet for confirmation
+#[derive(Debug, Clone, PartialEq)]
+pub struct StakingTxConfirmation {
+    /// Id of transaction
+    pub transaction_id: TxId,
+    /// Unbonded or withdrawn amount (`None` for transactions not moving funds, e.g. unjail)
+    pub amount: Option<Coin>,
+}
+
+impl StakingTxConfirmation {
+    /// Creates confirmation details of given staking transaction
+    pub fn new(tx: &Transaction) -> Result<Self> {
+        let amount = match tx {
+            Transaction::UnbondStakeTransaction(tx) => Some(tx.value),
+            Transaction::WithdrawUnbondedStakeTransaction(tx) => Some(
+                sum_coins(tx.outputs.iter().map(|output| output.value)).chain(|| {
+                    (
+                        ErrorKind::IllegalInput,
+                        "Sum of withdraw outputs exceeds maximum allowed amount",
+                    )
+                })?,
+            ),
+            _ => None,
+        };
+
+        Ok(Self {
+            transaction_id: tx.id(),
+            amount,
+        })
+    }
+}
 
 /// collection of hardware key interfaces
 pub trait HardwareWalletAction: Send + Sync + Clone {
@@ -18,6 +54,17 @@ pub trait HardwareWalletAction: Send + Sync + Clone {
     fn get_sign_key(&self, _public_key: &PublicKey) -> Result<Box<dyn PrivateKeyAction>> {
         Err(ErrorKind::PermissionDenied.into())
     }
+
+    /// sign a staking transaction after the user confirms its id and amount on device, return
+    /// an error of kind `ErrorKind::RejectedByUser` if the user rejects it
+    fn sign_staking_tx(
+        &self,
+        _public_key: &PublicKey,
+        _tx: &Transaction,
+        _confirmation: &StakingTxConfirmation,
+    ) -> Result<RecoverableSignature> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
 }
 
 /// unauthorized hardware key service
@@ -34,6 +81,9 @@ pub enum HwKeyService {
     /// mock key service
     #[cfg(feature = "mock-hardware-wallet")]
     Mock(MockHardwareService),
+    /// key service recording signed staking transactions
+    #[cfg(test)]
+    Recording(tests::RecordingHwKeyService),
 }
 
 impl Default for HwKeyService {
@@ -49,6 +99,8 @@ impl HwKeyService {
             HwKeyService::Unauthorized(hw_key_service) => hw_key_service.new_staking_address(),
             #[cfg(feature = "mock-hardware-wallet")]
             HwKeyService::Mock(hw_key_service) => hw_key_service.new_transfer_address(),
+            #[cfg(test)]
+            HwKeyService::Recording(hw_key_service) => hw_key_service.new_transfer_address(),
         }
     }
 
@@ -58,6 +110,8 @@ impl HwKeyService {
             HwKeyService::Unauthorized(hw_key_service) => hw_key_service.new_transfer_address(),
             #[cfg(feature = "mock-hardware-wallet")]
             HwKeyService::Mock(hw_key_service) => hw_key_service.new_staking_address(),
+            #[cfg(test)]
+            HwKeyService::Recording(hw_key_service) => hw_key_service.new_staking_address(),
         }
     }
 
@@ -67,6 +121,130 @@ impl HwKeyService {
             HwKeyService::Unauthorized(hw_key_service) => hw_key_service.get_sign_key(public_key),
             #[cfg(feature = "mock-hardware-wallet")]
             HwKeyService::Mock(hw_key_service) => hw_key_service.get_sign_key(public_key),
+            #[cfg(test)]
+            HwKeyService::Recording(hw_key_service) => hw_key_service.get_sign_key(public_key),
+        }
+    }
+
+    /// sign a staking transaction after the user confirms it on device
+    pub fn sign_staking_tx(
+        &self,
+        public_key: &PublicKey,
+        tx: &Transaction,
+    ) -> Result<RecoverableSignature> {
+        let confirmation = StakingTxConfirmation::new(tx)?;
+        match self {
+            HwKeyService::Unauthorized(hw_key_service) => {
+                hw_key_service.sign_staking_tx(public_key, tx, &confirmation)
+            }
+            #[cfg(feature = "mock-hardware-wallet")]
+            HwKeyService::Mock(hw_key_service) => {
+                hw_key_service.sign_staking_tx(public_key, tx, &confirmation)
+            }
+            #[cfg(test)]
+            HwKeyService::Recording(hw_key_service) => {
+                hw_key_service.sign_staking_tx(public_key, tx, &confirmation)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::state::account::{StakedStateAddress, StakedStateOpAttributes, UnbondTx};
+    use client_common::{Error, PrivateKey};
+
+    /// Hardware key service keeping keys in memory and recording confirmations of signed
+    /// staking transactions
+    #[derive(Debug, Clone, Default)]
+    pub struct RecordingHwKeyService {
+        /// Keys stored on device
+        pub keys: Arc<Mutex<BTreeMap<Vec<u8>, PrivateKey>>>,
+        /// Confirmations presented to the user
+        pub confirmations: Arc<Mutex<Vec<StakingTxConfirmation>>>,
+        /// `true` if the user rejects transactions
+        pub reject: bool,
+    }
+
+    impl RecordingHwKeyService {
+        fn new_key(&self) -> Result<PublicKey> {
+            let private_key = PrivateKey::new()?;
+            let public_key = PublicKey::from(&private_key);
+            self.keys
+                .lock()
+                .unwrap()
+                .insert(public_key.serialize(), private_key);
+            Ok(public_key)
+        }
+
+        fn private_key(&self, public_key: &PublicKey) -> Result<PrivateKey> {
+            self.keys
+                .lock()
+                .unwrap()
+                .get(&public_key.serialize())
+                .cloned()
+                .chain(|| (ErrorKind::InvalidInput, "Unknown hardware key"))
+        }
+    }
+
+    impl HardwareWalletAction for RecordingHwKeyService {
+        fn new_transfer_address(&self) -> Result<PublicKey> {
+            self.new_key()
+        }
+
+        fn new_staking_address(&self) -> Result<PublicKey> {
+            self.new_key()
         }
+
+        fn get_sign_key(&self, public_key: &PublicKey) -> Result<Box<dyn PrivateKeyAction>> {
+            Ok(Box::new(self.private_key(public_key)?))
+        }
+
+        fn sign_staking_tx(
+            &self,
+            public_key: &PublicKey,
+            tx: &Transaction,
+            confirmation: &StakingTxConfirmation,
+        ) -> Result<RecoverableSignature> {
+            self.confirmations
+                .lock()
+                .unwrap()
+                .push(confirmation.clone());
+
+            if self.reject {
+                return Err(Error::new(
+                    ErrorKind::RejectedByUser,
+                    "Transaction rejected on device",
+                ));
+            }
+
+            self.private_key(public_key)?.sign(tx)
+        }
+    }
+
+    #[test]
+    fn check_staking_tx_confirmation() {
+        let address = StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]));
+        let tx = Transaction::UnbondStakeTransaction(UnbondTx::new(
+            address,
+            0,
+            Coin::new(100).unwrap(),
+            StakedStateOpAttributes::new(0),
+        ));
+
+        let confirmation = StakingTxConfirmation::new(&tx).unwrap();
+        assert_eq!(tx.id(), confirmation.transaction_id);
+        assert_eq!(Some(Coin::new(100).unwrap()), confirmation.amount);
+
+        let error = HwKeyService::default()
+            .sign_staking_tx(&PublicKey::from(&PrivateKey::new().unwrap()), &tx)
+            .unwrap_err();
+        assert_eq!(ErrorKind::PermissionDenied, error.kind());
     }
 }
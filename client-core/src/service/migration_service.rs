@@ -0,0 +1,322 @@
+use parity_scale_codec::{Decode, Encode};
+
+use client_common::{Error, ErrorKind, Result, ResultExt, Storage};
+
+use super::broadcast_log_service::BroadcastLogService;
+use super::wallet_service::WalletService;
+
+/// Keyspace of storage schema version
+const KEYSPACE: &str = "core_schema";
+/// Key of storage schema version
+const VERSION_KEY: &str = "version";
+
+/// Version of storage schema written by this version of client
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Migration of storage from one schema version to the next one
+///
+/// Schema version is recorded only after `migrate` succeeds, so migrations have to be safe to
+/// run again on partially migrated storage.
+pub trait Migration<S: Storage> {
+    /// Schema version this migration is applied to
+    fn version_from(&self) -> u32;
+
+    /// Schema version of storage after this migration
+    fn version_to(&self) -> u32;
+
+    /// Migrates storage
+    fn migrate(&self, storage: &S) -> Result<()>;
+}
+
+/// Returns schema version recorded in storage (`0` if storage was written before versions were
+/// recorded)
+pub fn schema_version<S: Storage>(storage: &S) -> Result<u32> {
+    match storage.get(KEYSPACE, VERSION_KEY)? {
+        None => Ok(0),
+        Some(bytes) => u32::decode(&mut bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to decode storage schema version",
+            )
+        }),
+    }
+}
+
+/// Applies given migrations to storage until it reaches the highest schema version they migrate
+/// to, returning the resulting schema version
+///
+/// Storage with schema version newer than any of given migrations is refused, so that it is never
+/// misread by an older client.
+pub fn run_migrations<S: Storage>(
+    storage: &S,
+    migrations: &[Box<dyn Migration<S>>],
+) -> Result<u32> {
+    let supported = migrations
+        .iter()
+        .map(|migration| migration.version_to())
+        .max()
+        .unwrap_or_default();
+    let mut version = check_schema_version(storage, supported)?;
+
+    while version < supported {
+        let migration = migrations
+            .iter()
+            .find(|migration| {
+                migration.version_from() == version && migration.version_to() > version
+            })
+            .chain(|| {
+                (
+                    ErrorKind::InternalError,
+                    format!("No migration from storage schema version {}", version),
+                )
+            })?;
+
+        migration.migrate(storage).chain(|| {
+            (
+                ErrorKind::StorageError,
+                format!(
+                    "Unable to migrate storage from schema version {} to {}",
+                    version,
+                    migration.version_to()
+                ),
+            )
+        })?;
+        version = migration.version_to();
+        storage.set(KEYSPACE, VERSION_KEY, version.encode())?;
+    }
+
+    Ok(version)
+}
+
+/// Migrates storage to current schema version (`SCHEMA_VERSION`)
+///
+/// Read-only storage cannot be migrated, so its schema version is only checked: it is refused if
+/// it is newer than current one and used as it is (with a warning) if it is older.
+pub fn migrate_storage<S: Storage>(storage: &S) -> Result<()> {
+    if !storage.is_read_only() {
+        return run_migrations(storage, &migrations()).map(|_| ());
+    }
+
+    let version = check_schema_version(storage, SCHEMA_VERSION)?;
+    if version < SCHEMA_VERSION {
+        log::warn!(
+            "Storage schema version ({}) is older than current schema version ({}), open it for writing to migrate it",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// Returns schema version recorded in storage, refusing storage with schema version newer than
+/// `supported` one
+fn check_schema_version<S: Storage>(storage: &S, supported: u32) -> Result<u32> {
+    let version = schema_version(storage)?;
+
+    if version > supported {
+        Err(Error::new(
+            ErrorKind::StorageError,
+            format!(
+                "Storage schema version ({}) is newer than supported schema version ({}), please upgrade client",
+                version, supported
+            ),
+        ))
+    } else {
+        Ok(version)
+    }
+}
+
+/// Returns all migrations up to current schema version
+pub fn migrations<S: Storage>() -> Vec<Box<dyn Migration<S>>> {
+    vec![
+        Box::new(StakingKeyIndexMigration),
+        Box::new(BroadcastLogTimeIndexMigration),
+    ]
+}
+
+/// Indexes staking keys of all wallets by redeem address (schema version `0` to `1`)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StakingKeyIndexMigration;
+
+impl<S: Storage> Migration<S> for StakingKeyIndexMigration {
+    fn version_from(&self) -> u32 {
+        0
+    }
+
+    fn version_to(&self) -> u32 {
+        1
+    }
+
+    fn migrate(&self, storage: &S) -> Result<()> {
+        let wallet_service = WalletService::new(storage.clone());
+        for name in wallet_service.names()? {
+            wallet_service.backfill_staking_key_index(&name)?;
+        }
+        Ok(())
+    }
+}
+
+/// Indexes broadcast log entries by timestamp (schema version `1` to `2`)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BroadcastLogTimeIndexMigration;
+
+impl<S: Storage> Migration<S> for BroadcastLogTimeIndexMigration {
+    fn version_from(&self) -> u32 {
+        1
+    }
+
+    fn version_to(&self) -> u32 {
+        2
+    }
+
+    fn migrate(&self, storage: &S) -> Result<()> {
+        BroadcastLogService::new(storage.clone()).backfill_time_index()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secstr::SecUtf8;
+
+    use chain_core::init::address::RedeemAddress;
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::{MemoryStorage, TestStorage};
+    use client_common::{PrivateKey, PublicKey};
+
+    use crate::service::BroadcastOutcome;
+    use crate::types::WalletKind;
+
+    /// Migration recording its run in storage
+    struct RecordingMigration(u32);
+
+    impl Migration<MemoryStorage> for RecordingMigration {
+        fn version_from(&self) -> u32 {
+            self.0
+        }
+
+        fn version_to(&self) -> u32 {
+            self.0 + 1
+        }
+
+        fn migrate(&self, storage: &MemoryStorage) -> Result<()> {
+            storage.set("migrated", self.0.to_string(), vec![])?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn check_run_migrations() {
+        let storage = MemoryStorage::default();
+        let migrations: Vec<Box<dyn Migration<MemoryStorage>>> = vec![
+            Box::new(RecordingMigration(1)),
+            Box::new(RecordingMigration(0)),
+        ];
+
+        assert_eq!(0, schema_version(&storage).unwrap());
+        assert_eq!(2, run_migrations(&storage, &migrations).unwrap());
+        assert_eq!(2, schema_version(&storage).unwrap());
+        assert_eq!(2, storage.keys("migrated").unwrap().len());
+
+        // migrations are applied only once
+        storage.clear("migrated").unwrap();
+        assert_eq!(2, run_migrations(&storage, &migrations).unwrap());
+        assert!(storage.keys("migrated").unwrap().is_empty());
+
+        // storage written by a newer client is refused
+        let error = run_migrations(&storage, &migrations[1..]).unwrap_err();
+        assert_eq!(ErrorKind::StorageError, error.kind());
+        assert_eq!(2, schema_version(&storage).unwrap());
+
+        // missing migration
+        let storage = MemoryStorage::default();
+        assert!(run_migrations(&storage, &migrations[..1]).is_err());
+        assert_eq!(0, schema_version(&storage).unwrap());
+    }
+
+    #[test]
+    fn check_migrate_read_only_storage() {
+        // schema version 0 is used as it is
+        let storage = TestStorage::default().with_read_only();
+        migrate_storage(&storage).unwrap();
+        assert_eq!(0, storage.take_writes());
+        assert_eq!(0, schema_version(&storage).unwrap());
+
+        // storage written by a newer client is refused
+        let storage = TestStorage::default();
+        storage
+            .set(KEYSPACE, VERSION_KEY, (SCHEMA_VERSION + 1).encode())
+            .unwrap();
+        let storage = storage.with_read_only();
+        let error = migrate_storage(&storage).unwrap_err();
+        assert_eq!(ErrorKind::StorageError, error.kind());
+    }
+
+    #[test]
+    fn check_staking_key_index_migration() {
+        let storage = MemoryStorage::default();
+        let wallet_service = WalletService::new(storage.clone());
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), "name").unwrap();
+        let view_key = PublicKey::from(&PrivateKey::new().unwrap());
+        wallet_service
+            .create("name", &enckey, view_key, WalletKind::Basic)
+            .unwrap();
+        let staking_key = PublicKey::from(&PrivateKey::new().unwrap());
+        wallet_service
+            .add_staking_key("name", &enckey, &staking_key)
+            .unwrap();
+        let redeem_address = RedeemAddress::from(&staking_key).to_string();
+
+        // version 0: staking keys are not indexed
+        storage
+            .delete("core_wallet_name_stakingkeyset", &redeem_address)
+            .unwrap();
+        storage
+            .delete("core_wallet_name_info", "stakingkeysetindexed")
+            .unwrap();
+
+        migrate_storage(&storage).unwrap();
+
+        assert_eq!(SCHEMA_VERSION, schema_version(&storage).unwrap());
+        assert_eq!(
+            Some(staking_key.serialize()),
+            storage
+                .get("core_wallet_name_stakingkeyset", &redeem_address)
+                .unwrap()
+        );
+        assert!(storage
+            .get("core_wallet_name_info", "stakingkeysetindexed")
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn check_broadcast_log_time_index_migration() {
+        let storage = MemoryStorage::default();
+        storage.set(KEYSPACE, VERSION_KEY, 1u32.encode()).unwrap();
+
+        // version 1: broadcast log entries are not indexed by timestamp
+        let transaction_id = [1; 32];
+        let entry = (
+            transaction_id,
+            "unbond".to_owned(),
+            0u64,
+            None::<BroadcastOutcome>,
+        );
+        storage
+            .set(
+                "core_broadcast_log",
+                hex::encode(transaction_id),
+                entry.encode(),
+            )
+            .unwrap();
+
+        migrate_storage(&storage).unwrap();
+
+        assert_eq!(SCHEMA_VERSION, schema_version(&storage).unwrap());
+        assert_eq!(
+            vec![format!("{:020}{}", 0, hex::encode(transaction_id)).into_bytes()],
+            storage.keys("core_broadcast_log_time").unwrap()
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::hd_wallet::KeyChain;
 use crate::hd_wallet::{ChainPath, DefaultKeyChain, ExtendedPrivKey};
-use crate::service::hw_key_service::HardwareWalletAction;
+use crate::service::hw_key_service::{HardwareWalletAction, StakingTxConfirmation};
 use crate::service::{HDAccountType, HdKey};
 use crate::{HDSeed, Mnemonic};
 use client_common::{
@@ -311,6 +311,21 @@ impl HardwareWalletAction for MockHardwareService {
         };
         Ok(Box::new(hw_key))
     }
+
+    fn sign_staking_tx(
+        &self,
+        public_key: &PublicKey,
+        tx: &Transaction,
+        confirmation: &StakingTxConfirmation,
+    ) -> Result<RecoverableSignature> {
+        // mock device confirms every transaction
+        log::info!(
+            "confirm staking transaction {} with amount {:?}",
+            hex::encode(confirmation.transaction_id),
+            confirmation.amount
+        );
+        self.get_sign_key(public_key)?.sign(tx)
+    }
 }
 
 mod protocol {
@@ -1,13 +1,16 @@
+use parity_scale_codec::{Decode, Encode};
 use secp256k1::schnorrsig::SchnorrSignature;
 
 use chain_core::common::H256;
+use chain_core::tx::data::Tx;
 use client_common::{
     ErrorKind, PrivateKey, PublicKey, Result, ResultExt, SecKey, SecureStorage, Storage,
 };
 
-use crate::multi_sig::MultiSigBuilder;
+use crate::multi_sig::{CosignerContribution, MultiSigBuilder};
 
 const KEYSPACE: &str = "core_multi_sig_address";
+const TRANSACTION_KEYSPACE: &str = "core_multi_sig_transaction";
 
 /// Maintains mapping `multi-sig session-id -> multi-sig session`
 #[derive(Debug, Default, Clone)]
@@ -169,6 +172,87 @@ where
         Ok(session.public_keys())
     }
 
+    /// Returns contributions of all signers in this session
+    pub fn cosigner_contributions(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+    ) -> Result<Vec<CosignerContribution>> {
+        let session = self.get_session(session_id, enckey)?;
+        Ok(session.cosigner_contributions())
+    }
+
+    /// Returns public keys of signers whose nonce commitments are not received yet
+    pub fn missing_nonce_commitments(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+    ) -> Result<Vec<PublicKey>> {
+        let mut session = self.get_session(session_id, enckey)?;
+        missing_signers(&mut session, |session, public_key| {
+            session.has_nonce_commitment(public_key)
+        })
+    }
+
+    /// Returns public keys of signers whose nonces are not received yet
+    pub fn missing_nonces(&self, session_id: &H256, enckey: &SecKey) -> Result<Vec<PublicKey>> {
+        let mut session = self.get_session(session_id, enckey)?;
+        missing_signers(&mut session, |session, public_key| {
+            session.has_nonce(public_key)
+        })
+    }
+
+    /// Returns public keys of signers whose partial signatures are not received yet
+    pub fn missing_partial_signatures(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+    ) -> Result<Vec<PublicKey>> {
+        let mut session = self.get_session(session_id, enckey)?;
+        missing_signers(&mut session, |session, public_key| {
+            session.has_partial_signature(public_key)
+        })
+    }
+
+    /// Persists unsigned transaction signed in session with given id
+    pub fn set_transaction(
+        &self,
+        session_id: &H256,
+        transaction: &Tx,
+        enckey: &SecKey,
+    ) -> Result<()> {
+        self.storage
+            .set_secure(
+                TRANSACTION_KEYSPACE,
+                session_id,
+                transaction.encode(),
+                enckey,
+            )
+            .map(|_| ())
+    }
+
+    /// Returns unsigned transaction signed in session with given id
+    pub fn transaction(&self, session_id: &H256, enckey: &SecKey) -> Result<Tx> {
+        let transaction_bytes = self
+            .storage
+            .get_secure(TRANSACTION_KEYSPACE, session_id, enckey)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Transaction of session with ID ({}) not found",
+                        hex::encode(session_id)
+                    ),
+                )
+            })?;
+        Tx::decode(&mut transaction_bytes.as_slice()).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Unable to deserialize transaction of multi-sig session",
+            )
+        })
+    }
+
     /// Retrieves a session from storage
     fn get_session(&self, session_id: &H256, enckey: &SecKey) -> Result<MultiSigBuilder> {
         let session_bytes = self
@@ -196,6 +280,20 @@ where
     }
 }
 
+/// Returns public keys of signers in session for which `has` returns false
+fn missing_signers<F>(session: &mut MultiSigBuilder, mut has: F) -> Result<Vec<PublicKey>>
+where
+    F: FnMut(&mut MultiSigBuilder, &PublicKey) -> Result<bool>,
+{
+    let mut missing = Vec::new();
+    for public_key in session.public_keys() {
+        if !has(session, &public_key)? {
+            missing.push(public_key);
+        }
+    }
+    Ok(missing)
+}
+
 #[cfg(test)]
 mod multi_sig_session_service_tests {
     use super::*;
@@ -251,6 +349,12 @@ mod multi_sig_session_service_tests {
         let nonce_commitment_2 = multi_sig_service
             .nonce_commitment(&session_id_2, &enckey)
             .unwrap();
+        assert_eq!(
+            vec![public_key_2.clone()],
+            multi_sig_service
+                .missing_nonce_commitments(&session_id_1, &enckey)
+                .unwrap()
+        );
 
         multi_sig_service
             .add_nonce_commitment(&session_id_1, nonce_commitment_2, &public_key_2, &enckey)
@@ -0,0 +1,246 @@
+use parity_scale_codec::{Decode, Encode};
+
+use client_common::storage::{decrypt_bytes, encrypt_bytes};
+use client_common::{ErrorKind, Result, ResultExt, SecKey, Storage, StorageOp};
+
+/// Keyspace of commit markers (`wallet-name -> number of staged records`)
+const KEYSPACE: &str = "core_passphrase_change";
+
+fn get_staging_keyspace(name: &str) -> String {
+    format!("{}_{}", KEYSPACE, name)
+}
+
+/// Returns `true` if given keyspace holds commit markers or staged records of passphrase changes
+pub fn is_passphrase_change_keyspace(keyspace: &[u8]) -> bool {
+    keyspace.starts_with(KEYSPACE.as_bytes())
+}
+
+/// Re-encrypts all records of a wallet with a new enckey
+///
+/// Records are re-encrypted into a staging keyspace first and a commit marker is written in the
+/// same batch of writes. Staged records are then swapped in (and marker removed) in another batch.
+/// Interrupted change is either discarded (if marker was not written) or completed (if it was) by
+/// `recover`, so that all records of wallet are always encrypted with the same enckey.
+#[derive(Debug, Default, Clone)]
+pub struct PassphraseService<T: Storage> {
+    storage: T,
+}
+
+impl<T> PassphraseService<T>
+where
+    T: Storage,
+{
+    /// Creates a new instance of passphrase service
+    #[inline]
+    pub fn new(storage: T) -> Self {
+        PassphraseService { storage }
+    }
+
+    /// Re-encrypts all records of wallet encrypted with `old_enckey` with `new_enckey`
+    pub fn change_enckey(
+        &self,
+        name: &str,
+        old_enckey: &SecKey,
+        new_enckey: &SecKey,
+    ) -> Result<()> {
+        self.recover(name)?;
+
+        let staging_keyspace = get_staging_keyspace(name);
+        let records = self.wallet_records(old_enckey)?;
+        let mut ops = Vec::with_capacity(records.len() + 1);
+        for (index, (keyspace, key, value)) in records.iter().enumerate() {
+            let cipher = encrypt_bytes(key, new_enckey, value)?;
+            ops.push(StorageOp::set(
+                &staging_keyspace,
+                index.to_string(),
+                (keyspace, key, cipher).encode(),
+            ));
+        }
+
+        // commit point: staged records are swapped in even if this process dies after it
+        ops.push(StorageOp::set(
+            KEYSPACE,
+            name,
+            (records.len() as u64).encode(),
+        ));
+        self.storage.write_batch(ops)?;
+        self.recover(name)
+    }
+
+    /// Completes (or discards) an interrupted change of enckey of wallet
+    pub fn recover(&self, name: &str) -> Result<()> {
+        let staging_keyspace = get_staging_keyspace(name);
+
+        if let Some(marker) = self.storage.get(KEYSPACE, name)? {
+            let count = u64::decode(&mut marker.as_slice()).chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    "Unable to decode number of staged records",
+                )
+            })?;
+
+            let mut ops = Vec::with_capacity(count as usize + 1);
+            for index in 0..count {
+                let staged = self
+                    .storage
+                    .get(&staging_keyspace, index.to_string())?
+                    .chain(|| {
+                        (
+                            ErrorKind::StorageError,
+                            format!("Staged record {} of wallet ({}) not found", index, name),
+                        )
+                    })?;
+                let (keyspace, key, cipher) =
+                    <(Vec<u8>, Vec<u8>, Vec<u8>)>::decode(&mut staged.as_slice()).chain(|| {
+                        (
+                            ErrorKind::DeserializationError,
+                            "Unable to decode staged record",
+                        )
+                    })?;
+                ops.push(StorageOp::set(keyspace, key, cipher));
+            }
+
+            ops.push(StorageOp::delete(KEYSPACE, name));
+            self.storage.write_batch(ops)?;
+        }
+
+        let has_staged_records = self
+            .storage
+            .keyspaces()?
+            .iter()
+            .any(|keyspace| keyspace.as_slice() == staging_keyspace.as_bytes());
+        if has_staged_records {
+            self.storage.clear(&staging_keyspace)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns keyspace, key and decrypted value of all records encrypted with given enckey
+    #[allow(clippy::type_complexity)]
+    fn wallet_records(&self, enckey: &SecKey) -> Result<Vec<(Vec<u8>, Vec<u8>, Vec<u8>)>> {
+        let mut records = Vec::new();
+
+        for keyspace in self.storage.keyspaces()? {
+            if is_passphrase_change_keyspace(&keyspace) {
+                continue;
+            }
+
+            for record in self.storage.iter_prefix(&keyspace, b"")? {
+                let (key, value) = record?;
+                // records of other wallets (and unencrypted records) fail to decrypt
+                if let Ok(decrypted) = decrypt_bytes(&key, enckey, &value) {
+                    records.push((keyspace.clone(), key, decrypted));
+                }
+            }
+        }
+
+        Ok(records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secstr::SecUtf8;
+
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::{MemoryStorage, TestStorage};
+    use client_common::SecureStorage;
+
+    fn enckey(passphrase: &str, name: &str) -> SecKey {
+        derive_enckey(&SecUtf8::from(passphrase), name).unwrap()
+    }
+
+    #[test]
+    fn check_change_enckey() {
+        let storage = MemoryStorage::default();
+        let service = PassphraseService::new(storage.clone());
+        let old_enckey = enckey("old", "name");
+        let new_enckey = enckey("new", "name");
+        let other_enckey = enckey("old", "other");
+
+        storage
+            .set_secure("a", "name", b"record".to_vec(), &old_enckey)
+            .unwrap();
+        storage
+            .set_secure("b", "key", b"another record".to_vec(), &old_enckey)
+            .unwrap();
+        storage
+            .set_secure("a", "other", b"other wallet".to_vec(), &other_enckey)
+            .unwrap();
+        storage.set("a", "plain", vec![1]).unwrap();
+
+        service
+            .change_enckey("name", &old_enckey, &new_enckey)
+            .unwrap();
+
+        assert_eq!(
+            Some(b"record".to_vec()),
+            storage.get_secure("a", "name", &new_enckey).unwrap()
+        );
+        assert_eq!(
+            Some(b"another record".to_vec()),
+            storage.get_secure("b", "key", &new_enckey).unwrap()
+        );
+        assert!(storage.get_secure("a", "name", &old_enckey).is_err());
+        assert_eq!(
+            Some(b"other wallet".to_vec()),
+            storage.get_secure("a", "other", &other_enckey).unwrap()
+        );
+        assert_eq!(Some(vec![1]), storage.get("a", "plain").unwrap());
+
+        // staged records are cleaned up
+        assert!(storage.get(KEYSPACE, "name").unwrap().is_none());
+        assert!(storage
+            .keys(get_staging_keyspace("name"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn check_interrupted_change_enckey() {
+        let storage = TestStorage::default();
+        let service = PassphraseService::new(storage.clone());
+        let old_enckey = enckey("old", "name");
+        let new_enckey = enckey("new", "name");
+
+        let records = (0..5u8)
+            .map(|i| (format!("keyspace{}", i % 2), format!("key{}", i), vec![i]))
+            .collect::<Vec<_>>();
+        for (keyspace, key, value) in records.iter() {
+            storage
+                .set_secure(keyspace, key, value.clone(), &old_enckey)
+                .unwrap();
+        }
+        let encrypted_with = |enckey: &SecKey| {
+            records.iter().all(|(keyspace, key, value)| {
+                storage.get_secure(keyspace, key, enckey).ok() == Some(Some(value.clone()))
+            })
+        };
+
+        let (mut discarded, mut completed) = (0, 0);
+        for writes in 0.. {
+            storage.fail_after(Some(writes));
+            let result = service.change_enckey("name", &old_enckey, &new_enckey);
+            storage.fail_after(None);
+            if result.is_ok() {
+                break;
+            }
+
+            service.recover("name").unwrap();
+            if encrypted_with(&old_enckey) {
+                discarded += 1;
+            } else {
+                assert!(encrypted_with(&new_enckey));
+                completed += 1;
+                service
+                    .change_enckey("name", &new_enckey, &old_enckey)
+                    .unwrap();
+            }
+        }
+
+        assert!(discarded > 0 && completed > 0);
+        assert!(encrypted_with(&new_enckey));
+    }
+}
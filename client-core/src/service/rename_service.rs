@@ -0,0 +1,210 @@
+use client_common::storage::encrypt_bytes;
+use client_common::{Error, ErrorKind, Result, SecKey, Storage};
+
+use super::wallet_service::{get_wallet_keyspace, get_wallet_keyspaces, wallet_records};
+use super::WalletService;
+
+/// Moves all records of a wallet under a new wallet name
+///
+/// Records are copied (and re-encrypted) under the new name first. Old records are deleted only
+/// after the copy is verified, so that an interrupted rename never loses the old wallet.
+#[derive(Debug, Default, Clone)]
+pub struct RenameService<T: Storage> {
+    storage: T,
+}
+
+impl<T> RenameService<T>
+where
+    T: Storage,
+{
+    /// Creates a new instance of rename service
+    #[inline]
+    pub fn new(storage: T) -> Self {
+        RenameService { storage }
+    }
+
+    /// Renames wallet `old_name` to `new_name`, re-encrypting its records with `new_enckey`
+    ///
+    /// `verify` is called once all records are copied under the new name; old records are
+    /// deleted only if it succeeds (copied records are discarded otherwise).
+    pub fn rename<F>(
+        &self,
+        old_name: &str,
+        new_name: &str,
+        old_enckey: &SecKey,
+        new_enckey: &SecKey,
+        verify: F,
+    ) -> Result<()>
+    where
+        F: FnOnce() -> Result<()>,
+    {
+        let names = WalletService::new(self.storage.clone()).names()?;
+        if !names.iter().any(|name| name == old_name) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Wallet with name ({}) not found", old_name),
+            ));
+        }
+        if names.iter().any(|name| name == new_name) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Wallet with name ({}) already exists", new_name),
+            ));
+        }
+
+        let records = wallet_records(&self.storage, old_name, old_enckey)?;
+        let old_keyspaces = get_wallet_keyspaces(old_name);
+        let new_keyspaces = get_wallet_keyspaces(new_name);
+        let wallet_keyspace = get_wallet_keyspace().into_bytes();
+
+        // (record, new keyspace, new key, new value)
+        let mut moved = Vec::new();
+        let mut in_place = Vec::new();
+        for record in records.iter() {
+            if !record.named {
+                in_place.push(record);
+                continue;
+            }
+
+            let (keyspace, key) = match old_keyspaces
+                .iter()
+                .position(|old| old.as_bytes() == record.keyspace.as_slice())
+            {
+                Some(index) => (new_keyspaces[index].as_bytes().to_vec(), record.key.clone()),
+                None => (record.keyspace.clone(), new_name.as_bytes().to_vec()),
+            };
+            let value = if record.keyspace == wallet_keyspace {
+                new_name.as_bytes().to_vec()
+            } else {
+                match record.decrypted {
+                    Some(ref decrypted) => encrypt_bytes(&key, new_enckey, decrypted)?,
+                    None => record.value.clone(),
+                }
+            };
+            moved.push((record, keyspace, key, value));
+        }
+
+        let mut copied = Vec::with_capacity(moved.len());
+        for (record, keyspace, key, value) in moved.iter() {
+            // wallet becomes visible under new name only after verification
+            if record.keyspace == wallet_keyspace {
+                continue;
+            }
+            if let Err(err) = self.storage.set(keyspace, key, value.clone()) {
+                self.storage.wipe(&copied)?;
+                return Err(err);
+            }
+            copied.push((keyspace, key));
+        }
+
+        if let Err(err) = verify() {
+            self.storage.wipe(&copied)?;
+            return Err(err);
+        }
+
+        for (record, keyspace, key, value) in moved.iter() {
+            if record.keyspace == wallet_keyspace {
+                self.storage.set(keyspace, key, value.clone())?;
+            }
+        }
+        for record in in_place {
+            if let Some(ref decrypted) = record.decrypted {
+                let cipher = encrypt_bytes(&record.key, new_enckey, decrypted)?;
+                self.storage.set(&record.keyspace, &record.key, cipher)?;
+            }
+        }
+        let old_records = moved
+            .iter()
+            .map(|(record, ..)| (&record.keyspace, &record.key))
+            .collect::<Vec<_>>();
+        self.storage.wipe(&old_records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secstr::SecUtf8;
+
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+    use client_common::{PrivateKey, PublicKey, SecureStorage};
+
+    use crate::types::WalletKind;
+
+    fn enckey(passphrase: &str, name: &str) -> SecKey {
+        derive_enckey(&SecUtf8::from(passphrase), name).unwrap()
+    }
+
+    #[test]
+    fn check_rename() {
+        let storage = MemoryStorage::default();
+        let wallet_service = WalletService::new(storage.clone());
+        let service = RenameService::new(storage.clone());
+        let old_enckey = enckey("passphrase", "old");
+        let new_enckey = enckey("passphrase", "new");
+        let other_enckey = enckey("passphrase", "other");
+
+        let view_key = PublicKey::from(&PrivateKey::new().unwrap());
+        wallet_service
+            .create("old", &old_enckey, view_key.clone(), WalletKind::Basic)
+            .unwrap();
+        wallet_service
+            .create("other", &other_enckey, view_key.clone(), WalletKind::Basic)
+            .unwrap();
+        storage
+            .set_secure("state", "old", b"state".to_vec(), &old_enckey)
+            .unwrap();
+        storage.set("sync", "old", vec![1]).unwrap();
+        storage
+            .set_secure("session", "id", b"session".to_vec(), &old_enckey)
+            .unwrap();
+
+        // collisions are rejected before anything is copied
+        assert!(service
+            .rename("old", "other", &old_enckey, &new_enckey, || Ok(()))
+            .is_err());
+
+        // failed verification discards copied records
+        assert!(service
+            .rename("old", "new", &old_enckey, &new_enckey, || Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Verification failed"
+            )))
+            .is_err());
+        assert!(storage.get("state", "new").unwrap().is_none());
+        assert_eq!(
+            view_key,
+            wallet_service.view_key("old", &old_enckey).unwrap()
+        );
+
+        service
+            .rename("old", "new", &old_enckey, &new_enckey, || Ok(()))
+            .unwrap();
+
+        assert_eq!(vec!["new".to_owned(), "other".to_owned()], {
+            let mut names = wallet_service.names().unwrap();
+            names.sort();
+            names
+        });
+        assert_eq!(
+            view_key,
+            wallet_service.view_key("new", &new_enckey).unwrap()
+        );
+        assert!(wallet_service.view_key("old", &old_enckey).is_err());
+        assert_eq!(
+            view_key,
+            wallet_service.view_key("other", &other_enckey).unwrap()
+        );
+        assert_eq!(
+            Some(b"state".to_vec()),
+            storage.get_secure("state", "new", &new_enckey).unwrap()
+        );
+        assert!(storage.get("state", "old").unwrap().is_none());
+        assert_eq!(Some(vec![1]), storage.get("sync", "new").unwrap());
+        assert_eq!(
+            Some(b"session".to_vec()),
+            storage.get_secure("session", "id", &new_enckey).unwrap()
+        );
+    }
+}
@@ -34,7 +34,18 @@ where
     ) -> Result<(H256, MultiSigAddress)> {
         let multi_sig_address =
             MultiSigAddress::new(public_keys, self_public_key, required_signers)?;
+        let root_hash = self.add_multi_sig_address(name, &multi_sig_address, enckey)?;
 
+        Ok((root_hash, multi_sig_address))
+    }
+
+    /// Persists an existing multi-sig address (e.g., from a backup) and returns its root hash
+    pub fn add_multi_sig_address(
+        &self,
+        name: &str,
+        multi_sig_address: &MultiSigAddress,
+        enckey: &SecKey,
+    ) -> Result<H256> {
         let root_hash = multi_sig_address.root_hash();
 
         // key: roothash
@@ -47,7 +58,7 @@ where
             enckey,
         )?;
 
-        Ok((root_hash, multi_sig_address))
+        Ok(root_hash)
     }
 
     /// delete root hash
@@ -80,6 +91,13 @@ where
         Ok(address.required_signers())
     }
 
+    /// Returns the total number of cosigners for given root_hash
+    pub fn total_signers(&self, name: &str, root_hash: &H256, enckey: &SecKey) -> Result<usize> {
+        let address = self.get_multi_sig_address_from_root_hash(name, root_hash, enckey)?;
+
+        Ok(address.total_signers())
+    }
+
     /// Returns public key of current signer
     pub fn public_key(&self, name: &str, root_hash: &H256, enckey: &SecKey) -> Result<PublicKey> {
         let address = self.get_multi_sig_address_from_root_hash(name, root_hash, enckey)?;
@@ -89,7 +107,7 @@ where
 
     /// Returns MultiSig address from storage with the given root_hash
     /// decrypted with enckey
-    fn get_multi_sig_address_from_root_hash(
+    pub fn get_multi_sig_address_from_root_hash(
         &self,
         name: &str,
         root_hash: &H256,
@@ -1,12 +1,14 @@
 use indexmap::IndexSet;
 use parity_scale_codec::{Decode, Encode, Input, Output};
 
-use crate::service::{load_wallet_state, WalletState};
-use crate::types::WalletKind;
+use super::passphrase_service::is_passphrase_change_keyspace;
+use crate::service::{load_wallet_state, HdKeyService, WalletState};
+use crate::types::{LabeledAddress, NetworkInfo, WalletKind, WalletMetadata};
 use chain_core::common::H256;
 use chain_core::init::address::RedeemAddress;
 use chain_core::state::account::StakedStateAddress;
 use chain_core::tx::data::address::ExtendedAddr;
+use client_common::storage::decrypt_bytes;
 use client_common::{
     Error, ErrorKind, PrivateKey, PublicKey, Result, ResultExt, SecKey, SecureStorage, Storage,
 };
@@ -16,6 +18,8 @@ use serde::export::PhantomData;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
 use std::str;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroize;
 /// Key space of wallet
 const KEYSPACE: &str = "core_wallet";
 
@@ -43,6 +47,10 @@ fn get_roothashset_keyspace(name: &str) -> String {
     format!("{}_{}_roothashset", KEYSPACE, name)
 }
 
+fn get_importedkey_keyspace(name: &str) -> String {
+    format!("{}_{}_importedkey", KEYSPACE, name)
+}
+
 pub fn get_multisig_keyspace(name: &str) -> String {
     format!("{}_{}_multisigaddress", KEYSPACE, name)
 }
@@ -51,10 +59,25 @@ fn get_info_keyspace(name: &str) -> String {
     format!("{}_{}_info", KEYSPACE, name)
 }
 
-fn get_wallet_keyspace() -> String {
+pub fn get_wallet_keyspace() -> String {
     format!("{}_walletname", KEYSPACE)
 }
 
+/// Returns keyspaces holding records of given wallet only
+pub fn get_wallet_keyspaces(name: &str) -> Vec<String> {
+    vec![
+        get_info_keyspace(name),
+        get_public_keyspace(name),
+        get_stakingkey_keyspace(name),
+        get_stakingkeyset_keyspace(name),
+        get_private_keyspace(name),
+        get_roothash_keyspace(name),
+        get_roothashset_keyspace(name),
+        get_multisig_keyspace(name),
+        get_importedkey_keyspace(name),
+    ]
+}
+
 fn serde_to_str<T, S>(value: &T, serializer: S) -> std::result::Result<S::Ok, S::Error>
 where
     T: Encode,
@@ -106,6 +129,24 @@ pub struct WalletInfo {
     pub private_key: PrivateKey,
     /// passphrase used when import wallet
     pub passphrase: Option<SecUtf8>,
+    /// labels of addresses
+    #[serde(default)]
+    pub labels: Vec<(LabeledAddress, String)>,
+}
+
+/// Watch-only wallet information (view key and public keys, without any signing key) to export
+/// and import
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WatchOnlyWalletInfo {
+    /// name of the the wallet
+    pub name: String,
+    /// private view key to decrypt enclave transactions
+    #[serde(deserialize_with = "deserde_from_str", serialize_with = "serde_to_str")]
+    pub view_key: PrivateKey,
+    /// public keys of (1-of-1) transfer addresses
+    pub public_keys: Vec<PublicKey>,
+    /// public keys of staking addresses
+    pub staking_keys: Vec<PublicKey>,
 }
 
 /// Wallet meta data
@@ -266,6 +307,79 @@ fn write_number<S: SecureStorage>(
     Ok(())
 }
 
+/// Record of a wallet in storage (decrypted value is zeroized on drop)
+#[derive(Debug)]
+pub(crate) struct WalletRecord {
+    /// Keyspace of record
+    pub keyspace: Vec<u8>,
+    /// Key of record
+    pub key: Vec<u8>,
+    /// Stored value of record
+    pub value: Vec<u8>,
+    /// Decrypted value of record (if it is encrypted with wallet's enckey)
+    pub decrypted: Option<Vec<u8>>,
+    /// Whether the record is stored under wallet's name (in its own keyspaces or with its name as
+    /// key) as opposed to being found by decryption only (e.g., multi-sig sessions)
+    pub named: bool,
+}
+
+impl Drop for WalletRecord {
+    fn drop(&mut self) {
+        if let Some(ref mut decrypted) = self.decrypted {
+            decrypted.zeroize();
+        }
+    }
+}
+
+/// Finds all records of given wallet in storage
+pub(crate) fn wallet_records<S: Storage>(
+    storage: &S,
+    name: &str,
+    enckey: &SecKey,
+) -> Result<Vec<WalletRecord>> {
+    let own_keyspaces = get_wallet_keyspaces(name);
+    // index keys in keyspaces of other wallets may collide with wallet name
+    let other_keyspaces = WalletService::new(storage.clone())
+        .names()?
+        .iter()
+        .filter(|other| *other != name)
+        .flat_map(|other| get_wallet_keyspaces(other))
+        .collect::<Vec<_>>();
+
+    let mut records = Vec::new();
+
+    for keyspace in storage.keyspaces()? {
+        if is_passphrase_change_keyspace(&keyspace)
+            || other_keyspaces
+                .iter()
+                .any(|other| other.as_bytes() == keyspace.as_slice())
+        {
+            continue;
+        }
+        let own_keyspace = own_keyspaces
+            .iter()
+            .any(|own| own.as_bytes() == keyspace.as_slice());
+
+        for record in storage.iter_prefix(&keyspace, b"")? {
+            let (key, value) = record?;
+            let decrypted = decrypt_bytes(&key, enckey, &value).ok();
+            let named = own_keyspace || key.as_slice() == name.as_bytes();
+
+            if named || decrypted.is_some() {
+                records.push(WalletRecord {
+                    keyspace: keyspace.clone(),
+                    key,
+                    value,
+                    decrypted,
+                    named,
+                });
+            }
+        }
+    }
+
+    Ok(records)
+}
+
 /// Load wallet from storage
 pub fn load_wallet<S: SecureStorage>(
     storage: &S,
@@ -366,6 +480,8 @@ where
         for public_key in wallet.staking_keys.iter() {
             self.add_staking_key(name, enckey, public_key)?;
         }
+        // all staking keys are indexed by redeem address from now on
+        write_number(&self.storage, &info_keyspace, "stakingkeysetindexed", 1)?;
 
         // root hash
         write_number(&self.storage, &info_keyspace, "roothashindex", 0)?;
@@ -382,7 +498,11 @@ where
         self.save_wallet(name, enckey, &wallet)
     }
 
-    /// Finds staking key corresponding to given redeem address
+    /// Finds staking key corresponding to given redeem address (`None` if it is not a staking
+    /// address of given wallet)
+    ///
+    /// Staking keys are looked up in their index by redeem address. Index of a wallet stored
+    /// before it was known to be complete is backfilled on first miss.
     // TODO: change api not to use _enckey
     pub fn find_staking_key(
         &self,
@@ -390,17 +510,71 @@ where
         _enckey: &SecKey,
         redeem_address: &RedeemAddress,
     ) -> Result<Option<PublicKey>> {
-        let stakingkey_keyspace = get_stakingkeyset_keyspace(name);
+        if let Some(staking_key) = self.indexed_staking_key(name, redeem_address)? {
+            return Ok(Some(staking_key));
+        }
 
-        if let Ok(value) = read_pubkey(
+        if !self.storage.contains_key(KEYSPACE, name)? {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Wallet with name ({}) not found", name),
+            ));
+        }
+        let info_keyspace = get_info_keyspace(name);
+        if read_number(
             &self.storage,
-            &stakingkey_keyspace,
-            &redeem_address.to_string(),
-        ) {
-            Ok(Some(value))
-        } else {
-            Err(Error::new(ErrorKind::InvalidInput, "finding staking"))
+            &info_keyspace,
+            "stakingkeysetindexed",
+            Some(0),
+        )? != 0
+        {
+            return Ok(None);
+        }
+
+        self.backfill_staking_key_index(name)?;
+        self.indexed_staking_key(name, redeem_address)
+    }
+
+    fn indexed_staking_key(
+        &self,
+        name: &str,
+        redeem_address: &RedeemAddress,
+    ) -> Result<Option<PublicKey>> {
+        self.storage
+            .get(get_stakingkeyset_keyspace(name), redeem_address.to_string())?
+            .map(|value| PublicKey::deserialize_from(&value))
+            .transpose()
+    }
+
+    /// Adds all staking keys of given wallet to index of staking keys by redeem address (unless
+    /// the index is already complete)
+    pub(crate) fn backfill_staking_key_index(&self, name: &str) -> Result<()> {
+        let stakingkey_keyspace = get_stakingkey_keyspace(name);
+        let stakingkeyset_keyspace = get_stakingkeyset_keyspace(name);
+        let info_keyspace = get_info_keyspace(name);
+        if read_number(
+            &self.storage,
+            &info_keyspace,
+            "stakingkeysetindexed",
+            Some(0),
+        )? != 0
+        {
+            return Ok(());
+        }
+
+        let staking_count: u64 =
+            read_number(&self.storage, &info_keyspace, "stakingkeyindex", Some(0))?;
+        for i in 0..staking_count {
+            let staking_key = read_pubkey(&self.storage, &stakingkey_keyspace, &format!("{}", i))?;
+            write_pubkey(
+                &self.storage,
+                &stakingkeyset_keyspace,
+                &RedeemAddress::from(&staking_key).to_string(),
+                &staking_key,
+            )?;
         }
+
+        write_number(&self.storage, &info_keyspace, "stakingkeysetindexed", 1)
     }
 
     /// Finds private_key corresponding to given public_key
@@ -425,6 +599,21 @@ where
         }
     }
 
+    /// Returns all key pairs (of transfer and staking addresses) stored in a wallet
+    pub fn key_pairs(&self, name: &str, enckey: &SecKey) -> Result<Vec<(PublicKey, PrivateKey)>> {
+        let private_keyspace = get_private_keyspace(name);
+
+        let mut key_pairs = Vec::new();
+        for key in self.storage.keys(&private_keyspace)? {
+            let public_key = PublicKey::deserialize_from(&key)?;
+            let private_key = self
+                .find_private_key(name, enckey, &public_key)?
+                .chain(|| (ErrorKind::InvalidInput, "private_key not found"))?;
+            key_pairs.push((public_key, private_key));
+        }
+        Ok(key_pairs)
+    }
+
     /// Checks if root hash exists in current wallet and returns root hash if exists
     // TODO: change api not to use _enckey
     pub fn find_root_hash(
@@ -476,6 +665,14 @@ where
         // value: view-key
         write_pubkey_enc(&self.storage, &info_keyspace, "viewkey", &view_key, enckey)?;
 
+        // key: "createdat"
+        // value: seconds since unix epoch
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .chain(|| (ErrorKind::InternalError, "System time is before unix epoch"))?;
+        write_number(&self.storage, &info_keyspace, "createdat", created_at)?;
+
         // key: index
         // value: walletname
         let wallet_keyspace = get_wallet_keyspace();
@@ -687,6 +884,118 @@ where
         Ok(())
     }
 
+    /// Flags public key of given wallet as imported (i.e., not derived from HD key of wallet)
+    // TODO: change api not to use _enckey
+    pub fn add_imported_key(
+        &self,
+        name: &str,
+        _enckey: &SecKey,
+        public_key: &PublicKey,
+    ) -> Result<()> {
+        // key: public key (hex)
+        // value: public key
+        write_pubkey(
+            &self.storage,
+            &get_importedkey_keyspace(name),
+            &hex::encode(public_key.serialize()),
+            public_key,
+        )
+    }
+
+    /// Returns all imported public keys of given wallet
+    // TODO: change api not to use _enckey
+    pub fn imported_keys(&self, name: &str, _enckey: &SecKey) -> Result<IndexSet<PublicKey>> {
+        let importedkey_keyspace = get_importedkey_keyspace(name);
+
+        let mut ret = IndexSet::new();
+        for key in self.storage.keys(&importedkey_keyspace)? {
+            let value = self
+                .storage
+                .get(&importedkey_keyspace, key)?
+                .chain(|| (ErrorKind::InvalidInput, "imported key not found"))?;
+            ret.insert(PublicKey::deserialize_from(&value)?);
+        }
+        Ok(ret)
+    }
+
+    /// Records network given wallet is used on
+    pub fn set_network(&self, name: &str, network: &NetworkInfo) -> Result<()> {
+        // key: "network"
+        // value: network info
+        self.storage
+            .set(get_info_keyspace(name), "network", network.encode())?;
+        Ok(())
+    }
+
+    /// Returns recorded network of given wallet (`None` if wallet was created before networks
+    /// were recorded)
+    pub fn network(&self, name: &str) -> Result<Option<NetworkInfo>> {
+        if !self.storage.contains_key(KEYSPACE, name)? {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Wallet with name ({}) not found", name),
+            ));
+        }
+
+        self.storage
+            .get(get_info_keyspace(name), "network")?
+            .map(|value| {
+                NetworkInfo::decode(&mut value.as_slice()).chain(|| {
+                    (
+                        ErrorKind::DeserializationError,
+                        "Unable to decode network of wallet",
+                    )
+                })
+            })
+            .transpose()
+    }
+
+    /// Returns metadata of given wallet (read from its unencrypted records, so that it does not
+    /// need enckey)
+    ///
+    /// Every record it is built from is also stored by wallets created before metadata was
+    /// queried, except creation time.
+    pub fn metadata(&self, name: &str) -> Result<WalletMetadata> {
+        if !self.storage.contains_key(KEYSPACE, name)? {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Wallet with name ({}) not found", name),
+            ));
+        }
+
+        let info_keyspace = get_info_keyspace(name);
+        let created_at = if self.storage.contains_key(&info_keyspace, "createdat")? {
+            Some(read_number(
+                &self.storage,
+                &info_keyspace,
+                "createdat",
+                None,
+            )?)
+        } else {
+            None
+        };
+
+        Ok(WalletMetadata {
+            name: name.to_owned(),
+            kind: read_number(&self.storage, &info_keyspace, "walletkind", Some(0))?.into(),
+            created_at,
+            network: self.network(name)?,
+            hd: HdKeyService::new(self.storage.clone()).has_wallet(name)?,
+            transfer_address_count: read_number(
+                &self.storage,
+                &info_keyspace,
+                "roothashindex",
+                Some(0),
+            )?,
+            staking_address_count: read_number(
+                &self.storage,
+                &info_keyspace,
+                "stakingkeyindex",
+                Some(0),
+            )?,
+        })
+    }
+
     /// Adds a multi-sig address to given wallet
     // TODO: change api not to use _enckey
     pub fn add_root_hash(&self, name: &str, _enckey: &SecKey, root_hash: H256) -> Result<()> {
@@ -771,6 +1080,7 @@ where
         let roothash_keyspace = get_roothash_keyspace(name);
         let roothashset_keyspace = get_roothashset_keyspace(name);
         let multisigaddress_keyspace = get_multisig_keyspace(name);
+        let importedkey_keyspace = get_importedkey_keyspace(name);
         let wallet_keyspace = get_wallet_keyspace();
         self.storage.delete(wallet_keyspace, name)?;
         self.storage.clear(info_keyspace)?;
@@ -781,6 +1091,7 @@ where
         self.storage.clear(public_keyspace)?;
         self.storage.clear(private_keyspace)?;
         self.storage.clear(multisigaddress_keyspace)?;
+        self.storage.clear(importedkey_keyspace)?;
         Ok(())
     }
     /// Delete the key
@@ -798,7 +1109,7 @@ mod tests {
     use super::*;
     use secstr::SecUtf8;
 
-    use client_common::storage::MemoryStorage;
+    use client_common::storage::{MemoryStorage, TestStorage};
     use client_common::{seckey::derive_enckey, PrivateKey};
 
     #[test]
@@ -859,4 +1170,70 @@ mod tests {
 
         assert_eq!(error.kind(), ErrorKind::InvalidInput);
     }
+
+    #[test]
+    fn check_find_staking_key_is_indexed() {
+        let storage = TestStorage::default();
+        let wallet_service = WalletService::new(storage.clone());
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), "name").unwrap();
+        let view_key = PublicKey::from(&PrivateKey::new().unwrap());
+        wallet_service
+            .create("name", &enckey, view_key, WalletKind::Basic)
+            .unwrap();
+
+        let staking_keys = (0..3000)
+            .map(|_| PublicKey::from(&PrivateKey::new().unwrap()))
+            .collect::<Vec<_>>();
+        for staking_key in staking_keys.iter() {
+            wallet_service
+                .add_staking_key("name", &enckey, staking_key)
+                .unwrap();
+        }
+        let unknown = RedeemAddress::from(&PublicKey::from(&PrivateKey::new().unwrap()));
+
+        // lookup of first and last key costs the same (single read), misses are also constant
+        storage.take_reads();
+        for staking_key in [&staking_keys[0], &staking_keys[2999]].iter() {
+            assert_eq!(
+                Some((*staking_key).clone()),
+                wallet_service
+                    .find_staking_key("name", &enckey, &RedeemAddress::from(*staking_key))
+                    .unwrap()
+            );
+            assert_eq!(1, storage.take_reads());
+        }
+        assert_eq!(
+            None,
+            wallet_service
+                .find_staking_key("name", &enckey, &unknown)
+                .unwrap()
+        );
+        assert!(storage.take_reads() <= 3);
+        assert!(wallet_service
+            .find_staking_key("other", &enckey, &unknown)
+            .is_err());
+
+        // wallet stored before its index was known to be complete is backfilled once
+        let last = RedeemAddress::from(&staking_keys[2999]);
+        storage
+            .delete(get_stakingkeyset_keyspace("name"), last.to_string())
+            .unwrap();
+        storage
+            .delete(get_info_keyspace("name"), "stakingkeysetindexed")
+            .unwrap();
+        assert_eq!(
+            Some(staking_keys[2999].clone()),
+            wallet_service
+                .find_staking_key("name", &enckey, &last)
+                .unwrap()
+        );
+        assert!(storage.take_reads() > 3000);
+        assert_eq!(
+            None,
+            wallet_service
+                .find_staking_key("name", &enckey, &unknown)
+                .unwrap()
+        );
+        assert!(storage.take_reads() <= 3);
+    }
 }
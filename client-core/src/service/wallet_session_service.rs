@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use client_common::{Error, ErrorKind, Result, SecKey};
+
+/// Default maximum number of simultaneously unlocked sessions of a wallet
+pub const DEFAULT_MAX_SESSIONS_PER_WALLET: usize = 8;
+
+/// Token of an unlocked wallet session
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SessionToken(String);
+
+impl SessionToken {
+    /// Returns token as a string
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for SessionToken {
+    #[inline]
+    fn from(token: String) -> Self {
+        SessionToken(token)
+    }
+}
+
+impl fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // token unlocks a wallet, so it is never printed
+        write!(f, "SessionToken(***)")
+    }
+}
+
+/// Unlocked wallet (enckey is zeroized when session is dropped)
+#[derive(Debug)]
+struct Session {
+    name: String,
+    enckey: SecKey,
+    expires_at: Instant,
+}
+
+impl Session {
+    fn is_expired(&self, now: Instant) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Keeps enckeys of unlocked wallets in memory for a limited time, so that callers only need to
+/// keep a session token
+///
+/// Sessions are shared between clones of the service; all enckeys are wiped once the last clone is
+/// dropped.
+#[derive(Debug, Clone)]
+pub struct WalletSessionService {
+    sessions: Arc<Mutex<HashMap<SessionToken, Session>>>,
+    max_sessions_per_wallet: usize,
+}
+
+impl Default for WalletSessionService {
+    #[inline]
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_SESSIONS_PER_WALLET)
+    }
+}
+
+impl WalletSessionService {
+    /// Creates a new instance of wallet session service (oldest session of a wallet is locked
+    /// when more than `max_sessions_per_wallet` sessions are unlocked)
+    pub fn new(max_sessions_per_wallet: usize) -> Self {
+        Self {
+            sessions: Default::default(),
+            max_sessions_per_wallet: max_sessions_per_wallet.max(1),
+        }
+    }
+
+    /// Starts a new session of wallet with given enckey, valid for `ttl`
+    pub fn unlock(&self, name: &str, enckey: SecKey, ttl: Duration) -> Result<SessionToken> {
+        let now = Instant::now();
+        let expires_at = now.checked_add(ttl).ok_or_else(|| {
+            Error::new(ErrorKind::InvalidInput, "Session time to live is too long")
+        })?;
+
+        let mut sessions = self.sessions()?;
+        sessions.retain(|_, session| !session.is_expired(now));
+
+        let mut wallet_sessions = sessions
+            .iter()
+            .filter(|(_, session)| session.name == name)
+            .map(|(token, session)| (session.expires_at, token.clone()))
+            .collect::<Vec<_>>();
+        if wallet_sessions.len() >= self.max_sessions_per_wallet {
+            wallet_sessions.sort();
+            let excess = wallet_sessions.len() + 1 - self.max_sessions_per_wallet;
+            for (_, token) in wallet_sessions.into_iter().take(excess) {
+                sessions.remove(&token);
+            }
+        }
+
+        let mut bytes = [0; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token = SessionToken(hex::encode(&bytes));
+        sessions.insert(
+            token.clone(),
+            Session {
+                name: name.to_owned(),
+                enckey,
+                expires_at,
+            },
+        );
+        Ok(token)
+    }
+
+    /// Returns name and enckey of wallet of given session
+    pub fn get(&self, token: &SessionToken) -> Result<(String, SecKey)> {
+        let now = Instant::now();
+        let mut sessions = self.sessions()?;
+
+        match sessions.get(token) {
+            Some(session) if !session.is_expired(now) => {
+                Ok((session.name.clone(), session.enckey.clone()))
+            }
+            Some(_) => {
+                sessions.remove(token);
+                Err(Error::new(
+                    ErrorKind::InvalidSession,
+                    "Wallet session is expired",
+                ))
+            }
+            None => Err(Error::new(
+                ErrorKind::InvalidSession,
+                "Wallet session is locked or does not exist",
+            )),
+        }
+    }
+
+    /// Ends given session (wiping its enckey)
+    pub fn lock(&self, token: &SessionToken) -> Result<()> {
+        match self.sessions()?.remove(token) {
+            Some(_) => Ok(()),
+            None => Err(Error::new(
+                ErrorKind::InvalidSession,
+                "Wallet session is locked or does not exist",
+            )),
+        }
+    }
+
+    /// Ends all sessions of given wallet
+    pub fn lock_wallet(&self, name: &str) -> Result<()> {
+        self.sessions()?.retain(|_, session| session.name != name);
+        Ok(())
+    }
+
+    fn sessions(&self) -> Result<MutexGuard<'_, HashMap<SessionToken, Session>>> {
+        self.sessions.lock().map_err(|_| {
+            Error::new(
+                ErrorKind::InternalError,
+                "Unable to acquire lock on wallet sessions",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use secstr::SecUtf8;
+
+    use client_common::seckey::derive_enckey;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn enckey(name: &str) -> SecKey {
+        derive_enckey(&SecUtf8::from("passphrase"), name).unwrap()
+    }
+
+    #[test]
+    fn check_sessions() {
+        let service = WalletSessionService::default();
+
+        let token = service.unlock("name", enckey("name"), TTL).unwrap();
+        let other = service.unlock("other", enckey("other"), TTL).unwrap();
+        assert_eq!(
+            ("name".to_owned(), enckey("name")),
+            service.get(&token).unwrap()
+        );
+
+        service.lock(&token).unwrap();
+        assert_eq!(
+            ErrorKind::InvalidSession,
+            service.get(&token).unwrap_err().kind()
+        );
+        assert_eq!(
+            ErrorKind::InvalidSession,
+            service.lock(&token).unwrap_err().kind()
+        );
+        assert!(service.get(&other).is_ok());
+
+        let expired = service
+            .unlock("name", enckey("name"), Duration::from_secs(0))
+            .unwrap();
+        assert_eq!(
+            ErrorKind::InvalidSession,
+            service.get(&expired).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn check_sessions_bounded_per_wallet() {
+        let service = WalletSessionService::new(2);
+
+        let first = service.unlock("name", enckey("name"), TTL).unwrap();
+        let other = service.unlock("other", enckey("other"), TTL).unwrap();
+        let second = service.unlock("name", enckey("name"), TTL * 2).unwrap();
+        let third = service.unlock("name", enckey("name"), TTL * 3).unwrap();
+
+        // session expiring first is locked
+        assert!(service.get(&first).is_err());
+        assert!(service.get(&second).is_ok());
+        assert!(service.get(&third).is_ok());
+        assert!(service.get(&other).is_ok());
+
+        service.lock_wallet("name").unwrap();
+        assert!(service.get(&second).is_err());
+        assert!(service.get(&third).is_err());
+        assert!(service.get(&other).is_ok());
+    }
+}
@@ -1,13 +1,20 @@
-use parity_scale_codec::{Decode, Encode};
-use std::collections::BTreeMap;
+use indexmap::IndexMap;
+use parity_scale_codec::{Decode, Encode, Error as CodecError, Input};
+use std::cell::Cell;
+use std::collections::{BTreeMap, BTreeSet};
 
 use chain_core::{
-    init::coin::{sum_coins, CoinError},
+    common::Timespec,
+    init::coin::{sum_coins, Coin, CoinError},
     tx::data::{input::TxoPointer, output::TxOut, TxId},
 };
 use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, SecureStorage, Storage};
 
-use crate::types::{TransactionChange, TransactionPending, WalletBalance};
+use crate::types::{
+    AddressBalance, BalanceChange, DetailedBalance, HistoryFilter, HistoryPage, LabeledAddress,
+    PendingReconciliation, PendingTransactionInfo, PendingTransactionStatus, TransactionChange,
+    TransactionPending, UtxoFilter, UtxoInfo, WalletBalance,
+};
 
 /// key space of wallet state
 const KEYSPACE: &str = "core_wallet_state";
@@ -68,6 +75,78 @@ where
         }
     }
 
+    /// Returns unspent transactions of given wallet which coin selection may spend (neither used
+    /// by pending transactions nor frozen)
+    #[inline]
+    pub fn get_selectable_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<BTreeMap<TxoPointer, TxOut>> {
+        Ok(self
+            .get_wallet_state(name, enckey)?
+            .get_selectable_transactions())
+    }
+
+    /// Returns frozen unspent transactions of given wallet
+    #[inline]
+    pub fn get_frozen_utxos(&self, name: &str, enckey: &SecKey) -> Result<BTreeSet<TxoPointer>> {
+        Ok(self.get_wallet_state(name, enckey)?.frozen_utxos)
+    }
+
+    /// Freezes (`frozen` is `true`) or unfreezes given unspent transaction of given wallet
+    pub fn set_utxo_frozen(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        pointer: &TxoPointer,
+        frozen: bool,
+    ) -> Result<()> {
+        self.modify_state(name, enckey, |state| {
+            if !state.unspent_transactions.contains_key(pointer) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "UTXO not found in wallet: {}:{}",
+                        hex::encode(&pointer.id),
+                        pointer.index
+                    ),
+                ));
+            }
+            let mut memento = WalletStateMemento::default();
+            if frozen {
+                memento.freeze_utxo(pointer.clone());
+            } else {
+                memento.unfreeze_utxo(pointer.clone());
+            }
+            state.apply_memento(&memento)
+        })
+    }
+
+    /// Returns unspent transactions (including the ones used by pending transactions) of given
+    /// wallet which pass given filter, in order of the filter
+    #[inline]
+    pub fn list_unspent(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        filter: &UtxoFilter,
+    ) -> Result<Vec<UtxoInfo>> {
+        Ok(self.get_wallet_state(name, enckey)?.list_unspent(filter))
+    }
+
+    /// Returns balances and activity of given addresses of given wallet (in given order)
+    pub fn get_address_balances(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        addresses: Vec<LabeledAddress>,
+    ) -> Result<Vec<AddressBalance>> {
+        self.get_wallet_state(name, enckey)?
+            .get_address_balances(addresses)
+            .chain(|| (ErrorKind::StorageError, "Calculate balance error"))
+    }
+
     /// Returns `true` or `false` depending if input is unspent or not. `true` if the input is unspent, `false`
     /// otherwise
     pub fn are_inputs_unspent(
@@ -106,6 +185,53 @@ where
         })
     }
 
+    /// Returns a page of currently stored transaction history (without transactions which do not
+    /// change balance) passing given filter for given wallet
+    ///
+    /// History is stored in a single encrypted wallet state record, so it cannot be paginated with
+    /// `Storage::iter_prefix`: whole state is decrypted and only the page is cloned from it.
+    #[inline]
+    pub fn get_transaction_history_page(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        filter: &HistoryFilter,
+        offset: usize,
+        limit: usize,
+        reversed: bool,
+    ) -> Result<HistoryPage> {
+        Ok(self
+            .get_wallet_state(name, enckey)?
+            .get_transaction_history_page(filter, offset, limit, reversed))
+    }
+
+    /// Returns pending information of given transaction if it is still pending
+    #[inline]
+    pub fn get_pending_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tx_id: &TxId,
+    ) -> Result<Option<TransactionPending>> {
+        Ok(self
+            .get_wallet_state(name, enckey)?
+            .pending_transactions
+            .remove(tx_id))
+    }
+
+    /// Returns transactions broadcast by given wallet (pending ones and the ones which are no
+    /// longer pending) along with their status
+    #[inline]
+    pub fn list_pending_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<Vec<PendingTransactionInfo>> {
+        Ok(self
+            .get_wallet_state(name, enckey)?
+            .list_pending_transactions())
+    }
+
     /// Returns currently stored transaction change for given wallet and transaction id
     #[inline]
     pub fn get_transaction_change(
@@ -138,6 +264,21 @@ where
         Ok(balance)
     }
 
+    /// Returns currently stored balance for given wallet, broken down by spendability at given
+    /// time (seconds since unix epoch)
+    pub fn get_detailed_balance(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        now: Timespec,
+    ) -> Result<DetailedBalance> {
+        let wallet_state = self.get_wallet_state(name, enckey)?;
+        let balance = wallet_state
+            .get_detailed_balance(now)
+            .chain(|| (ErrorKind::StorageError, "Calculate balance error"))?;
+        Ok(balance)
+    }
+
     fn modify_state<F>(&self, name: &str, enckey: &SecKey, f: F) -> Result<()>
     where
         F: Fn(&mut WalletState) -> Result<()>,
@@ -161,6 +302,45 @@ where
         self.modify_state(name, enckey, |state| state.apply_memento(memento))
     }
 
+    /// Removes pending information of given transaction (unlocking its inputs if it is still
+    /// pending)
+    pub fn forget_pending_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tx_id: &TxId,
+    ) -> Result<()> {
+        self.modify_state(name, enckey, |state| {
+            if !state.pending_transactions.contains_key(tx_id)
+                && !state.settled_transactions.contains_key(tx_id)
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Pending transaction not found: {}", hex::encode(tx_id)),
+                ));
+            }
+            let mut memento = WalletStateMemento::default();
+            memento.remove_pending_transaction(*tx_id);
+            state.apply_memento(&memento)
+        })
+    }
+
+    /// Removes pending information of transactions of given wallet settled before given block
+    /// height (see `WalletState::prune_settled_transactions`), returning number of removed records
+    pub fn prune_settled_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        before_height: u64,
+    ) -> Result<usize> {
+        let removed = Cell::new(0);
+        self.modify_state(name, enckey, |state| {
+            removed.set(state.prune_settled_transactions(before_height));
+            Ok(())
+        })?;
+        Ok(removed.get())
+    }
+
     /// Deletes all the state data corresponding to a wallet
     #[inline]
     pub fn delete_wallet_state(&self, name: &str, enckey: &SecKey) -> Result<()> {
@@ -241,7 +421,7 @@ pub fn delete_wallet_state<S: Storage>(storage: &S, name: &str) -> Result<()> {
 }
 
 /// Wallet state
-#[derive(Debug, Encode, Decode)]
+#[derive(Debug, Encode)]
 pub struct WalletState {
     /// UTxO
     pub unspent_transactions: BTreeMap<TxoPointer, TxOut>,
@@ -251,6 +431,50 @@ pub struct WalletState {
     pub transaction_history: BTreeMap<TxId, TransactionChange>,
     /// Transaction ids ordered by insert order.
     pub transaction_log: Vec<TxId>,
+    /// Pending information of transactions which are no longer pending (confirmed or expired)
+    /// indexed by txid
+    pub settled_transactions: BTreeMap<TxId, TransactionPending>,
+    /// Unspent transactions which coin selection never spends
+    pub frozen_utxos: BTreeSet<TxoPointer>,
+    /// Outcome of checking confirmed transactions in `settled_transactions` against their pending
+    /// information indexed by txid
+    pub reconciliations: BTreeMap<TxId, PendingReconciliation>,
+}
+
+impl Decode for WalletState {
+    fn decode<I: Input>(input: &mut I) -> std::result::Result<Self, CodecError> {
+        let unspent_transactions = <BTreeMap<TxoPointer, TxOut>>::decode(input)?;
+        let pending_transactions = <BTreeMap<TxId, TransactionPending>>::decode(input)?;
+        let transaction_history = <BTreeMap<TxId, TransactionChange>>::decode(input)?;
+        let transaction_log = <Vec<TxId>>::decode(input)?;
+        // states stored before settled transactions were tracked end here
+        let settled_transactions = if input.remaining_len()? == Some(0) {
+            BTreeMap::new()
+        } else {
+            <BTreeMap<TxId, TransactionPending>>::decode(input)?
+        };
+        // states stored before outputs could be frozen end here
+        let frozen_utxos = if input.remaining_len()? == Some(0) {
+            BTreeSet::new()
+        } else {
+            <BTreeSet<TxoPointer>>::decode(input)?
+        };
+        // states stored before confirmed transactions were reconciled end here
+        let reconciliations = if input.remaining_len()? == Some(0) {
+            BTreeMap::new()
+        } else {
+            <BTreeMap<TxId, PendingReconciliation>>::decode(input)?
+        };
+        Ok(WalletState {
+            unspent_transactions,
+            pending_transactions,
+            transaction_history,
+            transaction_log,
+            settled_transactions,
+            frozen_utxos,
+            reconciliations,
+        })
+    }
 }
 
 impl Default for WalletState {
@@ -261,13 +485,16 @@ impl Default for WalletState {
             pending_transactions: Default::default(),
             transaction_history: Default::default(),
             transaction_log: vec![],
+            settled_transactions: Default::default(),
+            frozen_utxos: Default::default(),
+            reconciliations: Default::default(),
         }
     }
 }
 
 impl WalletState {
-    /// if the txid can not be found in the latest `block_height_ensure` blocks after it broadcast
-    /// we need to rollback
+    /// if the txid can not be found before its `valid_until_height` we need to rollback
+    /// (`block_height_ensure` blocks after it broadcast for records without `valid_until_height`)
     pub fn get_rollback_pending_tx(
         &self,
         current_block_height: u64,
@@ -276,7 +503,12 @@ impl WalletState {
         self.pending_transactions
             .iter()
             .filter_map(|(key, value)| {
-                if value.block_height + block_height_ensure < current_block_height {
+                let valid_until_height = if value.valid_until_height == 0 {
+                    value.block_height + block_height_ensure
+                } else {
+                    value.valid_until_height
+                };
+                if valid_until_height < current_block_height {
                     Some(*key)
                 } else {
                     None
@@ -285,6 +517,55 @@ impl WalletState {
             .collect()
     }
 
+    /// Returns transactions broadcast by the wallet along with their status (a settled
+    /// transaction is confirmed if it is in transaction history or reconciled, expired otherwise)
+    pub fn list_pending_transactions(&self) -> Vec<PendingTransactionInfo> {
+        let pending = self
+            .pending_transactions
+            .iter()
+            .map(|(tx_id, pending)| (tx_id, pending, PendingTransactionStatus::Pending));
+        let settled = self.settled_transactions.iter().map(|(tx_id, pending)| {
+            let status = if self.transaction_history.contains_key(tx_id)
+                || self.reconciliations.contains_key(tx_id)
+            {
+                PendingTransactionStatus::Confirmed
+            } else {
+                PendingTransactionStatus::Expired
+            };
+            (tx_id, pending, status)
+        });
+
+        pending
+            .chain(settled)
+            .map(|(tx_id, pending, status)| PendingTransactionInfo {
+                pending: TransactionPending {
+                    tx_id: *tx_id,
+                    ..pending.clone()
+                },
+                status,
+                reconciliation: self.reconciliations.get(tx_id).cloned(),
+            })
+            .collect()
+    }
+
+    /// Removes pending information of transactions settled before given block height (unless
+    /// some of their inputs are still unspent), returning number of removed records
+    pub fn prune_settled_transactions(&mut self, before_height: u64) -> usize {
+        let unspent_transactions = &self.unspent_transactions;
+        let count = self.settled_transactions.len();
+        self.settled_transactions.retain(|_, pending| {
+            pending.block_height >= before_height
+                || pending
+                    .used_inputs
+                    .iter()
+                    .any(|input| unspent_transactions.contains_key(input))
+        });
+        let settled_transactions = &self.settled_transactions;
+        self.reconciliations
+            .retain(|tx_id, _| settled_transactions.contains_key(tx_id));
+        count - self.settled_transactions.len()
+    }
+
     fn get_pending_inputs(&self) -> Vec<TxoPointer> {
         self.pending_transactions
             .values()
@@ -304,6 +585,78 @@ impl WalletState {
             .collect::<Vec<_>>();
         result
     }
+
+    /// Returns available transactions which are not frozen
+    pub fn get_selectable_transactions(&self) -> BTreeMap<TxoPointer, TxOut> {
+        let mut available = self.get_available_transactions();
+        available.retain(|pointer, _| !self.frozen_utxos.contains(pointer));
+        available
+    }
+
+    /// Returns unspent transactions which pass given filter, in order of the filter
+    pub fn list_unspent(&self, filter: &UtxoFilter) -> Vec<UtxoInfo> {
+        let pending_inputs = self.get_pending_inputs();
+        let mut utxos = self
+            .unspent_transactions
+            .iter()
+            .filter(|(_, output)| filter.matches(output))
+            .map(|(pointer, output)| UtxoInfo {
+                pointer: pointer.clone(),
+                output: output.clone(),
+                confirmation_height: self
+                    .transaction_history
+                    .get(&pointer.id)
+                    .map(|change| change.block_height),
+                pending: pending_inputs.contains(pointer),
+                frozen: self.frozen_utxos.contains(pointer),
+                label: None,
+            })
+            .collect::<Vec<_>>();
+        filter.sort(&mut utxos);
+        utxos
+    }
+
+    /// Returns balances and activity of given addresses (in given order), aggregated in a single
+    /// pass over unspent transactions and transaction history
+    pub fn get_address_balances(
+        &self,
+        addresses: Vec<LabeledAddress>,
+    ) -> std::result::Result<Vec<AddressBalance>, CoinError> {
+        let mut balances = addresses
+            .into_iter()
+            .map(|address| (address.clone(), AddressBalance::new(address)))
+            .collect::<IndexMap<_, _>>();
+
+        for output in self.unspent_transactions.values() {
+            let address = LabeledAddress::Transfer(output.address.clone());
+            if let Some(balance) = balances.get_mut(&address) {
+                balance.balance = (balance.balance + output.value)?;
+                balance.utxo_count += 1;
+            }
+        }
+
+        for change in self.transaction_history.values() {
+            for output in change.outputs.iter() {
+                let address = LabeledAddress::Transfer(output.address.clone());
+                if let Some(balance) = balances.get_mut(&address) {
+                    balance.record_activity(change.block_height, true);
+                }
+            }
+            for output in change
+                .inputs
+                .iter()
+                .filter_map(|input| input.output.as_ref())
+            {
+                let address = LabeledAddress::Transfer(output.address.clone());
+                if let Some(balance) = balances.get_mut(&address) {
+                    balance.record_activity(change.block_height, false);
+                }
+            }
+        }
+
+        Ok(balances.into_iter().map(|(_, balance)| balance).collect())
+    }
+
     /// get the balance info
     pub fn get_balance(&self) -> std::result::Result<WalletBalance, CoinError> {
         // pending amount
@@ -332,6 +685,55 @@ impl WalletState {
         };
         Ok(wallet_balances)
     }
+
+    /// get the balance info broken down by spendability at given time
+    pub fn get_detailed_balance(
+        &self,
+        now: Timespec,
+    ) -> std::result::Result<DetailedBalance, CoinError> {
+        let pending_incoming = sum_coins(
+            self.pending_transactions
+                .values()
+                .map(|value| value.return_amount),
+        )?;
+
+        let pending_inputs = self.get_pending_inputs();
+        let mut available = Coin::zero();
+        let mut pending_outgoing = Coin::zero();
+        let mut timelocked = Coin::zero();
+        let mut frozen = Coin::zero();
+        let mut earliest_unlock: Option<Timespec> = None;
+        for (key, value) in self.unspent_transactions.iter() {
+            if pending_inputs.contains(key) {
+                pending_outgoing = (pending_outgoing + value.value)?;
+                continue;
+            }
+            if self.frozen_utxos.contains(key) {
+                frozen = (frozen + value.value)?;
+                continue;
+            }
+            match value.valid_from {
+                Some(valid_from) if valid_from > now => {
+                    timelocked = (timelocked + value.value)?;
+                    earliest_unlock = Some(
+                        earliest_unlock.map_or(valid_from, |earliest| earliest.min(valid_from)),
+                    );
+                }
+                _ => available = (available + value.value)?,
+            }
+        }
+
+        let total = (((available + timelocked)? + frozen)? + pending_incoming)?;
+        Ok(DetailedBalance {
+            total,
+            available,
+            pending_incoming,
+            pending_outgoing,
+            timelocked,
+            frozen,
+            earliest_unlock,
+        })
+    }
     /// Applies memento to wallet state
     pub fn apply_memento(&mut self, memento: &WalletStateMemento) -> Result<()> {
         for operation in memento.0.iter() {
@@ -353,12 +755,27 @@ impl WalletState {
                     self.add_transaction_change(transaction_id.clone(), transaction_change.clone());
                 }
             }
+            MementoOperation::UpdateTransactionChange(
+                ref transaction_id,
+                ref transaction_change,
+            ) => {
+                if let Some(change) = self.transaction_history.get_mut(transaction_id) {
+                    *change = transaction_change.clone();
+                }
+            }
             MementoOperation::AddUnspentTransaction(ref input, ref output) => {
                 self.unspent_transactions
                     .insert(input.clone(), output.clone());
             }
             MementoOperation::RemoveUnspentTransaction(ref input) => {
                 self.unspent_transactions.remove(input);
+                self.frozen_utxos.remove(input);
+            }
+            MementoOperation::FreezeUtxo(ref input) => {
+                self.frozen_utxos.insert(input.clone());
+            }
+            MementoOperation::UnfreezeUtxo(ref input) => {
+                self.frozen_utxos.remove(input);
             }
             MementoOperation::AddPendingTransaction(ref transaction_id, ref pending_info) => {
                 if !self.pending_transactions.contains_key(transaction_id) {
@@ -367,13 +784,68 @@ impl WalletState {
                         .insert(*transaction_id, pending_info.clone());
                 }
             }
+            MementoOperation::SettlePendingTransaction(ref transaction_id) => {
+                if let Some(pending_info) = self.pending_transactions.remove(transaction_id) {
+                    self.settled_transactions
+                        .insert(*transaction_id, pending_info);
+                }
+            }
+            MementoOperation::ReconcilePendingTransaction(
+                ref transaction_id,
+                ref reconciliation,
+            ) => {
+                if self.settled_transactions.contains_key(transaction_id) {
+                    self.reconciliations
+                        .insert(*transaction_id, reconciliation.clone());
+                }
+            }
             MementoOperation::RemovePendingTransaction(ref transaction_id) => {
                 self.pending_transactions.remove(transaction_id);
+                self.settled_transactions.remove(transaction_id);
+                self.reconciliations.remove(transaction_id);
             }
         }
         Ok(())
     }
 
+    /// Returns a page of transaction history (without transactions which do not change balance)
+    /// passing given filter, cloning only the records in the page
+    pub fn get_transaction_history_page(
+        &self,
+        filter: &HistoryFilter,
+        offset: usize,
+        limit: usize,
+        reversed: bool,
+    ) -> HistoryPage {
+        let changes = self
+            .transaction_log
+            .iter()
+            .filter_map(|txid| self.transaction_history.get(txid))
+            .filter(|change| BalanceChange::NoChange != change.balance_change)
+            .filter(|change| filter.matches(change));
+        let changes: Box<dyn Iterator<Item = &TransactionChange>> = if reversed {
+            Box::new(changes.rev())
+        } else {
+            Box::new(changes)
+        };
+
+        let mut total = 0;
+        let mut records = Vec::new();
+        for change in changes {
+            if total >= offset && records.len() < limit {
+                records.push(change.clone());
+            }
+            total += 1;
+        }
+
+        HistoryPage {
+            has_more: offset.saturating_add(records.len()) < total,
+            records,
+            total,
+            labels: Vec::new(),
+        }
+    }
+
     /// Returns currently stored transaction change for given wallet and transaction id
     pub fn get_transaction_change(&self, transaction_id: &TxId) -> Option<TransactionChange> {
         self.transaction_history.get(transaction_id).cloned()
@@ -403,10 +875,15 @@ pub struct WalletStateMemento(Vec<MementoOperation>);
 #[derive(Debug, Clone)]
 enum MementoOperation {
     AddTransactionChange(TxId, TransactionChange),
+    UpdateTransactionChange(TxId, TransactionChange),
     AddUnspentTransaction(TxoPointer, TxOut),
     AddPendingTransaction(TxId, TransactionPending),
+    SettlePendingTransaction(TxId),
+    ReconcilePendingTransaction(TxId, PendingReconciliation),
     RemovePendingTransaction(TxId),
     RemoveUnspentTransaction(TxoPointer),
+    FreezeUtxo(TxoPointer),
+    UnfreezeUtxo(TxoPointer),
 }
 
 impl WalletStateMemento {
@@ -419,6 +896,15 @@ impl WalletStateMemento {
         ))
     }
 
+    /// Replaces transaction change already in wallet state (e.g., with recalculated balance change)
+    #[inline]
+    pub fn update_transaction_change(&mut self, transaction_change: TransactionChange) {
+        self.0.push(MementoOperation::UpdateTransactionChange(
+            transaction_change.transaction_id,
+            transaction_change,
+        ))
+    }
+
     /// Adds transaction pending info to memento
     #[inline]
     pub fn add_pending_transaction(&mut self, tx_id: TxId, tx_pending: TransactionPending) {
@@ -440,12 +926,46 @@ impl WalletStateMemento {
             .push(MementoOperation::RemoveUnspentTransaction(input))
     }
 
-    /// Removes pending transaction from memento
+    /// Marks pending transaction as no longer pending (confirmed or expired) in memento (its
+    /// inputs are unlocked, but its pending information is kept)
+    #[inline]
+    pub fn settle_pending_transaction(&mut self, tx_id: TxId) {
+        self.0
+            .push(MementoOperation::SettlePendingTransaction(tx_id))
+    }
+
+    /// Records outcome of checking confirmed transaction against its (settled) pending information
+    /// in memento
+    #[inline]
+    pub fn reconcile_pending_transaction(
+        &mut self,
+        tx_id: TxId,
+        reconciliation: PendingReconciliation,
+    ) {
+        self.0.push(MementoOperation::ReconcilePendingTransaction(
+            tx_id,
+            reconciliation,
+        ))
+    }
+
+    /// Removes pending transaction (or settled one) from memento
     #[inline]
     pub fn remove_pending_transaction(&mut self, tx_id: TxId) {
         self.0
             .push(MementoOperation::RemovePendingTransaction(tx_id))
     }
+
+    /// Excludes unspent transaction from coin selection in memento
+    #[inline]
+    pub fn freeze_utxo(&mut self, input: TxoPointer) {
+        self.0.push(MementoOperation::FreezeUtxo(input))
+    }
+
+    /// Includes frozen unspent transaction in coin selection again in memento
+    #[inline]
+    pub fn unfreeze_utxo(&mut self, input: TxoPointer) {
+        self.0.push(MementoOperation::UnfreezeUtxo(input))
+    }
 }
 
 #[cfg(test)]
@@ -459,7 +979,7 @@ mod tests {
     use client_common::tendermint::types::Time;
     use client_common::{seckey::derive_enckey, storage::MemoryStorage};
 
-    use crate::types::{BalanceChange, TransactionType};
+    use crate::types::{BalanceChange, Direction, TransactionType};
     use chain_core::init::coin::Coin;
 
     #[test]
@@ -542,6 +1062,8 @@ mod tests {
                 used_inputs: vec![],
                 block_height: 0,
                 return_amount: Coin::unit(),
+                tx_id: [0; 32],
+                valid_until_height: 50,
             },
         );
         assert!(wallet_state_service
@@ -571,6 +1093,8 @@ mod tests {
             block_height: 0,
             fee_paid: Fee::new(Coin::new(10).unwrap()),
             block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
+            depositor: None,
         });
 
         assert!(wallet_state_service
@@ -610,6 +1134,8 @@ mod tests {
             block_height: 0,
             fee_paid: Fee::new(Coin::new(10).unwrap()),
             block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
+            depositor: None,
         });
 
         assert!(wallet_state_service
@@ -661,6 +1187,8 @@ mod tests {
                 used_inputs: vec![tx_pointer(0, 0)],
                 block_height: 1,
                 return_amount: Coin::new(50).unwrap(),
+                tx_id: [1; 32],
+                valid_until_height: 51,
             },
         );
         wallet_state_service
@@ -748,4 +1276,527 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn test_rollback_uses_valid_until_height() {
+        let pending = |block_height: u64, valid_until_height: u64| TransactionPending {
+            used_inputs: vec![],
+            block_height,
+            return_amount: Coin::unit(),
+            tx_id: [0; 32],
+            valid_until_height,
+        };
+        let mut wallet_state = WalletState::default();
+        wallet_state
+            .pending_transactions
+            .insert([1; 32], pending(10, 12));
+        wallet_state
+            .pending_transactions
+            .insert([2; 32], pending(10, 100));
+        // created before `valid_until_height` was tracked
+        wallet_state
+            .pending_transactions
+            .insert([3; 32], pending(10, 0));
+
+        assert_eq!(vec![[1; 32]], wallet_state.get_rollback_pending_tx(13, 50));
+        assert_eq!(
+            vec![[1; 32], [3; 32]],
+            wallet_state.get_rollback_pending_tx(61, 50)
+        );
+    }
+
+    #[test]
+    fn test_settle_pending_transactions() {
+        let pending = TransactionPending {
+            used_inputs: vec![TxoPointer::new([0; 32], 0)],
+            block_height: 10,
+            return_amount: Coin::unit(),
+            tx_id: [0; 32],
+            valid_until_height: 12,
+        };
+        let mut wallet_state = WalletState::default();
+        wallet_state
+            .pending_transactions
+            .insert([1; 32], pending.clone());
+        wallet_state.pending_transactions.insert([2; 32], pending);
+
+        let mut memento = WalletStateMemento::default();
+        memento.settle_pending_transaction([1; 32]);
+        memento.settle_pending_transaction([2; 32]);
+        wallet_state.apply_memento(&memento).unwrap();
+        assert!(wallet_state.pending_transactions.is_empty());
+        assert!(wallet_state.get_pending_inputs().is_empty());
+
+        // confirmed after it expired
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(TransactionChange {
+            transaction_id: [2; 32],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            fee_paid: Fee::new(Coin::zero()),
+            balance_change: BalanceChange::NoChange,
+            transaction_type: TransactionType::Transfer,
+            block_height: 13,
+            block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
+            depositor: None,
+        });
+        wallet_state.apply_memento(&memento).unwrap();
+
+        let statuses = wallet_state
+            .list_pending_transactions()
+            .into_iter()
+            .map(|info| (info.pending.tx_id, info.status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ([1; 32], PendingTransactionStatus::Expired),
+                ([2; 32], PendingTransactionStatus::Confirmed)
+            ],
+            statuses
+        );
+
+        // states stored before settled transactions were tracked are still readable
+        let encoded = wallet_state.encode();
+        let legacy = (
+            &wallet_state.unspent_transactions,
+            &wallet_state.pending_transactions,
+            &wallet_state.transaction_history,
+            &wallet_state.transaction_log,
+        )
+            .encode();
+        assert_eq!(
+            2,
+            WalletState::decode(&mut encoded.as_slice())
+                .unwrap()
+                .settled_transactions
+                .len()
+        );
+        let decoded = WalletState::decode(&mut legacy.as_slice()).unwrap();
+        assert!(decoded.settled_transactions.is_empty());
+        assert_eq!(1, decoded.transaction_history.len());
+    }
+
+    #[test]
+    fn test_list_unspent() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let storage = prepare_wallet_storage(name, enckey);
+        let wallet_state_service = WalletStateService::new(storage);
+        let tx_pointer = |n: u8, i: usize| TxoPointer::new([n; 32], i);
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(TransactionChange {
+            transaction_id: [0; 32],
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            fee_paid: Fee::new(Coin::zero()),
+            balance_change: BalanceChange::Incoming {
+                value: Coin::new(140).unwrap(),
+            },
+            transaction_type: TransactionType::Transfer,
+            block_height: 3,
+            block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+            memo: None,
+            depositor: None,
+        });
+        let mut output = TxOut::new(ExtendedAddr::OrTree([2; 32]), Coin::new(70).unwrap());
+        output.valid_from = Some(1000);
+        memento.add_unspent_transaction(tx_pointer(2, 0), output);
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        let utxos = wallet_state_service
+            .list_unspent(name, enckey, &UtxoFilter::default())
+            .unwrap();
+        let summary = utxos
+            .iter()
+            .map(|utxo| (utxo.pointer.clone(), utxo.confirmation_height, utxo.pending))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                (tx_pointer(2, 0), None, false),
+                (tx_pointer(0, 0), Some(3), true),
+                (tx_pointer(0, 1), Some(3), false),
+            ],
+            summary
+        );
+
+        let filter = UtxoFilter {
+            min_value: Some(Coin::new(50).unwrap()),
+            spendable_at: Some(999),
+            ..Default::default()
+        };
+        let utxos = wallet_state_service
+            .list_unspent(name, enckey, &filter)
+            .unwrap();
+        assert_eq!(1, utxos.len());
+        assert_eq!(tx_pointer(0, 0), utxos[0].pointer);
+    }
+
+    #[test]
+    fn test_get_detailed_balance() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+        let tx_pointer = |n: u8| TxoPointer::new([n; 32], 0);
+        let output = |m: u64, valid_from: Option<Timespec>| {
+            let mut output = TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(m).unwrap());
+            output.valid_from = valid_from;
+            output
+        };
+
+        // one free, one timelocked and one spent (by a pending transaction returning 40) output
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(tx_pointer(0), output(30, None));
+        memento.add_unspent_transaction(tx_pointer(1), output(50, Some(2000)));
+        memento.add_unspent_transaction(tx_pointer(2), output(100, Some(1000)));
+        memento.add_pending_transaction(
+            [3; 32],
+            TransactionPending {
+                used_inputs: vec![tx_pointer(2)],
+                block_height: 1,
+                return_amount: Coin::new(40).unwrap(),
+                tx_id: [3; 32],
+                valid_until_height: 51,
+            },
+        );
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        let balance = wallet_state_service
+            .get_detailed_balance(name, enckey, 1500)
+            .unwrap();
+        assert_eq!(
+            DetailedBalance {
+                total: Coin::new(120).unwrap(),
+                available: Coin::new(30).unwrap(),
+                pending_incoming: Coin::new(40).unwrap(),
+                pending_outgoing: Coin::new(100).unwrap(),
+                timelocked: Coin::new(50).unwrap(),
+                frozen: Coin::zero(),
+                earliest_unlock: Some(2000),
+            },
+            balance
+        );
+
+        // reconciles with single-figure balance
+        let wallet_balance = wallet_state_service.get_balance(name, enckey).unwrap();
+        assert_eq!(wallet_balance.total, balance.total);
+        assert_eq!(
+            wallet_balance.available,
+            (balance.available + balance.timelocked).unwrap()
+        );
+        assert_eq!(wallet_balance.pending, balance.pending_incoming);
+
+        let balance = wallet_state_service
+            .get_detailed_balance(name, enckey, 2000)
+            .unwrap();
+        assert_eq!(Coin::new(80).unwrap(), balance.available);
+        assert_eq!(Coin::zero(), balance.timelocked);
+        assert_eq!(None, balance.earliest_unlock);
+
+        // confirmation of pending transaction moves its coins to synced outputs
+        let mut memento = WalletStateMemento::default();
+        memento.remove_pending_transaction([3; 32]);
+        memento.remove_unspent_transaction(tx_pointer(2));
+        memento.add_unspent_transaction(TxoPointer::new([3; 32], 1), output(40, None));
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        let balance = wallet_state_service
+            .get_detailed_balance(name, enckey, 1500)
+            .unwrap();
+        assert_eq!(Coin::new(120).unwrap(), balance.total);
+        assert_eq!(Coin::new(70).unwrap(), balance.available);
+        assert_eq!(Coin::zero(), balance.pending_incoming);
+        assert_eq!(Coin::zero(), balance.pending_outgoing);
+    }
+
+    #[test]
+    fn test_frozen_utxos() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+        let tx_pointer = |n: u8| TxoPointer::new([n; 32], 0);
+        let output = |m: u64| TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(m).unwrap());
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(tx_pointer(0), output(30));
+        memento.add_unspent_transaction(tx_pointer(1), output(50));
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        wallet_state_service
+            .set_utxo_frozen(name, enckey, &tx_pointer(1), true)
+            .unwrap();
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            wallet_state_service
+                .set_utxo_frozen(name, enckey, &tx_pointer(2), true)
+                .unwrap_err()
+                .kind()
+        );
+
+        // frozen output is still unspent, but not selectable
+        let selectable = wallet_state_service
+            .get_selectable_transactions(name, enckey)
+            .unwrap();
+        assert_eq!(vec![&tx_pointer(0)], selectable.keys().collect::<Vec<_>>());
+        assert!(wallet_state_service
+            .has_unspent_transactions(name, enckey, &[tx_pointer(1)])
+            .unwrap());
+
+        let utxos = wallet_state_service
+            .list_unspent(name, enckey, &UtxoFilter::default())
+            .unwrap();
+        assert_eq!(
+            vec![&tx_pointer(1)],
+            utxos
+                .iter()
+                .filter(|utxo| utxo.frozen)
+                .map(|utxo| &utxo.pointer)
+                .collect::<Vec<_>>()
+        );
+
+        let balance = wallet_state_service
+            .get_detailed_balance(name, enckey, 0)
+            .unwrap();
+        assert_eq!(Coin::new(30).unwrap(), balance.available);
+        assert_eq!(Coin::new(50).unwrap(), balance.frozen);
+        assert_eq!(Coin::new(80).unwrap(), balance.total);
+
+        // spent output is no longer frozen
+        let mut memento = WalletStateMemento::default();
+        memento.remove_unspent_transaction(tx_pointer(1));
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+        assert!(wallet_state_service
+            .get_frozen_utxos(name, enckey)
+            .unwrap()
+            .is_empty());
+
+        // states stored before outputs could be frozen are still readable
+        let mut wallet_state = WalletState::default();
+        wallet_state.frozen_utxos.insert(tx_pointer(0));
+        let encoded = wallet_state.encode();
+        let legacy = (
+            &wallet_state.unspent_transactions,
+            &wallet_state.pending_transactions,
+            &wallet_state.transaction_history,
+            &wallet_state.transaction_log,
+            &wallet_state.settled_transactions,
+        )
+            .encode();
+        assert_eq!(
+            wallet_state.frozen_utxos,
+            WalletState::decode(&mut encoded.as_slice())
+                .unwrap()
+                .frozen_utxos
+        );
+        assert!(WalletState::decode(&mut legacy.as_slice())
+            .unwrap()
+            .frozen_utxos
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_transaction_history_page() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+
+        let mut memento = WalletStateMemento::default();
+        for n in 0..6u8 {
+            // transaction without balance change is not in history
+            let balance_change = if n == 2 {
+                BalanceChange::NoChange
+            } else {
+                BalanceChange::Incoming {
+                    value: Coin::new(u64::from(n) + 1).unwrap(),
+                }
+            };
+            memento.add_transaction_change(TransactionChange {
+                transaction_id: [n; 32],
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                fee_paid: Fee::new(Coin::zero()),
+                balance_change,
+                transaction_type: TransactionType::Transfer,
+                block_height: u64::from(n),
+                block_time: Time::from_str("2019-04-09T09:38:41.735577Z").unwrap(),
+                memo: None,
+                depositor: None,
+            });
+        }
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        let page = |offset: usize, limit: usize, reversed: bool| {
+            let page = wallet_state_service
+                .get_transaction_history_page(
+                    name,
+                    enckey,
+                    &HistoryFilter::default(),
+                    offset,
+                    limit,
+                    reversed,
+                )
+                .unwrap();
+            let ids = page
+                .records
+                .iter()
+                .map(|change| change.transaction_id[0])
+                .collect::<Vec<_>>();
+            (ids, page.total, page.has_more)
+        };
+
+        assert_eq!((vec![0, 1], 5, true), page(0, 2, false));
+        assert_eq!((vec![3, 4], 5, true), page(2, 2, false));
+        assert_eq!((vec![5], 5, false), page(4, 2, false));
+        assert_eq!((vec![5, 4, 3], 5, true), page(0, 3, true));
+        assert_eq!((vec![1, 0], 5, false), page(3, 3, true));
+        assert_eq!((vec![], 5, false), page(10, 3, false));
+    }
+
+    #[test]
+    fn test_filter_transaction_history_page() {
+        let name = "name";
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
+        let wallet_state_service = WalletStateService::new(MemoryStorage::default());
+
+        let incoming = |value: u64| BalanceChange::Incoming {
+            value: Coin::new(value).unwrap(),
+        };
+        let outgoing = |value: u64| BalanceChange::Outgoing {
+            value: Coin::new(value).unwrap(),
+        };
+        // (type, balance change, block time) in March and April 2020
+        let march = 1_583_020_800;
+        let april = 1_585_699_200;
+        let history = vec![
+            (
+                TransactionType::Transfer,
+                incoming(100),
+                "2020-03-01T00:00:00Z",
+            ),
+            (
+                TransactionType::Transfer,
+                outgoing(30),
+                "2020-03-01T00:00:10Z",
+            ),
+            (
+                TransactionType::Transfer,
+                outgoing(5),
+                "2020-04-01T00:00:10Z",
+            ),
+            (
+                TransactionType::Deposit,
+                outgoing(50),
+                "2020-03-01T00:00:20Z",
+            ),
+            (TransactionType::Unbond, outgoing(1), "2020-03-31T23:59:59Z"),
+            (
+                TransactionType::Withdraw,
+                incoming(49),
+                "2020-04-01T00:00:00Z",
+            ),
+            (TransactionType::Unjail, outgoing(1), "2020-04-01T00:00:20Z"),
+            (
+                TransactionType::Nodejoin,
+                outgoing(1),
+                "2020-04-01T00:00:30Z",
+            ),
+        ];
+
+        let mut memento = WalletStateMemento::default();
+        for (n, (transaction_type, balance_change, block_time)) in history.into_iter().enumerate() {
+            memento.add_transaction_change(TransactionChange {
+                transaction_id: [n as u8; 32],
+                inputs: Vec::new(),
+                outputs: Vec::new(),
+                fee_paid: Fee::new(Coin::zero()),
+                balance_change,
+                transaction_type,
+                block_height: n as u64,
+                block_time: Time::from_str(block_time).unwrap(),
+                memo: None,
+                depositor: None,
+            });
+        }
+        wallet_state_service
+            .apply_memento(name, enckey, &memento)
+            .unwrap();
+
+        let count = |filter: HistoryFilter| {
+            wallet_state_service
+                .get_transaction_history_page(name, enckey, &filter, 0, 100, false)
+                .unwrap()
+                .total
+        };
+
+        assert_eq!(8, count(HistoryFilter::default()));
+        let tx_types = vec![
+            (TransactionType::Transfer, 3),
+            (TransactionType::Deposit, 1),
+            (TransactionType::Unbond, 1),
+            (TransactionType::Withdraw, 1),
+            (TransactionType::Unjail, 1),
+            (TransactionType::Nodejoin, 1),
+        ];
+        for (tx_type, expected) in tx_types {
+            let filter = HistoryFilter {
+                tx_type: Some(tx_type),
+                ..Default::default()
+            };
+            assert_eq!(expected, count(filter), "{}", tx_type);
+        }
+        let incoming_filter = HistoryFilter {
+            direction: Some(Direction::Incoming),
+            ..Default::default()
+        };
+        assert_eq!(2, count(incoming_filter));
+        let min_amount_filter = HistoryFilter {
+            min_amount: Some(Coin::new(30).unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(4, count(min_amount_filter));
+
+        // outgoing transfers in March
+        let march_filter = HistoryFilter {
+            direction: Some(Direction::Outgoing),
+            tx_type: Some(TransactionType::Transfer),
+            from_time: Some(march),
+            to_time: Some(april),
+            ..Default::default()
+        };
+        assert_eq!(1, count(march_filter.clone()));
+        let page = wallet_state_service
+            .get_transaction_history_page(name, enckey, &march_filter, 0, 100, false)
+            .unwrap();
+        assert_eq!([1; 32], page.records[0].transaction_id);
+
+        // filters and pages compose
+        let outgoing_filter = HistoryFilter {
+            direction: Some(Direction::Outgoing),
+            ..Default::default()
+        };
+        let page = wallet_state_service
+            .get_transaction_history_page(name, enckey, &outgoing_filter, 2, 2, false)
+            .unwrap();
+        assert_eq!(6, page.total);
+        assert!(page.has_more);
+        let ids = page
+            .records
+            .iter()
+            .map(|change| change.transaction_id[0])
+            .collect::<Vec<_>>();
+        assert_eq!(vec![3, 4], ids);
+    }
 }
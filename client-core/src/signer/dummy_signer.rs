@@ -3,15 +3,16 @@ use chain_core::common::MerkleTree;
 use chain_core::common::H256;
 use chain_core::init::address::RedeemAddress;
 use chain_core::state::account::{
-    DepositBondTx, StakedStateAddress, StakedStateOpAttributes, StakedStateOpWitness,
-    WithdrawUnbondedTx,
+    DepositBondTx, StakedStateAddress, StakedStateOpAttributes, StakedStateOpWitness, UnbondTx,
+    UnjailTx, WithdrawUnbondedTx,
 };
 use chain_core::state::tendermint::BlockHeight;
+use chain_core::state::validator::NodeJoinRequestTx;
 use chain_core::tx::data::input::{TxoPointer, TxoSize};
 use chain_core::tx::data::{Tx, TxId};
 use chain_core::tx::witness::tree::RawXOnlyPubkey;
 use chain_core::tx::witness::{TxInWitness, TxWitness};
-use chain_core::tx::{PlainTxAux, TransactionId, TxAux, TxEnclaveAux, TxObfuscated};
+use chain_core::tx::{PlainTxAux, TransactionId, TxAux, TxEnclaveAux, TxObfuscated, TxPublicAux};
 use client_common::Result;
 use parity_scale_codec::Encode;
 use secp256k1::recovery::{RecoverableSignature, RecoveryId};
@@ -86,10 +87,13 @@ impl DummySigner {
         let plain_payload = PlainTxAux::DepositStakeTx(witness);
         let padded_payload = self.pad_payload(plain_payload);
         let deposit_bond_tx = DepositBondTx {
-            inputs: vec![TxoPointer {
-                id: TxId::default(),
-                index: TxoSize::default(),
-            }],
+            inputs: vec![
+                TxoPointer {
+                    id: TxId::default(),
+                    index: TxoSize::default(),
+                };
+                inputs.len()
+            ],
             to_staked_account: StakedStateAddress::BasicRedeem(RedeemAddress::default()),
             attributes: StakedStateOpAttributes::default(),
         };
@@ -126,4 +130,29 @@ impl DummySigner {
             },
         })
     }
+
+    /// Mock the txaux for unbond transactions
+    pub fn mock_txaux_for_unbond(&self, tx: UnbondTx) -> TxAux {
+        let witness = self.mock_staked_state_op_witness();
+        TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, witness))
+    }
+
+    /// Mock the txaux for unjail transactions
+    pub fn mock_txaux_for_unjail(&self, tx: UnjailTx) -> TxAux {
+        let witness = self.mock_staked_state_op_witness();
+        TxAux::PublicTx(TxPublicAux::UnjailTx(tx, witness))
+    }
+
+    /// Mock the txaux for node join transactions
+    pub fn mock_txaux_for_node_join(&self, tx: NodeJoinRequestTx) -> TxAux {
+        let witness = self.mock_staked_state_op_witness();
+        TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness))
+    }
+
+    /// Mock the witness of staked state operations
+    fn mock_staked_state_op_witness(&self) -> StakedStateOpWitness {
+        let ecdsa_signature =
+            RecoverableSignature::from_compact(&[0; 64], RecoveryId::from_i32(1).unwrap()).unwrap();
+        StakedStateOpWitness::new(ecdsa_signature)
+    }
 }
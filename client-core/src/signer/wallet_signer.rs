@@ -1,9 +1,10 @@
 //! Wallet signer responsible for signing as wallet
 use chain_core::common::H256;
+use chain_core::state::account::{StakedStateAddress, StakedStateOpWitness};
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::witness::{TxInWitness, TxWitness};
 use client_common::Transaction;
-use client_common::{Error, ErrorKind, Result, ResultExt, SecKey, Storage};
+use client_common::{Error, ErrorKind, PrivateKeyAction, Result, ResultExt, SecKey, Storage};
 
 use crate::service::{HwKeyService, KeyService, RootHashService, WalletService};
 use crate::types::WalletKind;
@@ -136,6 +137,44 @@ impl<'a, S> WalletSigner<'a, S>
 where
     S: Storage,
 {
+    /// Signs staking transaction with the staking key of given address; hardware wallets ask the
+    /// user to confirm transaction id and amount on device
+    pub fn sign_staking_tx(
+        &self,
+        tx: &Transaction,
+        address: &StakedStateAddress,
+    ) -> Result<StakedStateOpWitness> {
+        let public_key = match address {
+            StakedStateAddress::BasicRedeem(ref redeem_address) => self
+                .wallet_service
+                .find_staking_key(self.name, self.enckey, redeem_address)?,
+        }
+        .chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("Address not found in current wallet: {}", address),
+            )
+        })?;
+
+        let wallet = self.wallet_service.get_wallet(self.name, self.enckey)?;
+        wallet.wallet_kind.verify_can_sign()?;
+        let signature = match wallet.wallet_kind {
+            WalletKind::HW => self.hw_key_service.sign_staking_tx(&public_key, tx)?,
+            WalletKind::Basic | WalletKind::HD | WalletKind::WatchOnly => self
+                .wallet_service
+                .find_private_key(self.name, self.enckey, &public_key)?
+                .chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        format!("Unable to find private key of staking address: {}", address),
+                    )
+                })?
+                .sign(tx)?,
+        };
+
+        Ok(StakedStateOpWitness::new(signature))
+    }
+
     /// Schnorr signs message with private key corresponding to `self_public_key` in given 1-of-n root hash
     fn schnorr_sign_with_root_hash(
         &self,
@@ -157,9 +196,10 @@ where
             .root_hash_service
             .public_key(self.name, &root_hash, self.enckey)?;
         let wallet = self.wallet_service.get_wallet(self.name, self.enckey)?;
+        wallet.wallet_kind.verify_can_sign()?;
         let sign_key = match wallet.wallet_kind {
             WalletKind::HW => self.hw_key_service.get_sign_key(&public_key)?,
-            WalletKind::Basic | WalletKind::HD => {
+            WalletKind::Basic | WalletKind::HD | WalletKind::WatchOnly => {
                 let private_key = self
                     .wallet_service
                     .find_private_key(self.name, self.enckey, &public_key)?
@@ -192,11 +232,16 @@ mod wallet_signer_tests {
     use super::*;
     use secstr::SecUtf8;
 
+    use chain_core::init::coin::Coin;
+    use chain_core::state::account::{StakedStateOpAttributes, UnbondTx};
     use chain_core::tx::data::Tx;
     use chain_core::tx::TransactionId;
-    use chain_tx_validation::witness::verify_tx_address;
+    use chain_tx_validation::witness::{verify_tx_address, verify_tx_recover_address};
     use client_common::storage::MemoryStorage;
+    use client_common::tendermint::UnauthorizedClient;
 
+    use crate::service::{RecordingHwKeyService, StakingTxConfirmation};
+    use crate::transaction_builder::UnauthorizedWalletTransactionBuilder;
     use crate::types::WalletKind;
     use crate::wallet::{DefaultWalletClient, WalletClient};
 
@@ -211,7 +256,7 @@ mod wallet_signer_tests {
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
         let (enckey, _) = wallet_client
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
 
         let public_keys = vec![
@@ -251,7 +296,7 @@ mod wallet_signer_tests {
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
         let (enckey, _) = wallet_client
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
 
         let public_keys = vec![
@@ -282,4 +327,88 @@ mod wallet_signer_tests {
                 .kind()
         );
     }
+
+    #[test]
+    fn check_sign_staking_tx_flow() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+        let storage = MemoryStorage::default();
+
+        let recording = RecordingHwKeyService::default();
+        let hw_key_service = HwKeyService::Recording(recording.clone());
+        let wallet_client = DefaultWalletClient::new(
+            storage.clone(),
+            UnauthorizedClient,
+            UnauthorizedWalletTransactionBuilder,
+            None,
+            hw_key_service.clone(),
+        );
+        let signer_manager = WalletSignerManager::new(storage, hw_key_service.clone());
+
+        let (basic_enckey, _) = wallet_client
+            .new_wallet("basic", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let basic_address = wallet_client
+            .new_staking_address("basic", &basic_enckey)
+            .unwrap();
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::HW, None, None)
+            .unwrap();
+        let address = wallet_client.new_staking_address(name, &enckey).unwrap();
+
+        let unbond = |address| {
+            Transaction::UnbondStakeTransaction(UnbondTx::new(
+                address,
+                0,
+                Coin::new(100).unwrap(),
+                StakedStateOpAttributes::new(0),
+            ))
+        };
+
+        // software keys are used without confirmation
+        let tx = unbond(basic_address);
+        let witness = signer_manager
+            .create_signer("basic", &basic_enckey, &hw_key_service)
+            .sign_staking_tx(&tx, &basic_address)
+            .unwrap();
+        assert_eq!(
+            basic_address,
+            verify_tx_recover_address(&witness, &tx.id()).unwrap()
+        );
+        assert!(recording.confirmations.lock().unwrap().is_empty());
+
+        // hardware keys present transaction id and amount for confirmation
+        let tx = unbond(address);
+        let witness = signer_manager
+            .create_signer(name, &enckey, &hw_key_service)
+            .sign_staking_tx(&tx, &address)
+            .unwrap();
+        assert_eq!(
+            address,
+            verify_tx_recover_address(&witness, &tx.id()).unwrap()
+        );
+        assert_eq!(
+            vec![StakingTxConfirmation {
+                transaction_id: tx.id(),
+                amount: Some(Coin::new(100).unwrap()),
+            }],
+            *recording.confirmations.lock().unwrap()
+        );
+
+        let rejecting = HwKeyService::Recording(RecordingHwKeyService {
+            reject: true,
+            ..recording
+        });
+        let error = signer_manager
+            .create_signer(name, &enckey, &rejecting)
+            .sign_staking_tx(&tx, &address)
+            .unwrap_err();
+        assert_eq!(ErrorKind::RejectedByUser, error.kind());
+
+        let error = signer_manager
+            .create_signer(name, &enckey, &hw_key_service)
+            .sign_staking_tx(&tx, &basic_address)
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+    }
 }
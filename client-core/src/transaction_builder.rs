@@ -11,14 +11,14 @@ pub use raw_transfer_transaction_builder::{
 pub use unauthorized_wallet_transaction_builder::UnauthorizedWalletTransactionBuilder;
 
 use chain_core::init::coin::Coin;
-use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use chain_core::tx::TxAux;
 use client_common::{PrivateKey, Result, SecKey, SignedTransaction, Transaction};
 
-use crate::UnspentTransactions;
+use crate::types::ChangeAddress;
+use crate::{CoinSelectionStrategy, UnspentTransactions};
 use chain_core::tx::data::TxId;
 
 /// Interface for wallet transaction building from output addresses and amount.
@@ -32,8 +32,9 @@ pub trait WalletTransactionBuilder: Send + Sync {
     /// - `enckey`: Encryption key of wallet
     /// - `unspent_transactions`: Unspent transactions
     /// - `outputs`: Transaction outputs
-    /// - `return_address`: Address to which change amount will get returned
+    /// - `change_address`: Address to which change amount will get returned
     /// - `attributes`: Transaction attributes,
+    /// - `coin_selection`: Strategy for selecting unspent transactions
     ///
     /// # return
     /// - `TxAux`: obfuscated transaction
@@ -45,10 +46,20 @@ pub trait WalletTransactionBuilder: Send + Sync {
         enckey: &SecKey,
         unspent_transactions: UnspentTransactions,
         outputs: Vec<TxOut>,
-        return_address: ExtendedAddr,
+        change_address: ChangeAddress,
         attributes: TxAttributes,
+        coin_selection: &dyn CoinSelectionStrategy,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)>;
 
+    /// Estimates fee of a transfer transaction spending given number of (single signature) unspent
+    /// transactions in given outputs
+    fn estimate_fee(
+        &self,
+        no_of_inputs: usize,
+        outputs: &[TxOut],
+        attributes: &TxAttributes,
+    ) -> Result<Coin>;
+
     /// Obfuscates given signed transaction
     fn obfuscate(&self, signed_transaction: SignedTransaction) -> Result<TxAux>;
 
@@ -11,9 +11,9 @@ use client_common::{
 
 use crate::signer::WalletSignerManager;
 use crate::transaction_builder::RawTransferTransactionBuilder;
+use crate::types::ChangeAddress;
 use crate::{
-    SelectedUnspentTransactions, TransactionObfuscation, UnspentTransactions,
-    WalletTransactionBuilder,
+    CoinSelectionStrategy, TransactionObfuscation, UnspentTransactions, WalletTransactionBuilder,
 };
 use chain_core::tx::{data::TxId, TransactionId};
 
@@ -22,13 +22,13 @@ use chain_core::tx::{data::TxId, TransactionId};
 /// # Algorithm
 ///
 /// 1. Calculate `output_value`: Sum of all the output values.
-/// 2. Initialize `fees = 0`.
-/// 3. Select unspent transactions with `fees + output_value`.
-/// 4. Build transaction with selected unspent transactions (also add an extra output for change amount).
-/// 5. Sign transaction with dummy signer.
-/// 6. Wrap up transaction.
-/// 7. Calculate `new_fees`.
-/// 8. If `new_fees > fees`, then change `fees = new_fees` and goto step 3, otherwise return signed transaction.
+/// 2. Select unspent transactions covering `output_value` with given coin selection strategy,
+///    which estimates fees of transactions (signed with dummy signer) with different number of
+///    inputs and with or without change output.
+/// 3. Build transaction with selected unspent transactions (also add an extra output for change
+///    amount, if any, to the change address; change which does not pay for its output is paid as
+///    fee).
+/// 4. Sign transaction and wrap it up.
 ///
 #[derive(Debug, Clone)]
 pub struct DefaultWalletTransactionBuilder<S, F, O>
@@ -56,26 +56,30 @@ where
         enckey: &SecKey,
         unspent_transactions: UnspentTransactions,
         outputs: Vec<TxOut>,
-        return_address: ExtendedAddr,
+        change_address: ChangeAddress,
         attributes: TxAttributes,
         // FIXME: this should be per unspent_transactions
         threshold: u16,
+        coin_selection: &dyn CoinSelectionStrategy,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
+        let no_of_outputs = outputs.len();
         let mut raw_builder = self.select_and_build(
             &unspent_transactions,
             outputs,
-            return_address.clone(),
+            change_address,
             attributes,
             threshold,
+            coin_selection,
         )?;
 
         let selected_inputs: Vec<TxoPointer> = raw_builder
             .iter_inputs()
             .map(|witness_utxo| witness_utxo.prev_txo_pointer.clone())
             .collect();
+        // change output (if any) is added after given outputs
         let return_amount = raw_builder
             .iter_outputs()
-            .find(|&m| m.address == return_address)
+            .nth(no_of_outputs)
             .map(|output| output.value)
             .unwrap_or_default();
 
@@ -103,20 +107,32 @@ where
         enckey: &SecKey,
         unspent_transactions: UnspentTransactions,
         outputs: Vec<TxOut>,
-        return_address: ExtendedAddr,
+        change_address: ChangeAddress,
         attributes: TxAttributes,
+        coin_selection: &dyn CoinSelectionStrategy,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
         self.build_transfer_tx_ex(
             name,
             enckey,
             unspent_transactions,
             outputs,
-            return_address,
+            change_address,
             attributes,
             1,
+            coin_selection,
         )
     }
 
+    #[inline]
+    fn estimate_fee(
+        &self,
+        no_of_inputs: usize,
+        outputs: &[TxOut],
+        attributes: &TxAttributes,
+    ) -> Result<Coin> {
+        self.estimate_fee_with(no_of_inputs, 1, outputs, attributes)
+    }
+
     #[inline]
     fn obfuscate(&self, signed_transaction: SignedTransaction) -> Result<TxAux> {
         self.transaction_obfuscation.encrypt(signed_transaction)
@@ -154,16 +170,18 @@ where
         }
     }
 
-    /// Create a `DummySigner` which signs a transaction with dummy values for fees calculation.
+    /// Selects unspent transactions with given strategy (estimating fees with a `DummySigner`
+    /// which signs a transaction with dummy values).
     /// Returns a result of unsigned raw transfer transaction builder
-    pub fn select_and_build<'a>(
+    pub fn select_and_build(
         &self,
-        unspent_transactions: &'a UnspentTransactions,
+        unspent_transactions: &UnspentTransactions,
         outputs: Vec<TxOut>,
-        return_address: ExtendedAddr,
+        change_address: ChangeAddress,
         attributes: TxAttributes,
         // FIXME: this should be per UnspentTransactions
         threshold: u16,
+        coin_selection: &dyn CoinSelectionStrategy,
     ) -> Result<RawTransferTransactionBuilder<F>> {
         let output_value = sum_coins(outputs.iter().map(|output| output.value)).chain(|| {
             (
@@ -171,38 +189,75 @@ where
                 "Sum of output values exceeds maximum allowed amount",
             )
         })?;
-        let mut fees = Coin::zero();
-        let raw_tx_builder = loop {
-            let (selected_unspent_txs, change_amount) =
-                unspent_transactions.select((output_value + fees).chain(|| {
-                    (
-                        ErrorKind::IllegalInput,
-                        "Sum of output values and fee exceeds maximum allowed amount",
-                    )
-                })?)?;
-            let raw_tx_builder = self.build_raw_transaction(
-                &selected_unspent_txs,
-                &outputs,
-                return_address.clone(),
-                change_amount,
-                attributes.clone(),
-                threshold,
-            );
-
-            let new_fees = raw_tx_builder.estimate_fee()?;
-            if new_fees > fees {
-                fees = new_fees;
-            } else {
-                break raw_tx_builder;
-            }
+
+        // fee only depends on number of inputs and outputs, as amounts (and addresses) are encoded
+        // in fixed size, so any address can stand for the change address before inputs are selected
+        let estimated_change_address = change_address
+            .resolve(
+                unspent_transactions
+                    .iter()
+                    .map(|(_, output)| &output.address),
+            )
+            .unwrap_or_else(|| ExtendedAddr::OrTree(Default::default()));
+        let change_output = TxOut::new(estimated_change_address, Coin::zero());
+        let fee_estimator = |no_of_inputs: usize, no_of_change_outputs: usize| {
+            let mut estimated_outputs = outputs.clone();
+            estimated_outputs
+                .extend(std::iter::repeat(change_output.clone()).take(no_of_change_outputs));
+            // a fee which cannot be calculated makes the selection fail
+            self.estimate_fee_with(no_of_inputs, threshold, &estimated_outputs, &attributes)
+                .unwrap_or_else(|_| Coin::max())
         };
 
+        let selection =
+            coin_selection.select(unspent_transactions, output_value, &fee_estimator)?;
+        let return_address = change_address
+            .resolve(selection.inputs.iter().map(|(_, output)| &output.address))
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "No unspent transaction is selected to return change to",
+                )
+            })?;
+        let raw_tx_builder = self.build_raw_transaction(
+            &selection.inputs,
+            &outputs,
+            return_address,
+            selection.change,
+            attributes,
+            threshold,
+        );
+
         Ok(raw_tx_builder)
     }
 
+    /// Estimates fee of a transaction with given number of inputs (signed with a `DummySigner`)
+    /// and given outputs
+    fn estimate_fee_with(
+        &self,
+        no_of_inputs: usize,
+        threshold: u16,
+        outputs: &[TxOut],
+        attributes: &TxAttributes,
+    ) -> Result<Coin> {
+        let dummy_input = (
+            TxoPointer::new(TxId::default(), 0),
+            TxOut::new(ExtendedAddr::OrTree(Default::default()), Coin::zero()),
+        );
+        let mut raw_tx_builder =
+            RawTransferTransactionBuilder::new(attributes.clone(), self.fee_algorithm.clone());
+        for _ in 0..no_of_inputs {
+            raw_tx_builder.add_input(dummy_input.clone(), threshold);
+        }
+        for output in outputs.iter() {
+            raw_tx_builder.add_output(output.clone());
+        }
+        raw_tx_builder.estimate_fee()
+    }
+
     fn build_raw_transaction(
         &self,
-        selected_unspent_transactions: &SelectedUnspentTransactions<'_>,
+        selected_unspent_transactions: &[(TxoPointer, TxOut)],
         outputs: &[TxOut],
         return_address: ExtendedAddr,
         change_amount: Coin,
@@ -246,6 +301,7 @@ mod default_wallet_transaction_builder_tests {
     use crate::types::WalletKind;
     use crate::unspent_transactions::{Operation, Sorter};
     use crate::wallet::{DefaultWalletClient, WalletClient};
+    use crate::LargestFirst;
 
     #[derive(Debug, Clone)]
     struct MockTransactionCipher;
@@ -289,7 +345,7 @@ mod default_wallet_transaction_builder_tests {
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
         let (enckey, _) = wallet_client
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
 
         let public_keys = vec![
@@ -355,9 +411,10 @@ mod default_wallet_transaction_builder_tests {
                 &enckey,
                 unspent_transactions.clone(),
                 outputs,
-                return_address,
+                return_address.into(),
                 attributes,
                 2,
+                &LargestFirst,
             )
             .unwrap();
 
@@ -417,6 +474,86 @@ mod default_wallet_transaction_builder_tests {
         }
     }
 
+    #[test]
+    fn check_dust_change_is_paid_as_fee() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let input = (
+            TxoPointer::new([0; 32], 0),
+            TxOut::new(
+                wallet_client.new_transfer_address(name, &enckey).unwrap(),
+                Coin::new(10000).unwrap(),
+            ),
+        );
+        let unspent_transactions = UnspentTransactions::new(vec![input.clone()]);
+        let recipient = ExtendedAddr::OrTree([9; 32]);
+        let attributes = TxAttributes::new(171);
+
+        let fee_algorithm = LinearFee::new(Milli::new(1, 1), Milli::new(1, 1));
+        let transaction_builder = DefaultWalletTransactionBuilder::new(
+            WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        // fee of transaction without change output
+        let mut estimation =
+            RawTransferTransactionBuilder::new(attributes.clone(), fee_algorithm.clone());
+        estimation.add_input(input.clone(), 1);
+        estimation.add_output(TxOut::new(recipient.clone(), Coin::zero()));
+        let fee = estimation.estimate_fee().unwrap();
+
+        // one unit of change does not pay for its own output
+        let amount = ((input.1.value - fee).unwrap() - Coin::unit()).unwrap();
+        let change_addresses = vec![
+            ChangeAddress::Address(wallet_client.new_transfer_address(name, &enckey).unwrap()),
+            ChangeAddress::FirstInput,
+        ];
+        for change_address in change_addresses {
+            let (tx_aux, selected_inputs, return_amount) = transaction_builder
+                .build_transfer_tx(
+                    name,
+                    &enckey,
+                    unspent_transactions.clone(),
+                    vec![TxOut::new(recipient.clone(), amount)],
+                    change_address,
+                    attributes.clone(),
+                    &LargestFirst,
+                )
+                .unwrap();
+            assert_eq!(vec![input.0.clone()], selected_inputs);
+            assert_eq!(Coin::zero(), return_amount);
+
+            match tx_aux {
+                TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                    payload: TxObfuscated { txpayload, .. },
+                    ..
+                }) => match PlainTxAux::decode(&mut txpayload.as_slice()).unwrap() {
+                    PlainTxAux::TransferTx(transaction, _) => {
+                        assert_eq!(
+                            vec![TxOut::new(recipient.clone(), amount)],
+                            transaction.outputs
+                        );
+                        // inputs are balanced by outputs and fee (including the dust)
+                        assert_eq!(
+                            input.1.value,
+                            ((amount + fee).unwrap() + Coin::unit()).unwrap()
+                        );
+                    }
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        }
+    }
+
     #[test]
     fn check_insufficient_balance_flow() {
         let name = "name";
@@ -426,7 +563,7 @@ mod default_wallet_transaction_builder_tests {
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
         let (enckey, _) = wallet_client
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
 
         let public_keys = vec![
@@ -477,15 +614,16 @@ mod default_wallet_transaction_builder_tests {
         )];
         let attributes = TxAttributes::new(171);
         assert_eq!(
-            ErrorKind::InvalidInput,
+            ErrorKind::InsufficientBalance,
             transaction_builder
                 .build_transfer_tx(
                     name,
                     &enckey,
                     unspent_transactions.clone(),
                     outputs,
-                    return_address,
+                    return_address.into(),
                     attributes,
+                    &LargestFirst,
                 )
                 .unwrap_err()
                 .kind()
@@ -1,12 +1,12 @@
 use chain_core::init::coin::Coin;
-use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use chain_core::tx::TxAux;
 use client_common::{ErrorKind, PrivateKey, Result, SecKey, SignedTransaction, Transaction};
 
-use crate::{UnspentTransactions, WalletTransactionBuilder};
+use crate::types::ChangeAddress;
+use crate::{CoinSelectionStrategy, UnspentTransactions, WalletTransactionBuilder};
 use chain_core::tx::data::TxId;
 
 /// Implementation of `WalletTransactionBuilder` which always returns
@@ -21,12 +21,17 @@ impl WalletTransactionBuilder for UnauthorizedWalletTransactionBuilder {
         _: &SecKey,
         _: UnspentTransactions,
         _: Vec<TxOut>,
-        _: ExtendedAddr,
+        _: ChangeAddress,
         _: TxAttributes,
+        _: &dyn CoinSelectionStrategy,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
         Err(ErrorKind::PermissionDenied.into())
     }
 
+    fn estimate_fee(&self, _: usize, _: &[TxOut], _: &TxAttributes) -> Result<Coin> {
+        Err(ErrorKind::PermissionDenied.into())
+    }
+
     fn obfuscate(&self, _: SignedTransaction) -> Result<TxAux> {
         Err(ErrorKind::PermissionDenied.into())
     }
@@ -1,13 +1,34 @@
 //! Types used in `client-core`
+mod address_balance;
+mod address_label;
 mod address_type;
+mod change_policy;
+mod consolidation;
+mod history;
+mod key_role;
+mod max_send;
+mod network_info;
+mod utxo;
+mod wallet_metadata;
 mod wallet_type;
 
 pub mod transaction_change;
 
+pub use self::address_balance::AddressBalance;
+pub use self::address_label::{LabeledAddress, MAX_ADDRESS_LABEL_LENGTH};
 pub use self::address_type::AddressType;
+pub use self::change_policy::{ChangeAddress, ChangePolicy};
+pub use self::consolidation::ConsolidationSummary;
+pub use self::history::{Direction, HistoryFilter, HistoryPage, SortOrder, MAX_HISTORY_PAGE_LIMIT};
+pub use self::key_role::KeyRole;
+pub use self::max_send::MaxSend;
+pub use self::network_info::{verify_wallet_network, NetworkInfo};
 #[doc(inline)]
 pub use self::transaction_change::{
-    BalanceChange, TransactionChange, TransactionInput, TransactionPending, TransactionType,
-    WalletBalance,
+    BalanceChange, Depositor, DetailedBalance, PendingReconciliation, PendingTransactionInfo,
+    PendingTransactionStatus, TransactionChange, TransactionInput, TransactionPending,
+    TransactionType, WalletBalance, DEFAULT_PENDING_TX_VALID_BLOCKS,
 };
+pub use self::utxo::{UtxoFilter, UtxoInfo, UtxoSortOrder};
+pub use self::wallet_metadata::WalletMetadata;
 pub use self::wallet_type::WalletKind;
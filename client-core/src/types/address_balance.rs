@@ -0,0 +1,61 @@
+//! Types for reporting balances of individual addresses of a wallet
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+
+use crate::types::LabeledAddress;
+
+/// Balance and activity of a single address of a wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AddressBalance {
+    /// Transfer or staking address
+    pub address: LabeledAddress,
+    /// Sum of synced unspent outputs of the address (including the ones used by pending
+    /// transactions)
+    pub balance: Coin,
+    /// Number of synced unspent outputs of the address
+    pub utxo_count: usize,
+    /// Height of first block with a transaction paying to the address
+    pub first_seen_height: Option<u64>,
+    /// Height of last block with a transaction paying to or spending from the address
+    pub last_active_height: Option<u64>,
+    /// Label of the address (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Bonded amount of staking address (if its staked state is known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bonded: Option<Coin>,
+    /// Unbonded amount of staking address (if its staked state is known)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unbonded: Option<Coin>,
+}
+
+impl AddressBalance {
+    /// Creates a balance of given address without any activity
+    pub fn new(address: LabeledAddress) -> Self {
+        Self {
+            address,
+            balance: Coin::zero(),
+            utxo_count: 0,
+            first_seen_height: None,
+            last_active_height: None,
+            label: None,
+            bonded: None,
+            unbonded: None,
+        }
+    }
+
+    /// Records activity of the address in block of given height
+    pub fn record_activity(&mut self, block_height: u64, received: bool) {
+        if received {
+            self.first_seen_height = Some(
+                self.first_seen_height
+                    .map_or(block_height, |height| height.min(block_height)),
+            );
+        }
+        self.last_active_height = Some(
+            self.last_active_height
+                .map_or(block_height, |height| height.max(block_height)),
+        );
+    }
+}
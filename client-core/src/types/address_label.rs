@@ -0,0 +1,46 @@
+//! Types for labelling addresses of a wallet
+use std::fmt;
+
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::state::account::StakedStateAddress;
+use chain_core::tx::data::address::ExtendedAddr;
+
+/// Maximum number of characters in an address label
+pub const MAX_ADDRESS_LABEL_LENGTH: usize = 64;
+
+/// Address which can be labelled
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Encode, Decode,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum LabeledAddress {
+    /// Transfer address
+    Transfer(ExtendedAddr),
+    /// Staking address
+    Staking(StakedStateAddress),
+}
+
+impl fmt::Display for LabeledAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LabeledAddress::Transfer(address) => write!(f, "{}", address),
+            LabeledAddress::Staking(address) => write!(f, "{}", address),
+        }
+    }
+}
+
+impl From<ExtendedAddr> for LabeledAddress {
+    #[inline]
+    fn from(address: ExtendedAddr) -> Self {
+        LabeledAddress::Transfer(address)
+    }
+}
+
+impl From<StakedStateAddress> for LabeledAddress {
+    #[inline]
+    fn from(address: StakedStateAddress) -> Self {
+        LabeledAddress::Staking(address)
+    }
+}
@@ -0,0 +1,50 @@
+use chain_core::tx::data::address::ExtendedAddr;
+
+/// Policy for choosing the address receiving change of transfers built by a wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangePolicy {
+    /// Change goes to a new transfer address of the wallet for every transaction
+    NewInternalAddress,
+    /// Change always goes to given transfer address of the wallet
+    FixedAddress(ExtendedAddr),
+    /// Change goes back to the address of the first selected input
+    ReuseFirstInput,
+}
+
+impl Default for ChangePolicy {
+    #[inline]
+    fn default() -> Self {
+        ChangePolicy::NewInternalAddress
+    }
+}
+
+/// Address receiving change of a transfer transaction (resolved from `ChangePolicy` before
+/// inputs are selected)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChangeAddress {
+    /// Change goes to given address
+    Address(ExtendedAddr),
+    /// Change goes to the address of the first selected input
+    FirstInput,
+}
+
+impl ChangeAddress {
+    /// Returns the address receiving change of a transaction spending given outputs (in order of
+    /// selection)
+    pub fn resolve<'a, I>(&self, mut spent_outputs: I) -> Option<ExtendedAddr>
+    where
+        I: Iterator<Item = &'a ExtendedAddr>,
+    {
+        match self {
+            ChangeAddress::Address(address) => Some(address.clone()),
+            ChangeAddress::FirstInput => spent_outputs.next().cloned(),
+        }
+    }
+}
+
+impl From<ExtendedAddr> for ChangeAddress {
+    #[inline]
+    fn from(address: ExtendedAddr) -> Self {
+        ChangeAddress::Address(address)
+    }
+}
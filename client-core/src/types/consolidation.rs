@@ -0,0 +1,19 @@
+//! Summary of a UTXO consolidation transaction
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::input::TxoPointer;
+
+/// Summary of a transaction consolidating unspent transactions of a wallet into one output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConsolidationSummary {
+    /// Unspent transactions consumed by transaction
+    pub inputs: Vec<TxoPointer>,
+    /// Fee paid by transaction
+    pub fee: Coin,
+    /// Transfer address receiving consolidated value
+    pub output_address: ExtendedAddr,
+    /// Value of consolidated output
+    pub output_value: Coin,
+}
@@ -0,0 +1,97 @@
+//! Types for paginated transaction history of a wallet
+use serde::{Deserialize, Serialize};
+
+use chain_core::common::Timespec;
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::address::ExtendedAddr;
+use client_common::tendermint::types::Time;
+
+use crate::types::{BalanceChange, TransactionChange, TransactionType};
+
+/// Maximum number of records in a page of transaction history
+pub const MAX_HISTORY_PAGE_LIMIT: usize = 1000;
+
+/// Order of records in transaction history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// Oldest transaction first
+    Ascending,
+    /// Newest transaction first
+    Descending,
+}
+
+impl Default for SortOrder {
+    #[inline]
+    fn default() -> Self {
+        SortOrder::Ascending
+    }
+}
+
+/// Direction of balance change of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// Transaction increases balance
+    Incoming,
+    /// Transaction decreases balance
+    Outgoing,
+}
+
+/// Filter of records in transaction history
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryFilter {
+    /// Direction of balance change
+    pub direction: Option<Direction>,
+    /// Type of transaction
+    pub tx_type: Option<TransactionType>,
+    /// Minimum block time (inclusive, seconds since unix epoch)
+    pub from_time: Option<Timespec>,
+    /// Maximum block time (exclusive, seconds since unix epoch)
+    pub to_time: Option<Timespec>,
+    /// Minimum value of balance change (inclusive)
+    pub min_amount: Option<Coin>,
+    /// Joins labels of addresses in the records into the page
+    #[serde(default)]
+    pub include_labels: bool,
+}
+
+impl HistoryFilter {
+    /// Returns `true` if given transaction change passes the filter, `false` otherwise
+    pub fn matches(&self, change: &TransactionChange) -> bool {
+        let (direction, amount) = match change.balance_change {
+            BalanceChange::Incoming { value } => (Some(Direction::Incoming), value),
+            BalanceChange::Outgoing { value } => (Some(Direction::Outgoing), value),
+            BalanceChange::NoChange => (None, Coin::zero()),
+        };
+        // block times before unix epoch are not produced by tendermint
+        let block_time = change
+            .block_time
+            .duration_since(Time::unix_epoch())
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+
+        self.direction
+            .map_or(true, |expected| direction == Some(expected))
+            && self
+                .tx_type
+                .map_or(true, |tx_type| tx_type == change.transaction_type)
+            && self.from_time.map_or(true, |from| block_time >= from)
+            && self.to_time.map_or(true, |to| block_time < to)
+            && self.min_amount.map_or(true, |min| amount >= min)
+    }
+}
+
+/// Page of transaction history
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryPage {
+    /// Transaction changes in the page
+    pub records: Vec<TransactionChange>,
+    /// Total number of transaction changes in history
+    pub total: usize,
+    /// `true` if there are transaction changes after this page, `false` otherwise
+    pub has_more: bool,
+    /// Labels of addresses in inputs and outputs of the records (if requested in filter)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<(ExtendedAddr, String)>,
+}
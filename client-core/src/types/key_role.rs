@@ -0,0 +1,31 @@
+use std::str::FromStr;
+
+use unicase::eq_ascii;
+
+use client_common::{Error, ErrorKind, Result};
+
+/// Role of an imported private key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyRole {
+    /// Key of a 1-of-1 transfer address
+    Transfer,
+    /// Key of a staking address
+    Staking,
+}
+
+impl FromStr for KeyRole {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if eq_ascii(s, "transfer") {
+            Ok(KeyRole::Transfer)
+        } else if eq_ascii(s, "staking") {
+            Ok(KeyRole::Staking)
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Key role can either be `transfer` or `staking`",
+            ))
+        }
+    }
+}
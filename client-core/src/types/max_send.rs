@@ -0,0 +1,16 @@
+//! Maximum amount a wallet can send in a single transfer
+use serde::{Deserialize, Serialize};
+
+use chain_core::init::coin::Coin;
+
+/// Largest amount a transfer spending all spendable unspent transactions of a wallet sends to one
+/// output (after fee)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaxSend {
+    /// Amount received by destination
+    pub amount: Coin,
+    /// Fee paid by transaction
+    pub fee: Coin,
+    /// Number of unspent transactions spent by transaction
+    pub inputs_used: usize,
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use client_common::tendermint::types::{Genesis, GenesisExt};
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+/// Network a wallet is used on (recorded when the wallet is created)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct NetworkInfo {
+    /// Chain hex id (last two hex digits of chain id)
+    pub chain_hex_id: u8,
+    /// Tendermint chain id
+    pub chain_id: String,
+}
+
+impl NetworkInfo {
+    /// Creates network information from tendermint chain id
+    pub fn from_chain_id(chain_id: &str) -> Result<Self> {
+        let chain_hex_id = chain_id
+            .len()
+            .checked_sub(2)
+            .and_then(|start| chain_id.get(start..))
+            .and_then(|hex_id| hex::decode(hex_id).ok())
+            .map(|hex_id| hex_id[0])
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!("Chain id does not end with two hex digits: {}", chain_id),
+                )
+            })?;
+
+        Ok(NetworkInfo {
+            chain_hex_id,
+            chain_id: chain_id.to_owned(),
+        })
+    }
+
+    /// Returns network of given genesis
+    pub fn from_genesis(genesis: &Genesis) -> Result<Self> {
+        Ok(NetworkInfo {
+            chain_hex_id: genesis.chain_hex_id()?,
+            chain_id: genesis.chain_id.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for NetworkInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:02x})", self.chain_id, self.chain_hex_id)
+    }
+}
+
+/// Checks that wallet with given recorded network is used on `connected` network
+///
+/// Wallets created before networks were recorded are accepted with a warning.
+pub fn verify_wallet_network(
+    name: &str,
+    recorded: Option<&NetworkInfo>,
+    connected: &NetworkInfo,
+) -> Result<()> {
+    match recorded {
+        Some(recorded) if recorded != connected => Err(Error::new(
+            ErrorKind::ValidationError,
+            format!(
+                "Wallet ({}) was created for network {}, but connected network is {}",
+                name, recorded, connected
+            ),
+        )),
+        Some(_) => Ok(()),
+        None => {
+            log::warn!(
+                "Network of wallet ({}) is not recorded, assuming connected network {}",
+                name,
+                connected
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_verify_wallet_network() {
+        let testnet = NetworkInfo::from_chain_id("test-chain-y3m1e6-AB").unwrap();
+        assert_eq!(0xab, testnet.chain_hex_id);
+        let other = NetworkInfo::from_chain_id("test-chain-y3m1e6-42").unwrap();
+        assert_eq!(0x42, other.chain_hex_id);
+        assert!(NetworkInfo::from_chain_id("test-chain").is_err());
+        assert!(NetworkInfo::from_chain_id("A").is_err());
+
+        assert!(verify_wallet_network("name", Some(&testnet), &testnet).is_ok());
+        assert!(verify_wallet_network("name", None, &testnet).is_ok());
+        assert_eq!(
+            ErrorKind::ValidationError,
+            verify_wallet_network("name", Some(&testnet), &other)
+                .unwrap_err()
+                .kind()
+        );
+    }
+}
@@ -7,6 +7,7 @@ use parity_scale_codec::{Decode, Encode, Error, Input, Output};
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use chain_core::{
+    common::Timespec,
     init::coin::{Coin, CoinError},
     tx::data::{input::TxoPointer, output::TxOut, TxId},
     tx::fee::Fee,
@@ -53,6 +54,39 @@ pub struct WalletBalance {
     pub pending: Coin,
 }
 
+/// Wallet balance broken down by why coins can or cannot be spent
+///
+/// It reconciles with `WalletBalance` as:
+///
+/// ```plain
+/// total = available + timelocked + frozen + pending_incoming
+/// WalletBalance.available = available + timelocked + frozen
+/// WalletBalance.pending = pending_incoming
+/// ```
+#[derive(Debug, Default, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DetailedBalance {
+    /// The total amount balance (same as `WalletBalance.total`)
+    pub total: Coin,
+    /// Synced unspent outputs which can be spent now
+    pub available: Coin,
+    /// Incoming (returned) coins of pending transactions
+    pub pending_incoming: Coin,
+    /// Synced unspent outputs spent by pending transactions
+    pub pending_outgoing: Coin,
+    /// Synced unspent outputs (not spent by pending transactions) which are still timelocked
+    pub timelocked: Coin,
+    /// Synced unspent outputs (not spent by pending transactions) which are frozen (whether they
+    /// are timelocked or not)
+    #[serde(default)]
+    pub frozen: Coin,
+    /// Earliest time (seconds since unix epoch) at which a timelocked output becomes spendable
+    pub earliest_unlock: Option<Timespec>,
+}
+
+/// Default number of blocks after which a pending transaction which is not included in a block
+/// is given up (and its inputs are released)
+pub const DEFAULT_PENDING_TX_VALID_BLOCKS: u64 = 50;
+
 /// Transaction pending infomation
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct TransactionPending {
@@ -62,6 +96,53 @@ pub struct TransactionPending {
     pub block_height: u64,
     /// the return amount of the transaction
     pub return_amount: Coin,
+    /// Id of the transaction
+    #[serde(default)]
+    pub tx_id: TxId,
+    /// The block height after which the transaction is given up if not included in a block
+    /// (`0` for records created before it was tracked)
+    #[serde(default)]
+    pub valid_until_height: u64,
+}
+
+/// Status of a transaction broadcast by the wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PendingTransactionStatus {
+    /// Transaction is not included in a block yet (its inputs are locked)
+    Pending,
+    /// Transaction is included in a block
+    Confirmed,
+    /// Transaction was not included in a block before its `valid_until_height` (its inputs are
+    /// unlocked)
+    Expired,
+}
+
+/// Transaction broadcast by the wallet along with its status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingTransactionInfo {
+    /// Pending information recorded when the transaction was broadcast
+    #[serde(flatten)]
+    pub pending: TransactionPending,
+    /// Current status of the transaction
+    pub status: PendingTransactionStatus,
+    /// Outcome of checking the confirmed transaction against pending information (`None` if it
+    /// is not confirmed)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reconciliation: Option<PendingReconciliation>,
+}
+
+/// Outcome of checking a confirmed transaction against its pending record
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub enum PendingReconciliation {
+    /// Confirmed transaction matches the pending record
+    ConfirmedVerified,
+    /// Confirmed transaction differs from the pending record
+    ConfirmedMismatch {
+        /// Human readable description of each difference
+        diffs: Vec<String>,
+    },
+    /// Transaction is confirmed but its content is not available to this wallet
+    ConfirmedUnverified,
 }
 
 /// Transaction data with attached metadata
@@ -87,6 +168,27 @@ pub struct TransactionChange {
     pub block_height: u64,
     /// Time of block which has this transaction
     pub block_time: Time,
+    /// Memo in attributes of transaction (if any)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Depositor of deposit transaction to a staking address of wallet (`None` for other
+    /// transactions)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub depositor: Option<Depositor>,
+}
+
+/// Depositor of a deposit transaction to a staking address of wallet
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Encode, Decode)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Depositor {
+    /// Deposit is paid from unspent transactions of this wallet
+    Wallet,
+    /// Deposit is paid by another wallet (recorded as incoming balance change, with zero value if
+    /// amount is unknown)
+    External {
+        /// Deposited amount (`None` if inputs of deposit are not visible to this wallet)
+        amount: Option<Coin>,
+    },
 }
 
 /// Transaction input
@@ -184,6 +286,8 @@ impl Encode for TransactionChange {
         self.transaction_type.encode_to(dest);
         self.block_height.encode_to(dest);
         self.block_time.to_rfc3339().encode_to(dest);
+        self.memo.encode_to(dest);
+        self.depositor.encode_to(dest);
     }
 
     fn size_hint(&self) -> usize {
@@ -194,6 +298,8 @@ impl Encode for TransactionChange {
             + self.balance_change.size_hint()
             + self.block_height.size_hint()
             + self.block_time.to_rfc3339().as_bytes().size_hint()
+            + self.memo.size_hint()
+            + self.depositor.size_hint()
     }
 }
 
@@ -208,6 +314,13 @@ impl Decode for TransactionChange {
         let block_height = u64::decode(input)?;
         let block_time = Time::from_str(&String::decode(input)?)
             .map_err(|_| Error::from("Unable to parse block time"))?;
+        let memo = <Option<String>>::decode(input)?;
+        // records stored before depositors were tracked end here
+        let depositor = if input.remaining_len()? == Some(0) {
+            None
+        } else {
+            <Option<Depositor>>::decode(input)?
+        };
         Ok(TransactionChange {
             transaction_id,
             inputs,
@@ -217,6 +330,8 @@ impl Decode for TransactionChange {
             transaction_type,
             block_height,
             block_time,
+            memo,
+            depositor,
         })
     }
 }
@@ -282,12 +397,33 @@ mod tests {
             fee_paid: Fee::new(Coin::one()),
             block_height: 0,
             block_time: Time::now(),
+            memo: Some("deposit tag 42".to_owned()),
+            depositor: None,
         };
 
         let encoded = transaction_change.encode();
         let decoded = TransactionChange::decode(&mut encoded.as_ref()).unwrap();
 
         assert_eq!(transaction_change, decoded);
+
+        let deposit = TransactionChange {
+            transaction_type: TransactionType::Deposit,
+            depositor: Some(Depositor::External { amount: None }),
+            ..transaction_change.clone()
+        };
+        let encoded = deposit.encode();
+        assert_eq!(
+            deposit,
+            TransactionChange::decode(&mut encoded.as_ref()).unwrap()
+        );
+
+        // records without depositor
+        let encoded = transaction_change.encode();
+        let legacy = &encoded[..encoded.len() - 1];
+        assert_eq!(
+            transaction_change,
+            TransactionChange::decode(&mut legacy.as_ref()).unwrap()
+        );
     }
 
     #[test]
@@ -0,0 +1,186 @@
+//! Types for listing unspent transactions of a wallet
+use serde::{Deserialize, Serialize};
+
+use chain_core::common::Timespec;
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::address::ExtendedAddr;
+use chain_core::tx::data::input::TxoPointer;
+use chain_core::tx::data::output::TxOut;
+
+/// Order of listed unspent transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UtxoSortOrder {
+    /// Smallest value first
+    ValueAscending,
+    /// Largest value first
+    ValueDescending,
+    /// Lowest confirmation height first
+    OldestFirst,
+    /// Highest confirmation height first
+    NewestFirst,
+}
+
+impl Default for UtxoSortOrder {
+    #[inline]
+    fn default() -> Self {
+        UtxoSortOrder::OldestFirst
+    }
+}
+
+/// Filter (and order) of listed unspent transactions
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UtxoFilter {
+    /// Minimum value (inclusive)
+    pub min_value: Option<Coin>,
+    /// Maximum value (inclusive)
+    pub max_value: Option<Coin>,
+    /// Address of output
+    pub address: Option<ExtendedAddr>,
+    /// Excludes outputs which are timelocked at given time
+    pub spendable_at: Option<Timespec>,
+    /// Order of listed unspent transactions
+    #[serde(default)]
+    pub sort: UtxoSortOrder,
+    /// Joins labels of output addresses into listed unspent transactions
+    #[serde(default)]
+    pub include_labels: bool,
+}
+
+impl UtxoFilter {
+    /// Returns `true` if given output passes the filter, `false` otherwise
+    pub fn matches(&self, output: &TxOut) -> bool {
+        self.min_value.map_or(true, |min| output.value >= min)
+            && self.max_value.map_or(true, |max| output.value <= max)
+            && self
+                .address
+                .as_ref()
+                .map_or(true, |address| output.address == *address)
+            && match (self.spendable_at, output.valid_from) {
+                (Some(time), Some(valid_from)) => valid_from <= time,
+                _ => true,
+            }
+    }
+
+    /// Sorts given unspent transactions in order of this filter
+    pub fn sort(&self, utxos: &mut [UtxoInfo]) {
+        // unknown confirmation heights are treated as oldest
+        match self.sort {
+            UtxoSortOrder::ValueAscending => utxos.sort_by_key(|utxo| utxo.output.value),
+            UtxoSortOrder::ValueDescending => {
+                utxos.sort_by(|a, b| b.output.value.cmp(&a.output.value))
+            }
+            UtxoSortOrder::OldestFirst => utxos.sort_by_key(|utxo| utxo.confirmation_height),
+            UtxoSortOrder::NewestFirst => {
+                utxos.sort_by(|a, b| b.confirmation_height.cmp(&a.confirmation_height))
+            }
+        }
+    }
+}
+
+/// Unspent transaction of a wallet
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UtxoInfo {
+    /// Pointer to unspent transaction
+    pub pointer: TxoPointer,
+    /// Unspent transaction output
+    pub output: TxOut,
+    /// Height of block containing the transaction (if it is in transaction history)
+    pub confirmation_height: Option<u64>,
+    /// `true` if output is used by a pending transaction
+    pub pending: bool,
+    /// `true` if output is frozen (excluded from coin selection)
+    #[serde(default)]
+    pub frozen: bool,
+    /// Label of output address (if requested in filter)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utxo(value: u64, height: Option<u64>) -> UtxoInfo {
+        UtxoInfo {
+            pointer: TxoPointer::new([value as u8; 32], 0),
+            output: TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(value).unwrap()),
+            confirmation_height: height,
+            pending: false,
+            frozen: false,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn check_filter_matches() {
+        let mut output = TxOut::new(ExtendedAddr::OrTree([1; 32]), Coin::new(50).unwrap());
+        output.valid_from = Some(1000);
+
+        assert!(UtxoFilter::default().matches(&output));
+
+        let filter = UtxoFilter {
+            min_value: Some(Coin::new(50).unwrap()),
+            max_value: Some(Coin::new(60).unwrap()),
+            address: Some(ExtendedAddr::OrTree([1; 32])),
+            spendable_at: Some(1000),
+            ..Default::default()
+        };
+        assert!(filter.matches(&output));
+
+        let excluding_filters = vec![
+            UtxoFilter {
+                min_value: Some(Coin::new(51).unwrap()),
+                ..filter.clone()
+            },
+            UtxoFilter {
+                max_value: Some(Coin::new(49).unwrap()),
+                ..filter.clone()
+            },
+            UtxoFilter {
+                address: Some(ExtendedAddr::OrTree([2; 32])),
+                ..filter.clone()
+            },
+            UtxoFilter {
+                spendable_at: Some(999),
+                ..filter.clone()
+            },
+        ];
+        for filter in excluding_filters {
+            assert!(!filter.matches(&output));
+        }
+    }
+
+    #[test]
+    fn check_filter_sort() {
+        let mut utxos = vec![utxo(30, Some(5)), utxo(10, None), utxo(20, Some(9))];
+        let values = |utxos: &[UtxoInfo]| {
+            utxos
+                .iter()
+                .map(|utxo| u64::from(utxo.output.value))
+                .collect::<Vec<_>>()
+        };
+
+        let fixtures = vec![
+            (UtxoSortOrder::ValueAscending, vec![10, 20, 30]),
+            (UtxoSortOrder::ValueDescending, vec![30, 20, 10]),
+            (UtxoSortOrder::OldestFirst, vec![10, 30, 20]),
+            (UtxoSortOrder::NewestFirst, vec![20, 30, 10]),
+        ];
+        for (sort, expected) in fixtures {
+            let filter = UtxoFilter {
+                sort,
+                ..Default::default()
+            };
+            filter.sort(&mut utxos);
+            assert_eq!(expected, values(&utxos));
+        }
+    }
+
+    #[test]
+    fn check_filter_serialization() {
+        let filter: UtxoFilter = serde_json::from_str(r#"{"min_value":"10"}"#).unwrap();
+        assert_eq!(Some(Coin::new(10).unwrap()), filter.min_value);
+        assert_eq!(UtxoSortOrder::OldestFirst, filter.sort);
+    }
+}
@@ -0,0 +1,26 @@
+//! Type for describing a wallet without unlocking it
+use serde::{Deserialize, Serialize};
+
+use chain_core::common::Timespec;
+
+use crate::types::{NetworkInfo, WalletKind};
+
+/// Metadata of a wallet (stored unencrypted, never contains keys or other secrets)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalletMetadata {
+    /// Name of wallet
+    pub name: String,
+    /// Kind of wallet
+    pub kind: WalletKind,
+    /// Time (seconds since unix epoch) at which wallet was created (`None` for wallets created
+    /// before it was recorded)
+    pub created_at: Option<Timespec>,
+    /// Network wallet was created for (`None` if it is not recorded)
+    pub network: Option<NetworkInfo>,
+    /// `true` if wallet has an HD seed (its addresses are derived from mnemonic)
+    pub hd: bool,
+    /// Number of transfer addresses of wallet
+    pub transfer_address_count: u64,
+    /// Number of staking addresses of wallet
+    pub staking_address_count: u64,
+}
@@ -16,6 +16,8 @@ pub enum WalletKind {
     HD,
     /// HW Wallet
     HW,
+    /// Watch-only Wallet (view key and public keys only, can not sign)
+    WatchOnly,
 }
 
 impl From<u64> for WalletKind {
@@ -23,6 +25,7 @@ impl From<u64> for WalletKind {
         match code {
             0 => WalletKind::Basic,
             1 => WalletKind::HD,
+            3 => WalletKind::WatchOnly,
             _ => WalletKind::HW,
         }
     }
@@ -38,15 +41,31 @@ impl FromStr for WalletKind {
             Ok(WalletKind::HW)
         } else if eq_ascii(s, "basic") {
             Ok(WalletKind::Basic)
+        } else if eq_ascii(s, "watch") || eq_ascii(s, "watch-only") {
+            Ok(WalletKind::WatchOnly)
         } else {
             Err(Error::new(
                 ErrorKind::InvalidInput,
-                "Wallet type can either be `hd` or `hw` or `basic`",
+                "Wallet type can either be `hd` or `hw` or `basic` or `watch`",
             ))
         }
     }
 }
 
+impl WalletKind {
+    /// Returns an error if wallets of this kind can not sign transactions
+    pub fn verify_can_sign(self) -> Result<()> {
+        if self == WalletKind::WatchOnly {
+            Err(Error::new(
+                ErrorKind::PermissionDenied,
+                "Watch-only wallet can not sign transactions",
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl Default for WalletKind {
     fn default() -> Self {
         WalletKind::Basic
@@ -3,7 +3,8 @@ use parity_scale_codec::{Decode, Encode};
 use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
-use chain_core::init::coin::Coin;
+use chain_core::common::Timespec;
+use chain_core::init::coin::{sum_coins, Coin};
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
 use client_common::{Error, ErrorKind, Result, ResultExt};
@@ -121,6 +122,37 @@ impl UnspentTransactions {
     pub fn select_all(&self) -> SelectedUnspentTransactions<'_> {
         SelectedUnspentTransactions { inner: &self.0 }
     }
+
+    /// Removes unspent transactions which are timelocked at given time (in seconds since unix
+    /// epoch) and returns their total value and earliest time one of them becomes spendable
+    pub fn remove_timelocked(&mut self, time: Timespec) -> Result<Option<(Coin, Timespec)>> {
+        let (timelocked, spendable): (Vec<_>, Vec<_>) = std::mem::take(&mut self.0)
+            .into_iter()
+            .partition(|(_, output)| {
+                output
+                    .valid_from
+                    .map_or(false, |valid_from| valid_from > time)
+            });
+        self.0 = spendable;
+
+        match timelocked
+            .iter()
+            .filter_map(|(_, output)| output.valid_from)
+            .min()
+        {
+            Some(earliest_unlock) => {
+                let amount =
+                    sum_coins(timelocked.iter().map(|(_, output)| output.value)).chain(|| {
+                        (
+                            ErrorKind::IllegalInput,
+                            "Total amount of timelocked UTXOs exceeds maximum allowed value",
+                        )
+                    })?;
+                Ok(Some((amount, earliest_unlock)))
+            }
+            None => Ok(None),
+        }
+    }
 }
 
 /// Builder for unspent transactions
@@ -263,4 +295,24 @@ mod unspent_transactions_tests {
             coin = tx_out.value;
         }
     }
+
+    #[test]
+    fn check_remove_timelocked() {
+        let mut unspent_transactions = sample();
+        unspent_transactions[1].1.valid_from = Some(2000);
+        unspent_transactions[2].1.valid_from = Some(1500);
+        unspent_transactions[3].1.valid_from = Some(1000);
+        let free = unspent_transactions[0].clone();
+
+        assert_eq!(
+            Some((Coin::new(500).unwrap(), 1500)),
+            unspent_transactions.remove_timelocked(1000).unwrap()
+        );
+        assert_eq!(3, unspent_transactions.len());
+        assert_eq!(free, unspent_transactions[0]);
+        assert_eq!(Some(1000), unspent_transactions[1].1.valid_from);
+
+        assert_eq!(None, unspent_transactions.remove_timelocked(1000).unwrap());
+        assert_eq!(3, unspent_transactions.len());
+    }
 }
@@ -1,15 +1,22 @@
 //! Wallet management
+mod address_discovery;
+mod backup;
 mod default_wallet_client;
+mod history_csv;
 /// Wallet synchronizer
 pub mod syncer;
 mod syncer_logic;
 
+pub use address_discovery::{DiscoveredAddresses, DEFAULT_GAP_LIMIT};
+pub use backup::BACKUP_VERSION;
 pub use default_wallet_client::DefaultWalletClient;
 
 use indexmap::IndexSet;
 use secp256k1::schnorrsig::SchnorrSignature;
 use secstr::SecUtf8;
 use std::collections::BTreeSet;
+use std::io::Write;
+use std::time::Duration;
 
 use chain_core::common::{Proof, H256};
 use chain_core::init::address::RedeemAddress;
@@ -29,10 +36,18 @@ use client_common::{
 use serde::{Deserialize, Serialize};
 
 use crate::hd_wallet::HardwareKind;
-use crate::service::WalletInfo;
+use crate::multi_sig::PartiallySignedTransfer;
+use crate::service::{
+    BroadcastLogEntry, HdPath, RebroadcastOutcome, SessionToken, WalletInfo, WatchOnlyWalletInfo,
+};
 use crate::transaction_builder::{SignedTransferTransaction, UnsignedTransferTransaction};
-use crate::types::{AddressType, TransactionChange, TransactionPending, WalletBalance, WalletKind};
-use crate::{InputSelectionStrategy, Mnemonic, UnspentTransactions};
+use crate::types::{
+    AddressBalance, AddressType, ConsolidationSummary, DetailedBalance, HistoryFilter, HistoryPage,
+    KeyRole, LabeledAddress, MaxSend, NetworkInfo, PendingTransactionInfo, SortOrder,
+    TransactionChange, TransactionPending, UtxoFilter, UtxoInfo, WalletBalance, WalletKind,
+    WalletMetadata, MAX_HISTORY_PAGE_LIMIT,
+};
+use crate::{CoinSelectionStrategy, Mnemonic, UnspentTransactions};
 
 /// information needed when create/delete a wallet
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -89,12 +104,27 @@ pub trait WalletClient: Send + Sync {
     /// Retrieves names of all wallets stored
     fn wallets(&self) -> Result<Vec<String>>;
 
+    /// Returns network given wallet was created for (`None` for wallets created before networks
+    /// were recorded, or by a client without configured network)
+    fn wallet_network(&self, name: &str) -> Result<Option<NetworkInfo>>;
+
+    /// Returns metadata of all wallets stored (does not need their passphrases)
+    fn list_wallets(&self) -> Result<Vec<WalletMetadata>>;
+
+    /// Returns metadata of given wallet (does not need its passphrase)
+    fn wallet_info(&self, name: &str) -> Result<WalletMetadata>;
+
     /// Creates a new wallet with given name, enckey and kind. Returns mnemonics if `wallet_kind` was `HD`.
+    /// `hd_account` selects account of HD wallet (defaults to `0`) and `mnemonic_passphrase` is
+    /// the optional BIP39 passphrase ("25th word") mixed into seed of HD wallet (it is never
+    /// stored, so it is needed along with mnemonic to restore the wallet).
     fn new_wallet(
         &self,
         name: &str,
         passphrase: &SecUtf8,
         wallet_kind: WalletKind,
+        hd_account: Option<u32>,
+        mnemonic_passphrase: Option<&SecUtf8>,
     ) -> Result<(SecKey, Option<Mnemonic>)>;
 
     /// export wallet info including private key, transfer address, staking address and so on
@@ -108,12 +138,16 @@ pub trait WalletClient: Send + Sync {
         wallet_info: WalletInfo,
     ) -> Result<SecKey>;
 
-    /// Restores a HD wallet from given mnemonic
+    /// Restores a HD wallet from given mnemonic and optional BIP39 passphrase ("25th word")
+    ///
+    /// NOTE: Any passphrase is valid, so restoring with a different passphrase than the one used
+    /// when creating the wallet silently restores a different (empty) wallet.
     fn restore_wallet(
         &self,
         name: &str,
         passphrase: &SecUtf8,
         mnemonic: &Mnemonic,
+        mnemonic_passphrase: Option<&SecUtf8>,
     ) -> Result<SecKey>;
 
     /// Restore a watch only wallet with view key
@@ -124,12 +158,125 @@ pub trait WalletClient: Send + Sync {
         view_key: &PrivateKey,
     ) -> Result<SecKey>;
 
-    /// Remove a wallet
-    fn delete_wallet(&self, name: &str, passphrase: &SecUtf8) -> Result<()>;
+    /// Creates a watch-only wallet from given private view key and public keys of transfer and
+    /// staking addresses (transactions can not be signed with it)
+    fn new_watch_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        view_key: &PrivateKey,
+        public_keys: &[PublicKey],
+        staking_keys: &[PublicKey],
+    ) -> Result<SecKey>;
+
+    /// Exports view key and public keys of a wallet, to be imported as a watch-only wallet
+    fn export_watch_only_wallet(&self, name: &str, enckey: &SecKey) -> Result<WatchOnlyWalletInfo>;
+
+    /// Creates an encrypted backup of a wallet (keys, addresses and labels, but not sync state)
+    /// protected with given backup passphrase
+    fn export_wallet_backup(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        backup_passphrase: &SecUtf8,
+    ) -> Result<Vec<u8>>;
+
+    /// Restores a wallet from an encrypted backup (returns enckey of restored wallet)
+    ///
+    /// Existing wallet with the same name is only overwritten if `force` is set (and `passphrase`
+    /// is its passphrase).
+    fn import_wallet_backup(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        backup_passphrase: &SecUtf8,
+        backup: &[u8],
+        force: bool,
+    ) -> Result<SecKey>;
+
+    /// Removes a wallet, overwriting all its records before deleting them
+    ///
+    /// Without `confirmation_token`, returns a token to be passed to a second call which actually
+    /// deletes the wallet (and returns `None`).
+    fn delete_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        confirmation_token: Option<&str>,
+    ) -> Result<Option<String>>;
 
     /// get auth token client
     fn auth_token(&self, name: &str, passphrase: &SecUtf8) -> Result<SecKey>;
 
+    /// Changes passphrase of a wallet, re-encrypting all its records (returns new enckey)
+    ///
+    /// Either all records are re-encrypted or none are, even if the change is interrupted (it is
+    /// completed or discarded on next `auth_token` call).
+    fn change_passphrase(
+        &self,
+        name: &str,
+        old_passphrase: &SecUtf8,
+        new_passphrase: &SecUtf8,
+    ) -> Result<SecKey>;
+
+    /// Renames a wallet, re-encrypting all its records under the new name (returns new enckey)
+    ///
+    /// Old records are deleted only after the renamed wallet is verified to match the old one.
+    fn rename_wallet(&self, old_name: &str, passphrase: &SecUtf8, new_name: &str)
+        -> Result<SecKey>;
+
+    /// Unlocks a wallet for `ttl`, keeping its enckey in the client, so that `*_with_session`
+    /// methods only need the returned token
+    fn unlock_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        ttl: Duration,
+    ) -> Result<SessionToken>;
+
+    /// Locks given wallet session (wiping its enckey)
+    fn lock_wallet(&self, token: &SessionToken) -> Result<()>;
+
+    /// Returns name and enckey of wallet of given session (fails with `ErrorKind::InvalidSession`
+    /// if the session is expired or locked)
+    fn unlocked_wallet(&self, token: &SessionToken) -> Result<(String, SecKey)>;
+
+    /// Returns all staking addresses of wallet of given session
+    fn staking_addresses_with_session(
+        &self,
+        token: &SessionToken,
+    ) -> Result<IndexSet<StakedStateAddress>> {
+        let (name, enckey) = self.unlocked_wallet(token)?;
+        self.staking_addresses(&name, &enckey)
+    }
+
+    /// Returns all the multi-sig transfer addresses of wallet of given session
+    fn transfer_addresses_with_session(
+        &self,
+        token: &SessionToken,
+    ) -> Result<IndexSet<ExtendedAddr>> {
+        let (name, enckey) = self.unlocked_wallet(token)?;
+        self.transfer_addresses(&name, &enckey)
+    }
+
+    /// Generates a new redeem address in wallet of given session
+    fn new_staking_address_with_session(&self, token: &SessionToken) -> Result<StakedStateAddress> {
+        let (name, enckey) = self.unlocked_wallet(token)?;
+        self.new_staking_address(&name, &enckey)
+    }
+
+    /// Generates a new 1-of-1 transfer address in wallet of given session
+    fn new_transfer_address_with_session(&self, token: &SessionToken) -> Result<ExtendedAddr> {
+        let (name, enckey) = self.unlocked_wallet(token)?;
+        self.new_transfer_address(&name, &enckey)
+    }
+
+    /// Retrieves current balance of wallet of given session
+    fn balance_with_session(&self, token: &SessionToken) -> Result<WalletBalance> {
+        let (name, enckey) = self.unlocked_wallet(token)?;
+        self.balance(&name, &enckey)
+    }
+
     /// Retrieves view key corresponding to a given wallet
     fn view_key(&self, name: &str, enckey: &SecKey) -> Result<PublicKey>;
 
@@ -155,6 +302,31 @@ pub trait WalletClient: Send + Sync {
     /// Returns all the multi-sig transfer addresses in current wallet
     fn transfer_addresses(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<ExtendedAddr>>;
 
+    /// Sets label of a transfer or staking address (at most `MAX_ADDRESS_LABEL_LENGTH`
+    /// characters, an empty label removes it)
+    fn set_address_label(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: LabeledAddress,
+        label: &str,
+    ) -> Result<()>;
+
+    /// Returns label of a transfer or staking address
+    fn get_address_label(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &LabeledAddress,
+    ) -> Result<Option<String>>;
+
+    /// Returns all labelled addresses with their labels
+    fn list_labeled_addresses(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<Vec<(LabeledAddress, String)>>;
+
     /// Finds staking key corresponding to given redeem address
     fn find_staking_key(
         &self,
@@ -209,6 +381,88 @@ pub trait WalletClient: Send + Sync {
     /// Generates a new 1-of-1 transfer address
     fn new_transfer_address(&self, name: &str, enckey: &SecKey) -> Result<ExtendedAddr>;
 
+    /// Generates given number of new redeem addresses (written to storage in a single batch)
+    fn new_staking_addresses(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        count: usize,
+    ) -> Result<Vec<StakedStateAddress>>;
+
+    /// Generates given number of new 1-of-1 transfer addresses (written to storage in a single
+    /// batch)
+    fn new_transfer_addresses(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        count: usize,
+    ) -> Result<Vec<ExtendedAddr>>;
+
+    /// Generates a redeem address at given key path of HD wallet
+    fn new_staking_address_at(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        path: &HdPath,
+    ) -> Result<StakedStateAddress>;
+
+    /// Generates a 1-of-1 transfer address at given key path of HD wallet
+    fn new_transfer_address_at(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        path: &HdPath,
+    ) -> Result<ExtendedAddr>;
+
+    /// Returns staking address at given derivation index of HD wallet (adding it to the wallet if it
+    /// does not exist yet)
+    fn staking_address_at_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        index: u32,
+    ) -> Result<StakedStateAddress>;
+
+    /// Returns 1-of-1 transfer address at given derivation index of HD wallet (adding it to the
+    /// wallet if it does not exist yet)
+    fn transfer_address_at_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        index: u32,
+    ) -> Result<ExtendedAddr>;
+
+    /// Returns derivation index of given staking or transfer address of HD wallet (`None` if the
+    /// address is not in the wallet or is not derived at an index of the wallet's account)
+    fn address_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &LabeledAddress,
+    ) -> Result<Option<u32>>;
+
+    /// Imports an external private key (32-byte secret key) into wallet, adding a staking or 1-of-1
+    /// transfer address of it (importing an already imported key does nothing)
+    ///
+    /// Imported keys are flagged, so that they are not expected to be derived from HD key of wallet.
+    fn import_private_key(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        key_bytes: &[u8],
+        role: KeyRole,
+    ) -> Result<PublicKey>;
+
+    /// Adds used addresses of HD wallet beyond its current indices (transfer addresses appearing
+    /// in synced transaction history, staking addresses with existing staking accounts), scanning
+    /// until `gap_limit` consecutive addresses are unused
+    fn rescan_addresses(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        gap_limit: usize,
+    ) -> Result<DiscoveredAddresses>;
+
     /// Add watch only staking address
     fn new_watch_staking_address(
         &self,
@@ -258,6 +512,14 @@ pub trait WalletClient: Send + Sync {
     /// Retrieves current balance of wallet
     fn balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance>;
 
+    /// Retrieves balance of wallet broken down into available, pending and timelocked coins
+    /// (at time of latest block)
+    fn get_detailed_balance(&self, name: &str, enckey: &SecKey) -> Result<DetailedBalance>;
+
+    /// Retrieves balances and activity of each transfer and staking address of wallet (bonded and
+    /// unbonded amounts of staking addresses are not known to wallet client)
+    fn balance_by_address(&self, name: &str, enckey: &SecKey) -> Result<Vec<AddressBalance>>;
+
     /// Retrieves transaction history of wallet
     fn history(
         &self,
@@ -268,6 +530,54 @@ pub trait WalletClient: Send + Sync {
         reversed: bool,
     ) -> Result<Vec<TransactionChange>>;
 
+    /// Retrieves a page of transaction history of wallet passing given filter (`limit` is capped
+    /// at `MAX_HISTORY_PAGE_LIMIT` and an `offset` beyond the end returns an empty page)
+    fn history_page(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        filter: HistoryFilter,
+        offset: usize,
+        limit: usize,
+        order: SortOrder,
+    ) -> Result<HistoryPage>;
+
+    /// Writes transaction history of wallet passing given filter to `writer` in CSV format (date,
+    /// txid, type, direction, amount, fee, counterparty addresses and memo), returning number of
+    /// rows
+    fn export_history_csv(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        filter: HistoryFilter,
+        writer: &mut dyn Write,
+    ) -> Result<u64> {
+        let own_addresses = self.transfer_addresses(name, enckey)?;
+        history_csv::write_header(writer)?;
+
+        let mut offset = 0;
+        let mut rows = 0;
+        loop {
+            let page = self.history_page(
+                name,
+                enckey,
+                filter.clone(),
+                offset,
+                MAX_HISTORY_PAGE_LIMIT,
+                SortOrder::Ascending,
+            )?;
+            for change in page.records.iter() {
+                history_csv::write_record(writer, change, &own_addresses)?;
+            }
+            rows += page.records.len() as u64;
+            offset += page.records.len();
+
+            if !page.has_more || page.records.is_empty() {
+                return Ok(rows);
+            }
+        }
+    }
+
     /// Retrieves transaction change corresponding to given transaction ID
     fn get_transaction_change(
         &self,
@@ -276,9 +586,51 @@ pub trait WalletClient: Send + Sync {
         transaction_id: &TxId,
     ) -> Result<Option<TransactionChange>>;
 
-    /// Retrieves all unspent transactions of wallet
+    /// Retrieves unspent transactions of wallet which coin selection may spend (neither used by
+    /// pending transactions nor frozen)
     fn unspent_transactions(&self, name: &str, enckey: &SecKey) -> Result<UnspentTransactions>;
 
+    /// Freezes given unspent transaction of wallet, so that coin selection never spends it
+    fn freeze_utxo(&self, name: &str, enckey: &SecKey, pointer: TxoPointer) -> Result<()>;
+
+    /// Unfreezes given frozen unspent transaction of wallet
+    fn unfreeze_utxo(&self, name: &str, enckey: &SecKey, pointer: TxoPointer) -> Result<()>;
+
+    /// Lists frozen unspent transactions of wallet
+    fn list_frozen_utxos(&self, name: &str, enckey: &SecKey) -> Result<Vec<TxoPointer>>;
+
+    /// Lists unspent transactions of wallet (including the ones used by pending transactions)
+    /// which pass given filter, in order of the filter
+    fn list_unspent(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        filter: UtxoFilter,
+    ) -> Result<Vec<UtxoInfo>>;
+
+    /// Lists transactions broadcast by wallet with their status (pending transactions not
+    /// included in a block before their `valid_until_height` are expired and their inputs are
+    /// unlocked on sync)
+    fn list_pending_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<Vec<PendingTransactionInfo>>;
+
+    /// Forgets pending information of given transaction (unlocking its inputs if it is still
+    /// pending)
+    fn forget_pending_transaction(&self, name: &str, enckey: &SecKey, txid: &TxId) -> Result<()>;
+
+    /// Removes pending information of transactions of given wallet settled more than
+    /// `keep_blocks` blocks before last synced block (unless some of their inputs are still
+    /// unspent), returning number of removed records
+    fn prune_settled_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        keep_blocks: u64,
+    ) -> Result<usize>;
+
     /// Checks if all the provided transaction inputs are present in unspent transaction for given wallet
     fn has_unspent_transactions(
         &self,
@@ -307,7 +659,8 @@ pub trait WalletClient: Send + Sync {
     /// - `enckey`: Passphrase of wallet
     /// - `outputs`: Transaction outputs
     /// - `attributes`: Transaction attributes,
-    /// - `input_selection_strategy`: Strategy to use while selecting unspent transactions
+    /// - `coin_selection`: Strategy to use while selecting unspent transactions (`LargestFirst`
+    ///   if `None`)
     /// - `return_address`: Address to which change amount will get returned
     fn create_transaction(
         &self,
@@ -315,13 +668,69 @@ pub trait WalletClient: Send + Sync {
         enckey: &SecKey,
         outputs: Vec<TxOut>,
         attributes: TxAttributes,
-        input_selection_strategy: Option<InputSelectionStrategy>,
+        coin_selection: Option<&dyn CoinSelectionStrategy>,
         return_address: ExtendedAddr,
     ) -> Result<(TxAux, Vec<TxoPointer>, Coin)>;
 
+    /// Builds a transfer transaction paying given amounts to multiple recipients (with change
+    /// returned to a new transfer address of the wallet); duplicate recipients are merged into
+    /// one output if `merge_duplicates` is `true`, otherwise rejected
+    fn create_transfer_to_many(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        recipients: Vec<(ExtendedAddr, Coin)>,
+        attributes: TxAttributes,
+        merge_duplicates: bool,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Builds a transaction consolidating up to `max_inputs` smallest spendable unspent
+    /// transactions into one output sent to `destination` (or a new transfer address) of the wallet
+    fn create_consolidation_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        max_inputs: usize,
+        destination: Option<ExtendedAddr>,
+    ) -> Result<(TxAux, TransactionPending, ConsolidationSummary)>;
+
+    /// Returns largest amount all spendable unspent transactions of wallet (neither timelocked nor
+    /// spent by pending transactions) can send to `to_address` after fee (zero if there are none)
+    fn estimate_max_send(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<MaxSend>;
+
+    /// Builds a transaction sending the amount returned by `estimate_max_send` to `to_address`
+    fn create_sweep_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<(TxAux, TransactionPending, MaxSend)>;
+
     /// Broadcasts a transaction to Crypto.com Chain
     fn broadcast_transaction(&self, tx_aux: &TxAux) -> Result<BroadcastTxResponse>;
 
+    /// Returns broadcast log entry of given transaction, to check if it was broadcasted
+    fn query_broadcast_log(&self, txid: &TxId) -> Result<Option<BroadcastLogEntry>>;
+
+    /// Returns broadcasted transactions whose outcome is not recorded (e.g. due to a crash)
+    fn unresolved_broadcasts(&self) -> Result<Vec<BroadcastLogEntry>>;
+
+    /// Broadcasts pending transaction of wallet again (e.g. after it is dropped from mempool of
+    /// node), extending validity of its pending record if it is accepted
+    fn rebroadcast_pending(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        txid: &TxId,
+    ) -> Result<RebroadcastOutcome>;
+
     /// When receiver's view key not included in the transaction, the receiver can't collect the outputs.
     /// The sender have to get the plain transaction and send it to the receiver by email or something
     /// so that the receiver can sync it into the wallet DB and get the outputs.
@@ -459,4 +868,72 @@ pub trait MultiSigWalletClient: WalletClient {
         enckey: &SecKey,
         unsigned_transaction: Tx,
     ) -> Result<TxAux>;
+
+    /// Creates a new `threshold`-of-n multi-sig transfer address from public keys of all
+    /// cosigners (one of which is owned by current wallet)
+    fn new_multisig_address(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        cosigner_pubkeys: Vec<PublicKey>,
+        threshold: usize,
+    ) -> Result<ExtendedAddr>;
+
+    /// Starts signing of given unsigned transfer transaction (spending one multi-sig output) by
+    /// given signers (one of which is owned by current wallet). Returns session-id and nonce
+    /// commitment of current signer.
+    fn start_multisig_transfer(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_transaction: Tx,
+        signer_public_keys: Vec<PublicKey>,
+    ) -> Result<(H256, H256)>;
+
+    /// Adds nonce commitment of a cosigner. Returns nonce of current signer once nonce commitments
+    /// of all cosigners are received.
+    fn add_cosigner_commitment(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+        nonce_commitment: H256,
+    ) -> Result<Option<H256>>;
+
+    /// Adds nonce of a cosigner. Returns partial signature of current signer once nonces of all
+    /// cosigners are received.
+    fn add_cosigner_nonce(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+        nonce: H256,
+    ) -> Result<Option<H256>>;
+
+    /// Adds partial signature of a cosigner. Returns `true` once partial signatures of all
+    /// cosigners are received (i.e., the transfer can be finalized).
+    fn add_cosigner_signature(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+        partial_signature: H256,
+    ) -> Result<bool>;
+
+    /// Returns broadcastable transfer transaction signed in session with given id
+    fn finalize_multisig_transfer(
+        &self,
+        name: &str,
+        session_id: &H256,
+        enckey: &SecKey,
+    ) -> Result<TxAux>;
+
+    /// Exports transfer transaction signed in session with given id (along with contributions of
+    /// cosigners received so far) in a portable format for exchange with cosigners
+    fn export_psct(
+        &self,
+        name: &str,
+        session_id: &H256,
+        enckey: &SecKey,
+    ) -> Result<PartiallySignedTransfer>;
 }
@@ -0,0 +1,236 @@
+//! Discovery of used addresses of HD wallet (e.g., after restoring it from mnemonic)
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::ops::Range;
+
+use chain_core::init::address::RedeemAddress;
+use chain_core::state::account::StakedStateAddress;
+use chain_core::tx::data::address::ExtendedAddr;
+use client_common::tendermint::types::AbciQueryExt;
+use client_common::tendermint::Client;
+use client_common::{
+    Error, ErrorKind, MultiSigAddress, PublicKey, Result, ResultExt, SecKey, Storage,
+};
+
+use super::syncer_logic::handle_new_addresses;
+use crate::service::{
+    load_wallet_state, HDAccountType, HdKeyService, HdPath, RootHashService, WalletService,
+    WalletStateService,
+};
+use crate::types::WalletKind;
+
+/// Default number of consecutive unused addresses after which address discovery stops
+pub const DEFAULT_GAP_LIMIT: usize = 20;
+
+/// Addresses added to wallet by address discovery
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DiscoveredAddresses {
+    /// Transfer addresses (in order of their indices)
+    pub transfer_addresses: Vec<ExtendedAddr>,
+    /// Staking addresses (in order of their indices)
+    pub staking_addresses: Vec<StakedStateAddress>,
+}
+
+/// Scans key chains of HD wallet in batches of `gap_limit` keys until `gap_limit` consecutive
+/// keys are unused, adds all keys up to the last used one to the wallet and updates wallet state
+///
+/// - transfer address is used if it appears in synced transaction history
+/// - staking address is used if its staking account exists on chain
+pub(crate) fn discover_addresses<S: Storage, C: Client>(
+    storage: &S,
+    client: &C,
+    name: &str,
+    enckey: &SecKey,
+    gap_limit: usize,
+) -> Result<DiscoveredAddresses> {
+    let gap_limit = u32::try_from(gap_limit)
+        .ok()
+        .filter(|gap_limit| *gap_limit > 0)
+        .chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("Invalid gap limit: {}", gap_limit),
+            )
+        })?;
+
+    let wallet_service = WalletService::new(storage.clone());
+    let wallet = wallet_service.get_wallet(name, enckey)?;
+    if wallet.wallet_kind != WalletKind::HD {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Addresses can only be discovered in HD wallet",
+        ));
+    }
+
+    let hd_key_service = HdKeyService::new(storage.clone());
+    let account = hd_key_service.account(name, enckey)?;
+    let public_key_at = |account_type, index| -> Result<PublicKey> {
+        let path = HdPath::wallet_key(account, account_type, index)?;
+        Ok(hd_key_service.derive_keypair_at(name, enckey, &path)?.0)
+    };
+
+    let wallet_state_service = WalletStateService::new(storage.clone());
+    let used_transfer_addresses = wallet_state_service
+        .get_transaction_history(name, enckey, false)?
+        .flat_map(|change| {
+            let input_addresses = change
+                .inputs
+                .into_iter()
+                .filter_map(|input| input.output.map(|output| output.address))
+                .collect::<Vec<_>>();
+            change
+                .outputs
+                .into_iter()
+                .map(|output| output.address)
+                .chain(input_addresses)
+        })
+        .collect::<HashSet<_>>();
+
+    let last_used_transfer = find_last_used(gap_limit, |indices| {
+        indices
+            .map(|index| {
+                let address = transfer_address(public_key_at(HDAccountType::Transfer, index)?)?;
+                Ok(used_transfer_addresses.contains(&address))
+            })
+            .collect()
+    })?;
+
+    let last_used_staking = find_last_used(gap_limit, |indices| {
+        let addresses = indices
+            .map(|index| {
+                Ok(RedeemAddress::from(&public_key_at(
+                    HDAccountType::Staking,
+                    index,
+                )?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let responses =
+            client.query_batch(addresses.iter().map(|address| ("account", &address.0[..])))?;
+        if responses.len() != addresses.len() {
+            return Err(Error::new(
+                ErrorKind::TendermintRpcError,
+                "Number of account query responses does not match number of addresses",
+            ));
+        }
+        Ok(responses
+            .iter()
+            .map(|response| !response.bytes().is_empty())
+            .collect())
+    })?;
+
+    let root_hash_service = RootHashService::new(storage.clone());
+    let mut discovered = DiscoveredAddresses::default();
+
+    if let Some(last_used) = last_used_transfer {
+        let transfer_addresses = wallet.transfer_addresses();
+        for index in 0..=last_used {
+            let path = HdPath::wallet_key(account, HDAccountType::Transfer, index)?;
+            let (public_key, private_key) =
+                hd_key_service.derive_keypair_at(name, enckey, &path)?;
+            let address = transfer_address(public_key.clone())?;
+            if transfer_addresses.contains(&address) {
+                continue;
+            }
+
+            wallet_service.add_key_pairs(name, enckey, &public_key, &private_key)?;
+            wallet_service.add_public_key(name, enckey, &public_key)?;
+            let (root_hash, _) = root_hash_service.new_root_hash(
+                name,
+                vec![public_key.clone()],
+                public_key,
+                1,
+                enckey,
+            )?;
+            wallet_service.add_root_hash(name, enckey, root_hash)?;
+            discovered.transfer_addresses.push(address);
+        }
+        hd_key_service.advance_index(name, enckey, HDAccountType::Transfer, last_used + 1)?;
+    }
+
+    if let Some(last_used) = last_used_staking {
+        for index in 0..=last_used {
+            let path = HdPath::wallet_key(account, HDAccountType::Staking, index)?;
+            let (public_key, private_key) =
+                hd_key_service.derive_keypair_at(name, enckey, &path)?;
+            if wallet.staking_keys.contains(&public_key) {
+                continue;
+            }
+
+            wallet_service.add_key_pairs(name, enckey, &public_key, &private_key)?;
+            wallet_service.add_staking_key(name, enckey, &public_key)?;
+            discovered
+                .staking_addresses
+                .push(StakedStateAddress::BasicRedeem(RedeemAddress::from(
+                    &public_key,
+                )));
+        }
+        hd_key_service.advance_index(name, enckey, HDAccountType::Staking, last_used + 1)?;
+    }
+
+    if !discovered.transfer_addresses.is_empty() {
+        let wallet = wallet_service.get_wallet(name, enckey)?;
+        let wallet_state = load_wallet_state(storage, name, enckey)?.unwrap_or_default();
+        let addresses = discovered
+            .transfer_addresses
+            .iter()
+            .cloned()
+            .collect::<HashSet<_>>();
+        let memento = handle_new_addresses(&wallet, &wallet_state, &addresses)
+            .map_err(|err| Error::new(ErrorKind::InvalidInput, err.to_string()))?;
+        wallet_state_service.apply_memento(name, enckey, &memento)?;
+    }
+
+    Ok(discovered)
+}
+
+/// Returns transfer address of given public key (1-of-1 multi-sig address)
+fn transfer_address(public_key: PublicKey) -> Result<ExtendedAddr> {
+    Ok(MultiSigAddress::new(vec![public_key.clone()], public_key, 1)?.into())
+}
+
+/// Returns index of last used key, checking keys in batches of `gap_limit` (with `used` returning
+/// whether each key in given range is used) until `gap_limit` consecutive keys are unused
+fn find_last_used<F>(gap_limit: u32, mut used: F) -> Result<Option<u32>>
+where
+    F: FnMut(Range<u32>) -> Result<Vec<bool>>,
+{
+    let mut last_used = None;
+    let mut start = 0;
+
+    loop {
+        let end = start.checked_add(gap_limit).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Address index exceeds maximum allowed value",
+            )
+        })?;
+        for (index, used) in (start..end).zip(used(start..end)?) {
+            if used {
+                last_used = Some(index);
+            }
+        }
+
+        if end - last_used.map_or(0, |index| index + 1) >= gap_limit {
+            return Ok(last_used);
+        }
+        start = end;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_find_last_used() {
+        let used_indices = [3, 21, 40];
+        let check = |indices: Range<u32>| -> Result<Vec<bool>> {
+            Ok(indices.map(|index| used_indices.contains(&index)).collect())
+        };
+
+        assert_eq!(Some(40), find_last_used(20, check).unwrap());
+        // gap between 3 and 21 is longer than gap limit
+        assert_eq!(Some(3), find_last_used(10, check).unwrap());
+        assert_eq!(None, find_last_used(3, check).unwrap());
+    }
+}
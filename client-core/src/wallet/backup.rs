@@ -0,0 +1,298 @@
+//! Encrypted backups of wallets (keys, addresses and labels of a wallet in a single portable file)
+//!
+//! Backup format: `magic (8 bytes) | version (u16, little endian) | header | ciphertext` where
+//! header (SCALE-encoded key derivation parameters and salt) is authenticated along with the
+//! magic bytes and version as additional data of AEAD encryption of SCALE-encoded payload.
+use parity_scale_codec::{Decode, Encode};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use secstr::SecUtf8;
+use zeroize::Zeroize;
+
+use client_common::seckey::{derive_backup_key, KdfParams};
+use client_common::storage::{decrypt_bytes, encrypt_bytes};
+use client_common::{
+    Error, ErrorKind, MultiSigAddress, PrivateKey, PublicKey, Result, ResultExt, SecKey, Storage,
+};
+
+use crate::service::{
+    AddressLabelService, HdKey, HdKeyService, KeyService, RootHashService, WalletService,
+};
+use crate::types::{LabeledAddress, WalletKind};
+
+/// Magic bytes at the start of every wallet backup
+const BACKUP_MAGIC: &[u8; 8] = b"CROWLTBK";
+/// Version of wallet backup format
+pub const BACKUP_VERSION: u16 = 1;
+/// Length of random salt of backup key derivation
+const SALT_LENGTH: usize = 16;
+/// Maximum memory cost (in KiB) of backup key derivation accepted when opening a backup
+const MAX_MEM_COST: u32 = 1 << 20;
+/// Maximum number of passes of backup key derivation accepted when opening a backup
+const MAX_TIME_COST: u32 = 64;
+
+/// Header of wallet backup (authenticated, but not encrypted)
+#[derive(Debug, Encode, Decode)]
+struct BackupHeader {
+    kdf_params: KdfParams,
+    salt: [u8; SALT_LENGTH],
+}
+
+/// Encrypted contents of wallet backup (sync state and transaction history are not included)
+#[derive(Debug, Encode, Decode)]
+pub(crate) struct BackupPayload {
+    wallet_kind: WalletKind,
+    view_key: PrivateKey,
+    /// HD key and account of HD wallet
+    hd_key: Option<(HdKey, u32)>,
+    /// Key pairs of transfer and staking addresses
+    key_pairs: Vec<(PublicKey, PrivateKey)>,
+    public_keys: Vec<PublicKey>,
+    staking_keys: Vec<PublicKey>,
+    /// Public keys of imported private keys
+    imported_keys: Vec<PublicKey>,
+    /// Multi-sig addresses of transfer addresses (in order of wallet's root hashes)
+    multi_sig_addresses: Vec<MultiSigAddress>,
+    labels: Vec<(LabeledAddress, String)>,
+}
+
+/// Creates an encrypted backup of given wallet
+pub(crate) fn export_backup<S: Storage>(
+    storage: &S,
+    name: &str,
+    enckey: &SecKey,
+    backup_passphrase: &SecUtf8,
+) -> Result<Vec<u8>> {
+    let wallet_service = WalletService::new(storage.clone());
+    let wallet = wallet_service.get_wallet(name, enckey)?;
+    if wallet.wallet_kind == WalletKind::HW {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Hardware wallet can not be backed up (its keys never leave the device)",
+        ));
+    }
+
+    let view_key = KeyService::new(storage.clone())
+        .wallet_private_key(name, enckey)?
+        .chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                "Can not find private key in wallet",
+            )
+        })?;
+
+    let hd_key_service = HdKeyService::new(storage.clone());
+    let hd_key = if hd_key_service.has_wallet(name)? {
+        Some((
+            hd_key_service.get_hd_key(name, enckey)?,
+            hd_key_service.account(name, enckey)?,
+        ))
+    } else {
+        None
+    };
+
+    let root_hash_service = RootHashService::new(storage.clone());
+    let multi_sig_addresses = wallet
+        .root_hashes
+        .iter()
+        .map(|root_hash| {
+            root_hash_service.get_multi_sig_address_from_root_hash(name, root_hash, enckey)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let payload = BackupPayload {
+        wallet_kind: wallet.wallet_kind,
+        view_key,
+        hd_key,
+        key_pairs: wallet_service.key_pairs(name, enckey)?,
+        public_keys: wallet_service
+            .public_keys(name, enckey)?
+            .into_iter()
+            .collect(),
+        staking_keys: wallet.staking_keys.into_iter().collect(),
+        imported_keys: wallet_service
+            .imported_keys(name, enckey)?
+            .into_iter()
+            .collect(),
+        multi_sig_addresses,
+        labels: AddressLabelService::new(storage.clone())
+            .get_labels(name, enckey)?
+            .into_iter()
+            .collect(),
+    };
+
+    let mut salt = [0; SALT_LENGTH];
+    OsRng.fill_bytes(&mut salt);
+    let header = BackupHeader {
+        kdf_params: KdfParams::default(),
+        salt,
+    };
+    let backup_key = derive_backup_key(backup_passphrase, &header.salt, &header.kdf_params)
+        .err_kind(ErrorKind::InvalidInput, || {
+            "unable to derive encryption key from backup passphrase"
+        })?;
+
+    let mut backup = BACKUP_MAGIC.to_vec();
+    backup.extend_from_slice(&BACKUP_VERSION.to_le_bytes());
+    header.encode_to(&mut backup);
+
+    let mut encoded = payload.encode();
+    let cipher = encrypt_bytes(&backup, &backup_key, &encoded);
+    encoded.zeroize();
+    backup.extend(cipher?);
+
+    Ok(backup)
+}
+
+/// Decrypts given wallet backup
+pub(crate) fn open_backup(backup: &[u8], backup_passphrase: &SecUtf8) -> Result<BackupPayload> {
+    let prefix_length = BACKUP_MAGIC.len() + 2;
+    if backup.len() < prefix_length || backup[..BACKUP_MAGIC.len()] != BACKUP_MAGIC[..] {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Given bytes are not a wallet backup",
+        ));
+    }
+
+    let version = u16::from_le_bytes([backup[BACKUP_MAGIC.len()], backup[BACKUP_MAGIC.len() + 1]]);
+    if version != BACKUP_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Unsupported wallet backup version: {} (supported version: {})",
+                version, BACKUP_VERSION
+            ),
+        ));
+    }
+
+    let mut cipher = &backup[prefix_length..];
+    let header = BackupHeader::decode(&mut cipher).chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Unable to decode wallet backup header",
+        )
+    })?;
+    if header.kdf_params.mem_cost > MAX_MEM_COST || header.kdf_params.time_cost > MAX_TIME_COST {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Key derivation parameters of wallet backup are too expensive",
+        ));
+    }
+
+    let backup_key = derive_backup_key(backup_passphrase, &header.salt, &header.kdf_params)
+        .err_kind(ErrorKind::InvalidInput, || {
+            "unable to derive encryption key from backup passphrase"
+        })?;
+    let authenticated = &backup[..backup.len() - cipher.len()];
+    let mut encoded = decrypt_bytes(authenticated, &backup_key, cipher).map_err(|_| {
+        Error::new(
+            ErrorKind::DecryptionError,
+            "Unable to decrypt wallet backup (wrong backup passphrase or corrupted backup)",
+        )
+    })?;
+
+    let payload = BackupPayload::decode(&mut encoded.as_slice());
+    encoded.zeroize();
+    payload.chain(|| {
+        (
+            ErrorKind::DeserializationError,
+            "Unable to decode contents of wallet backup",
+        )
+    })
+}
+
+/// Creates a new wallet with given name from decrypted wallet backup
+pub(crate) fn restore_backup<S: Storage>(
+    storage: &S,
+    name: &str,
+    enckey: &SecKey,
+    payload: BackupPayload,
+) -> Result<()> {
+    let wallet_service = WalletService::new(storage.clone());
+    wallet_service.create(
+        name,
+        enckey,
+        PublicKey::from(&payload.view_key),
+        payload.wallet_kind,
+    )?;
+    KeyService::new(storage.clone()).add_wallet_private_key(name, &payload.view_key, enckey)?;
+
+    if let Some((ref hd_key, account)) = payload.hd_key {
+        HdKeyService::new(storage.clone()).add_hd_key(name, hd_key, account, enckey)?;
+    }
+
+    for (public_key, private_key) in payload.key_pairs.iter() {
+        wallet_service.add_key_pairs(name, enckey, public_key, private_key)?;
+    }
+    for public_key in payload.public_keys.iter() {
+        wallet_service.add_public_key(name, enckey, public_key)?;
+    }
+    for staking_key in payload.staking_keys.iter() {
+        wallet_service.add_staking_key(name, enckey, staking_key)?;
+    }
+    for imported_key in payload.imported_keys.iter() {
+        wallet_service.add_imported_key(name, enckey, imported_key)?;
+    }
+
+    let root_hash_service = RootHashService::new(storage.clone());
+    for multi_sig_address in payload.multi_sig_addresses.iter() {
+        let root_hash = root_hash_service.add_multi_sig_address(name, multi_sig_address, enckey)?;
+        wallet_service.add_root_hash(name, enckey, root_hash)?;
+    }
+
+    AddressLabelService::new(storage.clone()).set_labels(name, enckey, payload.labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use client_common::seckey::derive_enckey;
+    use client_common::storage::MemoryStorage;
+
+    fn backup() -> Vec<u8> {
+        let storage = MemoryStorage::default();
+        let enckey = derive_enckey(&SecUtf8::from("passphrase"), "name").unwrap();
+        let view_key = PrivateKey::new().unwrap();
+        WalletService::new(storage.clone())
+            .create(
+                "name",
+                &enckey,
+                PublicKey::from(&view_key),
+                WalletKind::Basic,
+            )
+            .unwrap();
+        KeyService::new(storage.clone())
+            .add_wallet_private_key("name", &view_key, &enckey)
+            .unwrap();
+
+        export_backup(&storage, "name", &enckey, &SecUtf8::from("backup")).unwrap()
+    }
+
+    #[test]
+    fn check_open_backup() {
+        let backup = backup();
+        let passphrase = SecUtf8::from("backup");
+        assert!(open_backup(&backup, &passphrase).is_ok());
+
+        let error = open_backup(&backup, &SecUtf8::from("wrong")).unwrap_err();
+        assert_eq!(ErrorKind::DecryptionError, error.kind());
+
+        // header is authenticated
+        let mut tampered = backup.clone();
+        tampered[BACKUP_MAGIC.len() + 2] ^= 1;
+        assert!(open_backup(&tampered, &passphrase).is_err());
+
+        let mut newer = backup.clone();
+        newer[BACKUP_MAGIC.len()..BACKUP_MAGIC.len() + 2]
+            .copy_from_slice(&(BACKUP_VERSION + 1).to_le_bytes());
+        let error = open_backup(&newer, &passphrase).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error
+            .to_string()
+            .contains("Unsupported wallet backup version"));
+
+        assert!(open_backup(&backup[..4], &passphrase).is_err());
+        assert!(open_backup(b"not a wallet backup", &passphrase).is_err());
+    }
+}
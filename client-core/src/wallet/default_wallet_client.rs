@@ -6,24 +6,31 @@ use secstr::SecUtf8;
 use zxcvbn::{feedback::Feedback, zxcvbn as estimate_password_strength};
 
 use crate::hd_wallet::HardwareKind;
+use crate::multi_sig::{PartiallySignedInput, PartiallySignedTransfer};
 use crate::service::*;
 use crate::transaction_builder::UnauthorizedWalletTransactionBuilder;
 use crate::transaction_builder::{SignedTransferTransaction, UnsignedTransferTransaction};
 use crate::types::{
-    AddressType, BalanceChange, TransactionChange, TransactionPending, WalletBalance, WalletKind,
+    verify_wallet_network, AddressBalance, AddressType, BalanceChange, ChangeAddress, ChangePolicy,
+    ConsolidationSummary, DetailedBalance, HistoryFilter, HistoryPage, KeyRole, LabeledAddress,
+    MaxSend, NetworkInfo, PendingTransactionInfo, SortOrder, TransactionChange, TransactionPending,
+    UtxoFilter, UtxoInfo, WalletBalance, WalletKind, WalletMetadata,
+    DEFAULT_PENDING_TX_VALID_BLOCKS, MAX_HISTORY_PAGE_LIMIT,
 };
+use crate::wallet::address_discovery::{discover_addresses, DiscoveredAddresses};
+use crate::wallet::backup::{export_backup, open_backup, restore_backup};
 use crate::wallet::syncer_logic::create_transaction_change;
 use crate::{
-    InputSelectionStrategy, Mnemonic, MultiSigWalletClient, UnspentTransactions, WalletClient,
-    WalletTransactionBuilder,
+    CoinSelectionStrategy, LargestFirst, Mnemonic, MultiSigWalletClient, SweepAll,
+    UnspentTransactions, WalletClient, WalletTransactionBuilder,
 };
-use chain_core::common::{Proof, H256};
+use chain_core::common::{Proof, Timespec, H256};
 use chain_core::init::address::RedeemAddress;
-use chain_core::init::coin::Coin;
+use chain_core::init::coin::{sum_coins, Coin};
 use chain_core::state::account::StakedStateAddress;
 use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
-use chain_core::tx::data::attribute::TxAttributes;
+use chain_core::tx::data::attribute::{TxAttributes, MAX_TX_MEMO_LENGTH, MEMO_APP_VERSION};
 use chain_core::tx::data::input::{str2txid, TxoPointer};
 use chain_core::tx::data::output::TxOut;
 use chain_core::tx::data::{Tx, TxId};
@@ -31,16 +38,23 @@ use chain_core::tx::fee::Fee;
 use chain_core::tx::witness::tree::RawXOnlyPubkey;
 use chain_core::tx::witness::{TxInWitness, TxWitness};
 use chain_core::tx::{TransactionId, TxAux, TxEnclaveAux, TxObfuscated};
+use client_common::storage::BatchStorage;
 use client_common::tendermint::types::Time;
-use client_common::tendermint::types::{AbciQueryExt, BlockResults, BroadcastTxResponse};
+use client_common::tendermint::types::{
+    broadcast_error, check_broadcast_response, AbciQueryExt, BlockResults, BroadcastError,
+    BroadcastTxResponse, GenesisExt,
+};
 use client_common::tendermint::{Client, UnauthorizedClient};
 use client_common::{
-    seckey::derive_enckey, Error, ErrorKind, PrivateKey, PrivateKeyAction, PublicKey, Result,
-    ResultExt, SecKey, SignedTransaction, Storage, Transaction, TransactionInfo,
+    seckey::derive_enckey, Error, ErrorKind, MultiSigAddress, PrivateKey, PrivateKeyAction,
+    PublicKey, Result, ResultExt, SecKey, SignedTransaction, Storage, Transaction, TransactionInfo,
 };
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use std::time::Duration;
 
+/// Maximum number of fee recalculations while estimating maximum sendable amount
+const MAX_FEE_ITERATIONS: usize = 5;
+
 /// Default implementation of `WalletClient` based on `Storage` and `Index`
 #[derive(Debug, Default, Clone)]
 pub struct DefaultWalletClient<S, C, T>
@@ -54,13 +68,23 @@ where
     hw_key_service: HwKeyService,
     wallet_service: WalletService<S>,
     wallet_state_service: WalletStateService<S>,
-    sync_state_service: SyncStateService<S>,
     root_hash_service: RootHashService<S>,
     multi_sig_session_service: MultiSigSessionService<S>,
-
+    broadcast_log_service: BroadcastLogService<S>,
+    address_label_service: AddressLabelService<S>,
+    passphrase_service: PassphraseService<S>,
+    rename_service: RenameService<S>,
+    deletion_service: DeletionService<S>,
+    wallet_session_service: WalletSessionService,
+
+    storage: S,
     tendermint_client: C,
     transaction_builder: T,
     block_height_ensure: Option<u64>,
+    network: Option<NetworkInfo>,
+    network_mismatch_allowed: bool,
+    change_policy: ChangePolicy,
+    include_timelocked: bool,
 }
 
 impl<S, C, T> DefaultWalletClient<S, C, T>
@@ -83,181 +107,325 @@ where
             hw_key_service,
             wallet_service: WalletService::new(storage.clone()),
             wallet_state_service: WalletStateService::new(storage.clone()),
-            sync_state_service: SyncStateService::new(storage.clone()),
             root_hash_service: RootHashService::new(storage.clone()),
-            multi_sig_session_service: MultiSigSessionService::new(storage),
+            multi_sig_session_service: MultiSigSessionService::new(storage.clone()),
+            broadcast_log_service: BroadcastLogService::new(storage.clone()),
+            address_label_service: AddressLabelService::new(storage.clone()),
+            passphrase_service: PassphraseService::new(storage.clone()),
+            rename_service: RenameService::new(storage.clone()),
+            deletion_service: DeletionService::new(storage.clone()),
+            wallet_session_service: WalletSessionService::default(),
+            storage,
             tendermint_client,
             transaction_builder,
             block_height_ensure,
+            network: None,
+            network_mismatch_allowed: false,
+            change_policy: ChangePolicy::default(),
+            include_timelocked: false,
         }
     }
-}
 
-impl<S> DefaultWalletClient<S, UnauthorizedClient, UnauthorizedWalletTransactionBuilder>
-where
-    S: Storage,
-{
-    /// Creates a new read-only instance of `DefaultWalletClient`
-    pub fn new_read_only(storage: S) -> Self {
-        let hw_key_service = HwKeyService::default();
-        Self::new(
+    /// Creates a new instance of `DefaultWalletClient` after migrating storage to current schema
+    /// version (fails if storage was written by a newer client)
+    pub fn open(
+        storage: S,
+        tendermint_client: C,
+        transaction_builder: T,
+        block_height_ensure: Option<u64>,
+        hw_key_service: HwKeyService,
+    ) -> Result<Self> {
+        migrate_storage(&storage)?;
+        Ok(Self::new(
             storage,
-            UnauthorizedClient,
-            UnauthorizedWalletTransactionBuilder,
-            None,
+            tendermint_client,
+            transaction_builder,
+            block_height_ensure,
             hw_key_service,
-        )
+        ))
     }
-}
 
-impl<S, C, T> WalletClient for DefaultWalletClient<S, C, T>
-where
-    S: Storage,
-    C: Client,
-    T: WalletTransactionBuilder,
-{
-    fn get_transaction(&self, name: &str, enckey: &SecKey, txid: TxId) -> Result<Transaction> {
-        let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        let private_key = self
-            .wallet_private_key(name, enckey, wallet.wallet_kind)?
-            .chain(|| (ErrorKind::StorageError, "can not find private key"))?;
-        let tx = self.transaction_builder.decrypt_tx(txid, &private_key)?;
-        Ok(tx)
+    /// Records given network in wallets created by this client, so that they are never used on
+    /// another network
+    pub fn with_network(mut self, network: NetworkInfo) -> Self {
+        self.network = Some(network);
+        self
     }
 
-    fn update_hw_service(&mut self, hw_wallet_kind: HardwareKind) {
-        let hw_key_service = match hw_wallet_kind {
-            HardwareKind::Ledger => HwKeyService::Unauthorized(UnauthorizedHwKeyService),
-            HardwareKind::Trezor => HwKeyService::Unauthorized(UnauthorizedHwKeyService),
-            #[cfg(feature = "mock-hardware-wallet")]
-            HardwareKind::Mock => {
-                let mock = MockHardwareService::new();
-                HwKeyService::Mock(mock)
-            }
-        };
-        self.hw_key_service = hw_key_service;
+    /// Allows building transactions of wallets on networks other than the recorded one
+    pub fn with_network_mismatch_allowed(mut self, network_mismatch_allowed: bool) -> Self {
+        self.network_mismatch_allowed = network_mismatch_allowed;
+        self
     }
 
-    fn get_wallet_kind(&self, name: &str, enckey: &SecKey) -> Result<WalletKind> {
-        let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        Ok(wallet.wallet_kind)
+    /// Sets policy for choosing the address receiving change of transfers (a new transfer address
+    /// of wallet for every transaction by default)
+    pub fn with_change_policy(mut self, change_policy: ChangePolicy) -> Self {
+        self.change_policy = change_policy;
+        self
     }
 
-    fn send_to_address(
-        &self,
-        name: &str,
-        enckey: &SecKey,
-        amount: Coin,
-        address: ExtendedAddr,
-        view_keys: &mut BTreeSet<PublicKey>,
-        network_id: u8,
-    ) -> Result<TxId> {
-        let current_block_height = self.get_current_block_height()?;
-        let tx_out = TxOut::new(address, amount);
+    /// Allows spending unspent transactions which are still timelocked at latest block time (e.g.
+    /// to build a transaction which is broadcast after their timelock)
+    pub fn with_include_timelocked(mut self, include_timelocked: bool) -> Self {
+        self.include_timelocked = include_timelocked;
+        self
+    }
 
-        let view_key = self.view_key(name, enckey)?;
+    /// Returns time at which unspent transactions have to be spendable to be spent in a new
+    /// transaction (`None` if timelocked ones are included)
+    fn spendable_at(&self) -> Result<Option<Timespec>> {
+        if self.include_timelocked {
+            return Ok(None);
+        }
 
-        view_keys.insert(view_key);
+        let status = self.tendermint_client.status()?;
+        to_timespec(status.sync_info.latest_block_time).map(Some)
+    }
 
-        let access_policies: BTreeSet<_> = view_keys
-            .iter()
-            .map(|key| TxAccessPolicy {
-                view_key: key.into(),
-                access: TxAccess::AllData,
-            })
-            .collect();
+    /// Returns the address receiving change of a new transfer of given wallet according to change
+    /// policy
+    fn change_address(&self, name: &str, enckey: &SecKey) -> Result<ChangeAddress> {
+        match self.change_policy {
+            ChangePolicy::NewInternalAddress => Ok(self.new_transfer_address(name, enckey)?.into()),
+            ChangePolicy::FixedAddress(ref address) => {
+                if !self.transfer_addresses(name, enckey)?.contains(address) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Change address {} is not a transfer address of wallet",
+                            address
+                        ),
+                    ));
+                }
+                Ok(address.clone().into())
+            }
+            ChangePolicy::ReuseFirstInput => Ok(ChangeAddress::FirstInput),
+        }
+    }
 
-        let attributes =
-            TxAttributes::new_with_access(network_id, access_policies.into_iter().collect());
+    /// Builds a transfer transaction returning change to given change address
+    fn build_transfer(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        coin_selection: Option<&dyn CoinSelectionStrategy>,
+        change_address: ChangeAddress,
+    ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
+        self.get_wallet_kind(name, enckey)?.verify_can_sign()?;
+        self.verify_network(name, attributes.chain_hex_id)?;
+        verify_memo(&attributes)?;
 
-        let return_address = self.new_transfer_address(name, enckey)?;
-        let (transaction, selected_inputs, return_amount) =
-            self.create_transaction(name, enckey, vec![tx_out], attributes, None, return_address)?;
+        let spendable_at = self.spendable_at()?;
+        self.build_transfer_at(
+            name,
+            enckey,
+            outputs,
+            attributes,
+            coin_selection,
+            change_address,
+            spendable_at,
+        )
+    }
 
-        self.broadcast_transaction(&transaction)?;
-        //update the wallet state
-        let tx_pending = TransactionPending {
-            used_inputs: selected_inputs,
-            block_height: current_block_height,
-            return_amount,
+    /// Builds a transfer transaction spending unspent transactions which are not timelocked at
+    /// given time (any of them if `None`)
+    #[allow(clippy::too_many_arguments)]
+    fn build_transfer_at(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        coin_selection: Option<&dyn CoinSelectionStrategy>,
+        change_address: ChangeAddress,
+        spendable_at: Option<Timespec>,
+    ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
+        let mut unspent_transactions = self.unspent_transactions(name, enckey)?;
+        let timelocked = match spendable_at {
+            Some(time) => unspent_transactions.remove_timelocked(time)?,
+            None => None,
         };
 
-        self.update_tx_pending_state(name, enckey, transaction.tx_id(), tx_pending)?;
+        let result = self.transaction_builder.build_transfer_tx(
+            name,
+            enckey,
+            unspent_transactions,
+            outputs,
+            change_address,
+            attributes,
+            coin_selection.unwrap_or(&LargestFirst),
+        );
 
-        if let TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
-            payload: TxObfuscated { txid, .. },
-            ..
-        }) = transaction
-        {
-            Ok(txid)
-        } else {
-            Err(Error::new(
-                ErrorKind::IllegalInput,
-                "Transaction is not transfer transaction",
-            ))
+        match (result, timelocked) {
+            (Err(error), Some((amount, earliest_unlock)))
+                if error.kind() == ErrorKind::InsufficientBalance =>
+            {
+                Err(Error::new(
+                    ErrorKind::InsufficientBalance,
+                    format!(
+                        "{} ({} is timelocked, earliest until unix time {})",
+                        error.message(),
+                        amount,
+                        earliest_unlock
+                    ),
+                ))
+            }
+            (result, _) => result,
         }
     }
 
-    /// broadcast transaction and waiting it confiremed
-    fn send_to_address_commit(
+    /// Returns unspent transactions of wallet which are not spent by pending transactions (and not
+    /// timelocked at latest block time, unless timelocked ones are included)
+    fn spendable_unspent_transactions(
         &self,
         name: &str,
         enckey: &SecKey,
-        amount: Coin,
-        address: ExtendedAddr,
-        view_keys: &mut BTreeSet<PublicKey>,
-        network_id: u8,
-    ) -> Result<TxId> {
-        let tx_id = self.send_to_address(name, enckey, amount, address, view_keys, network_id)?;
-        let block_height = self.get_current_block_height()?;
-        loop {
-            // query tx_id from tendermint
-            let confirmed = self
-                .tendermint_client
-                .query("meta", &tx_id.to_vec())
-                .is_ok();
-            if !confirmed {
-                std::thread::sleep(Duration::from_secs(1));
-                let current_block_height = self.get_current_block_height()?;
-                if current_block_height - block_height >= self.block_height_ensure.unwrap_or(50) {
-                    return Err(Error::new(
-                        ErrorKind::TendermintRpcError,
-                        "waiting for transaction confirmed timeout",
-                    ));
-                }
-                continue;
-            }
-            break;
+    ) -> Result<UnspentTransactions> {
+        let mut unspent_transactions = self.unspent_transactions(name, enckey)?;
+        if let Some(time) = self.spendable_at()? {
+            unspent_transactions.remove_timelocked(time)?;
         }
-        Ok(tx_id)
+        Ok(unspent_transactions)
     }
 
-    #[inline]
-    fn wallets(&self) -> Result<Vec<String>> {
-        self.wallet_service.names()
+    /// Adds view key of wallet to given attributes (so that wallet can see its transaction)
+    fn with_wallet_view_key(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        mut attributes: TxAttributes,
+    ) -> Result<TxAttributes> {
+        let view_key = TxAccessPolicy {
+            view_key: (&self.view_key(name, enckey)?).into(),
+            access: TxAccess::AllData,
+        };
+        if !attributes.allowed_view.contains(&view_key) {
+            attributes.allowed_view.push(view_key);
+        }
+        Ok(attributes)
     }
 
-    fn export_wallet(&self, name: &str, enckey: &SecKey) -> Result<WalletInfo> {
-        let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        let private_key = self
-            .key_service
-            .wallet_private_key(name, enckey)?
-            .chain(|| {
+    /// Returns largest amount given unspent transactions can send to given address in one output
+    fn max_send(
+        &self,
+        unspent_transactions: &UnspentTransactions,
+        to_address: &ExtendedAddr,
+        attributes: &TxAttributes,
+    ) -> Result<MaxSend> {
+        if unspent_transactions.is_empty() {
+            return Ok(MaxSend::default());
+        }
+
+        let no_of_inputs = unspent_transactions.len();
+        let total =
+            sum_coins(unspent_transactions.iter().map(|(_, output)| output.value)).chain(|| {
                 (
-                    ErrorKind::InvalidInput,
-                    "Can not find private key in wallet",
+                    ErrorKind::IllegalInput,
+                    "Total amount of spendable UTXOs exceeds maximum allowed value",
                 )
             })?;
-        let wallet_info = WalletInfo {
-            name: name.into(),
-            wallet,
-            private_key,
-            passphrase: None,
-        };
-        Ok(wallet_info)
+        let output = |amount: Coin| [TxOut::new(to_address.clone(), amount)];
+
+        // fee depends on the size of transaction, so it is recalculated until it is stable
+        let mut fee = self.transaction_builder.estimate_fee(
+            no_of_inputs,
+            &output(Coin::zero()),
+            attributes,
+        )?;
+        for _ in 0..MAX_FEE_ITERATIONS {
+            let amount = (total - fee)
+                .ok()
+                .filter(|amount| *amount > Coin::zero())
+                .chain(|| {
+                    (
+                        ErrorKind::InsufficientBalance,
+                        format!(
+                            "Spendable balance {} does not cover fee {} of sending it",
+                            total, fee
+                        ),
+                    )
+                })?;
+            let new_fee =
+                self.transaction_builder
+                    .estimate_fee(no_of_inputs, &output(amount), attributes)?;
+
+            if new_fee == fee {
+                return Ok(MaxSend {
+                    amount,
+                    fee,
+                    inputs_used: no_of_inputs,
+                });
+            }
+            fee = new_fee;
+        }
+
+        Err(Error::new(
+            ErrorKind::IllegalInput,
+            format!(
+                "Fee of sending all spendable UTXOs did not converge after {} iterations",
+                MAX_FEE_ITERATIONS
+            ),
+        ))
     }
 
-    fn import_wallet(
+    /// Builds a transaction sending all given unspent transactions to given address (after fee)
+    fn build_sweep(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unspent_transactions: UnspentTransactions,
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<(TxAux, Vec<TxoPointer>, MaxSend)> {
+        let max_send = self.max_send(&unspent_transactions, &to_address, &attributes)?;
+        if max_send.inputs_used == 0 {
+            return Err(Error::new(
+                ErrorKind::InsufficientBalance,
+                "Wallet has no spendable unspent transactions",
+            ));
+        }
+
+        // everything above fee is returned in the only (change) output
+        let (transaction, used_inputs, amount) = self.transaction_builder.build_transfer_tx(
+            name,
+            enckey,
+            unspent_transactions,
+            Vec::new(),
+            to_address.into(),
+            attributes,
+            &SweepAll,
+        )?;
+        if amount != max_send.amount {
+            return Err(Error::new(
+                ErrorKind::InternalError,
+                format!(
+                    "Sent amount {} differs from estimated maximum amount {}",
+                    amount, max_send.amount
+                ),
+            ));
+        }
+
+        Ok((transaction, used_inputs, max_send))
+    }
+
+    /// Creates records of a new wallet (along with network of this client)
+    fn create_wallet(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        view_key: PublicKey,
+        wallet_kind: WalletKind,
+    ) -> Result<()> {
+        self.wallet_service
+            .create(name, enckey, view_key, wallet_kind)?;
+        self.record_network(name)
+    }
+
+    /// Creates a wallet from exported wallet information
+    fn create_imported_wallet(
         &self,
         name: &str,
         passphrase: &SecUtf8,
@@ -279,17 +447,35 @@ where
 
         self.wallet_service
             .set_wallet(name, &enckey, wallet_info.wallet)?;
+        self.address_label_service
+            .set_labels(name, &enckey, wallet_info.labels)?;
         Ok(enckey)
     }
 
-    fn new_wallet(
+    /// Creates a new wallet of given kind
+    fn create_new_wallet(
         &self,
         name: &str,
         passphrase: &SecUtf8,
         wallet_kind: WalletKind,
+        hd_account: Option<u32>,
+        mnemonic_passphrase: Option<&SecUtf8>,
     ) -> Result<(SecKey, Option<Mnemonic>)> {
         check_passphrase_strength(name, passphrase)?;
 
+        if hd_account.is_some() && wallet_kind != WalletKind::HD {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Account can only be selected for HD wallet",
+            ));
+        }
+        if mnemonic_passphrase.is_some() && wallet_kind != WalletKind::HD {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Mnemonic passphrase can only be used for HD wallet",
+            ));
+        }
+
         let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
             "unable to derive encryption key from passphrase"
         })?;
@@ -302,15 +488,20 @@ where
                 self.key_service
                     .add_wallet_private_key(name, &private_key, &enckey)?;
 
-                self.wallet_service
-                    .create(name, &enckey, view_key, wallet_kind)?;
+                self.create_wallet(name, &enckey, view_key, wallet_kind)?;
 
                 Ok((enckey, None))
             }
             WalletKind::HD => {
                 let mnemonic = Mnemonic::new();
 
-                self.hd_key_service.add_mnemonic(name, &mnemonic, &enckey)?;
+                self.hd_key_service.add_mnemonic(
+                    name,
+                    &mnemonic,
+                    mnemonic_passphrase,
+                    hd_account.unwrap_or_default(),
+                    &enckey,
+                )?;
 
                 let (public_key, private_key) =
                     self.hd_key_service
@@ -319,8 +510,7 @@ where
                 self.key_service
                     .add_wallet_private_key(name, &private_key, &enckey)?;
 
-                self.wallet_service
-                    .create(name, &enckey, public_key, wallet_kind)?;
+                self.create_wallet(name, &enckey, public_key, wallet_kind)?;
 
                 Ok((enckey, Some(mnemonic)))
             }
@@ -332,19 +522,24 @@ where
                 self.key_service
                     .add_wallet_private_key(name, &private_key, &enckey)?;
 
-                self.wallet_service
-                    .create(name, &enckey, view_key, wallet_kind)?;
+                self.create_wallet(name, &enckey, view_key, wallet_kind)?;
 
                 Ok((enckey, None))
             }
+            WalletKind::WatchOnly => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Watch-only wallet can only be created from view key and public keys of another wallet",
+            )),
         }
     }
 
-    fn restore_wallet(
+    /// Creates a HD wallet from given mnemonic
+    fn create_restored_wallet(
         &self,
         name: &str,
         passphrase: &SecUtf8,
         mnemonic: &Mnemonic,
+        mnemonic_passphrase: Option<&SecUtf8>,
     ) -> Result<SecKey> {
         check_passphrase_strength(name, passphrase)?;
 
@@ -352,7 +547,8 @@ where
             "unable to derive encryption key from passphrase"
         })?;
 
-        self.hd_key_service.add_mnemonic(name, mnemonic, &enckey)?;
+        self.hd_key_service
+            .add_mnemonic(name, mnemonic, mnemonic_passphrase, 0, &enckey)?;
 
         let (public_key, private_key) =
             self.hd_key_service
@@ -361,12 +557,12 @@ where
         self.key_service
             .add_wallet_private_key(name, &private_key, &enckey)?;
 
-        self.wallet_service
-            .create(name, &enckey, public_key, WalletKind::HD)?;
+        self.create_wallet(name, &enckey, public_key, WalletKind::HD)?;
         Ok(enckey)
     }
 
-    fn restore_basic_wallet(
+    /// Creates a basic wallet from given view key
+    fn create_restored_basic_wallet(
         &self,
         name: &str,
         passphrase: &SecUtf8,
@@ -381,963 +577,4789 @@ where
         let view_key = PublicKey::from(view_key_priv);
         self.key_service
             .add_wallet_private_key(name, &view_key_priv, &enckey)?;
-        self.wallet_service
-            .create(name, &enckey, view_key, WalletKind::Basic)?;
+        self.create_wallet(name, &enckey, view_key, WalletKind::Basic)?;
         Ok(enckey)
     }
 
-    fn delete_wallet(&self, name: &str, passphrase: &SecUtf8) -> Result<()> {
-        // remove from wallet/sync_state/wallet_state/key_service
+    /// Creates a watch-only wallet from given view key and public keys
+    fn create_watch_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        view_key: &PrivateKey,
+        public_keys: &[PublicKey],
+        staking_keys: &[PublicKey],
+    ) -> Result<SecKey> {
+        check_passphrase_strength(name, passphrase)?;
 
         let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
             "unable to derive encryption key from passphrase"
         })?;
 
-        // the passphrase is verified here.
-        self.wallet_service.delete(name, &enckey)?;
-        self.sync_state_service.delete_global_state(name)?;
-        self.wallet_state_service
-            .delete_wallet_state(name, &enckey)?;
-        if self.hd_key_service.has_wallet(name)? {
-            self.hd_key_service.delete_wallet(name, &enckey)?;
-        }
-        self.key_service.delete_wallet_private_key(name, &enckey)?;
-
-        Ok(())
-    }
+        self.create_wallet(
+            name,
+            &enckey,
+            PublicKey::from(view_key),
+            WalletKind::WatchOnly,
+        )?;
+        self.key_service
+            .add_wallet_private_key(name, view_key, &enckey)?;
 
-    fn auth_token(&self, name: &str, passphrase: &SecUtf8) -> Result<SecKey> {
-        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
-            "unable to derive encryption key from passphrase"
-        })?;
+        for public_key in public_keys {
+            self.wallet_service
+                .add_public_key(name, &enckey, public_key)?;
+            self.new_watch_transfer_address(name, &enckey, public_key)?;
+        }
+        for staking_key in staking_keys {
+            self.new_watch_staking_address(name, &enckey, staking_key)?;
+        }
 
-        // test validity of enckey
-        self.view_key(name, &enckey)?;
         Ok(enckey)
     }
 
-    #[inline]
-    fn view_key(&self, name: &str, enckey: &SecKey) -> Result<PublicKey> {
-        self.wallet_service.view_key(name, enckey)
-    }
-
-    #[inline]
-    fn view_key_private(&self, name: &str, enckey: &SecKey) -> Result<PrivateKey> {
-        self.key_service
-            .wallet_private_key(name, enckey)?
-            .err_kind(ErrorKind::InvalidInput, || "private view key not found")
+    /// Creates a wallet with `create` in a single batch of storage writes, so that either all of
+    /// its records are written or none
+    fn create_in_batch<A, F>(&self, create: F) -> Result<A>
+    where
+        F: FnOnce(
+            &DefaultWalletClient<
+                BatchStorage<S>,
+                UnauthorizedClient,
+                UnauthorizedWalletTransactionBuilder,
+            >,
+        ) -> Result<A>,
+    {
+        let batch = BatchStorage::new(self.storage.clone());
+        let mut client = DefaultWalletClient::new_read_only(batch.clone());
+        client.network = self.network.clone();
+        let result = create(&client)?;
+        batch.commit()?;
+        Ok(result)
     }
 
-    #[inline]
-    fn public_keys(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<PublicKey>> {
-        self.wallet_service.public_keys(name, enckey)
+    /// Records network of this client (if any) in given wallet
+    fn record_network(&self, name: &str) -> Result<()> {
+        match self.network {
+            Some(ref network) => self.wallet_service.set_network(name, network),
+            None => Ok(()),
+        }
     }
 
-    #[inline]
-    fn staking_keys(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<PublicKey>> {
-        self.wallet_service.staking_keys(name, enckey)
-    }
+    /// Checks that transactions of given wallet with given chain hex id are built for the network
+    /// wallet was created for and that connected node is on that network
+    fn verify_network(&self, name: &str, chain_hex_id: u8) -> Result<()> {
+        if self.network_mismatch_allowed {
+            return Ok(());
+        }
 
-    #[inline]
-    fn root_hashes(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<H256>> {
-        self.wallet_service.root_hashes(name, enckey)
+        let recorded = self.wallet_service.network(name)?;
+        if let Some(ref recorded) = recorded {
+            if recorded.chain_hex_id != chain_hex_id {
+                return Err(Error::new(
+                    ErrorKind::ValidationError,
+                    format!(
+                        "Chain hex id of transaction attributes ({:02x}) does not match network of wallet ({})",
+                        chain_hex_id, recorded
+                    ),
+                ));
+            }
+            let connected = NetworkInfo::from_genesis(&self.tendermint_client.genesis()?)?;
+            verify_wallet_network(name, Some(recorded), &connected)?;
+        } else {
+            log::warn!(
+                "Network of wallet ({}) is not recorded, transaction is built for chain hex id {:02x}",
+                name,
+                chain_hex_id
+            );
+        }
+        Ok(())
     }
 
-    #[inline]
-    fn staking_addresses(
+    /// Returns the public key among given ones which is owned by the wallet
+    fn owned_public_key(
         &self,
         name: &str,
         enckey: &SecKey,
-    ) -> Result<IndexSet<StakedStateAddress>> {
-        self.wallet_service.staking_addresses(name, enckey)
+        public_keys: &[PublicKey],
+    ) -> Result<PublicKey> {
+        let owned = self.wallet_service.public_keys(name, enckey)?;
+        public_keys
+            .iter()
+            .find(|public_key| owned.contains(*public_key))
+            .cloned()
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "None of given public keys is owned by current wallet",
+                )
+            })
     }
 
-    #[inline]
-    fn transfer_addresses(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<ExtendedAddr>> {
-        self.wallet_service.transfer_addresses(name, enckey)
-    }
+    /// Derives key pair of HD wallet at given key path and adds it to the wallet
+    fn add_hd_key_pair_at(&self, name: &str, enckey: &SecKey, path: &HdPath) -> Result<PublicKey> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        if wallet.wallet_kind != WalletKind::HD {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Addresses at explicit key paths can only be derived in HD wallet",
+            ));
+        }
 
-    #[inline]
-    fn find_staking_key(
-        &self,
-        name: &str,
-        enckey: &SecKey,
-        redeem_address: &RedeemAddress,
-    ) -> Result<Option<PublicKey>> {
+        let (public_key, private_key) =
+            self.hd_key_service.derive_keypair_at(name, enckey, path)?;
         self.wallet_service
-            .find_staking_key(name, enckey, redeem_address)
-    }
-
-    #[inline]
-    fn find_root_hash(
-        &self,
-        name: &str,
-        enckey: &SecKey,
-        address: &ExtendedAddr,
-    ) -> Result<Option<H256>> {
-        self.wallet_service.find_root_hash(name, enckey, address)
+            .add_key_pairs(name, enckey, &public_key, &private_key)?;
+        Ok(public_key)
     }
 
-    #[inline]
-    fn wallet_private_key(
-        &self,
-        name: &str,
-        enckey: &SecKey,
-        wallet_kind: WalletKind,
-    ) -> Result<Option<PrivateKey>> {
-        if wallet_kind != WalletKind::HW {
-            let k = self.key_service.wallet_private_key(name, enckey)?;
-            Ok(k)
-        } else {
-            unreachable!("can not get private key from HW key")
+    /// Returns wallet if it is an HD wallet
+    fn hd_wallet(&self, name: &str, enckey: &SecKey) -> Result<Wallet> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        if wallet.wallet_kind != WalletKind::HD {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Addresses at derivation indices are only supported in HD wallet",
+            ));
         }
+        Ok(wallet)
     }
 
-    fn sign_key(
+    /// Derives key pair of HD wallet at given index of given type and adds it to the wallet
+    ///
+    /// Index of next generated key of given type is advanced past `index`, so that the key is
+    /// never generated again.
+    fn add_hd_key_pair_at_index(
         &self,
         name: &str,
         enckey: &SecKey,
-        public_key: &PublicKey,
-    ) -> Result<Box<dyn PrivateKeyAction>> {
-        let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        match wallet.wallet_kind {
-            WalletKind::HW => self.hw_key_service.get_sign_key(public_key),
-            _ => {
-                let private_key = self
-                    .wallet_service
-                    .find_private_key(name, enckey, public_key)?
-                    .chain(|| {
-                        (
-                            ErrorKind::InvalidInput,
-                            "Not able to find private key for given public_key in current wallet",
-                        )
-                    })?;
-                Ok(Box::new(private_key))
-            }
-        }
+        account_type: HDAccountType,
+        index: u32,
+    ) -> Result<PublicKey> {
+        let account = self.hd_key_service.account(name, enckey)?;
+        let path = HdPath::wallet_key(account, account_type, index)?;
+        let public_key = self.add_hd_key_pair_at(name, enckey, &path)?;
+        self.hd_key_service
+            .advance_index(name, enckey, account_type, index + 1)?;
+        Ok(public_key)
     }
 
-    #[inline]
-    fn private_key(
+    /// Adds 1-of-1 transfer address of given public key to wallet (if it does not exist yet)
+    fn add_one_of_one_transfer_address(
         &self,
         name: &str,
         enckey: &SecKey,
-        public_key: &PublicKey,
-    ) -> Result<Option<PrivateKey>> {
-        let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        match wallet.wallet_kind {
-            WalletKind::HW => unreachable!("can not get private key in hw wallet"),
-            _ => self
-                .wallet_service
-                .find_private_key(name, enckey, public_key),
+        wallet: &Wallet,
+        public_key: PublicKey,
+    ) -> Result<ExtendedAddr> {
+        let address: ExtendedAddr =
+            MultiSigAddress::new(vec![public_key.clone()], public_key.clone(), 1)?.into();
+        if wallet.transfer_addresses().contains(&address) {
+            return Ok(address);
         }
+
+        self.wallet_service
+            .add_public_key(name, enckey, &public_key)?;
+        self.new_multisig_transfer_address(name, enckey, vec![public_key.clone()], public_key, 1)
     }
 
-    fn new_public_key(
+    /// Generates `count` addresses with `new_address` in a single batch of storage writes, so that
+    /// either all of them are added to the wallet or none
+    fn new_addresses_in_batch<A, F>(
         &self,
         name: &str,
         enckey: &SecKey,
-        address_type: Option<AddressType>,
-    ) -> Result<PublicKey> {
+        count: usize,
+        new_address: F,
+    ) -> Result<Vec<A>>
+    where
+        F: Fn(
+            &DefaultWalletClient<
+                BatchStorage<S>,
+                UnauthorizedClient,
+                UnauthorizedWalletTransactionBuilder,
+            >,
+        ) -> Result<A>,
+    {
         let wallet = self.wallet_service.get_wallet(name, enckey)?;
         match wallet.wallet_kind {
-            WalletKind::Basic => {
-                let private_key = PrivateKey::new()?;
-                let public_key = PublicKey::from(&private_key);
-                self.wallet_service
-                    .add_public_key(name, enckey, &public_key)?;
-                self.wallet_service
-                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
-                Ok(public_key)
-            }
-            WalletKind::HD => {
-                let (public_key, private_key) = self.hd_key_service.generate_keypair(
-                    name,
-                    enckey,
-                    address_type
-                        .chain(|| {
-                            (
-                                ErrorKind::InvalidInput,
-                                "Address type is needed when creating address for HD wallet",
-                            )
-                        })?
-                        .into(),
-                )?;
-                self.wallet_service
-                    .add_public_key(name, enckey, &public_key)?;
-                self.wallet_service
-                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
-                Ok(public_key)
-            }
+            WalletKind::Basic | WalletKind::HD => {}
             WalletKind::HW => {
-                let public_key = self.hw_key_service.new_staking_address()?;
-                self.wallet_service
-                    .add_public_key(name, enckey, &public_key)?;
-                Ok(public_key)
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Addresses of hardware wallet can not be generated in a batch",
+                ))
             }
+            WalletKind::WatchOnly => return Err(watch_only_new_key_error()),
         }
+
+        let batch = BatchStorage::new(self.storage.clone());
+        let client = DefaultWalletClient::new_read_only(batch.clone());
+        let addresses = (0..count)
+            .map(|_| new_address(&client))
+            .collect::<Result<Vec<_>>>()?;
+        batch.commit()?;
+        Ok(addresses)
     }
 
-    fn new_staking_address(&self, name: &str, enckey: &SecKey) -> Result<StakedStateAddress> {
-        let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        let public_key = match wallet.wallet_kind {
-            WalletKind::Basic => {
-                let private_key = PrivateKey::new()?;
-                let public_key = PublicKey::from(&private_key);
-                self.wallet_service
-                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
-                public_key
-            }
-            WalletKind::HD => {
-                let (public_key, private_key) =
-                    self.hd_key_service
-                        .generate_keypair(name, enckey, HDAccountType::Staking)?;
-                self.wallet_service
-                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
-                public_key
-            }
-            WalletKind::HW => self.hw_key_service.new_staking_address()?,
-        };
+    /// Number of blocks after which a broadcasted transaction is given up if not included in a
+    /// block
+    fn pending_tx_valid_blocks(&self) -> u64 {
+        self.block_height_ensure
+            .unwrap_or(DEFAULT_PENDING_TX_VALID_BLOCKS)
+    }
+}
 
-        self.wallet_service
-            .add_staking_key(name, enckey, &public_key)?;
+impl<S> DefaultWalletClient<S, UnauthorizedClient, UnauthorizedWalletTransactionBuilder>
+where
+    S: Storage,
+{
+    /// Creates a new read-only instance of `DefaultWalletClient`
+    pub fn new_read_only(storage: S) -> Self {
+        let hw_key_service = HwKeyService::default();
+        Self::new(
+            storage,
+            UnauthorizedClient,
+            UnauthorizedWalletTransactionBuilder,
+            None,
+            hw_key_service,
+        )
+    }
 
-        Ok(StakedStateAddress::BasicRedeem(RedeemAddress::from(
-            &public_key,
-        )))
+    /// Creates a new read-only instance of `DefaultWalletClient` after migrating storage to
+    /// current schema version (fails if storage was written by a newer client)
+    pub fn open_read_only(storage: S) -> Result<Self> {
+        migrate_storage(&storage)?;
+        Ok(Self::new_read_only(storage))
     }
+}
 
-    fn new_transfer_address(&self, name: &str, enckey: &SecKey) -> Result<ExtendedAddr> {
+impl<S, C, T> WalletClient for DefaultWalletClient<S, C, T>
+where
+    S: Storage,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    fn get_transaction(&self, name: &str, enckey: &SecKey, txid: TxId) -> Result<Transaction> {
         let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        let public_key = match wallet.wallet_kind {
-            WalletKind::Basic => {
-                let private_key = PrivateKey::new()?;
-                let public_key = PublicKey::from(&private_key);
-                self.wallet_service
-                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
-                public_key
-            }
-            WalletKind::HD => {
-                let (public_key, private_key) =
-                    self.hd_key_service
-                        .generate_keypair(name, enckey, HDAccountType::Transfer)?;
-                self.wallet_service
-                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
-                public_key
-            }
-            WalletKind::HW => self.hw_key_service.new_transfer_address()?,
-        };
-        self.wallet_service
-            .add_public_key(name, enckey, &public_key)?;
-
-        self.new_multisig_transfer_address(name, enckey, vec![public_key.clone()], public_key, 1)
+        let private_key = self
+            .wallet_private_key(name, enckey, wallet.wallet_kind)?
+            .chain(|| (ErrorKind::StorageError, "can not find private key"))?;
+        let tx = self.transaction_builder.decrypt_tx(txid, &private_key)?;
+        Ok(tx)
     }
 
-    fn new_watch_staking_address(
-        &self,
-        name: &str,
-        enckey: &SecKey,
-        public_key: &PublicKey,
-    ) -> Result<StakedStateAddress> {
-        self.wallet_service
-            .add_staking_key(name, enckey, public_key)?;
-
-        Ok(StakedStateAddress::BasicRedeem(RedeemAddress::from(
-            public_key,
-        )))
+    fn update_hw_service(&mut self, hw_wallet_kind: HardwareKind) {
+        let hw_key_service = match hw_wallet_kind {
+            HardwareKind::Ledger => HwKeyService::Unauthorized(UnauthorizedHwKeyService),
+            HardwareKind::Trezor => HwKeyService::Unauthorized(UnauthorizedHwKeyService),
+            #[cfg(feature = "mock-hardware-wallet")]
+            HardwareKind::Mock => {
+                let mock = MockHardwareService::new();
+                HwKeyService::Mock(mock)
+            }
+        };
+        self.hw_key_service = hw_key_service;
     }
 
-    fn new_watch_transfer_address(
-        &self,
-        name: &str,
-        enckey: &SecKey,
-        public_key: &PublicKey,
-    ) -> Result<ExtendedAddr> {
-        self.new_multisig_transfer_address(
-            name,
-            enckey,
-            vec![public_key.clone()],
-            public_key.clone(),
-            1,
-        )
+    fn get_wallet_kind(&self, name: &str, enckey: &SecKey) -> Result<WalletKind> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        Ok(wallet.wallet_kind)
     }
 
-    fn new_multisig_transfer_address(
+    fn send_to_address(
         &self,
         name: &str,
         enckey: &SecKey,
-        public_keys: Vec<PublicKey>,
-        self_public_key: PublicKey,
-        m: usize,
-    ) -> Result<ExtendedAddr> {
-        if !public_keys.contains(&self_public_key) {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Signer public keys does not contain self public key",
-            ));
-        }
+        amount: Coin,
+        address: ExtendedAddr,
+        view_keys: &mut BTreeSet<PublicKey>,
+        network_id: u8,
+    ) -> Result<TxId> {
+        let current_block_height = self.get_current_block_height()?;
+        let tx_out = TxOut::new(address, amount);
 
-        let (root_hash, multi_sig_address) =
-            self.root_hash_service
-                .new_root_hash(name, public_keys, self_public_key, m, enckey)?;
+        let view_key = self.view_key(name, enckey)?;
 
-        self.wallet_service.add_root_hash(name, enckey, root_hash)?;
+        view_keys.insert(view_key);
 
-        Ok(multi_sig_address.into())
+        let access_policies: BTreeSet<_> = view_keys
+            .iter()
+            .map(|key| TxAccessPolicy {
+                view_key: key.into(),
+                access: TxAccess::AllData,
+            })
+            .collect();
+
+        let attributes =
+            TxAttributes::new_with_access(network_id, access_policies.into_iter().collect());
+
+        let change_address = self.change_address(name, enckey)?;
+        let (transaction, selected_inputs, return_amount) =
+            self.build_transfer(name, enckey, vec![tx_out], attributes, None, change_address)?;
+
+        self.broadcast_transaction(&transaction)?;
+        //update the wallet state
+        let tx_pending = TransactionPending {
+            used_inputs: selected_inputs,
+            block_height: current_block_height,
+            return_amount,
+            tx_id: transaction.tx_id(),
+            valid_until_height: current_block_height + self.pending_tx_valid_blocks(),
+        };
+
+        self.update_tx_pending_state(name, enckey, transaction.tx_id(), tx_pending)?;
+
+        if let TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+            payload: TxObfuscated { txid, .. },
+            ..
+        }) = transaction
+        {
+            Ok(txid)
+        } else {
+            Err(Error::new(
+                ErrorKind::IllegalInput,
+                "Transaction is not transfer transaction",
+            ))
+        }
     }
 
-    fn generate_proof(
+    /// broadcast transaction and waiting it confiremed
+    fn send_to_address_commit(
         &self,
         name: &str,
         enckey: &SecKey,
-        address: &ExtendedAddr,
-        public_keys: Vec<PublicKey>,
-    ) -> Result<Proof<RawXOnlyPubkey>> {
-        // To verify if the enckey is correct or not
-        self.wallet_service.view_key(name, enckey)?;
-
-        match address {
-            ExtendedAddr::OrTree(ref address) => {
-                self.root_hash_service
-                    .generate_proof(name, address, public_keys, enckey)
+        amount: Coin,
+        address: ExtendedAddr,
+        view_keys: &mut BTreeSet<PublicKey>,
+        network_id: u8,
+    ) -> Result<TxId> {
+        let tx_id = self.send_to_address(name, enckey, amount, address, view_keys, network_id)?;
+        let block_height = self.get_current_block_height()?;
+        loop {
+            // query tx_id from tendermint
+            let confirmed = self
+                .tendermint_client
+                .query("meta", &tx_id.to_vec())
+                .is_ok();
+            if !confirmed {
+                std::thread::sleep(Duration::from_secs(1));
+                let current_block_height = self.get_current_block_height()?;
+                if current_block_height - block_height >= self.pending_tx_valid_blocks() {
+                    return Err(Error::new(
+                        ErrorKind::TendermintRpcError,
+                        "waiting for transaction confirmed timeout",
+                    ));
+                }
+                continue;
             }
+            break;
         }
+        Ok(tx_id)
     }
 
-    fn required_cosigners(&self, name: &str, enckey: &SecKey, root_hash: &H256) -> Result<usize> {
-        // To verify if the enckey is correct or not
-        self.wallet_service.view_key(name, enckey)?;
+    #[inline]
+    fn wallets(&self) -> Result<Vec<String>> {
+        self.wallet_service.names()
+    }
 
-        self.root_hash_service
-            .required_signers(name, root_hash, enckey)
+    #[inline]
+    fn wallet_network(&self, name: &str) -> Result<Option<NetworkInfo>> {
+        self.wallet_service.network(name)
+    }
+
+    fn list_wallets(&self) -> Result<Vec<WalletMetadata>> {
+        self.wallet_service
+            .names()?
+            .iter()
+            .map(|name| self.wallet_service.metadata(name))
+            .collect()
     }
 
     #[inline]
-    fn balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance> {
-        // Check if wallet exists
-        self.wallet_service.view_key(name, enckey)?;
-        self.wallet_state_service.get_balance(name, enckey)
+    fn wallet_info(&self, name: &str) -> Result<WalletMetadata> {
+        self.wallet_service.metadata(name)
     }
 
-    fn history(
+    fn export_wallet(&self, name: &str, enckey: &SecKey) -> Result<WalletInfo> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        let private_key = self
+            .key_service
+            .wallet_private_key(name, enckey)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "Can not find private key in wallet",
+                )
+            })?;
+        let labels = self
+            .address_label_service
+            .get_labels(name, enckey)?
+            .into_iter()
+            .collect();
+        let wallet_info = WalletInfo {
+            name: name.into(),
+            wallet,
+            private_key,
+            passphrase: None,
+            labels,
+        };
+        Ok(wallet_info)
+    }
+
+    fn import_wallet(
         &self,
         name: &str,
-        enckey: &SecKey,
-        offset: usize,
-        limit: usize,
-        reversed: bool,
-    ) -> Result<Vec<TransactionChange>> {
-        // Check if wallet exists
-        self.wallet_service.view_key(name, enckey)?;
+        passphrase: &SecUtf8,
+        wallet_info: WalletInfo,
+    ) -> Result<SecKey> {
+        self.create_in_batch(|client| client.create_imported_wallet(name, passphrase, wallet_info))
+    }
 
-        let history = self
-            .wallet_state_service
-            .get_transaction_history(name, enckey, reversed)?
-            .filter(|change| BalanceChange::NoChange != change.balance_change)
-            .skip(offset)
-            .take(limit)
-            .collect::<Vec<_>>();
+    fn new_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        wallet_kind: WalletKind,
+        hd_account: Option<u32>,
+        mnemonic_passphrase: Option<&SecUtf8>,
+    ) -> Result<(SecKey, Option<Mnemonic>)> {
+        self.create_in_batch(|client| {
+            client.create_new_wallet(
+                name,
+                passphrase,
+                wallet_kind,
+                hd_account,
+                mnemonic_passphrase,
+            )
+        })
+    }
 
-        Ok(history)
+    fn restore_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        mnemonic: &Mnemonic,
+        mnemonic_passphrase: Option<&SecUtf8>,
+    ) -> Result<SecKey> {
+        self.create_in_batch(|client| {
+            client.create_restored_wallet(name, passphrase, mnemonic, mnemonic_passphrase)
+        })
     }
 
-    #[inline]
-    fn get_transaction_change(
+    fn restore_basic_wallet(
         &self,
         name: &str,
-        enckey: &SecKey,
-        transaction_id: &TxId,
-    ) -> Result<Option<TransactionChange>> {
-        self.wallet_state_service
-            .get_transaction_change(name, enckey, transaction_id)
+        passphrase: &SecUtf8,
+        view_key_priv: &PrivateKey,
+    ) -> Result<SecKey> {
+        self.create_in_batch(|client| {
+            client.create_restored_basic_wallet(name, passphrase, view_key_priv)
+        })
     }
 
-    fn unspent_transactions(&self, name: &str, enckey: &SecKey) -> Result<UnspentTransactions> {
-        // Check if wallet exists
-        self.wallet_service.view_key(name, enckey)?;
+    fn new_watch_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        view_key: &PrivateKey,
+        public_keys: &[PublicKey],
+        staking_keys: &[PublicKey],
+    ) -> Result<SecKey> {
+        self.create_in_batch(|client| {
+            client.create_watch_wallet(name, passphrase, view_key, public_keys, staking_keys)
+        })
+    }
 
-        let unspent_transactions = self
-            .wallet_state_service
-            .get_unspent_transactions(name, enckey, false)?;
+    fn export_watch_only_wallet(&self, name: &str, enckey: &SecKey) -> Result<WatchOnlyWalletInfo> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        let view_key = self.view_key_private(name, enckey)?;
+
+        // only keys of 1-of-1 transfer addresses can be restored from a single public key
+        let mut public_keys = Vec::new();
+        for public_key in self.wallet_service.public_keys(name, enckey)? {
+            let address = MultiSigAddress::new(vec![public_key.clone()], public_key.clone(), 1)?;
+            if wallet.root_hashes.contains(&address.root_hash()) {
+                public_keys.push(public_key);
+            }
+        }
 
-        Ok(UnspentTransactions::new(
-            unspent_transactions.into_iter().collect(),
-        ))
+        Ok(WatchOnlyWalletInfo {
+            name: name.into(),
+            view_key,
+            public_keys,
+            staking_keys: wallet.staking_keys.into_iter().collect(),
+        })
     }
 
-    fn has_unspent_transactions(
+    fn export_wallet_backup(
         &self,
         name: &str,
-        enckey: &SecKey,
-        inputs: &[TxoPointer],
-    ) -> Result<bool> {
-        // Check if wallet exists
-        self.wallet_service.view_key(name, enckey)?;
-
-        self.wallet_state_service
-            .has_unspent_transactions(name, enckey, inputs)
+        passphrase: &SecUtf8,
+        backup_passphrase: &SecUtf8,
+    ) -> Result<Vec<u8>> {
+        let enckey = self.auth_token(name, passphrase)?;
+        export_backup(&self.storage, name, &enckey, backup_passphrase)
     }
 
-    #[inline]
-    fn are_inputs_unspent(
+    fn import_wallet_backup(
         &self,
         name: &str,
-        enckey: &SecKey,
-        inputs: Vec<TxoPointer>,
-    ) -> Result<Vec<(TxoPointer, bool)>> {
-        self.wallet_state_service
-            .are_inputs_unspent(name, enckey, inputs)
-    }
+        passphrase: &SecUtf8,
+        backup_passphrase: &SecUtf8,
+        backup: &[u8],
+        force: bool,
+    ) -> Result<SecKey> {
+        check_passphrase_strength(name, passphrase)?;
+        // invalid backups are refused before touching an existing wallet
+        let payload = open_backup(backup, backup_passphrase)?;
 
-    #[inline]
-    fn output(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<TxOut> {
-        // Check if wallet exists
-        self.wallet_service.view_key(name, enckey)?;
+        if self.wallets()?.iter().any(|wallet| wallet == name) {
+            if !force {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Wallet with name ({}) already exists", name),
+                ));
+            }
+            let token = self.delete_wallet(name, passphrase, None)?;
+            self.delete_wallet(name, passphrase, token.as_deref())?;
+        }
 
-        self.wallet_state_service
-            .get_output(name, enckey, input)
-            .and_then(|optional| {
-                optional.chain(|| {
-                    (
-                        ErrorKind::InvalidInput,
-                        "Output details not found for given transaction input",
-                    )
-                })
-            })
+        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
+            "unable to derive encryption key from passphrase"
+        })?;
+        self.create_in_batch(|client| {
+            restore_backup(&client.storage, name, &enckey, payload)?;
+            client.record_network(name)
+        })?;
+        Ok(enckey)
     }
 
-    fn create_transaction(
+    fn delete_wallet(
         &self,
         name: &str,
-        enckey: &SecKey,
-        outputs: Vec<TxOut>,
-        attributes: TxAttributes,
-        input_selection_strategy: Option<InputSelectionStrategy>,
-        return_address: ExtendedAddr,
-    ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
-        let mut unspent_transactions = self.unspent_transactions(name, enckey)?;
-        unspent_transactions.apply_all(input_selection_strategy.unwrap_or_default().as_ref());
-
-        self.transaction_builder.build_transfer_tx(
-            name,
-            enckey,
-            unspent_transactions,
-            outputs,
-            return_address,
-            attributes,
-        )
+        passphrase: &SecUtf8,
+        confirmation_token: Option<&str>,
+    ) -> Result<Option<String>> {
+        let enckey = self.auth_token(name, passphrase)?;
+
+        match confirmation_token {
+            None => self
+                .deletion_service
+                .request_deletion(name, &enckey)
+                .map(Some),
+            Some(token) => {
+                self.deletion_service.delete(name, &enckey, token)?;
+                self.wallet_session_service.lock_wallet(name)?;
+                Ok(None)
+            }
+        }
     }
 
-    #[inline]
-    fn broadcast_transaction(&self, tx_aux: &TxAux) -> Result<BroadcastTxResponse> {
-        self.tendermint_client
-            .broadcast_transaction(&tx_aux.encode())
+    fn auth_token(&self, name: &str, passphrase: &SecUtf8) -> Result<SecKey> {
+        let enckey = derive_enckey(passphrase, name).err_kind(ErrorKind::InvalidInput, || {
+            "unable to derive encryption key from passphrase"
+        })?;
+
+        // complete interrupted passphrase change before testing enckey
+        self.passphrase_service.recover(name)?;
+
+        // test validity of enckey
+        self.view_key(name, &enckey)?;
+        Ok(enckey)
     }
 
-    fn export_plain_tx(&self, name: &str, enckey: &SecKey, txid: &str) -> Result<TransactionInfo> {
-        let txid = str2txid(txid).chain(|| (ErrorKind::InvalidInput, "invalid transaction id"))?;
-        let tx = self.get_transaction(name, enckey, txid)?;
-        // get the block height
-        let tx_change = self
-            .wallet_state_service
-            .get_transaction_history(name, enckey, false)?
-            .filter(|change| BalanceChange::NoChange != change.balance_change)
-            .find(|tx_change| tx_change.transaction_id == tx.id())
-            .chain(|| {
-                (
-                    ErrorKind::InvalidInput,
-                    "no transaction find by transaction id",
-                )
+    fn change_passphrase(
+        &self,
+        name: &str,
+        old_passphrase: &SecUtf8,
+        new_passphrase: &SecUtf8,
+    ) -> Result<SecKey> {
+        let old_enckey = self.auth_token(name, old_passphrase)?;
+        check_passphrase_strength(name, new_passphrase)?;
+        let new_enckey = derive_enckey(new_passphrase, name)
+            .err_kind(ErrorKind::InvalidInput, || {
+                "unable to derive encryption key from passphrase"
             })?;
 
-        let tx_info = TransactionInfo {
-            tx,
-            block_height: tx_change.block_height,
-        };
-        Ok(tx_info)
+        self.passphrase_service
+            .change_enckey(name, &old_enckey, &new_enckey)?;
+        // sessions unlocked with old passphrase are no longer valid
+        self.wallet_session_service.lock_wallet(name)?;
+        Ok(new_enckey)
     }
 
-    /// import a plain base64 encoded plain transaction
-    fn import_plain_tx(&self, name: &str, enckey: &SecKey, tx_str: &str) -> Result<Coin> {
-        let tx_info = TransactionInfo::decode(tx_str)?;
-        // check if the output is spent or not
-        let v = self
-            .tendermint_client
-            .query("meta", &tx_info.tx.id().to_vec())?
-            .bytes();
-        let bit_flag = BitVec::from_bytes(&v);
-        let spent_flags: Result<Vec<bool>> = tx_info
-            .tx
-            .outputs()
-            .iter()
-            .enumerate()
-            .map(|(index, _output)| {
-                bit_flag
-                    .get(index)
-                    .chain(|| (ErrorKind::InvalidInput, "check failed in enclave"))
-            })
-            .collect();
-        let mut memento = WalletStateMemento::default();
-        // check if tx belongs to the block
-        let block = self.tendermint_client.block(tx_info.block_height)?;
-        let block_result = self.tendermint_client.block_results(tx_info.block_height)?;
-        let fees = block_result.fees()?;
-        let paid_fee = fees.get(&tx_info.tx.id());
-        if paid_fee.is_none() {
+    fn rename_wallet(
+        &self,
+        old_name: &str,
+        passphrase: &SecUtf8,
+        new_name: &str,
+    ) -> Result<SecKey> {
+        let old_enckey = self.auth_token(old_name, passphrase)?;
+        if self.wallets()?.iter().any(|name| name == new_name) {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
-                "block height and transaction not match",
+                format!("Wallet with name ({}) already exists", new_name),
             ));
         }
-        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        let new_enckey = derive_enckey(passphrase, new_name)
+            .err_kind(ErrorKind::InvalidInput, || {
+                "unable to derive encryption key from passphrase"
+            })?;
 
-        let wallet_state = self.wallet_service.get_wallet_state(name, enckey)?;
+        // re-open copied wallet and compare it with the old one before deleting old records
+        let verify = || -> Result<()> {
+            let matches = self.view_key(old_name, &old_enckey)?
+                == self.view_key(new_name, &new_enckey)?
+                && self.get_wallet_kind(old_name, &old_enckey)?
+                    == self.get_wallet_kind(new_name, &new_enckey)?
+                && self.public_keys(old_name, &old_enckey)?
+                    == self.public_keys(new_name, &new_enckey)?
+                && self.staking_keys(old_name, &old_enckey)?
+                    == self.staking_keys(new_name, &new_enckey)?
+                && self.root_hashes(old_name, &old_enckey)?
+                    == self.root_hashes(new_name, &new_enckey)?
+                && self.balance(old_name, &old_enckey)? == self.balance(new_name, &new_enckey)?
+                && load_sync_state(&self.storage, old_name)?.encode()
+                    == load_sync_state(&self.storage, new_name)?.encode();
+            if matches {
+                Ok(())
+            } else {
+                Err(Error::new(
+                    ErrorKind::StorageError,
+                    "Renamed wallet does not match the original one",
+                ))
+            }
+        };
 
-        let imported_value = import_transaction(
-            &wallet,
-            &wallet_state,
-            &mut memento,
-            &tx_info,
-            *paid_fee.expect("tx fee checked above"),
-            block.header.time,
-            spent_flags?,
-        )
-        .chain(|| (ErrorKind::InvalidInput, "import error"))?;
+        self.rename_service
+            .rename(old_name, new_name, &old_enckey, &new_enckey, verify)?;
+        self.wallet_session_service.lock_wallet(old_name)?;
+        Ok(new_enckey)
+    }
 
-        self.wallet_state_service
-            .apply_memento(name, enckey, &memento)?;
-        Ok(imported_value)
+    fn unlock_wallet(
+        &self,
+        name: &str,
+        passphrase: &SecUtf8,
+        ttl: Duration,
+    ) -> Result<SessionToken> {
+        let enckey = self.auth_token(name, passphrase)?;
+        self.wallet_session_service.unlock(name, enckey, ttl)
     }
 
-    fn get_current_block_height(&self) -> Result<u64> {
-        let status = self.tendermint_client.status()?;
-        let current_block_height = status.sync_info.latest_block_height.value();
-        Ok(current_block_height)
+    #[inline]
+    fn lock_wallet(&self, token: &SessionToken) -> Result<()> {
+        self.wallet_session_service.lock(token)
     }
 
-    fn update_tx_pending_state(
+    #[inline]
+    fn unlocked_wallet(&self, token: &SessionToken) -> Result<(String, SecKey)> {
+        self.wallet_session_service.get(token)
+    }
+
+    #[inline]
+    fn view_key(&self, name: &str, enckey: &SecKey) -> Result<PublicKey> {
+        self.wallet_service.view_key(name, enckey)
+    }
+
+    #[inline]
+    fn view_key_private(&self, name: &str, enckey: &SecKey) -> Result<PrivateKey> {
+        self.key_service
+            .wallet_private_key(name, enckey)?
+            .err_kind(ErrorKind::InvalidInput, || "private view key not found")
+    }
+
+    #[inline]
+    fn public_keys(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<PublicKey>> {
+        self.wallet_service.public_keys(name, enckey)
+    }
+
+    #[inline]
+    fn staking_keys(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<PublicKey>> {
+        self.wallet_service.staking_keys(name, enckey)
+    }
+
+    #[inline]
+    fn root_hashes(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<H256>> {
+        self.wallet_service.root_hashes(name, enckey)
+    }
+
+    #[inline]
+    fn staking_addresses(
         &self,
         name: &str,
         enckey: &SecKey,
-        tx_id: TxId,
-        tx_pending: TransactionPending,
-    ) -> Result<()> {
-        let mut wallet_state_memento = WalletStateMemento::default();
-        wallet_state_memento.add_pending_transaction(tx_id, tx_pending);
-        self.wallet_state_service
-            .apply_memento(name, enckey, &wallet_state_memento)
+    ) -> Result<IndexSet<StakedStateAddress>> {
+        self.wallet_service.staking_addresses(name, enckey)
     }
 
-    fn build_raw_transfer_tx(
+    #[inline]
+    fn transfer_addresses(&self, name: &str, enckey: &SecKey) -> Result<IndexSet<ExtendedAddr>> {
+        self.wallet_service.transfer_addresses(name, enckey)
+    }
+
+    fn set_address_label(
         &self,
         name: &str,
         enckey: &SecKey,
-        to_address: ExtendedAddr,
-        amount: Coin,
-        view_keys: Vec<PublicKey>,
-        network_id: u8,
-    ) -> Result<UnsignedTransferTransaction> {
-        let unspent_transactions = self.unspent_transactions(name, enckey)?;
-        let return_address = self.new_transfer_address(name, enckey)?;
-        let unsigned = UnsignedTransferTransaction {
-            unspent_transactions,
-            view_keys,
-            network_id,
-            to_address,
-            return_address,
-            amount,
-        };
-        Ok(unsigned)
+        address: LabeledAddress,
+        label: &str,
+    ) -> Result<()> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        self.address_label_service
+            .set_label(name, enckey, address, label)
     }
 
-    fn sign_raw_transfer_tx(
+    fn get_address_label(
         &self,
         name: &str,
         enckey: &SecKey,
-        unsigned_tx: UnsignedTransferTransaction,
-    ) -> Result<SignedTransferTransaction> {
-        let tx_out = TxOut::new(unsigned_tx.to_address, unsigned_tx.amount);
-        let view_key = self.view_key(name, enckey)?;
-        let mut view_keys = unsigned_tx.view_keys;
-        view_keys.push(view_key);
-        let access_policies: BTreeSet<_> = view_keys
-            .iter()
-            .map(|key| TxAccessPolicy {
-                view_key: key.into(),
-                access: TxAccess::AllData,
-            })
-            .collect();
-
-        let attributes = TxAttributes::new_with_access(
-            unsigned_tx.network_id,
-            access_policies.into_iter().collect(),
-        );
-
-        let return_address = unsigned_tx.return_address.clone();
+        address: &LabeledAddress,
+    ) -> Result<Option<String>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
 
-        let (transaction, selected_inputs, return_amount) =
-            self.transaction_builder.build_transfer_tx(
-                name,
-                enckey,
-                unsigned_tx.unspent_transactions,
-                vec![tx_out],
-                return_address,
-                attributes,
-            )?;
-        let signed_tx = SignedTransferTransaction {
-            signed_transaction: transaction,
-            used_inputs: selected_inputs,
-            return_amount,
-        };
-        Ok(signed_tx)
+        self.address_label_service.get_label(name, enckey, address)
     }
 
-    fn broadcast_signed_transfer_tx(
+    fn list_labeled_addresses(
         &self,
         name: &str,
         enckey: &SecKey,
-        signed_tx: SignedTransferTransaction,
-    ) -> Result<TxId> {
-        let current_block_height = self.get_current_block_height()?;
+    ) -> Result<Vec<(LabeledAddress, String)>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
 
-        self.broadcast_transaction(&signed_tx.signed_transaction)?;
+        Ok(self
+            .address_label_service
+            .get_labels(name, enckey)?
+            .into_iter()
+            .collect())
+    }
 
-        //update the wallet state
-        let tx_pending = TransactionPending {
-            used_inputs: signed_tx.used_inputs.clone(),
-            block_height: current_block_height,
-            return_amount: signed_tx.return_amount,
-        };
+    #[inline]
+    fn find_staking_key(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        redeem_address: &RedeemAddress,
+    ) -> Result<Option<PublicKey>> {
+        self.wallet_service
+            .find_staking_key(name, enckey, redeem_address)
+    }
 
-        let transaction = signed_tx.signed_transaction;
-
-        self.update_tx_pending_state(name, enckey, transaction.tx_id(), tx_pending)?;
+    #[inline]
+    fn find_root_hash(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &ExtendedAddr,
+    ) -> Result<Option<H256>> {
+        self.wallet_service.find_root_hash(name, enckey, address)
+    }
 
-        if let TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
-            payload: TxObfuscated { txid, .. },
-            ..
-        }) = transaction
-        {
-            Ok(txid)
+    #[inline]
+    fn wallet_private_key(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        wallet_kind: WalletKind,
+    ) -> Result<Option<PrivateKey>> {
+        if wallet_kind != WalletKind::HW {
+            let k = self.key_service.wallet_private_key(name, enckey)?;
+            Ok(k)
         } else {
-            Err(Error::new(
-                ErrorKind::IllegalInput,
-                "Transaction is not transfer transaction",
-            ))
+            unreachable!("can not get private key from HW key")
         }
     }
-}
 
-impl<S, C, T> MultiSigWalletClient for DefaultWalletClient<S, C, T>
-where
-    S: Storage,
-    C: Client,
-    T: WalletTransactionBuilder,
-{
-    fn schnorr_signature(
+    fn sign_key(
         &self,
         name: &str,
         enckey: &SecKey,
-        tx: &Transaction,
         public_key: &PublicKey,
-    ) -> Result<SchnorrSignature> {
-        // To verify if the enckey is correct or not
-        self.transfer_addresses(name, enckey)?;
+    ) -> Result<Box<dyn PrivateKeyAction>> {
         let wallet = self.wallet_service.get_wallet(name, enckey)?;
-        let sign_key = match wallet.wallet_kind {
-            WalletKind::HW => self.hw_key_service.get_sign_key(public_key)?,
+        wallet.wallet_kind.verify_can_sign()?;
+        match wallet.wallet_kind {
+            WalletKind::HW => self.hw_key_service.get_sign_key(public_key),
             _ => {
                 let private_key = self
                     .wallet_service
                     .find_private_key(name, enckey, public_key)?
-                    .chain(|| (ErrorKind::InvalidInput, ""))?;
-                Box::new(private_key)
+                    .chain(|| {
+                        (
+                            ErrorKind::InvalidInput,
+                            "Not able to find private key for given public_key in current wallet",
+                        )
+                    })?;
+                Ok(Box::new(private_key))
             }
-        };
-        sign_key.schnorr_sign(tx)
+        }
     }
 
-    fn new_multi_sig_session(
+    #[inline]
+    fn private_key(
         &self,
         name: &str,
         enckey: &SecKey,
-        message: H256,
-        signer_public_keys: Vec<PublicKey>,
-        self_public_key: PublicKey,
-    ) -> Result<H256> {
-        // To verify if the enckey is correct or not
-        self.transfer_addresses(name, enckey)?;
-        //        let sign_key = self.sign_key(name, enckey, &self_public_key)?;
+        public_key: &PublicKey,
+    ) -> Result<Option<PrivateKey>> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        match wallet.wallet_kind {
+            WalletKind::HW => unreachable!("can not get private key in hw wallet"),
+            _ => self
+                .wallet_service
+                .find_private_key(name, enckey, public_key),
+        }
+    }
 
-        let self_private_key = self
-            .private_key(name, enckey, &self_public_key)?
-            .chain(|| {
-                (
-                    ErrorKind::InvalidInput,
-                    format!(
-                        "Self public key ({}) is not owned by current wallet",
-                        self_public_key
-                    ),
-                )
-            })?;
+    fn new_public_key(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address_type: Option<AddressType>,
+    ) -> Result<PublicKey> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        match wallet.wallet_kind {
+            WalletKind::Basic => {
+                let private_key = PrivateKey::new()?;
+                let public_key = PublicKey::from(&private_key);
+                self.wallet_service
+                    .add_public_key(name, enckey, &public_key)?;
+                self.wallet_service
+                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
+                Ok(public_key)
+            }
+            WalletKind::HD => {
+                let (public_key, private_key) = self.hd_key_service.generate_keypair(
+                    name,
+                    enckey,
+                    address_type
+                        .chain(|| {
+                            (
+                                ErrorKind::InvalidInput,
+                                "Address type is needed when creating address for HD wallet",
+                            )
+                        })?
+                        .into(),
+                )?;
+                self.wallet_service
+                    .add_public_key(name, enckey, &public_key)?;
+                self.wallet_service
+                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
+                Ok(public_key)
+            }
+            WalletKind::HW => {
+                let public_key = self.hw_key_service.new_staking_address()?;
+                self.wallet_service
+                    .add_public_key(name, enckey, &public_key)?;
+                Ok(public_key)
+            }
+            WalletKind::WatchOnly => Err(watch_only_new_key_error()),
+        }
+    }
 
-        self.multi_sig_session_service.new_session(
-            message,
-            signer_public_keys,
-            self_public_key,
-            self_private_key,
-            enckey,
-        )
+    fn new_staking_address(&self, name: &str, enckey: &SecKey) -> Result<StakedStateAddress> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        let public_key = match wallet.wallet_kind {
+            WalletKind::Basic => {
+                let private_key = PrivateKey::new()?;
+                let public_key = PublicKey::from(&private_key);
+                self.wallet_service
+                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
+                public_key
+            }
+            WalletKind::HD => {
+                let (public_key, private_key) =
+                    self.hd_key_service
+                        .generate_keypair(name, enckey, HDAccountType::Staking)?;
+                self.wallet_service
+                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
+                public_key
+            }
+            WalletKind::HW => self.hw_key_service.new_staking_address()?,
+            WalletKind::WatchOnly => return Err(watch_only_new_key_error()),
+        };
+
+        self.wallet_service
+            .add_staking_key(name, enckey, &public_key)?;
+
+        Ok(StakedStateAddress::BasicRedeem(RedeemAddress::from(
+            &public_key,
+        )))
     }
 
-    fn nonce_commitment(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
-        self.multi_sig_session_service
-            .nonce_commitment(session_id, enckey)
+    fn new_transfer_address(&self, name: &str, enckey: &SecKey) -> Result<ExtendedAddr> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        let public_key = match wallet.wallet_kind {
+            WalletKind::Basic => {
+                let private_key = PrivateKey::new()?;
+                let public_key = PublicKey::from(&private_key);
+                self.wallet_service
+                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
+                public_key
+            }
+            WalletKind::HD => {
+                let (public_key, private_key) =
+                    self.hd_key_service
+                        .generate_keypair(name, enckey, HDAccountType::Transfer)?;
+                self.wallet_service
+                    .add_key_pairs(name, enckey, &public_key, &private_key)?;
+                public_key
+            }
+            WalletKind::HW => self.hw_key_service.new_transfer_address()?,
+            WalletKind::WatchOnly => return Err(watch_only_new_key_error()),
+        };
+        self.wallet_service
+            .add_public_key(name, enckey, &public_key)?;
+
+        self.new_multisig_transfer_address(name, enckey, vec![public_key.clone()], public_key, 1)
     }
 
-    fn add_nonce_commitment(
+    fn new_staking_addresses(
         &self,
-        session_id: &H256,
+        name: &str,
         enckey: &SecKey,
-        nonce_commitment: H256,
-        public_key: &PublicKey,
-    ) -> Result<()> {
-        self.multi_sig_session_service.add_nonce_commitment(
-            session_id,
-            nonce_commitment,
-            public_key,
-            enckey,
-        )
+        count: usize,
+    ) -> Result<Vec<StakedStateAddress>> {
+        self.new_addresses_in_batch(name, enckey, count, |client| {
+            client.new_staking_address(name, enckey)
+        })
     }
 
-    fn nonce(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
-        self.multi_sig_session_service.nonce(session_id, enckey)
+    fn new_transfer_addresses(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        count: usize,
+    ) -> Result<Vec<ExtendedAddr>> {
+        self.new_addresses_in_batch(name, enckey, count, |client| {
+            client.new_transfer_address(name, enckey)
+        })
     }
 
-    fn add_nonce(
+    fn new_staking_address_at(
         &self,
-        session_id: &H256,
+        name: &str,
         enckey: &SecKey,
-        nonce: &H256,
-        public_key: &PublicKey,
-    ) -> Result<()> {
-        self.multi_sig_session_service
-            .add_nonce(session_id, &nonce, public_key, enckey)
-    }
+        path: &HdPath,
+    ) -> Result<StakedStateAddress> {
+        let public_key = self.add_hd_key_pair_at(name, enckey, path)?;
+        self.wallet_service
+            .add_staking_key(name, enckey, &public_key)?;
 
-    fn partial_signature(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
-        self.multi_sig_session_service
-            .partial_signature(session_id, enckey)
+        Ok(StakedStateAddress::BasicRedeem(RedeemAddress::from(
+            &public_key,
+        )))
     }
 
-    fn add_partial_signature(
+    fn rescan_addresses(
         &self,
-        session_id: &H256,
+        name: &str,
         enckey: &SecKey,
-        partial_signature: H256,
-        public_key: &PublicKey,
-    ) -> Result<()> {
-        self.multi_sig_session_service.add_partial_signature(
-            session_id,
-            partial_signature,
-            public_key,
+        gap_limit: usize,
+    ) -> Result<DiscoveredAddresses> {
+        discover_addresses(
+            &self.storage,
+            &self.tendermint_client,
+            name,
             enckey,
+            gap_limit,
         )
     }
 
-    fn signature(&self, session_id: &H256, enckey: &SecKey) -> Result<SchnorrSignature> {
-        self.multi_sig_session_service.signature(session_id, enckey)
+    fn new_transfer_address_at(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        path: &HdPath,
+    ) -> Result<ExtendedAddr> {
+        let public_key = self.add_hd_key_pair_at(name, enckey, path)?;
+        self.wallet_service
+            .add_public_key(name, enckey, &public_key)?;
+
+        self.new_multisig_transfer_address(name, enckey, vec![public_key.clone()], public_key, 1)
     }
 
-    fn transaction(
+    fn staking_address_at_index(
         &self,
         name: &str,
-        session_id: &H256,
         enckey: &SecKey,
-        unsigned_transaction: Tx,
-    ) -> Result<TxAux> {
-        if unsigned_transaction.inputs.len() != 1 {
+        index: u32,
+    ) -> Result<StakedStateAddress> {
+        let wallet = self.hd_wallet(name, enckey)?;
+        let public_key =
+            self.add_hd_key_pair_at_index(name, enckey, HDAccountType::Staking, index)?;
+        if !wallet.staking_keys.contains(&public_key) {
+            self.wallet_service
+                .add_staking_key(name, enckey, &public_key)?;
+        }
+
+        Ok(StakedStateAddress::BasicRedeem(RedeemAddress::from(
+            &public_key,
+        )))
+    }
+
+    fn transfer_address_at_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        index: u32,
+    ) -> Result<ExtendedAddr> {
+        let wallet = self.hd_wallet(name, enckey)?;
+        let public_key =
+            self.add_hd_key_pair_at_index(name, enckey, HDAccountType::Transfer, index)?;
+        self.add_one_of_one_transfer_address(name, enckey, &wallet, public_key)
+    }
+
+    fn address_index(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &LabeledAddress,
+    ) -> Result<Option<u32>> {
+        let wallet = self.hd_wallet(name, enckey)?;
+        let account_type = match address {
+            LabeledAddress::Staking(address) if wallet.staking_addresses().contains(address) => {
+                HDAccountType::Staking
+            }
+            LabeledAddress::Transfer(address) if wallet.transfer_addresses().contains(address) => {
+                HDAccountType::Transfer
+            }
+            _ => return Ok(None),
+        };
+
+        let account = self.hd_key_service.account(name, enckey)?;
+        let next_index = self.hd_key_service.next_index(name, enckey, account_type)?;
+        for index in 0..next_index {
+            let path = HdPath::wallet_key(account, account_type, index)?;
+            let (public_key, _) = self.hd_key_service.derive_keypair_at(name, enckey, &path)?;
+            let derived = match account_type {
+                HDAccountType::Staking => {
+                    StakedStateAddress::BasicRedeem(RedeemAddress::from(&public_key)).into()
+                }
+                _ => LabeledAddress::Transfer(
+                    MultiSigAddress::new(vec![public_key.clone()], public_key, 1)?.into(),
+                ),
+            };
+            if derived == *address {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn import_private_key(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        key_bytes: &[u8],
+        role: KeyRole,
+    ) -> Result<PublicKey> {
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        match wallet.wallet_kind {
+            WalletKind::Basic | WalletKind::HD => {}
+            WalletKind::HW => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Private keys can not be imported into hardware wallet",
+                ))
+            }
+            WalletKind::WatchOnly => {
+                return Err(Error::new(
+                    ErrorKind::PermissionDenied,
+                    "Private keys can not be imported into watch-only wallet",
+                ))
+            }
+        }
+
+        // rejects zero and values not less than curve order
+        let private_key = PrivateKey::deserialize_from(key_bytes)?;
+        let public_key = PublicKey::from(&private_key);
+        self.wallet_service
+            .add_key_pairs(name, enckey, &public_key, &private_key)?;
+        self.wallet_service
+            .add_imported_key(name, enckey, &public_key)?;
+
+        match role {
+            KeyRole::Staking => {
+                if !wallet.staking_keys.contains(&public_key) {
+                    self.wallet_service
+                        .add_staking_key(name, enckey, &public_key)?;
+                }
+            }
+            KeyRole::Transfer => {
+                self.add_one_of_one_transfer_address(name, enckey, &wallet, public_key.clone())?;
+            }
+        }
+
+        Ok(public_key)
+    }
+
+    fn new_watch_staking_address(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+    ) -> Result<StakedStateAddress> {
+        self.wallet_service
+            .add_staking_key(name, enckey, public_key)?;
+
+        Ok(StakedStateAddress::BasicRedeem(RedeemAddress::from(
+            public_key,
+        )))
+    }
+
+    fn new_watch_transfer_address(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+    ) -> Result<ExtendedAddr> {
+        self.new_multisig_transfer_address(
+            name,
+            enckey,
+            vec![public_key.clone()],
+            public_key.clone(),
+            1,
+        )
+    }
+
+    fn new_multisig_transfer_address(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        public_keys: Vec<PublicKey>,
+        self_public_key: PublicKey,
+        m: usize,
+    ) -> Result<ExtendedAddr> {
+        if !public_keys.contains(&self_public_key) {
             return Err(Error::new(
                 ErrorKind::InvalidInput,
-                "Multi-Sig Signing is only supported for transactions with only one input",
+                "Signer public keys does not contain self public key",
             ));
         }
 
-        let output_to_spend = self.output(name, enckey, &unsigned_transaction.inputs[0])?;
-        let root_hash = self
-            .wallet_service
-            .find_root_hash(name, enckey, &output_to_spend.address)?
-            .chain(|| {
-                (
-                    ErrorKind::IllegalInput,
-                    "Output address is not owned by current wallet; cannot spend output in given transaction",
-                )
-            })?;
-        let public_keys = self
-            .multi_sig_session_service
-            .public_keys(session_id, enckey)?;
+        let (root_hash, multi_sig_address) =
+            self.root_hash_service
+                .new_root_hash(name, public_keys, self_public_key, m, enckey)?;
+
+        self.wallet_service.add_root_hash(name, enckey, root_hash)?;
+
+        Ok(multi_sig_address.into())
+    }
+
+    fn generate_proof(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &ExtendedAddr,
+        public_keys: Vec<PublicKey>,
+    ) -> Result<Proof<RawXOnlyPubkey>> {
+        // To verify if the enckey is correct or not
+        self.wallet_service.view_key(name, enckey)?;
+
+        match address {
+            ExtendedAddr::OrTree(ref address) => {
+                self.root_hash_service
+                    .generate_proof(name, address, public_keys, enckey)
+            }
+        }
+    }
+
+    fn required_cosigners(&self, name: &str, enckey: &SecKey, root_hash: &H256) -> Result<usize> {
+        // To verify if the enckey is correct or not
+        self.wallet_service.view_key(name, enckey)?;
+
+        self.root_hash_service
+            .required_signers(name, root_hash, enckey)
+    }
+
+    #[inline]
+    fn balance(&self, name: &str, enckey: &SecKey) -> Result<WalletBalance> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+        self.wallet_state_service.get_balance(name, enckey)
+    }
+
+    fn get_detailed_balance(&self, name: &str, enckey: &SecKey) -> Result<DetailedBalance> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let status = self.tendermint_client.status()?;
+        let block_time = to_timespec(status.sync_info.latest_block_time)?;
+        self.wallet_state_service
+            .get_detailed_balance(name, enckey, block_time)
+    }
+
+    fn balance_by_address(&self, name: &str, enckey: &SecKey) -> Result<Vec<AddressBalance>> {
+        let addresses = self
+            .transfer_addresses(name, enckey)?
+            .into_iter()
+            .map(LabeledAddress::Transfer)
+            .chain(
+                self.staking_addresses(name, enckey)?
+                    .into_iter()
+                    .map(LabeledAddress::Staking),
+            )
+            .collect();
+
+        let mut balances = self
+            .wallet_state_service
+            .get_address_balances(name, enckey, addresses)?;
+        let mut labels = self.address_label_service.get_labels(name, enckey)?;
+        for balance in balances.iter_mut() {
+            balance.label = labels.remove(&balance.address);
+        }
+        Ok(balances)
+    }
+
+    fn history(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        offset: usize,
+        limit: usize,
+        reversed: bool,
+    ) -> Result<Vec<TransactionChange>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let page = self.wallet_state_service.get_transaction_history_page(
+            name,
+            enckey,
+            &HistoryFilter::default(),
+            offset,
+            limit,
+            reversed,
+        )?;
+
+        Ok(page.records)
+    }
+
+    fn history_page(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        filter: HistoryFilter,
+        offset: usize,
+        limit: usize,
+        order: SortOrder,
+    ) -> Result<HistoryPage> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let mut page = self.wallet_state_service.get_transaction_history_page(
+            name,
+            enckey,
+            &filter,
+            offset,
+            limit.min(MAX_HISTORY_PAGE_LIMIT),
+            order == SortOrder::Descending,
+        )?;
+        if filter.include_labels {
+            let labels = self.address_label_service.get_labels(name, enckey)?;
+            page.labels = history_labels(&page.records, &labels);
+        }
+        Ok(page)
+    }
+
+    #[inline]
+    fn get_transaction_change(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        transaction_id: &TxId,
+    ) -> Result<Option<TransactionChange>> {
+        self.wallet_state_service
+            .get_transaction_change(name, enckey, transaction_id)
+    }
+
+    fn unspent_transactions(&self, name: &str, enckey: &SecKey) -> Result<UnspentTransactions> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let unspent_transactions = self
+            .wallet_state_service
+            .get_selectable_transactions(name, enckey)?;
+
+        Ok(UnspentTransactions::new(
+            unspent_transactions.into_iter().collect(),
+        ))
+    }
+
+    fn freeze_utxo(&self, name: &str, enckey: &SecKey, pointer: TxoPointer) -> Result<()> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        self.wallet_state_service
+            .set_utxo_frozen(name, enckey, &pointer, true)
+    }
+
+    fn unfreeze_utxo(&self, name: &str, enckey: &SecKey, pointer: TxoPointer) -> Result<()> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        if !self
+            .wallet_state_service
+            .get_frozen_utxos(name, enckey)?
+            .contains(&pointer)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "UTXO is not frozen: {}:{}",
+                    hex::encode(&pointer.id),
+                    pointer.index
+                ),
+            ));
+        }
+        self.wallet_state_service
+            .set_utxo_frozen(name, enckey, &pointer, false)
+    }
+
+    fn list_frozen_utxos(&self, name: &str, enckey: &SecKey) -> Result<Vec<TxoPointer>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        Ok(self
+            .wallet_state_service
+            .get_frozen_utxos(name, enckey)?
+            .into_iter()
+            .collect())
+    }
+
+    fn list_unspent(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        filter: UtxoFilter,
+    ) -> Result<Vec<UtxoInfo>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let mut utxos = self
+            .wallet_state_service
+            .list_unspent(name, enckey, &filter)?;
+        if filter.include_labels {
+            let labels = self.address_label_service.get_labels(name, enckey)?;
+            for utxo in utxos.iter_mut() {
+                utxo.label = labels
+                    .get(&LabeledAddress::Transfer(utxo.output.address.clone()))
+                    .cloned();
+            }
+        }
+        Ok(utxos)
+    }
+
+    fn list_pending_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+    ) -> Result<Vec<PendingTransactionInfo>> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        self.wallet_state_service
+            .list_pending_transactions(name, enckey)
+    }
+
+    fn forget_pending_transaction(&self, name: &str, enckey: &SecKey, txid: &TxId) -> Result<()> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        self.wallet_state_service
+            .forget_pending_transaction(name, enckey, txid)
+    }
+
+    fn prune_settled_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        keep_blocks: u64,
+    ) -> Result<usize> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        let last_block_height = load_sync_state(&self.storage, name)?
+            .map_or(0, |sync_state| sync_state.last_block_height);
+        let removed = self.wallet_state_service.prune_settled_transactions(
+            name,
+            enckey,
+            last_block_height.saturating_sub(keep_blocks),
+        )?;
+        self.storage.flush()?;
+        Ok(removed)
+    }
+
+    fn has_unspent_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        inputs: &[TxoPointer],
+    ) -> Result<bool> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        self.wallet_state_service
+            .has_unspent_transactions(name, enckey, inputs)
+    }
+
+    #[inline]
+    fn are_inputs_unspent(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        inputs: Vec<TxoPointer>,
+    ) -> Result<Vec<(TxoPointer, bool)>> {
+        self.wallet_state_service
+            .are_inputs_unspent(name, enckey, inputs)
+    }
+
+    #[inline]
+    fn output(&self, name: &str, enckey: &SecKey, input: &TxoPointer) -> Result<TxOut> {
+        // Check if wallet exists
+        self.wallet_service.view_key(name, enckey)?;
+
+        self.wallet_state_service
+            .get_output(name, enckey, input)
+            .and_then(|optional| {
+                optional.chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        "Output details not found for given transaction input",
+                    )
+                })
+            })
+    }
+
+    fn create_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        coin_selection: Option<&dyn CoinSelectionStrategy>,
+        return_address: ExtendedAddr,
+    ) -> Result<(TxAux, Vec<TxoPointer>, Coin)> {
+        self.build_transfer(
+            name,
+            enckey,
+            outputs,
+            attributes,
+            coin_selection,
+            return_address.into(),
+        )
+    }
+
+    fn create_transfer_to_many(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        recipients: Vec<(ExtendedAddr, Coin)>,
+        attributes: TxAttributes,
+        merge_duplicates: bool,
+    ) -> Result<(TxAux, TransactionPending)> {
+        let outputs = recipient_outputs(recipients, merge_duplicates)?;
+
+        // change output has to be visible to current wallet
+        let attributes = self.with_wallet_view_key(name, enckey, attributes)?;
+
+        let change_address = self.change_address(name, enckey)?;
+        let (transaction, used_inputs, return_amount) =
+            self.build_transfer(name, enckey, outputs, attributes, None, change_address)?;
+
+        let current_block_height = self.get_current_block_height()?;
+        let tx_pending = TransactionPending {
+            used_inputs,
+            block_height: current_block_height,
+            return_amount,
+            tx_id: transaction.tx_id(),
+            valid_until_height: current_block_height + self.pending_tx_valid_blocks(),
+        };
+        Ok((transaction, tx_pending))
+    }
+
+    fn create_consolidation_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        max_inputs: usize,
+        destination: Option<ExtendedAddr>,
+    ) -> Result<(TxAux, TransactionPending, ConsolidationSummary)> {
+        let status = self.tendermint_client.status()?;
+        let block_time = to_timespec(status.sync_info.latest_block_time)?;
+        let current_block_height = status.sync_info.latest_block_height.value();
+
+        let unspent_transactions = self.unspent_transactions(name, enckey)?;
+        let inputs = consolidation_inputs(unspent_transactions, max_inputs, block_time)?;
+        let input_value = sum_coins(inputs.iter().map(|(_, output)| output.value)).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Total amount of consolidated UTXOs exceeds maximum allowed value",
+            )
+        })?;
+
+        let output_address = match destination {
+            Some(address) => {
+                if !self.transfer_addresses(name, enckey)?.contains(&address) {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Consolidation destination {} is not a transfer address of wallet",
+                            address
+                        ),
+                    ));
+                }
+                address
+            }
+            None => self.new_transfer_address(name, enckey)?,
+        };
+
+        let chain_hex_id = self.tendermint_client.genesis()?.chain_hex_id()?;
+        self.verify_network(name, chain_hex_id)?;
+        let attributes = TxAttributes::new_with_access(
+            chain_hex_id,
+            vec![TxAccessPolicy {
+                view_key: (&self.view_key(name, enckey)?).into(),
+                access: TxAccess::AllData,
+            }],
+        );
+
+        let (transaction, used_inputs, output_value) = self.transaction_builder.build_transfer_tx(
+            name,
+            enckey,
+            UnspentTransactions::new(inputs),
+            Vec::new(),
+            output_address.clone().into(),
+            attributes,
+            &SweepAll,
+        )?;
+        let fee = (input_value - output_value).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Consolidated output exceeds value of inputs",
+            )
+        })?;
+
+        let summary = ConsolidationSummary {
+            inputs: used_inputs.clone(),
+            fee,
+            output_address,
+            output_value,
+        };
+        let tx_pending = TransactionPending {
+            used_inputs,
+            block_height: current_block_height,
+            return_amount: output_value,
+            tx_id: transaction.tx_id(),
+            valid_until_height: current_block_height + self.pending_tx_valid_blocks(),
+        };
+        Ok((transaction, tx_pending, summary))
+    }
+
+    fn estimate_max_send(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<MaxSend> {
+        let attributes = self.with_wallet_view_key(name, enckey, attributes)?;
+        let unspent_transactions = self.spendable_unspent_transactions(name, enckey)?;
+        self.max_send(&unspent_transactions, &to_address, &attributes)
+    }
+
+    fn create_sweep_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<(TxAux, TransactionPending, MaxSend)> {
+        self.get_wallet_kind(name, enckey)?.verify_can_sign()?;
+        self.verify_network(name, attributes.chain_hex_id)?;
+        verify_memo(&attributes)?;
+
+        // sent amount is returned to wallet if it is sent to one of its own addresses
+        let returned = self.transfer_addresses(name, enckey)?.contains(&to_address);
+        let attributes = self.with_wallet_view_key(name, enckey, attributes)?;
+        let unspent_transactions = self.spendable_unspent_transactions(name, enckey)?;
+        let (transaction, used_inputs, max_send) =
+            self.build_sweep(name, enckey, unspent_transactions, to_address, attributes)?;
+
+        let current_block_height = self.get_current_block_height()?;
+        let tx_pending = TransactionPending {
+            used_inputs,
+            block_height: current_block_height,
+            return_amount: if returned {
+                max_send.amount
+            } else {
+                Coin::zero()
+            },
+            tx_id: transaction.tx_id(),
+            valid_until_height: current_block_height + self.pending_tx_valid_blocks(),
+        };
+        Ok((transaction, tx_pending, max_send))
+    }
+
+    #[inline]
+    fn broadcast_transaction(&self, tx_aux: &TxAux) -> Result<BroadcastTxResponse> {
+        self.broadcast_log_service.record_intent(tx_aux)?;
+
+        let result = self
+            .tendermint_client
+            .broadcast_transaction(&tx_aux.encode());
+
+        let outcome = match result {
+            Ok(ref response) if response.code.is_err() => BroadcastOutcome::Rejected {
+                code: response.code.value(),
+                log: response.log.as_ref().to_owned(),
+            },
+            Ok(ref response) => BroadcastOutcome::Accepted(response.hash.to_string()),
+            Err(ref e) => BroadcastOutcome::TransportError(e.to_string()),
+        };
+        self.broadcast_log_service
+            .record_outcome(&tx_aux.tx_id(), outcome)?;
+
+        result
+    }
+
+    #[inline]
+    fn query_broadcast_log(&self, txid: &TxId) -> Result<Option<BroadcastLogEntry>> {
+        self.broadcast_log_service.query(txid)
+    }
+
+    #[inline]
+    fn unresolved_broadcasts(&self) -> Result<Vec<BroadcastLogEntry>> {
+        self.broadcast_log_service.unresolved()
+    }
+
+    fn rebroadcast_pending(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        txid: &TxId,
+    ) -> Result<RebroadcastOutcome> {
+        let pending = self
+            .wallet_state_service
+            .get_pending_transaction(name, enckey, txid)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!("Transaction {} is not pending", hex::encode(txid)),
+                )
+            })?;
+        let tx_aux = self.broadcast_log_service.transaction(txid)?.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!(
+                    "Broadcasted transaction {} is not found in broadcast log",
+                    hex::encode(txid)
+                ),
+            )
+        })?;
+
+        let response = self.broadcast_transaction(&tx_aux)?;
+        let outcome = match check_broadcast_response(&response) {
+            Ok(_) => RebroadcastOutcome::Accepted,
+            Err(ref error) if broadcast_error(error) == Some(&BroadcastError::AlreadyInMempool) => {
+                RebroadcastOutcome::AlreadyInMempool
+            }
+            // rejected as its inputs (or nonce) are already used by the transaction itself
+            Err(_) if self.tendermint_client.query("meta", &txid.to_vec()).is_ok() => {
+                RebroadcastOutcome::AlreadyCommitted
+            }
+            Err(error) => return Err(error),
+        };
+
+        if outcome != RebroadcastOutcome::AlreadyCommitted {
+            let current_block_height = self.get_current_block_height()?;
+            let mut memento = WalletStateMemento::default();
+            memento.remove_pending_transaction(*txid);
+            memento.add_pending_transaction(
+                *txid,
+                TransactionPending {
+                    block_height: current_block_height,
+                    valid_until_height: current_block_height + self.pending_tx_valid_blocks(),
+                    ..pending
+                },
+            );
+            self.wallet_state_service
+                .apply_memento(name, enckey, &memento)?;
+        }
+        Ok(outcome)
+    }
+
+    fn export_plain_tx(&self, name: &str, enckey: &SecKey, txid: &str) -> Result<TransactionInfo> {
+        let txid = str2txid(txid).chain(|| (ErrorKind::InvalidInput, "invalid transaction id"))?;
+        let tx = self.get_transaction(name, enckey, txid)?;
+        // get the block height
+        let tx_change = self
+            .wallet_state_service
+            .get_transaction_history(name, enckey, false)?
+            .filter(|change| BalanceChange::NoChange != change.balance_change)
+            .find(|tx_change| tx_change.transaction_id == tx.id())
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "no transaction find by transaction id",
+                )
+            })?;
+
+        let tx_info = TransactionInfo {
+            tx,
+            block_height: tx_change.block_height,
+        };
+        Ok(tx_info)
+    }
+
+    /// import a plain base64 encoded plain transaction
+    fn import_plain_tx(&self, name: &str, enckey: &SecKey, tx_str: &str) -> Result<Coin> {
+        let tx_info = TransactionInfo::decode(tx_str)?;
+        // check if the output is spent or not
+        let v = self
+            .tendermint_client
+            .query("meta", &tx_info.tx.id().to_vec())?
+            .bytes();
+        let bit_flag = BitVec::from_bytes(&v);
+        let spent_flags: Result<Vec<bool>> = tx_info
+            .tx
+            .outputs()
+            .iter()
+            .enumerate()
+            .map(|(index, _output)| {
+                bit_flag
+                    .get(index)
+                    .chain(|| (ErrorKind::InvalidInput, "check failed in enclave"))
+            })
+            .collect();
+        let mut memento = WalletStateMemento::default();
+        // check if tx belongs to the block
+        let block = self.tendermint_client.block(tx_info.block_height)?;
+        let block_result = self.tendermint_client.block_results(tx_info.block_height)?;
+        let fees = block_result.fees()?;
+        let paid_fee = fees.get(&tx_info.tx.id());
+        if paid_fee.is_none() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "block height and transaction not match",
+            ));
+        }
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+
+        let wallet_state = self.wallet_service.get_wallet_state(name, enckey)?;
+
+        let imported_value = import_transaction(
+            &wallet,
+            &wallet_state,
+            &mut memento,
+            &tx_info,
+            *paid_fee.expect("tx fee checked above"),
+            block.header.time,
+            spent_flags?,
+        )
+        .chain(|| (ErrorKind::InvalidInput, "import error"))?;
+
+        self.wallet_state_service
+            .apply_memento(name, enckey, &memento)?;
+        Ok(imported_value)
+    }
+
+    fn get_current_block_height(&self) -> Result<u64> {
+        let status = self.tendermint_client.status()?;
+        let current_block_height = status.sync_info.latest_block_height.value();
+        Ok(current_block_height)
+    }
+
+    fn update_tx_pending_state(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tx_id: TxId,
+        tx_pending: TransactionPending,
+    ) -> Result<()> {
+        let mut wallet_state_memento = WalletStateMemento::default();
+        wallet_state_memento.add_pending_transaction(tx_id, tx_pending);
+        self.wallet_state_service
+            .apply_memento(name, enckey, &wallet_state_memento)
+    }
+
+    fn build_raw_transfer_tx(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        to_address: ExtendedAddr,
+        amount: Coin,
+        view_keys: Vec<PublicKey>,
+        network_id: u8,
+    ) -> Result<UnsignedTransferTransaction> {
+        let unspent_transactions = self.spendable_unspent_transactions(name, enckey)?;
+        // raw transaction is signed with `LargestFirst` (selecting unspent transaction of highest
+        // value first), so the first input is known in advance
+        let mut by_value = unspent_transactions.iter().collect::<Vec<_>>();
+        by_value.sort_by(|(_, a), (_, b)| a.value.cmp(&b.value).reverse());
+        let return_address = self
+            .change_address(name, enckey)?
+            .resolve(by_value.into_iter().map(|(_, output)| &output.address))
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "No unspent transaction to return change to",
+                )
+            })?;
+        let unsigned = UnsignedTransferTransaction {
+            unspent_transactions,
+            view_keys,
+            network_id,
+            to_address,
+            return_address,
+            amount,
+        };
+        Ok(unsigned)
+    }
+
+    fn sign_raw_transfer_tx(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_tx: UnsignedTransferTransaction,
+    ) -> Result<SignedTransferTransaction> {
+        self.verify_network(name, unsigned_tx.network_id)?;
+        let tx_out = TxOut::new(unsigned_tx.to_address, unsigned_tx.amount);
+        let view_key = self.view_key(name, enckey)?;
+        let mut view_keys = unsigned_tx.view_keys;
+        view_keys.push(view_key);
+        let access_policies: BTreeSet<_> = view_keys
+            .iter()
+            .map(|key| TxAccessPolicy {
+                view_key: key.into(),
+                access: TxAccess::AllData,
+            })
+            .collect();
+
+        let attributes = TxAttributes::new_with_access(
+            unsigned_tx.network_id,
+            access_policies.into_iter().collect(),
+        );
+
+        let return_address = unsigned_tx.return_address.clone();
+
+        let (transaction, selected_inputs, return_amount) =
+            self.transaction_builder.build_transfer_tx(
+                name,
+                enckey,
+                unsigned_tx.unspent_transactions,
+                vec![tx_out],
+                return_address.into(),
+                attributes,
+                &LargestFirst,
+            )?;
+        let signed_tx = SignedTransferTransaction {
+            signed_transaction: transaction,
+            used_inputs: selected_inputs,
+            return_amount,
+        };
+        Ok(signed_tx)
+    }
+
+    fn broadcast_signed_transfer_tx(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        signed_tx: SignedTransferTransaction,
+    ) -> Result<TxId> {
+        let current_block_height = self.get_current_block_height()?;
+
+        self.broadcast_transaction(&signed_tx.signed_transaction)?;
+
+        //update the wallet state
+        let tx_pending = TransactionPending {
+            used_inputs: signed_tx.used_inputs.clone(),
+            block_height: current_block_height,
+            return_amount: signed_tx.return_amount,
+            tx_id: signed_tx.signed_transaction.tx_id(),
+            valid_until_height: current_block_height + self.pending_tx_valid_blocks(),
+        };
+
+        let transaction = signed_tx.signed_transaction;
+
+        self.update_tx_pending_state(name, enckey, transaction.tx_id(), tx_pending)?;
+
+        if let TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+            payload: TxObfuscated { txid, .. },
+            ..
+        }) = transaction
+        {
+            Ok(txid)
+        } else {
+            Err(Error::new(
+                ErrorKind::IllegalInput,
+                "Transaction is not transfer transaction",
+            ))
+        }
+    }
+}
+
+impl<S, C, T> MultiSigWalletClient for DefaultWalletClient<S, C, T>
+where
+    S: Storage,
+    C: Client,
+    T: WalletTransactionBuilder,
+{
+    fn schnorr_signature(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        tx: &Transaction,
+        public_key: &PublicKey,
+    ) -> Result<SchnorrSignature> {
+        // To verify if the enckey is correct or not
+        self.transfer_addresses(name, enckey)?;
+        let wallet = self.wallet_service.get_wallet(name, enckey)?;
+        wallet.wallet_kind.verify_can_sign()?;
+        let sign_key = match wallet.wallet_kind {
+            WalletKind::HW => self.hw_key_service.get_sign_key(public_key)?,
+            _ => {
+                let private_key = self
+                    .wallet_service
+                    .find_private_key(name, enckey, public_key)?
+                    .chain(|| (ErrorKind::InvalidInput, ""))?;
+                Box::new(private_key)
+            }
+        };
+        sign_key.schnorr_sign(tx)
+    }
+
+    fn new_multi_sig_session(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        message: H256,
+        signer_public_keys: Vec<PublicKey>,
+        self_public_key: PublicKey,
+    ) -> Result<H256> {
+        // To verify if the enckey is correct or not
+        self.transfer_addresses(name, enckey)?;
+        //        let sign_key = self.sign_key(name, enckey, &self_public_key)?;
+
+        let self_private_key = self
+            .private_key(name, enckey, &self_public_key)?
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Self public key ({}) is not owned by current wallet",
+                        self_public_key
+                    ),
+                )
+            })?;
+
+        self.multi_sig_session_service.new_session(
+            message,
+            signer_public_keys,
+            self_public_key,
+            self_private_key,
+            enckey,
+        )
+    }
+
+    fn nonce_commitment(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
+        self.multi_sig_session_service
+            .nonce_commitment(session_id, enckey)
+    }
+
+    fn add_nonce_commitment(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        nonce_commitment: H256,
+        public_key: &PublicKey,
+    ) -> Result<()> {
+        self.multi_sig_session_service.add_nonce_commitment(
+            session_id,
+            nonce_commitment,
+            public_key,
+            enckey,
+        )
+    }
+
+    fn nonce(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
+        self.multi_sig_session_service.nonce(session_id, enckey)
+    }
+
+    fn add_nonce(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        nonce: &H256,
+        public_key: &PublicKey,
+    ) -> Result<()> {
+        self.multi_sig_session_service
+            .add_nonce(session_id, &nonce, public_key, enckey)
+    }
+
+    fn partial_signature(&self, session_id: &H256, enckey: &SecKey) -> Result<H256> {
+        self.multi_sig_session_service
+            .partial_signature(session_id, enckey)
+    }
+
+    fn add_partial_signature(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        partial_signature: H256,
+        public_key: &PublicKey,
+    ) -> Result<()> {
+        self.multi_sig_session_service.add_partial_signature(
+            session_id,
+            partial_signature,
+            public_key,
+            enckey,
+        )
+    }
+
+    fn signature(&self, session_id: &H256, enckey: &SecKey) -> Result<SchnorrSignature> {
+        self.multi_sig_session_service.signature(session_id, enckey)
+    }
+
+    fn transaction(
+        &self,
+        name: &str,
+        session_id: &H256,
+        enckey: &SecKey,
+        unsigned_transaction: Tx,
+    ) -> Result<TxAux> {
+        if unsigned_transaction.inputs.len() != 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Multi-Sig Signing is only supported for transactions with only one input",
+            ));
+        }
+
+        let output_to_spend = self.output(name, enckey, &unsigned_transaction.inputs[0])?;
+        let root_hash = self
+            .wallet_service
+            .find_root_hash(name, enckey, &output_to_spend.address)?
+            .chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Output address is not owned by current wallet; cannot spend output in given transaction",
+                )
+            })?;
+        let public_keys = self
+            .multi_sig_session_service
+            .public_keys(session_id, enckey)?;
+
+        let proof = self
+            .root_hash_service
+            .generate_proof(name, &root_hash, public_keys, enckey)?;
+        let signature = self.signature(session_id, enckey)?;
+
+        let witness = TxWitness::from(vec![TxInWitness::TreeSig(signature, proof)]);
+        let signed_transaction =
+            SignedTransaction::TransferTransaction(unsigned_transaction, witness);
+
+        self.transaction_builder.obfuscate(signed_transaction)
+    }
+
+    fn new_multisig_address(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        cosigner_pubkeys: Vec<PublicKey>,
+        threshold: usize,
+    ) -> Result<ExtendedAddr> {
+        let self_public_key = self.owned_public_key(name, enckey, &cosigner_pubkeys)?;
+        self.new_multisig_transfer_address(
+            name,
+            enckey,
+            cosigner_pubkeys,
+            self_public_key,
+            threshold,
+        )
+    }
+
+    fn start_multisig_transfer(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned_transaction: Tx,
+        signer_public_keys: Vec<PublicKey>,
+    ) -> Result<(H256, H256)> {
+        if unsigned_transaction.inputs.len() != 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Multi-Sig Signing is only supported for transactions with only one input",
+            ));
+        }
+
+        let self_public_key = self.owned_public_key(name, enckey, &signer_public_keys)?;
+        let session_id = self.new_multi_sig_session(
+            name,
+            enckey,
+            unsigned_transaction.id(),
+            signer_public_keys,
+            self_public_key,
+        )?;
+        self.multi_sig_session_service.set_transaction(
+            &session_id,
+            &unsigned_transaction,
+            enckey,
+        )?;
+
+        let nonce_commitment = self.nonce_commitment(&session_id, enckey)?;
+        Ok((session_id, nonce_commitment))
+    }
+
+    fn add_cosigner_commitment(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+        nonce_commitment: H256,
+    ) -> Result<Option<H256>> {
+        self.add_nonce_commitment(session_id, enckey, nonce_commitment, public_key)?;
+
+        if self
+            .multi_sig_session_service
+            .missing_nonce_commitments(session_id, enckey)?
+            .is_empty()
+        {
+            self.nonce(session_id, enckey).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn add_cosigner_nonce(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+        nonce: H256,
+    ) -> Result<Option<H256>> {
+        self.add_nonce(session_id, enckey, &nonce, public_key)?;
+
+        if self
+            .multi_sig_session_service
+            .missing_nonces(session_id, enckey)?
+            .is_empty()
+        {
+            self.partial_signature(session_id, enckey).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn add_cosigner_signature(
+        &self,
+        session_id: &H256,
+        enckey: &SecKey,
+        public_key: &PublicKey,
+        partial_signature: H256,
+    ) -> Result<bool> {
+        self.add_partial_signature(session_id, enckey, partial_signature, public_key)?;
+
+        Ok(self
+            .multi_sig_session_service
+            .missing_partial_signatures(session_id, enckey)?
+            .is_empty())
+    }
+
+    fn finalize_multisig_transfer(
+        &self,
+        name: &str,
+        session_id: &H256,
+        enckey: &SecKey,
+    ) -> Result<TxAux> {
+        let missing = self
+            .multi_sig_session_service
+            .missing_partial_signatures(session_id, enckey)?;
+        if !missing.is_empty() {
+            return Err(Error::new(
+                ErrorKind::MultiSigError,
+                format!(
+                    "Missing partial signatures of cosigners: {}",
+                    missing
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ),
+            ));
+        }
+
+        let unsigned_transaction = self
+            .multi_sig_session_service
+            .transaction(session_id, enckey)?;
+        let output_to_spend = self.output(name, enckey, &unsigned_transaction.inputs[0])?;
+        if let Some(root_hash) =
+            self.wallet_service
+                .find_root_hash(name, enckey, &output_to_spend.address)?
+        {
+            let required_signers = self
+                .root_hash_service
+                .required_signers(name, &root_hash, enckey)?;
+            let signers = self
+                .multi_sig_session_service
+                .public_keys(session_id, enckey)?
+                .len();
+            if signers != required_signers {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Multi-sig address requires {} signers, but session is signed by {}",
+                        required_signers, signers
+                    ),
+                ));
+            }
+        }
+
+        self.transaction(name, session_id, enckey, unsigned_transaction)
+    }
+
+    fn export_psct(
+        &self,
+        name: &str,
+        session_id: &H256,
+        enckey: &SecKey,
+    ) -> Result<PartiallySignedTransfer> {
+        let transaction = self
+            .multi_sig_session_service
+            .transaction(session_id, enckey)?;
+        let public_keys = self
+            .multi_sig_session_service
+            .public_keys(session_id, enckey)?;
+        let cosigners = self
+            .multi_sig_session_service
+            .cosigner_contributions(session_id, enckey)?;
+        let signature = self
+            .multi_sig_session_service
+            .signature(session_id, enckey)
+            .ok();
+
+        let mut inputs = Vec::with_capacity(transaction.inputs.len());
+        let mut policy = None;
+        for input in transaction.inputs.iter() {
+            let output = self.output(name, enckey, input)?;
+            let root_hash = self
+                .wallet_service
+                .find_root_hash(name, enckey, &output.address)?
+                .chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Output address ({}) is not a multi-sig address of wallet",
+                            output.address
+                        ),
+                    )
+                })?;
+            let proof = self.root_hash_service.generate_proof(
+                name,
+                &root_hash,
+                public_keys.clone(),
+                enckey,
+            )?;
+            policy = Some((
+                self.root_hash_service
+                    .required_signers(name, &root_hash, enckey)?,
+                self.root_hash_service
+                    .total_signers(name, &root_hash, enckey)?,
+            ));
+            let witness = signature
+                .clone()
+                .map(|signature| TxInWitness::TreeSig(signature, proof.clone()));
+
+            inputs.push(PartiallySignedInput {
+                output,
+                proof,
+                witness,
+            });
+        }
+
+        let (required_signers, total_signers) = policy.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                "Transaction of multi-sig session has no inputs",
+            )
+        })?;
+
+        let psct = PartiallySignedTransfer {
+            transaction,
+            inputs,
+            required_signers: required_signers as u16,
+            total_signers: total_signers as u16,
+            cosigners,
+        };
+        psct.validate()?;
+        Ok(psct)
+    }
+}
+
+fn check_passphrase_strength(name: &str, passphrase: &SecUtf8) -> Result<()> {
+    // `estimate_password_strength` returns a score between `0-4`. Any score less than 3 should be considered too
+    // weak.
+    let password_entropy = estimate_password_strength(passphrase.unsecure(), &[name])
+        .chain(|| (ErrorKind::IllegalInput, "Blank passphrase"))?;
+
+    #[cfg(debug_assertions)]
+    let entropy_score = 0;
+    #[cfg(not(debug_assertions))]
+    let entropy_score = 3;
+
+    if password_entropy.score() < entropy_score {
+        return Err(Error::new(
+            ErrorKind::IllegalInput,
+            format!(
+                "Weak passphrase: {}",
+                parse_feedback(password_entropy.feedback().as_ref())
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Error returned when a new key pair is requested from a watch-only wallet
+fn watch_only_new_key_error() -> Error {
+    Error::new(
+        ErrorKind::PermissionDenied,
+        "Watch-only wallet can not generate new keys (add watch addresses instead)",
+    )
+}
+
+fn parse_feedback(feedback: Option<&Feedback>) -> String {
+    match feedback {
+        None => "No feedback available!".to_string(),
+        Some(feedback) => {
+            let mut feedbacks = Vec::new();
+
+            if let Some(warning) = feedback.warning() {
+                feedbacks.push(format!("Warning: {}", warning));
+            }
+
+            for suggestion in feedback.suggestions() {
+                feedbacks.push(format!("Suggestion: {}", suggestion));
+            }
+
+            if feedbacks.is_empty() {
+                feedbacks.push("No feedback available!".to_string());
+            }
+
+            feedbacks.join(" | ")
+        }
+    }
+}
+
+fn import_transaction(
+    wallet: &Wallet,
+    wallet_state: &WalletState,
+    memento: &mut WalletStateMemento,
+    transaction_info: &TransactionInfo,
+    paid_fee: Fee,
+    block_time: Time,
+    spent_flag: Vec<bool>,
+) -> Result<Coin> {
+    let transaction_change = create_transaction_change(
+        wallet,
+        wallet_state,
+        &transaction_info.tx,
+        paid_fee,
+        transaction_info.block_height,
+        block_time,
+    )
+    .chain(|| (ErrorKind::InvalidInput, "create transaction change failed"))?;
+    let mut value = Coin::zero();
+    let transfer_addresses = wallet.transfer_addresses();
+    for (i, (output, spent)) in transaction_change
+        .outputs
+        .iter()
+        .zip(spent_flag)
+        .enumerate()
+    {
+        // Only add unspent transaction if output address belongs to current wallet
+        if transfer_addresses.contains(&output.address) && !spent {
+            memento.add_unspent_transaction(
+                TxoPointer::new(transaction_change.transaction_id, i),
+                output.clone(),
+            );
+            value = (value + output.value).chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "invalid coin in outputs of transaction",
+                )
+            })?;
+        }
+    }
+    memento.add_transaction_change(transaction_change);
+    Ok(value)
+}
+
+/// Returns labels of transfer addresses appearing in inputs or outputs of given transactions
+fn history_labels(
+    records: &[TransactionChange],
+    labels: &BTreeMap<LabeledAddress, String>,
+) -> Vec<(ExtendedAddr, String)> {
+    let addresses = records
+        .iter()
+        .flat_map(|record| {
+            record
+                .inputs
+                .iter()
+                .filter_map(|input| input.output.as_ref())
+                .chain(record.outputs.iter())
+                .map(|output| &output.address)
+        })
+        .collect::<IndexSet<_>>();
+
+    addresses
+        .into_iter()
+        .filter_map(|address| {
+            labels
+                .get(&LabeledAddress::Transfer(address.clone()))
+                .map(|label| (address.clone(), label.clone()))
+        })
+        .collect()
+}
+
+/// Converts tendermint time to unix timestamp (in seconds)
+fn to_timespec(time: Time) -> Result<Timespec> {
+    time.duration_since(Time::unix_epoch())
+        .map(|duration| duration.as_secs())
+        .chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!("Block time {} is before unix epoch", time.to_rfc3339()),
+            )
+        })
+}
+
+/// Returns up to `max_inputs` smallest unspent transactions whose timelock has passed at given
+/// block time (in seconds since unix epoch)
+fn consolidation_inputs(
+    unspent_transactions: UnspentTransactions,
+    max_inputs: usize,
+    block_time: Timespec,
+) -> Result<Vec<(TxoPointer, TxOut)>> {
+    let mut inputs = unspent_transactions
+        .iter()
+        .filter(|(_, output)| match output.valid_from {
+            Some(valid_from) => valid_from <= block_time,
+            None => true,
+        })
+        .cloned()
+        .collect::<Vec<_>>();
+    inputs.sort_by_key(|(_, output)| output.value);
+    inputs.truncate(max_inputs);
+
+    if inputs.len() < 2 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Consolidation needs at least two spendable unspent transactions, found {}",
+                inputs.len()
+            ),
+        ));
+    }
+    Ok(inputs)
+}
+
+/// Checks that memo in transaction attributes is valid UTF-8 not longer than `MAX_TX_MEMO_LENGTH`
+/// (in attributes of app version supporting memo)
+fn verify_memo(attributes: &TxAttributes) -> Result<()> {
+    let memo = match attributes.memo {
+        Some(ref memo) => memo,
+        None => return Ok(()),
+    };
+    if attributes.app_version < MEMO_APP_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Memo requires app version {} of transaction attributes (got {})",
+                MEMO_APP_VERSION, attributes.app_version
+            ),
+        ));
+    }
+    if memo.len() > MAX_TX_MEMO_LENGTH {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Memo of {} bytes exceeds maximum length of {} bytes",
+                memo.len(),
+                MAX_TX_MEMO_LENGTH
+            ),
+        ));
+    }
+    std::str::from_utf8(memo).chain(|| (ErrorKind::InvalidInput, "Memo is not valid UTF-8"))?;
+    Ok(())
+}
+
+/// Converts recipients of a transfer to outputs, merging or rejecting duplicate recipients
+fn recipient_outputs(
+    recipients: Vec<(ExtendedAddr, Coin)>,
+    merge_duplicates: bool,
+) -> Result<Vec<TxOut>> {
+    if recipients.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Transfer has to have at least one recipient",
+        ));
+    }
+
+    let mut outputs: Vec<TxOut> = Vec::with_capacity(recipients.len());
+    for (address, value) in recipients {
+        if value == Coin::zero() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Transfer to {} has zero value", address),
+            ));
+        }
+
+        match outputs.iter_mut().find(|output| output.address == address) {
+            None => outputs.push(TxOut::new(address, value)),
+            Some(output) if merge_duplicates => {
+                output.value = (output.value + value).chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Total transfer to {} exceeds maximum allowed value",
+                            address
+                        ),
+                    )
+                })?;
+            }
+            Some(_) => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Recipient {} appears more than once in transfer", address),
+                ))
+            }
+        }
+    }
+
+    Ok(outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Mnemonic;
+    use client_common::storage::{MemoryStorage, TestStorage};
+
+    #[test]
+    fn check_recipient_outputs() {
+        let first = ExtendedAddr::OrTree([1; 32]);
+        let second = ExtendedAddr::OrTree([2; 32]);
+        let recipients = vec![
+            (first.clone(), Coin::new(10).unwrap()),
+            (second.clone(), Coin::new(20).unwrap()),
+            (first.clone(), Coin::new(5).unwrap()),
+        ];
+
+        assert_eq!(
+            vec![
+                TxOut::new(first.clone(), Coin::new(15).unwrap()),
+                TxOut::new(second.clone(), Coin::new(20).unwrap()),
+            ],
+            recipient_outputs(recipients.clone(), true).unwrap()
+        );
+
+        let error = recipient_outputs(recipients, false).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!(
+            format!("Recipient {} appears more than once in transfer", first),
+            error.message()
+        );
+
+        let error = recipient_outputs(vec![(second.clone(), Coin::zero())], true).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!(
+            format!("Transfer to {} has zero value", second),
+            error.message()
+        );
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            recipient_outputs(vec![], true).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn check_consolidation_inputs() {
+        let output = |index: u8, value: u64, valid_from: Option<u64>| {
+            let mut output = TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(value).unwrap());
+            output.valid_from = valid_from;
+            (TxoPointer::new([index; 32], 0), output)
+        };
+        let unspent_transactions = UnspentTransactions::new(vec![
+            output(0, 500, None),
+            output(1, 3, None),
+            output(2, 1, Some(2000)),
+            output(3, 70, Some(1000)),
+            output(4, 20, None),
+            output(5, 10_000, None),
+        ]);
+
+        let inputs = consolidation_inputs(unspent_transactions.clone(), 4, 1500).unwrap();
+        let values = inputs
+            .iter()
+            .map(|(_, output)| u64::from(output.value))
+            .collect::<Vec<_>>();
+        assert_eq!(vec![3, 20, 70, 500], values);
+
+        let inputs = consolidation_inputs(unspent_transactions.clone(), 10, 2000).unwrap();
+        assert_eq!(6, inputs.len());
+        assert_eq!(TxoPointer::new([2; 32], 0), inputs[0].0);
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            consolidation_inputs(unspent_transactions, 1, 1500)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_create_transaction_memo() {
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let (enckey, _) = client
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+                None,
+            )
+            .unwrap();
+        let address = client.new_transfer_address("name", &enckey).unwrap();
+        let outputs = vec![TxOut::new(address.clone(), Coin::new(10).unwrap())];
+
+        let invalid_memos = vec![vec![b'a'; MAX_TX_MEMO_LENGTH + 1], vec![0xff, 0xfe]];
+        for memo in invalid_memos {
+            let attributes = TxAttributes::new(0xab).with_memo(memo);
+            let error = client
+                .create_transaction(
+                    "name",
+                    &enckey,
+                    outputs.clone(),
+                    attributes,
+                    None,
+                    address.clone(),
+                )
+                .unwrap_err();
+            assert_eq!(ErrorKind::InvalidInput, error.kind());
+        }
+
+        let memo = "deposit tag: 42 ✓".as_bytes().to_vec();
+        assert!(verify_memo(&TxAttributes::new(0xab).with_memo(memo)).is_ok());
+        assert!(verify_memo(&TxAttributes::new(0xab)).is_ok());
+
+        let attributes = TxAttributes {
+            memo: Some(b"deposit tag".to_vec()),
+            ..TxAttributes::new(0xab)
+        };
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            verify_memo(&attributes).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn check_address_labels() {
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let passphrase = SecUtf8::from("passphrase");
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let transfer_address = client.new_transfer_address("name", &enckey).unwrap();
+        let staking_address = client.new_staking_address("name", &enckey).unwrap();
+
+        client
+            .set_address_label(
+                "name",
+                &enckey,
+                transfer_address.clone().into(),
+                "cold storage",
+            )
+            .unwrap();
+        client
+            .set_address_label("name", &enckey, staking_address.into(), "validator #2")
+            .unwrap();
+        assert_eq!(
+            Some("validator #2".to_owned()),
+            client
+                .get_address_label("name", &enckey, &staking_address.into())
+                .unwrap()
+        );
+        assert!(client
+            .set_address_label("unknown", &enckey, staking_address.into(), "label")
+            .is_err());
+
+        // labels survive export and import of wallet
+        let wallet_info = client.export_wallet("name", &enckey).unwrap();
+        assert_eq!(2, wallet_info.labels.len());
+        let token = client.delete_wallet("name", &passphrase, None).unwrap();
+        client
+            .delete_wallet("name", &passphrase, token.as_deref())
+            .unwrap();
+        let enckey = client
+            .import_wallet("name", &SecUtf8::from("new passphrase"), wallet_info)
+            .unwrap();
+        let labels = client.list_labeled_addresses("name", &enckey).unwrap();
+        assert!(labels.contains(&(transfer_address.clone().into(), "cold storage".to_owned())));
+        assert!(labels.contains(&(staking_address.into(), "validator #2".to_owned())));
+
+        // labels are joined into history
+        let other_address = ExtendedAddr::OrTree([0; 32]);
+        let record = TransactionChange {
+            transaction_id: [0; 32],
+            inputs: Vec::new(),
+            outputs: vec![
+                TxOut::new(other_address, Coin::new(10).unwrap()),
+                TxOut::new(transfer_address.clone(), Coin::new(20).unwrap()),
+            ],
+            fee_paid: Fee::new(Coin::zero()),
+            balance_change: BalanceChange::Incoming {
+                value: Coin::new(20).unwrap(),
+            },
+            transaction_type: crate::types::TransactionType::Transfer,
+            block_height: 1,
+            block_time: Time::unix_epoch(),
+            memo: None,
+            depositor: None,
+        };
+        let labels = labels.into_iter().collect::<BTreeMap<_, _>>();
+        assert_eq!(
+            vec![(transfer_address, "cold storage".to_owned())],
+            history_labels(&[record], &labels)
+        );
+    }
+
+    #[test]
+    fn check_delete_wallet() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+        let wrong_passphrase = SecUtf8::from("123457");
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let enckey = client
+            .restore_wallet("Default", &passphrase, &words, None)
+            .expect("restore wallet");
+        client.new_transfer_address("Default", &enckey).unwrap();
+        let staking_address = client.new_staking_address("Default", &enckey).unwrap();
+        client
+            .set_address_label("Default", &enckey, staking_address.into(), "validator")
+            .unwrap();
+        save_sync_state(&storage, "Default", &SyncState::genesis(Vec::new())).unwrap();
+        // FIXME this failure will leave storage in an inconsistant state
+        // assert!(client.restore_wallet("test", &passphrase, &words).is_err());
+        assert!(client
+            .delete_wallet("Default", &wrong_passphrase, None)
+            .is_err());
+        assert!(client.delete_wallet("Default1", &passphrase, None).is_err());
+
+        // deletion has to be confirmed with issued token
+        let token = client
+            .delete_wallet("Default", &passphrase, None)
+            .unwrap()
+            .expect("deletion token");
+        assert!(client
+            .delete_wallet("Default", &passphrase, Some("invalid token"))
+            .is_err());
+        assert!(client.auth_token("Default", &passphrase).is_ok());
+        assert_eq!(
+            None,
+            client
+                .delete_wallet("Default", &passphrase, Some(&token))
+                .expect("delete wallet")
+        );
+
+        assert!(client.wallets().unwrap().is_empty());
+        for keyspace in storage.keyspaces().unwrap() {
+            assert!(
+                storage.keys(&keyspace).unwrap().is_empty(),
+                "records remain in keyspace {}",
+                String::from_utf8_lossy(&keyspace)
+            );
+        }
+
+        client
+            .restore_wallet("test", &passphrase, &words, None)
+            .expect("restore wallet");
+    }
+
+    #[test]
+    fn check_restore_wallet_twice() {
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let name1 = "Default1";
+        let name2 = "Default2";
+        let passphrase = SecUtf8::from("123456");
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let enckey1 = client
+            .restore_wallet(name1, &passphrase, &words, None)
+            .expect("restore wallet 1 failed");
+        let enckey2 = client
+            .restore_wallet(name2, &passphrase, &words, None)
+            .expect("restore wallet 2 failed");
+        let transfer_address_1 = client
+            .new_transfer_address(name1, &enckey1)
+            .expect("create transfer address 1 failed");
+        let transfer_address_2 = client
+            .new_transfer_address(name2, &enckey2)
+            .expect("create transfer address 2 failed");
+        assert_eq!(transfer_address_1, transfer_address_2);
+        let staking_address_1 = client
+            .new_staking_address(name1, &enckey1)
+            .expect("create staking address 1 failed");
+        let staking_address_2 = client
+            .new_staking_address(name2, &enckey2)
+            .expect("create staking address 2 failed");
+        assert_eq!(staking_address_1, staking_address_2);
+        let transfer_address_22 = client.new_transfer_address(name2, &enckey2).unwrap();
+        assert_ne!(transfer_address_2, transfer_address_22);
+        let transfer_addresses = client.transfer_addresses(name2, &enckey2).unwrap();
+        assert_eq!(transfer_addresses.len(), 2);
+    }
+
+    #[test]
+    fn check_change_passphrase() {
+        let storage = TestStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let old_passphrase = SecUtf8::from("old passphrase");
+        let new_passphrase = SecUtf8::from("new passphrase");
+
+        let (enckey, _) = client
+            .new_wallet("name", &old_passphrase, WalletKind::HD, None, None)
+            .unwrap();
+        let transfer_address = client.new_transfer_address("name", &enckey).unwrap();
+        let staking_address = client.new_staking_address("name", &enckey).unwrap();
+        client
+            .set_address_label("name", &enckey, staking_address.into(), "validator")
+            .unwrap();
+
+        assert!(client
+            .change_passphrase("name", &SecUtf8::from("wrong passphrase"), &new_passphrase)
+            .is_err());
+
+        // process dies before re-encrypted records are staged
+        storage.fail_after(Some(0));
+        assert!(client
+            .change_passphrase("name", &old_passphrase, &new_passphrase)
+            .is_err());
+        storage.fail_after(None);
+
+        let enckey = client.auth_token("name", &old_passphrase).unwrap();
+        assert!(client.auth_token("name", &new_passphrase).is_err());
+        assert!(client
+            .transfer_addresses("name", &enckey)
+            .unwrap()
+            .contains(&transfer_address));
+
+        let new_enckey = client
+            .change_passphrase("name", &old_passphrase, &new_passphrase)
+            .unwrap();
+        assert!(client.auth_token("name", &old_passphrase).is_err());
+        assert!(client.view_key_private("name", &enckey).is_err());
+
+        assert_eq!(
+            new_enckey.unsecure(),
+            client
+                .auth_token("name", &new_passphrase)
+                .unwrap()
+                .unsecure()
+        );
+        assert!(client
+            .transfer_addresses("name", &new_enckey)
+            .unwrap()
+            .contains(&transfer_address));
+        assert!(client.view_key_private("name", &new_enckey).is_ok());
+        assert_eq!(
+            vec![(staking_address.into(), "validator".to_owned())],
+            client.list_labeled_addresses("name", &new_enckey).unwrap()
+        );
+        // HD seed is re-encrypted as well
+        assert!(client.new_transfer_address("name", &new_enckey).is_ok());
+    }
+
+    #[test]
+    fn check_wallet_sessions() {
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let passphrase = SecUtf8::from("old passphrase");
+        let ttl = Duration::from_secs(60);
+
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::HD, None, None)
+            .unwrap();
+        assert!(client
+            .unlock_wallet("name", &SecUtf8::from("wrong passphrase"), ttl)
+            .is_err());
+
+        let token = client.unlock_wallet("name", &passphrase, ttl).unwrap();
+        let (name, session_enckey) = client.unlocked_wallet(&token).unwrap();
+        assert_eq!("name", name);
+        assert_eq!(enckey, session_enckey);
+
+        // clones of client share sessions
+        let address = client.new_transfer_address_with_session(&token).unwrap();
+        let handles = (0..2)
+            .map(|_| {
+                let client = client.clone();
+                let token = token.clone();
+                std::thread::spawn(move || {
+                    (
+                        client.transfer_addresses_with_session(&token).unwrap(),
+                        client.balance_with_session(&token).unwrap(),
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            let (addresses, balance) = handle.join().unwrap();
+            assert!(addresses.contains(&address));
+            assert_eq!(WalletBalance::default(), balance);
+        }
+
+        client.lock_wallet(&token).unwrap();
+        assert_eq!(
+            ErrorKind::InvalidSession,
+            client.balance_with_session(&token).unwrap_err().kind()
+        );
+        assert!(client.lock_wallet(&token).is_err());
+
+        // changing passphrase locks all sessions of wallet
+        let token = client.unlock_wallet("name", &passphrase, ttl).unwrap();
+        client
+            .change_passphrase("name", &passphrase, &SecUtf8::from("new passphrase"))
+            .unwrap();
+        assert_eq!(
+            ErrorKind::InvalidSession,
+            client.unlocked_wallet(&token).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn check_wallet_network() {
+        let storage = MemoryStorage::default();
+        let network = NetworkInfo::from_chain_id("test-chain-y3m1e6-AB").unwrap();
+        let client =
+            DefaultWalletClient::new_read_only(storage.clone()).with_network(network.clone());
+        let passphrase = SecUtf8::from("passphrase");
+
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        assert_eq!(Some(network), client.wallet_network("name").unwrap());
+        assert!(client.wallet_network("other").is_err());
+
+        let return_address = client.new_transfer_address("name", &enckey).unwrap();
+        let error = client
+            .create_transaction(
+                "name",
+                &enckey,
+                Vec::new(),
+                TxAttributes::new(0x42),
+                None,
+                return_address.clone(),
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::ValidationError, error.kind());
+
+        let client =
+            DefaultWalletClient::new_read_only(storage).with_network_mismatch_allowed(true);
+        let error = client
+            .create_transaction(
+                "name",
+                &enckey,
+                Vec::new(),
+                TxAttributes::new(0x42),
+                None,
+                return_address,
+            )
+            .unwrap_err();
+        // read-only client can not build transactions
+        assert_eq!(ErrorKind::PermissionDenied, error.kind());
+
+        // wallets created without network are not associated with any
+        client
+            .new_wallet("other", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        assert_eq!(None, client.wallet_network("other").unwrap());
+    }
+
+    #[test]
+    fn check_wallet_backup() {
+        let passphrase = SecUtf8::from("passphrase");
+        let backup_passphrase = SecUtf8::from("backup passphrase");
+
+        for wallet_kind in [WalletKind::Basic, WalletKind::HD].iter().copied() {
+            let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+            let (enckey, _) = client
+                .new_wallet("name", &passphrase, wallet_kind, None, None)
+                .unwrap();
+            let transfer_address = client.new_transfer_address("name", &enckey).unwrap();
+            client.new_staking_address("name", &enckey).unwrap();
+            let public_key = client.new_public_key("name", &enckey, None).unwrap();
+            let multisig_address = client
+                .new_multisig_transfer_address(
+                    "name",
+                    &enckey,
+                    vec![
+                        public_key.clone(),
+                        PublicKey::from(&PrivateKey::new().unwrap()),
+                    ],
+                    public_key,
+                    1,
+                )
+                .unwrap();
+            client
+                .set_address_label("name", &enckey, transfer_address.into(), "cold storage")
+                .unwrap();
+
+            assert!(client
+                .export_wallet_backup("name", &SecUtf8::from("wrong"), &backup_passphrase)
+                .is_err());
+            let backup = client
+                .export_wallet_backup("name", &passphrase, &backup_passphrase)
+                .unwrap();
+
+            let restored = DefaultWalletClient::new_read_only(MemoryStorage::default());
+            let error = restored
+                .import_wallet_backup(
+                    "restored",
+                    &passphrase,
+                    &SecUtf8::from("wrong"),
+                    &backup,
+                    false,
+                )
+                .unwrap_err();
+            assert_eq!(ErrorKind::DecryptionError, error.kind());
+            assert!(restored.wallets().unwrap().is_empty());
+
+            let new_enckey = restored
+                .import_wallet_backup("restored", &passphrase, &backup_passphrase, &backup, false)
+                .unwrap();
+
+            let check_restored = |new_enckey: &SecKey| {
+                assert_eq!(
+                    client.view_key_private("name", &enckey).unwrap(),
+                    restored.view_key_private("restored", new_enckey).unwrap()
+                );
+                assert_eq!(
+                    client.transfer_addresses("name", &enckey).unwrap(),
+                    restored.transfer_addresses("restored", new_enckey).unwrap()
+                );
+                assert_eq!(
+                    client.staking_addresses("name", &enckey).unwrap(),
+                    restored.staking_addresses("restored", new_enckey).unwrap()
+                );
+                assert_eq!(
+                    client.list_labeled_addresses("name", &enckey).unwrap(),
+                    restored
+                        .list_labeled_addresses("restored", new_enckey)
+                        .unwrap()
+                );
+
+                // keys of all addresses can still sign
+                let public_keys = client.public_keys("name", &enckey).unwrap();
+                let staking_keys = client.staking_keys("name", &enckey).unwrap();
+                for public_key in public_keys.iter().chain(staking_keys.iter()) {
+                    let sign_key = restored
+                        .sign_key("restored", new_enckey, public_key)
+                        .unwrap();
+                    assert_eq!(public_key, &sign_key.public_key().unwrap());
+                }
+                if let ExtendedAddr::OrTree(ref root_hash) = multisig_address {
+                    assert_eq!(
+                        1,
+                        restored
+                            .required_cosigners("restored", new_enckey, root_hash)
+                            .unwrap()
+                    );
+                }
+            };
+            check_restored(&new_enckey);
+
+            // existing wallet is only overwritten if forced
+            assert!(restored
+                .import_wallet_backup("restored", &passphrase, &backup_passphrase, &backup, false)
+                .is_err());
+            let new_enckey = restored
+                .import_wallet_backup("restored", &passphrase, &backup_passphrase, &backup, true)
+                .unwrap();
+            check_restored(&new_enckey);
+
+            if wallet_kind == WalletKind::HD {
+                // indices of HD key are restored as well
+                assert_eq!(
+                    client.new_transfer_address("name", &enckey).unwrap(),
+                    restored
+                        .new_transfer_address("restored", &new_enckey)
+                        .unwrap()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn check_new_addresses_in_batch() {
+        let passphrase = SecUtf8::from("passphrase");
+        let storage = TestStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::HD, None, None)
+            .unwrap();
+        storage.take_writes();
+
+        let transfer_addresses = client.new_transfer_addresses("name", &enckey, 500).unwrap();
+        assert_eq!(1, storage.take_writes());
+        let staking_addresses = client.new_staking_addresses("name", &enckey, 500).unwrap();
+        assert_eq!(1, storage.take_writes());
+
+        assert_eq!(
+            transfer_addresses.into_iter().collect::<IndexSet<_>>(),
+            client.transfer_addresses("name", &enckey).unwrap()
+        );
+        assert_eq!(
+            staking_addresses.into_iter().collect::<IndexSet<_>>(),
+            client.staking_addresses("name", &enckey).unwrap()
+        );
+        assert_eq!(
+            500,
+            client.transfer_addresses("name", &enckey).unwrap().len()
+        );
+        // HD index is advanced past all generated addresses
+        let next_address = client.new_staking_address("name", &enckey).unwrap();
+        assert_eq!(
+            Some(500),
+            client
+                .address_index("name", &enckey, &next_address.into())
+                .unwrap()
+        );
+
+        // failed batch leaves nothing behind
+        let storage = TestStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::HD, None, None)
+            .unwrap();
+        storage.fail_after(Some(0));
+        assert!(client.new_transfer_addresses("name", &enckey, 10).is_err());
+        storage.fail_after(None);
+        assert!(client
+            .transfer_addresses("name", &enckey)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            0,
+            client
+                .hd_key_service
+                .next_index("name", &enckey, HDAccountType::Transfer)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn check_address_at_index() {
+        let passphrase = SecUtf8::from("passphrase");
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+
+        let addresses = (0..2)
+            .map(|_| {
+                let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+                let enckey = client
+                    .restore_wallet("name", &passphrase, &words, None)
+                    .unwrap();
+
+                let staking_address = client.staking_address_at_index("name", &enckey, 3).unwrap();
+                let transfer_address = client
+                    .transfer_address_at_index("name", &enckey, 3)
+                    .unwrap();
+
+                // existing addresses are not added again
+                assert_eq!(
+                    staking_address,
+                    client.staking_address_at_index("name", &enckey, 3).unwrap()
+                );
+                assert_eq!(
+                    transfer_address,
+                    client
+                        .transfer_address_at_index("name", &enckey, 3)
+                        .unwrap()
+                );
+                assert_eq!(1, client.staking_addresses("name", &enckey).unwrap().len());
+                assert_eq!(1, client.transfer_addresses("name", &enckey).unwrap().len());
+
+                assert_eq!(
+                    Some(3),
+                    client
+                        .address_index("name", &enckey, &staking_address.into())
+                        .unwrap()
+                );
+                assert_eq!(
+                    Some(3),
+                    client
+                        .address_index("name", &enckey, &transfer_address.clone().into())
+                        .unwrap()
+                );
+
+                // generated addresses continue after the index
+                let next_address = client.new_staking_address("name", &enckey).unwrap();
+                assert_eq!(
+                    Some(4),
+                    client
+                        .address_index("name", &enckey, &next_address.into())
+                        .unwrap()
+                );
+                let foreign_address = StakedStateAddress::BasicRedeem(RedeemAddress::from(
+                    &PublicKey::from(&PrivateKey::new().unwrap()),
+                ));
+                assert_eq!(
+                    None,
+                    client
+                        .address_index("name", &enckey, &foreign_address.into())
+                        .unwrap()
+                );
+
+                (staking_address, transfer_address)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(addresses[0], addresses[1]);
+
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let (enckey, _) = client
+            .new_wallet("basic", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = client.new_staking_address("basic", &enckey).unwrap();
+        assert!(client
+            .staking_address_at_index("basic", &enckey, 0)
+            .is_err());
+        assert!(client
+            .transfer_address_at_index("basic", &enckey, 0)
+            .is_err());
+        assert!(client
+            .address_index("basic", &enckey, &address.into())
+            .is_err());
+    }
+
+    #[test]
+    fn check_import_private_key() {
+        use chain_core::state::account::{StakedStateOpAttributes, StakedStateOpWitness, UnbondTx};
+        use chain_tx_validation::witness::verify_tx_recover_address;
+
+        let key_bytes =
+            hex::decode("c553a03604235df8fe2d9f2a5b9ba87a2c1bbe6e9f3f7ad71c4e7d8c9d0b1a2f")
+                .unwrap();
+        let public_key = PublicKey::from(&PrivateKey::deserialize_from(&key_bytes).unwrap());
+        let staking_address = StakedStateAddress::BasicRedeem(RedeemAddress::from(&public_key));
+        let transfer_address: ExtendedAddr =
+            MultiSigAddress::new(vec![public_key.clone()], public_key.clone(), 1)
+                .unwrap()
+                .into();
+
+        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let (enckey, _) = client
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::HD,
+                None,
+                None,
+            )
+            .unwrap();
+
+        // importing the same key twice is idempotent
+        for _ in 0..2 {
+            for role in [KeyRole::Staking, KeyRole::Transfer].iter().copied() {
+                assert_eq!(
+                    public_key,
+                    client
+                        .import_private_key("name", &enckey, &key_bytes, role)
+                        .unwrap()
+                );
+            }
+        }
+        assert_eq!(
+            vec![staking_address],
+            client
+                .staking_addresses("name", &enckey)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![transfer_address],
+            client
+                .transfer_addresses("name", &enckey)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>()
+        );
+        assert!(client
+            .wallet_service
+            .imported_keys("name", &enckey)
+            .unwrap()
+            .contains(&public_key));
+        assert_eq!(
+            Some(public_key.clone()),
+            client
+                .find_staking_key("name", &enckey, &RedeemAddress::from(&public_key))
+                .unwrap()
+        );
+
+        let unbond_tx = UnbondTx::new(
+            staking_address,
+            0,
+            Coin::unit(),
+            StakedStateOpAttributes::new(0),
+        );
+        let signature = client
+            .sign_key("name", &enckey, &public_key)
+            .unwrap()
+            .sign(&Transaction::UnbondStakeTransaction(unbond_tx.clone()))
+            .unwrap();
+        assert_eq!(
+            staking_address,
+            verify_tx_recover_address(&StakedStateOpWitness::new(signature), &unbond_tx.id())
+                .unwrap()
+        );
+
+        // invalid scalar values are rejected
+        for invalid in [vec![0; 32], vec![0xff; 32], key_bytes[..31].to_vec()].iter() {
+            assert!(client
+                .import_private_key("name", &enckey, invalid, KeyRole::Staking)
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn check_rename_wallet() {
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let passphrase = SecUtf8::from("passphrase");
+
+        let (enckey, _) = client
+            .new_wallet("old", &passphrase, WalletKind::HD, None, None)
+            .unwrap();
+        client
+            .new_wallet("other", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let transfer_address = client.new_transfer_address("old", &enckey).unwrap();
+        let staking_address = client.new_staking_address("old", &enckey).unwrap();
+        client
+            .set_address_label("old", &enckey, staking_address.into(), "validator")
+            .unwrap();
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            TxoPointer::new([1; 32], 0),
+            TxOut::new(transfer_address.clone(), Coin::new(100).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("old", &enckey, &memento)
+            .unwrap();
+        save_sync_state(&storage, "old", &SyncState::genesis(Vec::new())).unwrap();
+
+        let transfer_addresses = client.transfer_addresses("old", &enckey).unwrap();
+        let staking_addresses = client.staking_addresses("old", &enckey).unwrap();
+        let balance = client.balance("old", &enckey).unwrap();
+
+        assert!(client
+            .rename_wallet("old", &SecUtf8::from("wrong passphrase"), "new")
+            .is_err());
+        assert!(client.rename_wallet("old", &passphrase, "other").is_err());
+
+        let new_enckey = client.rename_wallet("old", &passphrase, "new").unwrap();
+
+        let mut wallets = client.wallets().unwrap();
+        wallets.sort();
+        assert_eq!(vec!["new".to_owned(), "other".to_owned()], wallets);
+        assert!(client.auth_token("old", &passphrase).is_err());
+        assert_eq!(
+            new_enckey.unsecure(),
+            client.auth_token("new", &passphrase).unwrap().unsecure()
+        );
+
+        assert_eq!(
+            transfer_addresses,
+            client.transfer_addresses("new", &new_enckey).unwrap()
+        );
+        assert_eq!(
+            staking_addresses,
+            client.staking_addresses("new", &new_enckey).unwrap()
+        );
+        assert_eq!(balance, client.balance("new", &new_enckey).unwrap());
+        assert_eq!(
+            Coin::new(100).unwrap(),
+            client.balance("new", &new_enckey).unwrap().total
+        );
+        assert_eq!(
+            vec![(staking_address.into(), "validator".to_owned())],
+            client.list_labeled_addresses("new", &new_enckey).unwrap()
+        );
+        assert!(load_sync_state(&storage, "new").unwrap().is_some());
+        assert!(load_sync_state(&storage, "old").unwrap().is_none());
+        assert!(client.view_key_private("new", &new_enckey).is_ok());
+        // HD seed is moved as well
+        assert!(client.new_transfer_address("new", &new_enckey).is_ok());
+    }
+
+    #[test]
+    fn check_rescan_addresses() {
+        use crate::types::TransactionInput;
+        use crate::wallet::DEFAULT_GAP_LIMIT;
+        use chain_core::state::ChainState;
+        use client_common::tendermint::lite;
+        use client_common::tendermint::types::*;
+
+        /// Client where only staking account of given address exists
+        #[derive(Clone)]
+        struct MockStakingClient(RedeemAddress);
+
+        impl Client for MockStakingClient {
+            fn genesis(&self) -> Result<Genesis> {
+                unreachable!()
+            }
+            fn status(&self) -> Result<StatusResponse> {
+                unreachable!()
+            }
+            fn block(&self, _height: u64) -> Result<Block> {
+                unreachable!()
+            }
+            fn block_batch<'a, T: Iterator<Item = &'a u64>>(
+                &self,
+                _heights: T,
+            ) -> Result<Vec<Block>> {
+                unreachable!()
+            }
+            fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+                unreachable!()
+            }
+            fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+                &self,
+                _heights: T,
+            ) -> Result<Vec<BlockResultsResponse>> {
+                unreachable!()
+            }
+            fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+                &self,
+                _state: lite::TrustedState,
+                _heights: T,
+            ) -> Result<(Vec<Block>, lite::TrustedState)> {
+                unreachable!()
+            }
+            fn broadcast_transaction(&self, _transaction: &[u8]) -> Result<BroadcastTxResponse> {
+                unreachable!()
+            }
+            fn query(&self, _path: &str, _data: &[u8]) -> Result<AbciQuery> {
+                unreachable!()
+            }
+            fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+                unreachable!()
+            }
+            fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+                &self,
+                queries: T,
+            ) -> Result<Vec<AbciQuery>> {
+                Ok(queries
+                    .map(|(path, data)| {
+                        assert_eq!("account", path);
+                        if data == &(self.0).0[..] {
+                            AbciQuery {
+                                value: Some(vec![1]),
+                                ..Default::default()
+                            }
+                        } else {
+                            AbciQuery {
+                                code: Code::Err(1),
+                                ..Default::default()
+                            }
+                        }
+                    })
+                    .collect())
+            }
+            fn query_state_batch<T: Iterator<Item = u64>>(
+                &self,
+                _heights: T,
+            ) -> Result<Vec<ChainState>> {
+                unreachable!()
+            }
+        }
+
+        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
+        let passphrase = SecUtf8::from("123456");
+
+        // addresses at high indices (derived in another wallet restored from the same mnemonic)
+        let other = DefaultWalletClient::new_read_only(MemoryStorage::default());
+        let other_enckey = other
+            .restore_wallet("other", &passphrase, &words, None)
+            .unwrap();
+        let transfer_address_at = |index| {
+            let path = HdPath::wallet_key(0, HDAccountType::Transfer, index).unwrap();
+            other
+                .new_transfer_address_at("other", &other_enckey, &path)
+                .unwrap()
+        };
+        let (address_10, address_25, address_37) = (
+            transfer_address_at(10),
+            transfer_address_at(25),
+            transfer_address_at(37),
+        );
+        let staking_address = other
+            .new_staking_address_at(
+                "other",
+                &other_enckey,
+                &HdPath::wallet_key(0, HDAccountType::Staking, 5).unwrap(),
+            )
+            .unwrap();
+        let StakedStateAddress::BasicRedeem(redeem_address) = staking_address;
+
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new(
+            storage.clone(),
+            MockStakingClient(redeem_address),
+            UnauthorizedWalletTransactionBuilder,
+            None,
+            HwKeyService::default(),
+        );
+        let enckey = client
+            .restore_wallet("name", &passphrase, &words, None)
+            .unwrap();
+
+        // history synced before the addresses are known to wallet
+        let other_address = ExtendedAddr::OrTree([0; 32]);
+        let change = |txid, inputs, outputs| TransactionChange {
+            transaction_id: txid,
+            inputs,
+            outputs,
+            fee_paid: Fee::new(Coin::zero()),
+            balance_change: BalanceChange::NoChange,
+            transaction_type: crate::types::TransactionType::Transfer,
+            block_height: 1,
+            block_time: Time::unix_epoch(),
+            memo: None,
+            depositor: None,
+        };
+        let output_10 = TxOut::new(address_10, Coin::new(30).unwrap());
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(change([1; 32], Vec::new(), vec![output_10.clone()]));
+        memento.add_transaction_change(change(
+            [2; 32],
+            Vec::new(),
+            vec![TxOut::new(address_37.clone(), Coin::new(70).unwrap())],
+        ));
+        memento.add_transaction_change(change(
+            [3; 32],
+            vec![TransactionInput {
+                pointer: TxoPointer::new([1; 32], 0),
+                output: Some(output_10),
+            }],
+            vec![
+                TxOut::new(address_25.clone(), Coin::new(20).unwrap()),
+                TxOut::new(other_address, Coin::new(10).unwrap()),
+            ],
+        ));
+        WalletStateService::new(storage)
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+        assert_eq!(Coin::zero(), client.balance("name", &enckey).unwrap().total);
+
+        assert!(client.rescan_addresses("name", &enckey, 0).is_err());
+        let discovered = client
+            .rescan_addresses("name", &enckey, DEFAULT_GAP_LIMIT)
+            .unwrap();
+        assert_eq!(38, discovered.transfer_addresses.len());
+        assert_eq!(address_37, discovered.transfer_addresses[37]);
+        assert_eq!(6, discovered.staking_addresses.len());
+        assert_eq!(staking_address, discovered.staking_addresses[5]);
+
+        assert_eq!(
+            Coin::new(90).unwrap(),
+            client.balance("name", &enckey).unwrap().total
+        );
+        assert!(client
+            .transfer_addresses("name", &enckey)
+            .unwrap()
+            .contains(&address_25));
+        assert!(client
+            .staking_addresses("name", &enckey)
+            .unwrap()
+            .contains(&staking_address));
+        assert_eq!(
+            BalanceChange::Outgoing {
+                value: Coin::new(10).unwrap()
+            },
+            client
+                .get_transaction_change("name", &enckey, &[3; 32])
+                .unwrap()
+                .unwrap()
+                .balance_change
+        );
+
+        // indices continue after discovered addresses, and nothing is discovered again
+        assert_eq!(
+            transfer_address_at(38),
+            client.new_transfer_address("name", &enckey).unwrap()
+        );
+        assert_eq!(
+            DiscoveredAddresses::default(),
+            client
+                .rescan_addresses("name", &enckey, DEFAULT_GAP_LIMIT)
+                .unwrap()
+        );
+
+        // a gap longer than gap limit hides later addresses
+        let enckey = client
+            .restore_wallet("short", &passphrase, &words, None)
+            .unwrap();
+        let discovered = client.rescan_addresses("short", &enckey, 5).unwrap();
+        assert!(discovered.transfer_addresses.is_empty());
+        assert!(discovered.staking_addresses.is_empty());
+    }
+
+    #[test]
+    fn check_rebroadcast_pending() {
+        use chain_core::state::account::{StakedStateOpAttributes, StakedStateOpWitness, UnbondTx};
+        use chain_core::state::ChainState;
+        use chain_core::tx::TxPublicAux;
+        use client_common::tendermint::types::*;
+        use client_common::tendermint::{lite, mock};
+
+        /// Client responding to every broadcast with given code and log (or failing to deliver it
+        /// without a response), where only given transaction is committed
+        #[derive(Clone)]
+        struct MockBroadcastClient {
+            response: Option<(u32, &'static str)>,
+            committed: Option<TxId>,
+        }
+
+        impl Client for MockBroadcastClient {
+            fn genesis(&self) -> Result<Genesis> {
+                unreachable!()
+            }
+            fn status(&self) -> Result<StatusResponse> {
+                Ok(mock::status_response())
+            }
+            fn block(&self, _height: u64) -> Result<Block> {
+                unreachable!()
+            }
+            fn block_batch<'a, T: Iterator<Item = &'a u64>>(
+                &self,
+                _heights: T,
+            ) -> Result<Vec<Block>> {
+                unreachable!()
+            }
+            fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+                unreachable!()
+            }
+            fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+                &self,
+                _heights: T,
+            ) -> Result<Vec<BlockResultsResponse>> {
+                unreachable!()
+            }
+            fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+                &self,
+                _state: lite::TrustedState,
+                _heights: T,
+            ) -> Result<(Vec<Block>, lite::TrustedState)> {
+                unreachable!()
+            }
+            fn broadcast_transaction(&self, _transaction: &[u8]) -> Result<BroadcastTxResponse> {
+                let (code, log) = self.response.ok_or_else(|| {
+                    Error::new(ErrorKind::TendermintRpcError, "connection refused")
+                })?;
+                Ok(serde_json::from_str(&format!(
+                    r#"{{"code":{},"data":"","log":"{}","hash":"{}"}}"#,
+                    code,
+                    log,
+                    "AB".repeat(32)
+                ))
+                .unwrap())
+            }
+            fn query(&self, path: &str, data: &[u8]) -> Result<AbciQuery> {
+                assert_eq!("meta", path);
+                match self.committed {
+                    Some(ref txid) if &txid[..] == data => Ok(AbciQuery::default()),
+                    _ => Err(Error::new(ErrorKind::TendermintRpcError, "not found")),
+                }
+            }
+            fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+                unreachable!()
+            }
+            fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+                &self,
+                _queries: T,
+            ) -> Result<Vec<AbciQuery>> {
+                unreachable!()
+            }
+            fn query_state_batch<T: Iterator<Item = u64>>(
+                &self,
+                _heights: T,
+            ) -> Result<Vec<ChainState>> {
+                unreachable!()
+            }
+        }
+
+        let storage = MemoryStorage::default();
+        let (enckey, _) = DefaultWalletClient::new_read_only(storage.clone())
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+                None,
+            )
+            .unwrap();
+        let client = |response, committed| {
+            DefaultWalletClient::new(
+                storage.clone(),
+                MockBroadcastClient {
+                    response,
+                    committed,
+                },
+                UnauthorizedWalletTransactionBuilder,
+                None,
+                HwKeyService::default(),
+            )
+        };
+
+        let transaction = UnbondTx::new(
+            StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+            0,
+            Coin::unit(),
+            StakedStateOpAttributes::new(0),
+        );
+        let witness = PrivateKey::new()
+            .unwrap()
+            .sign(&Transaction::UnbondStakeTransaction(transaction.clone()))
+            .map(StakedStateOpWitness::new)
+            .unwrap();
+        let tx_aux = TxAux::PublicTx(TxPublicAux::UnbondStakeTx(transaction, witness));
+        let txid = tx_aux.tx_id();
+
+        // transaction is broadcasted (storing it in broadcast log), but later dropped by node
+        let accepting = client(Some((0, "")), None);
+        accepting.broadcast_transaction(&tx_aux).unwrap();
+        accepting
+            .update_tx_pending_state(
+                "name",
+                &enckey,
+                txid,
+                TransactionPending {
+                    used_inputs: vec![],
+                    block_height: 100,
+                    return_amount: Coin::zero(),
+                    tx_id: txid,
+                    valid_until_height: 150,
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            RebroadcastOutcome::Accepted,
+            accepting
+                .rebroadcast_pending("name", &enckey, &txid)
+                .unwrap()
+        );
+        let pending = WalletStateService::new(storage.clone())
+            .get_pending_transaction("name", &enckey, &txid)
+            .unwrap()
+            .unwrap();
+        assert_ne!(100, pending.block_height);
+        assert_eq!(
+            pending.block_height + DEFAULT_PENDING_TX_VALID_BLOCKS,
+            pending.valid_until_height
+        );
+
+        assert_eq!(
+            RebroadcastOutcome::AlreadyInMempool,
+            client(Some((1, "tx already exists in cache")), None)
+                .rebroadcast_pending("name", &enckey, &txid)
+                .unwrap()
+        );
+
+        let spent = "public tx process failed: tx nonce don't match staking state";
+        assert_eq!(
+            RebroadcastOutcome::AlreadyCommitted,
+            client(Some((1, spent)), Some(txid))
+                .rebroadcast_pending("name", &enckey, &txid)
+                .unwrap()
+        );
+        // rejected for another reason (e.g. nonce used by another transaction)
+        let error = client(Some((1, spent)), None)
+            .rebroadcast_pending("name", &enckey, &txid)
+            .unwrap_err();
+        assert_eq!(
+            Some(&BroadcastError::NonceMismatch),
+            broadcast_error(&error)
+        );
+
+        assert_eq!(
+            ErrorKind::TendermintRpcError,
+            client(None, None)
+                .rebroadcast_pending("name", &enckey, &txid)
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            accepting
+                .rebroadcast_pending("name", &enckey, &[0; 32])
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_change_policy() {
+        use parity_scale_codec::Decode;
+
+        let storage = MemoryStorage::default();
+        let passphrase = SecUtf8::from("passphrase");
+        let (enckey, _) = multisig_test_client(storage.clone())
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let client = multisig_test_client(storage.clone());
+        let first = client.new_transfer_address("name", &enckey).unwrap();
+        let second = client.new_transfer_address("name", &enckey).unwrap();
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            TxoPointer::new([1; 32], 0),
+            TxOut::new(first.clone(), Coin::new(100).unwrap()),
+        );
+        memento.add_unspent_transaction(
+            TxoPointer::new([2; 32], 0),
+            TxOut::new(second.clone(), Coin::new(50).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+
+        // returns address receiving change of a transfer of 30 with given policy
+        let change_of = |change_policy: ChangePolicy| -> Result<ExtendedAddr> {
+            let client = multisig_test_client(storage.clone())
+                .with_change_policy(change_policy)
+                .with_include_timelocked(true);
+            let change_address = client.change_address("name", &enckey)?;
+            let (tx_aux, _, return_amount) = client.build_transfer(
+                "name",
+                &enckey,
+                vec![TxOut::new(
+                    ExtendedAddr::OrTree([9; 32]),
+                    Coin::new(30).unwrap(),
+                )],
+                TxAttributes::new(171),
+                None,
+                change_address,
+            )?;
+            assert_eq!(Coin::new(70).unwrap(), return_amount);
+
+            match tx_aux {
+                TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                    payload: TxObfuscated { txpayload, .. },
+                    ..
+                }) => match SignedTransaction::decode(&mut txpayload.as_slice()).unwrap() {
+                    SignedTransaction::TransferTransaction(tx, _) => {
+                        assert_eq!(2, tx.outputs.len());
+                        assert_eq!(Coin::new(70).unwrap(), tx.outputs[1].value);
+                        Ok(tx.outputs[1].address.clone())
+                    }
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            }
+        };
 
-        let proof = self
-            .root_hash_service
-            .generate_proof(name, &root_hash, public_keys, enckey)?;
-        let signature = self.signature(session_id, enckey)?;
+        let change = change_of(ChangePolicy::NewInternalAddress).unwrap();
+        assert_ne!(first, change);
+        assert_ne!(second, change);
+        assert!(client
+            .transfer_addresses("name", &enckey)
+            .unwrap()
+            .contains(&change));
+
+        assert_eq!(
+            second,
+            change_of(ChangePolicy::FixedAddress(second.clone())).unwrap()
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            change_of(ChangePolicy::FixedAddress(ExtendedAddr::OrTree([9; 32])))
+                .unwrap_err()
+                .kind()
+        );
 
-        let witness = TxWitness::from(vec![TxInWitness::TreeSig(signature, proof)]);
-        let signed_transaction =
-            SignedTransaction::TransferTransaction(unsigned_transaction, witness);
+        // input of highest value is selected first
+        assert_eq!(first, change_of(ChangePolicy::ReuseFirstInput).unwrap());
+        let unsigned = multisig_test_client(storage.clone())
+            .with_change_policy(ChangePolicy::ReuseFirstInput)
+            .with_include_timelocked(true)
+            .build_raw_transfer_tx(
+                "name",
+                &enckey,
+                ExtendedAddr::OrTree([9; 32]),
+                Coin::new(30).unwrap(),
+                vec![],
+                171,
+            )
+            .unwrap();
+        assert_eq!(first, unsigned.return_address);
+    }
 
-        self.transaction_builder.obfuscate(signed_transaction)
+    #[test]
+    fn check_timelocked_inputs() {
+        let storage = MemoryStorage::default();
+        let client = multisig_test_client(storage);
+        let (enckey, _) = client
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+                None,
+            )
+            .unwrap();
+        let address = client.new_transfer_address("name", &enckey).unwrap();
+
+        let locked = TxoPointer::new([1; 32], 0);
+        let free = TxoPointer::new([2; 32], 0);
+        let mut locked_output = TxOut::new(address.clone(), Coin::new(100).unwrap());
+        locked_output.valid_from = Some(2000);
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(locked.clone(), locked_output);
+        memento.add_unspent_transaction(
+            free.clone(),
+            TxOut::new(address.clone(), Coin::new(50).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+
+        let transfer = |value: u64, spendable_at: Option<Timespec>| {
+            client.build_transfer_at(
+                "name",
+                &enckey,
+                vec![TxOut::new(
+                    ExtendedAddr::OrTree([9; 32]),
+                    Coin::new(value).unwrap(),
+                )],
+                TxAttributes::new(171),
+                None,
+                address.clone().into(),
+                spendable_at,
+            )
+        };
+
+        // output of higher value is skipped while timelocked
+        let (_, inputs, _) = transfer(30, Some(1000)).unwrap();
+        assert_eq!(vec![free.clone()], inputs);
+
+        let error = transfer(80, Some(1000)).unwrap_err();
+        assert_eq!(ErrorKind::InsufficientBalance, error.kind());
+        assert!(error.message().contains(&format!(
+            "{} is timelocked, earliest until unix time 2000",
+            Coin::new(100).unwrap()
+        )));
+
+        let (_, inputs, _) = transfer(80, Some(2000)).unwrap();
+        assert_eq!(vec![locked.clone()], inputs);
+        let (_, inputs, _) = transfer(80, None).unwrap();
+        assert_eq!(vec![locked], inputs);
     }
-}
 
-fn check_passphrase_strength(name: &str, passphrase: &SecUtf8) -> Result<()> {
-    // `estimate_password_strength` returns a score between `0-4`. Any score less than 3 should be considered too
-    // weak.
-    let password_entropy = estimate_password_strength(passphrase.unsecure(), &[name])
-        .chain(|| (ErrorKind::IllegalInput, "Blank passphrase"))?;
+    #[test]
+    fn check_max_send() {
+        use chain_core::tx::fee::Milli;
+        use parity_scale_codec::Decode;
+
+        let client = fee_test_client(MemoryStorage::default(), Milli::new(1, 1))
+            .with_include_timelocked(true);
+        let (enckey, _) = client
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+                None,
+            )
+            .unwrap();
+        let destination = ExtendedAddr::OrTree([9; 32]);
+        let attributes = TxAttributes::new(171);
+
+        // nothing to send
+        assert_eq!(
+            MaxSend::default(),
+            client
+                .estimate_max_send("name", &enckey, destination.clone(), attributes.clone())
+                .unwrap()
+        );
 
-    #[cfg(debug_assertions)]
-    let entropy_score = 0;
-    #[cfg(not(debug_assertions))]
-    let entropy_score = 3;
+        // balance smaller than fee
+        let address = client.new_transfer_address("name", &enckey).unwrap();
+        let dust = UnspentTransactions::new(vec![(
+            TxoPointer::new([3; 32], 0),
+            TxOut::new(address.clone(), Coin::new(10).unwrap()),
+        )]);
+        assert_eq!(
+            ErrorKind::InsufficientBalance,
+            client
+                .max_send(&dust, &destination, &attributes)
+                .unwrap_err()
+                .kind()
+        );
 
-    if password_entropy.score() < entropy_score {
-        return Err(Error::new(
-            ErrorKind::IllegalInput,
-            format!(
-                "Weak passphrase: {}",
-                parse_feedback(password_entropy.feedback().as_ref())
-            ),
-        ));
+        let first = TxoPointer::new([1; 32], 0);
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            first.clone(),
+            TxOut::new(address.clone(), Coin::new(10000).unwrap()),
+        );
+        memento.add_unspent_transaction(
+            TxoPointer::new([2; 32], 0),
+            TxOut::new(address.clone(), Coin::new(20000).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+
+        let unspent_transactions = client.unspent_transactions("name", &enckey).unwrap();
+        let max_send = client
+            .max_send(&unspent_transactions, &destination, &attributes)
+            .unwrap();
+        assert_eq!(2, max_send.inputs_used);
+        assert!(max_send.fee > Coin::zero());
+        assert_eq!(
+            Coin::new(30000).unwrap(),
+            (max_send.amount + max_send.fee).unwrap()
+        );
+
+        // swept transaction sends estimated amount in a single output
+        let (tx_aux, used_inputs, swept) = client
+            .build_sweep(
+                "name",
+                &enckey,
+                unspent_transactions,
+                destination.clone(),
+                attributes.clone(),
+            )
+            .unwrap();
+        assert_eq!(max_send, swept);
+        assert_eq!(2, used_inputs.len());
+        match tx_aux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match SignedTransaction::decode(&mut txpayload.as_slice()).unwrap() {
+                SignedTransaction::TransferTransaction(tx, _) => {
+                    assert_eq!(
+                        vec![TxOut::new(destination.clone(), max_send.amount)],
+                        tx.outputs
+                    );
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+
+        // inputs of pending transactions are not sent
+        let mut memento = WalletStateMemento::default();
+        memento.add_pending_transaction(
+            [4; 32],
+            TransactionPending {
+                used_inputs: vec![first],
+                block_height: 0,
+                return_amount: Coin::zero(),
+                tx_id: [4; 32],
+                valid_until_height: DEFAULT_PENDING_TX_VALID_BLOCKS,
+            },
+        );
+        client
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+        let max_send = client
+            .estimate_max_send("name", &enckey, destination, attributes)
+            .unwrap();
+        assert_eq!(1, max_send.inputs_used);
+        assert_eq!(
+            Coin::new(20000).unwrap(),
+            (max_send.amount + max_send.fee).unwrap()
+        );
     }
 
-    Ok(())
-}
+    #[test]
+    fn check_frozen_utxos() {
+        let client = multisig_test_client(MemoryStorage::default());
+        let (enckey, _) = client
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+                None,
+            )
+            .unwrap();
+        let address = client.new_transfer_address("name", &enckey).unwrap();
+
+        let first = TxoPointer::new([1; 32], 0);
+        let second = TxoPointer::new([2; 32], 0);
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            first.clone(),
+            TxOut::new(address.clone(), Coin::new(100).unwrap()),
+        );
+        memento.add_unspent_transaction(
+            second.clone(),
+            TxOut::new(address.clone(), Coin::new(200).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
 
-fn parse_feedback(feedback: Option<&Feedback>) -> String {
-    match feedback {
-        None => "No feedback available!".to_string(),
-        Some(feedback) => {
-            let mut feedbacks = Vec::new();
+        client.freeze_utxo("name", &enckey, second.clone()).unwrap();
+        assert_eq!(
+            vec![second.clone()],
+            client.list_frozen_utxos("name", &enckey).unwrap()
+        );
+        let unspent_transactions = client.unspent_transactions("name", &enckey).unwrap();
+        assert_eq!(1, unspent_transactions.len());
+        assert_eq!(first, unspent_transactions[0].0);
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            client
+                .freeze_utxo("name", &enckey, TxoPointer::new([3; 32], 0))
+                .unwrap_err()
+                .kind()
+        );
 
-            if let Some(warning) = feedback.warning() {
-                feedbacks.push(format!("Warning: {}", warning));
-            }
+        client
+            .unfreeze_utxo("name", &enckey, second.clone())
+            .unwrap();
+        assert!(client
+            .list_frozen_utxos("name", &enckey)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            2,
+            client.unspent_transactions("name", &enckey).unwrap().len()
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            client
+                .unfreeze_utxo("name", &enckey, second)
+                .unwrap_err()
+                .kind()
+        );
+    }
 
-            for suggestion in feedback.suggestions() {
-                feedbacks.push(format!("Suggestion: {}", suggestion));
-            }
+    #[test]
+    fn check_balance_by_address() {
+        use crate::types::{TransactionInput, TransactionType};
+
+        let client = multisig_test_client(MemoryStorage::default());
+        let (enckey, _) = client
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+                None,
+            )
+            .unwrap();
+        let first = client.new_transfer_address("name", &enckey).unwrap();
+        let second = client.new_transfer_address("name", &enckey).unwrap();
+        let third = client.new_transfer_address("name", &enckey).unwrap();
+        let staking = client.new_staking_address("name", &enckey).unwrap();
+        client
+            .set_address_label("name", &enckey, second.clone().into(), "savings")
+            .unwrap();
 
-            if feedbacks.is_empty() {
-                feedbacks.push("No feedback available!".to_string());
-            }
+        let change = |txid, block_height, inputs, outputs| TransactionChange {
+            transaction_id: txid,
+            inputs,
+            outputs,
+            fee_paid: Fee::new(Coin::zero()),
+            balance_change: BalanceChange::NoChange,
+            transaction_type: TransactionType::Transfer,
+            block_height,
+            block_time: Time::unix_epoch(),
+            memo: None,
+            depositor: None,
+        };
+        let received = TxOut::new(first.clone(), Coin::new(100).unwrap());
+        let mut memento = WalletStateMemento::default();
+        memento.add_transaction_change(change([1; 32], 5, Vec::new(), vec![received.clone()]));
+        memento.add_transaction_change(change(
+            [2; 32],
+            7,
+            Vec::new(),
+            vec![TxOut::new(second.clone(), Coin::new(50).unwrap())],
+        ));
+        memento.add_transaction_change(change(
+            [3; 32],
+            9,
+            vec![TransactionInput {
+                pointer: TxoPointer::new([1; 32], 0),
+                output: Some(received),
+            }],
+            vec![
+                TxOut::new(second.clone(), Coin::new(60).unwrap()),
+                TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(40).unwrap()),
+            ],
+        ));
+        memento.add_unspent_transaction(
+            TxoPointer::new([2; 32], 0),
+            TxOut::new(second.clone(), Coin::new(50).unwrap()),
+        );
+        memento.add_unspent_transaction(
+            TxoPointer::new([3; 32], 0),
+            TxOut::new(second.clone(), Coin::new(60).unwrap()),
+        );
+        client
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+
+        let balances = client.balance_by_address("name", &enckey).unwrap();
+        assert_eq!(4, balances.len());
+
+        // spent everything it received
+        assert_eq!(LabeledAddress::Transfer(first), balances[0].address);
+        assert_eq!(Coin::zero(), balances[0].balance);
+        assert_eq!(0, balances[0].utxo_count);
+        assert_eq!(Some(5), balances[0].first_seen_height);
+        assert_eq!(Some(9), balances[0].last_active_height);
+        assert_eq!(None, balances[0].label);
+
+        assert_eq!(LabeledAddress::Transfer(second), balances[1].address);
+        assert_eq!(Coin::new(110).unwrap(), balances[1].balance);
+        assert_eq!(2, balances[1].utxo_count);
+        assert_eq!(Some(7), balances[1].first_seen_height);
+        assert_eq!(Some(9), balances[1].last_active_height);
+        assert_eq!(Some("savings".to_owned()), balances[1].label);
+
+        // unused addresses
+        assert_eq!(AddressBalance::new(third.into()), balances[2]);
+        assert_eq!(AddressBalance::new(staking.into()), balances[3]);
+
+        let serialized = serde_json::to_string(&balances[1]).unwrap();
+        assert_eq!(
+            balances[1],
+            serde_json::from_str::<AddressBalance>(&serialized).unwrap()
+        );
+    }
 
-            feedbacks.join(" | ")
+    #[test]
+    fn check_wallet_metadata() {
+        let storage = MemoryStorage::default();
+        let client = multisig_test_client(storage.clone());
+        let passphrase = SecUtf8::from("passphrase");
+        let (basic_enckey, _) = client
+            .new_wallet("basic", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let (hd_enckey, _) = client
+            .new_wallet("hd", &passphrase, WalletKind::HD, None, None)
+            .unwrap();
+
+        for _ in 0..2 {
+            client.new_transfer_address("basic", &basic_enckey).unwrap();
+        }
+        client.new_staking_address("basic", &basic_enckey).unwrap();
+        for _ in 0..3 {
+            client.new_staking_address("hd", &hd_enckey).unwrap();
         }
+
+        let wallets = client.list_wallets().unwrap();
+        assert_eq!(2, wallets.len());
+        let basic = wallets.iter().find(|info| info.name == "basic").unwrap();
+        assert_eq!(WalletKind::Basic, basic.kind);
+        assert!(!basic.hd);
+        assert!(basic.created_at.is_some());
+        assert_eq!(None, basic.network);
+        assert_eq!(2, basic.transfer_address_count);
+        assert_eq!(1, basic.staking_address_count);
+
+        let hd = client.wallet_info("hd").unwrap();
+        assert!(wallets.contains(&hd));
+        assert_eq!(WalletKind::HD, hd.kind);
+        assert!(hd.hd);
+        assert_eq!(0, hd.transfer_address_count);
+        assert_eq!(3, hd.staking_address_count);
+
+        // wallets created before creation time was recorded
+        storage
+            .delete("core_wallet_basic_info", "createdat")
+            .unwrap();
+        let legacy = client.wallet_info("basic").unwrap();
+        assert_eq!(None, legacy.created_at);
+        assert_eq!(basic.staking_address_count, legacy.staking_address_count);
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            client.wallet_info("unknown").unwrap_err().kind()
+        );
     }
-}
 
-fn import_transaction(
-    wallet: &Wallet,
-    wallet_state: &WalletState,
-    memento: &mut WalletStateMemento,
-    transaction_info: &TransactionInfo,
-    paid_fee: Fee,
-    block_time: Time,
-    spent_flag: Vec<bool>,
-) -> Result<Coin> {
-    let transaction_change = create_transaction_change(
-        wallet,
-        wallet_state,
-        &transaction_info.tx,
-        paid_fee,
-        transaction_info.block_height,
-        block_time,
-    )
-    .chain(|| (ErrorKind::InvalidInput, "create transaction change failed"))?;
-    let mut value = Coin::zero();
-    let transfer_addresses = wallet.transfer_addresses();
-    for (i, (output, spent)) in transaction_change
-        .outputs
-        .iter()
-        .zip(spent_flag)
-        .enumerate()
-    {
-        // Only add unspent transaction if output address belongs to current wallet
-        if transfer_addresses.contains(&output.address) && !spent {
-            memento.add_unspent_transaction(
-                TxoPointer::new(transaction_change.transaction_id, i),
-                output.clone(),
-            );
-            value = (value + output.value).chain(|| {
-                (
-                    ErrorKind::InvalidInput,
-                    "invalid coin in outputs of transaction",
-                )
-            })?;
+    #[test]
+    fn check_wallet_creation_is_atomic() {
+        let storage = TestStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let passphrase = SecUtf8::from("passphrase");
+
+        // process dies before any record of wallet is written
+        for wallet_kind in &[WalletKind::Basic, WalletKind::HD] {
+            storage.fail_after(Some(0));
+            assert!(client
+                .new_wallet("name", &passphrase, *wallet_kind, None, None)
+                .is_err());
+            storage.fail_after(None);
+            assert!(client.wallets().unwrap().is_empty());
+            assert!(storage.keyspaces().unwrap().is_empty());
         }
+
+        // all records of wallet are written in a single batch
+        storage.fail_after(Some(1));
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::HD, None, None)
+            .unwrap();
+        storage.fail_after(None);
+        let public_key = client.new_public_key("name", &enckey, None).unwrap();
+        let view_key = client.view_key_private("name", &enckey).unwrap();
+
+        storage.fail_after(Some(0));
+        assert!(client
+            .new_watch_wallet("watch", &passphrase, &view_key, &[public_key], &[])
+            .is_err());
+        storage.fail_after(None);
+        assert_eq!(vec!["name".to_owned()], client.wallets().unwrap());
     }
-    memento.add_transaction_change(transaction_change);
-    Ok(value)
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::Mnemonic;
-    use client_common::storage::MemoryStorage;
+    #[test]
+    fn check_open_migrates_storage() {
+        let storage = MemoryStorage::default();
+        DefaultWalletClient::open_read_only(storage.clone()).unwrap();
+        assert_eq!(SCHEMA_VERSION, schema_version(&storage).unwrap());
+
+        // storage written by a newer client
+        storage
+            .set("core_schema", "version", (SCHEMA_VERSION + 1).encode())
+            .unwrap();
+        let error = DefaultWalletClient::open_read_only(storage).unwrap_err();
+        assert_eq!(ErrorKind::StorageError, error.kind());
+    }
 
     #[test]
-    fn check_delete_wallet() {
-        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
-        let passphrase = SecUtf8::from("123456");
-        let wrong_passphrase = SecUtf8::from("123457");
-        let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
-        client
-            .restore_wallet("Default", &passphrase, &words)
-            .expect("restore wallet");
-        // FIXME this failure will leave storage in an inconsistant state
-        // assert!(client.restore_wallet("test", &passphrase, &words).is_err());
-        assert!(client.delete_wallet("Default", &wrong_passphrase).is_err());
-        assert!(client.delete_wallet("Default1", &passphrase).is_err());
-        client
-            .delete_wallet("Default", &passphrase)
-            .expect("delete wallet");
+    fn check_prune_settled_transactions() {
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let passphrase = SecUtf8::from("passphrase");
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = client.new_transfer_address("name", &enckey).unwrap();
+
+        // one settled transaction per block, every tenth one spending a still unspent output
+        let mut memento = WalletStateMemento::default();
+        for height in 0..300u64 {
+            let mut tx_id = [0; 32];
+            tx_id[..8].copy_from_slice(&height.to_le_bytes());
+            let mut input_id = tx_id;
+            input_id[31] = 1;
+            let input = TxoPointer::new(input_id, 0);
+
+            if height % 10 == 0 {
+                memento.add_unspent_transaction(
+                    input.clone(),
+                    TxOut::new(address.clone(), Coin::unit()),
+                );
+            }
+            memento.add_pending_transaction(
+                tx_id,
+                TransactionPending {
+                    used_inputs: vec![input],
+                    block_height: height,
+                    return_amount: Coin::zero(),
+                    tx_id,
+                    valid_until_height: height + 1,
+                },
+            );
+            memento.settle_pending_transaction(tx_id);
+        }
         client
-            .restore_wallet("test", &passphrase, &words)
-            .expect("restore wallet");
+            .wallet_state_service
+            .apply_memento("name", &enckey, &memento)
+            .unwrap();
+        let mut sync_state = SyncState::genesis(Vec::new());
+        sync_state.last_block_height = 300;
+        save_sync_state(&storage, "name", &sync_state).unwrap();
+        let balance = client.balance("name", &enckey).unwrap();
+
+        assert_eq!(
+            180,
+            client
+                .prune_settled_transactions("name", &enckey, 100)
+                .unwrap()
+        );
+
+        let mut heights = client
+            .list_pending_transactions("name", &enckey)
+            .unwrap()
+            .into_iter()
+            .map(|info| info.pending.block_height)
+            .collect::<Vec<_>>();
+        heights.sort();
+        let expected = (0..300u64)
+            .filter(|height| *height >= 200 || height % 10 == 0)
+            .collect::<Vec<_>>();
+        assert_eq!(expected, heights);
+        assert_eq!(balance, client.balance("name", &enckey).unwrap());
+        assert_eq!(
+            300,
+            load_sync_state(&storage, "name")
+                .unwrap()
+                .unwrap()
+                .last_block_height
+        );
+
+        // nothing left to prune
+        assert_eq!(
+            0,
+            client
+                .prune_settled_transactions("name", &enckey, 100)
+                .unwrap()
+        );
+        let wrong_enckey = derive_enckey(&SecUtf8::from("wrong passphrase"), "name").unwrap();
+        assert!(client
+            .prune_settled_transactions("name", &wrong_enckey, 100)
+            .is_err());
     }
 
     #[test]
-    fn check_restore_wallet_twice() {
-        let words = Mnemonic::from_secstr(&SecUtf8::from("pony thank pluck sweet bless tuna couple eight stove fluid essay debate cinnamon elite only")).unwrap();
-        let name1 = "Default1";
-        let name2 = "Default2";
-        let passphrase = SecUtf8::from("123456");
+    fn check_watch_only_wallet() {
         let client = DefaultWalletClient::new_read_only(MemoryStorage::default());
-        let enckey1 = client
-            .restore_wallet(name1, &passphrase, &words)
-            .expect("restore wallet 1 failed");
-        let enckey2 = client
-            .restore_wallet(name2, &passphrase, &words)
-            .expect("restore wallet 2 failed");
-        let transfer_address_1 = client
-            .new_transfer_address(name1, &enckey1)
-            .expect("create transfer address 1 failed");
-        let transfer_address_2 = client
-            .new_transfer_address(name2, &enckey2)
-            .expect("create transfer address 2 failed");
-        assert_eq!(transfer_address_1, transfer_address_2);
-        let staking_address_1 = client
-            .new_staking_address(name1, &enckey1)
-            .expect("create staking address 1 failed");
-        let staking_address_2 = client
-            .new_staking_address(name2, &enckey2)
-            .expect("create staking address 2 failed");
-        assert_eq!(staking_address_1, staking_address_2);
-        let transfer_address_22 = client.new_transfer_address(name2, &enckey2).unwrap();
-        assert_ne!(transfer_address_2, transfer_address_22);
-        let transfer_addresses = client.transfer_addresses(name2, &enckey2).unwrap();
-        assert_eq!(transfer_addresses.len(), 2);
+        let passphrase = SecUtf8::from("passphrase");
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let transfer_address = client.new_transfer_address("name", &enckey).unwrap();
+        let staking_address = client.new_staking_address("name", &enckey).unwrap();
+        // public keys which are not 1-of-1 transfer addresses are not exported
+        client.new_public_key("name", &enckey, None).unwrap();
+
+        let info = client.export_watch_only_wallet("name", &enckey).unwrap();
+        assert_eq!(1, info.public_keys.len());
+        assert_eq!(1, info.staking_keys.len());
+        let info: WatchOnlyWalletInfo =
+            serde_json::from_str(&serde_json::to_string(&info).unwrap()).unwrap();
+
+        let watch_enckey = client
+            .new_watch_wallet(
+                "watch",
+                &passphrase,
+                &info.view_key,
+                &info.public_keys,
+                &info.staking_keys,
+            )
+            .unwrap();
+        assert_eq!(
+            WalletKind::WatchOnly,
+            client.get_wallet_kind("watch", &watch_enckey).unwrap()
+        );
+        assert_eq!(
+            client.view_key("name", &enckey).unwrap(),
+            client.view_key("watch", &watch_enckey).unwrap()
+        );
+        assert!(client
+            .transfer_addresses("watch", &watch_enckey)
+            .unwrap()
+            .contains(&transfer_address));
+        assert!(client
+            .staking_addresses("watch", &watch_enckey)
+            .unwrap()
+            .contains(&staking_address));
+
+        // signing and key generation are refused
+        let public_key = &info.public_keys[0];
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .sign_key("watch", &watch_enckey, public_key)
+                .err()
+                .unwrap()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .create_transaction(
+                    "watch",
+                    &watch_enckey,
+                    vec![TxOut::new(transfer_address.clone(), Coin::one())],
+                    TxAttributes::new(171),
+                    None,
+                    transfer_address,
+                )
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::PermissionDenied,
+            client
+                .new_transfer_address("watch", &watch_enckey)
+                .unwrap_err()
+                .kind()
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            client
+                .new_wallet("other", &passphrase, WalletKind::WatchOnly, None, None)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    struct MockTransactionCipher;
+
+    impl crate::TransactionObfuscation for MockTransactionCipher {
+        fn decrypt(
+            &self,
+            _transaction_ids: &[TxId],
+            _private_key: &PrivateKey,
+        ) -> Result<Vec<Transaction>> {
+            unreachable!()
+        }
+
+        fn encrypt(&self, transaction: SignedTransaction) -> Result<TxAux> {
+            match transaction {
+                SignedTransaction::TransferTransaction(ref tx, _) => {
+                    Ok(TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                        inputs: tx.inputs.clone(),
+                        no_of_outputs: tx.outputs.len() as u16,
+                        payload: TxObfuscated {
+                            txid: tx.id(),
+                            key_from: chain_core::state::tendermint::BlockHeight::genesis(),
+                            init_vector: [0u8; 12],
+                            txpayload: transaction.encode(),
+                        },
+                    }))
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    type TestWalletClient = DefaultWalletClient<
+        MemoryStorage,
+        UnauthorizedClient,
+        crate::transaction_builder::DefaultWalletTransactionBuilder<
+            MemoryStorage,
+            chain_core::tx::fee::LinearFee,
+            MockTransactionCipher,
+        >,
+    >;
+
+    fn multisig_test_client(storage: MemoryStorage) -> TestWalletClient {
+        use chain_core::tx::fee::Milli;
+
+        fee_test_client(storage, Milli::new(0, 0))
+    }
+
+    /// Test client with linear fee of given constant and coefficient
+    fn fee_test_client(
+        storage: MemoryStorage,
+        fee: chain_core::tx::fee::Milli,
+    ) -> TestWalletClient {
+        use chain_core::tx::fee::LinearFee;
+
+        let transaction_builder = crate::transaction_builder::DefaultWalletTransactionBuilder::new(
+            crate::signer::WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+            LinearFee::new(fee, fee),
+            MockTransactionCipher,
+        );
+        DefaultWalletClient::new(
+            storage,
+            UnauthorizedClient,
+            transaction_builder,
+            None,
+            HwKeyService::default(),
+        )
+    }
+
+    #[test]
+    fn check_multisig_transfer_flow() {
+        use parity_scale_codec::Decode;
+
+        let passphrase = SecUtf8::from("passphrase");
+        let storages = (0..3).map(|_| MemoryStorage::default()).collect::<Vec<_>>();
+        let clients = storages
+            .iter()
+            .map(|storage| multisig_test_client(storage.clone()))
+            .collect::<Vec<_>>();
+        let enckeys = clients
+            .iter()
+            .map(|client| {
+                client
+                    .new_wallet("cosigner", &passphrase, WalletKind::Basic, None, None)
+                    .unwrap()
+                    .0
+            })
+            .collect::<Vec<_>>();
+        let public_keys = clients
+            .iter()
+            .zip(enckeys.iter())
+            .map(|(client, enckey)| client.new_public_key("cosigner", enckey, None).unwrap())
+            .collect::<Vec<_>>();
+
+        // every cosigner derives the same 2-of-3 address
+        let addresses = clients
+            .iter()
+            .zip(enckeys.iter())
+            .map(|(client, enckey)| {
+                client
+                    .new_multisig_address("cosigner", enckey, public_keys.clone(), 2)
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(addresses[0], addresses[1]);
+        assert_eq!(addresses[0], addresses[2]);
+        assert!(clients[0]
+            .new_multisig_address(
+                "cosigner",
+                &enckeys[0],
+                vec![public_keys[1].clone(), public_keys[2].clone()],
+                2
+            )
+            .is_err());
+
+        // first cosigner owns the multi-sig output and finalizes the transfer
+        let input = TxoPointer::new([1; 32], 0);
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            input.clone(),
+            TxOut::new(addresses[0].clone(), Coin::new(100).unwrap()),
+        );
+        clients[0]
+            .wallet_state_service
+            .apply_memento("cosigner", &enckeys[0], &memento)
+            .unwrap();
+        let unsigned_transaction = Tx::new_with(
+            vec![input],
+            vec![TxOut::new(
+                ExtendedAddr::OrTree([2; 32]),
+                Coin::new(100).unwrap(),
+            )],
+            TxAttributes::new(171),
+        );
+
+        // first and third cosigners sign
+        let signers = vec![public_keys[0].clone(), public_keys[2].clone()];
+        let (session_a, commitment_a) = clients[0]
+            .start_multisig_transfer(
+                "cosigner",
+                &enckeys[0],
+                unsigned_transaction.clone(),
+                signers.clone(),
+            )
+            .unwrap();
+        let (session_c, commitment_c) = clients[2]
+            .start_multisig_transfer(
+                "cosigner",
+                &enckeys[2],
+                unsigned_transaction.clone(),
+                signers,
+            )
+            .unwrap();
+
+        let nonce_a = clients[0]
+            .add_cosigner_commitment(&session_a, &enckeys[0], &public_keys[2], commitment_c)
+            .unwrap()
+            .unwrap();
+        let nonce_c = clients[2]
+            .add_cosigner_commitment(&session_c, &enckeys[2], &public_keys[0], commitment_a)
+            .unwrap()
+            .unwrap();
+
+        // session survives restart of the process
+        let restarted = multisig_test_client(storages[0].clone());
+        let partial_a = restarted
+            .add_cosigner_nonce(&session_a, &enckeys[0], &public_keys[2], nonce_c)
+            .unwrap()
+            .unwrap();
+        let partial_c = clients[2]
+            .add_cosigner_nonce(&session_c, &enckeys[2], &public_keys[0], nonce_a)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            ErrorKind::MultiSigError,
+            restarted
+                .finalize_multisig_transfer("cosigner", &session_a, &enckeys[0])
+                .unwrap_err()
+                .kind()
+        );
+        let partial_psct = restarted
+            .export_psct("cosigner", &session_a, &enckeys[0])
+            .unwrap();
+        assert_eq!(
+            (2, 3),
+            (partial_psct.required_signers, partial_psct.total_signers)
+        );
+        assert!(partial_psct.inputs[0].witness.is_none());
+        assert_eq!(
+            partial_psct,
+            PartiallySignedTransfer::from_armored(&partial_psct.to_armored()).unwrap()
+        );
+        assert!(clients[2]
+            .export_psct("cosigner", &session_c, &enckeys[2])
+            .is_err());
+
+        assert!(restarted
+            .add_cosigner_signature(&session_a, &enckeys[0], &public_keys[2], partial_c)
+            .unwrap());
+        let signed_psct = restarted
+            .export_psct("cosigner", &session_a, &enckeys[0])
+            .unwrap();
+        assert!(signed_psct.inputs[0].witness.is_some());
+        assert_eq!(
+            signed_psct,
+            crate::multi_sig::merge_psct(&partial_psct, &signed_psct).unwrap()
+        );
+        assert!(clients[2]
+            .add_cosigner_signature(&session_c, &enckeys[2], &public_keys[0], partial_a)
+            .unwrap());
+
+        let tx_aux = restarted
+            .finalize_multisig_transfer("cosigner", &session_a, &enckeys[0])
+            .unwrap();
+        match tx_aux {
+            TxAux::EnclaveTx(TxEnclaveAux::TransferTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match SignedTransaction::decode(&mut txpayload.as_slice()).unwrap() {
+                SignedTransaction::TransferTransaction(tx, witness) => {
+                    assert_eq!(unsigned_transaction, tx);
+                    assert!(chain_tx_validation::witness::verify_tx_address(
+                        &witness[0],
+                        &tx.id(),
+                        &addresses[0]
+                    )
+                    .is_ok());
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
     }
 }
@@ -0,0 +1,240 @@
+//! CSV formatting of wallet transaction history
+use std::borrow::Cow;
+use std::io::Write;
+
+use chrono::{SecondsFormat, TimeZone, Utc};
+use indexmap::IndexSet;
+
+use chain_core::init::coin::Coin;
+use chain_core::tx::data::address::ExtendedAddr;
+use client_common::tendermint::types::Time;
+use client_common::{ErrorKind, Result, ResultExt};
+
+use crate::types::{BalanceChange, Depositor, TransactionChange, TransactionType};
+
+/// Column names of exported transaction history
+const HEADER: [&str; 8] = [
+    "date",
+    "txid",
+    "type",
+    "direction",
+    "amount",
+    "fee",
+    "counterparty",
+    "memo",
+];
+
+/// Writes header row of exported transaction history
+pub(crate) fn write_header(writer: &mut dyn Write) -> Result<()> {
+    write_row(writer, &HEADER)
+}
+
+/// Writes a row of exported transaction history for given transaction change (`own_addresses`
+/// are excluded from counterparties)
+pub(crate) fn write_record(
+    writer: &mut dyn Write,
+    change: &TransactionChange,
+    own_addresses: &IndexSet<ExtendedAddr>,
+) -> Result<()> {
+    let (direction, amount) = match change.balance_change {
+        BalanceChange::Incoming { value } => ("incoming", value),
+        BalanceChange::Outgoing { value } => ("outgoing", value),
+        BalanceChange::NoChange => ("none", Coin::zero()),
+    };
+    let date = format_time(&change.block_time)?;
+    let txid = hex::encode(&change.transaction_id);
+    let amount = match change.depositor {
+        Some(Depositor::External { amount: None }) => "unknown".to_owned(),
+        _ => amount.to_string(),
+    };
+    let fee = change.fee_paid.to_coin().to_string();
+    let counterparties = counterparties(change, own_addresses)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let fields: [&str; 8] = [
+        &date,
+        &txid,
+        transaction_type_name(change.transaction_type),
+        direction,
+        &amount,
+        &fee,
+        &counterparties,
+        change.memo.as_ref().map_or("", String::as_str),
+    ];
+    write_row(writer, &fields)
+}
+
+fn write_row(writer: &mut dyn Write, fields: &[&str]) -> Result<()> {
+    let row = fields
+        .iter()
+        .map(|field| escape(field))
+        .collect::<Vec<_>>()
+        .join(",");
+    writeln!(writer, "{}", row)
+        .chain(|| (ErrorKind::IoError, "Unable to write transaction history"))
+}
+
+/// Quotes a field if it contains a separator, quote or line break (doubling its quotes)
+fn escape(field: &str) -> Cow<'_, str> {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Returns addresses (other than wallet's own) receiving outgoing value or sending incoming value
+fn counterparties<'a>(
+    change: &'a TransactionChange,
+    own_addresses: &IndexSet<ExtendedAddr>,
+) -> IndexSet<&'a ExtendedAddr> {
+    let addresses: Box<dyn Iterator<Item = &ExtendedAddr>> = match change.balance_change {
+        BalanceChange::Outgoing { .. } => {
+            Box::new(change.outputs.iter().map(|output| &output.address))
+        }
+        _ => Box::new(
+            change
+                .inputs
+                .iter()
+                .filter_map(|input| input.output.as_ref())
+                .map(|output| &output.address),
+        ),
+    };
+    addresses
+        .filter(|address| !own_addresses.contains(*address))
+        .collect()
+}
+
+fn format_time(time: &Time) -> Result<String> {
+    let seconds = time
+        .duration_since(Time::unix_epoch())
+        .chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("Block time {} is before unix epoch", time.to_rfc3339()),
+            )
+        })?
+        .as_secs();
+    Ok(Utc
+        .timestamp(seconds as i64, 0)
+        .to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+fn transaction_type_name(transaction_type: TransactionType) -> &'static str {
+    match transaction_type {
+        TransactionType::Transfer => "transfer",
+        TransactionType::Withdraw => "withdraw",
+        TransactionType::Unbond => "unbond",
+        TransactionType::Deposit => "deposit",
+        TransactionType::Unjail => "unjail",
+        TransactionType::Nodejoin => "nodejoin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use chain_core::tx::data::input::TxoPointer;
+    use chain_core::tx::data::output::TxOut;
+    use chain_core::tx::fee::Fee;
+
+    use crate::types::TransactionInput;
+
+    fn coin(value: u64) -> Coin {
+        Coin::new(value).unwrap()
+    }
+
+    fn input(address: &ExtendedAddr, value: u64) -> TransactionInput {
+        TransactionInput {
+            pointer: TxoPointer::new([0x11; 32], 0),
+            output: Some(TxOut::new(address.clone(), coin(value))),
+        }
+    }
+
+    #[test]
+    fn check_escape() {
+        assert_eq!("plain text", escape("plain text"));
+        assert_eq!(
+            r#""rent, ""March"" invoice""#,
+            escape(r#"rent, "March" invoice"#)
+        );
+        assert_eq!("\"two\nlines\"", escape("two\nlines"));
+    }
+
+    #[test]
+    fn check_export_golden_file() {
+        let own = ExtendedAddr::OrTree([1; 32]);
+        let sender = ExtendedAddr::OrTree([2; 32]);
+        let first_recipient = ExtendedAddr::OrTree([3; 32]);
+        let second_recipient = ExtendedAddr::OrTree([4; 32]);
+        let own_addresses = vec![own.clone()].into_iter().collect::<IndexSet<_>>();
+
+        let history = vec![
+            TransactionChange {
+                transaction_id: [0xaa; 32],
+                inputs: vec![input(&sender, 10_000_100_000)],
+                outputs: vec![
+                    TxOut::new(own.clone(), coin(10_000_000_000)),
+                    TxOut::new(sender.clone(), coin(99_900)),
+                ],
+                fee_paid: Fee::new(coin(100)),
+                balance_change: BalanceChange::Incoming {
+                    value: coin(10_000_000_000),
+                },
+                transaction_type: TransactionType::Transfer,
+                block_height: 10,
+                block_time: Time::from_str("2020-03-01T08:30:00.123456Z").unwrap(),
+                memo: Some(r#"invoice 7, "March""#.to_owned()),
+                depositor: None,
+            },
+            TransactionChange {
+                transaction_id: [0xbb; 32],
+                inputs: vec![input(&own, 10_000_000_000)],
+                outputs: vec![
+                    TxOut::new(first_recipient, coin(1_000_000_000)),
+                    TxOut::new(second_recipient, coin(2_050_000_000)),
+                    TxOut::new(own.clone(), coin(6_949_999_790)),
+                ],
+                fee_paid: Fee::new(coin(210)),
+                balance_change: BalanceChange::Outgoing {
+                    value: coin(3_050_000_210),
+                },
+                transaction_type: TransactionType::Transfer,
+                block_height: 20,
+                block_time: Time::from_str("2020-03-15T12:00:00Z").unwrap(),
+                memo: None,
+                depositor: None,
+            },
+            TransactionChange {
+                transaction_id: [0xcc; 32],
+                inputs: vec![input(&own, 5_000_000_000)],
+                outputs: Vec::new(),
+                fee_paid: Fee::new(coin(150)),
+                balance_change: BalanceChange::Outgoing {
+                    value: coin(5_000_000_000),
+                },
+                transaction_type: TransactionType::Deposit,
+                block_height: 30,
+                block_time: Time::from_str("2020-03-31T23:59:59Z").unwrap(),
+                memo: None,
+                depositor: None,
+            },
+        ];
+
+        let mut output = Vec::new();
+        write_header(&mut output).unwrap();
+        for change in history.iter() {
+            write_record(&mut output, change, &own_addresses).unwrap();
+        }
+
+        assert_eq!(
+            include_str!("history_csv_test_assets/history.csv"),
+            String::from_utf8(output).unwrap()
+        );
+    }
+}
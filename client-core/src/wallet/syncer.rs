@@ -16,9 +16,11 @@ use client_common::{
     Error, ErrorKind, PrivateKey, Result, ResultExt, SecKey, SecureStorage, Transaction,
 };
 
+use super::address_discovery::{discover_addresses, DEFAULT_GAP_LIMIT};
 use super::syncer_logic::handle_blocks;
 use crate::service;
 use crate::service::{KeyService, SyncState, Wallet, WalletState, WalletStateMemento};
+use crate::types::WalletKind;
 use crate::TransactionObfuscation;
 
 /// Transaction decryptor interface for wallet synchronizer
@@ -71,6 +73,8 @@ pub struct ObfuscationSyncerConfig<S: SecureStorage, C: Client, O: TransactionOb
     pub enable_fast_forward: bool,
     pub batch_size: usize,
     pub block_height_ensure: u64,
+    /// Number of consecutive unused addresses after which address discovery of HD wallet stops
+    pub gap_limit: usize,
 }
 
 impl<S: SecureStorage, C: Client, O: TransactionObfuscation> ObfuscationSyncerConfig<S, C, O> {
@@ -90,8 +94,15 @@ impl<S: SecureStorage, C: Client, O: TransactionObfuscation> ObfuscationSyncerCo
             enable_fast_forward,
             batch_size,
             block_height_ensure,
+            gap_limit: DEFAULT_GAP_LIMIT,
         }
     }
+
+    /// Sets number of consecutive unused addresses after which address discovery stops
+    pub fn with_gap_limit(mut self, gap_limit: usize) -> Self {
+        self.gap_limit = gap_limit;
+        self
+    }
 }
 
 /// Common configs for wallet syncer
@@ -105,6 +116,7 @@ pub struct SyncerConfig<S: SecureStorage, C: Client> {
     enable_fast_forward: bool,
     batch_size: usize,
     block_height_ensure: u64,
+    gap_limit: usize,
 }
 
 /// Wallet Syncer
@@ -116,6 +128,7 @@ pub struct WalletSyncer<S: SecureStorage, C: Client, D: TxDecryptor> {
     enable_fast_forward: bool,
     batch_size: usize,
     block_height_ensure: u64,
+    gap_limit: usize,
 
     // wallet
     decryptor: D,
@@ -145,6 +158,7 @@ where
             enable_fast_forward: config.enable_fast_forward,
             batch_size: config.batch_size,
             block_height_ensure: config.block_height_ensure,
+            gap_limit: config.gap_limit,
         }
     }
 
@@ -156,8 +170,25 @@ where
     }
 
     /// Load wallet state in memory, sync it to most recent latest, then drop the memory cache.
+    ///
+    /// Storage is migrated to current schema version first (fails if it was written by a newer
+    /// client). Used addresses of HD wallet beyond its current indices are discovered after
+    /// syncing.
     pub fn sync<F: FnMut(ProgressReport) -> bool>(&self, callback: F) -> Result<()> {
-        WalletSyncerImpl::new(self, callback)?.sync()
+        service::migrate_storage(&self.storage)?;
+        let mut syncer = WalletSyncerImpl::new(self, callback)?;
+        syncer.sync()?;
+
+        if syncer.wallet.wallet_kind == WalletKind::HD {
+            discover_addresses(
+                &self.storage,
+                &self.client,
+                &self.name,
+                &self.enckey,
+                self.gap_limit,
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -193,6 +224,7 @@ where
                 enable_fast_forward: config.enable_fast_forward,
                 batch_size: config.batch_size,
                 block_height_ensure: config.block_height_ensure,
+                gap_limit: config.gap_limit,
             },
             decryptor,
             name,
@@ -392,7 +424,8 @@ impl<'a, S: SecureStorage, C: Client, D: TxDecryptor, F: FnMut(ProgressReport) -
         for tx_id in
             state.get_rollback_pending_tx(current_block_height, self.env.block_height_ensure)
         {
-            memento.remove_pending_transaction(tx_id);
+            // inputs are unlocked, the transaction is listed as expired
+            memento.settle_pending_transaction(tx_id);
         }
         self.save(&memento)
     }
@@ -546,8 +579,12 @@ mod tests {
     use client_common::tendermint::{lite, Client};
     use test_common::block_generator::{BlockGenerator, GeneratorClient};
 
-    use crate::service::save_sync_state;
-    use crate::types::WalletKind;
+    use chain_core::init::coin::Coin;
+    use chain_core::tx::data::input::TxoPointer;
+    use chain_core::tx::data::output::TxOut;
+
+    use crate::service::{save_sync_state, WalletStateService};
+    use crate::types::{PendingTransactionStatus, TransactionPending, WalletKind};
     use crate::wallet::{DefaultWalletClient, WalletClient};
 
     fn check_wallet_syncer_impl(enable_fast_forward: bool) {
@@ -559,7 +596,7 @@ mod tests {
         let wallet = DefaultWalletClient::new_read_only(storage.clone());
 
         let (enckey, _) = wallet
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
 
         let client = GeneratorClient::new(BlockGenerator::one_node());
@@ -577,6 +614,7 @@ mod tests {
                 enable_fast_forward,
                 batch_size: 20,
                 block_height_ensure: 50,
+                gap_limit: DEFAULT_GAP_LIMIT,
             },
             |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
             name.to_owned(),
@@ -591,6 +629,93 @@ mod tests {
         check_wallet_syncer_impl(true);
     }
 
+    #[test]
+    fn check_wallet_syncer_expires_pending_tx() {
+        let storage = MemoryStorage::default();
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let wallet = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = wallet.new_transfer_address(name, &enckey).unwrap();
+
+        // transfer spending the only unspent output is broadcast, but never included in a block
+        let input = TxoPointer::new([1; 32], 0);
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(input.clone(), TxOut::new(address, Coin::unit()));
+        WalletStateService::new(storage.clone())
+            .apply_memento(name, &enckey, &memento)
+            .unwrap();
+        wallet
+            .update_tx_pending_state(
+                name,
+                &enckey,
+                [2; 32],
+                TransactionPending {
+                    used_inputs: vec![input.clone()],
+                    block_height: 1,
+                    return_amount: Coin::zero(),
+                    tx_id: [2; 32],
+                    valid_until_height: 5,
+                },
+            )
+            .unwrap();
+        assert!(wallet
+            .unspent_transactions(name, &enckey)
+            .unwrap()
+            .is_empty());
+
+        let client = GeneratorClient::new(BlockGenerator::one_node());
+        {
+            let mut gen = client.gen.write().unwrap();
+            for _ in 0..10 {
+                gen.gen_block(&[]);
+            }
+        }
+        let syncer = WalletSyncer::with_config(
+            SyncerConfig {
+                storage,
+                client,
+                enable_fast_forward: false,
+                batch_size: 20,
+                block_height_ensure: 50,
+                gap_limit: DEFAULT_GAP_LIMIT,
+            },
+            |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
+            name.to_owned(),
+            enckey.clone(),
+        );
+        syncer.sync(|_| true).expect("Unable to synchronize");
+
+        // inputs are selectable again
+        let unspent_transactions = wallet.unspent_transactions(name, &enckey).unwrap();
+        assert_eq!(1, unspent_transactions.len());
+        assert_eq!(input, unspent_transactions[0].0);
+        assert!(unspent_transactions.select(Coin::unit()).is_ok());
+
+        let pending = wallet.list_pending_transactions(name, &enckey).unwrap();
+        assert_eq!(1, pending.len());
+        assert_eq!([2; 32], pending[0].pending.tx_id);
+        assert_eq!(PendingTransactionStatus::Expired, pending[0].status);
+
+        wallet
+            .forget_pending_transaction(name, &enckey, &[2; 32])
+            .unwrap();
+        assert!(wallet
+            .list_pending_transactions(name, &enckey)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            wallet
+                .forget_pending_transaction(name, &enckey, &[2; 32])
+                .unwrap_err()
+                .kind()
+        );
+    }
+
     #[test]
     fn check_wallet_syncer_app_hash_on_multiple_tx() {
         #[derive(Clone)]
@@ -652,6 +777,17 @@ mod tests {
             }
 
             /// Match batch state `abci_query` call to tendermint
+            fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+                unreachable!()
+            }
+
+            fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+                &self,
+                _queries: T,
+            ) -> Result<Vec<AbciQuery>> {
+                unreachable!()
+            }
+
             fn query_state_batch<T: Iterator<Item = u64>>(
                 &self,
                 _heights: T,
@@ -683,7 +819,7 @@ mod tests {
         let wallet = DefaultWalletClient::new_read_only(storage.clone());
 
         let (wallet_enckey, _) = wallet
-            .new_wallet(name, &wallet_passphrase, WalletKind::Basic)
+            .new_wallet(name, &wallet_passphrase, WalletKind::Basic, None, None)
             .expect("create wallet failed");
         let client = MockTendermintClient {};
 
@@ -695,6 +831,7 @@ mod tests {
                 enable_fast_forward,
                 batch_size: 20,
                 block_height_ensure: 50,
+                gap_limit: DEFAULT_GAP_LIMIT,
             },
             |_txids: &[TxId]| -> Result<Vec<Transaction>> { Ok(vec![]) },
             name.to_owned(),
@@ -1,10 +1,11 @@
 use non_empty_vec::NonEmpty;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use thiserror::Error;
 
 use chain_core::init::coin::{sum_coins, Coin, CoinError};
 use chain_core::tx::{
     data::{
+        address::ExtendedAddr,
         input::{TxoPointer, TxoSize},
         output::TxOut,
         TxId,
@@ -17,7 +18,10 @@ use client_common::Transaction;
 
 use super::syncer::FilteredBlock;
 use crate::service::{Wallet, WalletState};
-use crate::types::{BalanceChange, TransactionChange, TransactionInput, TransactionType};
+use crate::types::{
+    BalanceChange, Depositor, PendingReconciliation, TransactionChange, TransactionInput,
+    TransactionPending, TransactionType,
+};
 use crate::WalletStateMemento;
 
 #[derive(Error, Debug)]
@@ -72,6 +76,18 @@ pub(crate) fn handle_blocks(
                     block.block_height,
                     block.block_time,
                 )?;
+            } else if let Some(fee) = block.valid_transaction_fees.get(txid) {
+                // Transaction content is not visible to this wallet
+                if let Some(pending) = wallet_state.pending_transactions.get(txid) {
+                    for input in pending.used_inputs.iter() {
+                        memento.remove_unspent_transaction(input.clone());
+                    }
+                    record_reconciliation(
+                        &mut memento,
+                        txid,
+                        reconcile_pending(wallet, wallet_state, txid, None, *fee),
+                    );
+                }
             }
         }
     }
@@ -90,7 +106,31 @@ pub fn create_transaction_change(
     let outputs = transaction.outputs().to_vec();
     let transaction_type = TransactionType::from(transaction);
     let inputs = decorate_inputs(wallet_state, transaction.inputs(), &transaction_id)?;
-    let balance_change = calculate_balance_change(wallet, &transaction_id, &inputs, &outputs)?;
+    let mut balance_change = calculate_balance_change(wallet, &transaction_id, &inputs, &outputs)?;
+    // deposits to staking addresses of wallet are recorded even if paid by another wallet
+    let depositor = match transaction {
+        Transaction::DepositStakeTransaction(tx)
+            if wallet.staking_addresses().contains(&tx.to_staked_account) =>
+        {
+            let depositor = deposit_depositor(wallet, &inputs, fee_paid);
+            if let Depositor::External { amount } = depositor {
+                balance_change = BalanceChange::Incoming {
+                    value: amount.unwrap_or_else(Coin::zero),
+                };
+            }
+            Some(depositor)
+        }
+        _ => None,
+    };
+    // memos are validated by sender only, so invalid UTF-8 is replaced
+    let memo = match transaction {
+        Transaction::TransferTransaction(tx) => tx
+            .attributes
+            .memo
+            .as_ref()
+            .map(|memo| String::from_utf8_lossy(memo).into_owned()),
+        _ => None,
+    };
 
     let transaction_change = TransactionChange {
         transaction_id,
@@ -101,6 +141,8 @@ pub fn create_transaction_change(
         transaction_type,
         block_height,
         block_time,
+        memo,
+        depositor,
     };
     Ok(transaction_change)
 }
@@ -139,11 +181,227 @@ pub(crate) fn handle_transaction(
         }
     }
 
-    memento.remove_pending_transaction(transaction_change.transaction_id);
+    let reconciliation = reconcile_pending(
+        wallet,
+        wallet_state,
+        &transaction_change.transaction_id,
+        Some(transaction),
+        fee_paid,
+    );
+    record_reconciliation(memento, &transaction_change.transaction_id, reconciliation);
     memento.add_transaction_change(transaction_change);
     Ok(())
 }
 
+/// Update wallet state after transfer addresses are added to wallet (e.g., by address discovery):
+/// outputs to them which are not spent yet become unspent transactions, and balance changes of
+/// transactions involving them are recalculated
+pub(crate) fn handle_new_addresses(
+    wallet: &Wallet,
+    wallet_state: &WalletState,
+    addresses: &HashSet<ExtendedAddr>,
+) -> Result<WalletStateMemento, SyncerLogicError> {
+    let spent_pointers = wallet_state
+        .transaction_history
+        .values()
+        .flat_map(|change| change.inputs.iter().map(|input| &input.pointer))
+        .collect::<BTreeSet<_>>();
+    let mut memento = WalletStateMemento::default();
+
+    for change in wallet_state
+        .transaction_log
+        .iter()
+        .filter_map(|txid| wallet_state.transaction_history.get(txid))
+    {
+        let mut involved = false;
+        for (i, output) in change.outputs.iter().enumerate() {
+            if addresses.contains(&output.address) {
+                involved = true;
+                let pointer = TxoPointer::new(change.transaction_id, i);
+                if !spent_pointers.contains(&pointer) {
+                    memento.add_unspent_transaction(pointer, output.clone());
+                }
+            }
+        }
+        involved |= change
+            .inputs
+            .iter()
+            .filter_map(|input| input.output.as_ref())
+            .any(|output| addresses.contains(&output.address));
+
+        if involved {
+            let balance_change = calculate_balance_change(
+                wallet,
+                &change.transaction_id,
+                &change.inputs,
+                &change.outputs,
+            )?;
+            if balance_change != change.balance_change {
+                memento.update_transaction_change(TransactionChange {
+                    balance_change,
+                    ..change.clone()
+                });
+            }
+        }
+    }
+    Ok(memento)
+}
+
+/// Settles pending record of a confirmed transaction in memento, recording outcome of its
+/// reconciliation (a mismatch is also logged as a warning)
+fn record_reconciliation(
+    memento: &mut WalletStateMemento,
+    txid: &TxId,
+    reconciliation: Option<PendingReconciliation>,
+) {
+    memento.settle_pending_transaction(*txid);
+
+    if let Some(reconciliation) = reconciliation {
+        if reconciliation != PendingReconciliation::ConfirmedVerified {
+            log::warn!(
+                "Pending transaction {} confirmed: {:?}",
+                hex::encode(txid),
+                reconciliation
+            );
+        }
+        memento.reconcile_pending_transaction(*txid, reconciliation);
+    }
+}
+
+/// Reconcile a confirmed transaction with its pending record, returns `None` if it was not pending
+pub(crate) fn reconcile_pending(
+    wallet: &Wallet,
+    wallet_state: &WalletState,
+    txid: &TxId,
+    transaction: Option<&Transaction>,
+    fee_paid: Fee,
+) -> Option<PendingReconciliation> {
+    let pending = wallet_state.pending_transactions.get(txid)?;
+    Some(match transaction {
+        Some(transaction) => {
+            reconcile_pending_transaction(wallet, wallet_state, pending, transaction, fee_paid)
+        }
+        None => PendingReconciliation::ConfirmedUnverified,
+    })
+}
+
+/// Compare a confirmed transaction against the pending record stored when it was broadcast
+fn reconcile_pending_transaction(
+    wallet: &Wallet,
+    wallet_state: &WalletState,
+    pending: &TransactionPending,
+    transaction: &Transaction,
+    fee_paid: Fee,
+) -> PendingReconciliation {
+    let mut diffs = Vec::new();
+
+    if !pending.used_inputs.is_empty() {
+        let mut expected = pending.used_inputs.clone();
+        let mut actual = transaction.inputs().to_vec();
+        expected.sort();
+        actual.sort();
+        if expected != actual {
+            diffs.push(format!("inputs: expected {:?}, got {:?}", expected, actual));
+        }
+    }
+
+    let outputs = transaction.outputs();
+    match transaction {
+        Transaction::WithdrawUnbondedStakeTransaction(_) => {
+            match sum_outputs(outputs.iter()) {
+                Ok(total) if total == pending.return_amount => {}
+                Ok(total) => diffs.push(format!(
+                    "output value: expected {}, got {}",
+                    pending.return_amount, total
+                )),
+                Err(_) => diffs.push("output value: total out of bound".to_owned()),
+            }
+            // chain only accepts withdraw outputs locked until the account is unbonded
+            for (i, output) in outputs.iter().enumerate() {
+                if output.valid_from.is_none() {
+                    diffs.push(format!(
+                        "output {} timelock: expected a timelock, got none",
+                        i
+                    ));
+                }
+            }
+        }
+        Transaction::TransferTransaction(_) => {
+            let transfer_addresses = wallet.transfer_addresses();
+            let returned = outputs
+                .iter()
+                .filter(|output| transfer_addresses.contains(&output.address));
+            // change is never timelocked, so locked outputs to wallet do not count as returned
+            match (
+                sum_outputs(returned.clone()),
+                sum_outputs(returned.filter(|output| output.valid_from.is_none())),
+            ) {
+                (Ok(_), Ok(unlocked)) if unlocked >= pending.return_amount => {}
+                (Ok(total), Ok(unlocked)) if total >= pending.return_amount => diffs.push(format!(
+                    "return timelock: expected {} unlocked, got {}",
+                    pending.return_amount, unlocked
+                )),
+                (Ok(total), _) => diffs.push(format!(
+                    "return amount: expected at least {}, got {}",
+                    pending.return_amount, total
+                )),
+                _ => diffs.push("return amount: total out of bound".to_owned()),
+            }
+
+            // fee of a transfer is the spent value which is not in its outputs (only known when
+            // all spent outputs are known)
+            let spent = pending
+                .used_inputs
+                .iter()
+                .map(|input| wallet_state.unspent_transactions.get(input))
+                .collect::<Option<Vec<_>>>();
+            if let Some(spent) = spent.filter(|spent| !spent.is_empty()) {
+                let expected_fee =
+                    match (sum_outputs(spent.into_iter()), sum_outputs(outputs.iter())) {
+                        (Ok(spent), Ok(sent)) => (spent - sent).ok(),
+                        _ => None,
+                    };
+                match expected_fee {
+                    Some(expected_fee) if expected_fee == fee_paid.to_coin() => {}
+                    Some(expected_fee) => diffs.push(format!(
+                        "fee: expected {}, got {}",
+                        expected_fee,
+                        fee_paid.to_coin()
+                    )),
+                    None => diffs.push(format!(
+                        "fee: outputs exceed spent value (fee paid {})",
+                        fee_paid.to_coin()
+                    )),
+                }
+            }
+        }
+        Transaction::DepositStakeTransaction(_) => {
+            // deposited value is what remains of spent value after fee
+            let spent = pending
+                .used_inputs
+                .iter()
+                .map(|input| wallet_state.unspent_transactions.get(input))
+                .collect::<Option<Vec<_>>>();
+            if let Some(Ok(spent)) = spent.map(|spent| sum_outputs(spent.into_iter())) {
+                if fee_paid.to_coin() > spent {
+                    diffs.push(format!(
+                        "fee: expected at most {}, got {}",
+                        spent,
+                        fee_paid.to_coin()
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+
+    if diffs.is_empty() {
+        PendingReconciliation::ConfirmedVerified
+    } else {
+        PendingReconciliation::ConfirmedMismatch { diffs }
+    }
+}
+
 fn decorate_inputs(
     wallet_state: &WalletState,
     raw_inputs: &[TxoPointer],
@@ -162,6 +420,31 @@ fn decorate_inputs(
         .collect()
 }
 
+/// Returns depositor of deposit with given inputs (amount of deposit paid by another wallet is only
+/// known if outputs spent by all its inputs are visible to this wallet)
+fn deposit_depositor(wallet: &Wallet, inputs: &[TransactionInput], fee_paid: Fee) -> Depositor {
+    let transfer_addresses = wallet.transfer_addresses();
+    let spent_outputs = inputs
+        .iter()
+        .map(|input| input.output.as_ref())
+        .collect::<Option<Vec<_>>>();
+
+    if inputs.iter().any(|input| {
+        input
+            .output
+            .as_ref()
+            .map_or(false, |output| transfer_addresses.contains(&output.address))
+    }) {
+        return Depositor::Wallet;
+    }
+
+    let amount = spent_outputs
+        .filter(|outputs| !outputs.is_empty())
+        .and_then(|outputs| sum_outputs(outputs.into_iter()).ok())
+        .and_then(|total| (total - fee_paid.to_coin()).ok());
+    Depositor::External { amount }
+}
+
 fn sum_outputs<'a>(outputs: impl Iterator<Item = &'a TxOut>) -> Result<Coin, CoinError> {
     sum_coins(outputs.map(|output| output.value))
 }
@@ -233,7 +516,9 @@ mod tests {
     use std::str::FromStr;
 
     use chain_core::init::{address::RedeemAddress, coin::Coin};
-    use chain_core::state::account::{StakedStateAddress, StakedStateOpAttributes, UnbondTx};
+    use chain_core::state::account::{
+        DepositBondTx, StakedStateAddress, StakedStateOpAttributes, UnbondTx, WithdrawUnbondedTx,
+    };
     use chain_core::tx::data::{address::ExtendedAddr, attribute::TxAttributes, output::TxOut, Tx};
     use chain_core::tx::fee::Fee;
     use chain_core::tx::TransactionId;
@@ -242,7 +527,7 @@ mod tests {
 
     use super::*;
     use crate::service::load_wallet;
-    use crate::types::WalletKind;
+    use crate::types::{PendingTransactionStatus, WalletKind};
     use crate::wallet::{DefaultWalletClient, WalletClient};
 
     fn create_test_wallet(n: usize) -> Result<Vec<Wallet>> {
@@ -254,7 +539,7 @@ mod tests {
                 let name = format!("name{}", i);
                 let passphrase = SecUtf8::from("passphrase");
                 let (enckey, _) = wallet
-                    .new_wallet(&name, &passphrase, WalletKind::Basic)
+                    .new_wallet(&name, &passphrase, WalletKind::Basic, None, None)
                     .expect("new wallet");
                 wallet
                     .new_transfer_address(&name, &enckey)
@@ -337,6 +622,282 @@ mod tests {
         assert!(state.transaction_history.contains_key(&tx_cloned.id()));
     }
 
+    #[test]
+    fn check_syncer_logic_external_deposit() {
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = client
+            .new_wallet(
+                "name",
+                &SecUtf8::from("passphrase"),
+                WalletKind::Basic,
+                None,
+                None,
+            )
+            .unwrap();
+        client.new_transfer_address("name", &enckey).unwrap();
+        let staking_address = client.new_staking_address("name", &enckey).unwrap();
+        let wallet = load_wallet(&storage, "name", &enckey).unwrap().unwrap();
+
+        // coins sent to another wallet earlier are visible to this wallet
+        let transfer = transfer_transaction();
+        let mut state = WalletState::default();
+        let blocks = [block_header(
+            &[wallet.view_key.clone()],
+            &[transfer.clone()],
+            &[],
+        )];
+        let memento = handle_blocks(&wallet, &state, &blocks, &[transfer.clone()]).unwrap();
+        state.apply_memento(&memento).unwrap();
+
+        let deposit = |input| {
+            Transaction::DepositStakeTransaction(DepositBondTx::new(
+                vec![input],
+                staking_address,
+                StakedStateOpAttributes::new(0),
+            ))
+        };
+        let unknown = deposit(TxoPointer::new([7; 32], 0));
+        let known = deposit(TxoPointer::new(transfer.id(), 0));
+        let blocks = [block_header(&[], &[], &[unknown.clone(), known.clone()])];
+        let memento = handle_blocks(&wallet, &state, &blocks, &[]).unwrap();
+        state.apply_memento(&memento).unwrap();
+
+        let change = state.get_transaction_change(&unknown.id()).unwrap();
+        assert_eq!(TransactionType::Deposit, change.transaction_type);
+        assert_eq!(
+            BalanceChange::Incoming {
+                value: Coin::zero()
+            },
+            change.balance_change
+        );
+        assert_eq!(Some(Depositor::External { amount: None }), change.depositor);
+
+        // fee is deducted from spent outputs
+        let change = state.get_transaction_change(&known.id()).unwrap();
+        assert_eq!(
+            BalanceChange::Incoming {
+                value: Coin::new(99).unwrap()
+            },
+            change.balance_change
+        );
+        assert_eq!(
+            Some(Depositor::External {
+                amount: Some(Coin::new(99).unwrap())
+            }),
+            change.depositor
+        );
+    }
+
+    #[test]
+    fn check_syncer_logic_memo() {
+        let wallets = create_test_wallet(1).unwrap();
+        let address = wallets[0].transfer_addresses().into_iter().next().unwrap();
+        let tx = Transaction::TransferTransaction(Tx::new_with(
+            Vec::new(),
+            vec![TxOut::new(address, Coin::new(100).unwrap())],
+            TxAttributes::new(0).with_memo(b"exchange tag, \"42\"".to_vec()),
+        ));
+        let blocks = [block_header(
+            &[wallets[0].view_key.clone()],
+            &[tx.clone()],
+            &[],
+        )];
+
+        let mut state = WalletState::default();
+        let memento = handle_blocks(&wallets[0], &state, &blocks, &[tx.clone()]).unwrap();
+        state.apply_memento(&memento).expect("apply memento");
+
+        let change = state.get_transaction_change(&tx.id()).unwrap();
+        assert_eq!(Some("exchange tag, \"42\"".to_owned()), change.memo);
+    }
+
+    fn withdraw_transaction(value: u64) -> Transaction {
+        Transaction::WithdrawUnbondedStakeTransaction(WithdrawUnbondedTx::new(
+            0,
+            vec![TxOut::new_with_timelock(
+                ExtendedAddr::OrTree([0; 32]),
+                Coin::new(value).unwrap(),
+                0,
+            )],
+            TxAttributes::default(),
+        ))
+    }
+
+    fn pending_state(txid: TxId, return_amount: u64) -> WalletState {
+        pending_state_with_inputs(txid, return_amount, Vec::new())
+    }
+
+    fn pending_state_with_inputs(
+        txid: TxId,
+        return_amount: u64,
+        used_inputs: Vec<TxoPointer>,
+    ) -> WalletState {
+        let mut state = WalletState::default();
+        state.pending_transactions.insert(
+            txid,
+            TransactionPending {
+                used_inputs,
+                block_height: 1,
+                return_amount: Coin::new(return_amount).unwrap(),
+                tx_id: txid,
+                valid_until_height: 51,
+            },
+        );
+        state
+    }
+
+    #[test]
+    fn check_reconcile_pending_withdraw() {
+        let wallets = create_test_wallet(1).unwrap();
+        let tx = withdraw_transaction(100);
+        let txid = tx.id();
+
+        let fee = Fee::new(Coin::one());
+
+        let state = pending_state(txid, 100);
+        assert_eq!(
+            reconcile_pending(&wallets[0], &state, &txid, Some(&tx), fee),
+            Some(PendingReconciliation::ConfirmedVerified)
+        );
+
+        let state = pending_state(txid, 90);
+        match reconcile_pending(&wallets[0], &state, &txid, Some(&tx), fee) {
+            Some(PendingReconciliation::ConfirmedMismatch { diffs }) => assert_eq!(diffs.len(), 1),
+            other => panic!("unexpected reconciliation: {:?}", other),
+        }
+
+        // withdraw outputs have to be timelocked
+        let unlocked = Transaction::WithdrawUnbondedStakeTransaction(WithdrawUnbondedTx::new(
+            0,
+            vec![TxOut::new(
+                ExtendedAddr::OrTree([0; 32]),
+                Coin::new(100).unwrap(),
+            )],
+            TxAttributes::default(),
+        ));
+        let state = pending_state(unlocked.id(), 100);
+        match reconcile_pending(&wallets[0], &state, &unlocked.id(), Some(&unlocked), fee) {
+            Some(PendingReconciliation::ConfirmedMismatch { diffs }) => {
+                assert_eq!(diffs.len(), 1);
+                assert!(diffs[0].contains("timelock"));
+            }
+            other => panic!("unexpected reconciliation: {:?}", other),
+        }
+
+        let state = pending_state(txid, 100);
+        assert_eq!(
+            reconcile_pending(&wallets[0], &state, &txid, None, fee),
+            Some(PendingReconciliation::ConfirmedUnverified)
+        );
+        assert_eq!(
+            reconcile_pending(&wallets[0], &WalletState::default(), &txid, Some(&tx), fee),
+            None
+        );
+    }
+
+    #[test]
+    fn check_reconcile_pending_transfer() {
+        let wallets = create_test_wallet(1).unwrap();
+        let address = wallets[0].transfer_addresses().into_iter().next().unwrap();
+        let input = TxoPointer::new([7; 32], 0);
+        let transfer = |change: TxOut| {
+            Transaction::TransferTransaction(Tx::new_with(
+                vec![input.clone()],
+                vec![
+                    TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(30).unwrap()),
+                    change,
+                ],
+                TxAttributes::default(),
+            ))
+        };
+        let reconcile = |tx: &Transaction, fee: u64| {
+            let mut state = pending_state_with_inputs(tx.id(), 60, vec![input.clone()]);
+            state.unspent_transactions.insert(
+                input.clone(),
+                TxOut::new(address.clone(), Coin::new(100).unwrap()),
+            );
+            reconcile_pending(
+                &wallets[0],
+                &state,
+                &tx.id(),
+                Some(tx),
+                Fee::new(Coin::new(fee).unwrap()),
+            )
+            .unwrap()
+        };
+        let diffs = |reconciliation| match reconciliation {
+            PendingReconciliation::ConfirmedMismatch { diffs } => diffs,
+            other => panic!("unexpected reconciliation: {:?}", other),
+        };
+
+        let tx = transfer(TxOut::new(address.clone(), Coin::new(60).unwrap()));
+        assert_eq!(PendingReconciliation::ConfirmedVerified, reconcile(&tx, 10));
+
+        // fee is not what remains of spent value
+        let mismatch = diffs(reconcile(&tx, 1));
+        assert_eq!(1, mismatch.len());
+        assert!(mismatch[0].starts_with("fee"));
+
+        // change is timelocked
+        let tx = transfer(TxOut::new_with_timelock(
+            address.clone(),
+            Coin::new(60).unwrap(),
+            1_000,
+        ));
+        let mismatch = diffs(reconcile(&tx, 10));
+        assert_eq!(1, mismatch.len());
+        assert!(mismatch[0].starts_with("return timelock"));
+
+        // change value differs
+        let tx = transfer(TxOut::new(address.clone(), Coin::new(50).unwrap()));
+        let mismatch = diffs(reconcile(&tx, 20));
+        assert_eq!(1, mismatch.len());
+        assert!(mismatch[0].starts_with("return amount"));
+    }
+
+    #[test]
+    fn check_syncer_logic_records_reconciliation() {
+        let wallets = create_test_wallet(1).unwrap();
+        let view_keys = [wallets[0].view_key.clone()];
+        let withdraw = withdraw_transaction(100);
+        let mismatched = withdraw_transaction(90);
+
+        let mut state = pending_state(withdraw.id(), 100);
+        state.pending_transactions.extend(
+            pending_state(mismatched.id(), 100)
+                .pending_transactions
+                .into_iter(),
+        );
+
+        // content of `withdraw` is not available to the wallet
+        let blocks = [block_header(
+            &view_keys,
+            &[withdraw.clone(), mismatched.clone()],
+            &[],
+        )];
+        let memento = handle_blocks(&wallets[0], &state, &blocks, &[mismatched.clone()]).unwrap();
+        state.apply_memento(&memento).unwrap();
+
+        assert!(state.pending_transactions.is_empty());
+        let reconciliations = state
+            .list_pending_transactions()
+            .into_iter()
+            .map(|info| {
+                assert_eq!(PendingTransactionStatus::Confirmed, info.status);
+                (info.pending.tx_id, info.reconciliation.unwrap())
+            })
+            .collect::<HashMap<_, _>>();
+        assert_eq!(
+            PendingReconciliation::ConfirmedUnverified,
+            reconciliations[&withdraw.id()]
+        );
+        match &reconciliations[&mismatched.id()] {
+            PendingReconciliation::ConfirmedMismatch { diffs } => assert_eq!(1, diffs.len()),
+            other => panic!("unexpected reconciliation: {:?}", other),
+        }
+    }
+
     fn transfer_transactions(addresses: [ExtendedAddr; 2]) -> [Transaction; 2] {
         let transaction1 = Transaction::TransferTransaction(Tx::new_with(
             Vec::new(),
@@ -412,4 +973,42 @@ mod tests {
         assert_eq!(states[1].transaction_history.len(), 1);
         assert_eq!(states[1].unspent_transactions.len(), 1);
     }
+
+    #[test]
+    fn check_syncer_logic_watch_only() {
+        let storage = MemoryStorage::default();
+        let client = DefaultWalletClient::new_read_only(storage.clone());
+        let passphrase = SecUtf8::from("passphrase");
+        let (enckey, _) = client
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = client.new_transfer_address("name", &enckey).unwrap();
+
+        let info = client.export_watch_only_wallet("name", &enckey).unwrap();
+        let watch_enckey = client
+            .new_watch_wallet(
+                "watch",
+                &passphrase,
+                &info.view_key,
+                &info.public_keys,
+                &info.staking_keys,
+            )
+            .unwrap();
+        let wallet = load_wallet(&storage, "watch", &watch_enckey)
+            .unwrap()
+            .unwrap();
+
+        let tx = Transaction::TransferTransaction(Tx::new_with(
+            Vec::new(),
+            vec![TxOut::new(address, Coin::new(100).unwrap())],
+            TxAttributes::default(),
+        ));
+        let blocks = [block_header(&[wallet.view_key.clone()], &[tx.clone()], &[])];
+
+        let mut state = WalletState::default();
+        let memento = handle_blocks(&wallet, &state, &blocks, &[tx]).unwrap();
+        state.apply_memento(&memento).expect("apply memento");
+        assert_eq!(Coin::new(100).unwrap(), state.get_balance().unwrap().total);
+        assert_eq!(1, state.transaction_history.len());
+    }
 }
@@ -0,0 +1,51 @@
+//! Discovery of optional features supported by current client build
+use chain_core::APP_VERSION;
+use client_common::storage::storage_backends;
+
+use crate::types::ClientCapabilities;
+
+/// Returns optional features supported by current client build
+pub fn client_capabilities() -> ClientCapabilities {
+    ClientCapabilities {
+        crate_version: env!("CARGO_PKG_VERSION").to_owned(),
+        min_app_version: APP_VERSION,
+        max_app_version: APP_VERSION,
+        storage_backends: storage_backends()
+            .into_iter()
+            .map(ToOwned::to_owned)
+            .collect(),
+        websocket_rpc: cfg!(feature = "websocket-rpc"),
+        mock_hardware_wallet: cfg!(feature = "mock-hardware-wallet"),
+        offline_signing: true,
+        network_ops: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_client_capabilities() {
+        let capabilities = client_capabilities();
+
+        assert_eq!(env!("CARGO_PKG_VERSION"), capabilities.crate_version);
+        assert_eq!(APP_VERSION, capabilities.min_app_version);
+        assert_eq!(APP_VERSION, capabilities.max_app_version);
+        assert!(capabilities.offline_signing);
+        assert!(capabilities.storage_backends.contains(&"memory".to_owned()));
+        assert_eq!(None, capabilities.network_ops);
+    }
+
+    #[test]
+    #[cfg(feature = "mock-hardware-wallet")]
+    fn check_client_capabilities_with_mock_hardware_wallet() {
+        assert!(client_capabilities().mock_hardware_wallet);
+    }
+
+    #[test]
+    #[cfg(not(feature = "mock-hardware-wallet"))]
+    fn check_client_capabilities_without_mock_hardware_wallet() {
+        assert!(!client_capabilities().mock_hardware_wallet);
+    }
+}
@@ -0,0 +1,215 @@
+//! Validation of council node metadata used in node join transactions
+use chain_core::state::account::CouncilNode;
+use chain_core::state::tendermint::TendermintValidatorPubKey;
+use client_common::{Error, ErrorKind, Result};
+
+/// Maximum length (in bytes) of validator name and security contact accepted by chain
+pub const MAX_COUNCIL_NODE_STRING_LEN: usize = 255;
+
+/// Certificates used as placeholders in node join transactions before keypackages were generated
+const PLACEHOLDER_CERTS: &[&[u8]] = &[b"FIXME"];
+
+/// Validates council node metadata before it is signed in a node join transaction
+pub fn validate_council_node(node: &CouncilNode) -> Result<()> {
+    validate_name(&node.name)?;
+
+    if let Some(ref security_contact) = node.security_contact {
+        validate_security_contact(security_contact)?;
+    }
+
+    validate_consensus_pubkey(&node.consensus_pubkey)?;
+
+    let cert = &node.confidential_init.cert;
+    if cert.is_empty() {
+        return Err(validation_error("Certificate of council node is empty"));
+    }
+    if is_placeholder_cert(cert) {
+        return Err(validation_error(
+            "Certificate of council node is a placeholder, use keypackage generated by the node",
+        ));
+    }
+
+    Ok(())
+}
+
+/// Returns `true` if given certificate is a known placeholder or all zeros
+///
+/// Keypackages are not parsed by client yet, so only these are refused besides empty certificates.
+pub(crate) fn is_placeholder_cert(cert: &[u8]) -> bool {
+    PLACEHOLDER_CERTS.contains(&cert) || cert.iter().all(|byte| *byte == 0)
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    if name.trim().is_empty() {
+        Err(validation_error("Validator name is empty"))
+    } else if name.len() > MAX_COUNCIL_NODE_STRING_LEN {
+        Err(validation_error(format!(
+            "Validator name longer than {} bytes",
+            MAX_COUNCIL_NODE_STRING_LEN
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_security_contact(security_contact: &str) -> Result<()> {
+    if security_contact.len() > MAX_COUNCIL_NODE_STRING_LEN {
+        return Err(validation_error(format!(
+            "Security contact longer than {} bytes",
+            MAX_COUNCIL_NODE_STRING_LEN
+        )));
+    }
+
+    if is_email(security_contact) || is_url(security_contact) {
+        Ok(())
+    } else {
+        Err(validation_error(
+            "Security contact is neither an email address nor a URL",
+        ))
+    }
+}
+
+fn validate_consensus_pubkey(pubkey: &TendermintValidatorPubKey) -> Result<()> {
+    match pubkey {
+        TendermintValidatorPubKey::Ed25519(bytes) if bytes.iter().all(|byte| *byte == 0) => Err(
+            validation_error("Validator consensus pub-key must not be all zeros"),
+        ),
+        TendermintValidatorPubKey::Ed25519(_) => Ok(()),
+    }
+}
+
+fn is_email(value: &str) -> bool {
+    let mut parts = value.splitn(2, '@');
+    match (parts.next(), parts.next()) {
+        (Some(local), Some(domain)) => {
+            !local.is_empty()
+                && !domain.contains('@')
+                && domain.contains('.')
+                && !domain.starts_with('.')
+                && !domain.ends_with('.')
+                && !value.contains(char::is_whitespace)
+        }
+        _ => false,
+    }
+}
+
+fn is_url(value: &str) -> bool {
+    let rest = if value.starts_with("https://") {
+        &value["https://".len()..]
+    } else if value.starts_with("http://") {
+        &value["http://".len()..]
+    } else {
+        return false;
+    };
+
+    let host = rest.split('/').next().unwrap_or_default();
+    !host.is_empty() && !value.contains(char::is_whitespace)
+}
+
+fn validation_error<M>(message: M) -> Error
+where
+    String: From<M>,
+{
+    Error::new(ErrorKind::ValidationError, message)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    use chain_core::state::account::ConfidentialInit;
+
+    /// Keypackage of council node (opaque to client)
+    pub(crate) fn keypackage() -> Vec<u8> {
+        base64::decode(concat!(
+            "v8TD1o/m4+PeeOyvkILIZ4IVDJT8BaG7M+x9WzLM4cRMVaxSM8CUoeLj19pkD2l6",
+            "JrQeghEeYgoltEY/2e+r/2anQ+96Rl7xxvl9G4YgQlXtNttCEy3E1ynHI5DkflV2",
+            "Ege3x0dK6qu1/MPxD6c/Nlut/euKzv/j6sE4V/q/+quIoeG97sSEqowKsckWGfDh",
+            "A6DJRvCshWT9cWqMF3vqnDGv9b6FkxpvLYigWXZ7B1AP5saXbreJyzZ5fWdlwOEj",
+        ))
+        .unwrap()
+    }
+
+    fn council_node() -> CouncilNode {
+        CouncilNode {
+            name: "test".to_owned(),
+            security_contact: Some("security@example.com".to_owned()),
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            confidential_init: ConfidentialInit { cert: keypackage() },
+        }
+    }
+
+    fn error_message(node: &CouncilNode) -> String {
+        let error = validate_council_node(node).unwrap_err();
+        assert_eq!(ErrorKind::ValidationError, error.kind());
+        error.message().to_owned()
+    }
+
+    #[test]
+    fn check_valid_council_node() {
+        let node = council_node();
+        assert!(validate_council_node(&node).is_ok());
+
+        let node = CouncilNode {
+            security_contact: Some("https://example.com/security".to_owned()),
+            ..council_node()
+        };
+        assert!(validate_council_node(&node).is_ok());
+
+        let node = CouncilNode {
+            security_contact: None,
+            ..council_node()
+        };
+        assert!(validate_council_node(&node).is_ok());
+    }
+
+    #[test]
+    fn check_invalid_council_node() {
+        let node = CouncilNode {
+            name: "".to_owned(),
+            ..council_node()
+        };
+        assert_eq!("Validator name is empty", error_message(&node));
+
+        let node = CouncilNode {
+            name: "a".repeat(MAX_COUNCIL_NODE_STRING_LEN + 1),
+            ..council_node()
+        };
+        assert_eq!("Validator name longer than 255 bytes", error_message(&node));
+
+        let node = CouncilNode {
+            security_contact: Some("security".to_owned()),
+            ..council_node()
+        };
+        assert_eq!(
+            "Security contact is neither an email address nor a URL",
+            error_message(&node)
+        );
+
+        let node = CouncilNode {
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0; 32]),
+            ..council_node()
+        };
+        assert_eq!(
+            "Validator consensus pub-key must not be all zeros",
+            error_message(&node)
+        );
+
+        let node = CouncilNode {
+            confidential_init: ConfidentialInit { cert: Vec::new() },
+            ..council_node()
+        };
+        assert_eq!("Certificate of council node is empty", error_message(&node));
+
+        for cert in &[b"FIXME".to_vec(), vec![0; 192]] {
+            let node = CouncilNode {
+                confidential_init: ConfidentialInit { cert: cert.clone() },
+                ..council_node()
+            };
+            assert_eq!(
+                "Certificate of council node is a placeholder, use keypackage generated by the node",
+                error_message(&node)
+            );
+        }
+    }
+}
@@ -6,7 +6,23 @@
 //!
 //! This crate provides and easy to use client for performing network operations on Crypto.com Chain. Payments, on the
 //! other hand, are handled by `WalletClient` in `client-core` crate.
+pub mod capabilities;
+pub mod council_node;
+pub mod network_fee;
 pub mod network_ops;
+pub mod pending_nonce;
+pub mod types;
+pub mod watch_list;
 
 #[doc(inline)]
-pub use self::network_ops::NetworkOpsClient;
+pub use self::capabilities::client_capabilities;
+#[doc(inline)]
+pub use self::council_node::validate_council_node;
+#[doc(inline)]
+pub use self::network_fee::NetworkFeeAlgorithm;
+#[doc(inline)]
+pub use self::network_ops::{BlockHeightSource, NetworkOpsClient};
+#[doc(inline)]
+pub use self::pending_nonce::{PendingNoncePolicy, PendingNonceRegistry};
+#[doc(inline)]
+pub use self::watch_list::{WatchList, WatchedAddress};
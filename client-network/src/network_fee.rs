@@ -0,0 +1,293 @@
+//! Fee algorithm using fee policy of connected network
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chain_core::init::coin::CoinError;
+use chain_core::init::params::NetworkParameters;
+use chain_core::tx::fee::{Fee, FeeAlgorithm, LinearFee};
+use chain_core::tx::TxAux;
+use client_common::tendermint::Client;
+use client_common::{Error, ErrorKind, Result, ResultExt};
+
+/// Default time for which fetched fee policy is used before querying it again
+pub const DEFAULT_FEE_POLICY_TTL: Duration = Duration::from_secs(60);
+
+/// Fee algorithm which uses linear fee policy of connected network (fetched lazily from chain
+/// state and cached for a configured time)
+pub struct NetworkFeeAlgorithm<C: Client> {
+    client: C,
+    ttl: Duration,
+    cache: Mutex<Option<(LinearFee, Instant)>>,
+}
+
+impl<C> NetworkFeeAlgorithm<C>
+where
+    C: Client,
+{
+    /// Creates a new instance of network fee algorithm with default TTL
+    #[inline]
+    pub fn new(client: C) -> Self {
+        Self::new_with_ttl(client, DEFAULT_FEE_POLICY_TTL)
+    }
+
+    /// Creates a new instance of network fee algorithm with given TTL of fetched fee policy
+    pub fn new_with_ttl(client: C, ttl: Duration) -> Self {
+        Self {
+            client,
+            ttl,
+            cache: Mutex::new(None),
+        }
+    }
+
+    /// Returns fee policy of connected network (queried again once cached policy expires; cache is
+    /// cleared if the query fails)
+    pub fn fee_policy(&self) -> Result<LinearFee> {
+        let mut cache = self.cache.lock().map_err(|_| {
+            Error::new(
+                ErrorKind::InternalError,
+                "Unable to acquire lock on fee policy cache",
+            )
+        })?;
+
+        if let Some((fee_policy, fetched_at)) = *cache {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(fee_policy);
+            }
+        }
+
+        match self.query_fee_policy() {
+            Ok(fee_policy) => {
+                *cache = Some((fee_policy, Instant::now()));
+                Ok(fee_policy)
+            }
+            Err(e) => {
+                *cache = None;
+                Err(e)
+            }
+        }
+    }
+
+    fn query_fee_policy(&self) -> Result<LinearFee> {
+        let height = self.client.status()?.sync_info.latest_block_height.value();
+        let chain_state = self
+            .client
+            .query_state_batch(std::iter::once(height))?
+            .pop()
+            .chain(|| (ErrorKind::InvalidInput, "Chain state not found"))?;
+
+        match chain_state.network_params {
+            NetworkParameters::Genesis(params) => Ok(params.initial_fee_policy),
+        }
+    }
+}
+
+/// `FeeAlgorithm` can only report `CoinError`, so failure to fetch fee policy is reported as
+/// `CoinError::Overflow` (details are available by calling `fee_policy` directly)
+impl<C> FeeAlgorithm for NetworkFeeAlgorithm<C>
+where
+    C: Client,
+{
+    fn calculate_fee(&self, num_bytes: usize) -> std::result::Result<Fee, CoinError> {
+        self.fee_policy()
+            .map_err(|_| CoinError::Overflow)?
+            .calculate_fee(num_bytes)
+    }
+
+    fn calculate_for_txaux(&self, txaux: &TxAux) -> std::result::Result<Fee, CoinError> {
+        self.fee_policy()
+            .map_err(|_| CoinError::Overflow)?
+            .calculate_for_txaux(txaux)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use parity_scale_codec::Encode;
+
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::init::coin::Coin;
+    use chain_core::init::params::{
+        InitNetworkParameters, JailingParameters, RewardsParameters, SlashingParameters,
+    };
+    use chain_core::state::account::{StakedStateAddress, StakedStateOpAttributes, UnbondTx};
+    use chain_core::state::tendermint::BlockHeight;
+    use chain_core::state::{ChainState, RewardsPoolState};
+    use chain_core::tx::fee::Milli;
+    use client_common::tendermint::lite;
+    use client_common::tendermint::mock;
+    use client_common::tendermint::types::*;
+    use client_core::signer::DummySigner;
+
+    #[derive(Clone, Default)]
+    struct MockClient {
+        queries: Arc<AtomicUsize>,
+        fail: Arc<AtomicBool>,
+    }
+
+    impl MockClient {
+        fn fee_policy() -> LinearFee {
+            LinearFee::new(Milli::new(2, 0), Milli::new(3, 0))
+        }
+
+        fn chain_state() -> ChainState {
+            ChainState {
+                account_root: [0; 32],
+                rewards_pool: RewardsPoolState {
+                    period_bonus: Coin::zero(),
+                    last_block_height: BlockHeight::genesis(),
+                    last_distribution_time: 0,
+                    minted: Coin::zero(),
+                    tau: 0,
+                },
+                network_params: NetworkParameters::Genesis(InitNetworkParameters {
+                    initial_fee_policy: Self::fee_policy(),
+                    required_council_node_stake: Coin::unit(),
+                    unbonding_period: 60,
+                    jailing_config: JailingParameters {
+                        block_signing_window: 100,
+                        missed_block_threshold: 50,
+                    },
+                    slashing_config: SlashingParameters {
+                        liveness_slash_percent: "0.1".parse().unwrap(),
+                        byzantine_slash_percent: "0.2".parse().unwrap(),
+                    },
+                    rewards_config: RewardsParameters {
+                        monetary_expansion_cap: Coin::zero(),
+                        reward_period_seconds: 86400,
+                        monetary_expansion_r0: Milli::new(0, 0),
+                        monetary_expansion_tau: 0,
+                        monetary_expansion_decay: 0,
+                    },
+                    max_validators: 50,
+                }),
+            }
+        }
+    }
+
+    impl Client for MockClient {
+        fn genesis(&self) -> Result<Genesis> {
+            unreachable!()
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            Ok(mock::status_response())
+        }
+
+        fn block(&self, _: u64) -> Result<Block> {
+            unreachable!()
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!()
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!()
+        }
+
+        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+            &self,
+            _state: lite::TrustedState,
+            _heights: T,
+        ) -> Result<(Vec<Block>, lite::TrustedState)> {
+            unreachable!()
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            unreachable!()
+        }
+
+        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
+            unreachable!()
+        }
+
+        fn query(&self, _path: &str, _data: &[u8]) -> Result<AbciQuery> {
+            unreachable!()
+        }
+
+        fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+            unreachable!()
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            _queries: T,
+        ) -> Result<Vec<AbciQuery>> {
+            unreachable!()
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            heights: T,
+        ) -> Result<Vec<ChainState>> {
+            self.queries.fetch_add(1, Ordering::SeqCst);
+            if self.fail.load(Ordering::SeqCst) {
+                return Err(Error::new(
+                    ErrorKind::TendermintRpcError,
+                    "Node unavailable",
+                ));
+            }
+            Ok(heights.map(|_| Self::chain_state()).collect())
+        }
+    }
+
+    fn tx_aux() -> TxAux {
+        DummySigner().mock_txaux_for_unbond(UnbondTx::new(
+            StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+            0,
+            Coin::zero(),
+            StakedStateOpAttributes::default(),
+        ))
+    }
+
+    #[test]
+    fn check_network_fee_policy_is_applied() {
+        let client = MockClient::default();
+        let fee_algorithm = NetworkFeeAlgorithm::new(client.clone());
+        let tx_aux = tx_aux();
+
+        assert_eq!(
+            MockClient::fee_policy()
+                .estimate(tx_aux.encode().len())
+                .unwrap(),
+            fee_algorithm.calculate_for_txaux(&tx_aux).unwrap()
+        );
+        assert!(fee_algorithm.calculate_fee(100).is_ok());
+        // fee policy is only queried once within TTL
+        assert_eq!(1, client.queries.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn check_network_fee_policy_cache_invalidation() {
+        let client = MockClient::default();
+        let fee_algorithm =
+            NetworkFeeAlgorithm::new_with_ttl(client.clone(), Duration::from_secs(0));
+
+        assert!(fee_algorithm.fee_policy().is_ok());
+
+        client.fail.store(true, Ordering::SeqCst);
+        assert_eq!(
+            ErrorKind::TendermintRpcError,
+            fee_algorithm.fee_policy().unwrap_err().kind()
+        );
+        assert_eq!(
+            CoinError::Overflow,
+            fee_algorithm.calculate_fee(100).unwrap_err()
+        );
+        assert!(fee_algorithm.cache.lock().unwrap().is_none());
+
+        client.fail.store(false, Ordering::SeqCst);
+        assert_eq!(
+            MockClient::fee_policy(),
+            fee_algorithm.fee_policy().unwrap()
+        );
+    }
+}
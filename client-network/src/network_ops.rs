@@ -3,33 +3,98 @@ mod default_network_ops_client;
 
 pub use self::default_network_ops_client::DefaultNetworkOpsClient;
 
+use chain_core::common::Timespec;
 use chain_core::init::coin::Coin;
 use chain_core::state::account::{
-    CouncilNode, StakedState, StakedStateAddress, StakedStateOpAttributes,
+    CouncilNode, StakedState, StakedStateAddress, StakedStateOpAttributes, StakedStateOpWitness,
 };
+use chain_core::state::tendermint::{TendermintValidatorAddress, TendermintValidatorPubKey};
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
+use chain_core::tx::fee::Fee;
 use chain_core::tx::TxAux;
-use client_common::{Result, SecKey};
-use client_core::types::TransactionPending;
+use client_common::tendermint::types::{PunishmentEvent, RewardRecord};
+use client_common::{Result, SecKey, Transaction};
+use client_core::service::SessionToken;
+use client_core::types::{AddressBalance, TransactionPending};
+use client_core::CoinSelectionStrategy;
+
+use crate::client_capabilities;
+use crate::types::{
+    BroadcastedTransaction, ClientCapabilities, CouncilNodeInfo, FeePriority, NetworkStakingStats,
+    NodeJoinPreview, NonceSource, StakingTxKind, UnbondingStatus, UnsignedStakingTransaction,
+    ValidatorInfo,
+};
 
 /// Interface for performing network operations on Crypto.com Chain
+///
+/// Staking operation attributes given as `None` are created for connected network, with chain hex
+/// id from genesis and app version reported by node.
 pub trait NetworkOpsClient: Send + Sync {
-    /// calculate the deposit fee
-    fn calculate_deposit_fee(&self) -> Result<Coin>;
+    /// Returns name and enckey of wallet of given session (see `WalletClient::unlock_wallet`)
+    fn unlocked_wallet(&self, token: &SessionToken) -> Result<(String, SecKey)>;
+
+    /// calculate the deposit fee for given number of inputs
+    fn calculate_deposit_fee(&self, no_of_inputs: usize) -> Result<Coin>;
+
+    /// calculate the deposit fee for a single input
+    fn calculate_single_input_deposit_fee(&self) -> Result<Coin> {
+        self.calculate_deposit_fee(1)
+    }
+
+    /// Estimates fee of a staking transaction of given kind
+    fn estimate_fee(&self, kind: StakingTxKind) -> Result<Fee>;
+
+    /// Verifies that deposit destination belongs to given wallet, unless `allow_external` is set
+    /// (external destinations are then checked not to be jailed)
+    fn verify_deposit_destination(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        to_address: &StakedStateAddress,
+        allow_external: bool,
+    ) -> Result<()>;
 
-    /// creates a new transaction for bonding stake transaction with utxos
+    /// creates a new transaction for bonding stake transaction with utxos (to a staking address
+    /// outside the wallet only if `allow_external` is set, spending frozen utxos only if
+    /// `allow_frozen` is set)
+    #[allow(clippy::too_many_arguments)]
     fn create_deposit_bonded_stake_transaction(
         &self,
         name: &str,
         enckey: &SecKey,
         transaction: Vec<(TxoPointer, TxOut)>,
         to_address: StakedStateAddress,
-        attributes: StakedStateOpAttributes,
+        attributes: Option<StakedStateOpAttributes>,
+        allow_external: bool,
+        allow_frozen: bool,
     ) -> Result<(TxAux, TransactionPending)>;
 
+    /// creates a new transaction for bonding stake transaction with utxos selected from wallet
+    /// (with given strategy, `LargestFirst` if `None`) to cover given amount and deposit fee
+    #[allow(clippy::too_many_arguments)]
+    fn create_deposit_amount_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        to_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        allow_external: bool,
+        coin_selection: Option<&dyn CoinSelectionStrategy>,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// builds a new unsigned transaction for unbonding stake transaction
+    fn build_unsigned_unbond_stake_transaction(
+        &self,
+        address: StakedStateAddress,
+        value: Coin,
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction>;
+
     /// creates a new transaction for unbonding stake transaction
     fn create_unbond_stake_transaction(
         &self,
@@ -37,10 +102,53 @@ pub trait NetworkOpsClient: Send + Sync {
         enckey: &SecKey,
         address: StakedStateAddress,
         value: Coin,
-        attributes: StakedStateOpAttributes,
-    ) -> Result<TxAux>;
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Creates a new transaction for unbonding stake with wallet of given session
+    fn create_unbond_stake_transaction_with_session(
+        &self,
+        token: &SessionToken,
+        address: StakedStateAddress,
+        value: Coin,
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)> {
+        let (name, enckey) = self.unlocked_wallet(token)?;
+        self.create_unbond_stake_transaction(
+            &name,
+            &enckey,
+            address,
+            value,
+            attributes,
+            nonce_source,
+        )
+    }
+
+    /// creates a new transaction for unbonding all bonded stake (after deducting fee)
+    fn create_unbond_all_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Builds a new unsigned transaction for withdrawing unbonded stake from an account
+    fn build_unsigned_withdraw_unbonded_stake_transaction(
+        &self,
+        from_address: &StakedStateAddress,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction>;
 
     /// Creates a new transaction for withdrawing unbonded stake from an account
+    ///
+    /// When nonce is fetched from network, outputs and fee must sum to the unbonded amount
+    /// exactly; if `auto_adjust_last_output` is `true`, any remaining unbonded amount is added to
+    /// the last output.
     fn create_withdraw_unbonded_stake_transaction(
         &self,
         name: &str,
@@ -48,9 +156,36 @@ pub trait NetworkOpsClient: Send + Sync {
         from_address: &StakedStateAddress,
         outputs: Vec<TxOut>,
         attributes: TxAttributes,
+        nonce_source: NonceSource,
+        auto_adjust_last_output: bool,
     ) -> Result<(TxAux, TransactionPending)>;
 
-    /// Creates a new transaction for withdrawing all unbonded stake from an account
+    /// Creates a new transaction for withdrawing unbonded stake with wallet of given session
+    fn create_withdraw_unbonded_stake_transaction_with_session(
+        &self,
+        token: &SessionToken,
+        from_address: &StakedStateAddress,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        nonce_source: NonceSource,
+        auto_adjust_last_output: bool,
+    ) -> Result<(TxAux, TransactionPending)> {
+        let (name, enckey) = self.unlocked_wallet(token)?;
+        self.create_withdraw_unbonded_stake_transaction(
+            &name,
+            &enckey,
+            from_address,
+            outputs,
+            attributes,
+            nonce_source,
+            auto_adjust_last_output,
+        )
+    }
+
+    /// Creates a new transaction for withdrawing all unbonded stake from an account (fee priority
+    /// only limits the fee by its maximum fee, multipliers other than `1.0` are refused as chain
+    /// requires the exact minimum fee); output is locked until `unbonded_from` of the account
+    /// (chain refuses any other lock time, so `extra_lock_until` must equal it if given)
     fn create_withdraw_all_unbonded_stake_transaction(
         &self,
         name: &str,
@@ -58,16 +193,73 @@ pub trait NetworkOpsClient: Send + Sync {
         from_address: &StakedStateAddress,
         to_address: ExtendedAddr,
         attributes: TxAttributes,
+        fee_priority: Option<FeePriority>,
+        extra_lock_until: Option<Timespec>,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Creates a new transaction for withdrawing all unbonded stake from an account, split across
+    /// multiple addresses by given shares (in basis points, summing to `10000`), each output
+    /// locked until its optional lock time (as in `create_withdraw_all_unbonded_stake_transaction`)
+    fn create_withdraw_all_unbonded_stake_split_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        destinations: Vec<(ExtendedAddr, u32, Option<Timespec>)>,
+        attributes: TxAttributes,
+        fee_priority: Option<FeePriority>,
     ) -> Result<(TxAux, TransactionPending)>;
 
+    /// Creates transactions withdrawing all unbonded stake of each of given accounts to given
+    /// address, fetching their states in a single batch; accounts which cannot be withdrawn from
+    /// (e.g. still unbonding or jailed) get an error without failing the other accounts
+    fn create_withdraw_all_batch(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_addresses: &[StakedStateAddress],
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<Vec<(StakedStateAddress, Result<(TxAux, TransactionPending)>)>>;
+
+    /// Creates transactions moving all unbonded stake of an account back to bonded stake of given
+    /// staking address (of the same wallet): a withdraw to a new transfer address (with given
+    /// attributes) and a deposit spending its output, returned in broadcast order
+    fn create_restake_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        to_staking_address: StakedStateAddress,
+        attributes: TxAttributes,
+    ) -> Result<(Vec<TxAux>, TransactionPending)>;
+
+    /// Builds a new unsigned transaction for un-jailing a previously jailed account
+    fn build_unsigned_unjail_transaction(
+        &self,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction>;
+
     /// Creates a new transaction for un-jailing a previously jailed account
     fn create_unjail_transaction(
         &self,
         name: &str,
         enckey: &SecKey,
         address: StakedStateAddress,
-        attributes: StakedStateOpAttributes,
-    ) -> Result<TxAux>;
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Builds a new unsigned transaction for a node joining validator set
+    fn build_unsigned_node_join_transaction(
+        &self,
+        staking_account_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        node_metadata: CouncilNode,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction>;
 
     /// Creates a new transaction for a node joining validator set
     fn create_node_join_transaction(
@@ -75,10 +267,214 @@ pub trait NetworkOpsClient: Send + Sync {
         name: &str,
         enckey: &SecKey,
         staking_account_address: StakedStateAddress,
-        attributes: StakedStateOpAttributes,
+        attributes: Option<StakedStateOpAttributes>,
         node_metadata: CouncilNode,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Returns certificate change information of a node join transaction, to be confirmed by user
+    fn preview_node_join_transaction(
+        &self,
+        staking_account_address: &StakedStateAddress,
+        node_metadata: &CouncilNode,
+    ) -> Result<NodeJoinPreview>;
+
+    /// Creates a node join transaction updating metadata (e.g. security contact or certificate)
+    /// of an existing council node; consensus pub-key can only be changed if
+    /// `allow_pubkey_rotation` is set
+    fn update_node_metadata(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        new_metadata: CouncilNode,
+        allow_pubkey_rotation: bool,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Creates a new transaction for a node joining validator set, only if given fingerprint
+    /// matches the new certificate fingerprint returned by preview
+    #[allow(clippy::too_many_arguments)]
+    fn create_acknowledged_node_join_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_account_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        node_metadata: CouncilNode,
+        nonce_source: NonceSource,
+        cert_fingerprint_ack: &str,
+    ) -> Result<(TxAux, TransactionPending)>;
+
+    /// Attaches an externally created witness to an unsigned staking transaction, after
+    /// verifying that it is signed by the expected staking address
+    fn attach_witness(
+        &self,
+        unsigned: UnsignedStakingTransaction,
+        witness: StakedStateOpWitness,
     ) -> Result<TxAux>;
 
+    /// Finalizes a staking transaction with a witness created by an external signer (e.g. an HSM),
+    /// after verifying that the witness is signed by transaction's staking address and the nonce
+    /// of transaction is still current
+    fn finalize_staking_tx(&self, tx: Transaction, witness: StakedStateOpWitness) -> Result<TxAux>;
+
+    /// Broadcasts a transaction encoded with `encode_tx_aux` or `encode_tx_aux_base64` (e.g. built
+    /// on another machine), without waiting for its inclusion in a block
+    fn broadcast_encoded_transaction(&self, encoded: &str) -> Result<BroadcastedTransaction>;
+
+    /// Creates and broadcasts a deposit transaction for given amount, waiting for its inclusion
+    /// in at most `wait_for_blocks` blocks (does not wait if it is zero)
+    fn deposit_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        to_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        wait_for_blocks: u64,
+        allow_external: bool,
+    ) -> Result<BroadcastedTransaction>;
+
+    /// Creates and broadcasts an unbond transaction, waiting for its inclusion in at most
+    /// `wait_for_blocks` blocks (does not wait if it is zero)
+    ///
+    /// If `retry_on_nonce_mismatch` is set, a transaction rejected due to an outdated nonce (e.g.
+    /// the account is used from another device) is rebuilt with a refetched nonce and broadcasted
+    /// again (same for other staking operation helpers below)
+    fn unbond_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        value: Coin,
+        attributes: Option<StakedStateOpAttributes>,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction>;
+
+    /// Creates and broadcasts a withdraw transaction, waiting for its inclusion in at most
+    /// `wait_for_blocks` blocks (does not wait if it is zero); outputs are not adjusted, so they
+    /// must sum to the unbonded amount minus fee
+    fn withdraw_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction>;
+
+    /// Creates and broadcasts an unjail transaction, waiting for its inclusion in at most
+    /// `wait_for_blocks` blocks (does not wait if it is zero)
+    fn unjail_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction>;
+
+    /// Creates and broadcasts a node join transaction, waiting for its inclusion in at most
+    /// `wait_for_blocks` blocks (does not wait if it is zero)
+    fn node_join_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_account_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        node_metadata: CouncilNode,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction>;
+
     /// Returns staked stake corresponding to given address
     fn get_staked_state(&self, address: &StakedStateAddress) -> Result<StakedState>;
+
+    /// Returns staked stake corresponding to given address (`None` if the address has no on-chain
+    /// state yet, i.e. nothing has been deposited to it)
+    fn get_staked_state_maybe(&self, address: &StakedStateAddress) -> Result<Option<StakedState>>;
+
+    /// Returns staked states corresponding to given addresses in a single batch of queries
+    /// (`None` for addresses which do not exist on chain)
+    fn get_staked_states(
+        &self,
+        addresses: &[StakedStateAddress],
+    ) -> Result<Vec<Option<StakedState>>>;
+
+    /// Returns balances and activity of each address of given wallet, with bonded and unbonded
+    /// amounts of staking addresses taken from their (cached) staked states
+    fn balance_by_address(&self, name: &str, enckey: &SecKey) -> Result<Vec<AddressBalance>>;
+
+    /// Returns unbonding status (and remaining time until unbonded amount can be withdrawn) of
+    /// given address
+    fn get_unbonding_status(&self, address: &StakedStateAddress) -> Result<UnbondingStatus>;
+
+    /// Returns active council nodes with their staking account information, sorted by bonded
+    /// amount (descending)
+    fn list_council_nodes(&self) -> Result<Vec<CouncilNodeInfo>>;
+
+    /// Returns validator details (voting power and membership in active validator set) of council
+    /// node with given staking address
+    fn get_validator_info(&self, address: &StakedStateAddress) -> Result<ValidatorInfo>;
+
+    /// Returns previously used validator addresses (with time of their last use) of council node
+    /// with given staking address, which cannot be used again as its consensus key
+    fn get_used_validator_addresses(
+        &self,
+        address: &StakedStateAddress,
+    ) -> Result<Vec<(TendermintValidatorAddress, Timespec)>>;
+
+    /// Returns staking address of active council node using given consensus public key
+    fn find_staking_address_by_consensus_key(
+        &self,
+        consensus_pubkey: &TendermintValidatorPubKey,
+    ) -> Result<Option<StakedStateAddress>>;
+
+    /// Returns jail and slash events of given address in blocks from `from_height` to
+    /// `to_height` (both inclusive)
+    fn get_punishment_history(
+        &self,
+        address: &StakedStateAddress,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<PunishmentEvent>>;
+
+    /// Returns rewards distributed to given address in blocks from `from_height` to `to_height`
+    /// (both inclusive; blocks without rewards of the address are skipped)
+    fn get_reward_history(
+        &self,
+        address: &StakedStateAddress,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<RewardRecord>>;
+
+    /// Returns total rewards distributed to given address in blocks from `from_height` to
+    /// `to_height` (both inclusive)
+    fn total_rewards(
+        &self,
+        address: &StakedStateAddress,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Coin>;
+
+    /// Returns network wide staking statistics
+    fn get_network_staking_stats(&self) -> Result<NetworkStakingStats>;
+
+    /// Returns optional features supported by current client build along with runtime
+    /// configuration of this client
+    fn capabilities(&self) -> ClientCapabilities {
+        client_capabilities()
+    }
+}
+
+/// Source of current block height recorded in pending transactions built by network operations
+/// client (tendermint client of network operations client is used if none is given)
+pub trait BlockHeightSource: Send + Sync {
+    /// Returns current block height
+    fn current_block_height(&self) -> Result<u64>;
 }
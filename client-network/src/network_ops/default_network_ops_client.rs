@@ -1,30 +1,95 @@
-use parity_scale_codec::Decode;
-
-use crate::NetworkOpsClient;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::sync::{Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use parity_scale_codec::{Decode, Encode};
+
+use crate::council_node::is_placeholder_cert;
+use crate::types::{
+    BroadcastedTransaction, ClientCapabilities, CouncilNodeEntry, CouncilNodeInfo, FeePriority,
+    InactiveReason, NetworkOpsConfig, NetworkStakingStats, NodeJoinPreview, NonceSource,
+    StakingTxKind, UnbondingStatus, UnsignedStakingTransaction, ValidatorInfo,
+};
+use crate::{
+    client_capabilities, validate_council_node, BlockHeightSource, NetworkFeeAlgorithm,
+    NetworkOpsClient, PendingNonceRegistry, WatchList,
+};
+use chain_core::common::fixed::monetary_expansion;
 use chain_core::common::Timespec;
+use chain_core::init::address::RedeemAddress;
 use chain_core::init::coin::{sum_coins, Coin};
 use chain_core::state::account::{
     CouncilNode, DepositBondTx, StakedState, StakedStateAddress, StakedStateOpAttributes,
     StakedStateOpWitness, UnbondTx, UnjailTx, WithdrawUnbondedTx,
 };
+use chain_core::state::tendermint::{TendermintValidatorAddress, TendermintValidatorPubKey};
 use chain_core::state::validator::NodeJoinRequestTx;
 use chain_core::tx::data::address::ExtendedAddr;
 use chain_core::tx::data::attribute::TxAttributes;
 use chain_core::tx::data::input::TxoPointer;
 use chain_core::tx::data::output::TxOut;
-use chain_core::tx::fee::FeeAlgorithm;
-use chain_core::tx::{TxAux, TxPublicAux};
+use chain_core::tx::data::{txid_hash, Tx, TxId};
+use chain_core::tx::fee::{Fee, FeeAlgorithm, Milli};
+use chain_core::tx::{TransactionId, TxAux, TxPublicAux};
+use chain_tx_validation::witness::verify_tx_recover_address;
 use chain_tx_validation::{check_inputs_basic, check_outputs_basic, verify_unjailed};
-use client_common::tendermint::types::AbciQueryExt;
+use client_common::tendermint::types::{
+    broadcast_error, check_broadcast_response, AbciQuery, AbciQueryExt, BlockResults,
+    BlockResultsResponse, BroadcastError, PunishmentEvent, RewardRecord,
+};
 use client_common::tendermint::Client;
 use client_common::{
-    Error, ErrorKind, Result, ResultExt, SecKey, SignedTransaction, Storage, Transaction,
+    decode_tx_aux, Error, ErrorKind, Result, ResultExt, SecKey, SignedTransaction, Storage,
+    Transaction,
 };
+use client_core::service::SessionToken;
 use client_core::signer::{DummySigner, Signer, WalletSignerManager};
 use client_core::transaction_builder::WitnessedUTxO;
-use client_core::types::TransactionPending;
-use client_core::{TransactionObfuscation, UnspentTransactions, WalletClient};
-use tendermint::{block::Height, Time};
+use client_core::types::{
+    verify_wallet_network, AddressBalance, LabeledAddress, NetworkInfo, TransactionPending,
+    DEFAULT_PENDING_TX_VALID_BLOCKS,
+};
+use client_core::{
+    CoinSelectionStrategy, LargestFirst, TransactionObfuscation, UnspentTransactions, WalletClient,
+};
+use tendermint::{block::Height, PublicKey, Time};
+
+/// Total of basis points shares of a split withdrawal
+const TOTAL_BASIS_POINTS: u32 = 10_000;
+
+/// Maximum number of fee recalculations while building a withdraw-all transaction
+const MAX_FEE_ITERATIONS: usize = 5;
+
+/// Response code of `account` query for a staking address which does not exist on chain
+const ACCOUNT_NOT_FOUND_CODE: u32 = 1;
+
+/// Default time for which queried staked states are cached (about one block)
+const DEFAULT_STAKED_STATE_TTL: Duration = Duration::from_secs(2);
+
+/// Time reported by tendermint as latest block time when there is no block yet
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+/// Number of block results requested in one batch while searching staking history
+const BLOCK_RESULTS_BATCH_SIZE: u64 = 100;
+
+/// Maximum number of rebuilds of a staking transaction rejected due to an outdated nonce
+const MAX_NONCE_MISMATCH_RETRIES: usize = 3;
+
+/// Interval between polls of latest block height while waiting for a transaction
+const BLOCK_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default time allowed for each awaited block while waiting for a transaction (so that waiting
+/// ends even if the chain is halted)
+const DEFAULT_BLOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Network information read from genesis (cached as it does not change)
+#[derive(Debug, Clone)]
+struct GenesisInfo {
+    network: NetworkInfo,
+    required_council_node_stake: Coin,
+}
 
 /// Default implementation of `NetworkOpsClient`
 pub struct DefaultNetworkOpsClient<W, S, C, F, E>
@@ -40,6 +105,41 @@ where
     client: C,
     fee_algorithm: F,
     transaction_cipher: E,
+    genesis_info: Mutex<Option<GenesisInfo>>,
+    network_attributes: Mutex<Option<StakedStateOpAttributes>>,
+    staked_state_ttl: Duration,
+    staked_states: Mutex<HashMap<StakedStateAddress, (Option<StakedState>, Instant)>>,
+    pending_nonces: Option<PendingNonceRegistry<S>>,
+    watch_list: Option<WatchList<S>>,
+    pending_tx_valid_blocks: u64,
+    block_wait_timeout: Duration,
+    block_height_source: Option<Box<dyn BlockHeightSource>>,
+    network_mismatch_allowed: bool,
+}
+
+impl<W, S, C, E> DefaultNetworkOpsClient<W, S, C, NetworkFeeAlgorithm<C>, E>
+where
+    W: WalletClient,
+    S: Storage,
+    C: Client,
+    E: TransactionObfuscation,
+{
+    /// Creates a new instance of `DefaultNetworkOpsClient` using fee policy of connected network
+    pub fn new_with_network_fee(
+        wallet_client: W,
+        signer_manager: WalletSignerManager<S>,
+        client: C,
+        transaction_cipher: E,
+    ) -> Self {
+        let fee_algorithm = NetworkFeeAlgorithm::new(client.clone());
+        Self::new(
+            wallet_client,
+            signer_manager,
+            client,
+            fee_algorithm,
+            transaction_cipher,
+        )
+    }
 }
 
 impl<W, S, C, F, E> DefaultNetworkOpsClient<W, S, C, F, E>
@@ -64,662 +164,6008 @@ where
             client,
             fee_algorithm,
             transaction_cipher,
+            genesis_info: Mutex::new(None),
+            network_attributes: Mutex::new(None),
+            staked_state_ttl: DEFAULT_STAKED_STATE_TTL,
+            staked_states: Mutex::new(HashMap::new()),
+            pending_nonces: None,
+            watch_list: None,
+            pending_tx_valid_blocks: DEFAULT_PENDING_TX_VALID_BLOCKS,
+            block_wait_timeout: DEFAULT_BLOCK_WAIT_TIMEOUT,
+            block_height_source: None,
+            network_mismatch_allowed: false,
         }
     }
 
-    /// Returns current underlying wallet client
-    pub fn get_wallet_client(&self) -> &W {
-        &self.wallet_client
+    /// Sets time for which queried staked states are cached (zero disables caching)
+    pub fn with_staked_state_ttl(mut self, staked_state_ttl: Duration) -> Self {
+        self.staked_state_ttl = staked_state_ttl;
+        self
     }
 
-    /// Get account info
-    fn get_account(&self, staked_state_address: &[u8]) -> Result<StakedState> {
-        let bytes = self.client.query("account", staked_state_address)?.bytes();
-
-        StakedState::decode(&mut bytes.as_slice()).chain(|| {
-            (
-                ErrorKind::DeserializationError,
-                format!(
-                    "Cannot deserialize staked state for address: {}",
-                    hex::encode(staked_state_address)
-                ),
-            )
-        })
+    /// Tracks nonces of built staking transactions in given registry, so that transactions built
+    /// for the same address before previous ones are applied on chain do not reuse their nonce
+    pub fn with_pending_nonce_registry(mut self, registry: PendingNonceRegistry<S>) -> Self {
+        self.pending_nonces = Some(registry);
+        self
     }
 
-    /// Get staked state info
-    fn get_staked_state_account(
-        &self,
-        to_staked_account: &StakedStateAddress,
-    ) -> Result<StakedState> {
-        match to_staked_account {
-            StakedStateAddress::BasicRedeem(ref a) => self.get_account(&a.0),
-        }
+    /// Refuses to build staking transactions of addresses in given watch list (which are only
+    /// monitored and not owned by any wallet)
+    pub fn with_watch_list(mut self, watch_list: WatchList<S>) -> Self {
+        self.watch_list = Some(watch_list);
+        self
     }
 
-    /// Calculate the withdraw unbounded fee
-    fn calculate_fee(&self, outputs: Vec<TxOut>, attributes: TxAttributes) -> Result<Coin> {
-        let tx = WithdrawUnbondedTx::new(0, outputs, attributes);
-        // mock the signature
-        let dummy_signer = DummySigner();
-        let tx_aux = dummy_signer.mock_txaux_for_withdraw(tx);
-        let fee = self
-            .fee_algorithm
-            .calculate_for_txaux(&tx_aux)
-            .chain(|| {
-                (
-                    ErrorKind::IllegalInput,
-                    "Calculated fee is more than the maximum allowed value",
-                )
-            })?
-            .to_coin();
-        Ok(fee)
+    /// Sets number of blocks after which built transactions are given up if not included in a
+    /// block (see `TransactionPending::valid_until_height`)
+    pub fn with_pending_tx_valid_blocks(mut self, pending_tx_valid_blocks: u64) -> Self {
+        self.pending_tx_valid_blocks = pending_tx_valid_blocks;
+        self
     }
 
-    fn get_last_block_time(&self) -> Result<Timespec> {
-        let status = self.client.status()?;
-        Ok(to_timespec(
-            if status.sync_info.latest_block_height == Height(0) {
-                self.client.genesis()?.genesis_time
-            } else {
-                status.sync_info.latest_block_time
-            },
-        ))
+    /// Sets time allowed for each awaited block while waiting for a broadcasted transaction to be
+    /// included in a block
+    pub fn with_block_wait_timeout(mut self, block_wait_timeout: Duration) -> Self {
+        self.block_wait_timeout = block_wait_timeout;
+        self
     }
-}
 
-impl<W, S, C, F, E> NetworkOpsClient for DefaultNetworkOpsClient<W, S, C, F, E>
-where
-    W: WalletClient,
-    S: Storage,
-    C: Client,
-    F: FeeAlgorithm,
-    E: TransactionObfuscation,
-{
-    fn calculate_deposit_fee(&self) -> Result<Coin> {
-        let dummy_signer = DummySigner();
-        let tx_aux = dummy_signer
-            .mock_txaux_for_deposit(&[WitnessedUTxO::dummy()])
-            .chain(|| (ErrorKind::ValidationError, "Calculated fee failed"))?;
-        let fee = self
-            .fee_algorithm
-            .calculate_for_txaux(&tx_aux)
-            .chain(|| {
-                (
-                    ErrorKind::IllegalInput,
-                    "Calculated fee is more than the maximum allowed value",
-                )
-            })?
-            .to_coin();
-        Ok(fee)
+    /// Reads current block height recorded in pending transactions from given source (instead of
+    /// connected node)
+    pub fn with_block_height_source<H: BlockHeightSource + 'static>(
+        mut self,
+        block_height_source: H,
+    ) -> Self {
+        self.block_height_source = Some(Box::new(block_height_source));
+        self
     }
 
-    fn create_deposit_bonded_stake_transaction<'a>(
-        &'a self,
-        name: &'a str,
-        enckey: &'a SecKey,
-        transactions: Vec<(TxoPointer, TxOut)>,
-        to_address: StakedStateAddress,
-        attributes: StakedStateOpAttributes,
-    ) -> Result<(TxAux, TransactionPending)> {
-        // if the to_address belongs to current wallet, we do not check the state
-        let staking_addresses = self.wallet_client.staking_addresses(name, enckey)?;
-        if !staking_addresses.contains(&to_address) {
-            let staked_state = self.get_staked_state(&to_address)?;
-            verify_unjailed(&staked_state).map_err(|e| {
-                Error::new(
-                    ErrorKind::ValidationError,
-                    format!("Failed to validate staking account: {}", e),
-                )
-            })?;
-        }
+    /// Allows building staking transactions of wallets on networks other than the one they were
+    /// created for
+    pub fn with_network_mismatch_allowed(mut self, network_mismatch_allowed: bool) -> Self {
+        self.network_mismatch_allowed = network_mismatch_allowed;
+        self
+    }
 
-        let inputs = transactions
-            .iter()
-            .map(|(input, _)| input.clone())
-            .collect::<Vec<_>>();
+    /// Removes cached staked state of given address (e.g. after broadcasting a transaction which
+    /// changes its nonce)
+    pub fn invalidate_staked_state(&self, address: &StakedStateAddress) -> Result<()> {
+        self.cached_staked_states()?.remove(address);
+        Ok(())
+    }
 
-        let transaction = DepositBondTx::new(inputs.clone(), to_address, attributes);
-        let unspent_transactions = UnspentTransactions::new(transactions);
-        let signer =
-            self.signer_manager
-                .create_signer(name, enckey, &self.signer_manager.hw_key_service);
+    /// Returns current underlying wallet client
+    pub fn get_wallet_client(&self) -> &W {
+        &self.wallet_client
+    }
 
-        let tx = Transaction::DepositStakeTransaction(transaction.clone());
-        let witness = signer.schnorr_sign_transaction(&tx, &unspent_transactions.select_all())?;
+    /// Returns runtime configuration of this client
+    pub fn config(&self) -> NetworkOpsConfig {
+        NetworkOpsConfig {
+            staked_state_ttl_millis: self.staked_state_ttl.as_millis() as u64,
+            pending_nonce_tracking: self.pending_nonces.is_some(),
+            watch_list: self.watch_list.is_some(),
+            pending_tx_valid_blocks: self.pending_tx_valid_blocks,
+            block_wait_timeout_secs: self.block_wait_timeout.as_secs(),
+            custom_block_height_source: self.block_height_source.is_some(),
+            network_mismatch_allowed: self.network_mismatch_allowed,
+        }
+    }
 
-        check_inputs_basic(&transaction.inputs, &witness).map_err(|e| {
+    /// Returns network information from genesis (genesis is only queried once)
+    fn get_genesis_info(&self) -> Result<GenesisInfo> {
+        let mut genesis_info = self.genesis_info.lock().map_err(|_| {
             Error::new(
-                ErrorKind::ValidationError,
-                format!("Failed to validate deposit transaction inputs: {}", e),
+                ErrorKind::InternalError,
+                "Unable to acquire lock on genesis information",
             )
         })?;
 
-        let signed_transaction = SignedTransaction::DepositStakeTransaction(transaction, witness);
-        let tx_aux = self.transaction_cipher.encrypt(signed_transaction)?;
-        let block_height = match self.wallet_client.get_current_block_height() {
-            Ok(h) => h,
-            Err(e) if e.kind() == ErrorKind::PermissionDenied => 0, // to make unit test pass
-            Err(e) => return Err(e),
-        };
-        let pending_transaction = TransactionPending {
-            block_height,
-            used_inputs: inputs,
-            return_amount: Coin::zero(),
+        if let Some(ref genesis_info) = *genesis_info {
+            return Ok(genesis_info.clone());
+        }
+
+        let genesis = self.client.genesis()?;
+        let required_council_node_stake = genesis
+            .app_state
+            .as_ref()
+            .chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "Genesis of connected network does not contain app state",
+                )
+            })?
+            .network_params
+            .required_council_node_stake;
+        let info = GenesisInfo {
+            network: NetworkInfo::from_genesis(&genesis)?,
+            required_council_node_stake,
         };
-        Ok((tx_aux, pending_transaction))
-    }
 
-    fn create_unbond_stake_transaction(
-        &self,
-        name: &str,
-        enckey: &SecKey,
-        address: StakedStateAddress,
-        value: Coin,
-        attributes: StakedStateOpAttributes,
-    ) -> Result<TxAux> {
-        let staked_state = self.get_staked_state(&address)?;
+        *genesis_info = Some(info.clone());
+        Ok(info)
+    }
 
-        verify_unjailed(&staked_state).map_err(|e| {
+    /// Creates staking operation attributes with chain hex id and app version of connected network
+    /// (node is only queried once)
+    pub fn network_staking_attributes(&self) -> Result<StakedStateOpAttributes> {
+        let mut network_attributes = self.network_attributes.lock().map_err(|_| {
             Error::new(
-                ErrorKind::ValidationError,
-                format!("Failed to validate staking account: {}", e),
+                ErrorKind::InternalError,
+                "Unable to acquire lock on network attributes",
             )
         })?;
 
-        if staked_state.bonded < value {
-            return Err(Error::new(
-                ErrorKind::InvalidInput,
-                "Staking account does not have enough coins to unbond (synchronizing your wallet may help)",
-            ));
+        if let Some(ref attributes) = *network_attributes {
+            return Ok(attributes.clone());
         }
 
-        let nonce = staked_state.nonce;
-
-        let transaction = UnbondTx::new(address, nonce, value, attributes);
-        let tx = Transaction::UnbondStakeTransaction(transaction.clone());
-
-        let public_key = match address {
-            StakedStateAddress::BasicRedeem(ref redeem_address) => self
-                .wallet_client
-                .find_staking_key(name, enckey, redeem_address)?
-                .chain(|| {
-                    (
-                        ErrorKind::InvalidInput,
-                        "Address not found in current wallet",
-                    )
-                })?,
+        let attributes = StakedStateOpAttributes {
+            chain_hex_id: self.get_genesis_info()?.network.chain_hex_id,
+            app_version: self.client.status()?.node_info.protocol_version.app,
         };
-        let sign_key = self.wallet_client.sign_key(name, enckey, &public_key)?;
-
-        let signature = sign_key.sign(&tx).map(StakedStateOpWitness::new)?;
 
-        Ok(TxAux::PublicTx(TxPublicAux::UnbondStakeTx(
-            transaction,
-            signature,
-        )))
+        *network_attributes = Some(attributes.clone());
+        Ok(attributes)
     }
 
-    fn create_withdraw_unbonded_stake_transaction(
+    /// Returns given staking operation attributes, or attributes of connected network if `None`
+    fn staking_attributes(
         &self,
-        name: &str,
-        enckey: &SecKey,
-        from_address: &StakedStateAddress,
-        outputs: Vec<TxOut>,
-        attributes: TxAttributes,
-    ) -> Result<(TxAux, TransactionPending)> {
-        let last_block_time = self.get_last_block_time()?;
-        let staked_state = self.get_staked_state(from_address)?;
+        attributes: Option<StakedStateOpAttributes>,
+    ) -> Result<StakedStateOpAttributes> {
+        match attributes {
+            Some(attributes) => Ok(attributes),
+            None => self.network_staking_attributes(),
+        }
+    }
+
+    /// Checks that chain hex id of transaction attributes matches connected network
+    fn verify_chain_hex_id(&self, chain_hex_id: u8) -> Result<()> {
+        let network_chain_hex_id = self.get_genesis_info()?.network.chain_hex_id;
 
-        if staked_state.unbonded_from > last_block_time {
+        if chain_hex_id != network_chain_hex_id {
             return Err(Error::new(
                 ErrorKind::ValidationError,
-                "Staking state is not yet unbonded",
+                format!(
+                    "Chain hex id of transaction attributes ({:02x}) does not match connected network ({:02x})",
+                    chain_hex_id, network_chain_hex_id
+                ),
             ));
         }
+        Ok(())
+    }
 
-        verify_unjailed(&staked_state).map_err(|e| {
-            Error::new(
-                ErrorKind::ValidationError,
-                format!("Failed to validate staking account: {}", e),
-            )
-        })?;
-
-        let output_value = sum_coins(outputs.iter().map(|output| output.value))
-            .chain(|| (ErrorKind::InvalidInput, "Error while adding output values"))?;
+    /// Checks that given wallet can sign staking transactions (i.e., it is not watch-only) and
+    /// that it was created for connected network (network is not contacted when building with
+    /// explicit nonce, so that offline signing keeps working)
+    fn verify_signing_wallet(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        nonce_source: NonceSource,
+    ) -> Result<()> {
+        self.wallet_client
+            .get_wallet_kind(name, enckey)?
+            .verify_can_sign()?;
+
+        if self.network_mismatch_allowed {
+            return Ok(());
+        }
+        if let NonceSource::Explicit(_) = nonce_source {
+            return Ok(());
+        }
+        match self.wallet_client.wallet_network(name)? {
+            Some(recorded) => {
+                verify_wallet_network(name, Some(&recorded), &self.get_genesis_info()?.network)
+            }
+            None => {
+                log::warn!("Network of wallet ({}) is not recorded", name);
+                Ok(())
+            }
+        }
+    }
 
-        if staked_state.unbonded < output_value {
-            return Err(Error::new(
+    /// Refuses staking address in watch list (watch-only, not owned by wallet)
+    fn verify_owned_address(&self, address: &StakedStateAddress) -> Result<()> {
+        match self.watch_list {
+            Some(ref watch_list) if watch_list.is_watched(address)? => Err(Error::new(
                 ErrorKind::InvalidInput,
-                "Staking account does not have enough unbonded coins to withdraw (synchronizing your wallet may help)",
-            ));
+                format!("Staking address ({}) is watch-only, not owned", address),
+            )),
+            _ => Ok(()),
         }
+    }
 
-        let nonce = staked_state.nonce;
-
-        let transaction = WithdrawUnbondedTx::new(nonce, outputs, attributes);
-        let tx = Transaction::WithdrawUnbondedStakeTransaction(transaction.clone());
-
-        let public_key = match from_address {
-            StakedStateAddress::BasicRedeem(ref redeem_address) => self
-                .wallet_client
-                .find_staking_key(name, enckey, redeem_address)?
-                .chain(|| {
-                    (
-                        ErrorKind::InvalidInput,
-                        "Address not found in current wallet",
-                    )
-                })?,
-        };
-        let sign_key = self.wallet_client.sign_key(name, enckey, &public_key)?;
-        let signature = sign_key.sign(&tx).map(StakedStateOpWitness::new)?;
+    /// Get account info (`None` if account does not exist)
+    fn get_account(&self, staked_state_address: &[u8]) -> Result<Option<StakedState>> {
+        // batch query does not fail on response code of a missing account
+        let response = self
+            .client
+            .query_batch(std::iter::once(("account", staked_state_address)))?
+            .pop()
+            .chain(|| {
+                (
+                    ErrorKind::TendermintRpcError,
+                    "No response to account query",
+                )
+            })?;
 
-        let signed_transaction =
-            SignedTransaction::WithdrawUnbondedStakeTransaction(transaction, signature);
-        let tx_aux = self.transaction_cipher.encrypt(signed_transaction)?;
-        let block_height = match self.wallet_client.get_current_block_height() {
-            Ok(h) => h,
-            Err(e) if e.kind() == ErrorKind::PermissionDenied => 0, // to make unit test pass
-            Err(e) => return Err(e),
-        };
-        let pending_transaction = TransactionPending {
-            block_height,
-            used_inputs: vec![],
-            return_amount: output_value,
-        };
-        Ok((tx_aux, pending_transaction))
+        decode_staked_state(response, staked_state_address)
     }
 
-    fn create_unjail_transaction(
+    /// Get staked state info (cached for configured time)
+    fn get_staked_state_account(
         &self,
-        name: &str,
-        enckey: &SecKey,
-        address: StakedStateAddress,
-        attributes: StakedStateOpAttributes,
-    ) -> Result<TxAux> {
-        let staked_state = self.get_staked_state(&address)?;
+        to_staked_account: &StakedStateAddress,
+    ) -> Result<Option<StakedState>> {
+        let mut staked_states = self.cached_staked_states()?;
 
-        if !staked_state.is_jailed() {
-            return Err(Error::new(
-                ErrorKind::IllegalInput,
-                "You can only unjail an already jailed account (synchronizing your wallet may help)",
-            ));
+        if let Some((staked_state, fetched_at)) = staked_states.get(to_staked_account) {
+            if fetched_at.elapsed() < self.staked_state_ttl {
+                return Ok(staked_state.clone());
+            }
         }
 
-        let nonce = staked_state.nonce;
-
-        let transaction = UnjailTx {
-            nonce,
-            address,
-            attributes,
-        };
-        let tx = Transaction::UnjailTransaction(transaction.clone());
+        let staked_state = match to_staked_account {
+            StakedStateAddress::BasicRedeem(ref a) => self.get_account(&a.0),
+        }?;
 
-        let public_key = match address {
-            StakedStateAddress::BasicRedeem(ref redeem_address) => self
-                .wallet_client
-                .find_staking_key(name, enckey, redeem_address)?
-                .chain(|| {
-                    (
-                        ErrorKind::InvalidInput,
-                        "Address not found in current wallet",
-                    )
-                })?,
-        };
-        let sign_key = self.wallet_client.sign_key(name, enckey, &public_key)?;
-        let signature = sign_key.sign(&tx).map(StakedStateOpWitness::new)?;
+        let staked_state_ttl = self.staked_state_ttl;
+        staked_states.retain(|_, (_, fetched_at)| fetched_at.elapsed() < staked_state_ttl);
+        staked_states.insert(*to_staked_account, (staked_state.clone(), Instant::now()));
 
-        Ok(TxAux::PublicTx(TxPublicAux::UnjailTx(
-            transaction,
-            signature,
-        )))
+        Ok(staked_state)
     }
 
-    fn create_withdraw_all_unbonded_stake_transaction(
+    fn cached_staked_states(
         &self,
-        name: &str,
-        enckey: &SecKey,
-        from_address: &StakedStateAddress,
-        to_address: ExtendedAddr,
-        attributes: TxAttributes,
-    ) -> Result<(TxAux, TransactionPending)> {
-        let staked_state = self.get_staked_state(from_address)?;
-
-        verify_unjailed(&staked_state).map_err(|e| {
+    ) -> Result<MutexGuard<HashMap<StakedStateAddress, (Option<StakedState>, Instant)>>> {
+        self.staked_states.lock().map_err(|_| {
             Error::new(
-                ErrorKind::ValidationError,
-                format!("Failed to validate staking account: {}", e),
+                ErrorKind::InternalError,
+                "Unable to acquire lock on staked state cache",
             )
-        })?;
+        })
+    }
 
-        let temp_output =
-            TxOut::new_with_timelock(to_address.clone(), Coin::zero(), staked_state.unbonded_from);
-        let fee = self.calculate_fee(vec![temp_output], attributes.clone())?;
-        let amount = (staked_state.unbonded - fee).chain(|| {
-            (
-                ErrorKind::IllegalInput,
-                "Calculated fee is more than the unbonded amount",
+    /// Returns nonce for a new transaction of given address, skipping pending nonces (if tracked)
+    fn next_nonce(&self, address: &StakedStateAddress, staked_state: &StakedState) -> Result<u64> {
+        match self.pending_nonces {
+            None => Ok(staked_state.nonce),
+            Some(ref pending_nonces) => {
+                let current_height = self.client.status()?.sync_info.latest_block_height.value();
+                pending_nonces.next_nonce(address, staked_state.nonce, current_height)
+            }
+        }
+    }
+
+    /// Get active council nodes
+    fn get_council_nodes(&self) -> Result<Vec<CouncilNodeEntry>> {
+        let bytes = self.client.query("council-nodes", &[])?.bytes();
+
+        serde_json::from_slice(&bytes).chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                "Cannot deserialize council nodes",
             )
-        })?;
-        let outputs = vec![TxOut::new_with_timelock(
-            to_address,
-            amount,
-            staked_state.unbonded_from,
-        )];
+        })
+    }
 
-        check_outputs_basic(&outputs).map_err(|e| {
-            Error::new(
-                ErrorKind::ValidationError,
-                format!("Failed to validate staking account: {}", e),
+    /// Refuses consensus public key which is used by another council node or was previously used
+    /// by given staking account
+    fn verify_consensus_pubkey_unused(
+        &self,
+        staking_account_address: &StakedStateAddress,
+        staked_state: &StakedState,
+        consensus_pubkey: &TendermintValidatorPubKey,
+    ) -> Result<()> {
+        if let Some(ref validator) = staked_state.validator {
+            let validator_address = TendermintValidatorAddress::from(consensus_pubkey);
+            let previously_used = validator.council_node.consensus_pubkey != *consensus_pubkey
+                && validator
+                    .used_validator_addresses
+                    .iter()
+                    .any(|(used_address, _)| *used_address == validator_address);
+
+            if previously_used {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Consensus pub-key was previously used by staking address {} and cannot be reused",
+                        staking_account_address
+                    ),
+                ));
+            }
+        }
+
+        match self.find_staking_address_by_consensus_key(consensus_pubkey)? {
+            Some(address) if address != *staking_account_address => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Consensus pub-key is already used by council node with staking address {}",
+                    address
+                ),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Calculates fee of given (mock) transaction with fee algorithm
+    fn fee_for_txaux(&self, tx_aux: &TxAux) -> Result<Fee> {
+        self.fee_algorithm.calculate_for_txaux(tx_aux).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Calculated fee is more than the maximum allowed value",
             )
-        })?;
+        })
+    }
 
-        self.create_withdraw_unbonded_stake_transaction(
-            name,
-            enckey,
-            from_address,
-            outputs,
-            attributes,
-        )
+    /// Calculate the withdraw unbounded fee
+    fn calculate_fee(&self, outputs: Vec<TxOut>, attributes: TxAttributes) -> Result<Coin> {
+        let tx = WithdrawUnbondedTx::new(0, outputs, attributes);
+        // mock the signature
+        let dummy_signer = DummySigner();
+        let tx_aux = dummy_signer.mock_txaux_for_withdraw(tx);
+        Ok(self.fee_for_txaux(&tx_aux)?.to_coin())
     }
 
-    fn create_node_join_transaction(
+    /// Returns minimum value of an output worth spending, i.e. the fee of a minimal transaction
+    /// (one input and one output) spending it
+    fn dust_threshold(&self) -> Result<Coin> {
+        let dummy_signer = DummySigner();
+        let tx = Tx::new_with(
+            vec![TxoPointer::new(TxId::default(), 0)],
+            vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::zero())],
+            TxAttributes::default(),
+        );
+        let witness = dummy_signer
+            .schnorr_sign_inputs_len(&[WitnessedUTxO::dummy()])
+            .chain(|| (ErrorKind::ValidationError, "Calculated fee failed"))?;
+        let tx_aux = dummy_signer.mock_txaux_for_tx(tx, witness);
+        Ok(self.fee_for_txaux(&tx_aux)?.to_coin())
+    }
+
+    /// Refuses deposit inputs which are frozen in wallet
+    fn verify_inputs_not_frozen(
         &self,
         name: &str,
         enckey: &SecKey,
-        staking_account_address: StakedStateAddress,
+        transactions: &[(TxoPointer, TxOut)],
+    ) -> Result<()> {
+        let frozen = self.wallet_client.list_frozen_utxos(name, enckey)?;
+        match transactions
+            .iter()
+            .find(|(input, _)| frozen.contains(input))
+        {
+            Some((input, _)) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Deposit input {}:{} is frozen (spending it has to be explicitly allowed)",
+                    hex::encode(&input.id),
+                    input.index
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Refuses deposit inputs which do not cover the fee plus a positive bonded amount
+    fn verify_deposit_fee_coverage(&self, transactions: &[(TxoPointer, TxOut)]) -> Result<()> {
+        let input_total =
+            sum_coins(transactions.iter().map(|(_, output)| output.value)).chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    "Total amount of deposit inputs exceeds maximum allowed value",
+                )
+            })?;
+        let fee = self.calculate_deposit_fee(transactions.len())?;
+
+        if input_total > fee {
+            return Ok(());
+        }
+
+        let shortfall = (fee - input_total).chain(|| {
+            (
+                ErrorKind::InternalError,
+                "Unable to calculate deposit shortfall",
+            )
+        })?;
+        let sign = if shortfall == Coin::zero() { "" } else { "-" };
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Deposit inputs total {} do not cover fee {} (resulting bonded amount would be {}{}), add more inputs",
+                input_total, fee, sign, shortfall
+            ),
+        ))
+    }
+
+    /// Refuses withdraw outputs with value below the dust threshold
+    fn verify_no_dust_outputs(&self, outputs: &[TxOut]) -> Result<()> {
+        let threshold = self.dust_threshold()?;
+        match outputs.iter().position(|output| output.value < threshold) {
+            Some(index) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Withdraw output {} has value {} which is below the minimum allowed value {} (fee of spending it)",
+                    index, outputs[index].value, threshold
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Calculate the unbond fee
+    fn calculate_unbond_fee(
+        &self,
+        address: StakedStateAddress,
+        nonce: u64,
+        value: Coin,
         attributes: StakedStateOpAttributes,
-        node_metadata: CouncilNode,
-    ) -> Result<TxAux> {
-        let staked_state = self.get_staked_state(&staking_account_address)?;
+    ) -> Result<Coin> {
+        let tx = UnbondTx::new(address, nonce, value, attributes);
+        // mock the signature
+        let dummy_signer = DummySigner();
+        let tx_aux = dummy_signer.mock_txaux_for_unbond(tx);
+        Ok(self.fee_for_txaux(&tx_aux)?.to_coin())
+    }
 
-        verify_unjailed(&staked_state).map_err(|e| {
-            Error::new(
-                ErrorKind::ValidationError,
-                format!("Failed to validate staking account: {}", e),
+    /// Verifies that withdraw outputs and fee sum to the unbonded amount exactly (as required by
+    /// the chain), optionally adding the remaining unbonded amount to the last output
+    fn balance_withdraw_outputs(
+        &self,
+        staked_state: &StakedState,
+        mut outputs: Vec<TxOut>,
+        attributes: &TxAttributes,
+        auto_adjust_last_output: bool,
+    ) -> Result<Vec<TxOut>> {
+        let output_value = sum_coins(outputs.iter().map(|output| output.value))
+            .chain(|| (ErrorKind::InvalidInput, "Error while adding output values"))?;
+        // output values are encoded with fixed length, so adjusting them does not change the fee
+        let fee = self.calculate_fee(outputs.clone(), attributes.clone())?;
+        let expected_value = (staked_state.unbonded - fee).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Calculated fee is more than the unbonded amount",
             )
         })?;
 
-        let transaction = NodeJoinRequestTx {
-            nonce: staked_state.nonce,
-            address: staking_account_address,
-            attributes,
-            node_meta: node_metadata,
-        };
-        let tx = Transaction::NodejoinTransaction(transaction.clone());
+        if output_value == expected_value {
+            return Ok(outputs);
+        }
 
-        let public_key = match staking_account_address {
-            StakedStateAddress::BasicRedeem(ref redeem_address) => self
-                .wallet_client
-                .find_staking_key(name, enckey, redeem_address)?
-                .chain(|| {
-                    (
-                        ErrorKind::InvalidInput,
-                        "Address not found in current wallet",
-                    )
-                })?,
+        let (difference, shortfall) = if output_value < expected_value {
+            (expected_value - output_value, true)
+        } else {
+            (output_value - expected_value, false)
         };
-        let sign_key = self.wallet_client.sign_key(name, enckey, &public_key)?;
-        let signature = sign_key.sign(&tx).map(StakedStateOpWitness::new)?;
+        let difference = difference.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                "Error while balancing output values",
+            )
+        })?;
 
-        Ok(TxAux::PublicTx(TxPublicAux::NodeJoinTx(
-            transaction,
-            signature,
-        )))
-    }
+        if shortfall && auto_adjust_last_output {
+            if let Some(last_output) = outputs.last_mut() {
+                last_output.value = (last_output.value + difference)
+                    .chain(|| (ErrorKind::InvalidInput, "Error while adjusting last output"))?;
+                return Ok(outputs);
+            }
+        }
 
-    #[inline]
-    fn get_staked_state(&self, address: &StakedStateAddress) -> Result<StakedState> {
-        self.get_staked_state_account(address)
+        let difference = if shortfall {
+            format!("shortfall of {}", difference)
+        } else {
+            format!("excess of {}", difference)
+        };
+
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Withdraw outputs must sum to {} (unbonded amount {} minus fee {}), but they sum to {} ({})",
+                expected_value, staked_state.unbonded, fee, output_value, difference
+            ),
+        ))
     }
-}
 
-fn to_timespec(time: Time) -> Timespec {
-    time.duration_since(Time::unix_epoch()).unwrap().as_secs()
-}
+    /// Builds and signs a withdraw transaction without checking the balance of its outputs
+    fn sign_withdraw_unbonded_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)> {
+        let output_value = sum_coins(outputs.iter().map(|output| output.value))
+            .chain(|| (ErrorKind::InvalidInput, "Error while adding output values"))?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use secstr::SecUtf8;
+        let unsigned = self.build_unsigned_withdraw_unbonded_stake_transaction(
+            from_address,
+            outputs,
+            attributes,
+            nonce_source,
+        )?;
+        let tx_aux = self.sign_staking_transaction(name, enckey, unsigned)?;
+        let pending_transaction = self.new_pending_transaction(&tx_aux, vec![], output_value)?;
+        Ok((tx_aux, pending_transaction))
+    }
 
-    use parity_scale_codec::Encode;
+    /// Builds and signs a transaction withdrawing all unbonded stake of given (already fetched)
+    /// staking account, split across given destinations
+    #[allow(clippy::too_many_arguments)]
+    fn withdraw_all_from_staked_state(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        staked_state: &StakedState,
+        destinations: Vec<(ExtendedAddr, u32, Option<Timespec>)>,
+        attributes: TxAttributes,
+        fee_priority: Option<FeePriority>,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)> {
+        // chain requires outputs to be locked exactly until `unbonded_from`
+        let unbonded_from = staked_state.unbonded_from;
+        let locks = destinations
+            .iter()
+            .map(|(address, _, lock_until)| match lock_until {
+                Some(lock_until) if *lock_until != unbonded_from => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Lock time {} of withdraw output to {} differs from unbonded from time {} (unix timestamp) required by the chain",
+                        lock_until, address, unbonded_from
+                    ),
+                )),
+                _ => Ok(unbonded_from),
+            })
+            .collect::<Result<Vec<Timespec>>>()?;
+        let shares = destinations
+            .iter()
+            .map(|(_, share, _)| *share)
+            .collect::<Vec<u32>>();
+
+        let build_outputs = |amount: Coin| -> Result<Vec<TxOut>> {
+            split_amount(amount, &shares)?
+                .into_iter()
+                .zip(destinations.iter().zip(locks.iter()))
+                .map(|(value, ((address, _, _), lock_until))| {
+                    if value == Coin::zero() {
+                        Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Withdraw split creates a zero value output for {}", address),
+                        ))
+                    } else {
+                        Ok(TxOut::new_with_timelock(
+                            address.clone(),
+                            value,
+                            *lock_until,
+                        ))
+                    }
+                })
+                .collect()
+        };
 
-    use chain_core::init::address::RedeemAddress;
-    use chain_core::init::coin::CoinError;
-    use chain_core::state::account::{
-        ConfidentialInit, StakedState, StakedStateOpAttributes, Validator,
-    };
-    use chain_core::state::tendermint::BlockHeight;
-    use chain_core::state::tendermint::TendermintValidatorPubKey;
-    use chain_core::state::ChainState;
-    use chain_core::tx::data::input::TxoSize;
-    use chain_core::tx::data::TxId;
-    use chain_core::tx::fee::Fee;
-    use chain_core::tx::TransactionId;
-    use chain_core::tx::{PlainTxAux, TxEnclaveAux, TxObfuscated};
-    use chain_tx_validation::witness::verify_tx_recover_address;
-    use client_common::storage::MemoryStorage;
-    use client_common::tendermint::lite;
-    use client_common::tendermint::mock;
-    use client_common::tendermint::types::*;
-    use client_common::{seckey::derive_enckey, PrivateKey, PublicKey, Transaction};
-    use client_core::service::HwKeyService;
-    use client_core::signer::WalletSignerManager;
-    use client_core::types::WalletKind;
-    use client_core::wallet::DefaultWalletClient;
+        // fee depends on the final transaction (including timelocks of its outputs), so it is
+        // recalculated until it is stable
+        let temp_outputs = destinations
+            .iter()
+            .zip(locks.iter())
+            .map(|((address, _, _), lock_until)| {
+                TxOut::new_with_timelock(address.clone(), Coin::zero(), *lock_until)
+            })
+            .collect();
+        let mut fee = self.calculate_fee(temp_outputs, attributes.clone())?;
+        let mut stable_outputs = None;
 
-    #[derive(Debug, Clone)]
-    struct MockTransactionCipher;
+        for _ in 0..MAX_FEE_ITERATIONS {
+            let amount = (staked_state.unbonded - fee).chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Calculated fee is more than the unbonded amount",
+                )
+            })?;
+            let outputs = build_outputs(amount)?;
+            let new_fee = self.calculate_fee(outputs.clone(), attributes.clone())?;
 
-    impl TransactionObfuscation for MockTransactionCipher {
-        fn decrypt(
-            &self,
-            _transaction_ids: &[TxId],
-            _private_key: &PrivateKey,
-        ) -> Result<Vec<Transaction>> {
-            unreachable!()
+            // chain requires the deducted fee to equal the minimum fee of the transaction exactly
+            if new_fee == fee {
+                stable_outputs = Some(outputs);
+                break;
+            }
+            fee = new_fee;
         }
 
-        fn encrypt(&self, transaction: SignedTransaction) -> Result<TxAux> {
-            match transaction {
-                SignedTransaction::TransferTransaction(_, _) => unreachable!(),
-                SignedTransaction::DepositStakeTransaction(tx, witness) => {
-                    let plain = PlainTxAux::DepositStakeTx(witness);
-                    Ok(TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx {
-                        tx: tx.clone(),
-                        payload: TxObfuscated {
-                            txid: tx.id(),
-                            key_from: BlockHeight::genesis(),
-                            init_vector: [0u8; 12],
-                            txpayload: plain.encode(),
-                        },
-                    }))
-                }
-                SignedTransaction::WithdrawUnbondedStakeTransaction(tx, witness) => {
-                    let plain = PlainTxAux::WithdrawUnbondedStakeTx(tx.clone());
-                    Ok(TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
-                        no_of_outputs: tx.outputs.len() as TxoSize,
-                        witness,
-                        payload: TxObfuscated {
-                            txid: tx.id(),
-                            key_from: BlockHeight::genesis(),
-                            init_vector: [0u8; 12],
-                            txpayload: plain.encode(),
-                        },
-                    }))
-                }
+        let outputs = stable_outputs.chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                format!(
+                    "Withdraw fee did not converge after {} iterations",
+                    MAX_FEE_ITERATIONS
+                ),
+            )
+        })?;
+
+        if let Some(FeePriority {
+            max_fee: Some(max_fee),
+            ..
+        }) = fee_priority
+        {
+            if max_fee < fee {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Required fee {} is more than the maximum fee {}",
+                        fee, max_fee
+                    ),
+                ));
             }
         }
+
+        check_outputs_basic(&outputs).map_err(|e| {
+            Error::new(
+                ErrorKind::ValidationError,
+                format!("Failed to validate staking account: {}", e),
+            )
+        })?;
+        // also covers withdrawing everything when unbonded amount minus fee is too small
+        self.verify_no_dust_outputs(&outputs)?;
+
+        // withdrawn amount already accounts for the fee
+        self.sign_withdraw_unbonded_stake_transaction(
+            name,
+            enckey,
+            from_address,
+            outputs,
+            attributes,
+            nonce_source,
+        )
     }
 
-    #[derive(Debug, Default)]
-    struct UnitFeeAlgorithm;
+    /// Creates pending information of a transaction built at current block height
+    fn new_pending_transaction(
+        &self,
+        tx_aux: &TxAux,
+        used_inputs: Vec<TxoPointer>,
+        return_amount: Coin,
+    ) -> Result<TransactionPending> {
+        let block_height = match self.block_height_source {
+            Some(ref block_height_source) => block_height_source.current_block_height()?,
+            None => self.client.status()?.sync_info.latest_block_height.value(),
+        };
+        Ok(TransactionPending {
+            block_height,
+            used_inputs,
+            return_amount,
+            tx_id: tx_aux.tx_id(),
+            valid_until_height: block_height + self.pending_tx_valid_blocks,
+        })
+    }
 
-    impl FeeAlgorithm for UnitFeeAlgorithm {
-        fn calculate_fee(&self, _num_bytes: usize) -> std::result::Result<Fee, CoinError> {
-            Ok(Fee::new(Coin::unit()))
-        }
+    fn get_last_block_time(&self) -> Result<Timespec> {
+        let status = self.client.status()?;
+        let latest_block_time = status.sync_info.latest_block_time;
 
-        fn calculate_for_txaux(&self, _txaux: &TxAux) -> std::result::Result<Fee, CoinError> {
-            Ok(Fee::new(Coin::unit()))
+        // there is no block at height 0 (some tendermint versions report zero time instead)
+        if status.sync_info.latest_block_height == Height(0) || is_zero_time(&latest_block_time) {
+            to_timespec(self.client.genesis()?.genesis_time)
+        } else {
+            to_timespec(latest_block_time)
         }
     }
 
-    #[derive(Default, Clone)]
-    pub struct MockJailedClient;
-
-    impl Client for MockJailedClient {
-        fn genesis(&self) -> Result<Genesis> {
-            unreachable!()
+    /// Broadcasts a transaction through wallet client, records it as pending in wallet and waits for its inclusion in
+    /// at most `wait_for_blocks` blocks (giving up after `block_wait_timeout` per awaited block if
+    /// no new block is produced)
+    fn broadcast_and_wait(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &StakedStateAddress,
+        tx_aux: TxAux,
+        tx_pending: TransactionPending,
+        wait_for_blocks: u64,
+    ) -> Result<BroadcastedTransaction> {
+        let transaction_id = tx_aux.tx_id();
+        let tx_bytes = tx_aux.encode();
+        let start_height = self.client.status()?.sync_info.latest_block_height.value();
+
+        let response = self.wallet_client.broadcast_transaction(&tx_aux);
+        // cached staked state is stale once the transaction is broadcasted (even a rejected
+        // transaction may have been built from an outdated nonce)
+        self.invalidate_staked_state(address)?;
+
+        if let Err(e) = response.and_then(|response| check_broadcast_response(&response)) {
+            if broadcast_error(&e).is_some() {
+                if let Some(ref pending_nonces) = self.pending_nonces {
+                    pending_nonces.remove(address, &transaction_id)?;
+                }
+            }
+            return Err(e);
         }
-
-        fn status(&self) -> Result<StatusResponse> {
-            unreachable!()
+        self.wallet_client
+            .update_tx_pending_state(name, enckey, transaction_id, tx_pending)?;
+
+        if wait_for_blocks == 0 {
+            return Ok(BroadcastedTransaction {
+                transaction_id,
+                block_height: None,
+            });
         }
 
-        fn block(&self, _: u64) -> Result<Block> {
-            unreachable!()
-        }
+        let last_height = start_height + wait_for_blocks;
+        let mut checked_height = start_height;
+        let timeout = self
+            .block_wait_timeout
+            .checked_mul(wait_for_blocks.try_into().unwrap_or(u32::max_value()))
+            .unwrap_or_else(|| Duration::from_secs(u64::max_value()));
+        let started = Instant::now();
+
+        while checked_height < last_height {
+            let latest_height = self.client.status()?.sync_info.latest_block_height.value();
+            if latest_height <= checked_height {
+                let elapsed = started.elapsed();
+                if elapsed >= timeout {
+                    return Err(Error::new(
+                        ErrorKind::TransactionTimeout,
+                        format!(
+                            "Transaction {} is not included in a block after {} seconds (chain is stuck at block {})",
+                            hex::encode(transaction_id),
+                            timeout.as_secs(),
+                            latest_height
+                        ),
+                    ));
+                }
+                thread::sleep(std::cmp::min(BLOCK_POLL_INTERVAL, timeout - elapsed));
+                continue;
+            }
 
-        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
-            unreachable!()
+            let latest_height = std::cmp::min(latest_height, last_height);
+            for height in (checked_height + 1)..=latest_height {
+                let block = self.client.block(height)?;
+                if block
+                    .data
+                    .iter()
+                    .any(|raw| raw.clone().into_vec() == tx_bytes)
+                {
+                    return Ok(BroadcastedTransaction {
+                        transaction_id,
+                        block_height: Some(height),
+                    });
+                }
+            }
+            checked_height = latest_height;
         }
 
-        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
-            unreachable!()
-        }
+        Err(Error::new(
+            ErrorKind::TransactionTimeout,
+            format!(
+                "Transaction {} is not included in a block after {} blocks",
+                hex::encode(transaction_id),
+                wait_for_blocks
+            ),
+        ))
+    }
 
-        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
-            &self,
-            _state: lite::TrustedState,
-            _heights: T,
-        ) -> Result<(Vec<Block>, lite::TrustedState)> {
-            unreachable!()
+    /// Builds a staking transaction with `build` and broadcasts it (see `broadcast_and_wait`); if
+    /// `retry_on_nonce_mismatch` is set and the transaction is rejected due to an outdated nonce,
+    /// it is rebuilt (with refetched staked state) and broadcasted again at most
+    /// `MAX_NONCE_MISMATCH_RETRIES` times
+    fn broadcast_with_retry<B>(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: &StakedStateAddress,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+        build: B,
+    ) -> Result<BroadcastedTransaction>
+    where
+        B: Fn() -> Result<(TxAux, TransactionPending)>,
+    {
+        let mut attempted = Vec::new();
+
+        loop {
+            let (tx_aux, tx_pending) = build()?;
+            attempted.push(hex::encode(tx_aux.tx_id()));
+
+            match self.broadcast_and_wait(
+                name,
+                enckey,
+                address,
+                tx_aux,
+                tx_pending,
+                wait_for_blocks,
+            ) {
+                Err(e) if retry_on_nonce_mismatch && is_nonce_mismatch(&e) => {
+                    // cached staked state is already invalidated, so rebuilt transaction uses
+                    // current nonce
+                    if attempted.len() > MAX_NONCE_MISMATCH_RETRIES {
+                        return Err(Error::new(
+                            e.kind(),
+                            format!(
+                                "{} (gave up after {} attempts, transaction ids: {})",
+                                e.message(),
+                                attempted.len(),
+                                attempted.join(", ")
+                            ),
+                        ));
+                    }
+                }
+                result => return result,
+            }
         }
+    }
 
-        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
-            &self,
-            _heights: T,
-        ) -> Result<Vec<BlockResultsResponse>> {
-            unreachable!()
+    /// Signs unsigned staking transaction with the staking key of expected signer in wallet
+    fn sign_staking_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        unsigned: UnsignedStakingTransaction,
+    ) -> Result<TxAux> {
+        let witness = self
+            .signer_manager
+            .create_signer(name, enckey, &self.signer_manager.hw_key_service)
+            .sign_staking_tx(&unsigned.transaction, &unsigned.signer)?;
+        let nonce = staking_nonce(&unsigned.transaction);
+
+        let tx_aux = self.assemble_staking_transaction(unsigned.transaction, witness)?;
+        if let (Some(pending_nonces), Some(nonce)) = (&self.pending_nonces, nonce) {
+            let current_height = self.client.status()?.sync_info.latest_block_height.value();
+            pending_nonces.record(&unsigned.signer, nonce, tx_aux.tx_id(), current_height)?;
         }
 
-        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
-            unreachable!()
+        Ok(tx_aux)
+    }
+
+    /// Calls `f` with block results of blocks from `from_height` to `to_height` (both inclusive),
+    /// fetched in batches of `BLOCK_RESULTS_BATCH_SIZE`
+    fn for_each_block_results<G>(&self, from_height: u64, to_height: u64, mut f: G) -> Result<()>
+    where
+        G: FnMut(&BlockResultsResponse) -> Result<()>,
+    {
+        if from_height > to_height {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Start height {} is greater than end height {}",
+                    from_height, to_height
+                ),
+            ));
         }
 
-        fn query(&self, _path: &str, _data: &[u8]) -> Result<AbciQuery> {
-            let staked_state = StakedState::new(
-                0,
-                Coin::new(1000000).unwrap(),
-                Coin::new(2499999999999999999 + 1).unwrap(),
-                0,
-                StakedStateAddress::BasicRedeem(RedeemAddress::default()),
-                Some(Validator {
-                    council_node: CouncilNode::new(
-                        TendermintValidatorPubKey::Ed25519([0xcd; 32]),
-                        ConfidentialInit {
-                            cert: b"FIXME".to_vec(),
-                        },
-                    ),
-                    jailed_until: Some(100),
-                    inactive_time: Some(0),
-                    inactive_block: Some(BlockHeight::genesis()),
-                    used_validator_addresses: vec![],
-                }),
+        let mut start = std::cmp::max(from_height, 1);
+        while start <= to_height {
+            let end = std::cmp::min(
+                start.saturating_add(BLOCK_RESULTS_BATCH_SIZE - 1),
+                to_height,
             );
+            let heights = (start..=end).collect::<Vec<u64>>();
+            for block_results in self.client.block_results_batch(heights.iter())? {
+                f(&block_results)?;
+            }
 
-            Ok(AbciQuery {
-                value: Some(staked_state.encode()),
-                ..Default::default()
-            })
+            if end == to_height {
+                break;
+            }
+            start = end + 1;
         }
 
-        fn query_state_batch<T: Iterator<Item = u64>>(
-            &self,
-            _heights: T,
-        ) -> Result<Vec<ChainState>> {
-            unreachable!()
+        Ok(())
+    }
+
+    /// Assembles final `TxAux` of a staking operation transaction from its witness
+    fn assemble_staking_transaction(
+        &self,
+        transaction: Transaction,
+        witness: StakedStateOpWitness,
+    ) -> Result<TxAux> {
+        match transaction {
+            Transaction::UnbondStakeTransaction(tx) => {
+                Ok(TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, witness)))
+            }
+            Transaction::UnjailTransaction(tx) => {
+                Ok(TxAux::PublicTx(TxPublicAux::UnjailTx(tx, witness)))
+            }
+            Transaction::NodejoinTransaction(tx) => {
+                Ok(TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness)))
+            }
+            Transaction::WithdrawUnbondedStakeTransaction(tx) => self.transaction_cipher.encrypt(
+                SignedTransaction::WithdrawUnbondedStakeTransaction(tx, witness),
+            ),
+            Transaction::TransferTransaction(_) | Transaction::DepositStakeTransaction(_) => {
+                Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Transaction is not signed by a staking address",
+                ))
+            }
         }
     }
+}
+
+impl<W, S, C, F, E> NetworkOpsClient for DefaultNetworkOpsClient<W, S, C, F, E>
+where
+    W: WalletClient,
+    S: Storage,
+    C: Client,
+    F: FeeAlgorithm,
+    E: TransactionObfuscation,
+{
+    #[inline]
+    fn unlocked_wallet(&self, token: &SessionToken) -> Result<(String, SecKey)> {
+        self.wallet_client.unlocked_wallet(token)
+    }
 
-    #[derive(Default, Clone)]
-    pub struct MockClient;
+    fn calculate_deposit_fee(&self, no_of_inputs: usize) -> Result<Coin> {
+        let dummy_signer = DummySigner();
+        let tx_aux = dummy_signer
+            .mock_txaux_for_deposit(&vec![WitnessedUTxO::dummy(); no_of_inputs])
+            .chain(|| (ErrorKind::ValidationError, "Calculated fee failed"))?;
+        Ok(self.fee_for_txaux(&tx_aux)?.to_coin())
+    }
 
-    impl Client for MockClient {
-        fn genesis(&self) -> Result<Genesis> {
-            unreachable!()
+    fn estimate_fee(&self, kind: StakingTxKind) -> Result<Fee> {
+        let dummy_signer = DummySigner();
+        let address = StakedStateAddress::BasicRedeem(RedeemAddress::default());
+        let attributes = StakedStateOpAttributes::default();
+
+        let tx_aux = match kind {
+            StakingTxKind::Deposit { inputs } => dummy_signer
+                .mock_txaux_for_deposit(&vec![WitnessedUTxO::dummy(); inputs])
+                .chain(|| (ErrorKind::ValidationError, "Calculated fee failed"))?,
+            StakingTxKind::Unbond => dummy_signer.mock_txaux_for_unbond(UnbondTx::new(
+                address,
+                0,
+                Coin::zero(),
+                attributes,
+            )),
+            StakingTxKind::Withdraw { outputs } => {
+                let output =
+                    TxOut::new_with_timelock(ExtendedAddr::OrTree([0; 32]), Coin::zero(), 0);
+                dummy_signer.mock_txaux_for_withdraw(WithdrawUnbondedTx::new(
+                    0,
+                    vec![output; outputs],
+                    TxAttributes::default(),
+                ))
+            }
+            StakingTxKind::Unjail => dummy_signer.mock_txaux_for_unjail(UnjailTx {
+                nonce: 0,
+                address,
+                attributes,
+            }),
+            StakingTxKind::NodeJoin { node_metadata } => {
+                dummy_signer.mock_txaux_for_node_join(NodeJoinRequestTx {
+                    nonce: 0,
+                    address,
+                    attributes,
+                    node_meta: node_metadata,
+                })
+            }
+        };
+
+        self.fee_for_txaux(&tx_aux)
+    }
+
+    fn verify_deposit_destination(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        to_address: &StakedStateAddress,
+        allow_external: bool,
+    ) -> Result<()> {
+        // if the to_address belongs to current wallet, we do not check the state; a missing
+        // state is fine as well, as deposit creates it
+        let staking_addresses = self.wallet_client.staking_addresses(name, enckey)?;
+        if staking_addresses.contains(to_address) {
+            return Ok(());
         }
 
-        fn status(&self) -> Result<StatusResponse> {
-            Ok(StatusResponse {
-                sync_info: status::SyncInfo {
-                    latest_block_height: Height::default(),
-                    latest_app_hash: None,
-                    ..mock::sync_info()
-                },
-                ..mock::status_response()
-            })
+        if !allow_external {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Staking address {} is not one of {} staking addresses of this wallet (deposits to external staking addresses have to be explicitly allowed)",
+                    to_address,
+                    staking_addresses.len()
+                ),
+            ));
         }
 
-        fn block(&self, _: u64) -> Result<Block> {
-            unreachable!()
+        if let Some(staked_state) = self.get_staked_state_maybe(to_address)? {
+            verify_unjailed(&staked_state).map_err(|e| {
+                Error::new(
+                    ErrorKind::ValidationError,
+                    format!("Failed to validate staking account: {}", e),
+                )
+            })?;
         }
 
-        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
-            unreachable!()
+        Ok(())
+    }
+
+    fn create_deposit_bonded_stake_transaction<'a>(
+        &'a self,
+        name: &'a str,
+        enckey: &'a SecKey,
+        transactions: Vec<(TxoPointer, TxOut)>,
+        to_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        allow_external: bool,
+        allow_frozen: bool,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        let attributes = self.staking_attributes(attributes)?;
+        self.verify_chain_hex_id(attributes.chain_hex_id)?;
+        self.verify_deposit_destination(name, enckey, &to_address, allow_external)?;
+        self.verify_deposit_fee_coverage(&transactions)?;
+        if !allow_frozen {
+            self.verify_inputs_not_frozen(name, enckey, &transactions)?;
         }
 
-        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
-            unreachable!()
+        let inputs = transactions
+            .iter()
+            .map(|(input, _)| input.clone())
+            .collect::<Vec<_>>();
+
+        let transaction = DepositBondTx::new(inputs.clone(), to_address, attributes);
+        let unspent_transactions = UnspentTransactions::new(transactions);
+        let signer =
+            self.signer_manager
+                .create_signer(name, enckey, &self.signer_manager.hw_key_service);
+
+        let tx = Transaction::DepositStakeTransaction(transaction.clone());
+        let witness = signer.schnorr_sign_transaction(&tx, &unspent_transactions.select_all())?;
+
+        check_inputs_basic(&transaction.inputs, &witness).map_err(|e| {
+            Error::new(
+                ErrorKind::ValidationError,
+                format!("Failed to validate deposit transaction inputs: {}", e),
+            )
+        })?;
+
+        let signed_transaction = SignedTransaction::DepositStakeTransaction(transaction, witness);
+        let tx_aux = self.transaction_cipher.encrypt(signed_transaction)?;
+        let pending_transaction = self.new_pending_transaction(&tx_aux, inputs, Coin::zero())?;
+        Ok((tx_aux, pending_transaction))
+    }
+
+    fn create_deposit_amount_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        to_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        allow_external: bool,
+        coin_selection: Option<&dyn CoinSelectionStrategy>,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        let unspent_transactions = self.wallet_client.unspent_transactions(name, enckey)?;
+
+        let available = sum_coins(unspent_transactions.iter().map(|(_, output)| output.value))
+            .chain(|| {
+                (
+                    ErrorKind::IllegalInput,
+                    "Total amount of UTXOs exceeds maximum allowed value",
+                )
+            })?;
+
+        // fee grows with number of inputs, the shortfall is reported for spending all of them
+        let fee = self.calculate_deposit_fee(unspent_transactions.len())?;
+        let required = (amount + fee).chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                "Deposit amount and fee exceed maximum allowed value",
+            )
+        })?;
+        if available < required {
+            let shortfall = (required - available).chain(|| {
+                (
+                    ErrorKind::InternalError,
+                    "Unable to calculate deposit shortfall",
+                )
+            })?;
+            return Err(Error::new(
+                ErrorKind::InsufficientBalance,
+                format!(
+                    "Not enough balance to deposit {} with fee {}, short by {}",
+                    amount, fee, shortfall
+                ),
+            ));
+        }
+
+        // deposit has no change output, any excess of selected inputs is bonded as well
+        let fee_estimator = |no_of_inputs: usize, _no_of_change_outputs: usize| {
+            // a fee which cannot be calculated makes the selection fail
+            self.calculate_deposit_fee(no_of_inputs)
+                .unwrap_or_else(|_| Coin::max())
+        };
+        let selection = coin_selection.unwrap_or(&LargestFirst).select(
+            &unspent_transactions,
+            amount,
+            &fee_estimator,
+        )?;
+
+        self.create_deposit_bonded_stake_transaction(
+            name,
+            enckey,
+            selection.inputs,
+            to_address,
+            attributes,
+            allow_external,
+            false,
+        )
+    }
+
+    fn build_unsigned_unbond_stake_transaction(
+        &self,
+        address: StakedStateAddress,
+        value: Coin,
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction> {
+        self.verify_owned_address(&address)?;
+        let attributes = self.staking_attributes(attributes)?;
+        let nonce = match nonce_source {
+            NonceSource::Network => {
+                self.verify_chain_hex_id(attributes.chain_hex_id)?;
+                let staked_state = self.get_staked_state(&address)?;
+
+                verify_unjailed(&staked_state).map_err(|e| {
+                    Error::new(
+                        ErrorKind::ValidationError,
+                        format!("Failed to validate staking account: {}", e),
+                    )
+                })?;
+
+                if staked_state.bonded < value {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Staking account does not have enough coins to unbond (synchronizing your wallet may help)",
+                    ));
+                }
+
+                self.next_nonce(&address, &staked_state)?
+            }
+            NonceSource::Explicit(nonce) => nonce,
+        };
+
+        let transaction = UnbondTx::new(address, nonce, value, attributes);
+
+        Ok(UnsignedStakingTransaction {
+            transaction: Transaction::UnbondStakeTransaction(transaction),
+            signer: address,
+        })
+    }
+
+    fn create_unbond_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        value: Coin,
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, nonce_source)?;
+        let unsigned =
+            self.build_unsigned_unbond_stake_transaction(address, value, attributes, nonce_source)?;
+        let tx_aux = self.sign_staking_transaction(name, enckey, unsigned)?;
+        let pending_transaction = self.new_pending_transaction(&tx_aux, vec![], Coin::zero())?;
+        Ok((tx_aux, pending_transaction))
+    }
+
+    fn create_unbond_all_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        let attributes = self.staking_attributes(attributes)?;
+        let staked_state = self.get_staked_state(&address)?;
+        let bonded = staked_state.bonded;
+        let amount_after_fee = |fee: Coin| {
+            if bonded <= fee {
+                Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Bonded amount {} is not enough to pay the unbond fee {}",
+                        bonded, fee
+                    ),
+                ))
+            } else {
+                (bonded - fee).chain(|| {
+                    (
+                        ErrorKind::IllegalInput,
+                        "Calculated fee is more than the bonded amount",
+                    )
+                })
+            }
+        };
+
+        let fee =
+            self.calculate_unbond_fee(address, staked_state.nonce, bonded, attributes.clone())?;
+        let mut amount = amount_after_fee(fee)?;
+
+        // fee may change once the actual amount is substituted
+        let new_fee =
+            self.calculate_unbond_fee(address, staked_state.nonce, amount, attributes.clone())?;
+        if new_fee != fee {
+            amount = amount_after_fee(new_fee)?;
+        }
+
+        self.create_unbond_stake_transaction(
+            name,
+            enckey,
+            address,
+            amount,
+            Some(attributes),
+            NonceSource::Network,
+        )
+    }
+
+    fn build_unsigned_withdraw_unbonded_stake_transaction(
+        &self,
+        from_address: &StakedStateAddress,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction> {
+        self.verify_owned_address(from_address)?;
+        let output_value = sum_coins(outputs.iter().map(|output| output.value))
+            .chain(|| (ErrorKind::InvalidInput, "Error while adding output values"))?;
+
+        let nonce = match nonce_source {
+            NonceSource::Network => {
+                self.verify_chain_hex_id(attributes.chain_hex_id)?;
+                let last_block_time = self.get_last_block_time()?;
+                let staked_state = self.get_staked_state(from_address)?;
+                verify_withdrawable(&staked_state, last_block_time)?;
+
+                if staked_state.unbonded < output_value {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Staking account does not have enough unbonded coins to withdraw (synchronizing your wallet may help)",
+                    ));
+                }
+
+                self.next_nonce(from_address, &staked_state)?
+            }
+            NonceSource::Explicit(nonce) => nonce,
+        };
+
+        let transaction = WithdrawUnbondedTx::new(nonce, outputs, attributes);
+
+        Ok(UnsignedStakingTransaction {
+            transaction: Transaction::WithdrawUnbondedStakeTransaction(transaction),
+            signer: *from_address,
+        })
+    }
+
+    fn create_withdraw_unbonded_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        nonce_source: NonceSource,
+        auto_adjust_last_output: bool,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, nonce_source)?;
+        let outputs = match nonce_source {
+            NonceSource::Network => {
+                let staked_state = self.get_staked_state(from_address)?;
+                self.balance_withdraw_outputs(
+                    &staked_state,
+                    outputs,
+                    &attributes,
+                    auto_adjust_last_output,
+                )?
+            }
+            NonceSource::Explicit(_) => outputs,
+        };
+        self.verify_no_dust_outputs(&outputs)?;
+
+        self.sign_withdraw_unbonded_stake_transaction(
+            name,
+            enckey,
+            from_address,
+            outputs,
+            attributes,
+            nonce_source,
+        )
+    }
+
+    fn build_unsigned_unjail_transaction(
+        &self,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction> {
+        self.verify_owned_address(&address)?;
+        let attributes = self.staking_attributes(attributes)?;
+        let nonce = match nonce_source {
+            NonceSource::Network => {
+                self.verify_chain_hex_id(attributes.chain_hex_id)?;
+                let staked_state = self.get_staked_state(&address)?;
+
+                if !staked_state.is_jailed() {
+                    return Err(Error::new(
+                        ErrorKind::IllegalInput,
+                        "You can only unjail an already jailed account (synchronizing your wallet may help)",
+                    ));
+                }
+
+                let jailed_until = staked_state
+                    .validator
+                    .as_ref()
+                    .and_then(|validator| validator.jailed_until);
+                if let Some(jailed_until) = jailed_until {
+                    let last_block_time = self.get_last_block_time()?;
+                    if last_block_time < jailed_until {
+                        return Err(Error::new(
+                            ErrorKind::IllegalInput,
+                            format!(
+                                "Jail period has not expired yet: {} seconds remaining, account can be unjailed at {} (unix timestamp)",
+                                jailed_until - last_block_time,
+                                jailed_until
+                            ),
+                        ));
+                    }
+                }
+
+                self.next_nonce(&address, &staked_state)?
+            }
+            NonceSource::Explicit(nonce) => nonce,
+        };
+
+        let transaction = UnjailTx {
+            nonce,
+            address,
+            attributes,
+        };
+
+        Ok(UnsignedStakingTransaction {
+            transaction: Transaction::UnjailTransaction(transaction),
+            signer: address,
+        })
+    }
+
+    fn create_unjail_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, nonce_source)?;
+        let unsigned = self.build_unsigned_unjail_transaction(address, attributes, nonce_source)?;
+        let tx_aux = self.sign_staking_transaction(name, enckey, unsigned)?;
+        let pending_transaction = self.new_pending_transaction(&tx_aux, vec![], Coin::zero())?;
+        Ok((tx_aux, pending_transaction))
+    }
+
+    fn create_withdraw_all_unbonded_stake_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+        fee_priority: Option<FeePriority>,
+        extra_lock_until: Option<Timespec>,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        self.create_withdraw_all_unbonded_stake_split_transaction(
+            name,
+            enckey,
+            from_address,
+            vec![(to_address, TOTAL_BASIS_POINTS, extra_lock_until)],
+            attributes,
+            fee_priority,
+        )
+    }
+
+    fn create_withdraw_all_unbonded_stake_split_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        destinations: Vec<(ExtendedAddr, u32, Option<Timespec>)>,
+        attributes: TxAttributes,
+        fee_priority: Option<FeePriority>,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        if let Some(ref fee_priority) = fee_priority {
+            verify_fee_priority(fee_priority)?;
+        }
+
+        let total_share: u64 = destinations
+            .iter()
+            .map(|(_, share, _)| u64::from(*share))
+            .sum();
+        if destinations.is_empty() || total_share != u64::from(TOTAL_BASIS_POINTS) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Shares of withdraw destinations must sum to {} basis points",
+                    TOTAL_BASIS_POINTS
+                ),
+            ));
+        }
+
+        let staked_state = self.get_staked_state(from_address)?;
+
+        verify_unjailed(&staked_state).map_err(|e| {
+            Error::new(
+                ErrorKind::ValidationError,
+                format!("Failed to validate staking account: {}", e),
+            )
+        })?;
+
+        self.withdraw_all_from_staked_state(
+            name,
+            enckey,
+            from_address,
+            &staked_state,
+            destinations,
+            attributes,
+            fee_priority,
+            NonceSource::Network,
+        )
+    }
+
+    fn create_withdraw_all_batch(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_addresses: &[StakedStateAddress],
+        to_address: ExtendedAddr,
+        attributes: TxAttributes,
+    ) -> Result<Vec<(StakedStateAddress, Result<(TxAux, TransactionPending)>)>> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        self.verify_chain_hex_id(attributes.chain_hex_id)?;
+        let last_block_time = self.get_last_block_time()?;
+        let staked_states = self.get_staked_states(from_addresses)?;
+
+        let withdraw = |address: &StakedStateAddress,
+                        staked_state: Option<StakedState>|
+         -> Result<(TxAux, TransactionPending)> {
+            let staked_state = staked_state.chain(|| {
+                (
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Staking address {} has no on-chain state yet (deposit to it first)",
+                        address
+                    ),
+                )
+            })?;
+            verify_withdrawable(&staked_state, last_block_time)?;
+            if staked_state.unbonded == Coin::zero() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "Staking address {} has no unbonded stake to withdraw",
+                        address
+                    ),
+                ));
+            }
+
+            // states are already fetched and checked, so nonce is not fetched again
+            let nonce = self.next_nonce(address, &staked_state)?;
+            self.withdraw_all_from_staked_state(
+                name,
+                enckey,
+                address,
+                &staked_state,
+                vec![(to_address.clone(), TOTAL_BASIS_POINTS, None)],
+                attributes.clone(),
+                None,
+                NonceSource::Explicit(nonce),
+            )
+        };
+
+        Ok(from_addresses
+            .iter()
+            .zip(staked_states.into_iter())
+            .map(|(address, staked_state)| (*address, withdraw(address, staked_state)))
+            .collect())
+    }
+
+    fn create_restake_transactions(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        to_staking_address: StakedStateAddress,
+        attributes: TxAttributes,
+    ) -> Result<(Vec<TxAux>, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        // withdraw output is locked until `unbonded_from`, deposit cannot spend it earlier
+        let staked_state = self.get_staked_state(from_address)?;
+        let last_block_time = self.get_last_block_time()?;
+        if last_block_time < staked_state.unbonded_from {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Withdrawn stake is locked until {} (unix timestamp), {} seconds from now, so it cannot be deposited yet",
+                    staked_state.unbonded_from,
+                    staked_state.unbonded_from - last_block_time
+                ),
+            ));
+        }
+
+        let withdraw_address = self.wallet_client.new_transfer_address(name, enckey)?;
+        let (withdraw_tx_aux, withdraw_pending) = self
+            .create_withdraw_all_unbonded_stake_transaction(
+                name,
+                enckey,
+                from_address,
+                withdraw_address.clone(),
+                attributes,
+                None,
+                None,
+            )?;
+
+        // the only output of withdraw transaction is known without waiting for a sync
+        let input = TxoPointer::new(withdraw_tx_aux.tx_id(), 0);
+        let output = TxOut::new_with_timelock(
+            withdraw_address,
+            withdraw_pending.return_amount,
+            staked_state.unbonded_from,
+        );
+        let (deposit_tx_aux, deposit_pending) = self.create_deposit_bonded_stake_transaction(
+            name,
+            enckey,
+            vec![(input, output)],
+            to_staking_address,
+            None,
+            false,
+            false,
+        )?;
+
+        // withdrawn amount is bonded again and the deposit input is not an unspent output of the
+        // wallet yet, so nothing is used or returned until the deposit is applied
+        let pending_transaction = TransactionPending {
+            used_inputs: vec![],
+            ..deposit_pending
+        };
+        Ok((vec![withdraw_tx_aux, deposit_tx_aux], pending_transaction))
+    }
+
+    fn build_unsigned_node_join_transaction(
+        &self,
+        staking_account_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        node_metadata: CouncilNode,
+        nonce_source: NonceSource,
+    ) -> Result<UnsignedStakingTransaction> {
+        self.verify_owned_address(&staking_account_address)?;
+        let attributes = self.staking_attributes(attributes)?;
+        let nonce = match nonce_source {
+            NonceSource::Network => {
+                self.verify_chain_hex_id(attributes.chain_hex_id)?;
+                let staked_state = self.get_staked_state(&staking_account_address)?;
+
+                verify_unjailed(&staked_state).map_err(|e| {
+                    Error::new(
+                        ErrorKind::ValidationError,
+                        format!("Failed to validate staking account: {}", e),
+                    )
+                })?;
+
+                let required_stake = self.get_genesis_info()?.required_council_node_stake;
+                if staked_state.bonded < required_stake {
+                    return Err(Error::new(
+                        ErrorKind::IllegalInput,
+                        format!(
+                            "Bonded amount {} is less than required council node stake {}",
+                            staked_state.bonded, required_stake
+                        ),
+                    ));
+                }
+
+                self.verify_consensus_pubkey_unused(
+                    &staking_account_address,
+                    &staked_state,
+                    &node_metadata.consensus_pubkey,
+                )?;
+
+                self.next_nonce(&staking_account_address, &staked_state)?
+            }
+            NonceSource::Explicit(nonce) => nonce,
+        };
+
+        validate_council_node(&node_metadata)?;
+
+        let transaction = NodeJoinRequestTx {
+            nonce,
+            address: staking_account_address,
+            attributes,
+            node_meta: node_metadata,
+        };
+
+        Ok(UnsignedStakingTransaction {
+            transaction: Transaction::NodejoinTransaction(transaction),
+            signer: staking_account_address,
+        })
+    }
+
+    fn create_node_join_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_account_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        node_metadata: CouncilNode,
+        nonce_source: NonceSource,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, nonce_source)?;
+        let unsigned = self.build_unsigned_node_join_transaction(
+            staking_account_address,
+            attributes,
+            node_metadata,
+            nonce_source,
+        )?;
+        let tx_aux = self.sign_staking_transaction(name, enckey, unsigned)?;
+        let pending_transaction = self.new_pending_transaction(&tx_aux, vec![], Coin::zero())?;
+        Ok((tx_aux, pending_transaction))
+    }
+
+    fn preview_node_join_transaction(
+        &self,
+        staking_account_address: &StakedStateAddress,
+        node_metadata: &CouncilNode,
+    ) -> Result<NodeJoinPreview> {
+        let new_cert = &node_metadata.confidential_init.cert;
+        validate_cert(new_cert)?;
+
+        let staked_state = self.get_staked_state(staking_account_address)?;
+        let current_cert_fingerprint = staked_state
+            .validator
+            .map(|validator| cert_fingerprint(&validator.council_node.confidential_init.cert));
+        let new_cert_fingerprint = cert_fingerprint(new_cert);
+        let cert_changes = current_cert_fingerprint.as_ref() != Some(&new_cert_fingerprint);
+
+        Ok(NodeJoinPreview {
+            new_cert_fingerprint,
+            current_cert_fingerprint,
+            cert_changes,
+        })
+    }
+
+    fn update_node_metadata(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        new_metadata: CouncilNode,
+        allow_pubkey_rotation: bool,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, NonceSource::Network)?;
+        let staked_state = self.get_staked_state(&address)?;
+        let current_metadata = match staked_state.validator {
+            Some(ref validator) => &validator.council_node,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                    "Staking address {} is not a council node (use node join transaction instead)",
+                    address
+                ),
+                ))
+            }
+        };
+
+        if *current_metadata == new_metadata {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "New council node metadata is identical to current one",
+            ));
+        }
+
+        if current_metadata.consensus_pubkey != new_metadata.consensus_pubkey
+            && !allow_pubkey_rotation
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "New council node metadata changes consensus pub-key (rotation has to be explicitly allowed)",
+            ));
+        }
+
+        // jailing, required stake and reuse of consensus pub-key are verified when building the
+        // node join transaction
+        self.create_node_join_transaction(
+            name,
+            enckey,
+            address,
+            attributes,
+            new_metadata,
+            NonceSource::Network,
+        )
+    }
+
+    fn create_acknowledged_node_join_transaction(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_account_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        node_metadata: CouncilNode,
+        nonce_source: NonceSource,
+        cert_fingerprint_ack: &str,
+    ) -> Result<(TxAux, TransactionPending)> {
+        self.verify_signing_wallet(name, enckey, nonce_source)?;
+        if cert_fingerprint_ack.is_empty() {
+            return Err(Error::new(
+                ErrorKind::ValidationError,
+                "Certificate fingerprint acknowledgement is missing",
+            ));
+        }
+        if cert_fingerprint(&node_metadata.confidential_init.cert) != cert_fingerprint_ack {
+            return Err(Error::new(
+                ErrorKind::ValidationError,
+                "Certificate fingerprint acknowledgement does not match new certificate",
+            ));
+        }
+
+        self.create_node_join_transaction(
+            name,
+            enckey,
+            staking_account_address,
+            attributes,
+            node_metadata,
+            nonce_source,
+        )
+    }
+
+    fn attach_witness(
+        &self,
+        unsigned: UnsignedStakingTransaction,
+        witness: StakedStateOpWitness,
+    ) -> Result<TxAux> {
+        let recovered_address = verify_tx_recover_address(&witness, &unsigned.transaction.id())
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::ValidationError,
+                    format!("Failed to verify witness: {}", e),
+                )
+            })?;
+
+        if recovered_address != unsigned.signer {
+            return Err(Error::new(
+                ErrorKind::ValidationError,
+                "Witness is not signed by expected staking address",
+            ));
+        }
+
+        self.assemble_staking_transaction(unsigned.transaction, witness)
+    }
+
+    fn finalize_staking_tx(&self, tx: Transaction, witness: StakedStateOpWitness) -> Result<TxAux> {
+        let nonce = staking_nonce(&tx).chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                "Transaction is not signed by a staking address",
+            )
+        })?;
+        let recovered_address = verify_tx_recover_address(&witness, &tx.id()).map_err(|e| {
+            Error::new(
+                ErrorKind::ValidationError,
+                format!("Failed to verify witness: {}", e),
+            )
+        })?;
+
+        // withdraw transaction does not contain its staking address, which is only identified by
+        // the witness
+        if let Some(address) = staking_signer(&tx) {
+            if address != recovered_address {
+                return Err(Error::new(
+                    ErrorKind::ValidationError,
+                    format!(
+                        "Witness is signed by staking address {} instead of transaction's staking address {}",
+                        recovered_address, address
+                    ),
+                ));
+            }
+        }
+
+        self.invalidate_staked_state(&recovered_address)?;
+        let staked_state = self.get_staked_state(&recovered_address)?;
+        if staked_state.nonce != nonce {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transaction nonce {} does not match current nonce {} of staking address {} (transaction has to be built and signed again)",
+                    nonce, staked_state.nonce, recovered_address
+                ),
+            ));
+        }
+
+        self.assemble_staking_transaction(tx, witness)
+    }
+
+    fn broadcast_encoded_transaction(&self, encoded: &str) -> Result<BroadcastedTransaction> {
+        let tx_aux = decode_tx_aux(encoded)?;
+        let transaction_id = tx_aux.tx_id();
+
+        // submitted bytes are always the canonical encoding of decoded transaction
+        let response = self.client.broadcast_transaction(&tx_aux.encode())?;
+        check_broadcast_response(&response)?;
+
+        Ok(BroadcastedTransaction {
+            transaction_id,
+            block_height: None,
+        })
+    }
+
+    fn deposit_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        amount: Coin,
+        to_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        wait_for_blocks: u64,
+        allow_external: bool,
+    ) -> Result<BroadcastedTransaction> {
+        let (tx_aux, tx_pending) = self.create_deposit_amount_transaction(
+            name,
+            enckey,
+            amount,
+            to_address,
+            attributes,
+            allow_external,
+            None,
+        )?;
+        self.broadcast_and_wait(
+            name,
+            enckey,
+            &to_address,
+            tx_aux,
+            tx_pending,
+            wait_for_blocks,
+        )
+    }
+
+    fn unbond_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        value: Coin,
+        attributes: Option<StakedStateOpAttributes>,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction> {
+        self.broadcast_with_retry(
+            name,
+            enckey,
+            &address,
+            wait_for_blocks,
+            retry_on_nonce_mismatch,
+            || {
+                self.create_unbond_stake_transaction(
+                    name,
+                    enckey,
+                    address,
+                    value,
+                    attributes.clone(),
+                    NonceSource::Network,
+                )
+            },
+        )
+    }
+
+    fn withdraw_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        from_address: &StakedStateAddress,
+        outputs: Vec<TxOut>,
+        attributes: TxAttributes,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction> {
+        self.broadcast_with_retry(
+            name,
+            enckey,
+            from_address,
+            wait_for_blocks,
+            retry_on_nonce_mismatch,
+            || {
+                self.create_withdraw_unbonded_stake_transaction(
+                    name,
+                    enckey,
+                    from_address,
+                    outputs.clone(),
+                    attributes.clone(),
+                    NonceSource::Network,
+                    false,
+                )
+            },
+        )
+    }
+
+    fn unjail_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction> {
+        self.broadcast_with_retry(
+            name,
+            enckey,
+            &address,
+            wait_for_blocks,
+            retry_on_nonce_mismatch,
+            || {
+                self.create_unjail_transaction(
+                    name,
+                    enckey,
+                    address,
+                    attributes.clone(),
+                    NonceSource::Network,
+                )
+            },
+        )
+    }
+
+    fn node_join_and_broadcast(
+        &self,
+        name: &str,
+        enckey: &SecKey,
+        staking_account_address: StakedStateAddress,
+        attributes: Option<StakedStateOpAttributes>,
+        node_metadata: CouncilNode,
+        wait_for_blocks: u64,
+        retry_on_nonce_mismatch: bool,
+    ) -> Result<BroadcastedTransaction> {
+        self.broadcast_with_retry(
+            name,
+            enckey,
+            &staking_account_address,
+            wait_for_blocks,
+            retry_on_nonce_mismatch,
+            || {
+                self.create_node_join_transaction(
+                    name,
+                    enckey,
+                    staking_account_address,
+                    attributes.clone(),
+                    node_metadata.clone(),
+                    NonceSource::Network,
+                )
+            },
+        )
+    }
+
+    fn get_staked_state(&self, address: &StakedStateAddress) -> Result<StakedState> {
+        self.get_staked_state_maybe(address)?.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!(
+                    "Staking address {} has no on-chain state yet (deposit to it first)",
+                    address
+                ),
+            )
+        })
+    }
+
+    #[inline]
+    fn get_staked_state_maybe(&self, address: &StakedStateAddress) -> Result<Option<StakedState>> {
+        self.get_staked_state_account(address)
+    }
+
+    fn get_staked_states(
+        &self,
+        addresses: &[StakedStateAddress],
+    ) -> Result<Vec<Option<StakedState>>> {
+        let staked_state_addresses = addresses
+            .iter()
+            .map(|address| match address {
+                StakedStateAddress::BasicRedeem(ref a) => a.0,
+            })
+            .collect::<Vec<_>>();
+
+        let responses = self.client.query_batch(
+            staked_state_addresses
+                .iter()
+                .map(|staked_state_address| ("account", &staked_state_address[..])),
+        )?;
+
+        if responses.len() != staked_state_addresses.len() {
+            return Err(Error::new(
+                ErrorKind::TendermintRpcError,
+                "Number of account query responses does not match number of addresses",
+            ));
+        }
+
+        responses
+            .into_iter()
+            .zip(staked_state_addresses.iter())
+            .map(|(response, staked_state_address)| {
+                decode_staked_state(response, staked_state_address)
+            })
+            .collect()
+    }
+
+    fn balance_by_address(&self, name: &str, enckey: &SecKey) -> Result<Vec<AddressBalance>> {
+        let mut balances = self.wallet_client.balance_by_address(name, enckey)?;
+        for balance in balances.iter_mut() {
+            if let LabeledAddress::Staking(ref address) = balance.address {
+                // addresses without on-chain state are reported without staked amounts
+                if let Some(staked_state) = self.get_staked_state_account(address)? {
+                    balance.bonded = Some(staked_state.bonded);
+                    balance.unbonded = Some(staked_state.unbonded);
+                }
+            }
+        }
+        Ok(balances)
+    }
+
+    fn get_unbonding_status(&self, address: &StakedStateAddress) -> Result<UnbondingStatus> {
+        let staked_state = self.get_staked_state(address)?;
+
+        Ok(UnbondingStatus::new(
+            staked_state.unbonded,
+            staked_state.unbonded_from,
+            self.get_last_block_time()?,
+        ))
+    }
+
+    fn list_council_nodes(&self) -> Result<Vec<CouncilNodeInfo>> {
+        let addresses = self
+            .get_council_nodes()?
+            .into_iter()
+            .map(|node| node.staking_address)
+            .collect::<Vec<_>>();
+
+        let mut council_nodes = self
+            .get_staked_states(&addresses)?
+            .into_iter()
+            .zip(addresses.into_iter())
+            .map(|(staked_state, staking_address)| {
+                let staked_state = staked_state.chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Staking account of council node {} is not found",
+                            staking_address
+                        ),
+                    )
+                })?;
+                let validator = staked_state.validator.chain(|| {
+                    (
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Staking account of council node {} is not a validator",
+                            staking_address
+                        ),
+                    )
+                })?;
+
+                Ok(CouncilNodeInfo {
+                    staking_address,
+                    jailed: validator.is_jailed(),
+                    inactive: !validator.is_active(),
+                    council_node: validator.council_node,
+                    bonded: staked_state.bonded,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        council_nodes.sort_by(|a, b| b.bonded.cmp(&a.bonded));
+        Ok(council_nodes)
+    }
+
+    fn get_validator_info(&self, address: &StakedStateAddress) -> Result<ValidatorInfo> {
+        let validator = self.get_staked_state(address)?.validator.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("Staking account {} is not a council node", address),
+            )
+        })?;
+
+        let height = self.client.status()?.sync_info.latest_block_height.value();
+        let mut validator_set = self.client.validators(height)?.validators;
+        validator_set.sort_by(|a, b| b.voting_power.value().cmp(&a.voting_power.value()));
+
+        let consensus_pubkey = match validator.council_node.consensus_pubkey {
+            TendermintValidatorPubKey::Ed25519(ref bytes) => PublicKey::from_raw_ed25519(bytes),
+        };
+        let position = validator_set
+            .iter()
+            .position(|info| consensus_pubkey.as_ref() == Some(&info.pub_key));
+
+        let inactive_reason = if position.is_some() {
+            None
+        } else if validator.is_jailed() {
+            Some(InactiveReason::Jailed)
+        } else if !validator.is_active() {
+            Some(InactiveReason::Inactive)
+        } else {
+            Some(InactiveReason::BelowThreshold)
+        };
+
+        Ok(ValidatorInfo {
+            staking_address: *address,
+            consensus_pubkey: validator.council_node.consensus_pubkey.clone(),
+            active: position.is_some(),
+            voting_power: position
+                .map(|index| validator_set[index].voting_power.value())
+                .unwrap_or_default(),
+            rank: position.map(|index| index + 1),
+            inactive_reason,
+            jailed_until: validator.jailed_until,
+            inactive_time: validator.inactive_time,
+            inactive_block: validator.inactive_block,
+        })
+    }
+
+    fn get_used_validator_addresses(
+        &self,
+        address: &StakedStateAddress,
+    ) -> Result<Vec<(TendermintValidatorAddress, Timespec)>> {
+        let validator = self.get_staked_state(address)?.validator.chain(|| {
+            (
+                ErrorKind::InvalidInput,
+                format!("Staking account {} is not a council node", address),
+            )
+        })?;
+
+        Ok(validator.used_validator_addresses)
+    }
+
+    fn find_staking_address_by_consensus_key(
+        &self,
+        consensus_pubkey: &TendermintValidatorPubKey,
+    ) -> Result<Option<StakedStateAddress>> {
+        Ok(self
+            .get_council_nodes()?
+            .into_iter()
+            .find(|entry| entry.tendermint_pubkey == *consensus_pubkey)
+            .map(|entry| entry.staking_address))
+    }
+
+    fn get_punishment_history(
+        &self,
+        address: &StakedStateAddress,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<PunishmentEvent>> {
+        let mut punishment_events = Vec::new();
+        self.for_each_block_results(from_height, to_height, |block_results| {
+            punishment_events.extend(block_results.punishment_events(address)?);
+            Ok(())
+        })?;
+
+        Ok(punishment_events)
+    }
+
+    fn get_reward_history(
+        &self,
+        address: &StakedStateAddress,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Vec<RewardRecord>> {
+        let mut reward_records = Vec::new();
+        self.for_each_block_results(from_height, to_height, |block_results| {
+            reward_records.extend(block_results.reward_record(address)?);
+            Ok(())
+        })?;
+
+        Ok(reward_records)
+    }
+
+    fn total_rewards(
+        &self,
+        address: &StakedStateAddress,
+        from_height: u64,
+        to_height: u64,
+    ) -> Result<Coin> {
+        let reward_records = self.get_reward_history(address, from_height, to_height)?;
+        sum_coins(reward_records.into_iter().map(|record| record.amount)).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Total rewards exceed maximum allowed value",
+            )
+        })
+    }
+
+    fn get_network_staking_stats(&self) -> Result<NetworkStakingStats> {
+        const YEAR_SECONDS: u128 = 365 * 24 * 60 * 60;
+
+        let status = self.client.status()?;
+        let height = status.sync_info.latest_block_height.value();
+        let chain_state = self
+            .client
+            .query_state_batch(std::iter::once(height))?
+            .pop()
+            .chain(|| (ErrorKind::InvalidInput, "Chain state not found"))?;
+        let params = &chain_state.network_params;
+
+        let council_nodes = self.get_council_nodes()?;
+        let staking_addresses = council_nodes
+            .iter()
+            .map(|node| node.staking_address)
+            .collect::<Vec<_>>();
+        let total_bonded = self
+            .get_staked_states(&staking_addresses)?
+            .into_iter()
+            .zip(staking_addresses.iter())
+            .map(|(staked_state, address)| {
+                staked_state
+                    .map(|staked_state| staked_state.bonded)
+                    .chain(|| {
+                        (
+                            ErrorKind::InvalidInput,
+                            format!("Staked state of council node ({}) not found", address),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let total_bonded = sum_coins(total_bonded.into_iter()).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Total bonded amount exceeds maximum allowed value",
+            )
+        })?;
+
+        let reward_period_seconds = params.get_rewards_reward_period_seconds();
+        let remaining_cap = (params.get_rewards_monetary_expansion_cap()
+            - chain_state.rewards_pool.minted)
+            .unwrap_or_else(|_| Coin::zero());
+        let estimated_period_rewards = if chain_state.rewards_pool.tau == 0 {
+            Coin::zero()
+        } else {
+            std::cmp::min(
+                monetary_expansion(
+                    total_bonded,
+                    chain_state.rewards_pool.tau,
+                    params.get_rewards_monetary_expansion_r0(),
+                    reward_period_seconds,
+                ),
+                remaining_cap,
+            )
+        };
+
+        let estimated_annual_rate_bps =
+            if total_bonded == Coin::zero() || reward_period_seconds == 0 {
+                0
+            } else {
+                let annual_rewards = u128::from(u64::from(estimated_period_rewards)) * YEAR_SECONDS
+                    / u128::from(reward_period_seconds);
+                (annual_rewards * 10_000 / u128::from(u64::from(total_bonded))) as u64
+            };
+
+        Ok(NetworkStakingStats {
+            computed_at_height: height,
+            validator_count: council_nodes.len(),
+            total_bonded,
+            reward_period_seconds,
+            estimated_period_rewards,
+            estimated_annual_rate_bps,
+        })
+    }
+
+    fn capabilities(&self) -> ClientCapabilities {
+        ClientCapabilities {
+            network_ops: Some(self.config()),
+            ..client_capabilities()
+        }
+    }
+}
+
+/// Converts tendermint time to unix timestamp (in seconds)
+fn to_timespec(time: Time) -> Result<Timespec> {
+    time.duration_since(Time::unix_epoch())
+        .map(|duration| duration.as_secs())
+        .chain(|| {
+            (
+                ErrorKind::DeserializationError,
+                format!("Block time {} is before unix epoch", time.to_rfc3339()),
+            )
+        })
+}
+
+/// Checks if given time is the zero time (`0001-01-01T00:00:00Z`) reported by tendermint when
+/// there is no block yet
+fn is_zero_time(time: &Time) -> bool {
+    ZERO_TIME
+        .parse::<Time>()
+        .map(|zero_time| zero_time == *time)
+        .unwrap_or_default()
+}
+
+/// Returns `true` if error is a rejection of a staking transaction due to an outdated nonce
+fn is_nonce_mismatch(error: &Error) -> bool {
+    broadcast_error(error) == Some(&BroadcastError::NonceMismatch)
+}
+
+/// Returns nonce of a staking operation transaction (`None` for transactions without nonce)
+fn staking_nonce(transaction: &Transaction) -> Option<u64> {
+    match transaction {
+        Transaction::UnbondStakeTransaction(tx) => Some(tx.nonce),
+        Transaction::UnjailTransaction(tx) => Some(tx.nonce),
+        Transaction::NodejoinTransaction(tx) => Some(tx.nonce),
+        Transaction::WithdrawUnbondedStakeTransaction(tx) => Some(tx.nonce),
+        Transaction::TransferTransaction(_) | Transaction::DepositStakeTransaction(_) => None,
+    }
+}
+
+/// Returns staking address signing given transaction (`None` if it is not a staking transaction or
+/// the address is not part of transaction, as for withdraw transaction)
+fn staking_signer(transaction: &Transaction) -> Option<StakedStateAddress> {
+    match transaction {
+        Transaction::UnbondStakeTransaction(tx) => Some(tx.from_staked_account),
+        Transaction::UnjailTransaction(tx) => Some(tx.address),
+        Transaction::NodejoinTransaction(tx) => Some(tx.address),
+        Transaction::WithdrawUnbondedStakeTransaction(_)
+        | Transaction::TransferTransaction(_)
+        | Transaction::DepositStakeTransaction(_) => None,
+    }
+}
+
+/// Checks that unbonded stake of given staking account can be withdrawn at given time
+fn verify_withdrawable(staked_state: &StakedState, last_block_time: Timespec) -> Result<()> {
+    let unbonding_status = UnbondingStatus::new(
+        staked_state.unbonded,
+        staked_state.unbonded_from,
+        last_block_time,
+    );
+    if let Some(remaining_secs) = unbonding_status.remaining_secs {
+        return Err(Error::new(
+            ErrorKind::ValidationError,
+            format!(
+                "Staking state is not yet unbonded: {} seconds remaining, unbonded stake can be withdrawn at {} (unix timestamp)",
+                remaining_secs, unbonding_status.unbonded_from
+            ),
+        ));
+    }
+
+    verify_unjailed(staked_state).map_err(|e| {
+        Error::new(
+            ErrorKind::ValidationError,
+            format!("Failed to validate staking account: {}", e),
+        )
+    })
+}
+
+/// Decodes staked state from response of `account` query (`None` if account does not exist)
+fn decode_staked_state(
+    response: AbciQuery,
+    staked_state_address: &[u8],
+) -> Result<Option<StakedState>> {
+    let bytes = response.bytes();
+
+    if !bytes.is_empty() {
+        StakedState::decode(&mut bytes.as_slice())
+            .map(Some)
+            .chain(|| {
+                (
+                    ErrorKind::DeserializationError,
+                    format!(
+                        "Cannot deserialize staked state for address: {}",
+                        hex::encode(staked_state_address)
+                    ),
+                )
+            })
+    } else if !response.code.is_err() || response.code.value() == ACCOUNT_NOT_FOUND_CODE {
+        Ok(None)
+    } else {
+        Err(Error::new(
+            ErrorKind::TendermintRpcError,
+            response.log.to_string(),
+        ))
+    }
+}
+
+/// Refuses fee priority of withdraw transactions other than the default one (chain requires
+/// unbonded amount to equal withdrawn amount plus the minimum fee, so fee cannot be bumped)
+fn verify_fee_priority(fee_priority: &FeePriority) -> Result<()> {
+    if fee_priority.multiplier != Milli::integral(1) {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "Fee multiplier {} is not supported: withdraw transactions pay exactly the minimum fee",
+                fee_priority.multiplier
+            ),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Splits amount by given shares (in basis points), rounding remainder is added to last share
+fn split_amount(amount: Coin, shares: &[u32]) -> Result<Vec<Coin>> {
+    let total = u128::from(u64::from(amount));
+    let mut values = Vec::with_capacity(shares.len());
+    let mut distributed = 0;
+
+    for (i, share) in shares.iter().enumerate() {
+        let value = if i + 1 == shares.len() {
+            total - distributed
+        } else {
+            total * u128::from(*share) / u128::from(TOTAL_BASIS_POINTS)
+        };
+        distributed += value;
+        values.push(Coin::new(value as u64).chain(|| {
+            (
+                ErrorKind::IllegalInput,
+                "Split withdraw amount exceeds maximum allowed value",
+            )
+        })?);
+    }
+
+    Ok(values)
+}
+
+/// Hex encoded fingerprint (blake3 hash) of a certificate
+fn cert_fingerprint(cert: &[u8]) -> String {
+    hex::encode(txid_hash(cert))
+}
+
+/// Refuses obviously invalid certificate payloads
+fn validate_cert(cert: &[u8]) -> Result<()> {
+    if cert.is_empty() {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Certificate of council node is empty",
+        ))
+    } else if is_placeholder_cert(cert) {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Certificate of council node is a placeholder, use keypackage generated by the node",
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::council_node::tests::keypackage;
+    use crate::PendingNoncePolicy;
+    use secstr::SecUtf8;
+    use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use parity_scale_codec::Encode;
+
+    use chain_core::init::address::RedeemAddress;
+    use chain_core::init::coin::CoinError;
+    use chain_core::init::params::{
+        InitNetworkParameters, JailingParameters, NetworkParameters, RewardsParameters,
+        SlashingParameters,
+    };
+    use chain_core::state::account::{
+        ConfidentialInit, StakedState, StakedStateOpAttributes, Validator,
+    };
+    use chain_core::state::tendermint::BlockHeight;
+    use chain_core::state::tendermint::TendermintValidatorPubKey;
+    use chain_core::state::{ChainState, RewardsPoolState};
+    use chain_core::tx::data::input::TxoSize;
+    use chain_core::tx::data::TxId;
+    use chain_core::tx::fee::Fee;
+    use chain_core::tx::fee::{LinearFee, Milli};
+    use chain_core::tx::TransactionId;
+    use chain_core::tx::{PlainTxAux, TxEnclaveAux, TxObfuscated};
+    use chain_tx_validation::witness::verify_tx_recover_address;
+    use client_common::storage::MemoryStorage;
+    use client_common::tendermint::lite;
+    use client_common::tendermint::mock;
+    use client_common::tendermint::types::*;
+    use client_common::{
+        encode_tx_aux, encode_tx_aux_base64, seckey::derive_enckey, PrivateKey, PrivateKeyAction,
+        PublicKey, Transaction,
+    };
+    use client_core::service::{
+        BroadcastLogEntry, BroadcastOutcome, HwKeyService, WalletStateService,
+    };
+    use client_core::signer::WalletSignerManager;
+    use client_core::transaction_builder::UnauthorizedWalletTransactionBuilder;
+    use client_core::types::WalletKind;
+    use client_core::wallet::DefaultWalletClient;
+    use client_core::WalletStateMemento;
+
+    #[derive(Debug, Clone)]
+    struct MockTransactionCipher;
+
+    impl TransactionObfuscation for MockTransactionCipher {
+        fn decrypt(
+            &self,
+            _transaction_ids: &[TxId],
+            _private_key: &PrivateKey,
+        ) -> Result<Vec<Transaction>> {
+            unreachable!()
+        }
+
+        fn encrypt(&self, transaction: SignedTransaction) -> Result<TxAux> {
+            match transaction {
+                SignedTransaction::TransferTransaction(_, _) => unreachable!(),
+                SignedTransaction::DepositStakeTransaction(tx, witness) => {
+                    let plain = PlainTxAux::DepositStakeTx(witness);
+                    Ok(TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx {
+                        tx: tx.clone(),
+                        payload: TxObfuscated {
+                            txid: tx.id(),
+                            key_from: BlockHeight::genesis(),
+                            init_vector: [0u8; 12],
+                            txpayload: plain.encode(),
+                        },
+                    }))
+                }
+                SignedTransaction::WithdrawUnbondedStakeTransaction(tx, witness) => {
+                    let plain = PlainTxAux::WithdrawUnbondedStakeTx(tx.clone());
+                    Ok(TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                        no_of_outputs: tx.outputs.len() as TxoSize,
+                        witness,
+                        payload: TxObfuscated {
+                            txid: tx.id(),
+                            key_from: BlockHeight::genesis(),
+                            init_vector: [0u8; 12],
+                            txpayload: plain.encode(),
+                        },
+                    }))
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct UnitFeeAlgorithm;
+
+    impl FeeAlgorithm for UnitFeeAlgorithm {
+        fn calculate_fee(&self, _num_bytes: usize) -> std::result::Result<Fee, CoinError> {
+            Ok(Fee::new(Coin::unit()))
+        }
+
+        fn calculate_for_txaux(&self, _txaux: &TxAux) -> std::result::Result<Fee, CoinError> {
+            Ok(Fee::new(Coin::unit()))
+        }
+    }
+
+    /// Fee algorithm charging more for every transaction built after the first one (e.g. when
+    /// encoded size of placeholder and actual amounts differ)
+    #[derive(Default)]
+    struct GrowingFeeAlgorithm {
+        calls: AtomicU64,
+    }
+
+    impl FeeAlgorithm for GrowingFeeAlgorithm {
+        fn calculate_fee(&self, _num_bytes: usize) -> std::result::Result<Fee, CoinError> {
+            unreachable!()
+        }
+
+        fn calculate_for_txaux(&self, _txaux: &TxAux) -> std::result::Result<Fee, CoinError> {
+            let calls = self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(Fee::new(Coin::new(1 + calls.min(2))?))
+        }
+    }
+
+    /// Fee algorithm charging given fee for transfer transactions and unit fee for other
+    /// transactions
+    struct TransferFeeAlgorithm {
+        transfer_fee: Coin,
+    }
+
+    impl FeeAlgorithm for TransferFeeAlgorithm {
+        fn calculate_fee(&self, _num_bytes: usize) -> std::result::Result<Fee, CoinError> {
+            unreachable!()
+        }
+
+        fn calculate_for_txaux(&self, txaux: &TxAux) -> std::result::Result<Fee, CoinError> {
+            match txaux {
+                TxAux::EnclaveTx(TxEnclaveAux::TransferTx { .. }) => {
+                    Ok(Fee::new(self.transfer_fee))
+                }
+                _ => Ok(Fee::new(Coin::unit())),
+            }
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct MockJailedClient {
+        jailed_until: Timespec,
+    }
+
+    impl Default for MockJailedClient {
+        fn default() -> Self {
+            Self { jailed_until: 100 }
+        }
+    }
+
+    impl Client for MockJailedClient {
+        fn genesis(&self) -> Result<Genesis> {
+            Ok(mock::genesis())
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            Ok(StatusResponse {
+                sync_info: status::SyncInfo {
+                    latest_block_height: Height::default(),
+                    latest_app_hash: None,
+                    ..mock::sync_info()
+                },
+                ..mock::status_response()
+            })
+        }
+
+        fn block(&self, _: u64) -> Result<Block> {
+            unreachable!()
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!()
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!()
+        }
+
+        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+            &self,
+            _state: lite::TrustedState,
+            _heights: T,
+        ) -> Result<(Vec<Block>, lite::TrustedState)> {
+            unreachable!()
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            unreachable!()
+        }
+
+        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
+            unreachable!()
+        }
+
+        fn query(&self, _path: &str, _data: &[u8]) -> Result<AbciQuery> {
+            let staked_state = StakedState::new(
+                0,
+                Coin::new(1000000).unwrap(),
+                Coin::new(2499999999999999999 + 1).unwrap(),
+                0,
+                StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+                Some(Validator {
+                    council_node: CouncilNode::new(
+                        TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+                        ConfidentialInit { cert: keypackage() },
+                    ),
+                    jailed_until: Some(self.jailed_until),
+                    inactive_time: Some(0),
+                    inactive_block: Some(BlockHeight::genesis()),
+                    used_validator_addresses: vec![],
+                }),
+            );
+
+            Ok(AbciQuery {
+                value: Some(staked_state.encode()),
+                ..Default::default()
+            })
+        }
+
+        fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+            unreachable!()
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            queries: T,
+        ) -> Result<Vec<AbciQuery>> {
+            queries.map(|(path, data)| self.query(path, data)).collect()
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<ChainState>> {
+            unreachable!()
+        }
+    }
+
+    #[derive(Clone)]
+    pub struct MockClient {
+        bonded: Coin,
+        app_version: u64,
+        account_queries: Arc<AtomicUsize>,
+        missing_account: bool,
+        unbonded_from: Timespec,
+        block_results_batches: Arc<AtomicUsize>,
+        council_node: Option<CouncilNode>,
+        used_validator_addresses: Vec<(TendermintValidatorAddress, Timespec)>,
+        staked_states: HashMap<Vec<u8>, StakedState>,
+        chain_id: Option<&'static str>,
+    }
+
+    impl Default for MockClient {
+        fn default() -> Self {
+            Self {
+                bonded: Coin::new(1000000).unwrap(),
+                app_version: 0,
+                account_queries: Default::default(),
+                missing_account: false,
+                unbonded_from: 0,
+                block_results_batches: Default::default(),
+                council_node: None,
+                used_validator_addresses: Vec::new(),
+                staked_states: HashMap::new(),
+                chain_id: None,
+            }
+        }
+    }
+
+    impl Client for MockClient {
+        fn genesis(&self) -> Result<Genesis> {
+            let mut genesis = mock::genesis();
+            if let Some(chain_id) = self.chain_id {
+                genesis.chain_id = chain_id.parse().unwrap();
+            }
+            Ok(genesis)
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            let mut node_info = mock::node_info();
+            node_info.protocol_version.app = self.app_version;
+
+            Ok(StatusResponse {
+                node_info,
+                sync_info: status::SyncInfo {
+                    latest_block_height: Height::default(),
+                    latest_app_hash: None,
+                    ..mock::sync_info()
+                },
+                ..mock::status_response()
+            })
+        }
+
+        fn block(&self, _: u64) -> Result<Block> {
+            unreachable!()
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!()
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!()
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            self.block_results_batches.fetch_add(1, Ordering::SeqCst);
+
+            // staking account `[1; 20]` is rewarded at heights 50 and 180 and jailed at height 150
+            let staking_change = |optype: &str, diff: &str| {
+                let attribute = |key: &str, value: &str| {
+                    serde_json::json!({
+                        "key": base64::encode(key),
+                        "value": base64::encode(value),
+                    })
+                };
+                serde_json::json!([{
+                    "type": "staking_change",
+                    "attributes": [
+                        attribute(
+                            "staking_address",
+                            &StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]))
+                                .to_string(),
+                        ),
+                        attribute("staking_optype", optype),
+                        attribute("staking_diff", diff),
+                        attribute("staking_opreason", "NonLive"),
+                    ],
+                }])
+            };
+            heights
+                .map(|height| {
+                    let begin_block_events = match *height {
+                        50 | 180 => {
+                            staking_change("reward", r#"[{"key":"Bonded","value":"1000"}]"#)
+                        }
+                        150 => staking_change("jail", r#"[{"key":"JailedUntil","value":1000}]"#),
+                        _ => serde_json::Value::Null,
+                    };
+
+                    Ok(serde_json::from_value(serde_json::json!({
+                        "height": height.to_string(),
+                        "txs_results": null,
+                        "begin_block_events": begin_block_events,
+                        "end_block_events": null,
+                        "validator_updates": null,
+                        "consensus_param_updates": null,
+                    }))
+                    .unwrap())
+                })
+                .collect()
+        }
+
+        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+            &self,
+            _state: lite::TrustedState,
+            _heights: T,
+        ) -> Result<(Vec<Block>, lite::TrustedState)> {
+            unreachable!()
+        }
+
+        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
+            unreachable!()
+        }
+
+        fn query(&self, path: &str, data: &[u8]) -> Result<AbciQuery> {
+            if path == "council-nodes" {
+                let nodes = serde_json::json!([{
+                    "staking_address": StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]))
+                        .to_string(),
+                    "tendermint_pubkey": TendermintValidatorPubKey::Ed25519([0xab; 32]),
+                }]);
+                return Ok(AbciQuery {
+                    value: Some(nodes.to_string().into_bytes()),
+                    ..Default::default()
+                });
+            }
+
+            self.account_queries.fetch_add(1, Ordering::SeqCst);
+            if let Some(staked_state) = self.staked_states.get(data) {
+                return Ok(AbciQuery {
+                    value: Some(staked_state.encode()),
+                    ..Default::default()
+                });
+            }
+            if self.missing_account {
+                return Ok(AbciQuery::default());
+            }
+
+            let staked_state = StakedState::new(
+                0,
+                self.bonded,
+                Coin::new(2499999999999999999 + 1).unwrap(),
+                self.unbonded_from,
+                StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+                self.council_node.clone().map(|council_node| Validator {
+                    used_validator_addresses: self.used_validator_addresses.clone(),
+                    ..Validator::new(council_node)
+                }),
+            );
+
+            Ok(AbciQuery {
+                value: Some(staked_state.encode()),
+                ..Default::default()
+            })
+        }
+
+        fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+            unreachable!()
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            queries: T,
+        ) -> Result<Vec<AbciQuery>> {
+            queries.map(|(path, data)| self.query(path, data)).collect()
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<ChainState>> {
+            unreachable!()
+        }
+    }
+
+    #[derive(Default, Clone)]
+    pub struct MockStatsClient;
+
+    impl MockStatsClient {
+        fn council_node_addresses() -> Vec<StakedStateAddress> {
+            vec![
+                StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20])),
+                StakedStateAddress::BasicRedeem(RedeemAddress::from([2; 20])),
+            ]
+        }
+
+        fn chain_state() -> ChainState {
+            ChainState {
+                account_root: [0; 32],
+                rewards_pool: RewardsPoolState {
+                    period_bonus: Coin::zero(),
+                    last_block_height: BlockHeight::genesis(),
+                    last_distribution_time: 0,
+                    minted: Coin::zero(),
+                    tau: 166_666_600,
+                },
+                network_params: NetworkParameters::Genesis(InitNetworkParameters {
+                    initial_fee_policy: LinearFee::new(Milli::new(0, 0), Milli::new(0, 0)),
+                    required_council_node_stake: Coin::unit(),
+                    unbonding_period: 86400,
+                    jailing_config: JailingParameters {
+                        block_signing_window: 100,
+                        missed_block_threshold: 50,
+                    },
+                    slashing_config: SlashingParameters {
+                        liveness_slash_percent: "0.1".parse().unwrap(),
+                        byzantine_slash_percent: "0.2".parse().unwrap(),
+                    },
+                    rewards_config: RewardsParameters {
+                        monetary_expansion_cap: Coin::new(10_000_000_000_000_000).unwrap(),
+                        reward_period_seconds: 24 * 60 * 60,
+                        monetary_expansion_r0: "0.5".parse().unwrap(),
+                        monetary_expansion_tau: 166_666_600,
+                        monetary_expansion_decay: 999_860,
+                    },
+                    max_validators: 50,
+                }),
+            }
+        }
+    }
+
+    impl Client for MockStatsClient {
+        fn genesis(&self) -> Result<Genesis> {
+            unreachable!()
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            Ok(StatusResponse {
+                sync_info: status::SyncInfo {
+                    latest_block_height: Height(10),
+                    latest_app_hash: None,
+                    ..mock::sync_info()
+                },
+                ..mock::status_response()
+            })
+        }
+
+        fn block(&self, _: u64) -> Result<Block> {
+            unreachable!()
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!()
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!()
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            unreachable!()
+        }
+
+        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+            &self,
+            _state: lite::TrustedState,
+            _heights: T,
+        ) -> Result<(Vec<Block>, lite::TrustedState)> {
+            unreachable!()
+        }
+
+        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
+            unreachable!()
+        }
+
+        fn query(&self, path: &str, data: &[u8]) -> Result<AbciQuery> {
+            let value = match path {
+                "council-nodes" => {
+                    let nodes = Self::council_node_addresses()
+                        .iter()
+                        .map(|address| {
+                            serde_json::json!({
+                                "staking_address": address.to_string(),
+                                "tendermint_pubkey": TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+                            })
+                        })
+                        .collect::<Vec<_>>();
+                    serde_json::to_vec(&nodes).unwrap()
+                }
+                "account" => {
+                    let address =
+                        StakedStateAddress::BasicRedeem(RedeemAddress::try_from(data).unwrap());
+                    // second council node has larger stake and is jailed
+                    let (bonded, jailed_until) = if address == Self::council_node_addresses()[0] {
+                        (50_000_000_000_000_000, None)
+                    } else {
+                        (150_000_000_000_000_000, Some(100))
+                    };
+                    let validator = Validator {
+                        jailed_until,
+                        ..Validator::new(CouncilNode::new(
+                            TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+                            ConfidentialInit { cert: keypackage() },
+                        ))
+                    };
+                    StakedState::new(
+                        0,
+                        Coin::new(bonded).unwrap(),
+                        Coin::zero(),
+                        0,
+                        address,
+                        Some(validator),
+                    )
+                    .encode()
+                }
+                _ => unreachable!(),
+            };
+
+            Ok(AbciQuery {
+                value: Some(value),
+                ..Default::default()
+            })
+        }
+
+        fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+            unreachable!()
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            queries: T,
+        ) -> Result<Vec<AbciQuery>> {
+            queries
+                .map(|(path, data)| {
+                    let address =
+                        StakedStateAddress::BasicRedeem(RedeemAddress::try_from(data).unwrap());
+
+                    // only staking accounts of council nodes exist
+                    if path == "account" && !Self::council_node_addresses().contains(&address) {
+                        Ok(AbciQuery {
+                            code: Code::Err(1),
+                            ..Default::default()
+                        })
+                    } else {
+                        self.query(path, data)
+                    }
+                })
+                .collect()
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            heights: T,
+        ) -> Result<Vec<ChainState>> {
+            Ok(heights.map(|_| Self::chain_state()).collect())
+        }
+    }
+
+    /// Client with a single validator (`mock::validators_response()`) in active validator set
+    #[derive(Clone)]
+    pub struct MockValidatorClient {
+        consensus_pubkey: TendermintValidatorPubKey,
+        jailed_until: Option<Timespec>,
+        inactive: bool,
+    }
+
+    impl MockValidatorClient {
+        fn active_consensus_pubkey() -> TendermintValidatorPubKey {
+            let mut bytes = [0; 32];
+            bytes.copy_from_slice(
+                &base64::decode("2H0sZxyy5iOU6q0/F+ZCQ3MyJJxg8odE5NMsGIyfFV0=").unwrap(),
+            );
+            TendermintValidatorPubKey::Ed25519(bytes)
+        }
+    }
+
+    impl Default for MockValidatorClient {
+        fn default() -> Self {
+            Self {
+                consensus_pubkey: Self::active_consensus_pubkey(),
+                jailed_until: None,
+                inactive: false,
+            }
+        }
+    }
+
+    impl Client for MockValidatorClient {
+        fn genesis(&self) -> Result<Genesis> {
+            unreachable!()
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            Ok(StatusResponse {
+                sync_info: status::SyncInfo {
+                    latest_block_height: Height(1),
+                    latest_app_hash: None,
+                    ..mock::sync_info()
+                },
+                ..mock::status_response()
+            })
+        }
+
+        fn block(&self, _: u64) -> Result<Block> {
+            unreachable!()
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!()
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!()
+        }
+
+        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+            &self,
+            _state: lite::TrustedState,
+            _heights: T,
+        ) -> Result<(Vec<Block>, lite::TrustedState)> {
+            unreachable!()
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            unreachable!()
+        }
+
+        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
+            unreachable!()
+        }
+
+        fn query(&self, _path: &str, _data: &[u8]) -> Result<AbciQuery> {
+            unreachable!()
+        }
+
+        fn validators(&self, height: u64) -> Result<ValidatorsResponse> {
+            assert_eq!(1, height);
+            Ok(mock::validators_response())
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            queries: T,
+        ) -> Result<Vec<AbciQuery>> {
+            let (inactive_time, inactive_block) = if self.inactive {
+                (Some(100), Some(BlockHeight::genesis()))
+            } else {
+                (None, None)
+            };
+            let validator = Validator {
+                jailed_until: self.jailed_until,
+                inactive_time,
+                inactive_block,
+                ..Validator::new(CouncilNode::new(
+                    self.consensus_pubkey.clone(),
+                    ConfidentialInit { cert: keypackage() },
+                ))
+            };
+            let staked_state = StakedState::new(
+                0,
+                Coin::new(100_000_000_000_000_000).unwrap(),
+                Coin::zero(),
+                0,
+                StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+                Some(validator),
+            );
+
+            Ok(queries
+                .map(|_| AbciQuery {
+                    value: Some(staked_state.encode()),
+                    ..Default::default()
+                })
+                .collect())
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<ChainState>> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn check_get_validator_info() {
+        let validator_info = |tendermint_client: MockValidatorClient| {
+            let storage = MemoryStorage::default();
+            DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage, HwKeyService::default()),
+                tendermint_client,
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            )
+            .get_validator_info(&StakedStateAddress::BasicRedeem(RedeemAddress::default()))
+            .unwrap()
+        };
+
+        let active = validator_info(MockValidatorClient::default());
+        assert!(active.active);
+        assert_eq!(12_500_000_000, active.voting_power);
+        assert_eq!(Some(1), active.rank);
+        assert_eq!(None, active.inactive_reason);
+
+        let jailed = validator_info(MockValidatorClient {
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            jailed_until: Some(100),
+            inactive: true,
+        });
+        assert!(!jailed.active);
+        assert_eq!(0, jailed.voting_power);
+        assert_eq!(None, jailed.rank);
+        assert_eq!(Some(InactiveReason::Jailed), jailed.inactive_reason);
+        assert_eq!(Some(100), jailed.jailed_until);
+
+        let inactive = validator_info(MockValidatorClient {
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            inactive: true,
+            ..Default::default()
+        });
+        assert!(!inactive.active);
+        assert_eq!(Some(InactiveReason::Inactive), inactive.inactive_reason);
+        assert_eq!(Some(100), inactive.inactive_time);
+        assert_eq!(Some(BlockHeight::genesis()), inactive.inactive_block);
+
+        let below_threshold = validator_info(MockValidatorClient {
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            ..Default::default()
+        });
+        assert!(!below_threshold.active);
+        assert_eq!(
+            Some(InactiveReason::BelowThreshold),
+            below_threshold.inactive_reason
+        );
+    }
+
+    #[test]
+    fn check_get_punishment_history() {
+        let tendermint_client = MockClient::default();
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            tendermint_client.clone(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+        let jailed_address = StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]));
+
+        let history = network_ops_client
+            .get_punishment_history(&jailed_address, 0, 250)
+            .unwrap();
+        assert_eq!(
+            vec![PunishmentEvent {
+                height: 150,
+                kind: PunishmentKind::Jail,
+                amount: None,
+                jailed_until: Some(1000),
+                reason: Some("NonLive".to_owned()),
+            }],
+            history
+        );
+        // block results are requested in batches of `BLOCK_RESULTS_BATCH_SIZE`
+        assert_eq!(
+            3,
+            tendermint_client
+                .block_results_batches
+                .load(Ordering::SeqCst)
+        );
+
+        assert!(network_ops_client
+            .get_punishment_history(&jailed_address, 151, 250)
+            .unwrap()
+            .is_empty());
+        assert!(network_ops_client
+            .get_punishment_history(
+                &StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+                1,
+                200
+            )
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            network_ops_client
+                .get_punishment_history(&jailed_address, 10, 1)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_get_reward_history() {
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+        let rewarded_address = StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]));
+
+        assert_eq!(
+            vec![
+                RewardRecord {
+                    height: 50,
+                    amount: Coin::new(1000).unwrap(),
+                },
+                RewardRecord {
+                    height: 180,
+                    amount: Coin::new(1000).unwrap(),
+                },
+            ],
+            network_ops_client
+                .get_reward_history(&rewarded_address, 1, 250)
+                .unwrap()
+        );
+        assert_eq!(
+            Coin::new(2000).unwrap(),
+            network_ops_client
+                .total_rewards(&rewarded_address, 1, 250)
+                .unwrap()
+        );
+        assert_eq!(
+            Coin::new(1000).unwrap(),
+            network_ops_client
+                .total_rewards(&rewarded_address, 100, 200)
+                .unwrap()
+        );
+        assert_eq!(
+            Coin::zero(),
+            network_ops_client
+                .total_rewards(&rewarded_address, 60, 170)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn check_watched_address() {
+        let passphrase = SecUtf8::from("passphrase");
+        let storage = MemoryStorage::default();
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet_client
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let watch_list = WatchList::new(storage.clone());
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        )
+        .with_watch_list(watch_list.clone());
+        let foreign_address = StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]));
+        watch_list
+            .add_watched_address("counterparty", foreign_address)
+            .unwrap();
+
+        // watched address is monitored like any other address
+        assert_eq!(
+            1,
+            network_ops_client
+                .get_punishment_history(&foreign_address, 0, 250)
+                .unwrap()
+                .len()
+        );
+        assert_eq!(
+            Coin::new(2000).unwrap(),
+            network_ops_client
+                .total_rewards(&foreign_address, 1, 250)
+                .unwrap()
+        );
+
+        // but its staking transactions are never built
+        let error = network_ops_client
+            .create_unbond_stake_transaction(
+                "name",
+                &enckey,
+                foreign_address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Explicit(0),
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().ends_with("is watch-only, not owned"));
+        assert!(network_ops_client
+            .build_unsigned_unjail_transaction(foreign_address, None, NonceSource::Explicit(0))
+            .is_err());
+
+        watch_list.remove_watched_address(&foreign_address).unwrap();
+        assert!(network_ops_client
+            .build_unsigned_unjail_transaction(
+                foreign_address,
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Explicit(0)
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn check_capabilities() {
+        let storage = MemoryStorage::default();
+        let network_ops_client = || {
+            DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+                MockClient::default(),
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            )
+        };
+
+        let capabilities = network_ops_client().capabilities();
+        assert_eq!(
+            client_capabilities().storage_backends,
+            capabilities.storage_backends
+        );
+        assert_eq!(
+            Some(NetworkOpsConfig {
+                staked_state_ttl_millis: 2000,
+                pending_nonce_tracking: false,
+                watch_list: false,
+                pending_tx_valid_blocks: DEFAULT_PENDING_TX_VALID_BLOCKS,
+                block_wait_timeout_secs: 60,
+                custom_block_height_source: false,
+                network_mismatch_allowed: false,
+            }),
+            capabilities.network_ops
+        );
+
+        let network_ops_client = network_ops_client()
+            .with_staked_state_ttl(Duration::from_secs(0))
+            .with_pending_nonce_registry(PendingNonceRegistry::new(
+                storage.clone(),
+                PendingNoncePolicy::Bump,
+            ))
+            .with_watch_list(WatchList::new(storage.clone()))
+            .with_network_mismatch_allowed(true);
+        let config = network_ops_client.capabilities().network_ops.unwrap();
+        assert_eq!(0, config.staked_state_ttl_millis);
+        assert!(config.pending_nonce_tracking);
+        assert!(config.watch_list);
+        assert!(config.network_mismatch_allowed);
+        assert!(!config.custom_block_height_source);
+    }
+
+    #[test]
+    fn check_new_with_network_fee() {
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new_with_network_fee(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockStatsClient,
+            MockTransactionCipher,
+        );
+
+        // fee policy of `MockStatsClient` is zero
+        assert_eq!(
+            Coin::zero(),
+            network_ops_client
+                .estimate_fee(StakingTxKind::Unbond)
+                .unwrap()
+                .to_coin()
+        );
+    }
+
+    #[test]
+    fn check_get_network_staking_stats() {
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockStatsClient,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let stats = network_ops_client.get_network_staking_stats().unwrap();
+
+        let total_bonded = Coin::new(200_000_000_000_000_000).unwrap();
+        let period_rewards =
+            monetary_expansion(total_bonded, 166_666_600, "0.5".parse().unwrap(), 86400);
+        assert_ne!(period_rewards, Coin::zero());
+        let annual_rate_bps = u128::from(u64::from(period_rewards)) * 365 * 10_000
+            / u128::from(u64::from(total_bonded));
+
+        assert_eq!(stats.computed_at_height, 10);
+        assert_eq!(stats.validator_count, 2);
+        assert_eq!(stats.total_bonded, total_bonded);
+        assert_eq!(stats.reward_period_seconds, 86400);
+        assert_eq!(stats.estimated_period_rewards, period_rewards);
+        assert_eq!(stats.estimated_annual_rate_bps, annual_rate_bps as u64);
+    }
+
+    #[test]
+    fn check_get_staked_states() {
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockStatsClient,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let missing_address = StakedStateAddress::BasicRedeem(RedeemAddress::from([3; 20]));
+        let mut addresses = MockStatsClient::council_node_addresses();
+        addresses.insert(1, missing_address);
+
+        let staked_states = network_ops_client.get_staked_states(&addresses).unwrap();
+
+        assert_eq!(3, staked_states.len());
+        assert_eq!(addresses[0], staked_states[0].as_ref().unwrap().address);
+        assert!(staked_states[1].is_none());
+        assert_eq!(addresses[2], staked_states[2].as_ref().unwrap().address);
+    }
+
+    #[test]
+    fn check_list_council_nodes() {
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockStatsClient,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let council_nodes = network_ops_client.list_council_nodes().unwrap();
+        let addresses = MockStatsClient::council_node_addresses();
+
+        assert_eq!(2, council_nodes.len());
+        // sorted by bonded amount (descending)
+        assert_eq!(addresses[1], council_nodes[0].staking_address);
+        assert_eq!(
+            Coin::new(150_000_000_000_000_000).unwrap(),
+            council_nodes[0].bonded
+        );
+        assert!(council_nodes[0].jailed);
+        assert_eq!(addresses[0], council_nodes[1].staking_address);
+        assert!(!council_nodes[1].jailed);
+        assert!(!council_nodes[1].inactive);
+        assert_eq!(
+            TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            council_nodes[1].council_node.consensus_pubkey
+        );
+    }
+
+    #[test]
+    fn check_create_deposit_bonded_stake_transaction() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let input = TxoPointer::new([0; 32], 0);
+        let output = TxOut {
+            address: ExtendedAddr::OrTree([0; 32]),
+            value: Coin::new(10).unwrap(),
+            valid_from: None,
+        };
+        let transactions = vec![(input, output)];
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let to_staked_account = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let attributes = Some(StakedStateOpAttributes::new(171));
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            network_ops_client
+                .create_deposit_bonded_stake_transaction(
+                    name,
+                    &enckey,
+                    transactions,
+                    to_staked_account,
+                    attributes,
+                    false,
+                    false,
+                )
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_deposit_fee_coverage() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let to_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let transactions = vec![(
+            TxoPointer::new([0; 32], 0),
+            TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit()),
+        )];
+        let error = network_ops_client
+            .create_deposit_bonded_stake_transaction(
+                name,
+                &enckey,
+                transactions,
+                to_address,
+                Some(StakedStateOpAttributes::new(171)),
+                false,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!(
+            format!(
+                "Deposit inputs total {} do not cover fee {} (resulting bonded amount would be {}), add more inputs",
+                Coin::unit(),
+                Coin::unit(),
+                Coin::zero()
+            ),
+            error.message()
+        );
+    }
+
+    #[test]
+    fn check_missing_staked_state() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient {
+                missing_account: true,
+                ..Default::default()
+            },
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        assert_eq!(
+            None,
+            network_ops_client.get_staked_state_maybe(&address).unwrap()
+        );
+
+        let error = network_ops_client
+            .create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Network,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().contains("has no on-chain state yet"));
+
+        // missing state of deposit destination is not checked, deposit only fails on the
+        // inputs (which are not in wallet), the same way as for an address of current wallet
+        let deposit = |to_address, allow_external| {
+            let transactions = vec![(
+                TxoPointer::new([0; 32], 0),
+                TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(10).unwrap()),
+            )];
+            network_ops_client
+                .create_deposit_bonded_stake_transaction(
+                    name,
+                    &enckey,
+                    transactions,
+                    to_address,
+                    Some(StakedStateOpAttributes::new(171)),
+                    allow_external,
+                    false,
+                )
+                .unwrap_err()
+        };
+        let other_address = StakedStateAddress::BasicRedeem(RedeemAddress::from([9; 20]));
+        assert_eq!(
+            deposit(address, false).message(),
+            deposit(other_address, true).message()
+        );
+
+        // external destination has to be explicitly allowed
+        let error = deposit(other_address, false);
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().contains(&other_address.to_string()));
+        assert!(error.message().contains("not one of 1 staking addresses"));
+    }
+
+    #[test]
+    fn check_calculate_deposit_fee_for_inputs() {
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockClient::default(),
+            LinearFee::new(Milli::new(1, 1), Milli::new(1, 1)),
+            MockTransactionCipher,
+        );
+
+        let single_input_fee = network_ops_client
+            .calculate_single_input_deposit_fee()
+            .unwrap();
+        assert_eq!(
+            single_input_fee,
+            network_ops_client.calculate_deposit_fee(1).unwrap()
+        );
+        assert!(network_ops_client.calculate_deposit_fee(10).unwrap() > single_input_fee);
+    }
+
+    #[test]
+    fn check_estimate_fee() {
+        let node_metadata = |cert_len| CouncilNode {
+            name: "test".to_owned(),
+            security_contact: None,
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            confidential_init: ConfidentialInit {
+                cert: vec![0xab; cert_len],
+            },
+        };
+        let kinds = vec![
+            StakingTxKind::Deposit { inputs: 1 },
+            StakingTxKind::Unbond,
+            StakingTxKind::Withdraw { outputs: 1 },
+            StakingTxKind::Unjail,
+            StakingTxKind::NodeJoin {
+                node_metadata: node_metadata(5),
+            },
+        ];
+
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+        for kind in kinds.iter().cloned() {
+            assert_eq!(
+                Coin::unit(),
+                network_ops_client.estimate_fee(kind).unwrap().to_coin()
+            );
+        }
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            LinearFee::new(Milli::new(1, 1), Milli::new(1, 1)),
+            MockTransactionCipher,
+        );
+        let estimate = |kind| network_ops_client.estimate_fee(kind).unwrap().to_coin();
+        for kind in kinds {
+            assert!(estimate(kind) > Coin::zero());
+        }
+        assert!(
+            estimate(StakingTxKind::Deposit { inputs: 10 })
+                > estimate(StakingTxKind::Deposit { inputs: 1 })
+        );
+        assert!(
+            estimate(StakingTxKind::Withdraw { outputs: 3 })
+                > estimate(StakingTxKind::Withdraw { outputs: 1 })
+        );
+        assert!(
+            estimate(StakingTxKind::NodeJoin {
+                node_metadata: node_metadata(500)
+            }) > estimate(StakingTxKind::NodeJoin {
+                node_metadata: node_metadata(5)
+            })
+        );
+    }
+
+    #[test]
+    fn check_create_deposit_amount_transaction_insufficient_balance() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let mut memento = WalletStateMemento::default();
+        memento.add_unspent_transaction(
+            TxoPointer::new([0; 32], 0),
+            TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::new(10).unwrap()),
+        );
+        WalletStateService::new(storage.clone())
+            .apply_memento(name, &enckey, &memento)
+            .unwrap();
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let to_staked_account = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let error = network_ops_client
+            .create_deposit_amount_transaction(
+                name,
+                &enckey,
+                Coin::new(10).unwrap(),
+                to_staked_account,
+                Some(StakedStateOpAttributes::new(171)),
+                false,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InsufficientBalance, error.kind());
+        assert!(error.message().ends_with("short by 0.00000001"));
+    }
+
+    #[test]
+    fn check_create_unbond_stake_transaction() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let value = Coin::new(0).unwrap();
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+        let attributes = Some(StakedStateOpAttributes::new(171));
+
+        assert!(network_ops_client
+            .create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                value,
+                attributes,
+                NonceSource::Network,
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn check_create_unbond_stake_transaction_watch_only() {
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet_client
+            .new_wallet("name", &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = wallet_client.new_staking_address("name", &enckey).unwrap();
+        let info = wallet_client
+            .export_watch_only_wallet("name", &enckey)
+            .unwrap();
+        let watch_enckey = wallet_client
+            .new_watch_wallet(
+                "watch",
+                &passphrase,
+                &info.view_key,
+                &info.public_keys,
+                &info.staking_keys,
+            )
+            .unwrap();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let error = network_ops_client
+            .create_unbond_stake_transaction(
+                "watch",
+                &watch_enckey,
+                address,
+                Coin::new(0).unwrap(),
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Network,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::PermissionDenied, error.kind());
+        assert_eq!(
+            "Watch-only wallet can not sign transactions",
+            error.message()
+        );
+    }
+
+    #[test]
+    fn check_pending_nonce_registry() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        for policy in vec![PendingNoncePolicy::Bump, PendingNoncePolicy::Refuse] {
+            let storage = MemoryStorage::default();
+            let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+            let (enckey, _) = wallet_client
+                .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+                .unwrap();
+
+            let network_ops_client = DefaultNetworkOpsClient::new(
+                wallet_client,
+                WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+                MockClient::default(),
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            )
+            .with_pending_nonce_registry(PendingNonceRegistry::new(storage, policy));
+            let address = network_ops_client
+                .get_wallet_client()
+                .new_staking_address(name, &enckey)
+                .unwrap();
+
+            let (unbond, _) = network_ops_client
+                .create_unbond_stake_transaction(
+                    name,
+                    &enckey,
+                    address,
+                    Coin::unit(),
+                    None,
+                    NonceSource::Network,
+                )
+                .unwrap();
+            match unbond {
+                TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => assert_eq!(0, tx.nonce),
+                _ => unreachable!("unbond transaction is expected"),
+            }
+
+            // on-chain nonce of `MockClient` is still 0
+            let second = network_ops_client.create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                None,
+                NonceSource::Network,
+            );
+            match policy {
+                PendingNoncePolicy::Bump => match second.unwrap().0 {
+                    TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => {
+                        assert_eq!(1, tx.nonce)
+                    }
+                    _ => unreachable!("unbond transaction is expected"),
+                },
+                PendingNoncePolicy::Refuse => {
+                    assert_eq!(ErrorKind::InvalidInput, second.unwrap_err().kind())
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn check_wallet_network_mismatch() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone())
+            .with_network(NetworkInfo::from_chain_id("test-chain-y3m1e6-AB").unwrap());
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = wallet_client.new_staking_address(name, &enckey).unwrap();
+
+        let network_ops_client = |network_mismatch_allowed| {
+            DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+                MockClient {
+                    chain_id: Some("test-chain-y3m1e6-42"),
+                    ..MockClient::default()
+                },
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            )
+            .with_network_mismatch_allowed(network_mismatch_allowed)
+        };
+        let unbond = |network_mismatch_allowed| {
+            network_ops_client(network_mismatch_allowed).create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(0x42)),
+                NonceSource::Network,
+            )
+        };
+
+        let error = unbond(false).unwrap_err();
+        assert_eq!(ErrorKind::ValidationError, error.kind());
+        assert_eq!(
+            "Wallet (name) was created for network test-chain-y3m1e6-AB (ab), but connected network is test-chain-y3m1e6-42 (42)",
+            error.message()
+        );
+        assert!(unbond(true).is_ok());
+    }
+
+    #[test]
+    fn check_chain_hex_id_mismatch() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let error = network_ops_client
+            .create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(0)),
+                NonceSource::Network,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::ValidationError, error.kind());
+        assert_eq!(
+            "Chain hex id of transaction attributes (00) does not match connected network (ab)",
+            error.message()
+        );
+
+        let attributes = network_ops_client.network_staking_attributes().unwrap();
+        assert_eq!(0xab, attributes.chain_hex_id);
+        assert!(network_ops_client
+            .create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(attributes),
+                NonceSource::Network,
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn check_network_staking_attributes() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockClient {
+                app_version: 7,
+                ..MockClient::default()
+            },
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let (transaction, _) = network_ops_client
+            .create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                None,
+                NonceSource::Network,
+            )
+            .unwrap();
+
+        match transaction {
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => {
+                let decoded = UnbondTx::decode(&mut tx.encode().as_slice()).unwrap();
+                assert_eq!(0xab, decoded.attributes.chain_hex_id);
+                assert_eq!(7, decoded.attributes.app_version);
+            }
+            _ => unreachable!("`create_unbond_stake_transaction()` created invalid transaction"),
+        }
+    }
+
+    #[test]
+    fn check_create_unbond_all_stake_transaction() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+        let attributes = Some(StakedStateOpAttributes::new(171));
+
+        let (transaction, pending_transaction) = network_ops_client
+            .create_unbond_all_stake_transaction(name, &enckey, address, attributes)
+            .unwrap();
+        assert!(pending_transaction.used_inputs.is_empty());
+        assert_eq!(pending_transaction.return_amount, Coin::zero());
+
+        match transaction {
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => {
+                assert_eq!(tx.value, Coin::new(1000000 - 1).unwrap());
+            }
+            _ => {
+                unreachable!("`create_unbond_all_stake_transaction()` created invalid transaction")
+            }
+        }
+    }
+
+    #[derive(Default, Clone)]
+    pub struct MockOfflineClient;
+
+    impl Client for MockOfflineClient {
+        fn genesis(&self) -> Result<Genesis> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn block(&self, _: u64) -> Result<Block> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+            &self,
+            _state: lite::TrustedState,
+            _heights: T,
+        ) -> Result<(Vec<Block>, lite::TrustedState)> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn query(&self, _path: &str, _data: &[u8]) -> Result<AbciQuery> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            _queries: T,
+        ) -> Result<Vec<AbciQuery>> {
+            unreachable!("offline client must not be called")
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<ChainState>> {
+            unreachable!("offline client must not be called")
+        }
+    }
+
+    /// Block height source returning fixed block height (or an error if there is none)
+    pub struct MockBlockHeightSource(Option<u64>);
+
+    impl BlockHeightSource for MockBlockHeightSource {
+        fn current_block_height(&self) -> Result<u64> {
+            self.0
+                .chain(|| (ErrorKind::ConnectionError, "Block height is unavailable"))
+        }
+    }
+
+    #[test]
+    fn check_explicit_nonce_transactions_offline() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockOfflineClient,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        )
+        .with_block_height_source(MockBlockHeightSource(Some(5)));
+
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let (transaction, pending_tx) = network_ops_client
+            .create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Explicit(7),
+            )
+            .unwrap();
+        assert_eq!(5, pending_tx.block_height);
+        match transaction {
+            TxAux::PublicTx(TxPublicAux::UnbondStakeTx(tx, _)) => assert_eq!(tx.nonce, 7),
+            _ => unreachable!("`create_unbond_stake_transaction()` created invalid transaction"),
+        }
+
+        let (transaction, _) = network_ops_client
+            .create_unjail_transaction(
+                name,
+                &enckey,
+                address,
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Explicit(8),
+            )
+            .unwrap();
+        match transaction {
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, _)) => assert_eq!(tx.nonce, 8),
+            _ => unreachable!("`create_unjail_transaction()` created invalid transaction"),
+        }
+
+        let (transaction, _) = network_ops_client
+            .create_withdraw_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &address,
+                vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit())],
+                TxAttributes::new(0),
+                NonceSource::Explicit(9),
+                false,
+            )
+            .unwrap();
+        match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match PlainTxAux::decode(&mut txpayload.as_slice()) {
+                Ok(PlainTxAux::WithdrawUnbondedStakeTx(tx)) => assert_eq!(tx.nonce, 9),
+                _ => unreachable!("invalid withdraw transaction payload"),
+            },
+            _ => unreachable!(
+                "`create_withdraw_unbonded_stake_transaction()` created invalid transaction type"
+            ),
+        }
+
+        // transaction is not built if current block height can not be obtained
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockOfflineClient,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        )
+        .with_block_height_source(MockBlockHeightSource(None));
+        let error = network_ops_client
+            .create_unbond_stake_transaction(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Explicit(10),
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::ConnectionError, error.kind());
+    }
+
+    #[test]
+    fn check_unsigned_transaction_attach_witness() {
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage),
+            signer_manager,
+            MockOfflineClient,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let private_key = PrivateKey::new().unwrap();
+        let redeem_address = RedeemAddress::from(&PublicKey::from(&private_key));
+        let address = StakedStateAddress::BasicRedeem(redeem_address);
+
+        let unsigned = network_ops_client
+            .build_unsigned_unjail_transaction(
+                address,
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Explicit(3),
+            )
+            .unwrap();
+        assert_eq!(unsigned.signer, address);
+
+        let other_witness = PrivateKey::new()
+            .unwrap()
+            .sign(&unsigned.transaction)
+            .map(StakedStateOpWitness::new)
+            .unwrap();
+        assert_eq!(
+            ErrorKind::ValidationError,
+            network_ops_client
+                .attach_witness(unsigned.clone(), other_witness)
+                .unwrap_err()
+                .kind()
+        );
+
+        let witness = private_key
+            .sign(&unsigned.transaction)
+            .map(StakedStateOpWitness::new)
+            .unwrap();
+        match network_ops_client
+            .attach_witness(unsigned, witness)
+            .unwrap()
+        {
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, _)) => {
+                assert_eq!(tx.nonce, 3);
+                assert_eq!(tx.address, address);
+            }
+            _ => unreachable!("`attach_witness()` created invalid transaction"),
+        }
+    }
+
+    #[test]
+    fn check_finalize_staking_tx() {
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let private_key = PrivateKey::new().unwrap();
+        let address =
+            StakedStateAddress::BasicRedeem(RedeemAddress::from(&PublicKey::from(&private_key)));
+        let unjail = |nonce| {
+            Transaction::UnjailTransaction(UnjailTx {
+                nonce,
+                address,
+                attributes: StakedStateOpAttributes::new(171),
+            })
+        };
+        let witness = |private_key: &PrivateKey, transaction: &Transaction| {
+            private_key
+                .sign(transaction)
+                .map(StakedStateOpWitness::new)
+                .unwrap()
+        };
+
+        // staked state returned by mock client has nonce 0
+        let transaction = unjail(0);
+        match network_ops_client
+            .finalize_staking_tx(transaction.clone(), witness(&private_key, &transaction))
+            .unwrap()
+        {
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, _)) => assert_eq!(tx.address, address),
+            _ => unreachable!("`finalize_staking_tx()` created invalid transaction"),
+        }
+
+        let other_key = PrivateKey::new().unwrap();
+        let other_address =
+            StakedStateAddress::BasicRedeem(RedeemAddress::from(&PublicKey::from(&other_key)));
+        let error = network_ops_client
+            .finalize_staking_tx(transaction.clone(), witness(&other_key, &transaction))
+            .unwrap_err();
+        assert_eq!(ErrorKind::ValidationError, error.kind());
+        assert_eq!(
+            format!(
+                "Witness is signed by staking address {} instead of transaction's staking address {}",
+                other_address, address
+            ),
+            error.message()
+        );
+
+        let transaction = unjail(1);
+        let error = network_ops_client
+            .finalize_staking_tx(transaction.clone(), witness(&private_key, &transaction))
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error
+            .message()
+            .starts_with("Transaction nonce 1 does not match current nonce 0"));
+
+        // withdraw transaction is encrypted
+        let transaction = Transaction::WithdrawUnbondedStakeTransaction(WithdrawUnbondedTx::new(
+            0,
+            vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit())],
+            TxAttributes::new(171),
+        ));
+        match network_ops_client
+            .finalize_staking_tx(transaction.clone(), witness(&private_key, &transaction))
+            .unwrap()
+        {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { .. }) => {}
+            _ => unreachable!("`finalize_staking_tx()` created invalid transaction"),
+        }
+    }
+
+    #[derive(Default, Clone)]
+    pub struct MockBroadcastClient {
+        height: Arc<AtomicU64>,
+        halted: bool,
+        reject: bool,
+        nonce_mismatches: usize,
+        broadcasts: Arc<AtomicUsize>,
+    }
+
+    impl Client for MockBroadcastClient {
+        fn genesis(&self) -> Result<Genesis> {
+            Ok(mock::genesis())
+        }
+
+        fn status(&self) -> Result<StatusResponse> {
+            let height = if self.halted {
+                self.height.load(Ordering::SeqCst)
+            } else {
+                self.height.fetch_add(1, Ordering::SeqCst)
+            };
+            Ok(StatusResponse {
+                sync_info: status::SyncInfo {
+                    latest_block_height: Height::from(height),
+                    ..mock::sync_info()
+                },
+                ..mock::status_response()
+            })
+        }
+
+        fn block(&self, _: u64) -> Result<Block> {
+            Ok(mock::block())
+        }
+
+        fn block_batch<'a, T: Iterator<Item = &'a u64>>(&self, _heights: T) -> Result<Vec<Block>> {
+            unreachable!()
+        }
+
+        fn block_results(&self, _height: u64) -> Result<BlockResultsResponse> {
+            unreachable!()
+        }
+
+        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<BlockResultsResponse>> {
+            unreachable!()
+        }
+
+        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
+            &self,
+            _state: lite::TrustedState,
+            _heights: T,
+        ) -> Result<(Vec<Block>, lite::TrustedState)> {
+            unreachable!()
+        }
+
+        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
+            let broadcasts = self.broadcasts.fetch_add(1, Ordering::SeqCst);
+            let (code, log) = if self.reject {
+                (1, "invalid nonce")
+            } else if broadcasts < self.nonce_mismatches {
+                (
+                    1,
+                    "public tx process failed: tx nonce don't match staking state",
+                )
+            } else {
+                (0, "invalid nonce")
+            };
+            Ok(serde_json::from_str(&format!(
+                r#"{{"code":{},"data":"","log":"{}","hash":"{}"}}"#,
+                code,
+                log,
+                "0".repeat(64)
+            ))
+            .unwrap())
+        }
+
+        fn query(&self, path: &str, data: &[u8]) -> Result<AbciQuery> {
+            let mut response = MockClient::default().query(path, data)?;
+            if path == "account" {
+                // account is used from another device before each broadcast is checked
+                let mut staked_state =
+                    StakedState::decode(&mut response.value.unwrap().as_slice()).unwrap();
+                staked_state.nonce = self.broadcasts.load(Ordering::SeqCst) as u64;
+                response.value = Some(staked_state.encode());
+            }
+            Ok(response)
+        }
+
+        fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+            unreachable!()
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            queries: T,
+        ) -> Result<Vec<AbciQuery>> {
+            queries.map(|(path, data)| self.query(path, data)).collect()
+        }
+
+        fn query_state_batch<T: Iterator<Item = u64>>(
+            &self,
+            _heights: T,
+        ) -> Result<Vec<ChainState>> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn check_unbond_and_broadcast() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new(
+            storage.clone(),
+            MockBroadcastClient::default(),
+            UnauthorizedWalletTransactionBuilder,
+            None,
+            HwKeyService::default(),
+        );
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockBroadcastClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let broadcasted = network_ops_client
+            .unbond_and_broadcast(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                0,
+                false,
+            )
+            .unwrap();
+        assert_eq!(None, broadcasted.block_height);
+        match network_ops_client
+            .get_wallet_client()
+            .query_broadcast_log(&broadcasted.transaction_id)
+            .unwrap()
+        {
+            Some(BroadcastLogEntry {
+                outcome: Some(BroadcastOutcome::Accepted(_)),
+                ..
+            }) => {}
+            _ => unreachable!("broadcast outcome is not recorded"),
+        }
+
+        let error = network_ops_client
+            .unbond_and_broadcast(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                2,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::TransactionTimeout, error.kind());
+    }
+
+    #[test]
+    fn check_unbond_and_broadcast_halted_chain() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let tendermint_client = MockBroadcastClient {
+            halted: true,
+            ..Default::default()
+        };
+        let wallet_client = DefaultWalletClient::new(
+            storage.clone(),
+            tendermint_client.clone(),
+            UnauthorizedWalletTransactionBuilder,
+            None,
+            HwKeyService::default(),
+        );
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        )
+        .with_block_wait_timeout(Duration::from_millis(10));
+
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let error = network_ops_client
+            .unbond_and_broadcast(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                2,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::TransactionTimeout, error.kind());
+        assert!(error.message().contains("chain is stuck at block 0"));
+    }
+
+    #[test]
+    fn check_unbond_and_broadcast_rejected() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new(
+            storage.clone(),
+            MockBroadcastClient {
+                reject: true,
+                ..Default::default()
+            },
+            UnauthorizedWalletTransactionBuilder,
+            None,
+            HwKeyService::default(),
+        );
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockBroadcastClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let error = network_ops_client
+            .unbond_and_broadcast(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                Some(StakedStateOpAttributes::new(171)),
+                2,
+                false,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::TendermintRpcError, error.kind());
+        assert_eq!(
+            "Transaction rejected with code 1: invalid nonce",
+            error.message()
+        );
+        assert_eq!(
+            Some(&BroadcastError::AppError {
+                code: 1,
+                log: "invalid nonce".to_owned(),
+            }),
+            broadcast_error(&error)
+        );
+
+        let unresolved = network_ops_client
+            .get_wallet_client()
+            .unresolved_broadcasts()
+            .unwrap();
+        assert!(unresolved.is_empty());
+    }
+
+    #[test]
+    fn check_broadcast_encoded_transaction() {
+        let broadcast_encoded = |tendermint_client: MockBroadcastClient, encoded: &str| {
+            let storage = MemoryStorage::default();
+            DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage, HwKeyService::default()),
+                tendermint_client,
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            )
+            .broadcast_encoded_transaction(encoded)
+        };
+
+        let tx_aux = DummySigner().mock_txaux_for_unbond(UnbondTx::new(
+            StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+            0,
+            Coin::unit(),
+            StakedStateOpAttributes::new(171),
+        ));
+
+        for encoded in &[encode_tx_aux(&tx_aux), encode_tx_aux_base64(&tx_aux)] {
+            let tendermint_client = MockBroadcastClient::default();
+            let broadcasted = broadcast_encoded(tendermint_client.clone(), encoded).unwrap();
+            assert_eq!(tx_aux.tx_id(), broadcasted.transaction_id);
+            assert_eq!(None, broadcasted.block_height);
+            assert_eq!(1, tendermint_client.broadcasts.load(Ordering::SeqCst));
+        }
+
+        let error = broadcast_encoded(
+            MockBroadcastClient {
+                reject: true,
+                ..Default::default()
+            },
+            &encode_tx_aux(&tx_aux),
+        )
+        .unwrap_err();
+        assert_eq!(ErrorKind::TendermintRpcError, error.kind());
+
+        // invalid transaction is never broadcasted
+        let tendermint_client = MockBroadcastClient::default();
+        let error = broadcast_encoded(
+            tendermint_client.clone(),
+            &format!("{}00", encode_tx_aux(&tx_aux)),
+        )
+        .unwrap_err();
+        assert_eq!(ErrorKind::DeserializationError, error.kind());
+        assert_eq!(0, tendermint_client.broadcasts.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn check_unbond_and_broadcast_retry_on_nonce_mismatch() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        for (nonce_mismatches, retry_on_nonce_mismatch, expected_broadcasts) in vec![
+            (1, true, 2),
+            (1, false, 1),
+            (
+                MAX_NONCE_MISMATCH_RETRIES + 1,
+                true,
+                MAX_NONCE_MISMATCH_RETRIES + 1,
+            ),
+        ] {
+            let storage = MemoryStorage::default();
+            let tendermint_client = MockBroadcastClient {
+                nonce_mismatches,
+                ..Default::default()
+            };
+            let wallet_client = DefaultWalletClient::new(
+                storage.clone(),
+                tendermint_client.clone(),
+                UnauthorizedWalletTransactionBuilder,
+                None,
+                HwKeyService::default(),
+            );
+            let (enckey, _) = wallet_client
+                .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+                .unwrap();
+
+            let network_ops_client = DefaultNetworkOpsClient::new(
+                wallet_client,
+                WalletSignerManager::new(storage, HwKeyService::default()),
+                tendermint_client.clone(),
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            );
+            let address = network_ops_client
+                .get_wallet_client()
+                .new_staking_address(name, &enckey)
+                .unwrap();
+
+            let result = network_ops_client.unbond_and_broadcast(
+                name,
+                &enckey,
+                address,
+                Coin::unit(),
+                None,
+                0,
+                retry_on_nonce_mismatch,
+            );
+            assert_eq!(
+                expected_broadcasts,
+                tendermint_client.broadcasts.load(Ordering::SeqCst)
+            );
+
+            if expected_broadcasts > nonce_mismatches {
+                assert!(result.is_ok());
+                continue;
+            }
+
+            let error = result.unwrap_err();
+            assert_eq!(ErrorKind::TendermintRpcError, error.kind());
+            if retry_on_nonce_mismatch {
+                // error reports ids of all attempted (differently built) transactions
+                assert!(error
+                    .message()
+                    .contains(&format!("gave up after {} attempts", expected_broadcasts)));
+                let ids = error.message().rsplit("transaction ids: ").next().unwrap();
+                let mut ids = ids.trim_end_matches(')').split(", ").collect::<Vec<_>>();
+                ids.dedup();
+                assert_eq!(expected_broadcasts, ids.len());
+            }
+        }
+    }
+
+    #[test]
+    fn check_withdraw_unbonded_stake_transaction() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        )
+        .with_pending_tx_valid_blocks(20)
+        .with_block_height_source(MockBlockHeightSource(Some(7)));
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let (transaction, pending_tx) = network_ops_client
+            .create_withdraw_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit())],
+                TxAttributes::new(171),
+                NonceSource::Network,
+                true,
+            )
+            .unwrap();
+        assert_eq!(transaction.tx_id(), pending_tx.tx_id);
+        assert_eq!(7, pending_tx.block_height);
+        assert_eq!(27, pending_tx.valid_until_height);
+
+        match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                payload: TxObfuscated { txid, .. },
+                witness,
+                ..
+            }) => {
+                let account_address = verify_tx_recover_address(&witness, &txid)
+                    .expect("Unable to verify transaction");
+
+                assert_eq!(account_address, from_address)
+            }
+            _ => unreachable!(
+                "`create_withdraw_unbonded_stake_transaction()` created invalid transaction type"
+            ),
+        }
+    }
+
+    #[test]
+    fn check_withdraw_unbonded_stake_transaction_with_session() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        )
+        .with_block_height_source(MockBlockHeightSource(Some(7)));
+        let wallet_client = network_ops_client.get_wallet_client();
+
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = wallet_client.new_staking_address(name, &enckey).unwrap();
+        let outputs = vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit())];
+
+        let token = wallet_client
+            .unlock_wallet(name, &passphrase, Duration::from_secs(60))
+            .unwrap();
+        assert!(network_ops_client
+            .create_withdraw_unbonded_stake_transaction_with_session(
+                &token,
+                &from_address,
+                outputs.clone(),
+                TxAttributes::new(171),
+                NonceSource::Network,
+                true,
+            )
+            .is_ok());
+
+        let expired = wallet_client
+            .unlock_wallet(name, &passphrase, Duration::from_secs(0))
+            .unwrap();
+        assert_eq!(
+            ErrorKind::InvalidSession,
+            network_ops_client
+                .create_withdraw_unbonded_stake_transaction_with_session(
+                    &expired,
+                    &from_address,
+                    outputs,
+                    TxAttributes::new(171),
+                    NonceSource::Network,
+                    true,
+                )
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_withdraw_unbonded_stake_exact_balance() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let withdraw = |last_value: u64, auto_adjust_last_output: bool| {
+            network_ops_client.create_withdraw_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                vec![
+                    TxOut::new(
+                        ExtendedAddr::OrTree([0; 32]),
+                        Coin::new(1_000_000_000_000_000_000).unwrap(),
+                    ),
+                    TxOut::new(
+                        ExtendedAddr::OrTree([1; 32]),
+                        Coin::new(last_value).unwrap(),
+                    ),
+                ],
+                TxAttributes::new(171),
+                NonceSource::Network,
+                auto_adjust_last_output,
+            )
+        };
+        let output_values = |transaction: TxAux| match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match PlainTxAux::decode(&mut txpayload.as_slice()).unwrap() {
+                PlainTxAux::WithdrawUnbondedStakeTx(transaction) => transaction
+                    .outputs
+                    .iter()
+                    .map(|output| u64::from(output.value))
+                    .collect::<Vec<_>>(),
+                _ => unreachable!("invalid withdraw transaction payload"),
+            },
+            _ => unreachable!("withdraw transaction is expected"),
+        };
+
+        // unbonded amount is 2_500_000_000_000_000_000 and fee is 1
+        let (transaction, _) = withdraw(1_499_999_999_999_999_999, false).unwrap();
+        assert_eq!(
+            vec![1_000_000_000_000_000_000, 1_499_999_999_999_999_999],
+            output_values(transaction)
+        );
+
+        let error = withdraw(1_000_000_000_000_000_000, false).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().contains("24999999999.99999999"));
+        assert!(error.message().contains("shortfall of 4999999999.99999999"));
+
+        let (transaction, _) = withdraw(1_000_000_000_000_000_000, true).unwrap();
+        assert_eq!(
+            vec![1_000_000_000_000_000_000, 1_499_999_999_999_999_999],
+            output_values(transaction)
+        );
+
+        for auto_adjust_last_output in &[false, true] {
+            let error = withdraw(1_500_000_000_000_000_000, *auto_adjust_last_output).unwrap_err();
+            assert_eq!(ErrorKind::InvalidInput, error.kind());
+            assert!(error.message().contains("excess of 0.00000001"));
+        }
+    }
+
+    #[test]
+    fn check_withdraw_all_unbonded_stake_transaction() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+        let to_address = ExtendedAddr::OrTree([0; 32]);
+
+        let (transaction, _) = network_ops_client
+            .create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                to_address,
+                TxAttributes::new(171),
+                None,
+                None,
+            )
+            .unwrap();
+
+        match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                witness,
+                payload: TxObfuscated {
+                    txid, txpayload, ..
+                },
+                ..
+            }) => {
+                let account_address = verify_tx_recover_address(&witness, &txid)
+                    .expect("Unable to verify transaction");
+
+                assert_eq!(account_address, from_address);
+
+                // NOTE: Mock decryption based on encryption logic in `MockTransactionCipher`
+                let tx = PlainTxAux::decode(&mut txpayload.as_slice());
+                if let Ok(PlainTxAux::WithdrawUnbondedStakeTx(transaction)) = tx {
+                    let amount = transaction.outputs[0].value;
+                    assert_eq!(amount, Coin::new(2500000000000000000 - 1).unwrap());
+                }
+            }
+            _ => unreachable!(
+                "`create_withdraw_unbonded_stake_transaction()` created invalid transaction type"
+            ),
+        }
+    }
+
+    #[test]
+    fn check_withdraw_all_unbonded_stake_split_transaction() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+        let cold_address = ExtendedAddr::OrTree([1; 32]);
+        let hot_address = ExtendedAddr::OrTree([2; 32]);
+
+        let withdraw = |destinations| {
+            network_ops_client.create_withdraw_all_unbonded_stake_split_transaction(
+                name,
+                &enckey,
+                &from_address,
+                destinations,
+                TxAttributes::new(171),
+                None,
+            )
+        };
+
+        let (transaction, _) = withdraw(vec![
+            (cold_address.clone(), 7000, None),
+            (hot_address.clone(), 3000, None),
+        ])
+        .unwrap();
+
+        match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match PlainTxAux::decode(&mut txpayload.as_slice()).unwrap() {
+                PlainTxAux::WithdrawUnbondedStakeTx(transaction) => {
+                    assert_eq!(2, transaction.outputs.len());
+                    assert_eq!(cold_address, transaction.outputs[0].address);
+                    assert_eq!(
+                        Coin::new(1_749_999_999_999_999_999).unwrap(),
+                        transaction.outputs[0].value
+                    );
+                    assert_eq!(hot_address, transaction.outputs[1].address);
+                    assert_eq!(
+                        Coin::new(750_000_000_000_000_000).unwrap(),
+                        transaction.outputs[1].value
+                    );
+                    assert!(transaction
+                        .outputs
+                        .iter()
+                        .all(|output| output.valid_from == Some(0)));
+                }
+                _ => unreachable!("invalid withdraw transaction payload"),
+            },
+            _ => unreachable!(
+                "`create_withdraw_all_unbonded_stake_split_transaction()` created invalid transaction type"
+            ),
+        }
+
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            withdraw(vec![
+                (cold_address.clone(), 7000, None),
+                (hot_address.clone(), 2000, None)
+            ])
+            .unwrap_err()
+            .kind()
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            withdraw(vec![(cold_address, 10000, None), (hot_address, 0, None)])
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_withdraw_all_unbonded_stake_extra_lock() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient {
+                unbonded_from: 100,
+                ..Default::default()
+            },
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+        let cold_address = ExtendedAddr::OrTree([1; 32]);
+        let hot_address = ExtendedAddr::OrTree([2; 32]);
+
+        let valid_froms = |transaction: TxAux| match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match PlainTxAux::decode(&mut txpayload.as_slice()).unwrap() {
+                PlainTxAux::WithdrawUnbondedStakeTx(transaction) => transaction
+                    .outputs
+                    .iter()
+                    .map(|output| output.valid_from)
+                    .collect::<Vec<_>>(),
+                _ => unreachable!("invalid withdraw transaction payload"),
+            },
+            _ => unreachable!("withdraw transaction is expected"),
+        };
+
+        let (transaction, _) = network_ops_client
+            .create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                cold_address.clone(),
+                TxAttributes::new(171),
+                None,
+                Some(100),
+            )
+            .unwrap();
+        assert_eq!(vec![Some(100)], valid_froms(transaction));
+
+        let (transaction, _) = network_ops_client
+            .create_withdraw_all_unbonded_stake_split_transaction(
+                name,
+                &enckey,
+                &from_address,
+                vec![
+                    (cold_address.clone(), 7000, Some(100)),
+                    (hot_address.clone(), 3000, None),
+                ],
+                TxAttributes::new(171),
+                None,
+            )
+            .unwrap();
+        assert_eq!(vec![Some(100), Some(100)], valid_froms(transaction));
+
+        // chain requires outputs to be locked exactly until `unbonded_from`
+        for lock_until in &[99, 15_000_000] {
+            let error = network_ops_client
+                .create_withdraw_all_unbonded_stake_split_transaction(
+                    name,
+                    &enckey,
+                    &from_address,
+                    vec![
+                        (cold_address.clone(), 7000, None),
+                        (hot_address.clone(), 3000, Some(*lock_until)),
+                    ],
+                    TxAttributes::new(171),
+                    None,
+                )
+                .unwrap_err();
+            assert_eq!(ErrorKind::InvalidInput, error.kind());
+        }
+        let error = network_ops_client
+            .create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                cold_address,
+                TxAttributes::new(171),
+                None,
+                Some(15_000_000),
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+    }
+
+    #[test]
+    fn check_withdraw_all_unbonded_stake_fee_convergence() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockClient::default(),
+            GrowingFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let (transaction, _) = network_ops_client
+            .create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                ExtendedAddr::OrTree([0; 32]),
+                TxAttributes::new(171),
+                None,
+                None,
+            )
+            .unwrap();
+
+        match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match PlainTxAux::decode(&mut txpayload.as_slice()).unwrap() {
+                PlainTxAux::WithdrawUnbondedStakeTx(transaction) => {
+                    // fee grows from 1 to 3 units before it is stable
+                    assert_eq!(
+                        Coin::new(2_500_000_000_000_000_000 - 3).unwrap(),
+                        transaction.outputs[0].value
+                    );
+                }
+                _ => unreachable!("invalid withdraw transaction payload"),
+            },
+            _ => unreachable!(
+                "`create_withdraw_all_unbonded_stake_transaction()` created invalid transaction type"
+            ),
+        }
+    }
+
+    #[test]
+    fn check_withdraw_dust_outputs() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let new_client = |transfer_fee: u64| {
+            DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+                MockClient::default(),
+                TransferFeeAlgorithm {
+                    transfer_fee: Coin::new(transfer_fee).unwrap(),
+                },
+                MockTransactionCipher,
+            )
+        };
+
+        let network_ops_client = new_client(1000);
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let output = |value: u64| {
+            TxOut::new_with_timelock(ExtendedAddr::OrTree([0; 32]), Coin::new(value).unwrap(), 0)
+        };
+        let withdraw = |outputs: Vec<TxOut>| {
+            network_ops_client.create_withdraw_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                outputs,
+                TxAttributes::new(171),
+                NonceSource::Explicit(0),
+                false,
+            )
+        };
+
+        assert!(withdraw(vec![output(1000), output(1000)]).is_ok());
+
+        let error = withdraw(vec![output(1000), output(999)]).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!(
+            format!(
+                "Withdraw output 1 has value {} which is below the minimum allowed value {} (fee of spending it)",
+                Coin::new(999).unwrap(),
+                Coin::new(1000).unwrap()
+            ),
+            error.message()
+        );
+
+        // unbonded amount minus fee is not worth spending
+        let error = new_client(2_500_000_000_000_000_000)
+            .create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                ExtendedAddr::OrTree([0; 32]),
+                TxAttributes::new(171),
+                None,
+                None,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().starts_with("Withdraw output 0 has value"));
+    }
+
+    #[test]
+    fn check_withdraw_all_unbonded_stake_fee_priority() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let withdraw = |multiplier: &str, max_fee: Option<u64>| {
+            network_ops_client.create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                ExtendedAddr::OrTree([0; 32]),
+                TxAttributes::new(171),
+                Some(FeePriority {
+                    multiplier: multiplier.parse().unwrap(),
+                    max_fee: max_fee.map(|max_fee| Coin::new(max_fee).unwrap()),
+                }),
+                None,
+            )
+        };
+        let withdrawn = |transaction: TxAux| {
+            match transaction {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
+                payload: TxObfuscated { txpayload, .. },
+                ..
+            }) => match PlainTxAux::decode(&mut txpayload.as_slice()).unwrap() {
+                PlainTxAux::WithdrawUnbondedStakeTx(transaction) => transaction.outputs[0].value,
+                _ => unreachable!("invalid withdraw transaction payload"),
+            },
+            _ => unreachable!(
+                "`create_withdraw_all_unbonded_stake_transaction()` created invalid transaction type"
+            ),
+        }
+        };
+
+        // minimum fee is subtracted from unbonded amount
+        let (transaction, _) = withdraw("1.0", Some(2)).unwrap();
+        assert_eq!(
+            Coin::new(2_500_000_000_000_000_000 - 1).unwrap(),
+            withdrawn(transaction)
+        );
+
+        // chain refuses withdraw transactions paying more than the minimum fee
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            withdraw("3.5", None).unwrap_err().kind()
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            withdraw("0.9", None).unwrap_err().kind()
+        );
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            withdraw("1.0", Some(0)).unwrap_err().kind()
+        );
+    }
+
+    #[test]
+    fn check_create_withdraw_all_batch() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+        let genesis_time = to_timespec(mock::genesis().genesis_time).unwrap();
+
+        let storage = MemoryStorage::default();
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let addresses = (0..3)
+            .map(|_| wallet_client.new_staking_address(name, &enckey).unwrap())
+            .collect::<Vec<_>>();
+
+        let staked_state = |address: StakedStateAddress,
+                            unbonded_from: Timespec,
+                            jailed_until: Option<Timespec>| {
+            let validator = jailed_until.map(|jailed_until| Validator {
+                jailed_until: Some(jailed_until),
+                ..Validator::new(CouncilNode::new(
+                    TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+                    ConfidentialInit { cert: keypackage() },
+                ))
+            });
+            let StakedStateAddress::BasicRedeem(ref redeem_address) = address;
+            (
+                redeem_address.0.to_vec(),
+                StakedState::new(
+                    0,
+                    Coin::zero(),
+                    Coin::new(1000).unwrap(),
+                    unbonded_from,
+                    address,
+                    validator,
+                ),
+            )
+        };
+        let tendermint_client = MockClient {
+            staked_states: vec![
+                staked_state(addresses[0], genesis_time, None),
+                staked_state(addresses[1], genesis_time, Some(genesis_time + 3600)),
+                staked_state(addresses[2], genesis_time + 3600, None),
+            ]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let account_queries = tendermint_client.account_queries.clone();
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            tendermint_client,
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let results = network_ops_client
+            .create_withdraw_all_batch(
+                name,
+                &enckey,
+                &addresses,
+                ExtendedAddr::OrTree([0; 32]),
+                TxAttributes::new(171),
+            )
+            .unwrap();
+        assert_eq!(
+            addresses,
+            results
+                .iter()
+                .map(|(address, _)| *address)
+                .collect::<Vec<_>>()
+        );
+        // states of all addresses are fetched in one batch only
+        assert_eq!(3, account_queries.load(Ordering::SeqCst));
+
+        let (tx_aux, pending_transaction) = results[0].1.as_ref().unwrap();
+        match tx_aux {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { .. }) => {}
+            _ => panic!("Expected withdraw transaction"),
+        }
+        assert_eq!(Coin::new(999).unwrap(), pending_transaction.return_amount);
+
+        let error = results[1].1.as_ref().unwrap_err();
+        assert_eq!(ErrorKind::ValidationError, error.kind());
+        assert_eq!(
+            "Failed to validate staking account: account is jailed",
+            error.message()
+        );
+
+        let error = results[2].1.as_ref().unwrap_err();
+        assert_eq!(ErrorKind::ValidationError, error.kind());
+        assert!(error
+            .message()
+            .starts_with("Staking state is not yet unbonded: 3600 seconds remaining"));
+    }
+
+    #[test]
+    fn check_create_restake_transactions() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+        let genesis_time = to_timespec(mock::genesis().genesis_time).unwrap();
+
+        let new_client = |unbonded_from| {
+            let storage = MemoryStorage::default();
+            DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage, HwKeyService::default()),
+                MockClient {
+                    unbonded_from,
+                    ..Default::default()
+                },
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            )
+        };
+
+        let network_ops_client = new_client(genesis_time);
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let (transactions, pending_transaction) = network_ops_client
+            .create_restake_transactions(name, &enckey, &address, address, TxAttributes::new(171))
+            .unwrap();
+        assert_eq!(2, transactions.len());
+
+        let withdraw_id = match transactions[0] {
+            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx { ref payload, .. }) => {
+                payload.txid
+            }
+            _ => panic!("Expected withdraw transaction"),
+        };
+        match transactions[1] {
+            TxAux::EnclaveTx(TxEnclaveAux::DepositStakeTx { ref tx, .. }) => {
+                assert_eq!(vec![TxoPointer::new(withdraw_id, 0)], tx.inputs);
+                assert_eq!(address, tx.to_staked_account);
+            }
+            _ => panic!("Expected deposit transaction"),
+        }
+        assert_eq!(transactions[1].tx_id(), pending_transaction.tx_id);
+        assert!(pending_transaction.used_inputs.is_empty());
+        assert_eq!(Coin::zero(), pending_transaction.return_amount);
+
+        // withdraw output cannot be spent before it is unlocked
+        let network_ops_client = new_client(genesis_time + 10);
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+        let error = network_ops_client
+            .create_restake_transactions(name, &enckey, &address, address, TxAttributes::new(171))
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().contains("10 seconds from now"));
+    }
+
+    #[test]
+    fn check_unbonding_status() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+        let genesis_time = to_timespec(mock::genesis().genesis_time).unwrap();
+
+        for (unbonded_from, remaining_secs) in vec![
+            (genesis_time + 3600, Some(3600)),
+            (genesis_time + 1, Some(1)),
+            (genesis_time, None),
+            (genesis_time - 1, None),
+        ] {
+            let storage = MemoryStorage::default();
+            let network_ops_client = DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage, HwKeyService::default()),
+                MockClient {
+                    unbonded_from,
+                    ..Default::default()
+                },
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            );
+
+            let (enckey, _) = network_ops_client
+                .get_wallet_client()
+                .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+                .unwrap();
+            let address = network_ops_client
+                .get_wallet_client()
+                .new_staking_address(name, &enckey)
+                .unwrap();
+
+            let unbonding_status = network_ops_client.get_unbonding_status(&address).unwrap();
+            assert_eq!(genesis_time, unbonding_status.current_time);
+            assert_eq!(unbonded_from, unbonding_status.unbonded_from);
+            assert_eq!(remaining_secs, unbonding_status.remaining_secs);
+
+            let withdraw = network_ops_client.create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &address,
+                ExtendedAddr::OrTree([0; 32]),
+                TxAttributes::new(171),
+                None,
+                None,
+            );
+            match remaining_secs {
+                Some(remaining_secs) => {
+                    let error = withdraw.unwrap_err();
+                    assert_eq!(ErrorKind::ValidationError, error.kind());
+                    assert!(error
+                        .message()
+                        .contains(&format!("{} seconds remaining", remaining_secs)));
+                }
+                None => assert!(withdraw.is_ok()),
+            }
         }
+    }
 
-        fn block_results_batch<'a, T: Iterator<Item = &'a u64>>(
-            &self,
-            _heights: T,
-        ) -> Result<Vec<BlockResultsResponse>> {
-            unreachable!()
-        }
+    #[test]
+    fn check_staked_state_cache() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
 
-        fn block_batch_verified<'a, T: Clone + Iterator<Item = &'a u64>>(
-            &self,
-            _state: lite::TrustedState,
-            _heights: T,
-        ) -> Result<(Vec<Block>, lite::TrustedState)> {
-            unreachable!()
-        }
+        let storage = MemoryStorage::default();
+        let tendermint_client = MockClient::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            tendermint_client.clone(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        )
+        .with_staked_state_ttl(Duration::from_secs(60));
 
-        fn broadcast_transaction(&self, _: &[u8]) -> Result<BroadcastTxResponse> {
-            unreachable!()
-        }
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
 
-        fn query(&self, _path: &str, _data: &[u8]) -> Result<AbciQuery> {
-            let staked_state = StakedState::new(
-                0,
-                Coin::new(1000000).unwrap(),
-                Coin::new(2499999999999999999 + 1).unwrap(),
-                0,
-                StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+        // staked state is queried once for both validation and nonce
+        network_ops_client
+            .create_withdraw_all_unbonded_stake_transaction(
+                name,
+                &enckey,
+                &from_address,
+                ExtendedAddr::OrTree([0; 32]),
+                TxAttributes::new(171),
                 None,
-            );
+                None,
+            )
+            .unwrap();
+        assert_eq!(1, tendermint_client.account_queries.load(Ordering::SeqCst));
 
-            Ok(AbciQuery {
-                value: Some(staked_state.encode()),
-                ..Default::default()
-            })
-        }
+        network_ops_client.get_staked_state(&from_address).unwrap();
+        assert_eq!(1, tendermint_client.account_queries.load(Ordering::SeqCst));
 
-        fn query_state_batch<T: Iterator<Item = u64>>(
-            &self,
-            _heights: T,
-        ) -> Result<Vec<ChainState>> {
-            unreachable!()
+        network_ops_client
+            .invalidate_staked_state(&from_address)
+            .unwrap();
+        network_ops_client.get_staked_state(&from_address).unwrap();
+        assert_eq!(2, tendermint_client.account_queries.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn check_balance_by_address() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        for missing_account in vec![false, true] {
+            let storage = MemoryStorage::default();
+            let network_ops_client = DefaultNetworkOpsClient::new(
+                DefaultWalletClient::new_read_only(storage.clone()),
+                WalletSignerManager::new(storage, HwKeyService::default()),
+                MockClient {
+                    missing_account,
+                    ..Default::default()
+                },
+                UnitFeeAlgorithm::default(),
+                MockTransactionCipher,
+            );
+
+            let (enckey, _) = network_ops_client
+                .get_wallet_client()
+                .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+                .unwrap();
+            network_ops_client
+                .get_wallet_client()
+                .new_transfer_address(name, &enckey)
+                .unwrap();
+            let address = network_ops_client
+                .get_wallet_client()
+                .new_staking_address(name, &enckey)
+                .unwrap();
+
+            let balances = network_ops_client
+                .balance_by_address(name, &enckey)
+                .unwrap();
+            assert_eq!(2, balances.len());
+            assert_eq!(None, balances[0].bonded);
+            assert_eq!(LabeledAddress::Staking(address), balances[1].address);
+            match network_ops_client.get_staked_state_maybe(&address).unwrap() {
+                Some(staked_state) => {
+                    assert!(!missing_account);
+                    assert_eq!(Some(staked_state.bonded), balances[1].bonded);
+                    assert_eq!(Some(staked_state.unbonded), balances[1].unbonded);
+                }
+                None => {
+                    assert!(missing_account);
+                    assert_eq!(None, balances[1].bonded);
+                    assert_eq!(None, balances[1].unbonded);
+                }
+            }
         }
     }
 
     #[test]
-    fn check_create_deposit_bonded_stake_transaction() {
+    fn check_withdraw_unbonded_stake_transaction_address_not_found() {
         let name = "name";
         let passphrase = SecUtf8::from("passphrase");
 
@@ -730,18 +6176,6 @@ mod tests {
 
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
-        let input = TxoPointer::new([0; 32], 0);
-        let output = TxOut {
-            address: ExtendedAddr::OrTree([0; 32]),
-            value: Coin::new(10).unwrap(),
-            valid_from: None,
-        };
-        let transactions = vec![(input, output)];
-
-        let (enckey, _) = wallet_client
-            .new_wallet(name, &passphrase, WalletKind::Basic)
-            .unwrap();
-
         let tendermint_client = MockClient::default();
         let network_ops_client = DefaultNetworkOpsClient::new(
             wallet_client,
@@ -751,22 +6185,24 @@ mod tests {
             MockTransactionCipher,
         );
 
-        let to_staked_account = network_ops_client
+        let (enckey, _) = network_ops_client
             .get_wallet_client()
-            .new_staking_address(name, &enckey)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
 
-        let attributes = StakedStateOpAttributes::new(0);
-
         assert_eq!(
             ErrorKind::InvalidInput,
             network_ops_client
-                .create_deposit_bonded_stake_transaction(
+                .create_withdraw_unbonded_stake_transaction(
                     name,
                     &enckey,
-                    transactions,
-                    to_staked_account,
-                    attributes,
+                    &StakedStateAddress::BasicRedeem(RedeemAddress::from(&PublicKey::from(
+                        &PrivateKey::new().unwrap()
+                    ))),
+                    vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit())],
+                    TxAttributes::new(171),
+                    NonceSource::Network,
+                    true,
                 )
                 .unwrap_err()
                 .kind()
@@ -774,9 +6210,9 @@ mod tests {
     }
 
     #[test]
-    fn check_create_unbond_stake_transaction() {
+    fn check_withdraw_unbonded_stake_transaction_wallet_not_found() {
         let name = "name";
-        let passphrase = SecUtf8::from("passphrase");
+        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
 
         let storage = MemoryStorage::default();
         let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
@@ -784,12 +6220,48 @@ mod tests {
         let fee_algorithm = UnitFeeAlgorithm::default();
 
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+        let tendermint_client = MockClient::default();
 
-        let (enckey, _) = wallet_client
-            .new_wallet(name, &passphrase, WalletKind::Basic)
-            .unwrap();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            tendermint_client,
+            fee_algorithm,
+            MockTransactionCipher,
+        );
 
-        let tendermint_client = MockClient::default();
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            network_ops_client
+                .create_withdraw_unbonded_stake_transaction(
+                    name,
+                    enckey,
+                    &StakedStateAddress::BasicRedeem(RedeemAddress::from(&PublicKey::from(
+                        &PrivateKey::new().unwrap()
+                    ))),
+                    Vec::new(),
+                    TxAttributes::new(171),
+                    NonceSource::Network,
+                    false,
+                )
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_unjail_transaction() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+
+        let fee_algorithm = UnitFeeAlgorithm::default();
+
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let tendermint_client = MockJailedClient::default();
         let network_ops_client = DefaultNetworkOpsClient::new(
             wallet_client,
             signer_manager,
@@ -798,20 +6270,101 @@ mod tests {
             MockTransactionCipher,
         );
 
-        let value = Coin::new(0).unwrap();
-        let address = network_ops_client
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
+        let from_address = network_ops_client
             .get_wallet_client()
             .new_staking_address(name, &enckey)
             .unwrap();
-        let attributes = StakedStateOpAttributes::new(0);
 
-        assert!(network_ops_client
-            .create_unbond_stake_transaction(name, &enckey, address, value, attributes)
-            .is_ok());
+        let (transaction, _) = network_ops_client
+            .create_unjail_transaction(
+                name,
+                &enckey,
+                from_address,
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Network,
+            )
+            .unwrap();
+        match transaction {
+            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, witness)) => {
+                let txid = tx.id();
+                let account_address = verify_tx_recover_address(&witness, &txid)
+                    .expect("Unable to verify transaction");
+                assert_eq!(account_address, from_address);
+            }
+            _ => unreachable!("`unjail_tx()` created invalid transaction"),
+        }
     }
 
     #[test]
-    fn check_withdraw_unbonded_stake_transaction() {
+    fn check_to_timespec_before_epoch() {
+        assert_eq!(
+            0,
+            to_timespec("1970-01-01T00:00:00Z".parse().unwrap()).unwrap()
+        );
+
+        let error = to_timespec("1969-12-31T23:59:59Z".parse().unwrap()).unwrap_err();
+        assert_eq!(ErrorKind::DeserializationError, error.kind());
+        assert!(error.message().contains("1969-12-31T23:59:59"));
+
+        assert!(is_zero_time(&ZERO_TIME.parse().unwrap()));
+        assert!(!is_zero_time(&mock::genesis().genesis_time));
+    }
+
+    #[test]
+    fn check_unjail_transaction_jail_period_not_expired() {
+        let name = "name";
+        let passphrase = SecUtf8::from("passphrase");
+
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let genesis_time = to_timespec(mock::genesis().genesis_time).unwrap();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockJailedClient {
+                jailed_until: genesis_time + 3600,
+            },
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let (enckey, _) = network_ops_client
+            .get_wallet_client()
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+        let from_address = network_ops_client
+            .get_wallet_client()
+            .new_staking_address(name, &enckey)
+            .unwrap();
+
+        let error = network_ops_client
+            .create_unjail_transaction(
+                name,
+                &enckey,
+                from_address,
+                Some(StakedStateOpAttributes::new(171)),
+                NonceSource::Network,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::IllegalInput, error.kind());
+        assert_eq!(
+            format!(
+                "Jail period has not expired yet: 3600 seconds remaining, account can be unjailed at {} (unix timestamp)",
+                genesis_time + 3600
+            ),
+            error.message()
+        );
+    }
+
+    #[test]
+    fn check_node_join_transaction() {
         let name = "name";
         let passphrase = SecUtf8::from("passphrase");
 
@@ -822,7 +6375,10 @@ mod tests {
 
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
-        let tendermint_client = MockClient::default();
+        let tendermint_client = MockClient {
+            bonded: Coin::new(1_250_000_000_000_000_000).unwrap(),
+            ..MockClient::default()
+        };
         let network_ops_client = DefaultNetworkOpsClient::new(
             wallet_client,
             signer_manager,
@@ -833,303 +6389,440 @@ mod tests {
 
         let (enckey, _) = network_ops_client
             .get_wallet_client()
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
 
-        let from_address = network_ops_client
+        let staking_account_address = network_ops_client
             .get_wallet_client()
             .new_staking_address(name, &enckey)
             .unwrap();
 
-        let (transaction, _pending_tx) = network_ops_client
-            .create_withdraw_unbonded_stake_transaction(
+        let mut validator_pubkey = [0; 32];
+        validator_pubkey.copy_from_slice(
+            &base64::decode("P2B49bRtePqHr0JGRVAOS9ZqSFjBpS6dFtCah9p+cro=").unwrap(),
+        );
+
+        let node_metadata = CouncilNode {
+            name: "test".to_owned(),
+            security_contact: None,
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519(validator_pubkey),
+            confidential_init: ConfidentialInit { cert: keypackage() },
+        };
+
+        let (transaction, _) = network_ops_client
+            .create_node_join_transaction(
                 name,
                 &enckey,
-                &from_address,
-                vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit())],
-                TxAttributes::new(171),
+                staking_account_address,
+                Some(StakedStateOpAttributes::new(171)),
+                node_metadata,
+                NonceSource::Network,
             )
             .unwrap();
 
         match transaction {
-            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
-                payload: TxObfuscated { txid, .. },
-                witness,
-                ..
-            }) => {
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness)) => {
+                let txid = tx.id();
                 let account_address = verify_tx_recover_address(&witness, &txid)
                     .expect("Unable to verify transaction");
-
-                assert_eq!(account_address, from_address)
+                assert_eq!(account_address, staking_account_address);
             }
-            _ => unreachable!(
-                "`create_withdraw_unbonded_stake_transaction()` created invalid transaction type"
-            ),
+            _ => unreachable!("`create_node_join_tx()` created invalid transaction"),
         }
     }
 
     #[test]
-    fn check_withdraw_all_unbonded_stake_transaction() {
+    fn check_node_join_transaction_insufficient_stake() {
         let name = "name";
         let passphrase = SecUtf8::from("passphrase");
 
         let storage = MemoryStorage::default();
         let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
-
-        let fee_algorithm = UnitFeeAlgorithm::default();
-
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
-        let tendermint_client = MockClient::default();
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
         let network_ops_client = DefaultNetworkOpsClient::new(
             wallet_client,
             signer_manager,
-            tendermint_client,
-            fee_algorithm,
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
             MockTransactionCipher,
         );
 
-        let (enckey, _) = network_ops_client
-            .get_wallet_client()
-            .new_wallet(name, &passphrase, WalletKind::Basic)
-            .unwrap();
-
-        let from_address = network_ops_client
+        let staking_account_address = network_ops_client
             .get_wallet_client()
             .new_staking_address(name, &enckey)
             .unwrap();
-        let to_address = ExtendedAddr::OrTree([0; 32]);
 
-        let (transaction, _) = network_ops_client
-            .create_withdraw_all_unbonded_stake_transaction(
+        let node_metadata = CouncilNode {
+            name: "test".to_owned(),
+            security_contact: None,
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            confidential_init: ConfidentialInit { cert: keypackage() },
+        };
+
+        let error = network_ops_client
+            .create_node_join_transaction(
                 name,
                 &enckey,
-                &from_address,
-                to_address,
-                TxAttributes::new(171),
+                staking_account_address,
+                Some(StakedStateOpAttributes::new(171)),
+                node_metadata,
+                NonceSource::Network,
             )
-            .unwrap();
-
-        match transaction {
-            TxAux::EnclaveTx(TxEnclaveAux::WithdrawUnbondedStakeTx {
-                witness,
-                payload: TxObfuscated {
-                    txid, txpayload, ..
-                },
-                ..
-            }) => {
-                let account_address = verify_tx_recover_address(&witness, &txid)
-                    .expect("Unable to verify transaction");
-
-                assert_eq!(account_address, from_address);
-
-                // NOTE: Mock decryption based on encryption logic in `MockTransactionCipher`
-                let tx = PlainTxAux::decode(&mut txpayload.as_slice());
-                if let Ok(PlainTxAux::WithdrawUnbondedStakeTx(transaction)) = tx {
-                    let amount = transaction.outputs[0].value;
-                    assert_eq!(amount, Coin::new(2500000000000000000 - 1).unwrap());
-                }
-            }
-            _ => unreachable!(
-                "`create_withdraw_unbonded_stake_transaction()` created invalid transaction type"
-            ),
-        }
+            .unwrap_err();
+        assert_eq!(ErrorKind::IllegalInput, error.kind());
+        assert_eq!(
+            "Bonded amount 0.01000000 is less than required council node stake 12500000000.00000000",
+            error.message()
+        );
     }
 
     #[test]
-    fn check_withdraw_unbonded_stake_transaction_address_not_found() {
+    fn check_node_join_transaction_duplicate_consensus_pubkey() {
         let name = "name";
         let passphrase = SecUtf8::from("passphrase");
 
         let storage = MemoryStorage::default();
         let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
-
-        let fee_algorithm = UnitFeeAlgorithm::default();
-
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
-        let tendermint_client = MockClient::default();
+        let (enckey, _) = wallet_client
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
+            .unwrap();
+
         let network_ops_client = DefaultNetworkOpsClient::new(
             wallet_client,
             signer_manager,
-            tendermint_client,
-            fee_algorithm,
+            MockClient {
+                bonded: Coin::new(1_250_000_000_000_000_000).unwrap(),
+                ..MockClient::default()
+            },
+            UnitFeeAlgorithm::default(),
             MockTransactionCipher,
         );
 
-        let (enckey, _) = network_ops_client
+        let staking_account_address = network_ops_client
             .get_wallet_client()
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_staking_address(name, &enckey)
             .unwrap();
 
+        let node_metadata = CouncilNode {
+            name: "test".to_owned(),
+            security_contact: None,
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xab; 32]),
+            confidential_init: ConfidentialInit { cert: keypackage() },
+        };
+
+        let error = network_ops_client
+            .create_node_join_transaction(
+                name,
+                &enckey,
+                staking_account_address,
+                Some(StakedStateOpAttributes::new(171)),
+                node_metadata.clone(),
+                NonceSource::Network,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
         assert_eq!(
-            ErrorKind::InvalidInput,
-            network_ops_client
-                .create_withdraw_unbonded_stake_transaction(
-                    name,
-                    &enckey,
-                    &StakedStateAddress::BasicRedeem(RedeemAddress::from(&PublicKey::from(
-                        &PrivateKey::new().unwrap()
-                    ))),
-                    vec![TxOut::new(ExtendedAddr::OrTree([0; 32]), Coin::unit())],
-                    TxAttributes::new(171),
-                )
-                .unwrap_err()
-                .kind()
+            format!(
+                "Consensus pub-key is already used by council node with staking address {}",
+                StakedStateAddress::BasicRedeem(RedeemAddress::from([1; 20]))
+            ),
+            error.message()
         );
+
+        // lookup is skipped when building offline
+        assert!(network_ops_client
+            .create_node_join_transaction(
+                name,
+                &enckey,
+                staking_account_address,
+                Some(StakedStateOpAttributes::new(171)),
+                node_metadata,
+                NonceSource::Explicit(0),
+            )
+            .is_ok());
     }
 
     #[test]
-    fn check_withdraw_unbonded_stake_transaction_wallet_not_found() {
-        let name = "name";
-        let enckey = &derive_enckey(&SecUtf8::from("passphrase"), name).unwrap();
-
+    fn check_used_validator_addresses() {
         let storage = MemoryStorage::default();
-        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let used_pubkey = TendermintValidatorPubKey::Ed25519([0xef; 32]);
+        let used_validator_addresses = vec![(TendermintValidatorAddress::from(&used_pubkey), 100)];
+        let staking_address = StakedStateAddress::BasicRedeem(RedeemAddress::from([2; 20]));
 
-        let fee_algorithm = UnitFeeAlgorithm::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage.clone(), HwKeyService::default()),
+            MockClient {
+                council_node: Some(CouncilNode::new(
+                    TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+                    ConfidentialInit { cert: keypackage() },
+                )),
+                used_validator_addresses: used_validator_addresses.clone(),
+                ..MockClient::default()
+            },
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
 
-        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
-        let tendermint_client = MockClient::default();
+        assert_eq!(
+            used_validator_addresses,
+            network_ops_client
+                .get_used_validator_addresses(&staking_address)
+                .unwrap()
+        );
+        assert_eq!(
+            Some(StakedStateAddress::BasicRedeem(RedeemAddress::from(
+                [1; 20]
+            ))),
+            network_ops_client
+                .find_staking_address_by_consensus_key(&TendermintValidatorPubKey::Ed25519(
+                    [0xab; 32]
+                ))
+                .unwrap()
+        );
+        assert_eq!(
+            None,
+            network_ops_client
+                .find_staking_address_by_consensus_key(&used_pubkey)
+                .unwrap()
+        );
 
         let network_ops_client = DefaultNetworkOpsClient::new(
-            wallet_client,
-            signer_manager,
-            tendermint_client,
-            fee_algorithm,
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
             MockTransactionCipher,
         );
-
         assert_eq!(
             ErrorKind::InvalidInput,
             network_ops_client
-                .create_withdraw_unbonded_stake_transaction(
-                    name,
-                    enckey,
-                    &StakedStateAddress::BasicRedeem(RedeemAddress::from(&PublicKey::from(
-                        &PrivateKey::new().unwrap()
-                    ))),
-                    Vec::new(),
-                    TxAttributes::new(171),
-                )
+                .get_used_validator_addresses(&staking_address)
                 .unwrap_err()
                 .kind()
         );
     }
 
     #[test]
-    fn check_unjail_transaction() {
+    fn check_update_node_metadata() {
         let name = "name";
         let passphrase = SecUtf8::from("passphrase");
 
-        let storage = MemoryStorage::default();
-        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
-
-        let fee_algorithm = UnitFeeAlgorithm::default();
-
-        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+        let current_metadata = CouncilNode {
+            name: "test".to_owned(),
+            security_contact: Some("security@example.com".to_owned()),
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            confidential_init: ConfidentialInit { cert: keypackage() },
+        };
 
-        let tendermint_client = MockJailedClient::default();
+        let storage = MemoryStorage::default();
         let network_ops_client = DefaultNetworkOpsClient::new(
-            wallet_client,
-            signer_manager,
-            tendermint_client,
-            fee_algorithm,
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient {
+                bonded: Coin::new(1_250_000_000_000_000_000).unwrap(),
+                council_node: Some(current_metadata.clone()),
+                ..MockClient::default()
+            },
+            UnitFeeAlgorithm::default(),
             MockTransactionCipher,
         );
 
         let (enckey, _) = network_ops_client
             .get_wallet_client()
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
-
-        let from_address = network_ops_client
+        let address = network_ops_client
             .get_wallet_client()
             .new_staking_address(name, &enckey)
             .unwrap();
 
-        let transaction = network_ops_client
-            .create_unjail_transaction(
+        let update = |new_metadata: &CouncilNode, allow_pubkey_rotation| {
+            network_ops_client.update_node_metadata(
                 name,
                 &enckey,
-                from_address,
-                StakedStateOpAttributes::new(171),
+                address,
+                Some(StakedStateOpAttributes::new(171)),
+                new_metadata.clone(),
+                allow_pubkey_rotation,
             )
-            .unwrap();
-        match transaction {
-            TxAux::PublicTx(TxPublicAux::UnjailTx(tx, witness)) => {
-                let txid = tx.id();
-                let account_address = verify_tx_recover_address(&witness, &txid)
-                    .expect("Unable to verify transaction");
-                assert_eq!(account_address, from_address);
-            }
-            _ => unreachable!("`unjail_tx()` created invalid transaction"),
-        }
+        };
+        let node_meta = |transaction| match transaction {
+            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, _)) => tx.node_meta,
+            _ => unreachable!("`update_node_metadata()` created invalid transaction"),
+        };
+
+        let error = update(&current_metadata, false).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert_eq!(
+            "New council node metadata is identical to current one",
+            error.message()
+        );
+
+        let contact_changed = CouncilNode {
+            security_contact: Some("https://example.com/security".to_owned()),
+            ..current_metadata.clone()
+        };
+        let (transaction, _) = update(&contact_changed, false).unwrap();
+        assert_eq!(contact_changed, node_meta(transaction));
+
+        let pubkey_rotated = CouncilNode {
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xef; 32]),
+            ..current_metadata
+        };
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            update(&pubkey_rotated, false).unwrap_err().kind()
+        );
+        let (transaction, _) = update(&pubkey_rotated, true).unwrap();
+        assert_eq!(pubkey_rotated, node_meta(transaction));
+
+        // staking address without validator record has to use node join transaction
+        let storage = MemoryStorage::default();
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            DefaultWalletClient::new_read_only(storage.clone()),
+            WalletSignerManager::new(storage, HwKeyService::default()),
+            MockClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+        let error = network_ops_client
+            .update_node_metadata(
+                name,
+                &enckey,
+                address,
+                Some(StakedStateOpAttributes::new(171)),
+                pubkey_rotated,
+                true,
+            )
+            .unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().contains("is not a council node"));
     }
 
     #[test]
-    fn check_node_join_transaction() {
+    fn check_node_join_cert_fingerprint_ack() {
         let name = "name";
         let passphrase = SecUtf8::from("passphrase");
 
         let storage = MemoryStorage::default();
         let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
-
-        let fee_algorithm = UnitFeeAlgorithm::default();
-
         let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
 
-        let tendermint_client = MockClient::default();
         let network_ops_client = DefaultNetworkOpsClient::new(
             wallet_client,
             signer_manager,
-            tendermint_client,
-            fee_algorithm,
+            MockClient {
+                bonded: Coin::new(1_250_000_000_000_000_000).unwrap(),
+                ..MockClient::default()
+            },
+            UnitFeeAlgorithm::default(),
             MockTransactionCipher,
         );
 
         let (enckey, _) = network_ops_client
             .get_wallet_client()
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
-
         let staking_account_address = network_ops_client
             .get_wallet_client()
             .new_staking_address(name, &enckey)
             .unwrap();
 
-        let mut validator_pubkey = [0; 32];
-        validator_pubkey.copy_from_slice(
-            &base64::decode("P2B49bRtePqHr0JGRVAOS9ZqSFjBpS6dFtCah9p+cro=").unwrap(),
-        );
-
         let node_metadata = CouncilNode {
             name: "test".to_owned(),
             security_contact: None,
-            consensus_pubkey: TendermintValidatorPubKey::Ed25519(validator_pubkey),
-            confidential_init: ConfidentialInit {
-                cert: b"FIXME".to_vec(),
-            },
+            consensus_pubkey: TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            confidential_init: ConfidentialInit { cert: keypackage() },
         };
 
-        let transaction = network_ops_client
-            .create_node_join_transaction(
+        let preview = network_ops_client
+            .preview_node_join_transaction(&staking_account_address, &node_metadata)
+            .unwrap();
+        assert!(preview.cert_changes);
+        assert_eq!(preview.current_cert_fingerprint, None);
+
+        let create = |ack: &str| {
+            network_ops_client.create_acknowledged_node_join_transaction(
                 name,
                 &enckey,
                 staking_account_address,
-                StakedStateOpAttributes::new(171),
-                node_metadata,
+                Some(StakedStateOpAttributes::new(171)),
+                node_metadata.clone(),
+                NonceSource::Network,
+                ack,
             )
-            .unwrap();
+        };
 
-        match transaction {
-            TxAux::PublicTx(TxPublicAux::NodeJoinTx(tx, witness)) => {
-                let txid = tx.id();
-                let account_address = verify_tx_recover_address(&witness, &txid)
-                    .expect("Unable to verify transaction");
-                assert_eq!(account_address, staking_account_address);
-            }
-            _ => unreachable!("`create_node_join_tx()` created invalid transaction"),
-        }
+        assert!(create(&preview.new_cert_fingerprint).is_ok());
+        assert_eq!(ErrorKind::ValidationError, create("").unwrap_err().kind());
+        assert_eq!(
+            ErrorKind::ValidationError,
+            create(&hex::encode([0; 32])).unwrap_err().kind()
+        );
+
+        let empty_cert = CouncilNode {
+            confidential_init: ConfidentialInit { cert: Vec::new() },
+            ..node_metadata.clone()
+        };
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            network_ops_client
+                .preview_node_join_transaction(&staking_account_address, &empty_cert)
+                .unwrap_err()
+                .kind()
+        );
+
+        let placeholder_cert = CouncilNode {
+            confidential_init: ConfidentialInit {
+                cert: b"FIXME".to_vec(),
+            },
+            ..node_metadata.clone()
+        };
+        assert_eq!(
+            ErrorKind::InvalidInput,
+            network_ops_client
+                .preview_node_join_transaction(&staking_account_address, &placeholder_cert)
+                .unwrap_err()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn check_node_join_preview_unchanged_cert() {
+        let storage = MemoryStorage::default();
+        let signer_manager = WalletSignerManager::new(storage.clone(), HwKeyService::default());
+        let wallet_client = DefaultWalletClient::new_read_only(storage.clone());
+
+        let network_ops_client = DefaultNetworkOpsClient::new(
+            wallet_client,
+            signer_manager,
+            MockJailedClient::default(),
+            UnitFeeAlgorithm::default(),
+            MockTransactionCipher,
+        );
+
+        let node_metadata = CouncilNode::new(
+            TendermintValidatorPubKey::Ed25519([0xcd; 32]),
+            ConfidentialInit { cert: keypackage() },
+        );
+        let preview = network_ops_client
+            .preview_node_join_transaction(
+                &StakedStateAddress::BasicRedeem(RedeemAddress::default()),
+                &node_metadata,
+            )
+            .unwrap();
+        assert!(!preview.cert_changes);
+        assert_eq!(
+            preview.current_cert_fingerprint,
+            Some(preview.new_cert_fingerprint)
+        );
     }
 }
@@ -0,0 +1,205 @@
+//! Registry of staking transactions which are built but not yet applied on chain, used to avoid
+//! building several transactions with the same nonce
+use parity_scale_codec::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use chain_core::state::account::StakedStateAddress;
+use chain_core::tx::data::TxId;
+use client_common::{Error, ErrorKind, Result, Storage};
+
+const KEYSPACE: &str = "network_pending_nonce";
+
+/// Default number of blocks after which a pending nonce expires
+pub const DEFAULT_PENDING_NONCE_EXPIRY_BLOCKS: u64 = 10;
+
+/// Behaviour when a staking transaction is built for an address with pending transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PendingNoncePolicy {
+    /// Use the nonce following the highest pending nonce
+    Bump,
+    /// Refuse to build the transaction until pending transactions are applied or expire
+    Refuse,
+}
+
+impl Default for PendingNoncePolicy {
+    #[inline]
+    fn default() -> Self {
+        PendingNoncePolicy::Bump
+    }
+}
+
+/// Staking transaction built with a nonce which is not yet applied on chain
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct PendingNonce {
+    /// Nonce used in transaction
+    pub nonce: u64,
+    /// Id of transaction
+    pub transaction_id: TxId,
+    /// Block height after which the entry is discarded
+    pub expiry_height: u64,
+}
+
+/// Maintains pending nonces of staking addresses
+#[derive(Debug, Clone)]
+pub struct PendingNonceRegistry<S: Storage> {
+    storage: S,
+    policy: PendingNoncePolicy,
+    expiry_blocks: u64,
+}
+
+impl<S> PendingNonceRegistry<S>
+where
+    S: Storage,
+{
+    /// Creates a new instance of pending nonce registry with given policy and default expiry
+    #[inline]
+    pub fn new(storage: S, policy: PendingNoncePolicy) -> Self {
+        Self::new_with_expiry(storage, policy, DEFAULT_PENDING_NONCE_EXPIRY_BLOCKS)
+    }
+
+    /// Creates a new instance of pending nonce registry whose entries expire after given number
+    /// of blocks
+    pub fn new_with_expiry(storage: S, policy: PendingNoncePolicy, expiry_blocks: u64) -> Self {
+        Self {
+            storage,
+            policy,
+            expiry_blocks,
+        }
+    }
+
+    /// Records a transaction of given address built at given block height
+    pub fn record(
+        &self,
+        address: &StakedStateAddress,
+        nonce: u64,
+        transaction_id: TxId,
+        current_height: u64,
+    ) -> Result<()> {
+        let mut pending = self.pending(address)?;
+        pending.retain(|entry| entry.transaction_id != transaction_id);
+        pending.push(PendingNonce {
+            nonce,
+            transaction_id,
+            expiry_height: current_height + self.expiry_blocks,
+        });
+        self.save(address, &pending)
+    }
+
+    /// Removes a transaction of given address (e.g. when it is rejected by the chain)
+    pub fn remove(&self, address: &StakedStateAddress, transaction_id: &TxId) -> Result<()> {
+        let mut pending = self.pending(address)?;
+        pending.retain(|entry| entry.transaction_id != *transaction_id);
+        self.save(address, &pending)
+    }
+
+    /// Returns pending transactions of given address
+    pub fn pending(&self, address: &StakedStateAddress) -> Result<Vec<PendingNonce>> {
+        Ok(self
+            .storage
+            .load(KEYSPACE, &address.to_string())?
+            .unwrap_or_default())
+    }
+
+    /// Returns the nonce to use for a new transaction of given address, after discarding entries
+    /// which are expired or already applied on chain (nonce below on-chain nonce)
+    pub fn next_nonce(
+        &self,
+        address: &StakedStateAddress,
+        on_chain_nonce: u64,
+        current_height: u64,
+    ) -> Result<u64> {
+        let mut pending = self.pending(address)?;
+        pending
+            .retain(|entry| entry.nonce >= on_chain_nonce && entry.expiry_height > current_height);
+        self.save(address, &pending)?;
+
+        let last = match pending.iter().max_by_key(|entry| entry.nonce) {
+            None => return Ok(on_chain_nonce),
+            Some(last) => last,
+        };
+
+        match self.policy {
+            PendingNoncePolicy::Bump => Ok(last.nonce + 1),
+            PendingNoncePolicy::Refuse => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Staking address {} has pending transaction {} with nonce {} (wait until it is included in a block or expires at height {})",
+                    address,
+                    hex::encode(last.transaction_id),
+                    last.nonce,
+                    last.expiry_height
+                ),
+            )),
+        }
+    }
+
+    fn save(&self, address: &StakedStateAddress, pending: &[PendingNonce]) -> Result<()> {
+        if pending.is_empty() {
+            self.storage
+                .delete(KEYSPACE, address.to_string())
+                .map(|_| ())
+        } else {
+            self.storage
+                .save(KEYSPACE, &address.to_string(), &pending.to_vec())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chain_core::init::address::RedeemAddress;
+    use client_common::storage::MemoryStorage;
+
+    #[test]
+    fn check_pending_nonce_flow() {
+        let registry = PendingNonceRegistry::new_with_expiry(
+            MemoryStorage::default(),
+            PendingNoncePolicy::Bump,
+            5,
+        );
+        let address = StakedStateAddress::BasicRedeem(RedeemAddress::default());
+
+        assert_eq!(3, registry.next_nonce(&address, 3, 10).unwrap());
+
+        registry.record(&address, 3, [1; 32], 10).unwrap();
+        registry.record(&address, 4, [2; 32], 11).unwrap();
+        assert_eq!(5, registry.next_nonce(&address, 3, 12).unwrap());
+
+        // transaction with nonce 3 is applied on chain
+        assert_eq!(5, registry.next_nonce(&address, 4, 12).unwrap());
+        assert_eq!(1, registry.pending(&address).unwrap().len());
+
+        // rejected transaction is removed
+        registry.remove(&address, &[2; 32]).unwrap();
+        assert_eq!(4, registry.next_nonce(&address, 4, 12).unwrap());
+        assert!(registry.pending(&address).unwrap().is_empty());
+    }
+
+    #[test]
+    fn check_pending_nonce_expiry() {
+        let registry = PendingNonceRegistry::new_with_expiry(
+            MemoryStorage::default(),
+            PendingNoncePolicy::Bump,
+            5,
+        );
+        let address = StakedStateAddress::BasicRedeem(RedeemAddress::default());
+
+        registry.record(&address, 0, [1; 32], 10).unwrap();
+        assert_eq!(1, registry.next_nonce(&address, 0, 14).unwrap());
+        assert_eq!(0, registry.next_nonce(&address, 0, 15).unwrap());
+    }
+
+    #[test]
+    fn check_pending_nonce_refuse_policy() {
+        let registry =
+            PendingNonceRegistry::new(MemoryStorage::default(), PendingNoncePolicy::Refuse);
+        let address = StakedStateAddress::BasicRedeem(RedeemAddress::default());
+
+        registry.record(&address, 0, [1; 32], 0).unwrap();
+        let error = registry.next_nonce(&address, 0, 0).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, error.kind());
+        assert!(error.message().contains(&hex::encode([1; 32])));
+    }
+}
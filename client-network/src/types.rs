@@ -0,0 +1,242 @@
+//! Types used in `client-network`
+use serde::{Deserialize, Serialize};
+
+use chain_core::common::Timespec;
+use chain_core::init::coin::Coin;
+use chain_core::state::account::{CouncilNode, StakedStateAddress};
+use chain_core::state::tendermint::{BlockHeight, TendermintValidatorPubKey};
+use chain_core::tx::data::TxId;
+use chain_core::tx::fee::Milli;
+use client_common::Transaction;
+
+/// Source of the nonce used when building staking transactions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NonceSource {
+    /// Fetch staked state from network, which also validates the staking account
+    Network,
+    /// Use given nonce without contacting the network (for offline signing); staking account
+    /// is unchecked, so invalid transactions are only rejected by the chain
+    Explicit(u64),
+}
+
+impl Default for NonceSource {
+    #[inline]
+    fn default() -> Self {
+        NonceSource::Network
+    }
+}
+
+/// Staking operation transaction built without signing, to be signed externally (e.g. on an
+/// offline machine or a hardware wallet)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnsignedStakingTransaction {
+    /// Unsigned transaction
+    pub transaction: Transaction,
+    /// Staking address expected to sign the transaction
+    pub signer: StakedStateAddress,
+}
+
+/// Priority of a transaction: minimum fee calculated for the transaction is multiplied so that
+/// the transaction gets included sooner during congestion
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeePriority {
+    /// Multiplier of the minimum fee (withdraw transactions only accept `1.0`)
+    pub multiplier: Milli,
+    /// Maximum fee to pay (multiplied fee is capped at this value, transaction is refused if its
+    /// minimum fee is higher)
+    pub max_fee: Option<Coin>,
+}
+
+/// Kind (and shape) of a staking transaction whose fee is estimated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StakingTxKind {
+    /// Deposit transaction spending given number of inputs
+    Deposit {
+        /// Number of inputs
+        inputs: usize,
+    },
+    /// Unbond transaction
+    Unbond,
+    /// Withdraw transaction with given number of outputs
+    Withdraw {
+        /// Number of outputs
+        outputs: usize,
+    },
+    /// Unjail transaction
+    Unjail,
+    /// Node join transaction with given council node metadata
+    NodeJoin {
+        /// Metadata of joining council node
+        node_metadata: CouncilNode,
+    },
+}
+
+/// Unbonding status of a staking account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnbondingStatus {
+    /// Unbonded amount
+    pub unbonded: Coin,
+    /// Time (unix timestamp) from which unbonded amount can be withdrawn
+    pub unbonded_from: Timespec,
+    /// Time (unix timestamp) of last block
+    pub current_time: Timespec,
+    /// Seconds until unbonded amount can be withdrawn (`None` if it can already be withdrawn)
+    pub remaining_secs: Option<u64>,
+}
+
+impl UnbondingStatus {
+    /// Creates unbonding status of an account at given time of last block
+    pub fn new(unbonded: Coin, unbonded_from: Timespec, current_time: Timespec) -> Self {
+        let remaining_secs = if unbonded_from > current_time {
+            Some(unbonded_from - current_time)
+        } else {
+            None
+        };
+
+        Self {
+            unbonded,
+            unbonded_from,
+            current_time,
+            remaining_secs,
+        }
+    }
+}
+
+/// Result of broadcasting a transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BroadcastedTransaction {
+    /// Id of broadcasted transaction
+    pub transaction_id: TxId,
+    /// Height of block including the transaction (`None` if inclusion is not waited for)
+    pub block_height: Option<u64>,
+}
+
+/// Preview of certificate change caused by a node join transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeJoinPreview {
+    /// Hex encoded fingerprint of the new certificate
+    pub new_cert_fingerprint: String,
+    /// Hex encoded fingerprint of currently registered certificate (if any)
+    pub current_cert_fingerprint: Option<String>,
+    /// `true` if the new certificate replaces a different (or missing) one
+    pub cert_changes: bool,
+}
+
+/// Network wide staking statistics
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkStakingStats {
+    /// Block height at which the statistics are computed
+    pub computed_at_height: u64,
+    /// Number of active validators (council nodes)
+    pub validator_count: usize,
+    /// Total bonded amount of all active validators' staking accounts
+    pub total_bonded: Coin,
+    /// Reward distribution period in seconds
+    pub reward_period_seconds: u64,
+    /// Rewards minted in next distribution period by the monetary expansion formula from current
+    /// `tau` and network parameters, capped by the remaining expansion cap (transaction fees
+    /// added to rewards pool are not included)
+    pub estimated_period_rewards: Coin,
+    /// Annual reward rate in basis points of `estimated_period_rewards`, if total bonded amount
+    /// and `tau` stay unchanged for a year
+    pub estimated_annual_rate_bps: u64,
+}
+
+/// Optional features supported by current client build
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClientCapabilities {
+    /// Version of `client-network` crate
+    pub crate_version: String,
+    /// Minimum supported app version of transactions
+    pub min_app_version: u64,
+    /// Maximum supported app version of transactions
+    pub max_app_version: u64,
+    /// Identifiers of compiled storage backends
+    pub storage_backends: Vec<String>,
+    /// `true` if websocket subscriptions to tendermint are supported
+    pub websocket_rpc: bool,
+    /// `true` if mock hardware wallet is compiled in
+    pub mock_hardware_wallet: bool,
+    /// `true` if staking transactions can be built unsigned and signed externally
+    pub offline_signing: bool,
+    /// Runtime configuration of network operations client (`None` unless reported by a
+    /// constructed client, see `NetworkOpsClient::capabilities`)
+    pub network_ops: Option<NetworkOpsConfig>,
+}
+
+/// Runtime configuration of network operations client
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkOpsConfig {
+    /// Milliseconds for which queried staked states are cached (zero if caching is disabled)
+    pub staked_state_ttl_millis: u64,
+    /// `true` if nonces of built staking transactions are tracked until they are applied on chain
+    pub pending_nonce_tracking: bool,
+    /// `true` if staking transactions of watch-listed addresses are refused
+    pub watch_list: bool,
+    /// Number of blocks after which built transactions are given up if not included in a block
+    pub pending_tx_valid_blocks: u64,
+    /// Seconds allowed for each awaited block while waiting for a broadcasted transaction
+    pub block_wait_timeout_secs: u64,
+    /// `true` if current block height is read from a source other than connected node
+    pub custom_block_height_source: bool,
+    /// `true` if staking transactions of wallets can be built on other networks than their own
+    pub network_mismatch_allowed: bool,
+}
+
+/// Council node with information of its staking account
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CouncilNodeInfo {
+    /// Address of staking account of council node
+    pub staking_address: StakedStateAddress,
+    /// Council node metadata
+    pub council_node: CouncilNode,
+    /// Bonded amount of staking account
+    pub bonded: Coin,
+    /// `true` if council node is jailed
+    pub jailed: bool,
+    /// `true` if council node is inactive
+    pub inactive: bool,
+}
+
+/// Reason why a council node is not in active validator set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InactiveReason {
+    /// Council node is jailed
+    Jailed,
+    /// Council node is inactive (e.g. its bonded amount dropped below required stake)
+    Inactive,
+    /// Council node is not among validators with the highest voting power
+    BelowThreshold,
+}
+
+/// Validator details of a council node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidatorInfo {
+    /// Address of staking account of council node
+    pub staking_address: StakedStateAddress,
+    /// Tendermint consensus public key of council node
+    pub consensus_pubkey: TendermintValidatorPubKey,
+    /// `true` if council node is in active validator set
+    pub active: bool,
+    /// Voting power in active validator set (`0` if not active)
+    pub voting_power: u64,
+    /// Position (starting from `1`) in active validator set ordered by voting power
+    pub rank: Option<usize>,
+    /// Reason why council node is not in active validator set (`None` if active)
+    pub inactive_reason: Option<InactiveReason>,
+    /// Time until which council node is jailed
+    pub jailed_until: Option<Timespec>,
+    /// Block time when council node became inactive
+    pub inactive_time: Option<Timespec>,
+    /// Block height when council node became inactive
+    pub inactive_block: Option<BlockHeight>,
+}
+
+/// Council node entry returned by `council-nodes` query
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct CouncilNodeEntry {
+    /// Address of staking account of validator
+    pub staking_address: StakedStateAddress,
+    /// Tendermint consensus public key of validator
+    pub tendermint_pubkey: TendermintValidatorPubKey,
+}
@@ -0,0 +1,123 @@
+//! Watch list of staking addresses which are not owned by any local wallet
+use parity_scale_codec::{Decode, Encode};
+
+use chain_core::state::account::StakedStateAddress;
+use client_common::{Result, Storage};
+
+const KEYSPACE: &str = "network_watch_list";
+
+/// A staking address watched for monitoring only, no keys are involved
+#[derive(Debug, Clone, PartialEq, Encode, Decode)]
+pub struct WatchedAddress {
+    /// User defined label of the address
+    pub label: String,
+    /// Watched staking address
+    pub address: StakedStateAddress,
+}
+
+/// Maintains the list of watched (foreign) staking addresses
+#[derive(Debug, Default, Clone)]
+pub struct WatchList<S: Storage> {
+    storage: S,
+}
+
+impl<S> WatchList<S>
+where
+    S: Storage,
+{
+    /// Creates a new instance of watch list
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    /// Adds a staking address to watch list, replacing the label if it is already watched
+    pub fn add_watched_address(&self, label: &str, address: StakedStateAddress) -> Result<()> {
+        let watched = WatchedAddress {
+            label: label.to_owned(),
+            address,
+        };
+        self.storage.save(KEYSPACE, &address.to_string(), &watched)
+    }
+
+    /// Removes a staking address from watch list
+    pub fn remove_watched_address(&self, address: &StakedStateAddress) -> Result<()> {
+        self.storage
+            .delete(KEYSPACE, address.to_string())
+            .map(|_| ())
+    }
+
+    /// Returns `true` if given staking address is in watch list
+    pub fn is_watched(&self, address: &StakedStateAddress) -> Result<bool> {
+        self.storage.contains_key(KEYSPACE, address.to_string())
+    }
+
+    /// Returns all watched staking addresses
+    pub fn watched_addresses(&self) -> Result<Vec<WatchedAddress>> {
+        let mut watched = Vec::new();
+        for key in self.storage.keys(KEYSPACE)? {
+            let key = String::from_utf8_lossy(&key);
+            if let Some(address) = self.storage.load(KEYSPACE, &key)? {
+                watched.push(address);
+            }
+        }
+        Ok(watched)
+    }
+
+    /// Exports watch list, independently from any wallet
+    #[inline]
+    pub fn export(&self) -> Result<Vec<WatchedAddress>> {
+        self.watched_addresses()
+    }
+
+    /// Imports watched addresses into watch list
+    pub fn import(&self, watched: &[WatchedAddress]) -> Result<()> {
+        for item in watched {
+            self.add_watched_address(&item.label, item.address)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chain_core::init::address::RedeemAddress;
+    use client_common::storage::MemoryStorage;
+    use client_common::{PrivateKey, PublicKey};
+
+    fn new_address() -> StakedStateAddress {
+        StakedStateAddress::BasicRedeem(RedeemAddress::from(&PublicKey::from(
+            &PrivateKey::new().unwrap(),
+        )))
+    }
+
+    #[test]
+    fn check_watch_list_flow() {
+        let watch_list = WatchList::new(MemoryStorage::default());
+        let address = new_address();
+
+        assert!(!watch_list.is_watched(&address).unwrap());
+        watch_list
+            .add_watched_address("counterparty", address)
+            .unwrap();
+        assert!(watch_list.is_watched(&address).unwrap());
+
+        let exported = watch_list.export().unwrap();
+        assert_eq!(
+            exported,
+            vec![WatchedAddress {
+                label: "counterparty".to_owned(),
+                address,
+            }]
+        );
+
+        watch_list.remove_watched_address(&address).unwrap();
+        assert!(!watch_list.is_watched(&address).unwrap());
+
+        let other_watch_list = WatchList::new(MemoryStorage::default());
+        other_watch_list.import(&exported).unwrap();
+        assert!(other_watch_list.is_watched(&address).unwrap());
+    }
+}
@@ -362,7 +362,7 @@ mod test {
 
         let (enckey, _) = multisig_rpc
             .client
-            .new_wallet(name, &passphrase, WalletKind::Basic)
+            .new_wallet(name, &passphrase, WalletKind::Basic, None, None)
             .unwrap();
         let wallet_request = WalletRequest {
             name: "Default".to_owned(),
@@ -505,6 +505,17 @@ mod test {
             unreachable!("query")
         }
 
+        fn validators(&self, _height: u64) -> CommonResult<ValidatorsResponse> {
+            unreachable!("validators")
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            _queries: T,
+        ) -> CommonResult<Vec<AbciQuery>> {
+            unreachable!("query_batch")
+        }
+
         fn query_state_batch<T: Iterator<Item = u64>>(
             &self,
             _heights: T,
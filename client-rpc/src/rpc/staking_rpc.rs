@@ -5,9 +5,7 @@ use jsonrpc_derive::rpc;
 
 use crate::server::{rpc_error_from_string, to_rpc_error};
 use chain_core::init::coin::Coin;
-use chain_core::state::account::{
-    ConfidentialInit, CouncilNode, StakedState, StakedStateAddress, StakedStateOpAttributes,
-};
+use chain_core::state::account::{ConfidentialInit, CouncilNode, StakedState, StakedStateAddress};
 use chain_core::state::tendermint::TendermintValidatorPubKey;
 use chain_core::tx::data::access::{TxAccess, TxAccessPolicy};
 use chain_core::tx::data::address::ExtendedAddr;
@@ -17,6 +15,7 @@ use chain_core::tx::data::output::TxOut;
 use client_common::{Error, ErrorKind, PublicKey, Result as CommonResult, ResultExt, Transaction};
 use client_core::wallet::WalletRequest;
 use client_core::{MultiSigWalletClient, WalletClient};
+use client_network::types::{ClientCapabilities, NonceSource};
 use client_network::NetworkOpsClient;
 use std::collections::BTreeSet;
 
@@ -28,6 +27,8 @@ pub trait StakingRpc: Send + Sync {
         request: WalletRequest,
         to_address: String,
         inputs: Vec<TxoPointer>,
+        allow_external: Option<bool>,
+        allow_frozen: Option<bool>,
     ) -> Result<String>;
 
     #[rpc(name = "staking_depositAmountStake")]
@@ -36,11 +37,15 @@ pub trait StakingRpc: Send + Sync {
         request: WalletRequest,
         to_address: String,
         amount: Coin,
+        allow_external: Option<bool>,
     ) -> Result<String>;
 
     #[rpc(name = "staking_state")]
     fn state(&self, address: StakedStateAddress) -> Result<StakedState>;
 
+    #[rpc(name = "staking_clientCapabilities")]
+    fn client_capabilities(&self) -> Result<ClientCapabilities>;
+
     #[rpc(name = "staking_unbondStake")]
     fn unbond_stake(
         &self,
@@ -105,6 +110,8 @@ where
         request: WalletRequest,
         to_address: String,
         inputs: Vec<TxoPointer>,
+        allow_external: Option<bool>,
+        allow_frozen: Option<bool>,
     ) -> Result<String> {
         let to_address = StakedStateAddress::from_str(&to_address)
             .chain(|| {
@@ -114,7 +121,6 @@ where
                 )
             })
             .map_err(to_rpc_error)?;
-        let attributes = StakedStateOpAttributes::new(self.network_id);
 
         if !self
             .client
@@ -143,7 +149,9 @@ where
                 &request.enckey,
                 transactions,
                 to_address,
-                attributes,
+                None,
+                allow_external.unwrap_or(false),
+                allow_frozen.unwrap_or(false),
             )
             .map_err(to_rpc_error)?;
 
@@ -174,6 +182,7 @@ where
         request: WalletRequest,
         to_address: String,
         amount: Coin,
+        allow_external: Option<bool>,
     ) -> Result<String> {
         let to_staking_address = StakedStateAddress::from_str(&to_address)
             .chain(|| {
@@ -183,10 +192,19 @@ where
                 )
             })
             .map_err(to_rpc_error)?;
-        let attr = StakedStateOpAttributes::new(self.network_id);
+        let allow_external = allow_external.unwrap_or(false);
+        // destination is verified before funds are moved by the transfer transaction below
+        self.ops_client
+            .verify_deposit_destination(
+                &request.name,
+                &request.enckey,
+                &to_staking_address,
+                allow_external,
+            )
+            .map_err(to_rpc_error)?;
         let fee = self
             .ops_client
-            .calculate_deposit_fee()
+            .calculate_single_input_deposit_fee()
             .map_err(to_rpc_error)?;
         let total_amount = (amount + fee).map_err(to_rpc_error)?;
         // 1. build a transfer transaction to make a UTXO which amount is `deposit_amount + fee`
@@ -229,7 +247,9 @@ where
                 &request.enckey,
                 transactions,
                 to_staking_address,
-                attr,
+                None,
+                allow_external,
+                false,
             )
             .map_err(to_rpc_error)?;
 
@@ -255,13 +275,17 @@ where
             .map_err(to_rpc_error)
     }
 
+    #[inline]
+    fn client_capabilities(&self) -> Result<ClientCapabilities> {
+        Ok(self.ops_client.capabilities())
+    }
+
     fn unbond_stake(
         &self,
         request: WalletRequest,
         staking_address: String,
         amount: Coin,
     ) -> Result<String> {
-        let attr = StakedStateOpAttributes::new(self.network_id);
         let addr = StakedStateAddress::from_str(&staking_address)
             .chain(|| {
                 (
@@ -274,14 +298,30 @@ where
             })
             .map_err(to_rpc_error)?;
 
-        let transaction = self
+        let (transaction, tx_pending) = self
             .ops_client
-            .create_unbond_stake_transaction(&request.name, &request.enckey, addr, amount, attr)
+            .create_unbond_stake_transaction(
+                &request.name,
+                &request.enckey,
+                addr,
+                amount,
+                None,
+                NonceSource::Network,
+            )
             .map_err(to_rpc_error)?;
 
         self.client
             .broadcast_transaction(&transaction)
             .map_err(to_rpc_error)?;
+        // update the wallet pending transaction state
+        self.client
+            .update_tx_pending_state(
+                &request.name,
+                &request.enckey,
+                transaction.tx_id(),
+                tx_pending,
+            )
+            .map_err(to_rpc_error)?;
 
         Ok(hex::encode(transaction.tx_id()))
     }
@@ -341,6 +381,8 @@ where
                 &from_address,
                 to_address,
                 attributes,
+                None,
+                None,
             )
             .map_err(to_rpc_error)?;
 
@@ -369,16 +411,29 @@ where
             })
             .map_err(to_rpc_error)?;
 
-        let attributes = StakedStateOpAttributes::new(self.network_id);
-
-        let transaction = self
+        let (transaction, tx_pending) = self
             .ops_client
-            .create_unjail_transaction(&request.name, &request.enckey, unjail_address, attributes)
+            .create_unjail_transaction(
+                &request.name,
+                &request.enckey,
+                unjail_address,
+                None,
+                NonceSource::Network,
+            )
             .map_err(to_rpc_error)?;
 
         self.client
             .broadcast_transaction(&transaction)
             .map_err(to_rpc_error)?;
+        // update the wallet pending transaction state
+        self.client
+            .update_tx_pending_state(
+                &request.name,
+                &request.enckey,
+                transaction.tx_id(),
+                tx_pending,
+            )
+            .map_err(to_rpc_error)?;
 
         Ok(hex::encode(transaction.tx_id()))
     }
@@ -390,7 +445,6 @@ where
         validator_pubkey: String,
         staking_addr: String,
     ) -> Result<String> {
-        let attributes = StakedStateOpAttributes::new(self.network_id);
         let staking_account_address = staking_addr
             .parse::<StakedStateAddress>()
             .chain(|| {
@@ -401,19 +455,29 @@ where
             })
             .map_err(to_rpc_error)?;
         let node_metadata = get_node_metadata(&validator_node_name, &validator_pubkey)?;
-        let transaction = self
+        let (transaction, tx_pending) = self
             .ops_client
             .create_node_join_transaction(
                 &request.name,
                 &request.enckey,
                 staking_account_address,
-                attributes,
+                None,
                 node_metadata,
+                NonceSource::Network,
             )
             .map_err(to_rpc_error)?;
         self.client
             .broadcast_transaction(&transaction)
             .map_err(to_rpc_error)?;
+        // update the wallet pending transaction state
+        self.client
+            .update_tx_pending_state(
+                &request.name,
+                &request.enckey,
+                transaction.tx_id(),
+                tx_pending,
+            )
+            .map_err(to_rpc_error)?;
 
         Ok(hex::encode(transaction.tx_id()))
     }
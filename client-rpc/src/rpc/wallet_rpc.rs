@@ -10,7 +10,7 @@ use chain_core::tx::data::address::ExtendedAddr;
 use client_common::{PrivateKey, PublicKey, Result as CommonResult, SecKey};
 use client_core::service::WalletInfo;
 use client_core::transaction_builder::SignedTransferTransaction;
-use client_core::types::{TransactionChange, WalletBalance, WalletKind};
+use client_core::types::{PendingTransactionInfo, TransactionChange, WalletBalance, WalletKind};
 use client_core::wallet::{CreateWalletRequest, WalletRequest};
 use client_core::{Mnemonic, MultiSigWalletClient, UnspentTransactions, WalletClient};
 use parity_scale_codec::{Decode, Encode};
@@ -36,7 +36,11 @@ pub trait WalletRpc: Send + Sync {
     fn restore_basic(&self, request: CreateWalletRequest, view_key: SecUtf8) -> Result<SecKey>;
 
     #[rpc(name = "wallet_delete")]
-    fn delete(&self, request: CreateWalletRequest) -> Result<()>;
+    fn delete(
+        &self,
+        request: CreateWalletRequest,
+        confirmation_token: Option<String>,
+    ) -> Result<Option<String>>;
 
     #[rpc(name = "wallet_createStakingAddress")]
     fn create_staking_address(&self, request: WalletRequest) -> Result<String>;
@@ -82,6 +86,12 @@ pub trait WalletRpc: Send + Sync {
     #[rpc(name = "wallet_listUTxO")]
     fn list_utxo(&self, request: WalletRequest) -> Result<UnspentTransactions>;
 
+    #[rpc(name = "wallet_listPendingTransactions")]
+    fn list_pending_transactions(
+        &self,
+        request: WalletRequest,
+    ) -> Result<Vec<PendingTransactionInfo>>;
+
     #[rpc(name = "wallet_sendToAddress")]
     fn send_to_address(
         &self,
@@ -167,7 +177,7 @@ where
         // TODO: add hardware wallet
         let (enckey, mnemonic) = self
             .client
-            .new_wallet(&request.name, &request.passphrase, kind)
+            .new_wallet(&request.name, &request.passphrase, kind, None, None)
             .map_err(to_rpc_error)?;
 
         self.client
@@ -191,7 +201,7 @@ where
     fn restore(&self, request: CreateWalletRequest, mnemonic: Mnemonic) -> Result<SecKey> {
         let enckey = self
             .client
-            .restore_wallet(&request.name, &request.passphrase, &mnemonic)
+            .restore_wallet(&request.name, &request.passphrase, &mnemonic, None)
             .map_err(to_rpc_error)?;
 
         mnemonic.zeroize();
@@ -217,9 +227,17 @@ where
         Ok(enckey)
     }
 
-    fn delete(&self, request: CreateWalletRequest) -> Result<()> {
+    fn delete(
+        &self,
+        request: CreateWalletRequest,
+        confirmation_token: Option<String>,
+    ) -> Result<Option<String>> {
         self.client
-            .delete_wallet(&request.name, &request.passphrase)
+            .delete_wallet(
+                &request.name,
+                &request.passphrase,
+                confirmation_token.as_deref(),
+            )
             .map_err(to_rpc_error)
     }
 
@@ -330,6 +348,15 @@ where
             .map_err(to_rpc_error)
     }
 
+    fn list_pending_transactions(
+        &self,
+        request: WalletRequest,
+    ) -> Result<Vec<PendingTransactionInfo>> {
+        self.client
+            .list_pending_transactions(&request.name, &request.enckey)
+            .map_err(to_rpc_error)
+    }
+
     fn send_to_address(
         &self,
         request: WalletRequest,
@@ -643,6 +670,17 @@ pub mod tests {
             unreachable!("query")
         }
 
+        fn validators(&self, _height: u64) -> CommonResult<ValidatorsResponse> {
+            unreachable!("validators")
+        }
+
+        fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+            &self,
+            _queries: T,
+        ) -> CommonResult<Vec<AbciQuery>> {
+            unreachable!("query_batch")
+        }
+
         fn query_state_batch<T: Iterator<Item = u64>>(
             &self,
             _heights: T,
@@ -823,7 +861,8 @@ pub mod tests {
         let old_enckey = wallet_rpc.get_enc_key(create_request.clone()).unwrap();
         let wallet_info = wallet_rpc.export(wallet_request.clone()).unwrap();
         // delete the old wallet
-        wallet_rpc.delete(create_request.clone()).unwrap();
+        let token = wallet_rpc.delete(create_request.clone(), None).unwrap();
+        wallet_rpc.delete(create_request.clone(), token).unwrap();
         let new_enckey = wallet_rpc
             .import(create_request.clone(), wallet_info)
             .unwrap();
@@ -19,9 +19,11 @@ use client_core::cipher::DefaultTransactionObfuscation;
 use client_core::service::HwKeyService;
 use client_core::signer::WalletSignerManager;
 use client_core::transaction_builder::DefaultWalletTransactionBuilder;
+use client_core::types::NetworkInfo;
 use client_core::wallet::syncer::ObfuscationSyncerConfig;
 use client_core::wallet::DefaultWalletClient;
 use client_network::network_ops::DefaultNetworkOpsClient;
+use client_network::WatchList;
 use jsonrpc_core::{self, IoHandler};
 use jsonrpc_http_server::{AccessControlAllowOrigin, DomainsValidation, ServerBuilder};
 
@@ -41,6 +43,7 @@ pub(crate) struct Server {
     host: String,
     port: u16,
     network_id: u8,
+    network: NetworkInfo,
     storage_dir: String,
     websocket_url: String,
     enable_fast_forward: bool,
@@ -63,6 +66,7 @@ impl Server {
             host: options.host,
             port: options.port,
             network_id,
+            network: NetworkInfo::from_chain_id(&options.chain_id)?,
             storage_dir: options.storage_dir,
             websocket_url: options.websocket_url,
             enable_fast_forward: !options.disable_fast_forward,
@@ -84,13 +88,14 @@ impl Server {
             tendermint_client.genesis().unwrap().fee_policy(),
             transaction_cipher,
         );
-        Ok(DefaultWalletClient::new(
+        Ok(DefaultWalletClient::open(
             storage,
             tendermint_client,
             transaction_builder,
             Some(self.block_height_ensure),
             hw_key_service,
-        ))
+        )?
+        .with_network(self.network.clone()))
     }
 
     pub fn make_ops_client(
@@ -102,6 +107,7 @@ impl Server {
         let transaction_cipher = get_tx_query(tendermint_client.clone())?;
         let signer_manager = WalletSignerManager::new(storage.clone(), hw_key_service);
         let fee_algorithm = tendermint_client.genesis().unwrap().fee_policy();
+        let watch_list = WatchList::new(storage.clone());
         let wallet_client = self.make_wallet_client(storage, tendermint_client.clone())?;
         Ok(DefaultNetworkOpsClient::new(
             wallet_client,
@@ -109,7 +115,8 @@ impl Server {
             tendermint_client,
             fee_algorithm,
             transaction_cipher,
-        ))
+        )
+        .with_watch_list(watch_list))
     }
 
     pub fn make_syncer_config(
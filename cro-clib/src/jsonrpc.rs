@@ -60,13 +60,13 @@ fn make_wallet_client(
         tendermint_client.genesis().unwrap().fee_policy(),
         transaction_cipher,
     );
-    Ok(DefaultWalletClient::new(
+    DefaultWalletClient::open(
         storage,
         tendermint_client,
         transaction_builder,
         Some(50),
         hw_key_service,
-    ))
+    )
 }
 
 fn make_ops_client(
@@ -331,13 +331,18 @@ impl InitCommand {
 
     fn read_staking_address(&mut self) -> Result<()> {
         let storage = SledStorage::new(InitCommand::storage_path())?;
-        let wallet_client = DefaultWalletClient::new_read_only(storage);
+        let wallet_client = DefaultWalletClient::open_read_only(storage)?;
 
         let name = self.ask_string("please enter wallet name=", "my");
 
         let passphrase = InitCommand::ask_passphrase()?;
-        let enckey = match wallet_client.new_wallet(&name.as_str(), &passphrase, WalletKind::Basic)
-        {
+        let enckey = match wallet_client.new_wallet(
+            &name.as_str(),
+            &passphrase,
+            WalletKind::Basic,
+            None,
+            None,
+        ) {
             Ok((enckey, _)) => enckey,
             Err(b) => {
                 println!("new wallet fail={}", b.to_string());
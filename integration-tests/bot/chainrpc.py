@@ -137,7 +137,9 @@ class Wallet(BaseService):
         return self.call('wallet_restoreBasic', [name, passphrase or get_passphrase()], private_view_key)
 
     def delete(self, name=DEFAULT_WALLET, passphrase=None):
-        return self.call('wallet_delete', [name, passphrase or get_passphrase()])
+        request = [name, passphrase or get_passphrase()]
+        token = self.call('wallet_delete', request)
+        return self.call('wallet_delete', request, token)
 
     def view_key(self, name=DEFAULT_WALLET, private=False, enckey=None):
         return self.call(
@@ -185,11 +187,11 @@ class Wallet(BaseService):
 
 
 class Staking(BaseService):
-    def deposit(self, to_address, inputs, name=DEFAULT_WALLET, enckey=None):
-        return self.call('staking_depositStake', [name, enckey or get_enckey()], fix_address(to_address), inputs)
+    def deposit(self, to_address, inputs, name=DEFAULT_WALLET, enckey=None, allow_external=False):
+        return self.call('staking_depositStake', [name, enckey or get_enckey()], fix_address(to_address), inputs, allow_external)
 
-    def deposit_amount(self, to_address, amount, name=DEFAULT_WALLET, enckey=None):
-        return self.call('staking_depositAmountStake', [name, enckey or get_enckey()], fix_address(to_address), str(amount))
+    def deposit_amount(self, to_address, amount, name=DEFAULT_WALLET, enckey=None, allow_external=False):
+        return self.call('staking_depositAmountStake', [name, enckey or get_enckey()], fix_address(to_address), str(amount), allow_external)
 
     def state(self, address):
         return self.call('staking_state', fix_address(address))
@@ -222,17 +222,23 @@ describe("HDWallet management", () => {
 			walletRequest, words
 		]);
 
-		await client.request("wallet_delete", [
+		const deletionToken = await client.request("wallet_delete", [
 			walletRequest,
 		]);
+		await client.request("wallet_delete", [
+			walletRequest, deletionToken,
+		]);
 
 		await client.request("wallet_restore", [
 			walletRequest, words
 		]);
 
-		await client.request("wallet_delete", [
+		const secondDeletionToken = await client.request("wallet_delete", [
 			walletRequest,
 		]);
+		await client.request("wallet_delete", [
+			walletRequest, secondDeletionToken,
+		]);
 	})
 
 });
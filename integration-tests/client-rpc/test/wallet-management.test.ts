@@ -181,16 +181,22 @@ describe("Wallet management", () => {
 			walletRequest, "Basic"
 		]);
 
-		await client.request("wallet_delete", [
+		const deletionToken = await client.request("wallet_delete", [
 			walletRequest,
 		]);
+		await client.request("wallet_delete", [
+			walletRequest, deletionToken,
+		]);
 
 		await client.request("wallet_create", [
 			walletRequest, "Basic"
 		]);
 
-		await client.request("wallet_delete", [
+		const secondDeletionToken = await client.request("wallet_delete", [
 			walletRequest,
 		]);
+		await client.request("wallet_delete", [
+			walletRequest, secondDeletionToken,
+		]);
 	})
 });
@@ -38,7 +38,9 @@ use chain_core::tx::fee::{LinearFee, Milli};
 use chain_core::tx::TxAux;
 use chain_storage::buffer::MemStore;
 use chain_storage::jellyfish::{put_stakings, StakingGetter};
-use client_common::tendermint::types::{AbciQuery, BroadcastTxResponse, Genesis};
+use client_common::tendermint::types::{
+    AbciQuery, BroadcastTxResponse, Genesis, ValidatorsResponse,
+};
 use client_common::tendermint::{lite, Client};
 use client_common::Result;
 use client_core::{service::HDAccountType, HDSeed, Mnemonic};
@@ -577,6 +579,17 @@ impl Client for GeneratorClient {
         unreachable!();
     }
 
+    fn validators(&self, _height: u64) -> Result<ValidatorsResponse> {
+        unreachable!()
+    }
+
+    fn query_batch<'a, T: Iterator<Item = (&'a str, &'a [u8])>>(
+        &self,
+        _queries: T,
+    ) -> Result<Vec<AbciQuery>> {
+        unreachable!()
+    }
+
     fn query_state_batch<T: Iterator<Item = u64>>(&self, heights: T) -> Result<Vec<ChainState>> {
         Ok(heights
             .map(|height| {